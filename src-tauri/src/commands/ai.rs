@@ -1,6 +1,40 @@
 use crate::services::ollama::{get_ollama_service, OllamaOptions, UpdateConfig};
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle};
+
+/// 健康檢查結果快取的有效時間
+const HEALTH_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// 單一端點的最近一次健康檢查結果
+struct CachedHealth {
+    checked_at: Instant,
+    last_checked: String,
+    service: ServiceInfo,
+    models: Option<ModelsInfo>, // 僅 get_service_status 會填入
+}
+
+/// 以服務端點為鍵的健康檢查快取，避免 UI 高頻輪詢時重複探測
+static HEALTH_CACHE: OnceLock<Mutex<HashMap<String, CachedHealth>>> = OnceLock::new();
+
+fn health_cache() -> &'static Mutex<HashMap<String, CachedHealth>> {
+    HEALTH_CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 讀取尚未過期的快取項目
+fn read_cached_health<T>(endpoint: &str, read: impl FnOnce(&CachedHealth) -> Option<T>) -> Option<T> {
+    let cache = health_cache().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache.get(endpoint)
+        .filter(|entry| entry.checked_at.elapsed() < HEALTH_CACHE_TTL)
+        .and_then(read)
+}
+
+fn store_cached_health(endpoint: &str, entry: CachedHealth) {
+    let mut cache = health_cache().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache.insert(endpoint.to_string(), entry);
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ServiceStatus {
@@ -32,27 +66,63 @@ pub struct GenerateParams {
     pub max_context_tokens: Option<u32>,
 }
 
-/// 檢查 Ollama 服務是否可用
+/// 檢查 Ollama 服務是否可用（`force` 為 true 時略過快取）
 #[command]
-pub async fn check_ollama_service() -> Result<bool, String> {
+pub async fn check_ollama_service(force: Option<bool>) -> Result<bool, String> {
     log::info!("=== Tauri Command: 檢查 Ollama 服務 ===");
     
     let ollama_service = get_ollama_service();
     let service = ollama_service.lock().await;
+    let endpoint = service.base_url().to_string();
+    
+    if !force.unwrap_or(false) {
+        if let Some(available) = read_cached_health(&endpoint, |entry| Some(entry.service.available)) {
+            log::debug!("Tauri: 使用快取的 Ollama 服務檢查結果: {}", available);
+            return Ok(available);
+        }
+    }
+    
     let result = service.check_service_availability().await;
     
     log::info!("Tauri: Ollama 服務檢查結果: {:?}", result);
     
+    store_cached_health(&endpoint, CachedHealth {
+        checked_at: Instant::now(),
+        last_checked: chrono::Utc::now().to_rfc3339(),
+        service: ServiceInfo {
+            available: result.available,
+            version: result.version,
+            error: result.error,
+        },
+        models: None,
+    });
+    
     Ok(result.available)
 }
 
-/// 獲取詳細的服務狀態
+/// 獲取詳細的服務狀態（`force` 為 true 時略過快取）
 #[command]
-pub async fn get_service_status() -> Result<ServiceStatus, String> {
+pub async fn get_service_status(force: Option<bool>) -> Result<ServiceStatus, String> {
     log::info!("=== 開始獲取服務狀態 ===");
     
     let ollama_service = get_ollama_service();
     let service = ollama_service.lock().await;
+    let endpoint = service.base_url().to_string();
+    
+    if !force.unwrap_or(false) {
+        let cached = read_cached_health(&endpoint, |entry| {
+            entry.models.clone().map(|models| ServiceStatus {
+                service: entry.service.clone(),
+                models,
+                last_checked: entry.last_checked.clone(),
+            })
+        });
+        if let Some(status) = cached {
+            log::debug!("使用快取的服務狀態: {:?}", status);
+            return Ok(status);
+        }
+    }
+    
     let service_check = service.check_service_availability().await;
     let models_result = service.list_models().await;
     
@@ -77,6 +147,13 @@ pub async fn get_service_status() -> Result<ServiceStatus, String> {
         last_checked: chrono::Utc::now().to_rfc3339(),
     };
     
+    store_cached_health(&endpoint, CachedHealth {
+        checked_at: Instant::now(),
+        last_checked: status.last_checked.clone(),
+        service: status.service.clone(),
+        models: Some(status.models.clone()),
+    });
+    
     log::info!("服務狀態: {:?}", status);
     Ok(status)
 }
@@ -151,7 +228,75 @@ pub async fn generate_text(
     }
 }
 
+/// 語言純度重試次數上限
+const MAX_PURITY_RETRIES: u32 = 5;
+
+/// 純度不足時自動重新生成的設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PurityRetryOptions {
+    /// 低於此分數（0.0-1.0）時重新生成
+    pub min_score: f64,
+    /// 最多重新生成幾次（不含第一次生成）
+    pub max_retries: u32,
+}
+
+impl Default for PurityRetryOptions {
+    fn default() -> Self {
+        Self { min_score: 0.95, max_retries: 2 }
+    }
+}
+
+/// 分離上下文生成結果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeparatedGenerationResult {
+    pub text: String,
+    pub purity_score: f64,
+    pub attempts: u32,
+}
+
+/// 生成文本並在純度不足時附上修正指示重新生成，回傳分數最高的結果
+///
+/// `generate` 的參數為附加在提示詞後的修正指示（第一次生成為 None）。
+pub(crate) async fn generate_until_pure<F, Fut>(
+    mut generate: F,
+    retry: Option<&PurityRetryOptions>,
+) -> Result<SeparatedGenerationResult, String>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: std::future::Future<Output = Result<String, String>>,
+{
+    let enforcer = crate::utils::language_purity::LanguagePurityEnforcer::new();
+    let max_retries = retry.map_or(0, |r| r.max_retries.min(MAX_PURITY_RETRIES));
+    let min_score = retry.map_or(0.0, |r| r.min_score);
+    
+    let mut best: Option<SeparatedGenerationResult> = None;
+    let mut corrective = None;
+    for attempt in 1..=max_retries + 1 {
+        let text = generate(corrective.take()).await?;
+        let analysis = enforcer.analyze_purity(&text);
+        log::info!("第 {} 次生成，語言純度: {:.3}", attempt, analysis.purity_score);
+        
+        let passed = analysis.purity_score >= min_score;
+        if !best.as_ref().is_some_and(|b| b.purity_score >= analysis.purity_score) {
+            best = Some(SeparatedGenerationResult { text, purity_score: analysis.purity_score, attempts: attempt });
+        }
+        if let Some(best) = best.as_mut() {
+            best.attempts = attempt;
+        }
+        if passed {
+            break;
+        }
+        corrective = Some(enforcer.build_corrective_instructions(&analysis));
+    }
+    
+    best.ok_or_else(|| "生成文本失敗".to_string())
+}
+
 /// 使用分離上下文生成文本（簡化版）
+///
+/// 提供 `purity_retry` 時，語言純度低於門檻會附上檢測到的問題重新生成，
+/// 最後回傳分數最高的結果與實際生成次數。
 #[command]
 pub async fn generate_with_separated_context(
     project_id: String,
@@ -159,13 +304,14 @@ pub async fn generate_with_separated_context(
     position: usize,
     model: String,
     params: GenerateParams,
-) -> Result<String, String> {
+    purity_retry: Option<PurityRetryOptions>,
+) -> Result<SeparatedGenerationResult, String> {
     log::info!("=== 開始使用分離上下文生成文本（簡化版）===");
     log::info!("專案: {}, 章節: {}, 位置: {}, 模型: {}", project_id, chapter_id, position, model);
     
     // 1. 構建分離的上下文
     let (system_prompt, user_context) = crate::commands::context::build_separated_context(
-        project_id.clone(), chapter_id.clone(), position
+        project_id.clone(), chapter_id.clone(), position, None
     )
         .await
         .map_err(|e| format!("構建分離上下文失敗: {}", e))?;
@@ -173,37 +319,46 @@ pub async fn generate_with_separated_context(
     log::info!("系統提示長度: {} 字符", system_prompt.len());
     log::info!("用戶上下文長度: {} 字符", user_context.len());
     
-    // 2. 如果有 Chat API 支援，使用 Chat API
-    // 目前先回退到傳統方式，但結構已準備好 Chat API 升級
-    let combined_prompt = format!("{}\n\n{}", system_prompt, user_context);
-    
+    // 2. 系統提示透過 Ollama 原生的 system 欄位傳送，不再與用戶上下文合併
     // 3. 使用上下文生成文本
-    let options = crate::services::ollama::OllamaOptions {
-        temperature: params.temperature,
-        top_p: params.top_p,
-        max_tokens: params.max_tokens,
-        presence_penalty: params.presence_penalty,
-        frequency_penalty: params.frequency_penalty,
-    };
-    
     let ollama_service = get_ollama_service();
     let service = ollama_service.lock().await;
-    let result = service.generate_text(&model, &combined_prompt, Some(options)).await;
+    let generate = |corrective: Option<String>| {
+        let prompt = match corrective {
+            Some(corrective) => format!("{}{}", user_context, corrective),
+            None => user_context.clone(),
+        };
+        let options = crate::services::ollama::OllamaOptions {
+            temperature: params.temperature,
+            top_p: params.top_p,
+            max_tokens: params.max_tokens,
+            presence_penalty: params.presence_penalty,
+            frequency_penalty: params.frequency_penalty,
+        };
+        let service = &service;
+        let model = &model;
+        let system_prompt = &system_prompt;
+        async move {
+            let result = service.generate_text_with_system(model, &prompt, Some(system_prompt), Some(options)).await;
+            if result.success {
+                Ok(result.response.unwrap_or_default())
+            } else {
+                let error_msg = result.error.unwrap_or("生成文本失敗".to_string());
+                log::error!("生成文本失敗: {}", error_msg);
+                Err(error_msg)
+            }
+        }
+    };
     
-    if result.success {
-        let generated_text = result.response.unwrap_or_default();
-        log::info!("生成文本成功，長度: {} 字符", generated_text.len());
-        Ok(generated_text)
-    } else {
-        let error_msg = result.error.unwrap_or("生成文本失敗".to_string());
-        log::error!("生成文本失敗: {}", error_msg);
-        Err(error_msg)
-    }
+    let result = generate_until_pure(generate, purity_retry.as_ref()).await?;
+    log::info!("生成文本成功，長度: {} 字符，純度: {:.3}，共 {} 次", result.text.len(), result.purity_score, result.attempts);
+    Ok(result)
 }
 
 /// 使用上下文生成文本（傳統版本）
 #[command]
 pub async fn generate_with_context(
+    app: AppHandle,
     project_id: String,
     chapter_id: String,
     position: usize,
@@ -216,12 +371,12 @@ pub async fn generate_with_context(
     
     // 🔥 修復：使用新的多提供者系統
     // 首先需要找到使用此模型的提供者
-    use crate::database::connection::create_connection;
+    use crate::database::get_db_conn;
     use rusqlite::params;
     
     // 🔥 智能提供者匹配邏輯 - 讓一個提供者支持多個模型
     let provider_id = {
-        let conn = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+        let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
         
         // 首先嘗試精確模型匹配（向後兼容）
         let mut stmt = conn.prepare(
@@ -280,7 +435,7 @@ pub async fn generate_with_context(
         stop: None,
     };
     
-    match crate::commands::ai_providers::generate_ai_text(request).await {
+    match crate::commands::ai_providers::generate_ai_text(app, request).await {
         Ok(result) => {
             if result.success {
                 let generated_text = result.generated_text.unwrap_or_default();
@@ -328,4 +483,48 @@ pub struct UpdateConfigRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConfigUpdateResult {
     pub success: bool,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test]
+    async fn test_regenerates_until_purity_passes() {
+        let outputs = ["他推門走進这片 magic 雨夜", "他推門走進 the rainy night", "他推門走進雨夜"];
+        let mut prompts = Vec::new();
+        let retry = PurityRetryOptions { min_score: 0.95, max_retries: 3 };
+        
+        let result = generate_until_pure(|corrective| {
+            prompts.push(corrective);
+            let text = outputs[prompts.len() - 1].to_string();
+            async move { Ok(text) }
+        }, Some(&retry)).await.unwrap();
+        
+        assert_eq!(result.text, "他推門走進雨夜");
+        assert_eq!(result.attempts, 3);
+        assert!(prompts[0].is_none());
+        let corrective = prompts[1].as_deref().unwrap();
+        assert!(corrective.contains("magic") && corrective.contains("这"));
+    }
+    
+    #[tokio::test]
+    async fn test_returns_best_attempt_when_retries_run_out() {
+        let outputs = ["他說 ok", "他說 okay okay okay"];
+        let mut calls = 0;
+        let retry = PurityRetryOptions { min_score: 0.99, max_retries: 1 };
+        
+        let result = generate_until_pure(|_| {
+            calls += 1;
+            let text = outputs[calls - 1].to_string();
+            async move { Ok(text) }
+        }, Some(&retry)).await.unwrap();
+        
+        assert_eq!(result.text, "他說 ok");
+        assert_eq!(result.attempts, 2);
+        
+        // 未啟用重試時只生成一次
+        let single = generate_until_pure(|_| async { Ok("他說 ok".to_string()) }, None).await.unwrap();
+        assert_eq!(single.attempts, 1);
+    }
+}
@@ -1,5 +1,6 @@
-use crate::database::{get_db, models::*};
-use chrono::Utc;
+use crate::database::{get_db_conn, models::*};
+use crate::utils::csv::escape_csv_field;
+use chrono::{DateTime, NaiveDate, Utc};
 use rusqlite::{params, Connection};
 use tauri::command;
 use uuid::Uuid;
@@ -7,8 +8,7 @@ use uuid::Uuid;
 /// 創建新的 AI 生成歷史記錄
 #[command]
 pub async fn create_ai_history(request: CreateAIHistoryRequest) -> Result<AIGenerationHistory, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().map_err(|e| format!("無法獲取資料庫鎖: {}", e))?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let id = Uuid::new_v4().to_string();
     let created_at = Utc::now();
@@ -41,6 +41,26 @@ pub async fn create_ai_history(request: CreateAIHistoryRequest) -> Result<AIGene
     get_ai_history_by_id(&*conn, &id)
 }
 
+/// 將查詢結果行轉換為歷史記錄（欄位順序需與 SELECT 一致）
+fn history_from_row(row: &rusqlite::Row) -> rusqlite::Result<AIGenerationHistory> {
+    Ok(AIGenerationHistory {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        chapter_id: row.get(2)?,
+        provider_id: row.get(3)?,
+        model: row.get(4)?,
+        prompt: row.get(5)?,
+        generated_text: row.get(6)?,
+        parameters: row.get(7)?,
+        language_purity: row.get(8)?,
+        token_count: row.get(9)?,
+        generation_time_ms: row.get(10)?,
+        selected: row.get(11)?,
+        position: row.get(12)?,
+        created_at: row.get(13)?,
+    })
+}
+
 /// 根據 ID 獲取 AI 生成歷史記錄
 fn get_ai_history_by_id(conn: &Connection, id: &str) -> Result<AIGenerationHistory, String> {
     let mut stmt = conn.prepare(
@@ -51,90 +71,96 @@ fn get_ai_history_by_id(conn: &Connection, id: &str) -> Result<AIGenerationHisto
          WHERE id = ?1"
     ).map_err(|e| e.to_string())?;
     
-    let history = stmt.query_row([id], |row| {
-        Ok(AIGenerationHistory {
-            id: row.get(0)?,
-            project_id: row.get(1)?,
-            chapter_id: row.get(2)?,
-            provider_id: row.get(3)?,
-            model: row.get(4)?,
-            prompt: row.get(5)?,
-            generated_text: row.get(6)?,
-            parameters: row.get(7)?,
-            language_purity: row.get(8)?,
-            token_count: row.get(9)?,
-            generation_time_ms: row.get(10)?,
-            selected: row.get(11)?,
-            position: row.get(12)?,
-            created_at: row.get(13)?,
-        })
-    }).map_err(|e| format!("獲取 AI 歷史記錄失敗: {}", e))?;
+    let history = stmt.query_row([id], history_from_row)
+        .map_err(|e| format!("獲取 AI 歷史記錄失敗: {}", e))?;
     
     Ok(history)
 }
 
+/// 解析日期篩選條件，支援 RFC 3339 與 YYYY-MM-DD（結束日期涵蓋當日整天）
+fn parse_date_bound(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.with_timezone(&Utc));
+    }
+    
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("無效的日期格式: {}", value))?;
+    let time = if end_of_day {
+        date.and_hms_milli_opt(23, 59, 59, 999)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    };
+    
+    time.map(|t| t.and_utc())
+        .ok_or_else(|| format!("無效的日期格式: {}", value))
+}
+
 /// 查詢 AI 生成歷史記錄
 #[command]
 pub async fn query_ai_history(request: QueryAIHistoryRequest) -> Result<Vec<AIGenerationHistory>, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().map_err(|e| format!("無法獲取資料庫鎖: {}", e))?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut query = String::from(
         "SELECT id, project_id, chapter_id, provider_id, model, prompt, generated_text,
                 parameters, language_purity, token_count, generation_time_ms,
                 selected, position, created_at
-         FROM ai_generation_history
-         WHERE 1=1"
+         FROM ai_generation_history"
     );
     
+    let mut conditions = Vec::new();
     let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
     
     if let Some(project_id) = &request.project_id {
-        query.push_str(" AND project_id = ?");
+        conditions.push("project_id = ?");
         params.push(Box::new(project_id.clone()));
     }
     
     if let Some(chapter_id) = &request.chapter_id {
-        query.push_str(" AND chapter_id = ?");
+        conditions.push("chapter_id = ?");
         params.push(Box::new(chapter_id.clone()));
     }
     
+    if let Some(provider_id) = &request.provider_id {
+        conditions.push("provider_id = ?");
+        params.push(Box::new(provider_id.clone()));
+    }
+    
+    if let Some(from_date) = &request.from_date {
+        conditions.push("created_at >= ?");
+        params.push(Box::new(parse_date_bound(from_date, false)?));
+    }
+    
+    if let Some(to_date) = &request.to_date {
+        conditions.push("created_at <= ?");
+        params.push(Box::new(parse_date_bound(to_date, true)?));
+    }
+    
     if let Some(true) = request.selected_only {
-        query.push_str(" AND selected = 1");
+        conditions.push("selected = 1");
+    }
+    
+    if !conditions.is_empty() {
+        query.push_str(" WHERE ");
+        query.push_str(&conditions.join(" AND "));
     }
     
     query.push_str(" ORDER BY created_at DESC");
     
     if let Some(limit) = request.limit {
-        query.push_str(&format!(" LIMIT {}", limit));
+        query.push_str(" LIMIT ?");
+        params.push(Box::new(limit));
     }
     
     if let Some(offset) = request.offset {
-        query.push_str(&format!(" OFFSET {}", offset));
+        query.push_str(" OFFSET ?");
+        params.push(Box::new(offset));
     }
     
     let mut stmt = conn.prepare(&query).map_err(|e| e.to_string())?;
     
     let history_iter = stmt.query_map(
         params.iter().map(|p| p.as_ref()).collect::<Vec<_>>().as_slice(),
-        |row| {
-            Ok(AIGenerationHistory {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                chapter_id: row.get(2)?,
-                provider_id: row.get(3)?,
-                model: row.get(4)?,
-                prompt: row.get(5)?,
-                generated_text: row.get(6)?,
-                parameters: row.get(7)?,
-                language_purity: row.get(8)?,
-                token_count: row.get(9)?,
-                generation_time_ms: row.get(10)?,
-                selected: row.get(11)?,
-                position: row.get(12)?,
-                created_at: row.get(13)?,
-            })
-        }
+        history_from_row,
     ).map_err(|e| e.to_string())?;
     
     let mut histories = Vec::new();
@@ -148,8 +174,7 @@ pub async fn query_ai_history(request: QueryAIHistoryRequest) -> Result<Vec<AIGe
 /// 標記某個歷史記錄為已選擇
 #[command]
 pub async fn mark_ai_history_selected(history_id: String, project_id: String) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().map_err(|e| format!("無法獲取資料庫鎖: {}", e))?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     // 首先將該專案的所有歷史記錄標記為未選擇
     conn.execute(
@@ -169,8 +194,7 @@ pub async fn mark_ai_history_selected(history_id: String, project_id: String) ->
 /// 刪除 AI 生成歷史記錄
 #[command]
 pub async fn delete_ai_history(history_id: String) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().map_err(|e| format!("無法獲取資料庫鎖: {}", e))?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     conn.execute(
         "DELETE FROM ai_generation_history WHERE id = ?1",
@@ -181,10 +205,22 @@ pub async fn delete_ai_history(history_id: String) -> Result<(), String> {
 }
 
 /// 清理舊的 AI 生成歷史記錄（保留最近的 N 條）
+///
+/// 指定 `keep_per_chapter` 時改為逐章節清理：每個章節保留最新的 N 條與所有已選用的記錄。
 #[command]
-pub async fn cleanup_ai_history(project_id: String, keep_count: i32) -> Result<i32, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().map_err(|e| format!("無法獲取資料庫鎖: {}", e))?;
+pub async fn cleanup_ai_history(
+    project_id: String,
+    keep_count: i32,
+    keep_per_chapter: Option<usize>,
+) -> Result<i32, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    if let Some(keep_per_chapter) = keep_per_chapter {
+        let deleted_count = cleanup_history_per_chapter(&conn, &project_id, keep_per_chapter)
+            .map_err(|e| format!("清理歷史記錄失敗: {}", e))?;
+        log::info!("逐章節清理 AI 歷史記錄：刪除 {} 筆", deleted_count);
+        return Ok(deleted_count as i32);
+    }
     
     // 獲取要保留的記錄 ID
     let keep_ids: Vec<String> = conn.prepare(
@@ -224,4 +260,406 @@ pub async fn cleanup_ai_history(project_id: String, keep_count: i32) -> Result<i
     ).map_err(|e| format!("清理歷史記錄失敗: {}", e))?;
     
     Ok(deleted_count as i32)
-}
\ No newline at end of file
+}
+
+/// 每個章節保留最新的 N 條記錄與所有 selected = 1 的記錄，其餘刪除
+fn cleanup_history_per_chapter(conn: &rusqlite::Connection, project_id: &str, keep_per_chapter: usize) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM ai_generation_history
+         WHERE project_id = ?1
+           AND selected = 0
+           AND id IN (
+               SELECT id FROM (
+                   SELECT id, ROW_NUMBER() OVER (
+                       PARTITION BY chapter_id
+                       ORDER BY created_at DESC, rowid DESC
+                   ) AS row_num
+                   FROM ai_generation_history
+                   WHERE project_id = ?1
+               )
+               WHERE row_num > ?2
+           )",
+        params![project_id, keep_per_chapter as i64],
+    )
+}
+
+/// trigram 分詞器可比對的最短關鍵字長度
+const FTS_MIN_QUERY_CHARS: usize = 3;
+
+/// 全文搜尋 AI 生成歷史記錄（提示詞與生成內容）
+#[command]
+pub async fn search_ai_history(
+    project_id: String,
+    query: String,
+    limit: Option<i32>,
+) -> Result<Vec<AIHistorySearchResult>, String> {
+    let query = query.trim().to_string();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(50);
+    
+    // trigram 無法比對少於 3 個字元的關鍵字，短關鍵字改為子字串比對並自行擷取片段
+    if query.chars().count() < FTS_MIN_QUERY_CHARS {
+        let mut stmt = conn.prepare(
+            "SELECT id, project_id, chapter_id, provider_id, model, prompt, generated_text,
+                    parameters, language_purity, token_count, generation_time_ms,
+                    selected, position, created_at
+             FROM ai_generation_history
+             WHERE project_id = ?1 AND (instr(prompt, ?2) > 0 OR instr(generated_text, ?2) > 0)
+             ORDER BY created_at DESC
+             LIMIT ?3"
+        ).map_err(|e| e.to_string())?;
+        
+        let rows = stmt.query_map(params![project_id, query, limit], history_from_row)
+            .map_err(|e| e.to_string())?;
+        
+        let mut results = Vec::new();
+        for row in rows {
+            let history = row.map_err(|e| e.to_string())?;
+            results.push(AIHistorySearchResult {
+                prompt_snippet: build_snippet(&history.prompt, &query),
+                generated_text_snippet: build_snippet(&history.generated_text, &query),
+                history,
+            });
+        }
+        return Ok(results);
+    }
+    
+    // 以片語查詢避免 FTS5 語法字元造成錯誤
+    let fts_query = format!("\"{}\"", query.replace('"', "\"\""));
+    
+    let mut stmt = conn.prepare(
+        "SELECT h.id, h.project_id, h.chapter_id, h.provider_id, h.model, h.prompt, h.generated_text,
+                h.parameters, h.language_purity, h.token_count, h.generation_time_ms,
+                h.selected, h.position, h.created_at,
+                snippet(ai_generation_history_fts, 0, '<mark>', '</mark>', '…', 16),
+                snippet(ai_generation_history_fts, 1, '<mark>', '</mark>', '…', 16)
+         FROM ai_generation_history_fts
+         JOIN ai_generation_history h ON h.rowid = ai_generation_history_fts.rowid
+         WHERE ai_generation_history_fts MATCH ?1 AND h.project_id = ?2
+         ORDER BY rank
+         LIMIT ?3"
+    ).map_err(|e| e.to_string())?;
+    
+    let rows = stmt.query_map(params![fts_query, project_id, limit], |row| {
+        Ok(AIHistorySearchResult {
+            history: history_from_row(row)?,
+            prompt_snippet: row.get(14)?,
+            generated_text_snippet: row.get(15)?,
+        })
+    }).map_err(|e| format!("搜尋 AI 歷史記錄失敗: {}", e))?;
+    
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    
+    Ok(results)
+}
+
+/// 擷取關鍵字前後的文字片段並以 <mark> 標示（用於短關鍵字搜尋）
+fn build_snippet(text: &str, query: &str) -> String {
+    const CONTEXT_CHARS: usize = 16;
+    
+    let Some(byte_index) = text.find(query) else {
+        return text.chars().take(CONTEXT_CHARS * 2).collect();
+    };
+    
+    let char_index = text[..byte_index].chars().count();
+    let query_len = query.chars().count();
+    let start = char_index.saturating_sub(CONTEXT_CHARS);
+    
+    let before: String = text.chars().skip(start).take(char_index - start).collect();
+    let after: String = text.chars().skip(char_index + query_len).take(CONTEXT_CHARS).collect();
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if char_index + query_len + CONTEXT_CHARS < text.chars().count() { "…" } else { "" };
+    
+    format!("{}{}<mark>{}</mark>{}{}", prefix, before, query, after, suffix)
+}
+
+/// 將歷史生成內容套用回章節（插入到記錄的位置）
+#[command]
+pub async fn apply_ai_history_to_chapter(history_id: String) -> Result<String, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    apply_history_record(&mut conn, &history_id)
+}
+
+/// 在單一交易中套用歷史記錄，套用前先保存章節版本，讓套用可以復原
+fn apply_history_record(conn: &mut Connection, history_id: &str) -> Result<String, String> {
+    let history = get_ai_history_by_id(conn, history_id)?;
+    
+    let (project_id, content): (String, Option<String>) = conn
+        .query_row(
+            "SELECT project_id, content FROM chapters WHERE id = ?1",
+            [&history.chapter_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("章節不存在: {}", e))?;
+    
+    let position = history.position.and_then(|p| usize::try_from(p).ok());
+    let new_content = crate::utils::slate::insert_text_at_offset(
+        content.as_deref().unwrap_or("[]"),
+        position,
+        &history.generated_text,
+    )?;
+    
+    let now = Utc::now();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    crate::commands::chapter::snapshot_chapter_version(&tx, &history.chapter_id, "ai_history")
+        .map_err(|e| format!("保存章節版本失敗: {}", e))?;
+    
+    tx.execute(
+        "UPDATE chapters SET content = ?1, updated_at = ?2 WHERE id = ?3",
+        params![new_content, now, history.chapter_id],
+    ).map_err(|e| format!("更新章節失敗: {}", e))?;
+    
+    tx.execute(
+        "UPDATE projects SET updated_at = ?1 WHERE id = ?2",
+        params![now, project_id],
+    ).map_err(|e| format!("更新專案時間戳失敗: {}", e))?;
+    
+    tx.execute(
+        "UPDATE ai_generation_history SET selected = 1 WHERE id = ?1",
+        [history_id],
+    ).map_err(|e| format!("標記歷史記錄失敗: {}", e))?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    log::info!("已將 AI 歷史記錄 {} 套用到章節 {}（位置: {:?}）", history_id, history.chapter_id, position);
+    
+    Ok(new_content)
+}
+
+/// 獲取專案的 AI 生成歷史統計（含各模型分項）
+#[command]
+pub async fn get_ai_history_stats(project_id: String) -> Result<AIHistoryStats, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let (total_generations, selected_count, avg_language_purity, avg_generation_time_ms, total_tokens): (i64, i64, Option<f64>, Option<f64>, i64) = conn
+        .query_row(
+            "SELECT COUNT(*),
+                    COALESCE(SUM(CASE WHEN selected = 1 THEN 1 ELSE 0 END), 0),
+                    AVG(language_purity),
+                    AVG(generation_time_ms),
+                    COALESCE(SUM(token_count), 0)
+             FROM ai_generation_history
+             WHERE project_id = ?1",
+            [&project_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .map_err(|e| format!("統計 AI 歷史記錄失敗: {}", e))?;
+    
+    let mut stmt = conn.prepare(
+        "SELECT provider_id, model,
+                COUNT(*),
+                COALESCE(SUM(CASE WHEN selected = 1 THEN 1 ELSE 0 END), 0),
+                AVG(language_purity),
+                AVG(generation_time_ms),
+                COALESCE(SUM(token_count), 0)
+         FROM ai_generation_history
+         WHERE project_id = ?1
+         GROUP BY provider_id, model
+         ORDER BY COUNT(*) DESC"
+    ).map_err(|e| e.to_string())?;
+    
+    let by_model = stmt
+        .query_map([&project_id], |row| {
+            let generations: i64 = row.get(2)?;
+            let selected_count: i64 = row.get(3)?;
+            Ok(AIHistoryModelStats {
+                provider_id: row.get(0)?,
+                model: row.get(1)?,
+                generations,
+                selected_count,
+                acceptance_rate: acceptance_rate(selected_count, generations),
+                avg_language_purity: row.get(4)?,
+                avg_generation_time_ms: row.get(5)?,
+                total_tokens: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    
+    Ok(AIHistoryStats {
+        total_generations,
+        selected_count,
+        acceptance_rate: acceptance_rate(selected_count, total_generations),
+        avg_language_purity,
+        avg_generation_time_ms,
+        total_tokens,
+        by_model,
+    })
+}
+
+fn acceptance_rate(selected: i64, total: i64) -> f64 {
+    if total > 0 {
+        selected as f64 / total as f64
+    } else {
+        0.0
+    }
+}
+
+/// 匯出專案的 AI 生成歷史記錄到檔案（format: "json" 或 "csv"），回傳匯出筆數
+///
+/// 逐筆寫入檔案，避免大量歷史記錄一次載入記憶體。
+#[command]
+pub async fn export_ai_history(project_id: String, format: String, path: String) -> Result<usize, String> {
+    use std::io::{BufWriter, Write};
+    
+    let format = format.to_lowercase();
+    if format != "json" && format != "csv" {
+        return Err(format!("不支援的匯出格式: {}", format));
+    }
+    
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let file = std::fs::File::create(&path).map_err(|e| format!("無法建立匯出檔案: {}", e))?;
+    let mut writer = BufWriter::new(file);
+    
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, chapter_id, provider_id, model, prompt, generated_text,
+                parameters, language_purity, token_count, generation_time_ms,
+                selected, position, created_at
+         FROM ai_generation_history
+         WHERE project_id = ?1
+         ORDER BY created_at ASC"
+    ).map_err(|e| e.to_string())?;
+    let mut rows = stmt.query([&project_id]).map_err(|e| e.to_string())?;
+    
+    let write_err = |e: std::io::Error| format!("寫入匯出檔案失敗: {}", e);
+    let mut count = 0;
+    
+    if format == "json" {
+        writer.write_all(b"[\n").map_err(write_err)?;
+    } else {
+        // 加上 BOM 讓試算表軟體正確辨識 UTF-8 中文
+        writer.write_all("\u{feff}id,chapter_id,provider_id,model,prompt,generated_text,parameters,language_purity,token_count,generation_time_ms,selected,created_at\n".as_bytes())
+            .map_err(write_err)?;
+    }
+    
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        let history = history_from_row(row).map_err(|e| e.to_string())?;
+        
+        if format == "json" {
+            if count > 0 {
+                writer.write_all(b",\n").map_err(write_err)?;
+            }
+            let parameters = history.parameters.as_deref()
+                .and_then(|p| serde_json::from_str::<serde_json::Value>(p).ok());
+            let record = serde_json::json!({
+                "id": history.id,
+                "chapter_id": history.chapter_id,
+                "provider_id": history.provider_id,
+                "model": history.model,
+                "prompt": history.prompt,
+                "generated_text": history.generated_text,
+                "parameters": parameters,
+                "language_purity": history.language_purity,
+                "token_count": history.token_count,
+                "generation_time_ms": history.generation_time_ms,
+                "selected": history.selected,
+                "created_at": history.created_at,
+            });
+            serde_json::to_writer_pretty(&mut writer, &record).map_err(|e| e.to_string())?;
+        } else {
+            let fields = [
+                history.id,
+                history.chapter_id,
+                history.provider_id.unwrap_or_default(),
+                history.model,
+                history.prompt,
+                history.generated_text,
+                history.parameters.unwrap_or_default(),
+                history.language_purity.map(|v| v.to_string()).unwrap_or_default(),
+                history.token_count.map(|v| v.to_string()).unwrap_or_default(),
+                history.generation_time_ms.map(|v| v.to_string()).unwrap_or_default(),
+                history.selected.to_string(),
+                history.created_at.to_rfc3339(),
+            ];
+            let line = fields.iter().map(|f| escape_csv_field(f)).collect::<Vec<_>>().join(",");
+            writer.write_all(line.as_bytes()).map_err(write_err)?;
+            writer.write_all(b"\n").map_err(write_err)?;
+        }
+        
+        count += 1;
+    }
+    
+    if format == "json" {
+        writer.write_all(b"\n]\n").map_err(write_err)?;
+    }
+    writer.flush().map_err(write_err)?;
+    
+    log::info!("已匯出 {} 筆 AI 歷史記錄到 {}", count, path);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn setup_history(conn: &Connection) {
+        conn.execute_batch(
+            "CREATE TABLE ai_generation_history (
+                id TEXT PRIMARY KEY, project_id TEXT, chapter_id TEXT,
+                selected BOOLEAN DEFAULT 0, created_at DATETIME
+            );",
+        ).unwrap();
+        let rows = [
+            ("a1", "c1", 0, "2024-01-01"),
+            ("a2", "c1", 1, "2024-01-02"),
+            ("a3", "c1", 0, "2024-01-03"),
+            ("a4", "c1", 0, "2024-01-04"),
+            ("b1", "c2", 0, "2024-01-01"),
+            ("b2", "c2", 0, "2024-01-02"),
+        ];
+        for (id, chapter_id, selected, created_at) in rows {
+            conn.execute(
+                "INSERT INTO ai_generation_history (id, project_id, chapter_id, selected, created_at) VALUES (?1, 'p1', ?2, ?3, ?4)",
+                params![id, chapter_id, selected, created_at],
+            ).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_cleanup_per_chapter_keeps_newest_and_selected() {
+        let conn = Connection::open_in_memory().unwrap();
+        setup_history(&conn);
+
+        let deleted = cleanup_history_per_chapter(&conn, "p1", 1).unwrap();
+        assert_eq!(deleted, 3);
+
+        let mut stmt = conn.prepare("SELECT id FROM ai_generation_history ORDER BY id").unwrap();
+        let remaining: Vec<String> = stmt.query_map([], |row| row.get(0)).unwrap()
+            .collect::<Result<_, _>>().unwrap();
+        assert_eq!(remaining, vec!["a2", "a4", "b2"]);
+    }
+
+    #[test]
+    fn test_apply_history_keeps_plain_text_chapter_and_snapshots() {
+        let mut conn = crate::database::test_support::migrated_conn_with_project();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content) VALUES ('c1', 'p1', '第一章', '舊版第一段\n舊版第二段');
+             INSERT INTO ai_generation_history (id, project_id, chapter_id, model, prompt, generated_text)
+                 VALUES ('h1', 'p1', 'c1', 'm', 'p', '新的段落');",
+        ).unwrap();
+
+        let content = apply_history_record(&mut conn, "h1").unwrap();
+        assert_eq!(
+            crate::utils::slate::slate_to_plain_text(&content),
+            "舊版第一段\n舊版第二段\n新的段落"
+        );
+        let (versions, selected): (i64, bool) = conn
+            .query_row(
+                "SELECT (SELECT COUNT(*) FROM chapter_versions WHERE chapter_id = 'c1' AND content LIKE '舊版%'),
+                        (SELECT selected FROM ai_generation_history WHERE id = 'h1')",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((versions, selected), (1, true));
+    }
+}
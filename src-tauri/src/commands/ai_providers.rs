@@ -1,10 +1,11 @@
-use crate::database::{connection::create_connection, models::*};
-use crate::services::ai_providers::{AIProviderFactory, ProviderConfig};
+use crate::database::{get_db_conn, models::*};
+use crate::services::ai_providers::{AIProviderFactory, AIUsageInfo, ProviderConfig};
 use anyhow::{Result, anyhow};
 use chrono::Utc;
 use rusqlite::{params, Row};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tauri::{AppHandle, Emitter};
 use uuid::Uuid;
 
 // 響應結構體
@@ -24,6 +25,20 @@ pub struct AIProviderTestResult {
     pub error: Option<String>,
 }
 
+/// 單一提供者的健康檢查結果
+#[derive(Debug, Serialize)]
+pub struct AIProviderHealth {
+    pub id: String,
+    pub name: String,
+    pub provider_type: String,
+    pub reachable: bool,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// 批次健康檢查中每個提供者的預設逾時（秒）
+const PROVIDER_HEALTH_TIMEOUT_SECS: u64 = 10;
+
 #[derive(Debug, Serialize)]
 pub struct AIGenerationResult {
     pub success: bool,
@@ -31,6 +46,7 @@ pub struct AIGenerationResult {
     pub model: Option<String>,
     pub usage: Option<serde_json::Value>,
     pub provider_id: Option<String>,
+    pub history_id: Option<String>, // 對應的 AI 生成歷史記錄 ID
     pub error: Option<String>,
 }
 
@@ -114,7 +130,7 @@ fn provider_to_config(provider: &crate::database::models::AIProvider) -> Result<
 pub async fn get_ai_providers() -> Result<AIProviderResponse, String> {
     log::info!("獲取所有AI提供者");
     
-    let conn = create_connection().map_err(|e| e.to_string())?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn.prepare(
         "SELECT id, name, provider_type, api_key_encrypted, endpoint, model, 
@@ -143,7 +159,7 @@ pub async fn get_ai_providers() -> Result<AIProviderResponse, String> {
 pub async fn create_ai_provider(request: CreateAIProviderRequest) -> Result<AIProviderResponse, String> {
     log::info!("創建AI提供者: {}", request.name);
     
-    let conn = create_connection().map_err(|e| e.to_string())?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     let id = Uuid::new_v4().to_string();
     let now = Utc::now();
     
@@ -200,7 +216,7 @@ pub async fn create_ai_provider(request: CreateAIProviderRequest) -> Result<AIPr
 pub async fn update_ai_provider(request: UpdateAIProviderRequest) -> Result<AIProviderResponse, String> {
     log::info!("更新AI提供者: {}", request.id);
     
-    let conn = create_connection().map_err(|e| e.to_string())?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     let now = Utc::now();
     
     // 構建動態SQL更新語句
@@ -263,7 +279,7 @@ pub async fn update_ai_provider(request: UpdateAIProviderRequest) -> Result<AIPr
 pub async fn delete_ai_provider(id: String) -> Result<AIProviderResponse, String> {
     log::info!("刪除AI提供者: {}", id);
     
-    let conn = create_connection().map_err(|e| e.to_string())?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     conn.execute("DELETE FROM ai_providers WHERE id = ?1", params![id])
         .map_err(|e| e.to_string())?;
@@ -283,7 +299,7 @@ pub async fn test_ai_provider(id: String) -> Result<AIProviderTestResult, String
     
     // 先從數據庫獲取提供者資訊，然後關閉連接
     let (config, provider_type) = {
-        let conn = create_connection().map_err(|e| e.to_string())?;
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
         
         let mut stmt = conn.prepare(
             "SELECT id, name, provider_type, api_key_encrypted, endpoint, model, 
@@ -355,32 +371,157 @@ pub async fn test_ai_provider(id: String) -> Result<AIProviderTestResult, String
     }
 }
 
-/// 使用指定提供者生成文本（帶上下文構建）
+/// 同時測試所有已啟用的AI提供者（每個提供者各自逾時，互不阻塞）
 #[tauri::command]
-pub async fn generate_ai_text(request: AIGenerationRequestData) -> Result<AIGenerationResult, String> {
-    log::info!("使用AI提供者生成文本（帶上下文）: {} -> {}", request.provider_id, request.model);
-    log::info!("項目ID: {}, 章節ID: {}, 位置: {:?}", request.project_id, request.chapter_id, request.position);
-    
-    let start_time = std::time::Instant::now();
+pub async fn test_all_ai_providers(timeout_secs: Option<u64>) -> Result<Vec<AIProviderHealth>, String> {
+    let timeout = std::time::Duration::from_secs(timeout_secs.unwrap_or(PROVIDER_HEALTH_TIMEOUT_SECS).max(1));
     
-    // 先從數據庫獲取提供者資訊，然後關閉連接
-    let config = {
-        let conn = create_connection().map_err(|e| e.to_string())?;
-        
+    let providers = {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
         let mut stmt = conn.prepare(
             "SELECT id, name, provider_type, api_key_encrypted, endpoint, model, 
              is_enabled, settings_json, created_at, updated_at 
-             FROM ai_providers WHERE id = ?1 AND is_enabled = 1"
+             FROM ai_providers WHERE is_enabled = 1 ORDER BY name"
         ).map_err(|e| e.to_string())?;
-        
-        let provider = stmt.query_row(params![request.provider_id], build_ai_provider_from_row)
-            .map_err(|e| format!("找不到或未啟用的AI提供者: {}", e))?;
-        
-        provider_to_config(&provider).map_err(|e| e.to_string())?
+        let rows = stmt.query_map([], build_ai_provider_from_row).map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
     };
+    log::info!("批次測試 {} 個AI提供者", providers.len());
+    
+    let checks: Vec<_> = providers
+        .into_iter()
+        .map(|provider| {
+            tokio::spawn(async move {
+                let mut health = AIProviderHealth {
+                    id: provider.id.clone(),
+                    name: provider.name.clone(),
+                    provider_type: provider.provider_type.clone(),
+                    reachable: false,
+                    latency_ms: None,
+                    error: None,
+                };
+                
+                let instance = provider_to_config(&provider)
+                    .and_then(|config| AIProviderFactory::create_provider(&config));
+                let instance = match instance {
+                    Ok(instance) => instance,
+                    Err(e) => {
+                        health.error = Some(format!("創建提供者實例失敗: {}", e));
+                        return health;
+                    }
+                };
+                
+                let started = std::time::Instant::now();
+                match tokio::time::timeout(timeout, instance.check_availability()).await {
+                    Ok(Ok(true)) => {
+                        health.reachable = true;
+                        health.latency_ms = Some(started.elapsed().as_millis() as u64);
+                    }
+                    Ok(Ok(false)) => health.error = Some("服務不可用".to_string()),
+                    Ok(Err(e)) => health.error = Some(e.to_string()),
+                    Err(_) => health.error = Some(format!("連接逾時（{} 秒）", timeout.as_secs())),
+                }
+                health
+            })
+        })
+        .collect();
     
-    // 🔥 核心修復：添加上下文構建功能（和舊版 generate_with_context 一樣）
-    let enhanced_prompt = if let Some(position) = request.position {
+    let mut results = Vec::with_capacity(checks.len());
+    for check in checks {
+        results.push(check.await.map_err(|e| format!("提供者檢查中斷: {}", e))?);
+    }
+    Ok(results)
+}
+
+/// 粗略估算文本的 token 數（中文約 2 字符 = 1 token）
+fn estimate_token_count(text: &str) -> i32 {
+    (text.chars().count() / 2) as i32
+}
+
+/// 整理提供者回傳的 token 用量，缺少的欄位以估算值補齊
+///
+/// 回傳 (用量, 是否包含估算值)
+fn resolve_token_usage(
+    usage: Option<&AIUsageInfo>,
+    prompt: &str,
+    completion: &str,
+) -> (AIUsageInfo, bool) {
+    let reported_prompt = usage.and_then(|u| u.prompt_tokens);
+    let reported_completion = usage.and_then(|u| u.completion_tokens);
+    let estimated = reported_prompt.is_none() || reported_completion.is_none();
+    
+    let prompt_tokens = reported_prompt.unwrap_or_else(|| estimate_token_count(prompt));
+    let completion_tokens = reported_completion.unwrap_or_else(|| estimate_token_count(completion));
+    let total_tokens = usage
+        .and_then(|u| u.total_tokens)
+        .filter(|_| !estimated)
+        .unwrap_or(prompt_tokens + completion_tokens);
+    
+    (
+        AIUsageInfo {
+            prompt_tokens: Some(prompt_tokens),
+            completion_tokens: Some(completion_tokens),
+            total_tokens: Some(total_tokens),
+        },
+        estimated,
+    )
+}
+
+/// 將生成結果寫入 AI 生成歷史記錄，回傳記錄 ID
+fn save_generation_history(
+    request: &AIGenerationRequestData,
+    model: &str,
+    generated_text: &str,
+    parameters: &str,
+    token_count: Option<i32>,
+    generation_time_ms: i32,
+) -> Result<String> {
+    let conn = get_db_conn()?;
+    let id = Uuid::new_v4().to_string();
+    
+    conn.execute(
+        "INSERT INTO ai_generation_history (
+            id, project_id, chapter_id, provider_id, model, prompt, generated_text,
+            parameters, token_count, generation_time_ms, selected, position, created_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 0, ?11, ?12)",
+        params![
+            id,
+            request.project_id,
+            request.chapter_id,
+            request.provider_id,
+            model,
+            request.prompt,
+            generated_text,
+            parameters,
+            token_count,
+            generation_time_ms,
+            request.position.map(|p| p as i32),
+            Utc::now(),
+        ],
+    )?;
+    
+    Ok(id)
+}
+
+/// 讀取已啟用的 AI 提供者設定
+pub(crate) fn load_enabled_provider_config(provider_id: &str) -> Result<ProviderConfig, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let mut stmt = conn.prepare(
+        "SELECT id, name, provider_type, api_key_encrypted, endpoint, model, 
+         is_enabled, settings_json, created_at, updated_at 
+         FROM ai_providers WHERE id = ?1 AND is_enabled = 1"
+    ).map_err(|e| e.to_string())?;
+    
+    let provider = stmt.query_row(params![provider_id], build_ai_provider_from_row)
+        .map_err(|e| format!("找不到或未啟用的AI提供者: {}", e))?;
+    
+    provider_to_config(&provider).map_err(|e| e.to_string())
+}
+
+/// 依游標位置構建帶上下文的提示詞，沒有位置資訊時直接使用原始提示
+async fn build_enhanced_prompt(request: &AIGenerationRequestData) -> String {
+    if let Some(position) = request.position {
         log::info!("構建上下文，位置: {}", position);
         
         // 1. 構建上下文（使用和舊版相同的邏輯）
@@ -439,16 +580,17 @@ pub async fn generate_ai_text(request: AIGenerationRequestData) -> Result<AIGene
         // 沒有位置信息，直接使用原始提示
         log::info!("沒有位置信息，使用原始提示");
         request.prompt.clone()
-    };
+    }
+}
 
-    // 創建提供者實例
-    let provider_instance = AIProviderFactory::create_provider(&config)
-        .map_err(|e| format!("創建提供者實例失敗: {}", e))?;
-    
-    // 構建生成請求（使用增強的上下文提示詞）
-    let generation_request = crate::services::ai_providers::AIGenerationRequest {
+/// 將前端請求轉換為提供者生成請求
+fn build_generation_request(
+    request: &AIGenerationRequestData,
+    prompt: String,
+) -> crate::services::ai_providers::AIGenerationRequest {
+    crate::services::ai_providers::AIGenerationRequest {
         model: request.model.clone(),
-        prompt: enhanced_prompt, // 🔥 使用帶上下文的增強提示詞
+        prompt, // 🔥 使用帶上下文的增強提示詞
         system_prompt: request.system_prompt.clone(),
         params: crate::services::ai_providers::AIGenerationParams {
             temperature: request.temperature.unwrap_or(0.7),
@@ -458,26 +600,106 @@ pub async fn generate_ai_text(request: AIGenerationRequestData) -> Result<AIGene
             frequency_penalty: request.frequency_penalty,
             stop: request.stop.clone(),
         },
+    }
+}
+
+/// Ollama 模型冷啟動時先預熱，載入期間發送 `model-loading` 事件供 UI 顯示「正在載入模型…」
+///
+/// 預熱失敗只記錄警告，實際生成時仍會依模型載入狀態退避重試。
+async fn warm_up_ollama_model(app: &AppHandle, config: &ProviderConfig, model: &str) {
+    let provider = match crate::services::ai_providers::ollama::OllamaProvider::new(config) {
+        Ok(provider) => provider,
+        Err(e) => {
+            log::warn!("建立 Ollama 預熱客戶端失敗: {}", e);
+            return;
+        }
     };
+    let emit_loading = |status: &crate::services::ai_providers::ollama::ModelLoadingStatus| {
+        if let Err(e) = app.emit("model-loading", status) {
+            log::warn!("發送模型載入事件失敗: {}", e);
+        }
+    };
+    if let Err(e) = provider.warm_up_model(model, emit_loading).await {
+        log::warn!("Ollama 模型 {} 預熱失敗: {}", model, e);
+    }
+}
+
+/// 使用指定提供者生成文本（帶上下文構建）
+#[tauri::command]
+pub async fn generate_ai_text(app: AppHandle, request: AIGenerationRequestData) -> Result<AIGenerationResult, String> {
+    log::info!("使用AI提供者生成文本（帶上下文）: {} -> {}", request.provider_id, request.model);
+    log::info!("項目ID: {}, 章節ID: {}, 位置: {:?}", request.project_id, request.chapter_id, request.position);
+    
+    let start_time = std::time::Instant::now();
+    
+    // 先從數據庫獲取提供者資訊，然後關閉連接
+    let config = load_enabled_provider_config(&request.provider_id)?;
+    
+    // 🔥 核心修復：添加上下文構建功能（和舊版 generate_with_context 一樣）
+    let enhanced_prompt = build_enhanced_prompt(&request).await;
+
+    // 創建提供者實例
+    let provider_instance = AIProviderFactory::create_provider(&config)
+        .map_err(|e| format!("創建提供者實例失敗: {}", e))?;
+    
+    if config.provider_type == "ollama" {
+        warm_up_ollama_model(&app, &config, &request.model).await;
+    }
+    
+    // 構建生成請求（使用增強的上下文提示詞）
+    let generation_request = build_generation_request(&request, enhanced_prompt);
+    
+    // 生成參數快照（用於歷史記錄）
+    let parameters_json = serde_json::json!({
+        "temperature": generation_request.params.temperature,
+        "max_tokens": generation_request.params.max_tokens,
+        "top_p": generation_request.params.top_p,
+        "presence_penalty": generation_request.params.presence_penalty,
+        "frequency_penalty": generation_request.params.frequency_penalty,
+        "stop": generation_request.params.stop,
+    }).to_string();
+    let prompt_for_estimate = format!(
+        "{}{}",
+        generation_request.system_prompt.as_deref().unwrap_or(""),
+        generation_request.prompt
+    );
     
     // 生成文本
     match provider_instance.generate_text(generation_request).await {
         Ok(response) => {
-            let _generation_time_ms = start_time.elapsed().as_millis() as i32;
+            let generation_time_ms = start_time.elapsed().as_millis() as i32;
             
-            // 將結果保存到歷史記錄（可選）
-            // TODO: 實現歷史記錄保存
+            // 整理 token 用量，提供者未回傳的欄位以字數估算補齊
+            let (usage, estimated) = resolve_token_usage(response.usage.as_ref(), &prompt_for_estimate, &response.text);
+            
+            // 將結果保存到歷史記錄
+            let history_id = match save_generation_history(
+                &request,
+                &response.model,
+                &response.text,
+                &parameters_json,
+                usage.total_tokens,
+                generation_time_ms,
+            ) {
+                Ok(id) => Some(id),
+                Err(e) => {
+                    log::warn!("保存 AI 生成歷史記錄失敗: {}", e);
+                    None
+                }
+            };
             
             Ok(AIGenerationResult {
                 success: true,
                 generated_text: Some(response.text),
                 model: Some(response.model),
-                usage: response.usage.map(|usage| serde_json::json!({
+                usage: Some(serde_json::json!({
                     "prompt_tokens": usage.prompt_tokens,
                     "completion_tokens": usage.completion_tokens,
                     "total_tokens": usage.total_tokens,
+                    "estimated": estimated,
                 })),
                 provider_id: Some(request.provider_id),
+                history_id,
                 error: None,
             })
         }
@@ -488,12 +710,35 @@ pub async fn generate_ai_text(request: AIGenerationRequestData) -> Result<AIGene
                 model: None,
                 usage: None,
                 provider_id: Some(request.provider_id),
+                history_id: None,
                 error: Some(e.to_string()),
             })
         }
     }
 }
 
+/// 預覽送往提供者的實際請求內容（不呼叫 API、不消耗 token）
+#[tauri::command]
+pub async fn preview_provider_request(provider_id: String, request: AIGenerationRequestData) -> Result<serde_json::Value, String> {
+    log::info!("預覽AI提供者請求: {} -> {}", provider_id, request.model);
+    
+    let config = load_enabled_provider_config(&provider_id)?;
+    let provider_instance = AIProviderFactory::create_provider(&config)
+        .map_err(|e| format!("創建提供者實例失敗: {}", e))?;
+    
+    let enhanced_prompt = build_enhanced_prompt(&request).await;
+    let generation_request = build_generation_request(&request, enhanced_prompt);
+    
+    let preview = provider_instance.preview_request(&generation_request)
+        .map_err(|e| format!("組裝請求失敗: {}", e))?;
+    
+    Ok(serde_json::json!({
+        "provider_id": provider_id,
+        "provider_type": config.provider_type,
+        "request": preview,
+    }))
+}
+
 /// 獲取支援的AI提供者類型
 #[tauri::command]
 pub async fn get_supported_ai_provider_types() -> Result<Vec<String>, String> {
@@ -508,7 +753,7 @@ pub async fn get_available_models(providerId: String) -> Result<AIProviderTestRe
     
     // 先從數據庫獲取提供者資訊，然後關閉連接
     let (config, provider_type) = {
-        let conn = create_connection().map_err(|e| e.to_string())?;
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
         
         let mut stmt = conn.prepare(
             "SELECT id, name, provider_type, api_key_encrypted, endpoint, model, 
@@ -0,0 +1,1491 @@
+use crate::commands::ai::{generate_until_pure, PurityRetryOptions};
+use crate::commands::ai_providers::load_enabled_provider_config;
+use crate::commands::character::character_name_variants;
+use crate::database::get_db_conn;
+use crate::services::analysis_cache;
+use crate::services::analysis_queue::{self, AnalysisTask, SUPPORTED_ANALYSIS_TYPES};
+use crate::services::ai_providers::{AIGenerationParams, AIGenerationRequest, AIProviderFactory};
+use crate::utils::language_purity::LanguagePurityEnforcer;
+use crate::utils::slate::{count_text, slate_to_plain_text};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+use uuid::Uuid;
+
+/// 低於此字數的章節不產生摘要
+const SUMMARY_MIN_CHARS: usize = 300;
+
+/// 送往 AI 的章節內容上限（字數），過長時只取前段
+const SUMMARY_MAX_INPUT_CHARS: usize = 12000;
+
+/// 寫入 character_analysis 的對話樣本數上限
+const MAX_DIALOGUE_SAMPLES: usize = 20;
+
+/// 角色分析演算法版本（character_analysis.analysis_version）
+const CHARACTER_ANALYSIS_VERSION: &str = "1.0";
+
+/// 創意建議的目標類型（creative_suggestions.target_type）
+const SUGGESTION_TARGET_TYPES: &[&str] = &["plot", "character", "dialogue", "scene", "general"];
+
+/// 創意建議的類型與說明（creative_suggestions.suggestion_type）
+const SUGGESTION_TYPES: &[(&str, &str)] = &[
+    ("continuation", "接下來的劇情發展"),
+    ("alternative", "不同於目前走向的替代方案"),
+    ("enhancement", "強化現有內容的改進方式"),
+    ("conflict", "可以加入的衝突或危機"),
+    ("resolution", "化解目前衝突的方式"),
+];
+
+/// 創意建議參考的章節結尾字數
+const SUGGESTION_CONTEXT_CHARS: usize = 3000;
+
+/// 分析佇列沒有任務時的輪詢間隔
+const ANALYSIS_QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+lazy_static::lazy_static! {
+    /// 有新任務加入時喚醒背景分析工作者
+    static ref ANALYSIS_QUEUE_NOTIFY: tokio::sync::Notify = tokio::sync::Notify::new();
+}
+
+/// 動作描寫的常見動詞
+const ACTION_KEYWORDS: &[&str] = &[
+    "跑", "衝", "揮", "抓", "打", "踢", "跳", "推", "拉", "撲", "砍", "刺", "擋", "閃", "躲",
+    "拔", "舉", "摔", "扔", "丟", "奔", "追", "逃", "轉身", "伸手", "握緊", "站起", "坐下", "走向", "撞",
+];
+
+/// 環境與外觀描寫的常見詞彙
+const DESCRIPTION_KEYWORDS: &[&str] = &[
+    "天空", "陽光", "月光", "星光", "夕陽", "雲", "風", "雨", "雪", "霧", "樹", "花", "草", "山", "河",
+    "海", "湖", "街", "牆", "窗", "房間", "屋", "城", "色", "光", "影", "氣味", "香", "聲音", "安靜",
+];
+
+/// 章節摘要結果
+#[derive(Debug, Serialize)]
+pub struct ChapterSummaryResult {
+    pub chapter_id: String,
+    pub summary: Option<String>,
+    /// 內容未變更，沿用先前的摘要
+    pub cached: bool,
+    /// 章節字數不足，未產生摘要
+    pub skipped: bool,
+    pub purity_score: Option<f64>,
+}
+
+/// 計算文字內容的 SHA-256（十六進位）
+pub(crate) fn content_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 解析章節 metadata，無法解析時視為空物件
+fn parse_chapter_metadata(raw: Option<&str>) -> Map<String, Value> {
+    raw.and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+        .and_then(|value| match value {
+            Value::Object(map) => Some(map),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// 內容雜湊相同時回傳既有摘要
+fn cached_summary(raw_metadata: Option<&str>, hash: &str) -> Option<String> {
+    let metadata = parse_chapter_metadata(raw_metadata);
+    if metadata.get("summary_hash").and_then(Value::as_str) != Some(hash) {
+        return None;
+    }
+    metadata
+        .get("summary")
+        .and_then(Value::as_str)
+        .filter(|summary| !summary.trim().is_empty())
+        .map(str::to_string)
+}
+
+/// 將摘要寫入 metadata，保留其他欄位（如章節筆記）
+fn apply_summary_metadata(raw_metadata: Option<&str>, summary: &str, hash: &str, purity_score: f64) -> String {
+    let mut metadata = parse_chapter_metadata(raw_metadata);
+    metadata.insert("summary".to_string(), Value::String(summary.to_string()));
+    metadata.insert("summary_hash".to_string(), Value::String(hash.to_string()));
+    metadata.insert("summary_purity_score".to_string(), serde_json::json!(purity_score));
+    metadata.insert(
+        "summary_updated_at".to_string(),
+        Value::String(chrono::Utc::now().to_rfc3339()),
+    );
+    Value::Object(metadata).to_string()
+}
+
+/// 使用指定的 AI 提供者（其預設模型）生成文字
+pub(crate) async fn generate_with_provider(
+    provider_id: &str,
+    system_prompt: String,
+    prompt: String,
+    max_tokens: i32,
+) -> Result<(String, String), String> {
+    let config = load_enabled_provider_config(provider_id)?;
+    let provider = AIProviderFactory::create_provider(&config)
+        .map_err(|e| format!("創建提供者實例失敗: {}", e))?;
+    
+    let response = provider
+        .generate_text(AIGenerationRequest {
+            model: config.model.clone(),
+            prompt,
+            system_prompt: Some(system_prompt),
+            params: AIGenerationParams {
+                temperature: 0.3,
+                max_tokens,
+                ..Default::default()
+            },
+        })
+        .await
+        .map_err(|e| format!("AI 生成失敗: {}", e))?;
+    
+    Ok((response.text.trim().to_string(), response.model))
+}
+
+/// 以 AI 產生章節摘要（前情提要），存入章節 metadata.summary
+///
+/// 摘要的語言純度低於門檻時會重新生成，最終仍未達標則回傳錯誤、不寫入 metadata。
+#[command]
+pub async fn summarize_chapter(chapter_id: String, provider_id: String) -> Result<ChapterSummaryResult, String> {
+    log::info!("產生章節摘要: {}", chapter_id);
+    
+    let content: Option<String> = {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        conn.query_row("SELECT content FROM chapters WHERE id = ?1", [&chapter_id], |row| row.get(0))
+            .map_err(|e| format!("找不到章節: {}", e))?
+    };
+    
+    let plain_text = slate_to_plain_text(content.as_deref().unwrap_or("")).trim().to_string();
+    if plain_text.chars().count() < SUMMARY_MIN_CHARS {
+        log::info!("章節字數不足 {} 字，略過摘要", SUMMARY_MIN_CHARS);
+        return Ok(ChapterSummaryResult {
+            chapter_id,
+            summary: None,
+            cached: false,
+            skipped: true,
+            purity_score: None,
+        });
+    }
+    
+    let hash = content_hash(&plain_text);
+    {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        let metadata: Option<String> = conn
+            .query_row("SELECT metadata FROM chapters WHERE id = ?1", [&chapter_id], |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        if let Some(summary) = cached_summary(metadata.as_deref(), &hash) {
+            log::info!("章節內容未變更，沿用既有摘要");
+            return Ok(ChapterSummaryResult {
+                chapter_id,
+                summary: Some(summary),
+                cached: true,
+                skipped: false,
+                purity_score: None,
+            });
+        }
+    }
+    
+    let enforcer = LanguagePurityEnforcer::new();
+    let system_prompt = enforcer.generate_enhanced_system_prompt(
+        "你是專業的小說編輯，負責為章節撰寫簡潔的前情提要。",
+    );
+    let excerpt: String = plain_text.chars().take(SUMMARY_MAX_INPUT_CHARS).collect();
+    let prompt = format!(
+        "請用繁體中文為以下章節撰寫 150 字以內的摘要，涵蓋主要事件、登場角色與情節轉折，只輸出摘要內容：\n\n{}",
+        excerpt
+    );
+    
+    // 純度不足時附上修正指示重新生成；重試後仍未達標則不保存，避免汙染前情提要
+    let retry = PurityRetryOptions::default();
+    let generated = generate_until_pure(
+        |corrective: Option<String>| {
+            let prompt = match corrective {
+                Some(corrective) => format!("{}{}", prompt, corrective),
+                None => prompt.clone(),
+            };
+            let system_prompt = system_prompt.clone();
+            let provider_id = &provider_id;
+            async move {
+                let (summary, _model) = generate_with_provider(provider_id, system_prompt, prompt, 400).await?;
+                if summary.is_empty() {
+                    return Err("AI 未回傳摘要內容".to_string());
+                }
+                Ok(summary)
+            }
+        },
+        Some(&retry),
+    )
+    .await?;
+    if generated.purity_score < retry.min_score {
+        log::warn!("章節摘要語言純度不足: {:.2}（共生成 {} 次）", generated.purity_score, generated.attempts);
+        return Err(format!(
+            "章節摘要語言純度不足（{:.2}，門檻 {:.2}），已重新生成 {} 次仍未達標，未保存摘要",
+            generated.purity_score,
+            retry.min_score,
+            generated.attempts - 1
+        ));
+    }
+    let summary = generated.text;
+    
+    // 重新讀取 metadata 再寫入，避免覆蓋生成期間使用者儲存的筆記
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    let metadata: Option<String> = conn
+        .query_row("SELECT metadata FROM chapters WHERE id = ?1", [&chapter_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE chapters SET metadata = ?1 WHERE id = ?2",
+        rusqlite::params![
+            apply_summary_metadata(metadata.as_deref(), &summary, &hash, generated.purity_score),
+            chapter_id
+        ],
+    )
+    .map_err(|e| format!("保存章節摘要失敗: {}", e))?;
+    
+    log::info!("章節摘要已保存（{} 字）", summary.chars().count());
+    Ok(ChapterSummaryResult {
+        chapter_id,
+        summary: Some(summary),
+        cached: false,
+        skipped: false,
+        purity_score: Some(generated.purity_score),
+    })
+}
+
+/// AI 估計的角色人格特徵（Big Five 與情緒）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersonalityEstimate {
+    pub openness: f64,
+    pub conscientiousness: f64,
+    pub extraversion: f64,
+    pub agreeableness: f64,
+    pub neuroticism: f64,
+    pub emotional_tone: String,
+    pub emotional_intensity: f64,
+    pub confidence: f64,
+}
+
+/// 角色對話統計
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DialogueStats {
+    pub dialogue_count: usize,
+    pub avg_dialogue_length: f64,
+    /// 相異字數 / 總字數（不含標點與空白）
+    pub vocabulary_richness: f64,
+    /// 平均每句的子句數
+    pub sentence_complexity: f64,
+}
+
+/// 角色分析結果（對應 character_analysis 的一筆資料）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CharacterAnalysisResult {
+    pub id: String,
+    pub character_id: String,
+    pub chapter_id: String,
+    pub project_id: String,
+    pub stats: DialogueStats,
+    pub dialogue_samples: Vec<String>,
+    /// 未指定 AI 提供者、沒有對話或 AI 分析失敗時為 None
+    pub personality: Option<PersonalityEstimate>,
+    /// 章節內容未變更，沿用快取的分析結果
+    #[serde(default)]
+    pub cached: bool,
+}
+
+/// 從 AI 回應中取出第一個 JSON 物件（容許前後夾雜說明文字或 ``` 區塊）
+pub(crate) fn extract_json_object(text: &str) -> Option<Map<String, Value>> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    if end <= start {
+        return None;
+    }
+    match serde_json::from_str::<Value>(&text[start..=end]).ok()? {
+        Value::Object(map) => Some(map),
+        _ => None,
+    }
+}
+
+/// 讀取 0-1 之間的分數，超出範圍時截斷
+pub(crate) fn unit_score(map: &Map<String, Value>, key: &str) -> Option<f64> {
+    map.get(key).and_then(Value::as_f64).map(|value| value.clamp(0.0, 1.0))
+}
+
+/// 讀取分析快取，無法解析的舊資料視為未命中
+fn load_cached_analysis<T: DeserializeOwned>(
+    project_id: &str,
+    analysis_type: &str,
+    target_id: &str,
+    data_hash: &str,
+) -> Result<Option<T>, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    let key = analysis_cache::cache_key(project_id, analysis_type, target_id, data_hash);
+    let data = analysis_cache::lookup(&conn, &key).map_err(|e| format!("讀取分析快取失敗: {}", e))?;
+    Ok(data.and_then(|data| serde_json::from_str(&data).ok()))
+}
+
+/// 寫入分析快取；失敗只記錄警告，不影響分析結果
+fn store_cached_analysis<T: Serialize>(project_id: &str, analysis_type: &str, target_id: &str, data_hash: &str, result: &T) {
+    let stored = serde_json::to_string(result)
+        .map_err(|e| e.to_string())
+        .and_then(|data| {
+            let conn = get_db_conn().map_err(|e| e.to_string())?;
+            analysis_cache::store(&conn, project_id, analysis_type, target_id, data_hash, &data).map_err(|e| e.to_string())
+        });
+    if let Err(e) = stored {
+        log::warn!("寫入分析快取失敗: {}", e);
+    }
+}
+
+/// 擷取段落中的引號對話（「」『』“”""）
+fn quoted_segments(paragraph: &str) -> Vec<(usize, usize)> {
+    const QUOTES: &[(char, char)] = &[('「', '」'), ('『', '』'), ('“', '”'), ('"', '"')];
+    let mut segments = Vec::new();
+    let mut open: Option<(usize, char)> = None;
+    for (index, ch) in paragraph.char_indices() {
+        match open {
+            Some((start, close)) if ch == close => {
+                segments.push((start, index + ch.len_utf8()));
+                open = None;
+            }
+            Some(_) => {}
+            None => {
+                if let Some((_, close)) = QUOTES.iter().find(|(quote, _)| *quote == ch) {
+                    open = Some((index, *close));
+                }
+            }
+        }
+    }
+    segments
+}
+
+/// 擷取角色在章節中的對話
+///
+/// 以段落為單位：引號外的敘述提到角色名稱（或別名）時，該段落的引號內容視為角色的台詞。
+fn extract_character_dialogues(text: &str, names: &[String]) -> Vec<String> {
+    let mut dialogues = Vec::new();
+    for paragraph in text.lines() {
+        let segments = quoted_segments(paragraph);
+        if segments.is_empty() {
+            continue;
+        }
+        let mut narration = String::new();
+        let mut last = 0;
+        for (start, end) in &segments {
+            narration.push_str(&paragraph[last..*start]);
+            last = *end;
+        }
+        narration.push_str(&paragraph[last..]);
+        if !names.iter().any(|name| narration.contains(name.as_str())) {
+            continue;
+        }
+        for (start, end) in segments {
+            let mut quoted = paragraph[start..end].chars();
+            quoted.next();
+            quoted.next_back();
+            let inner = quoted.as_str().trim();
+            if !inner.is_empty() {
+                dialogues.push(inner.to_string());
+            }
+        }
+    }
+    dialogues
+}
+
+/// 計算對話統計
+fn compute_dialogue_stats(dialogues: &[String]) -> DialogueStats {
+    if dialogues.is_empty() {
+        return DialogueStats::default();
+    }
+    let mut total_chars = 0usize;
+    let mut distinct = HashSet::new();
+    let mut sentences = 0usize;
+    let mut clauses = 0usize;
+    for dialogue in dialogues {
+        for ch in dialogue.chars().filter(|ch| ch.is_alphanumeric()) {
+            total_chars += 1;
+            distinct.insert(ch);
+        }
+        for sentence in dialogue
+            .split(['。', '！', '？', '!', '?', '…'])
+            .filter(|sentence| !sentence.trim().is_empty())
+        {
+            sentences += 1;
+            clauses += sentence
+                .split(['，', '、', '；', ',', ';'])
+                .filter(|clause| !clause.trim().is_empty())
+                .count()
+                .max(1);
+        }
+    }
+    let length: usize = dialogues.iter().map(|dialogue| dialogue.chars().count()).sum();
+    DialogueStats {
+        dialogue_count: dialogues.len(),
+        avg_dialogue_length: length as f64 / dialogues.len() as f64,
+        vocabulary_richness: if total_chars == 0 { 0.0 } else { distinct.len() as f64 / total_chars as f64 },
+        sentence_complexity: if sentences == 0 { 0.0 } else { clauses as f64 / sentences as f64 },
+    }
+}
+
+/// 對話中出現最多次的雙字詞（粗略的 n-gram 語言模式）
+fn top_bigrams(dialogues: &[String], limit: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for dialogue in dialogues {
+        let chars: Vec<char> = dialogue.chars().collect();
+        for pair in chars.windows(2) {
+            if pair.iter().all(|ch| ch.is_alphanumeric()) {
+                *counts.entry(pair.iter().collect()).or_default() += 1;
+            }
+        }
+    }
+    let mut bigrams: Vec<(String, usize)> = counts.into_iter().filter(|(_, count)| *count > 1).collect();
+    bigrams.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    bigrams.truncate(limit);
+    bigrams
+}
+
+/// 解析 AI 回傳的人格估計
+fn parse_personality_estimate(text: &str) -> Option<PersonalityEstimate> {
+    let map = extract_json_object(text)?;
+    let emotional_tone = map
+        .get("emotional_tone")
+        .and_then(Value::as_str)
+        .map(str::to_lowercase)
+        .filter(|tone| matches!(tone.as_str(), "positive" | "negative" | "neutral" | "mixed"))
+        .unwrap_or_else(|| "neutral".to_string());
+    Some(PersonalityEstimate {
+        openness: unit_score(&map, "openness")?,
+        conscientiousness: unit_score(&map, "conscientiousness")?,
+        extraversion: unit_score(&map, "extraversion")?,
+        agreeableness: unit_score(&map, "agreeableness")?,
+        neuroticism: unit_score(&map, "neuroticism")?,
+        emotional_tone,
+        emotional_intensity: unit_score(&map, "emotional_intensity").unwrap_or(0.5),
+        confidence: unit_score(&map, "confidence").unwrap_or(0.5),
+    })
+}
+
+/// 請 AI 依對話估計角色的人格特徵
+async fn estimate_personality(provider_id: &str, name: &str, dialogues: &[String]) -> Result<PersonalityEstimate, String> {
+    let samples = dialogues
+        .iter()
+        .take(MAX_DIALOGUE_SAMPLES * 2)
+        .map(|dialogue| format!("- {}", dialogue))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = format!(
+        "以下是角色「{}」在章節中的台詞：\n{}\n\n請依台詞估計此角色的五大人格特徵，只輸出 JSON，格式為：\n\
+         {{\"openness\": 0-1, \"conscientiousness\": 0-1, \"extraversion\": 0-1, \"agreeableness\": 0-1, \"neuroticism\": 0-1, \
+         \"emotional_tone\": \"positive|negative|neutral|mixed\", \"emotional_intensity\": 0-1, \"confidence\": 0-1}}",
+        name, samples
+    );
+    let (text, _model) = generate_with_provider(
+        provider_id,
+        "你是小說角色分析專家，只輸出符合要求的 JSON。".to_string(),
+        prompt,
+        300,
+    )
+    .await?;
+    parse_personality_estimate(&text).ok_or_else(|| "無法解析 AI 回傳的人格分析".to_string())
+}
+
+/// 分析角色在章節中的對話與人格，結果寫入 character_analysis
+///
+/// 對話統計一定會計算；指定 provider_id 時另請 AI 估計 Big Five 人格與情緒，
+/// AI 失敗時保留預設值。同一角色與章節只保留最新一筆分析。
+#[command]
+pub async fn analyze_character(
+    character_id: String,
+    chapter_id: String,
+    provider_id: Option<String>,
+) -> Result<CharacterAnalysisResult, String> {
+    log::info!("分析角色 {} 於章節 {}", character_id, chapter_id);
+    
+    let (project_id, name, attributes, content) = {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        let (project_id, name, attributes): (String, String, Option<String>) = conn
+            .query_row(
+                "SELECT project_id, name, attributes FROM characters WHERE id = ?1",
+                [&character_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .map_err(|e| format!("找不到角色: {}", e))?;
+        let (chapter_project_id, content): (String, Option<String>) = conn
+            .query_row(
+                "SELECT project_id, content FROM chapters WHERE id = ?1",
+                [&chapter_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("找不到章節: {}", e))?;
+        if chapter_project_id != project_id {
+            return Err("角色與章節不屬於同一個專案".to_string());
+        }
+        (project_id, name, attributes, content)
+    };
+    
+    let plain_text = slate_to_plain_text(content.as_deref().unwrap_or(""));
+    let (full_name, mut names) = character_name_variants(&name, attributes.as_deref());
+    names.push(full_name.clone());
+    
+    // 分析結果取決於章節內容、角色名稱（含別名）與 AI 提供者
+    let target_id = format!("{}:{}", character_id, chapter_id);
+    let hash = content_hash(&format!("{}\n{}\n{}", provider_id.as_deref().unwrap_or(""), names.join("|"), plain_text));
+    if let Some(mut cached) = load_cached_analysis::<CharacterAnalysisResult>(&project_id, "character", &target_id, &hash)? {
+        log::info!("章節與角色資料未變更，沿用快取的角色分析");
+        cached.cached = true;
+        return Ok(cached);
+    }
+    
+    let dialogues = extract_character_dialogues(&plain_text, &names);
+    let stats = compute_dialogue_stats(&dialogues);
+    let dialogue_samples: Vec<String> = dialogues.iter().take(MAX_DIALOGUE_SAMPLES).cloned().collect();
+    
+    let personality = match provider_id.as_deref() {
+        Some(provider_id) if !dialogues.is_empty() => {
+            match estimate_personality(provider_id, &full_name, &dialogues).await {
+                Ok(estimate) => Some(estimate),
+                Err(e) => {
+                    log::warn!("角色人格 AI 分析失敗，僅保存對話統計: {}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+    
+    let question_count = dialogues.iter().filter(|dialogue| dialogue.contains(['？', '?'])).count();
+    let exclamation_count = dialogues.iter().filter(|dialogue| dialogue.contains(['！', '!'])).count();
+    let linguistic_patterns = serde_json::json!({
+        "top_bigrams": top_bigrams(&dialogues, 10),
+        "question_ratio": if dialogues.is_empty() { 0.0 } else { question_count as f64 / dialogues.len() as f64 },
+        "exclamation_ratio": if dialogues.is_empty() { 0.0 } else { exclamation_count as f64 / dialogues.len() as f64 },
+    });
+    
+    let id = Uuid::new_v4().to_string();
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM character_analysis WHERE character_id = ?1 AND chapter_id = ?2",
+        rusqlite::params![character_id, chapter_id],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO character_analysis (
+            id, character_id, chapter_id, project_id,
+            dialogue_samples, dialogue_count, avg_dialogue_length,
+            openness, conscientiousness, extraversion, agreeableness, neuroticism,
+            linguistic_patterns, vocabulary_richness, sentence_complexity,
+            emotional_tone, emotional_intensity, analysis_version, confidence_score
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)",
+        rusqlite::params![
+            id,
+            character_id,
+            chapter_id,
+            project_id,
+            serde_json::to_string(&dialogue_samples).map_err(|e| e.to_string())?,
+            stats.dialogue_count as i64,
+            stats.avg_dialogue_length,
+            personality.as_ref().map_or(0.5, |p| p.openness),
+            personality.as_ref().map_or(0.5, |p| p.conscientiousness),
+            personality.as_ref().map_or(0.5, |p| p.extraversion),
+            personality.as_ref().map_or(0.5, |p| p.agreeableness),
+            personality.as_ref().map_or(0.5, |p| p.neuroticism),
+            linguistic_patterns.to_string(),
+            stats.vocabulary_richness,
+            stats.sentence_complexity,
+            personality.as_ref().map(|p| p.emotional_tone.clone()),
+            personality.as_ref().map_or(0.5, |p| p.emotional_intensity),
+            CHARACTER_ANALYSIS_VERSION,
+            personality.as_ref().map_or(0.0, |p| p.confidence),
+        ],
+    )
+    .map_err(|e| format!("保存角色分析失敗: {}", e))?;
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    log::info!("角色分析完成: {} 句對話", stats.dialogue_count);
+    let result = CharacterAnalysisResult {
+        id,
+        character_id,
+        chapter_id,
+        project_id,
+        stats,
+        dialogue_samples,
+        personality,
+        cached: false,
+    };
+    store_cached_analysis(&result.project_id, "character", &target_id, &hash, &result);
+    Ok(result)
+}
+
+/// 章節文字節奏統計（plot_analysis 的確定性欄位）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlotTextStats {
+    pub dialogue_ratio: f64,
+    pub action_ratio: f64,
+    pub description_ratio: f64,
+    pub word_count: usize,
+    pub paragraph_count: usize,
+    pub sentence_count: usize,
+    /// 平均句長（可見字符數）
+    pub avg_sentence_length: f64,
+}
+
+/// AI 估計的劇情緊張度與節奏
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlotTensionEstimate {
+    pub tension_level: f64,
+    pub pacing_score: f64,
+    pub climax_detected: bool,
+    /// 衝突點：[{ "position": 0-1, "intensity": 0-1, "description": "..." }]
+    pub conflict_points: Vec<Value>,
+    pub summary: Option<String>,
+    pub confidence: f64,
+}
+
+/// 劇情分析結果（對應 plot_analysis 的一筆資料）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlotAnalysisResult {
+    pub id: String,
+    pub project_id: String,
+    pub chapter_id: String,
+    pub stats: PlotTextStats,
+    /// 未指定 AI 提供者或 AI 分析失敗時為 None
+    pub tension: Option<PlotTensionEstimate>,
+    pub ai_provider: Option<String>,
+    pub ai_model: Option<String>,
+    /// 章節內容未變更，沿用快取的分析結果
+    #[serde(default)]
+    pub cached: bool,
+}
+
+fn visible_chars(text: &str) -> usize {
+    text.chars().filter(|ch| !ch.is_whitespace()).count()
+}
+
+fn keyword_hits(text: &str, keywords: &[&str]) -> usize {
+    keywords.iter().map(|keyword| text.matches(keyword).count()).sum()
+}
+
+/// 計算章節的對話 / 動作 / 描寫比例與段落、句子統計
+///
+/// 比例以可見字符數計算：引號內為對話，其餘敘述依句子中動作動詞與環境詞彙的多寡歸類，
+/// 兩者皆無的敘述不計入動作或描寫。
+fn compute_plot_text_stats(text: &str) -> PlotTextStats {
+    let total = visible_chars(text);
+    if total == 0 {
+        return PlotTextStats::default();
+    }
+    
+    let mut dialogue_chars = 0;
+    let mut action_chars = 0;
+    let mut description_chars = 0;
+    let mut paragraph_count = 0;
+    let mut sentence_count = 0;
+    for paragraph in text.lines().filter(|line| !line.trim().is_empty()) {
+        paragraph_count += 1;
+        sentence_count += paragraph
+            .split(['。', '！', '？', '!', '?', '…'])
+            .filter(|sentence| sentence.chars().any(char::is_alphanumeric))
+            .count();
+        
+        let mut narration = String::new();
+        let mut last = 0;
+        for (start, end) in quoted_segments(paragraph) {
+            dialogue_chars += visible_chars(&paragraph[start..end]);
+            narration.push_str(&paragraph[last..start]);
+            narration.push('。');
+            last = end;
+        }
+        narration.push_str(&paragraph[last..]);
+        
+        for sentence in narration.split(['。', '！', '？', '!', '?', '；']) {
+            let actions = keyword_hits(sentence, ACTION_KEYWORDS);
+            let descriptions = keyword_hits(sentence, DESCRIPTION_KEYWORDS);
+            if actions == 0 && descriptions == 0 {
+                continue;
+            }
+            if actions >= descriptions {
+                action_chars += visible_chars(sentence);
+            } else {
+                description_chars += visible_chars(sentence);
+            }
+        }
+    }
+    
+    let sentence_count = sentence_count.max(1);
+    PlotTextStats {
+        dialogue_ratio: dialogue_chars as f64 / total as f64,
+        action_ratio: action_chars as f64 / total as f64,
+        description_ratio: description_chars as f64 / total as f64,
+        word_count: count_text(text).words,
+        paragraph_count,
+        sentence_count,
+        avg_sentence_length: total as f64 / sentence_count as f64,
+    }
+}
+
+/// 解析 AI 回傳的緊張度 / 節奏估計
+fn parse_plot_tension_estimate(text: &str) -> Option<PlotTensionEstimate> {
+    let map = extract_json_object(text)?;
+    let conflict_points = map
+        .get("conflict_points")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    Some(PlotTensionEstimate {
+        tension_level: unit_score(&map, "tension_level")?,
+        pacing_score: unit_score(&map, "pacing_score")?,
+        climax_detected: map.get("climax_detected").and_then(Value::as_bool).unwrap_or(false),
+        conflict_points,
+        summary: map
+            .get("summary")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|summary| !summary.is_empty())
+            .map(str::to_string),
+        confidence: unit_score(&map, "confidence").unwrap_or(0.5),
+    })
+}
+
+fn plot_analysis_prompt(excerpt: &str, stats: &PlotTextStats) -> String {
+    format!(
+        "請分析以下小說章節的劇情緊張度與節奏。參考統計：對話比例 {:.2}、動作比例 {:.2}、描寫比例 {:.2}、平均句長 {:.1} 字。\n\
+         只輸出 JSON，格式為：\n\
+         {{\"tension_level\": 0-1, \"pacing_score\": 0-1, \"climax_detected\": true|false, \
+         \"conflict_points\": [{{\"position\": 0-1, \"intensity\": 0-1, \"description\": \"衝突描述\"}}], \
+         \"summary\": \"一句話的節奏評語（繁體中文）\", \"confidence\": 0-1}}\n\n章節內容：\n{}",
+        stats.dialogue_ratio, stats.action_ratio, stats.description_ratio, stats.avg_sentence_length, excerpt
+    )
+}
+
+/// 分析章節的劇情節奏，結果寫入 plot_analysis
+///
+/// 對話 / 動作 / 描寫比例與字數統計一定會計算；指定 provider_id 時另請 AI 估計緊張度、
+/// 節奏與衝突點，並記錄使用的提供者與模型。同一章節只保留最新一筆分析。
+#[command]
+pub async fn analyze_chapter_plot(chapter_id: String, provider_id: Option<String>) -> Result<PlotAnalysisResult, String> {
+    log::info!("分析章節劇情節奏: {}", chapter_id);
+    let started = std::time::Instant::now();
+    
+    let (project_id, content): (String, Option<String>) = {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT project_id, content FROM chapters WHERE id = ?1",
+            [&chapter_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("找不到章節: {}", e))?
+    };
+    
+    let plain_text = slate_to_plain_text(content.as_deref().unwrap_or(""));
+    let hash = content_hash(&format!("{}\n{}", provider_id.as_deref().unwrap_or(""), plain_text));
+    if let Some(mut cached) = load_cached_analysis::<PlotAnalysisResult>(&project_id, "plot", &chapter_id, &hash)? {
+        log::info!("章節內容未變更，沿用快取的劇情分析");
+        cached.cached = true;
+        return Ok(cached);
+    }
+    let stats = compute_plot_text_stats(&plain_text);
+    
+    let mut tension = None;
+    let mut ai_model = None;
+    let mut analysis_prompt = None;
+    if let Some(provider_id) = provider_id.as_deref().filter(|_| stats.sentence_count > 0 && !plain_text.trim().is_empty()) {
+        let excerpt: String = plain_text.chars().take(SUMMARY_MAX_INPUT_CHARS).collect();
+        let prompt = plot_analysis_prompt(&excerpt, &stats);
+        match generate_with_provider(
+            provider_id,
+            "你是小說劇情結構分析專家，只輸出符合要求的 JSON。".to_string(),
+            prompt.clone(),
+            600,
+        )
+        .await
+        {
+            Ok((text, model)) => match parse_plot_tension_estimate(&text) {
+                Some(estimate) => {
+                    tension = Some(estimate);
+                    ai_model = Some(model);
+                    analysis_prompt = Some(prompt);
+                }
+                None => log::warn!("無法解析 AI 回傳的劇情分析，僅保存文字統計"),
+            },
+            Err(e) => log::warn!("劇情 AI 分析失敗，僅保存文字統計: {}", e),
+        }
+    }
+    let ai_provider = ai_model.as_ref().and(provider_id);
+    
+    let id = Uuid::new_v4().to_string();
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM plot_analysis WHERE chapter_id = ?1", [&chapter_id])
+        .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO plot_analysis (
+            id, project_id, chapter_id,
+            conflict_points, tension_level, climax_detected, pacing_score,
+            dialogue_ratio, action_ratio, description_ratio,
+            word_count, paragraph_count, sentence_count, avg_sentence_length,
+            ai_provider, ai_model, analysis_prompt, summary, confidence_score, processing_time
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
+        rusqlite::params![
+            id,
+            project_id,
+            chapter_id,
+            tension.as_ref().map(|t| Value::Array(t.conflict_points.clone()).to_string()),
+            tension.as_ref().map_or(0.5, |t| t.tension_level),
+            tension.as_ref().is_some_and(|t| t.climax_detected),
+            tension.as_ref().map_or(0.5, |t| t.pacing_score),
+            stats.dialogue_ratio,
+            stats.action_ratio,
+            stats.description_ratio,
+            stats.word_count as i64,
+            stats.paragraph_count as i64,
+            stats.sentence_count as i64,
+            stats.avg_sentence_length,
+            ai_provider,
+            ai_model,
+            analysis_prompt,
+            tension.as_ref().and_then(|t| t.summary.clone()),
+            tension.as_ref().map_or(0.0, |t| t.confidence),
+            started.elapsed().as_millis() as i64,
+        ],
+    )
+    .map_err(|e| format!("保存劇情分析失敗: {}", e))?;
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    log::info!(
+        "劇情分析完成: 對話 {:.0}% / 動作 {:.0}% / 描寫 {:.0}%",
+        stats.dialogue_ratio * 100.0,
+        stats.action_ratio * 100.0,
+        stats.description_ratio * 100.0
+    );
+    let result = PlotAnalysisResult {
+        id,
+        project_id,
+        chapter_id,
+        stats,
+        tension,
+        ai_provider,
+        ai_model,
+        cached: false,
+    };
+    store_cached_analysis(&result.project_id, "plot", &result.chapter_id, &hash, &result);
+    Ok(result)
+}
+
+/// 清除專案的分析快取，回傳刪除筆數
+#[command]
+pub async fn clear_analysis_cache(project_id: String) -> Result<usize, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    let removed = analysis_cache::clear_project(&conn, &project_id)
+        .map_err(|e| format!("清除分析快取失敗: {}", e))?;
+    log::info!("已清除專案 {} 的分析快取（{} 筆）", project_id, removed);
+    Ok(removed)
+}
+
+/// 分析佇列進度事件（analysis-queue-progress）
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisQueueEvent {
+    pub task_id: String,
+    pub project_id: String,
+    pub analysis_type: String,
+    pub target_id: Option<String>,
+    pub status: String,
+    pub progress: f64,
+    pub current_step: Option<String>,
+    pub result_id: Option<String>,
+    pub error_message: Option<String>,
+}
+
+/// 發送分析佇列進度事件（發送失敗不影響分析流程）
+fn emit_analysis_queue_event(app: &AppHandle, task: &AnalysisTask) {
+    let event = AnalysisQueueEvent {
+        task_id: task.id.clone(),
+        project_id: task.project_id.clone(),
+        analysis_type: task.analysis_type.clone(),
+        target_id: task.target_id.clone(),
+        status: task.status.clone(),
+        progress: task.progress,
+        current_step: task.current_step.clone(),
+        result_id: task.result_id.clone(),
+        error_message: task.error_message.clone(),
+    };
+    if let Err(e) = app.emit("analysis-queue-progress", event) {
+        log::warn!("發送分析佇列事件失敗: {}", e);
+    }
+}
+
+fn task_param<'a>(task: &'a AnalysisTask, key: &str) -> Option<&'a str> {
+    task.params.as_ref().and_then(|params| params.get(key)).and_then(Value::as_str)
+}
+
+/// 執行佇列中的分析任務，回傳結果 ID
+async fn execute_analysis_task(app: &AppHandle, task: &mut AnalysisTask) -> Result<String, String> {
+    let target_id = task.target_id.clone().ok_or("分析任務缺少目標 ID")?;
+    let provider_id = task_param(task, "provider_id").map(str::to_string);
+    
+    let step = match task.analysis_type.as_str() {
+        "character" => "分析角色對話與人格",
+        "plot" => "分析章節劇情節奏",
+        other => return Err(format!("不支援的分析類型: {}", other)),
+    };
+    {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        analysis_queue::update_progress(&conn, &task.id, 0.1, step).map_err(|e| e.to_string())?;
+    }
+    task.progress = 0.1;
+    task.current_step = Some(step.to_string());
+    emit_analysis_queue_event(app, task);
+    
+    match task.analysis_type.as_str() {
+        "character" => {
+            let chapter_id = task_param(task, "chapter_id").ok_or("角色分析任務缺少 chapter_id 參數")?;
+            analyze_character(target_id, chapter_id.to_string(), provider_id).await.map(|result| result.id)
+        }
+        _ => analyze_chapter_plot(target_id, provider_id).await.map(|result| result.id),
+    }
+}
+
+/// 處理一個分析任務並更新佇列狀態
+async fn run_analysis_task(app: &AppHandle, mut task: AnalysisTask) {
+    log::info!("[AnalysisQueue] 開始處理任務 {}（{}）", task.id, task.analysis_type);
+    emit_analysis_queue_event(app, &task);
+    
+    let outcome = execute_analysis_task(app, &mut task).await;
+    let updated = get_db_conn().map_err(|e| e.to_string()).and_then(|conn| {
+        match &outcome {
+            Ok(result_id) => analysis_queue::complete(&conn, &task.id, result_id),
+            Err(e) => analysis_queue::fail(&conn, &task.id, e),
+        }
+        .map_err(|e| e.to_string())
+    });
+    
+    match updated {
+        Ok(updated) => {
+            if let Err(e) = &outcome {
+                log::warn!(
+                    "[AnalysisQueue] 任務 {} 失敗（第 {}/{} 次）: {}",
+                    updated.id, updated.retry_count, updated.max_retries, e
+                );
+            }
+            emit_analysis_queue_event(app, &updated);
+        }
+        Err(e) => log::error!("[AnalysisQueue] 更新任務 {} 狀態失敗: {}", task.id, e),
+    }
+}
+
+/// 啟動背景分析工作者，依優先級逐一處理 analysis_queue 中的任務
+pub fn spawn_analysis_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        match get_db_conn().map_err(|e| e.to_string()).and_then(|conn| {
+            analysis_queue::requeue_interrupted(&conn).map_err(|e| e.to_string())
+        }) {
+            Ok(0) => {}
+            Ok(count) => log::info!("[AnalysisQueue] {} 個中斷的分析任務已放回佇列", count),
+            Err(e) => log::warn!("[AnalysisQueue] 重置中斷任務失敗: {}", e),
+        }
+        
+        loop {
+            let next = get_db_conn()
+                .map_err(|e| e.to_string())
+                .and_then(|conn| analysis_queue::claim_next(&conn).map_err(|e| e.to_string()));
+            match next {
+                Ok(Some(task)) => run_analysis_task(&app, task).await,
+                Ok(None) => {
+                    let _ = tokio::time::timeout(ANALYSIS_QUEUE_POLL_INTERVAL, ANALYSIS_QUEUE_NOTIFY.notified()).await;
+                }
+                Err(e) => {
+                    log::error!("[AnalysisQueue] 讀取分析佇列失敗: {}", e);
+                    tokio::time::sleep(ANALYSIS_QUEUE_POLL_INTERVAL).await;
+                }
+            }
+        }
+    });
+}
+
+/// 加入背景分析任務
+///
+/// - `character`：target_id 為角色 ID，params 需包含 `chapter_id`
+/// - `plot`：target_id 為章節 ID
+///
+/// params 可另外指定 `provider_id` 以啟用 AI 分析；priority 為 1-10（預設 5，10 最高）。
+#[command]
+pub async fn enqueue_analysis(
+    app: AppHandle,
+    project_id: String,
+    analysis_type: String,
+    target_id: String,
+    priority: Option<i64>,
+    params: Option<Value>,
+) -> Result<AnalysisTask, String> {
+    if !SUPPORTED_ANALYSIS_TYPES.contains(&analysis_type.as_str()) {
+        return Err(format!("不支援的分析類型: {}", analysis_type));
+    }
+    if analysis_type == "character"
+        && params.as_ref().and_then(|params| params.get("chapter_id")).and_then(Value::as_str).is_none()
+    {
+        return Err("角色分析需要在 params 指定 chapter_id".to_string());
+    }
+    
+    let task = {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        analysis_queue::enqueue(&conn, &project_id, &analysis_type, Some(&target_id), priority.unwrap_or(5), params.as_ref())
+            .map_err(|e| format!("加入分析佇列失敗: {}", e))?
+    };
+    log::info!("分析任務已加入佇列: {}（{} / {}）", task.id, analysis_type, target_id);
+    
+    emit_analysis_queue_event(&app, &task);
+    ANALYSIS_QUEUE_NOTIFY.notify_one();
+    Ok(task)
+}
+
+/// 取得專案的分析任務列表
+#[command]
+pub async fn get_analysis_queue(project_id: String) -> Result<Vec<AnalysisTask>, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    analysis_queue::list_project_tasks(&conn, &project_id).map_err(|e| format!("讀取分析佇列失敗: {}", e))
+}
+
+/// AI 產生的單一創意建議
+#[derive(Debug, Clone, PartialEq)]
+struct SuggestionDraft {
+    title: String,
+    content: String,
+    themes: Vec<String>,
+    relevance: f64,
+    creativity: f64,
+    quality: f64,
+}
+
+/// 創意建議（對應 creative_suggestions 的一筆資料）
+#[derive(Debug, Serialize)]
+pub struct CreativeSuggestion {
+    pub id: String,
+    pub project_id: String,
+    pub chapter_id: String,
+    pub target_type: String,
+    pub target_id: Option<String>,
+    pub suggestion_type: String,
+    pub title: String,
+    pub content: String,
+    pub themes: Vec<String>,
+    pub relevance_score: f64,
+    pub creativity_score: f64,
+    pub quality_score: f64,
+    pub ai_provider: String,
+    pub ai_model: String,
+    /// 與其他提供者建議的主題相似度（提供者 ID → 0-1）
+    pub votes: HashMap<String, f64>,
+    pub consensus_score: f64,
+    pub status: String,
+}
+
+/// 創意建議生成結果
+#[derive(Debug, Serialize)]
+pub struct CreativeSuggestionsResult {
+    pub suggestions: Vec<CreativeSuggestion>,
+    /// 生成失敗的提供者（提供者 ID → 錯誤訊息）
+    pub failures: HashMap<String, String>,
+}
+
+/// 解析 AI 回傳的建議；未提供主題時以內容的常見雙字詞代替
+fn parse_suggestion_draft(text: &str) -> Option<SuggestionDraft> {
+    let map = extract_json_object(text)?;
+    let field = |key: &str| {
+        map.get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let content = field("content")?;
+    let mut themes: Vec<String> = map
+        .get("themes")
+        .and_then(Value::as_array)
+        .map(|themes| {
+            themes
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|theme| theme.trim().to_lowercase())
+                .filter(|theme| !theme.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    if themes.is_empty() {
+        themes = top_bigrams(std::slice::from_ref(&content), 5).into_iter().map(|(bigram, _)| bigram).collect();
+    }
+    let mut seen = HashSet::new();
+    themes.retain(|theme| seen.insert(theme.clone()));
+    Some(SuggestionDraft {
+        title: field("title").unwrap_or_else(|| content.chars().take(20).collect()),
+        content,
+        themes,
+        relevance: unit_score(&map, "relevance").unwrap_or(0.5),
+        creativity: unit_score(&map, "creativity").unwrap_or(0.5),
+        quality: unit_score(&map, "quality").unwrap_or(0.5),
+    })
+}
+
+/// 主題集合的相似度（Jaccard）
+fn theme_similarity(a: &[String], b: &[String]) -> f64 {
+    let a: HashSet<&String> = a.iter().collect();
+    let b: HashSet<&String> = b.iter().collect();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// 計算各建議與其他提供者建議的主題相似度，共識分數為相似度平均值（單一提供者時為 0）
+fn compute_consensus(themes: &[(String, Vec<String>)]) -> Vec<(HashMap<String, f64>, f64)> {
+    themes
+        .iter()
+        .enumerate()
+        .map(|(index, (_, own))| {
+            let votes: HashMap<String, f64> = themes
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, (provider, other))| (provider.clone(), theme_similarity(own, other)))
+                .collect();
+            let consensus = if votes.is_empty() {
+                0.0
+            } else {
+                votes.values().sum::<f64>() / votes.len() as f64
+            };
+            (votes, consensus)
+        })
+        .collect()
+}
+
+/// 以多個 AI 提供者為章節產生創意建議，並依主題重疊程度計算共識分數
+///
+/// 每個提供者的建議各存成一筆 creative_suggestions；部分提供者失敗時仍保存其他結果。
+#[command]
+pub async fn generate_creative_suggestions(
+    chapter_id: String,
+    target_type: String,
+    suggestion_type: String,
+    provider_ids: Vec<String>,
+    target_id: Option<String>,
+) -> Result<CreativeSuggestionsResult, String> {
+    if !SUGGESTION_TARGET_TYPES.contains(&target_type.as_str()) {
+        return Err(format!("不支援的建議目標類型: {}", target_type));
+    }
+    let suggestion_goal = SUGGESTION_TYPES
+        .iter()
+        .find(|(kind, _)| *kind == suggestion_type)
+        .map(|(_, goal)| *goal)
+        .ok_or_else(|| format!("不支援的建議類型: {}", suggestion_type))?;
+    let mut seen = HashSet::new();
+    let mut provider_ids = provider_ids;
+    provider_ids.retain(|provider_id| seen.insert(provider_id.clone()));
+    if provider_ids.is_empty() {
+        return Err("請至少選擇一個 AI 提供者".to_string());
+    }
+    log::info!("產生創意建議: 章節 {}（{} 個提供者）", chapter_id, provider_ids.len());
+    
+    let (project_id, content): (String, Option<String>) = {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT project_id, content FROM chapters WHERE id = ?1",
+            [&chapter_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("找不到章節: {}", e))?
+    };
+    let plain_text = slate_to_plain_text(content.as_deref().unwrap_or(""));
+    let char_count = plain_text.chars().count();
+    let context: String = plain_text.chars().skip(char_count.saturating_sub(SUGGESTION_CONTEXT_CHARS)).collect();
+    
+    let enforcer = LanguagePurityEnforcer::new();
+    let system_prompt = enforcer.generate_enhanced_system_prompt("你是富有創意的小說寫作顧問，只輸出符合要求的 JSON。");
+    let prompt = format!(
+        "以下是小說章節的內容（結尾部分）：\n{}\n\n請針對「{}」提出一個{}的建議。只輸出 JSON，格式為：\n\
+         {{\"title\": \"建議標題\", \"content\": \"建議內容（200 字以內）\", \"themes\": [\"2-5 個主題關鍵詞\"], \
+         \"relevance\": 0-1, \"creativity\": 0-1, \"quality\": 0-1}}",
+        context, target_type, suggestion_goal
+    );
+    
+    let started = std::time::Instant::now();
+    let tasks: Vec<_> = provider_ids
+        .iter()
+        .cloned()
+        .map(|provider_id| {
+            let system_prompt = system_prompt.clone();
+            let prompt = prompt.clone();
+            tokio::spawn(async move {
+                let generated = generate_with_provider(&provider_id, system_prompt, prompt, 800).await;
+                (provider_id, generated)
+            })
+        })
+        .collect();
+    
+    let mut drafts = Vec::new();
+    let mut failures = HashMap::new();
+    for task in tasks {
+        let (provider_id, generated) = task.await.map_err(|e| format!("創意建議生成中斷: {}", e))?;
+        match generated.and_then(|(text, model)| {
+            parse_suggestion_draft(&text)
+                .map(|draft| (draft, model))
+                .ok_or_else(|| "無法解析 AI 回傳的建議".to_string())
+        }) {
+            Ok((draft, model)) => drafts.push((provider_id, model, draft)),
+            Err(e) => {
+                log::warn!("提供者 {} 產生創意建議失敗: {}", provider_id, e);
+                failures.insert(provider_id, e);
+            }
+        }
+    }
+    if drafts.is_empty() {
+        return Err(format!("所有 AI 提供者皆無法產生建議: {:?}", failures));
+    }
+    let processing_time = started.elapsed().as_millis() as i64;
+    
+    let themes: Vec<(String, Vec<String>)> = drafts
+        .iter()
+        .map(|(provider_id, _, draft)| (provider_id.clone(), draft.themes.clone()))
+        .collect();
+    let consensus = compute_consensus(&themes);
+    
+    let mut suggestions = Vec::with_capacity(drafts.len());
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for ((provider_id, model, draft), (votes, consensus_score)) in drafts.into_iter().zip(consensus) {
+        let suggestion = CreativeSuggestion {
+            id: Uuid::new_v4().to_string(),
+            project_id: project_id.clone(),
+            chapter_id: chapter_id.clone(),
+            target_type: target_type.clone(),
+            target_id: target_id.clone(),
+            suggestion_type: suggestion_type.clone(),
+            title: draft.title,
+            content: draft.content,
+            themes: draft.themes,
+            relevance_score: draft.relevance,
+            creativity_score: draft.creativity,
+            quality_score: draft.quality,
+            ai_provider: provider_id,
+            ai_model: model,
+            votes,
+            consensus_score,
+            status: "pending".to_string(),
+        };
+        tx.execute(
+            "INSERT INTO creative_suggestions (
+                id, project_id, chapter_id, target_type, target_id, suggestion_type,
+                title, content, context, relevance_score, creativity_score, quality_score,
+                ai_provider, ai_model, generation_params, prompt_used, status, votes, consensus_score, processing_time
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, 'pending', ?17, ?18, ?19)",
+            rusqlite::params![
+                suggestion.id,
+                suggestion.project_id,
+                suggestion.chapter_id,
+                suggestion.target_type,
+                suggestion.target_id,
+                suggestion.suggestion_type,
+                suggestion.title,
+                suggestion.content,
+                context,
+                suggestion.relevance_score,
+                suggestion.creativity_score,
+                suggestion.quality_score,
+                suggestion.ai_provider,
+                suggestion.ai_model,
+                serde_json::json!({ "themes": suggestion.themes, "temperature": 0.3, "max_tokens": 800 }).to_string(),
+                prompt,
+                serde_json::to_string(&suggestion.votes).map_err(|e| e.to_string())?,
+                suggestion.consensus_score,
+                processing_time,
+            ],
+        )
+        .map_err(|e| format!("保存創意建議失敗: {}", e))?;
+        suggestions.push(suggestion);
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    log::info!("已產生 {} 則創意建議（{} 個提供者失敗）", suggestions.len(), failures.len());
+    Ok(CreativeSuggestionsResult { suggestions, failures })
+}
+
+fn validate_user_rating(rating: Option<u8>) -> Result<(), String> {
+    match rating {
+        Some(rating) if !(1..=5).contains(&rating) => Err("評分必須介於 1 到 5 之間".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// 更新建議的使用者回饋，建議不存在時回傳錯誤
+fn update_suggestion_status(
+    conn: &rusqlite::Connection,
+    id: &str,
+    status: &str,
+    user_rating: Option<u8>,
+    feedback: Option<&str>,
+) -> Result<(), String> {
+    validate_user_rating(user_rating)?;
+    let updated = conn
+        .execute(
+            "UPDATE creative_suggestions
+             SET status = ?2,
+                 user_rating = COALESCE(?3, user_rating),
+                 user_feedback = COALESCE(?4, user_feedback),
+                 applied_at = CASE WHEN ?2 = 'accepted' THEN CURRENT_TIMESTAMP ELSE applied_at END,
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?1",
+            rusqlite::params![id, status, user_rating, feedback],
+        )
+        .map_err(|e| format!("更新創意建議失敗: {}", e))?;
+    if updated == 0 {
+        return Err(format!("找不到創意建議: {}", id));
+    }
+    Ok(())
+}
+
+/// 採用創意建議（可附 1-5 星評分）
+#[command]
+pub async fn accept_suggestion(id: String, user_rating: Option<u8>) -> Result<(), String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    update_suggestion_status(&conn, &id, "accepted", user_rating, None)?;
+    log::info!("已採用創意建議: {}", id);
+    Ok(())
+}
+
+/// 拒絕創意建議並記錄使用者回饋（可附 1-5 星評分）
+#[command]
+pub async fn reject_suggestion(id: String, feedback: Option<String>, user_rating: Option<u8>) -> Result<(), String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    update_suggestion_status(&conn, &id, "rejected", user_rating, feedback.as_deref())?;
+    log::info!("已拒絕創意建議: {}", id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    #[test]
+    fn test_summary_metadata_keeps_notes_and_caches_by_hash() {
+        let original = r#"{"notes":"伏筆：戒指"}"#;
+        let hash = content_hash("第一章內容");
+        assert_eq!(cached_summary(Some(original), &hash), None);
+        
+        let updated = apply_summary_metadata(Some(original), "主角得到戒指。", &hash, 1.0);
+        let metadata = parse_chapter_metadata(Some(&updated));
+        assert_eq!(metadata["notes"], "伏筆：戒指");
+        assert_eq!(cached_summary(Some(&updated), &hash).as_deref(), Some("主角得到戒指。"));
+        assert_eq!(cached_summary(Some(&updated), &content_hash("修改後的內容")), None);
+        
+        // 無效的 metadata 也能寫入
+        assert!(apply_summary_metadata(Some("not json"), "摘要", &hash, 0.9).contains("摘要"));
+    }
+    
+    #[test]
+    fn test_extract_character_dialogues_and_stats() {
+        let text = "艾莉絲笑著說：「今天天氣真好，我們去森林吧！」\n\
+                    「不行。」雷恩搖頭。\n\
+                    小艾皺眉：「為什麼？森林很安全。」\n\
+                    沒有引號的段落，艾莉絲在想事情。";
+        let names = vec!["艾莉絲".to_string(), "小艾".to_string()];
+        let dialogues = extract_character_dialogues(text, &names);
+        assert_eq!(dialogues, vec!["今天天氣真好，我們去森林吧！", "為什麼？森林很安全。"]);
+        
+        let stats = compute_dialogue_stats(&dialogues);
+        assert_eq!(stats.dialogue_count, 2);
+        assert!((stats.avg_dialogue_length - 12.0).abs() < f64::EPSILON);
+        assert!(stats.vocabulary_richness > 0.0 && stats.vocabulary_richness <= 1.0);
+        assert!((stats.sentence_complexity - 4.0 / 3.0).abs() < 1e-9);
+        assert_eq!(top_bigrams(&dialogues, 5), vec![("森林".to_string(), 2)]);
+        assert_eq!(compute_dialogue_stats(&[]), DialogueStats::default());
+    }
+    
+    #[test]
+    fn test_parse_personality_estimate() {
+        let text = "分析如下：\n```json\n{\"openness\": 0.8, \"conscientiousness\": 0.4, \"extraversion\": 1.3, \"agreeableness\": 0.7, \"neuroticism\": 0.2, \"emotional_tone\": \"Positive\"}\n```";
+        let estimate = parse_personality_estimate(text).unwrap();
+        assert_eq!(estimate.extraversion, 1.0);
+        assert_eq!(estimate.emotional_tone, "positive");
+        assert_eq!(estimate.confidence, 0.5);
+        assert!(parse_personality_estimate("{\"openness\": 0.8}").is_none());
+        assert!(parse_personality_estimate("沒有 JSON").is_none());
+    }
+    
+    #[test]
+    fn test_compute_plot_text_stats() {
+        let text = "夕陽灑在山上，天空染成橘色。\n\
+                    他拔出長劍，衝向敵人！\n\
+                    「快逃！」\n\
+                    \n\
+                    她點了點頭。";
+        let stats = compute_plot_text_stats(text);
+        assert_eq!(stats.paragraph_count, 4);
+        assert_eq!(stats.sentence_count, 4);
+        let total = visible_chars(text) as f64;
+        assert!((stats.dialogue_ratio - 5.0 / total).abs() < 1e-9);
+        assert!((stats.action_ratio - 10.0 / total).abs() < 1e-9);
+        assert!((stats.description_ratio - 13.0 / total).abs() < 1e-9);
+        assert!((stats.avg_sentence_length - total / 4.0).abs() < 1e-9);
+        assert_eq!(compute_plot_text_stats("  \n"), PlotTextStats::default());
+    }
+    
+    #[test]
+    fn test_parse_plot_tension_estimate() {
+        let estimate = parse_plot_tension_estimate(
+            r#"{"tension_level": 0.9, "pacing_score": 0.6, "climax_detected": true, "conflict_points": [{"position": 0.8, "intensity": 0.9, "description": "決鬥"}], "summary": "節奏緊湊"}"#,
+        )
+        .unwrap();
+        assert!(estimate.climax_detected);
+        assert_eq!(estimate.conflict_points.len(), 1);
+        assert_eq!(estimate.summary.as_deref(), Some("節奏緊湊"));
+        assert!(parse_plot_tension_estimate(r#"{"pacing_score": 0.6}"#).is_none());
+    }
+    
+    #[test]
+    fn test_suggestion_consensus_by_theme_overlap() {
+        let draft = parse_suggestion_draft(
+            r#"{"title": "背叛", "content": "導師其實是敵方間諜。", "themes": ["背叛", "導師", "間諜"], "quality": 0.8}"#,
+        )
+        .unwrap();
+        assert_eq!(draft.themes, vec!["背叛", "導師", "間諜"]);
+        assert_eq!(draft.quality, 0.8);
+        assert!(parse_suggestion_draft(r#"{"title": "沒有內容"}"#).is_none());
+        
+        let themes = vec![
+            ("a".to_string(), vec!["背叛".to_string(), "導師".to_string()]),
+            ("b".to_string(), vec!["背叛".to_string(), "間諜".to_string()]),
+            ("c".to_string(), vec!["戀愛".to_string()]),
+        ];
+        let consensus = compute_consensus(&themes);
+        assert!((consensus[0].0["b"] - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(consensus[0].0["c"], 0.0);
+        assert!((consensus[0].1 - 1.0 / 6.0).abs() < 1e-9);
+        assert_eq!(consensus[2].1, 0.0);
+        assert_eq!(compute_consensus(&themes[..1])[0].1, 0.0);
+    }
+    
+    #[test]
+    fn test_update_suggestion_status() {
+        let conn = migrated_conn_with_project();
+        conn.execute(
+            "INSERT INTO creative_suggestions (id, project_id, target_type, suggestion_type, title, content, ai_provider, ai_model)
+             VALUES ('s1', 'p1', 'plot', 'conflict', '標題', '內容', 'prov', 'model')",
+            [],
+        )
+        .unwrap();
+        
+        update_suggestion_status(&conn, "s1", "rejected", Some(2), Some("太突兀")).unwrap();
+        let (status, rating, feedback): (String, i64, String) = conn
+            .query_row("SELECT status, user_rating, user_feedback FROM creative_suggestions WHERE id = 's1'", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((status.as_str(), rating, feedback.as_str()), ("rejected", 2, "太突兀"));
+        
+        assert!(update_suggestion_status(&conn, "s1", "accepted", Some(6), None).is_err());
+        assert!(update_suggestion_status(&conn, "missing", "accepted", None, None).is_err());
+    }
+}
@@ -2,9 +2,9 @@ use serde_json::Value;
 use crate::services::illustration::{
     BatchManager, BatchRequest, 
     TaskPriority, EnhancedIllustrationRequest, IllustrationRequest,
-    IllustrationManager
+    IllustrationManager, SeedManager, MAX_BATCH_SEEDS, DEFAULT_DIVERSE_SEED_SPACING
 };
-use crate::database::connection::create_connection;
+use crate::database::get_db;
 use std::sync::{Arc, Mutex};
 
 // 全局批次管理器實例（簡化實現）
@@ -17,8 +17,7 @@ lazy_static::lazy_static! {
 pub async fn initialize_batch_manager() -> Result<Value, String> {
     log::info!("[BatchCommand] 初始化批次管理器");
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     // 創建插畫管理器
     let illustration_manager = IllustrationManager::new(db_arc)
@@ -44,9 +43,58 @@ pub async fn initialize_batch_manager() -> Result<Value, String> {
     }))
 }
 
+/// 為批次中的每個請求分配 seed
+///
+/// `seed_mode`：`"diverse"` 在 u32 空間中分散（變體明顯不同），`"clustered"` 使用批次變體序列
+/// （延續基礎 seed 的一致性）。未指定時維持各請求原本的角色 seed。
+fn assign_batch_seeds(
+    requests: &mut [EnhancedIllustrationRequest],
+    seed_mode: Option<&str>,
+    base_seed: Option<u32>,
+    min_spacing: Option<u32>,
+) -> Result<Option<Vec<u32>>, String> {
+    let Some(seed_mode) = seed_mode else {
+        return Ok(None);
+    };
+    if requests.len() > MAX_BATCH_SEEDS as usize {
+        return Err(format!("指定 seed 模式時，批次請求數量不能超過 {}", MAX_BATCH_SEEDS));
+    }
+
+    // 未提供基礎 seed 時沿用第一個角色的一致性 seed
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let seed_manager = SeedManager::new(db_arc);
+    let base_seed = match base_seed {
+        Some(seed) => seed,
+        None => {
+            let character_id = requests.iter()
+                .find_map(|request| request.basic_request.character_id.clone())
+                .ok_or("指定 seed 模式時需提供 baseSeed 或包含角色的請求")?;
+            seed_manager.get_or_create_seed(&character_id, "Character")
+                .map_err(|e| format!("取得角色 seed 失敗: {:?}", e))?
+        }
+    };
+
+    let count = requests.len() as u32;
+    let seeds = match seed_mode {
+        "diverse" => seed_manager.generate_diverse_seeds(base_seed, count, min_spacing.unwrap_or(DEFAULT_DIVERSE_SEED_SPACING)),
+        "clustered" => seed_manager.generate_batch_seeds(base_seed, count),
+        other => return Err(format!("不支援的 seed 模式: {}（支援 diverse、clustered）", other)),
+    };
+
+    for (request, seed) in requests.iter_mut().zip(&seeds) {
+        request.basic_request.seed = Some(*seed);
+    }
+    log::info!("[BatchCommand] 已分配 {} 個 {} seed（基礎值: {}）", seeds.len(), seed_mode, base_seed);
+    Ok(Some(seeds))
+}
+
 /// 提交批次插畫生成請求
+///
+/// `seedMode` 為 `"diverse"` 時各變體的 seed 至少相距 `minSeedSpacing`，適合追求多樣性；
+/// `"clustered"` 適合追求一致性。兩者皆可由 `baseSeed` 重現。
 #[tauri::command]
 #[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
 pub async fn submit_batch_illustration_request(
     batchName: String,
     projectId: String,
@@ -54,6 +102,9 @@ pub async fn submit_batch_illustration_request(
     priority: Option<String>,
     maxParallel: Option<u32>,
     _apiKey: Option<String>,
+    seedMode: Option<String>,
+    baseSeed: Option<u32>,
+    minSeedSpacing: Option<u32>,
 ) -> Result<Value, String> {
     log::info!("[BatchCommand] 提交批次插畫請求，批次名稱: {}，請求數量: {}", 
                batchName, requests.len());
@@ -91,6 +142,8 @@ pub async fn submit_batch_illustration_request(
         enhanced_requests.push(enhanced_request);
     }
     
+    let assigned_seeds = assign_batch_seeds(&mut enhanced_requests, seedMode.as_deref(), baseSeed, minSeedSpacing)?;
+    
     // 構建批次請求
     let total_tasks = enhanced_requests.len();
     let _batch_request = BatchRequest {
@@ -141,7 +194,8 @@ pub async fn submit_batch_illustration_request(
                 "success": true,
                 "batch_id": batch_id,
                 "message": "批次插畫生成請求提交成功",
-                "total_tasks": total_tasks
+                "total_tasks": total_tasks,
+                "seeds": assigned_seeds
             }))
         },
         Err(e) => {
@@ -0,0 +1,290 @@
+use crate::commands::epub::generate_epub;
+use crate::commands::illustration::query_project_illustrations;
+use crate::commands::pdf_chrome::generate_pdf_chrome;
+use crate::database::get_db_conn;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle};
+use tempfile::NamedTempFile;
+use zip::{CompressionMethod, ZipWriter};
+
+/// 作品包支援的導出格式
+pub const BUNDLE_FORMATS: &[&str] = &["epub", "pdf"];
+
+/// 作品包清單中的一個檔案
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifestEntry {
+    pub kind: String, // "epub", "pdf", "illustration"
+    pub path: String, // 在壓縮檔中的路徑
+    pub size: u64,
+}
+
+/// 作品包清單（壓縮檔內的 manifest.json）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub project_id: String,
+    pub project_name: String,
+    pub created_at: String,
+    pub app_version: String,
+    pub formats: Vec<String>,
+    pub files: Vec<BundleManifestEntry>,
+}
+
+/// 作品包導出結果
+#[derive(Debug, Serialize)]
+pub struct ProjectBundleResult {
+    pub id: String,
+    pub file_path: String,
+    pub file_size: u64,
+    pub illustration_count: usize,
+    pub manifest: BundleManifest,
+}
+
+/// 驗證並整理導出格式（轉小寫、去除重複，保留請求順序）
+fn normalize_bundle_formats(formats: &[String]) -> Result<Vec<String>, String> {
+    let mut seen = HashSet::new();
+    let mut normalized = Vec::new();
+    for format in formats {
+        let format = format.trim().to_lowercase();
+        if !BUNDLE_FORMATS.contains(&format.as_str()) {
+            return Err(format!("不支援的導出格式: {}（可用: {}）", format, BUNDLE_FORMATS.join(", ")));
+        }
+        if seen.insert(format.clone()) {
+            normalized.push(format);
+        }
+    }
+    if normalized.is_empty() {
+        return Err("至少需要選擇一種導出格式".to_string());
+    }
+    Ok(normalized)
+}
+
+/// 收集專案插畫（與 PDF、EPUB 導出使用相同的查詢）
+fn collect_bundle_illustrations(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<PathBuf>, String> {
+    Ok(query_project_illustrations(conn, project_id)?
+        .into_iter()
+        .map(|illustration| illustration.file_path)
+        .collect())
+}
+
+/// 為插畫決定壓縮檔內的唯一路徑
+fn illustration_archive_path(path: &Path, used: &mut HashSet<String>) -> String {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "illustration".to_string());
+    let mut candidate = format!("illustrations/{}", file_name);
+    let mut suffix = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("illustrations/{}_{}", suffix, file_name);
+        suffix += 1;
+    }
+    candidate
+}
+
+/// 將檔案與清單寫入作品包壓縮檔，回傳壓縮檔大小
+///
+/// `files` 為 (種類, 壓縮檔內路徑, 來源檔案)；先寫入暫存檔，完成後才複製到目標位置。
+fn write_bundle_archive(
+    output_path: &Path,
+    files: &[(String, String, PathBuf)],
+    manifest: &mut BundleManifest,
+) -> Result<u64, String> {
+    let temp_file = NamedTempFile::new().map_err(|e| format!("創建臨時文件失敗: {}", e))?;
+    let mut zip = ZipWriter::new(temp_file.as_file());
+    let zip_options = zip::write::FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for (kind, archive_path, source) in files {
+        let data = std::fs::read(source)
+            .map_err(|e| format!("讀取檔案失敗 {}: {}", source.display(), e))?;
+        zip.start_file(archive_path.as_str(), zip_options)
+            .map_err(|e| format!("建立壓縮檔項目失敗 {}: {}", archive_path, e))?;
+        zip.write_all(&data)
+            .map_err(|e| format!("寫入壓縮檔項目失敗 {}: {}", archive_path, e))?;
+        manifest.files.push(BundleManifestEntry {
+            kind: kind.clone(),
+            path: archive_path.clone(),
+            size: data.len() as u64,
+        });
+    }
+
+    let manifest_json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("序列化清單失敗: {}", e))?;
+    zip.start_file("manifest.json", zip_options)
+        .map_err(|e| format!("建立清單檔案失敗: {}", e))?;
+    zip.write_all(manifest_json.as_bytes())
+        .map_err(|e| format!("寫入清單檔案失敗: {}", e))?;
+    zip.finish().map_err(|e| format!("完成作品包失敗: {}", e))?;
+
+    std::fs::copy(temp_file.path(), output_path)
+        .map_err(|e| format!("複製作品包到目標位置失敗: {}", e))
+}
+
+/// 保存作品包導出記錄
+fn save_bundle_record(conn: &rusqlite::Connection, result: &ProjectBundleResult) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO project_bundles (
+            id, project_id, file_path, file_size, formats, illustration_count, manifest, created_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![
+            result.id,
+            result.manifest.project_id,
+            result.file_path,
+            result.file_size as i64,
+            serde_json::to_string(&result.manifest.formats).unwrap_or_default(),
+            result.illustration_count as i64,
+            serde_json::to_string(&result.manifest).unwrap_or_default(),
+            result.manifest.created_at,
+        ],
+    )
+    .map_err(|e| format!("保存作品包記錄失敗: {}", e))?;
+    Ok(())
+}
+
+/// 導出作品包：依指定格式生成 EPUB/PDF，連同專案插畫與 manifest.json 打包成單一 ZIP
+#[command]
+pub async fn export_project_bundle(
+    app: AppHandle,
+    project_id: String,
+    formats: Vec<String>,
+    output_path: String,
+) -> Result<ProjectBundleResult, String> {
+    let formats = normalize_bundle_formats(&formats)?;
+    let output_path = PathBuf::from(output_path);
+    log::info!("開始導出作品包，專案: {}，格式: {:?}", project_id, formats);
+
+    let (project_name, illustrations) = {
+        let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+        let project_name: String = conn
+            .query_row("SELECT name FROM projects WHERE id = ?1", [&project_id], |row| row.get(0))
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => "專案不存在".to_string(),
+                e => format!("獲取專案失敗: {}", e),
+            })?;
+        (project_name, collect_bundle_illustrations(&conn, &project_id)?)
+    };
+
+    let mut files = Vec::new();
+    for format in &formats {
+        let generated = match format.as_str() {
+            "epub" => generate_epub(project_id.clone(), None).await?.file_path,
+            "pdf" => generate_pdf_chrome(app.clone(), project_id.clone(), None)
+                .await?
+                .file_path
+                .ok_or("PDF 生成未回傳檔案路徑")?,
+            _ => unreachable!("格式已在 normalize_bundle_formats 驗證"),
+        };
+        let generated = PathBuf::from(generated);
+        let file_name = generated
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| format!("{}.{}", project_name, format));
+        files.push((format.clone(), file_name, generated));
+    }
+
+    let mut used_paths = HashSet::new();
+    for illustration in &illustrations {
+        let archive_path = illustration_archive_path(illustration, &mut used_paths);
+        files.push(("illustration".to_string(), archive_path, illustration.clone()));
+    }
+
+    let mut manifest = BundleManifest {
+        project_id: project_id.clone(),
+        project_name,
+        created_at: Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        formats,
+        files: Vec::new(),
+    };
+    let file_size = write_bundle_archive(&output_path, &files, &mut manifest)?;
+
+    let result = ProjectBundleResult {
+        id: uuid::Uuid::new_v4().to_string(),
+        file_path: output_path.to_string_lossy().to_string(),
+        file_size,
+        illustration_count: illustrations.len(),
+        manifest,
+    };
+
+    match get_db_conn() {
+        Ok(conn) => {
+            if let Err(e) = save_bundle_record(&conn, &result) {
+                log::warn!("{}", e);
+            }
+        }
+        Err(e) => log::warn!("資料庫連接失敗，未記錄作品包導出: {}", e),
+    }
+
+    log::info!("作品包導出完成: {} ({} bytes)", result.file_path, result.file_size);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+    use std::io::Read;
+
+    #[test]
+    fn test_bundle_formats_are_validated() {
+        let formats = vec!["PDF".to_string(), "epub".to_string(), "pdf".to_string()];
+        assert_eq!(normalize_bundle_formats(&formats).unwrap(), vec!["pdf", "epub"]);
+        assert!(normalize_bundle_formats(&["docx".to_string()]).is_err());
+        assert!(normalize_bundle_formats(&[]).is_err());
+    }
+
+    #[test]
+    fn test_bundle_archive_contains_files_and_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = migrated_conn_with_project();
+
+        let epub = dir.path().join("星之書.epub");
+        std::fs::write(&epub, b"epub-data").unwrap();
+        let mut used = HashSet::new();
+        let first = illustration_archive_path(Path::new("/a/cover.png"), &mut used);
+        let second = illustration_archive_path(Path::new("/b/cover.png"), &mut used);
+        assert_eq!((first.as_str(), second.as_str()), ("illustrations/cover.png", "illustrations/2_cover.png"));
+        let files = vec![
+            ("epub".to_string(), "星之書.epub".to_string(), epub.clone()),
+            ("illustration".to_string(), first, epub.clone()),
+            ("illustration".to_string(), second, epub),
+        ];
+
+        let mut manifest = BundleManifest {
+            project_id: "p1".to_string(),
+            project_name: "星之書".to_string(),
+            created_at: Utc::now().to_rfc3339(),
+            app_version: "test".to_string(),
+            formats: vec!["epub".to_string()],
+            files: Vec::new(),
+        };
+        let output = dir.path().join("bundle.zip");
+        let file_size = write_bundle_archive(&output, &files, &mut manifest).unwrap();
+        assert_eq!(file_size, std::fs::metadata(&output).unwrap().len());
+        assert_eq!(manifest.files.len(), 3);
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&output).unwrap()).unwrap();
+        assert_eq!(archive.len(), 4);
+        let mut manifest_json = String::new();
+        archive.by_name("manifest.json").unwrap().read_to_string(&mut manifest_json).unwrap();
+        let parsed: BundleManifest = serde_json::from_str(&manifest_json).unwrap();
+        assert_eq!(parsed.files[0].path, "星之書.epub");
+        assert_eq!(parsed.files[0].size, 9);
+
+        let result = ProjectBundleResult {
+            id: "b1".to_string(),
+            file_path: output.to_string_lossy().to_string(),
+            file_size,
+            illustration_count: 2,
+            manifest,
+        };
+        save_bundle_record(&conn, &result).unwrap();
+        let (formats, count): (String, i64) = conn
+            .query_row("SELECT formats, illustration_count FROM project_bundles WHERE id = 'b1'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((formats.as_str(), count), (r#"["epub"]"#, 2));
+    }
+}
@@ -1,13 +1,79 @@
-use crate::database::{get_db, models::*};
+use crate::database::{get_db_conn, models::*};
+use crate::commands::settings::{read_project_setting, COUNT_PROLOGUE_AS_ZERO_KEY};
 use anyhow::Result;
-use chrono::Utc;
+use crate::utils::slate::{parse_slate_document, slate_to_plain_text};
+use chrono::{DateTime, Utc};
 use rusqlite::params;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// 每個章節保留的歷史版本數上限
+const MAX_CHAPTER_VERSIONS: i64 = 50;
+
+/// 章節歷史版本
+#[derive(Debug, Serialize)]
+pub struct ChapterVersion {
+    pub id: String,
+    pub chapter_id: String,
+    pub title: String,
+    pub content: Option<String>,
+    pub plain_text: String,
+    pub metadata: Option<String>,
+    pub source: String,
+    pub created_at: String,
+}
+
+/// 尋找與取代的比對預覽前後保留的字數
+const FIND_REPLACE_PREVIEW_CHARS: usize = 15;
+
+/// 視為序章的章節標題關鍵字
+const PROLOGUE_TITLE_KEYWORDS: &[&str] = &["序章", "序幕", "楔子", "引子", "prologue"];
+
+/// 章節編號變更
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ChapterNumberChange {
+    pub chapter_id: String,
+    pub title: String,
+    pub old_number: Option<i32>,
+    pub new_number: i32,
+}
+
+/// 全專案尋找與取代的選項
+#[derive(Debug, Default, Deserialize)]
+pub struct FindReplaceOptions {
+    /// 區分大小寫（預設不區分）
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// 全字比對：英數字組成的搜尋詞不會匹配到較長單字的一部分（中文不受影響）
+    #[serde(default)]
+    pub whole_word: bool,
+    /// 只回傳比對結果，不修改章節
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// 單一章節的取代結果
+#[derive(Debug, Serialize)]
+pub struct ChapterReplacement {
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub replacements: usize,
+    /// 比對位置的前後文預覽
+    pub previews: Vec<String>,
+}
+
+/// 全專案尋找與取代結果
+#[derive(Debug, Serialize)]
+pub struct FindReplaceResult {
+    pub dry_run: bool,
+    pub total_replacements: usize,
+    /// 只列出有比對到的章節（依章節順序）
+    pub chapters: Vec<ChapterReplacement>,
+}
+
 #[tauri::command]
 pub async fn get_chapters_by_project_id(project_id: String) -> Result<Vec<Chapter>, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn
         .prepare("SELECT id, project_id, title, content, order_index, chapter_number, metadata, created_at, updated_at 
@@ -40,8 +106,7 @@ pub async fn get_chapters_by_project_id(project_id: String) -> Result<Vec<Chapte
 
 #[tauri::command]
 pub async fn get_chapter_by_id(id: String) -> Result<Chapter, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn
         .prepare("SELECT id, project_id, title, content, order_index, chapter_number, metadata, created_at, updated_at 
@@ -69,8 +134,7 @@ pub async fn get_chapter_by_id(id: String) -> Result<Chapter, String> {
 
 #[tauri::command]
 pub async fn create_chapter(chapter: CreateChapterRequest) -> Result<String, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let chapter_id = Uuid::new_v4().to_string();
     let now = Utc::now();
@@ -118,20 +182,57 @@ pub async fn create_chapter(chapter: CreateChapterRequest) -> Result<String, Str
     Ok(chapter_id)
 }
 
+/// 更新章節，回傳新的 updated_at
+///
+/// 提供 `expected_updated_at` 時會檢查資料庫中的版本：若已被其他寫入（例如 AI 生成或另一次自動儲存）
+/// 更新過，回傳以 `Conflict:` 開頭的錯誤而不覆寫，由呼叫端重新載入後協調。
 #[tauri::command]
-pub async fn update_chapter(chapter: UpdateChapterRequest) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+pub async fn update_chapter(chapter: UpdateChapterRequest) -> Result<DateTime<Utc>, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let chapter_id = chapter.id.clone();
+    let (project_id, updated_at) = update_chapter_record(&mut conn, chapter)?;
+    
+    log::info!("更新章節成功: ID {} (專案 ID: {})", chapter_id, project_id);
+    Ok(updated_at)
+}
+
+/// 在單一交易中檢查版本並更新章節，回傳 (專案 ID, 新的 updated_at)
+fn update_chapter_record(
+    conn: &mut rusqlite::Connection,
+    chapter: UpdateChapterRequest,
+) -> Result<(String, DateTime<Utc>), String> {
+    // IMMEDIATE 交易先取得寫入鎖，避免檢查版本與寫入之間被其他連線插入更新
+    let tx = conn
+        .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
     
     let now = Utc::now();
     
     // 先獲取章節的 project_id，用於後續更新父專案的時間戳
-    let project_id: String = conn
-        .prepare("SELECT project_id FROM chapters WHERE id = ?1")
+    let (project_id, current_content, stored_updated_at): (String, Option<String>, Option<DateTime<Utc>>) = tx
+        .prepare("SELECT project_id, content, updated_at FROM chapters WHERE id = ?1")
         .map_err(|e| e.to_string())?
-        .query_row([&chapter.id], |row| row.get(0))
+        .query_row([&chapter.id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2).ok())))
         .map_err(|e| format!("章節不存在: {}", e))?;
     
+    // 以毫秒比較，前端的 Date 只保留到毫秒
+    if let (Some(expected), Some(stored)) = (chapter.expected_updated_at, stored_updated_at) {
+        if stored.timestamp_millis() > expected.timestamp_millis() {
+            log::warn!("拒絕過期的章節寫入: ID {}（預期 {}，目前 {}）", chapter.id, expected, stored);
+            return Err(format!(
+                "Conflict: 章節已在 {} 被其他操作更新，請重新載入後再儲存",
+                stored.to_rfc3339()
+            ));
+        }
+    }
+    
+    // 內容有變更時先保存目前版本，供誤覆寫後復原
+    if chapter.content.is_some() && chapter.content != current_content {
+        snapshot_chapter_version(&tx, &chapter.id, "update")
+            .map_err(|e| format!("保存章節版本失敗: {}", e))?;
+    }
+    
     // 構建更新語句，只更新有提供的欄位
     let mut sql = "UPDATE chapters SET title = ?1, updated_at = ?2".to_string();
     let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![
@@ -167,7 +268,7 @@ pub async fn update_chapter(chapter: UpdateChapterRequest) -> Result<(), String>
     sql.push_str(&(params.len() + 1).to_string());
     params.push(Box::new(chapter.id.clone()));
     
-    let rows_affected = conn
+    let rows_affected = tx
         .execute(&sql, rusqlite::params_from_iter(params))
         .map_err(|e| format!("更新章節失敗: {}", e))?;
     
@@ -176,19 +277,18 @@ pub async fn update_chapter(chapter: UpdateChapterRequest) -> Result<(), String>
     }
     
     // 同時更新父專案的 updated_at 時間戳，以反映專案內容的實際更新時間
-    conn.execute(
+    tx.execute(
         "UPDATE projects SET updated_at = ?1 WHERE id = ?2",
         params![now, project_id],
     ).map_err(|e| format!("更新專案時間戳失敗: {}", e))?;
     
-    log::info!("更新章節成功: ID {} (專案 ID: {})", chapter.id, project_id);
-    Ok(())
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok((project_id, now))
 }
 
 #[tauri::command]
 pub async fn delete_chapter(id: String) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let rows_affected = conn
         .execute("DELETE FROM chapters WHERE id = ?1", [&id])
@@ -200,4 +300,557 @@ pub async fn delete_chapter(id: String) -> Result<(), String> {
     
     log::info!("刪除章節成功: ID {}", id);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 列出章節的歷史版本（由新到舊）
+#[tauri::command]
+pub async fn list_chapter_versions(chapter_id: String) -> Result<Vec<ChapterVersion>, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let mut stmt = conn
+        .prepare("SELECT id, chapter_id, title, content, plain_text, metadata, source, created_at
+                  FROM chapter_versions WHERE chapter_id = ?1 ORDER BY created_at DESC, rowid DESC")
+        .map_err(|e| e.to_string())?;
+    
+    let version_iter = stmt
+        .query_map([chapter_id], |row| {
+            Ok(ChapterVersion {
+                id: row.get(0)?,
+                chapter_id: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                plain_text: row.get(4)?,
+                metadata: row.get(5)?,
+                source: row.get(6)?,
+                created_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    
+    let mut versions = Vec::new();
+    for version in version_iter {
+        versions.push(version.map_err(|e| e.to_string())?);
+    }
+    
+    Ok(versions)
+}
+
+/// 將章節還原到指定的歷史版本
+///
+/// 還原前會先把目前內容保存為新版本，因此還原本身也可以再復原。
+#[tauri::command]
+pub async fn restore_chapter_version(version_id: String) -> Result<(), String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let chapter_id = restore_chapter_version_record(&mut conn, &version_id)?;
+    
+    log::info!("還原章節版本成功: 章節 ID {} (版本 ID: {})", chapter_id, version_id);
+    Ok(())
+}
+
+/// 保存章節目前的標題、內容與元數據為新版本，並清除超出上限的舊版本
+pub(crate) fn snapshot_chapter_version(conn: &rusqlite::Connection, chapter_id: &str, source: &str) -> rusqlite::Result<()> {
+    let (title, content, metadata): (String, Option<String>, Option<String>) = conn.query_row(
+        "SELECT title, content, metadata FROM chapters WHERE id = ?1",
+        [chapter_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let plain_text = slate_to_plain_text(content.as_deref().unwrap_or(""));
+    
+    conn.execute(
+        "INSERT INTO chapter_versions (id, chapter_id, title, content, plain_text, metadata, source, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![Uuid::new_v4().to_string(), chapter_id, title, content, plain_text, metadata, source, Utc::now()],
+    )?;
+    
+    conn.execute(
+        "DELETE FROM chapter_versions WHERE chapter_id = ?1 AND id NOT IN (
+             SELECT id FROM chapter_versions WHERE chapter_id = ?1
+             ORDER BY created_at DESC, rowid DESC LIMIT ?2
+         )",
+        params![chapter_id, MAX_CHAPTER_VERSIONS],
+    )?;
+    
+    Ok(())
+}
+
+/// 在單一交易中還原版本，回傳章節 ID
+fn restore_chapter_version_record(conn: &mut rusqlite::Connection, version_id: &str) -> Result<String, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let (chapter_id, title, content, metadata): (String, String, Option<String>, Option<String>) = tx
+        .query_row(
+            "SELECT chapter_id, title, content, metadata FROM chapter_versions WHERE id = ?1",
+            [version_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| format!("章節版本不存在: {}", e))?;
+    
+    snapshot_chapter_version(&tx, &chapter_id, "restore")
+        .map_err(|e| format!("保存章節版本失敗: {}", e))?;
+    
+    let now = Utc::now();
+    tx.execute(
+        "UPDATE chapters SET title = ?1, content = ?2, metadata = ?3, updated_at = ?4 WHERE id = ?5",
+        params![title, content, metadata, now, chapter_id],
+    )
+    .map_err(|e| format!("還原章節失敗: {}", e))?;
+    
+    tx.execute(
+        "UPDATE projects SET updated_at = ?1 WHERE id = (SELECT project_id FROM chapters WHERE id = ?2)",
+        params![now, chapter_id],
+    ).map_err(|e| format!("更新專案時間戳失敗: {}", e))?;
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(chapter_id)
+}
+
+/// 在專案所有章節內容中尋找並取代文字
+///
+/// 只在 Slate.js 的文字葉節點內比對，因此粗體、斜體等格式會保留；
+/// 跨越不同格式片段的文字不會被比對到。實際取代時在單一交易中完成，
+/// 並先為每個修改的章節保存歷史版本。
+#[tauri::command]
+pub async fn find_and_replace_in_project(
+    project_id: String,
+    find: String,
+    replace: String,
+    options: Option<FindReplaceOptions>,
+) -> Result<FindReplaceResult, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let result = find_and_replace_records(&mut conn, &project_id, &find, &replace, &options.unwrap_or_default())?;
+    
+    log::info!(
+        "{}尋找與取代完成: 專案 ID {}（{} 個章節，共 {} 處）",
+        if result.dry_run { "[預覽] " } else { "" },
+        project_id,
+        result.chapters.len(),
+        result.total_replacements
+    );
+    Ok(result)
+}
+
+fn find_and_replace_records(
+    conn: &mut rusqlite::Connection,
+    project_id: &str,
+    find: &str,
+    replace: &str,
+    options: &FindReplaceOptions,
+) -> Result<FindReplaceResult, String> {
+    if find.is_empty() {
+        return Err("搜尋文字不能為空".to_string());
+    }
+    let pattern = regex::RegexBuilder::new(&regex::escape(find))
+        .case_insensitive(!options.case_sensitive)
+        .build()
+        .map_err(|e| format!("搜尋文字無效: {}", e))?;
+    
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let chapters: Vec<(String, String, Option<String>)> = tx
+        .prepare("SELECT id, title, content FROM chapters WHERE project_id = ?1 ORDER BY order_index ASC")
+        .and_then(|mut stmt| {
+            stmt.query_map([project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<rusqlite::Result<_>>()
+        })
+        .map_err(|e| e.to_string())?;
+    
+    let now = Utc::now();
+    let mut result = FindReplaceResult {
+        dry_run: options.dry_run,
+        total_replacements: 0,
+        chapters: Vec::new(),
+    };
+    for (chapter_id, chapter_title, content) in chapters {
+        let content = content.unwrap_or_default();
+        let mut replacement = ChapterReplacement {
+            chapter_id,
+            chapter_title,
+            replacements: 0,
+            previews: Vec::new(),
+        };
+        
+        // 非 Slate JSON 的舊資料視為單一文字片段
+        let new_content = match parse_slate_document(&content) {
+            Some(mut nodes) => {
+                for node in &mut nodes {
+                    replace_in_slate_node(node, &pattern, replace, options.whole_word, &mut replacement);
+                }
+                serde_json::Value::Array(nodes).to_string()
+            }
+            None => replace_in_text(&content, &pattern, replace, options.whole_word, &mut replacement),
+        };
+        if replacement.replacements == 0 {
+            continue;
+        }
+        
+        if !options.dry_run {
+            snapshot_chapter_version(&tx, &replacement.chapter_id, "find_replace")
+                .map_err(|e| format!("保存章節版本失敗: {}", e))?;
+            tx.execute(
+                "UPDATE chapters SET content = ?1, updated_at = ?2 WHERE id = ?3",
+                params![new_content, now, replacement.chapter_id],
+            )
+            .map_err(|e| format!("更新章節失敗: {}", e))?;
+        }
+        result.total_replacements += replacement.replacements;
+        result.chapters.push(replacement);
+    }
+    
+    if !options.dry_run && result.total_replacements > 0 {
+        tx.execute("UPDATE projects SET updated_at = ?1 WHERE id = ?2", params![now, project_id])
+            .map_err(|e| format!("更新專案時間戳失敗: {}", e))?;
+        tx.commit().map_err(|e| e.to_string())?;
+    }
+    Ok(result)
+}
+
+fn replace_in_slate_node(
+    node: &mut serde_json::Value,
+    pattern: &regex::Regex,
+    replace: &str,
+    whole_word: bool,
+    replacement: &mut ChapterReplacement,
+) {
+    if let Some(serde_json::Value::String(text)) = node.get_mut("text") {
+        *text = replace_in_text(text, pattern, replace, whole_word, replacement);
+        return;
+    }
+    if let Some(serde_json::Value::Array(children)) = node.get_mut("children") {
+        for child in children {
+            replace_in_slate_node(child, pattern, replace, whole_word, replacement);
+        }
+    }
+}
+
+/// 取代單一文字片段中的比對，並記錄次數與前後文預覽
+fn replace_in_text(
+    text: &str,
+    pattern: &regex::Regex,
+    replace: &str,
+    whole_word: bool,
+    replacement: &mut ChapterReplacement,
+) -> String {
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut output = String::with_capacity(text.len());
+    let mut last_end = 0;
+    for found in pattern.find_iter(text) {
+        if whole_word {
+            let before = text[..found.start()].chars().next_back();
+            let after = text[found.end()..].chars().next();
+            let first = found.as_str().chars().next();
+            let last = found.as_str().chars().next_back();
+            let joined_before = first.is_some_and(is_word_char) && before.is_some_and(is_word_char);
+            let joined_after = last.is_some_and(is_word_char) && after.is_some_and(is_word_char);
+            if joined_before || joined_after {
+                continue;
+            }
+        }
+        
+        let preview_before: String = {
+            let chars: Vec<char> = text[..found.start()].chars().rev().take(FIND_REPLACE_PREVIEW_CHARS).collect();
+            chars.into_iter().rev().collect()
+        };
+        let preview_after: String = text[found.end()..].chars().take(FIND_REPLACE_PREVIEW_CHARS).collect();
+        replacement.previews.push(format!("{}【{}】{}", preview_before, found.as_str(), preview_after));
+        replacement.replacements += 1;
+        
+        output.push_str(&text[last_end..found.start()]);
+        output.push_str(replace);
+        last_end = found.end();
+    }
+    output.push_str(&text[last_end..]);
+    output
+}
+
+/// 依指定順序重新排列專案的所有章節
+///
+/// 在單一交易中將 order_index 設為 0..N，並依新順序重新計算 chapter_number（從 1 開始）。
+/// `ordered_ids` 必須恰好包含專案的所有章節 ID。
+#[tauri::command]
+pub async fn reorder_chapters(project_id: String, ordered_ids: Vec<String>) -> Result<(), String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    reorder_chapter_records(&mut conn, &project_id, &ordered_ids)?;
+    
+    log::info!("重新排序章節成功: 專案 ID {} ({} 個章節)", project_id, ordered_ids.len());
+    Ok(())
+}
+
+fn reorder_chapter_records(conn: &mut rusqlite::Connection, project_id: &str, ordered_ids: &[String]) -> Result<(), String> {
+    use std::collections::HashSet;
+    
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let existing_ids: HashSet<String> = tx
+        .prepare("SELECT id FROM chapters WHERE project_id = ?1")
+        .and_then(|mut stmt| {
+            stmt.query_map([project_id], |row| row.get(0))?
+                .collect::<Result<HashSet<String>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+    
+    let requested_ids: HashSet<String> = ordered_ids.iter().cloned().collect();
+    if requested_ids.len() != ordered_ids.len() {
+        return Err("章節排序包含重複的章節 ID".to_string());
+    }
+    
+    let missing: Vec<&String> = existing_ids.difference(&requested_ids).collect();
+    let extra: Vec<&String> = requested_ids.difference(&existing_ids).collect();
+    if !missing.is_empty() || !extra.is_empty() {
+        return Err(format!(
+            "章節排序與專案章節不符（缺少: {:?}，多出: {:?}）",
+            missing, extra
+        ));
+    }
+    
+    let now = Utc::now();
+    for (index, chapter_id) in ordered_ids.iter().enumerate() {
+        tx.execute(
+            "UPDATE chapters SET order_index = ?1, chapter_number = ?2, updated_at = ?3 WHERE id = ?4",
+            params![index as i32, index as i32 + 1, now, chapter_id],
+        )
+        .map_err(|e| format!("更新章節順序失敗: {}", e))?;
+    }
+    
+    tx.execute(
+        "UPDATE projects SET updated_at = ?1 WHERE id = ?2",
+        params![now, project_id],
+    ).map_err(|e| format!("更新專案時間戳失敗: {}", e))?;
+    
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// 依 order_index 重新計算專案的 chapter_number（1..N），回傳有變更的章節
+///
+/// 專案設定 `count_prologue_as_zero` 為 true 且第一章標題為序章時，序章編為 0，其餘從 1 開始。
+#[tauri::command]
+pub async fn normalize_chapter_numbers(project_id: String) -> Result<Vec<ChapterNumberChange>, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let count_prologue_as_zero = read_project_setting(&conn, &project_id, COUNT_PROLOGUE_AS_ZERO_KEY)?
+        .and_then(|value| serde_json::from_str::<bool>(&value).ok())
+        .unwrap_or(false);
+    let changes = normalize_chapter_number_records(&mut conn, &project_id, count_prologue_as_zero)?;
+    
+    log::info!("重新計算章節編號: 專案 ID {} ({} 個章節變更)", project_id, changes.len());
+    Ok(changes)
+}
+
+fn is_prologue_title(title: &str) -> bool {
+    let title = title.trim().to_lowercase();
+    PROLOGUE_TITLE_KEYWORDS.iter().any(|keyword| title.starts_with(keyword))
+}
+
+fn normalize_chapter_number_records(
+    conn: &mut rusqlite::Connection,
+    project_id: &str,
+    count_prologue_as_zero: bool,
+) -> Result<Vec<ChapterNumberChange>, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    
+    let chapters: Vec<(String, String, Option<i32>)> = tx
+        .prepare("SELECT id, title, chapter_number FROM chapters WHERE project_id = ?1 ORDER BY order_index, rowid")
+        .and_then(|mut stmt| {
+            stmt.query_map([project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| e.to_string())?;
+    
+    let offset = match chapters.first() {
+        Some((_, title, _)) if count_prologue_as_zero && is_prologue_title(title) => 0,
+        _ => 1,
+    };
+    
+    let mut changes = Vec::new();
+    for (index, (chapter_id, title, old_number)) in chapters.into_iter().enumerate() {
+        let new_number = index as i32 + offset;
+        if old_number == Some(new_number) {
+            continue;
+        }
+        // 只更新編號，不更動 updated_at，避免編輯中的章節儲存時被判定為衝突
+        tx.execute(
+            "UPDATE chapters SET chapter_number = ?1 WHERE id = ?2",
+            params![new_number, chapter_id],
+        )
+        .map_err(|e| format!("更新章節編號失敗: {}", e))?;
+        changes.push(ChapterNumberChange { chapter_id, title, old_number, new_number });
+    }
+    
+    if !changes.is_empty() {
+        tx.execute(
+            "UPDATE projects SET updated_at = ?1 WHERE id = ?2",
+            params![Utc::now(), project_id],
+        ).map_err(|e| format!("更新專案時間戳失敗: {}", e))?;
+    }
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    fn setup() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE projects (id TEXT PRIMARY KEY, updated_at TEXT);
+             CREATE TABLE chapters (
+                 id TEXT PRIMARY KEY, project_id TEXT, order_index INTEGER,
+                 chapter_number INTEGER, updated_at TEXT
+             );
+             INSERT INTO projects (id) VALUES ('p1');
+             INSERT INTO chapters (id, project_id, order_index, chapter_number) VALUES ('a', 'p1', 1, 1);
+             INSERT INTO chapters (id, project_id, order_index, chapter_number) VALUES ('b', 'p1', 5, 2);
+             INSERT INTO chapters (id, project_id, order_index, chapter_number) VALUES ('c', 'p1', 9, 3);",
+        ).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_reorder_chapters_reindexes() {
+        let mut conn = setup();
+        let ids = vec!["c".to_string(), "a".to_string(), "b".to_string()];
+        reorder_chapter_records(&mut conn, "p1", &ids).unwrap();
+
+        let mut stmt = conn.prepare("SELECT id, order_index, chapter_number FROM chapters ORDER BY order_index").unwrap();
+        let rows: Vec<(String, i32, i32)> = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap().collect::<Result<_, _>>().unwrap();
+        assert_eq!(rows, vec![("c".to_string(), 0, 1), ("a".to_string(), 1, 2), ("b".to_string(), 2, 3)]);
+    }
+
+    #[test]
+    fn test_reorder_chapters_rejects_non_permutation() {
+        let mut conn = setup();
+        assert!(reorder_chapter_records(&mut conn, "p1", &["a".to_string(), "b".to_string()]).is_err());
+        assert!(reorder_chapter_records(&mut conn, "p1", &["a".to_string(), "b".to_string(), "x".to_string()]).is_err());
+        assert!(reorder_chapter_records(&mut conn, "p1", &["a".to_string(), "a".to_string(), "b".to_string()]).is_err());
+
+        let untouched: i32 = conn.query_row("SELECT order_index FROM chapters WHERE id = 'c'", [], |row| row.get(0)).unwrap();
+        assert_eq!(untouched, 9);
+    }
+
+    #[test]
+    fn test_normalize_chapter_numbers_reports_changes() {
+        let mut conn = setup();
+        conn.execute_batch(
+            "ALTER TABLE chapters ADD COLUMN title TEXT;
+             UPDATE chapters SET title = '第' || id || '章';
+             UPDATE chapters SET title = '序章：出發', chapter_number = 7 WHERE id = 'a';
+             UPDATE chapters SET chapter_number = NULL WHERE id = 'c';",
+        ).unwrap();
+
+        let changes = normalize_chapter_number_records(&mut conn, "p1", false).unwrap();
+        assert_eq!(changes, vec![
+            ChapterNumberChange { chapter_id: "a".to_string(), title: "序章：出發".to_string(), old_number: Some(7), new_number: 1 },
+            ChapterNumberChange { chapter_id: "c".to_string(), title: "第c章".to_string(), old_number: None, new_number: 3 },
+        ]);
+        assert!(normalize_chapter_number_records(&mut conn, "p1", false).unwrap().is_empty());
+
+        // 序章編為 0，其後章節依序遞補
+        let changes = normalize_chapter_number_records(&mut conn, "p1", true).unwrap();
+        let numbers: Vec<(&str, i32)> = changes.iter().map(|c| (c.chapter_id.as_str(), c.new_number)).collect();
+        assert_eq!(numbers, vec![("a", 0), ("b", 1), ("c", 2)]);
+    }
+
+    #[test]
+    fn test_chapter_versions_snapshot_prune_and_restore() {
+        let mut conn = migrated_conn_with_project();
+        conn.execute_batch(
+            r#"INSERT INTO chapters (id, project_id, title, content)
+                   VALUES ('c1', 'p1', '第一章', '[{"type":"paragraph","children":[{"text":"原稿"}]}]');"#,
+        ).unwrap();
+
+        snapshot_chapter_version(&conn, "c1", "update").unwrap();
+        conn.execute("UPDATE chapters SET content = '[]' WHERE id = 'c1'", []).unwrap();
+
+        let (version_id, plain_text): (String, String) = conn
+            .query_row("SELECT id, plain_text FROM chapter_versions WHERE chapter_id = 'c1'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!(plain_text, "原稿");
+
+        restore_chapter_version_record(&mut conn, &version_id).unwrap();
+        let content: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c1'", [], |row| row.get(0)).unwrap();
+        assert!(content.contains("原稿"));
+        let restore_snapshot: String = conn
+            .query_row("SELECT content FROM chapter_versions WHERE source = 'restore'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(restore_snapshot, "[]");
+
+        for _ in 0..MAX_CHAPTER_VERSIONS + 5 {
+            snapshot_chapter_version(&conn, "c1", "update").unwrap();
+        }
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM chapter_versions", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, MAX_CHAPTER_VERSIONS);
+    }
+
+    #[test]
+    fn test_find_and_replace_preserves_marks() {
+        let mut conn = migrated_conn_with_project();
+        conn.execute_batch(
+            r#"INSERT INTO chapters (id, project_id, title, content, order_index)
+                   VALUES ('c1', 'p1', '第一章', '[{"type":"paragraph","children":[{"text":"Al 與 Alice 見面，"},{"text":"al 笑了","bold":true}]}]', 0);
+               INSERT INTO chapters (id, project_id, title, content, order_index) VALUES ('c2', 'p1', '第二章', 'Al說：走吧', 1);
+               INSERT INTO chapters (id, project_id, title, content, order_index) VALUES ('c3', 'p1', '第三章', '[]', 2);"#,
+        ).unwrap();
+
+        let preview = FindReplaceOptions { whole_word: true, dry_run: true, ..Default::default() };
+        let result = find_and_replace_records(&mut conn, "p1", "Al", "亞倫", &preview).unwrap();
+        assert_eq!(result.total_replacements, 3);
+        assert_eq!(result.chapters.iter().map(|c| c.replacements).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(result.chapters[0].previews[0], "【Al】 與 Alice 見面，");
+        let untouched: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c2'", [], |row| row.get(0)).unwrap();
+        assert_eq!(untouched, "Al說：走吧");
+
+        let options = FindReplaceOptions { case_sensitive: true, whole_word: true, ..Default::default() };
+        let result = find_and_replace_records(&mut conn, "p1", "Al", "亞倫", &options).unwrap();
+        assert_eq!(result.total_replacements, 2);
+        let content: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&content).unwrap(),
+            serde_json::json!([{ "type": "paragraph", "children": [{ "text": "亞倫 與 Alice 見面，" }, { "text": "al 笑了", "bold": true }] }])
+        );
+        let plain: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c2'", [], |row| row.get(0)).unwrap();
+        assert_eq!(plain, "亞倫說：走吧");
+        let snapshots: i64 = conn
+            .query_row("SELECT COUNT(*) FROM chapter_versions WHERE source = 'find_replace'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(snapshots, 2);
+
+        assert!(find_and_replace_records(&mut conn, "p1", "", "x", &options).is_err());
+    }
+
+    #[test]
+    fn test_update_chapter_rejects_stale_write() {
+        let mut conn = migrated_conn_with_project();
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, updated_at)
+                 VALUES ('c1', 'p1', '第一章', '[]', '2026-10-01 08:00:00');",
+        ).unwrap();
+        let request = |content: &str, expected: Option<DateTime<Utc>>| UpdateChapterRequest {
+            id: "c1".to_string(),
+            title: "第一章".to_string(),
+            content: Some(content.to_string()),
+            order_index: None,
+            chapter_number: None,
+            metadata: None,
+            expected_updated_at: expected,
+        };
+        let loaded: DateTime<Utc> = "2026-10-01T08:00:00Z".parse().unwrap();
+
+        // 背景儲存先寫入
+        let (_, saved_at) = update_chapter_record(&mut conn, request("AI 生成", Some(loaded))).unwrap();
+        // 以舊版本為基礎的自動儲存被拒絕
+        let conflict = update_chapter_record(&mut conn, request("舊的編輯", Some(loaded))).unwrap_err();
+        assert!(conflict.starts_with("Conflict:"));
+        let content: String = conn.query_row("SELECT content FROM chapters WHERE id = 'c1'", [], |row| row.get(0)).unwrap();
+        assert_eq!(content, "AI 生成");
+
+        // 以最新版本（毫秒精度）為基礎的寫入成功；未提供版本時維持原本行為
+        let millis = DateTime::from_timestamp_millis(saved_at.timestamp_millis()).unwrap();
+        update_chapter_record(&mut conn, request("新的編輯", Some(millis))).unwrap();
+        update_chapter_record(&mut conn, request("強制寫入", None)).unwrap();
+    }
+}
@@ -1,13 +1,110 @@
-use crate::database::{get_db, models::*};
+use crate::database::{get_db_conn, models::*};
+use crate::utils::slate::slate_to_plain_text;
 use anyhow::Result;
 use chrono::Utc;
 use rusqlite::params;
+use serde::Serialize;
 use uuid::Uuid;
 
+/// 標準關係類型與其別名（與前端 RELATIONSHIP_TYPES 一致）
+const RELATIONSHIP_VOCABULARY: &[(&str, &[&str])] = &[
+    ("朋友", &["友人", "好友", "摯友", "friend", "friends", "buddy"]),
+    ("戀人", &["情人", "愛人", "男友", "女友", "男朋友", "女朋友", "lover", "partner"]),
+    ("家人", &["家族", "親人", "family", "relative"]),
+    ("師父", &["師傅", "導師", "master", "mentor"]),
+    ("弟子", &["徒弟", "學徒", "disciple", "apprentice"]),
+    ("競爭對手", &["對手", "勁敵", "宿敵", "rival", "competitor"]),
+    ("敵人", &["仇人", "仇敵", "enemy", "foe", "nemesis"]),
+    ("同事", &["同僚", "colleague", "coworker"]),
+    ("上司", &["主管", "上級", "老闆", "boss", "superior"]),
+    ("下屬", &["部下", "手下", "屬下", "subordinate"]),
+    ("鄰居", &["鄰人", "neighbor", "neighbour"]),
+    ("同學", &["classmate", "schoolmate"]),
+    ("其他", &["other"]),
+];
+
+/// 非對稱關係的反向類型（其餘關係的反向與正向相同）
+const RELATIONSHIP_INVERSES: &[(&str, &str)] = &[
+    ("師父", "弟子"),
+    ("上司", "下屬"),
+];
+
+/// 關係圖中的角色節點
+#[derive(Debug, Serialize)]
+pub struct RelationshipGraphNode {
+    pub id: String,
+    pub name: String,
+}
+
+/// 關係圖中的有向邊
+#[derive(Debug, Serialize)]
+pub struct RelationshipGraphEdge {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub relationship_type: String,
+    pub description: Option<String>,
+    pub pair_id: Option<String>,
+    /// 是否存在反方向的關係（用於找出單方面的關係）
+    pub reciprocated: bool,
+}
+
+/// 專案角色關係圖
+#[derive(Debug, Serialize)]
+pub struct RelationshipGraph {
+    pub nodes: Vec<RelationshipGraphNode>,
+    pub edges: Vec<RelationshipGraphEdge>,
+    /// 角色 ID → 其關係指向的角色 ID
+    pub adjacency: std::collections::HashMap<String, Vec<String>>,
+}
+
+/// 角色在單一章節的出場統計
+#[derive(Debug, Serialize)]
+pub struct ChapterMention {
+    pub chapter_id: String,
+    pub chapter_title: String,
+    pub mention_count: usize,
+    pub first_position: usize,
+}
+
+/// 角色出場掃描結果
+#[derive(Debug, Serialize)]
+pub struct CharacterMentionSummary {
+    pub character_id: String,
+    pub name: String,
+    pub total_mentions: usize,
+    pub chapters: Vec<ChapterMention>,
+}
+
+/// 屬性中可能記錄別名的欄位
+const ALIAS_ATTRIBUTE_KEYS: &[&str] = &["aliases", "alias", "nicknames", "nickname", "別名", "暱稱", "綽號"];
+
+/// 建議的關係類型
+#[derive(Debug, Serialize)]
+pub struct RelationshipTypeInfo {
+    pub value: String,
+    pub aliases: Vec<String>,
+}
+
+/// 將關係類型正規化為標準名稱
+///
+/// 符合標準名稱或別名（英文不分大小寫）時回傳標準名稱，否則保留使用者輸入（去除前後空白）。
+pub fn normalize_relationship_type(relationship_type: &str) -> String {
+    let trimmed = relationship_type.trim();
+    RELATIONSHIP_VOCABULARY
+        .iter()
+        .find(|(canonical, aliases)| {
+            std::iter::once(canonical)
+                .chain(aliases.iter())
+                .any(|name| name.eq_ignore_ascii_case(trimmed))
+        })
+        .map(|(canonical, _)| canonical.to_string())
+        .unwrap_or_else(|| trimmed.to_string())
+}
+
 #[tauri::command]
 pub async fn get_characters_by_project_id(project_id: String) -> Result<Vec<Character>, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn
         .prepare("SELECT id, project_id, name, description, attributes, avatar_url, created_at, updated_at 
@@ -39,8 +136,7 @@ pub async fn get_characters_by_project_id(project_id: String) -> Result<Vec<Char
 
 #[tauri::command]
 pub async fn get_character_by_id(id: String) -> Result<Character, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn
         .prepare("SELECT id, project_id, name, description, attributes, avatar_url, created_at, updated_at 
@@ -65,10 +161,45 @@ pub async fn get_character_by_id(id: String) -> Result<Character, String> {
     Ok(character)
 }
 
+/// 驗證角色屬性 JSON：必須是物件，且值只能是字串、數字、布林、null 或由這些值組成的陣列
+#[tauri::command]
+pub async fn validate_character_attributes(attributes: String) -> Result<(), String> {
+    validate_attributes_json(&attributes)
+}
+
+fn validate_attributes_json(attributes: &str) -> Result<(), String> {
+    // 空字串視為沒有屬性
+    if attributes.trim().is_empty() {
+        return Ok(());
+    }
+    
+    let value: serde_json::Value = serde_json::from_str(attributes)
+        .map_err(|e| format!("角色屬性不是有效的 JSON: {}", e))?;
+    let object = value
+        .as_object()
+        .ok_or_else(|| "角色屬性必須是 JSON 物件（例如 {\"age\": \"17\"}）".to_string())?;
+    
+    let is_simple = |v: &serde_json::Value| !v.is_object() && !v.is_array();
+    for (key, value) in object {
+        let valid = match value {
+            serde_json::Value::Array(items) => items.iter().all(is_simple),
+            other => is_simple(other),
+        };
+        if !valid {
+            return Err(format!("角色屬性「{}」的值必須是字串、數字、布林或簡單陣列，不能是巢狀物件", key));
+        }
+    }
+    
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn create_character(character: CreateCharacterRequest) -> Result<String, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    if let Some(attributes) = &character.attributes {
+        validate_attributes_json(attributes)?;
+    }
+    
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let character_id = Uuid::new_v4().to_string();
     let now = Utc::now();
@@ -95,8 +226,11 @@ pub async fn create_character(character: CreateCharacterRequest) -> Result<Strin
 
 #[tauri::command]
 pub async fn update_character(character: UpdateCharacterRequest) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    if let Some(attributes) = &character.attributes {
+        validate_attributes_json(attributes)?;
+    }
+    
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let now = Utc::now();
     
@@ -125,8 +259,7 @@ pub async fn update_character(character: UpdateCharacterRequest) -> Result<(), S
 
 #[tauri::command]
 pub async fn delete_character(id: String) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     // 因為有外鍵約束，刪除角色會自動刪除相關的關係
     let rows_affected = conn
@@ -143,62 +276,148 @@ pub async fn delete_character(id: String) -> Result<(), String> {
 
 // 角色關係管理
 
+/// 建立角色關係
+///
+/// `bidirectional` 為 true 時會在同一交易中同時建立反向關係，兩筆記錄共用 `pair_id`；
+/// 反向關係類型會自動對應（例如「師父」的反向為「弟子」）。
 #[tauri::command]
 pub async fn create_character_relationship(
     from_character_id: String,
     to_character_id: String,
     relationship_type: String,
     description: Option<String>,
+    bidirectional: Option<bool>,
 ) -> Result<String, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let relationship_type = normalize_relationship_type(&relationship_type);
+    if relationship_type.is_empty() {
+        return Err("關係類型不能為空".to_string());
+    }
+    if from_character_id == to_character_id {
+        return Err("不能建立角色與自己的關係".to_string());
+    }
     
-    let relationship_id = Uuid::new_v4().to_string();
-    let now = Utc::now();
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
     
-    conn.execute(
-        "INSERT INTO character_relationships (id, from_character_id, to_character_id, relationship_type, description, created_at, updated_at) 
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        params![
-            relationship_id,
-            from_character_id,
-            to_character_id,
-            relationship_type,
-            description,
-            now,
-            now
-        ],
-    )
-    .map_err(|e| format!("建立角色關係失敗: {}", e))?;
+    let relationship_id = insert_relationship_records(
+        &mut conn,
+        &from_character_id,
+        &to_character_id,
+        &relationship_type,
+        description.as_deref(),
+        bidirectional.unwrap_or(false),
+    )?;
     
     log::info!("建立角色關係成功: ID {}", relationship_id);
     Ok(relationship_id)
 }
 
+/// 插入關係記錄（雙向時一併插入反向記錄），回傳正向關係的 ID
+///
+/// 拒絕自我關係；相同的（起點、終點、類型）已存在時回傳明確錯誤而不重複插入。
+fn insert_relationship_records(
+    conn: &mut rusqlite::Connection,
+    from_character_id: &str,
+    to_character_id: &str,
+    relationship_type: &str,
+    description: Option<&str>,
+    bidirectional: bool,
+) -> Result<String, String> {
+    if from_character_id == to_character_id {
+        return Err("不能建立角色與自己的關係".to_string());
+    }
+    
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    let relationship_id = Uuid::new_v4().to_string();
+    let pair_id = bidirectional.then(|| Uuid::new_v4().to_string());
+    
+    let insert = |id: &str, from: &str, to: &str, rel_type: &str| -> Result<(), String> {
+        let exists: bool = tx
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM character_relationships
+                 WHERE from_character_id = ?1 AND to_character_id = ?2 AND relationship_type = ?3)",
+                params![from, to, rel_type],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        if exists {
+            return Err(format!("角色關係已存在：{} → {}（{}）", from, to, rel_type));
+        }
+        
+        tx.execute(
+            "INSERT INTO character_relationships (id, from_character_id, to_character_id, relationship_type, description, pair_id, created_at, updated_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![id, from, to, rel_type, description, pair_id, now, now],
+        )
+        .map_err(|e| format!("建立角色關係失敗: {}", e))?;
+        Ok(())
+    };
+    
+    insert(&relationship_id, from_character_id, to_character_id, relationship_type)?;
+    if bidirectional {
+        let reverse_type = inverse_relationship_type(relationship_type);
+        insert(&Uuid::new_v4().to_string(), to_character_id, from_character_id, &reverse_type)?;
+    }
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(relationship_id)
+}
+
+/// 取得關係的反向類型（例如「師父」的反向為「弟子」）
+fn inverse_relationship_type(relationship_type: &str) -> String {
+    RELATIONSHIP_INVERSES
+        .iter()
+        .find_map(|(a, b)| match relationship_type {
+            t if t == *a => Some(b.to_string()),
+            t if t == *b => Some(a.to_string()),
+            _ => None,
+        })
+        .unwrap_or_else(|| relationship_type.to_string())
+}
+
+/// 取得建議的關係類型清單（自由輸入仍可使用，符合別名時會自動正規化）
+#[tauri::command]
+pub async fn get_relationship_types() -> Result<Vec<RelationshipTypeInfo>, String> {
+    Ok(RELATIONSHIP_VOCABULARY
+        .iter()
+        .map(|(canonical, aliases)| RelationshipTypeInfo {
+            value: canonical.to_string(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+        })
+        .collect())
+}
+
 #[tauri::command]
 pub async fn delete_character_relationship(id: String) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
-    let rows_affected = conn
-        .execute("DELETE FROM character_relationships WHERE id = ?1", [&id])
+    let rows_affected = delete_relationship_records(&conn, &id)
         .map_err(|e| format!("刪除角色關係失敗: {}", e))?;
     
     if rows_affected == 0 {
         return Err("角色關係不存在".to_string());
     }
     
-    log::info!("刪除角色關係成功: ID {}", id);
+    log::info!("刪除角色關係成功: ID {} (共 {} 筆)", id, rows_affected);
     Ok(())
 }
 
+/// 刪除關係；若屬於雙向配對則連同另一半一起刪除
+fn delete_relationship_records(conn: &rusqlite::Connection, id: &str) -> rusqlite::Result<usize> {
+    conn.execute(
+        "DELETE FROM character_relationships
+         WHERE id = ?1
+            OR pair_id = (SELECT pair_id FROM character_relationships WHERE id = ?1 AND pair_id IS NOT NULL)",
+        [id],
+    )
+}
+
 #[tauri::command]
 pub async fn get_character_relationships(character_id: String) -> Result<Vec<CharacterRelationship>, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn
-        .prepare("SELECT id, from_character_id, to_character_id, relationship_type, description, created_at, updated_at 
+        .prepare("SELECT id, from_character_id, to_character_id, relationship_type, description, pair_id, created_at, updated_at 
                   FROM character_relationships WHERE from_character_id = ?1 ORDER BY created_at ASC")
         .map_err(|e| e.to_string())?;
     
@@ -210,8 +429,9 @@ pub async fn get_character_relationships(character_id: String) -> Result<Vec<Cha
                 to_character_id: row.get(2)?,
                 relationship_type: row.get(3)?,
                 description: row.get(4)?,
-                created_at: row.get(5)?,
-                updated_at: row.get(6)?,
+                pair_id: row.get(5)?,
+                created_at: row.get(6)?,
+                updated_at: row.get(7)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -224,10 +444,240 @@ pub async fn get_character_relationships(character_id: String) -> Result<Vec<Cha
     Ok(relationships)
 }
 
+/// 取得專案的角色關係圖（節點、邊與鄰接表），供 UI 繪製關係網路
+#[tauri::command]
+pub async fn get_character_relationship_graph(project_id: String) -> Result<RelationshipGraph, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    build_relationship_graph(&conn, &project_id).map_err(|e| format!("取得角色關係圖失敗: {}", e))
+}
+
+fn build_relationship_graph(conn: &rusqlite::Connection, project_id: &str) -> rusqlite::Result<RelationshipGraph> {
+    use std::collections::{HashMap, HashSet};
+    
+    let nodes: Vec<RelationshipGraphNode> = {
+        let mut stmt = conn.prepare("SELECT id, name FROM characters WHERE project_id = ?1 ORDER BY created_at ASC")?;
+        let rows = stmt.query_map([project_id], |row| Ok(RelationshipGraphNode { id: row.get(0)?, name: row.get(1)? }))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    
+    let mut edges: Vec<RelationshipGraphEdge> = {
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.from_character_id, r.to_character_id, r.relationship_type, r.description, r.pair_id
+             FROM character_relationships r
+             JOIN characters f ON f.id = r.from_character_id
+             JOIN characters t ON t.id = r.to_character_id
+             WHERE f.project_id = ?1 AND t.project_id = ?1
+             ORDER BY r.created_at ASC",
+        )?;
+        let rows = stmt.query_map([project_id], |row| {
+            Ok(RelationshipGraphEdge {
+                id: row.get(0)?,
+                from: row.get(1)?,
+                to: row.get(2)?,
+                relationship_type: row.get(3)?,
+                description: row.get(4)?,
+                pair_id: row.get(5)?,
+                reciprocated: false,
+            })
+        })?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    
+    let directed: HashSet<(String, String)> = edges.iter().map(|e| (e.from.clone(), e.to.clone())).collect();
+    let mut adjacency: HashMap<String, Vec<String>> = nodes.iter().map(|n| (n.id.clone(), Vec::new())).collect();
+    for edge in &mut edges {
+        edge.reciprocated = directed.contains(&(edge.to.clone(), edge.from.clone()));
+        let neighbors = adjacency.entry(edge.from.clone()).or_default();
+        if !neighbors.contains(&edge.to) {
+            neighbors.push(edge.to.clone());
+        }
+    }
+    
+    Ok(RelationshipGraph { nodes, edges, adjacency })
+}
+
+/// 掃描專案所有章節，記錄每個角色出場的章節與次數
+///
+/// 比對角色全名、以「·」等分隔的名字片段，以及屬性中的別名；
+/// 採最長匹配，因此某角色名稱是另一角色名稱的一部分時不會重複計算。
+#[tauri::command]
+pub async fn scan_character_mentions(project_id: String) -> Result<Vec<CharacterMentionSummary>, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let summaries = scan_character_mention_records(&mut conn, &project_id)
+        .map_err(|e| format!("掃描角色出場失敗: {}", e))?;
+    
+    log::info!("掃描角色出場完成: 專案 ID {} ({} 個角色)", project_id, summaries.len());
+    Ok(summaries)
+}
+
+/// 取得角色的名稱變體（全名、名字片段、屬性別名）
+pub(crate) fn character_name_variants(name: &str, attributes: Option<&str>) -> (String, Vec<String>) {
+    let full_name = name.trim().to_string();
+    let mut variants = Vec::new();
+    
+    let parts: Vec<&str> = full_name
+        .split(['·', '・', '•', '=', ' '])
+        .map(str::trim)
+        .filter(|part| part.chars().count() >= 2)
+        .collect();
+    if parts.len() > 1 {
+        variants.extend(parts.iter().map(|part| part.to_string()));
+    }
+    
+    if let Some(Ok(serde_json::Value::Object(object))) = attributes.map(serde_json::from_str::<serde_json::Value>) {
+        for key in ALIAS_ATTRIBUTE_KEYS {
+            match object.get(*key) {
+                Some(serde_json::Value::String(aliases)) => variants.extend(
+                    aliases.split([',', '，', '、', '/']).map(|alias| alias.trim().to_string()),
+                ),
+                Some(serde_json::Value::Array(aliases)) => variants.extend(
+                    aliases.iter().filter_map(|alias| alias.as_str()).map(|alias| alias.trim().to_string()),
+                ),
+                _ => {}
+            }
+        }
+    }
+    
+    variants.retain(|variant| !variant.is_empty() && *variant != full_name);
+    (full_name, variants)
+}
+
+/// 建立比對用的名稱表（依長度由長到短），同一名稱對應多個角色時以全名優先，無法判斷則略過
+fn build_mention_patterns(characters: &[(String, String, Option<String>)]) -> Vec<(String, String)> {
+    use std::collections::HashMap;
+    
+    // 名稱 → (角色 ID, 是否為全名)；None 表示無法判斷屬於哪個角色
+    let mut owners: HashMap<String, Option<(String, bool)>> = HashMap::new();
+    let mut claim = |pattern: String, character_id: &str, is_full_name: bool| {
+        let entry = owners.entry(pattern).or_insert_with(|| Some((character_id.to_string(), is_full_name)));
+        match entry {
+            Some((owner, owner_full)) if owner != character_id => {
+                if is_full_name && !*owner_full {
+                    *entry = Some((character_id.to_string(), true));
+                } else if is_full_name == *owner_full {
+                    *entry = None;
+                }
+            }
+            _ => {}
+        }
+    };
+    
+    for (character_id, name, attributes) in characters {
+        let (full_name, variants) = character_name_variants(name, attributes.as_deref());
+        if !full_name.is_empty() {
+            claim(full_name, character_id, true);
+        }
+        for variant in variants {
+            claim(variant, character_id, false);
+        }
+    }
+    
+    let mut patterns: Vec<(String, String)> = owners
+        .into_iter()
+        .filter_map(|(pattern, owner)| owner.map(|(character_id, _)| (pattern, character_id)))
+        .collect();
+    patterns.sort_by(|a, b| b.0.chars().count().cmp(&a.0.chars().count()).then_with(|| a.0.cmp(&b.0)));
+    patterns
+}
+
+/// 以最長匹配計算文字中各角色的出現次數與第一次出現的位置（字符）
+fn count_mentions(text: &str, patterns: &[(String, String)]) -> std::collections::HashMap<String, (usize, usize)> {
+    let mut mentions = std::collections::HashMap::new();
+    let mut byte_index = 0;
+    let mut char_index = 0;
+    
+    while byte_index < text.len() {
+        let rest = &text[byte_index..];
+        let previous = text[..byte_index].chars().next_back();
+        let matched = patterns.iter().find(|(pattern, _)| {
+            if !rest.starts_with(pattern.as_str()) {
+                return false;
+            }
+            // 英文名稱需要完整單詞邊界，避免 "Al" 匹配到 "Alice"
+            let next = rest[pattern.len()..].chars().next();
+            let is_word = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+            let starts_ascii = pattern.chars().next().is_some_and(|c| c.is_ascii_alphanumeric());
+            let ends_ascii = pattern.chars().next_back().is_some_and(|c| c.is_ascii_alphanumeric());
+            !(starts_ascii && is_word(previous) || ends_ascii && is_word(next))
+        });
+        
+        match matched {
+            Some((pattern, character_id)) => {
+                let entry = mentions.entry(character_id.clone()).or_insert((0, char_index));
+                entry.0 += 1;
+                byte_index += pattern.len();
+                char_index += pattern.chars().count();
+            }
+            None => {
+                byte_index += rest.chars().next().map_or(1, char::len_utf8);
+                char_index += 1;
+            }
+        }
+    }
+    
+    mentions
+}
+
+fn scan_character_mention_records(conn: &mut rusqlite::Connection, project_id: &str) -> rusqlite::Result<Vec<CharacterMentionSummary>> {
+    let tx = conn.transaction()?;
+    
+    let characters: Vec<(String, String, Option<String>)> = {
+        let mut stmt = tx.prepare("SELECT id, name, attributes FROM characters WHERE project_id = ?1 ORDER BY created_at ASC")?;
+        let rows = stmt.query_map([project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    let chapters: Vec<(String, String, Option<String>)> = {
+        let mut stmt = tx.prepare("SELECT id, title, content FROM chapters WHERE project_id = ?1 ORDER BY order_index ASC")?;
+        let rows = stmt.query_map([project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    
+    let patterns = build_mention_patterns(&characters);
+    let mut summaries: Vec<CharacterMentionSummary> = characters
+        .iter()
+        .map(|(id, name, _)| CharacterMentionSummary {
+            character_id: id.clone(),
+            name: name.clone(),
+            total_mentions: 0,
+            chapters: Vec::new(),
+        })
+        .collect();
+    
+    tx.execute(
+        "DELETE FROM character_chapter_mentions WHERE chapter_id IN (SELECT id FROM chapters WHERE project_id = ?1)",
+        [project_id],
+    )?;
+    
+    let now = Utc::now();
+    for (chapter_id, title, content) in &chapters {
+        let text = slate_to_plain_text(content.as_deref().unwrap_or(""));
+        for (character_id, (count, first_position)) in count_mentions(&text, &patterns) {
+            tx.execute(
+                "INSERT INTO character_chapter_mentions (character_id, chapter_id, mention_count, first_position, scanned_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![character_id, chapter_id, count as i64, first_position as i64, now],
+            )?;
+            if let Some(summary) = summaries.iter_mut().find(|s| s.character_id == character_id) {
+                summary.total_mentions += count;
+                summary.chapters.push(ChapterMention {
+                    chapter_id: chapter_id.clone(),
+                    chapter_title: title.clone(),
+                    mention_count: count,
+                    first_position,
+                });
+            }
+        }
+    }
+    
+    tx.commit()?;
+    Ok(summaries)
+}
+
 #[tauri::command]
 pub async fn clear_character_relationships(character_id: String) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     conn.execute(
         "DELETE FROM character_relationships WHERE from_character_id = ?1 OR to_character_id = ?1",
@@ -237,4 +687,494 @@ pub async fn clear_character_relationships(character_id: String) -> Result<(), S
     
     log::info!("清除角色關係成功: Character ID {}", character_id);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 疑似重複角色的判定門檻（綜合分數）
+const DUPLICATE_SCORE_THRESHOLD: f64 = 0.6;
+/// 名稱相似度低於此值時不視為重複
+const DUPLICATE_MIN_NAME_SIMILARITY: f64 = 0.5;
+
+/// 疑似重複的角色組合
+#[derive(Debug, Serialize)]
+pub struct DuplicateCharacterPair {
+    pub first_id: String,
+    pub first_name: String,
+    pub second_id: String,
+    pub second_name: String,
+    /// 名稱相似度（0-1，別名完全符合時為 1）
+    pub name_similarity: f64,
+    /// 兩者都有設定的屬性中相符的比例；任一方沒有屬性時為 None
+    pub attribute_overlap: Option<f64>,
+    pub score: f64,
+}
+
+/// 合併角色的結果（各資料表改指向保留角色的筆數）
+#[derive(Debug, Default, Serialize)]
+pub struct MergeCharactersResult {
+    pub keep_id: String,
+    pub merged_id: String,
+    pub relationships_moved: usize,
+    pub relationships_removed: usize,
+    pub analyses_moved: usize,
+    pub illustrations_moved: usize,
+    pub visual_traits_moved: bool,
+    pub mentions_merged: usize,
+}
+
+/// 找出專案中疑似重複的角色（名稱相似度加上屬性重疊），依分數由高到低排序
+#[tauri::command]
+pub async fn find_duplicate_characters(project_id: String) -> Result<Vec<DuplicateCharacterPair>, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let characters: Vec<(String, String, Option<String>)> = {
+        let mut stmt = conn
+            .prepare("SELECT id, name, attributes FROM characters WHERE project_id = ?1 ORDER BY created_at ASC")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([&project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>().map_err(|e| e.to_string())?
+    };
+    
+    let pairs = find_duplicate_pairs(&characters);
+    log::info!("檢查重複角色完成: 專案 ID {} ({} 組疑似重複)", project_id, pairs.len());
+    Ok(pairs)
+}
+
+fn find_duplicate_pairs(characters: &[(String, String, Option<String>)]) -> Vec<DuplicateCharacterPair> {
+    let mut pairs = Vec::new();
+    for (index, (first_id, first_name, first_attributes)) in characters.iter().enumerate() {
+        for (second_id, second_name, second_attributes) in &characters[index + 1..] {
+            let name_similarity = name_similarity(
+                (first_name, first_attributes.as_deref()),
+                (second_name, second_attributes.as_deref()),
+            );
+            if name_similarity < DUPLICATE_MIN_NAME_SIMILARITY {
+                continue;
+            }
+            let attribute_overlap = attribute_overlap(first_attributes.as_deref(), second_attributes.as_deref());
+            let score = match attribute_overlap {
+                Some(overlap) => name_similarity * 0.7 + overlap * 0.3,
+                None => name_similarity,
+            };
+            if score >= DUPLICATE_SCORE_THRESHOLD {
+                pairs.push(DuplicateCharacterPair {
+                    first_id: first_id.clone(),
+                    first_name: first_name.clone(),
+                    second_id: second_id.clone(),
+                    second_name: second_name.clone(),
+                    name_similarity,
+                    attribute_overlap,
+                    score,
+                });
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.score.total_cmp(&a.score));
+    pairs
+}
+
+/// 名稱相似度：名稱或別名完全相同為 1，包含關係為 0.8，其餘為字元二元組的 Dice 係數
+fn name_similarity(first: (&str, Option<&str>), second: (&str, Option<&str>)) -> f64 {
+    let (first_name, first_variants) = character_name_variants(first.0, first.1);
+    let (second_name, second_variants) = character_name_variants(second.0, second.1);
+    let normalize = |name: &str| -> String {
+        name.chars()
+            .filter(|c| !c.is_whitespace() && !matches!(c, '·' | '・' | '•' | '='))
+            .flat_map(char::to_lowercase)
+            .collect()
+    };
+    let first_all: Vec<String> = std::iter::once(&first_name).chain(&first_variants).map(|n| normalize(n)).collect();
+    let second_all: Vec<String> = std::iter::once(&second_name).chain(&second_variants).map(|n| normalize(n)).collect();
+    
+    if first_all.iter().any(|name| !name.is_empty() && second_all.contains(name)) {
+        return 1.0;
+    }
+    
+    let (a, b) = (normalize(&first_name), normalize(&second_name));
+    if a.chars().count() >= 2 && b.chars().count() >= 2 && (a.contains(&b) || b.contains(&a)) {
+        return 0.8;
+    }
+    
+    let bigrams = |text: &str| -> Vec<(char, char)> {
+        let chars: Vec<char> = text.chars().collect();
+        chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+    };
+    let (a_bigrams, mut b_bigrams) = (bigrams(&a), bigrams(&b));
+    let total = a_bigrams.len() + b_bigrams.len();
+    if total == 0 {
+        return 0.0;
+    }
+    let mut shared = 0;
+    for bigram in &a_bigrams {
+        if let Some(position) = b_bigrams.iter().position(|other| other == bigram) {
+            b_bigrams.swap_remove(position);
+            shared += 1;
+        }
+    }
+    (2 * shared) as f64 / total as f64
+}
+
+/// 兩個角色都有設定的屬性中，值相同（忽略大小寫與前後空白）的比例
+fn attribute_overlap(first: Option<&str>, second: Option<&str>) -> Option<f64> {
+    let parse = |attributes: Option<&str>| match attributes.map(serde_json::from_str::<serde_json::Value>) {
+        Some(Ok(serde_json::Value::Object(object))) => Some(object),
+        _ => None,
+    };
+    let (first, second) = (parse(first)?, parse(second)?);
+    let normalize = |value: &serde_json::Value| match value {
+        serde_json::Value::String(text) => text.trim().to_lowercase(),
+        other => other.to_string(),
+    };
+    
+    let shared_keys: Vec<&String> = first
+        .keys()
+        .filter(|key| !ALIAS_ATTRIBUTE_KEYS.contains(&key.as_str()) && second.contains_key(*key))
+        .collect();
+    if shared_keys.is_empty() {
+        return None;
+    }
+    let matching = shared_keys
+        .iter()
+        .filter(|key| normalize(&first[key.as_str()]) == normalize(&second[key.as_str()]))
+        .count();
+    Some(matching as f64 / shared_keys.len() as f64)
+}
+
+/// 合併重複角色：將 `merge_id` 的關係、分析、插畫、視覺特徵與出場記錄改指向 `keep_id`，再刪除 `merge_id`
+///
+/// 全部在同一交易中完成；保留角色缺少的描述與屬性會由被合併角色補上，
+/// 被合併角色的名稱會加入保留角色的別名。
+#[tauri::command]
+pub async fn merge_characters(keep_id: String, merge_id: String) -> Result<MergeCharactersResult, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let result = merge_character_records(&mut conn, &keep_id, &merge_id)?;
+    
+    log::info!(
+        "合併角色成功: {} → {}（關係 {} 筆、分析 {} 筆、插畫 {} 筆）",
+        merge_id, keep_id, result.relationships_moved, result.analyses_moved, result.illustrations_moved
+    );
+    Ok(result)
+}
+
+fn merge_character_records(
+    conn: &mut rusqlite::Connection,
+    keep_id: &str,
+    merge_id: &str,
+) -> Result<MergeCharactersResult, String> {
+    if keep_id == merge_id {
+        return Err("不能將角色與自己合併".to_string());
+    }
+    
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let load = |id: &str| {
+        tx.query_row(
+            "SELECT project_id, name, description, attributes FROM characters WHERE id = ?1",
+            [id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, Option<String>>(3)?)),
+        )
+        .map_err(|_| format!("角色不存在: {}", id))
+    };
+    let (keep_project, keep_name, keep_description, keep_attributes) = load(keep_id)?;
+    let (merge_project, merge_name, merge_description, merge_attributes) = load(merge_id)?;
+    if keep_project != merge_project {
+        return Err("只能合併同一專案中的角色".to_string());
+    }
+    
+    let db_error = |e: rusqlite::Error| format!("合併角色失敗: {}", e);
+    let mut result = MergeCharactersResult {
+        keep_id: keep_id.to_string(),
+        merged_id: merge_id.to_string(),
+        ..Default::default()
+    };
+    
+    // 關係：改指向保留角色後，移除自我關係與重複關係（保留最早建立的一筆）
+    result.relationships_moved = tx
+        .execute("UPDATE character_relationships SET from_character_id = ?1 WHERE from_character_id = ?2", params![keep_id, merge_id])
+        .map_err(db_error)?
+        + tx
+            .execute("UPDATE character_relationships SET to_character_id = ?1 WHERE to_character_id = ?2", params![keep_id, merge_id])
+            .map_err(db_error)?;
+    result.relationships_removed = tx
+        .execute(
+            "DELETE FROM character_relationships
+             WHERE (from_character_id = ?1 AND to_character_id = ?1)
+                OR ((from_character_id = ?1 OR to_character_id = ?1) AND EXISTS (
+                    SELECT 1 FROM character_relationships other
+                    WHERE other.from_character_id = character_relationships.from_character_id
+                      AND other.to_character_id = character_relationships.to_character_id
+                      AND other.relationship_type = character_relationships.relationship_type
+                      AND (other.created_at < character_relationships.created_at
+                           OR (other.created_at = character_relationships.created_at AND other.rowid < character_relationships.rowid))
+                ))",
+            [keep_id],
+        )
+        .map_err(db_error)?;
+    // 配對的另一半被移除時，剩下的一筆改為單向關係
+    tx.execute(
+        "UPDATE character_relationships SET pair_id = NULL
+         WHERE pair_id IS NOT NULL
+           AND (SELECT COUNT(*) FROM character_relationships other WHERE other.pair_id = character_relationships.pair_id) < 2",
+        [],
+    )
+    .map_err(db_error)?;
+    
+    // 分析、插畫與待處理的分析任務
+    result.analyses_moved = tx
+        .execute("UPDATE character_analysis SET character_id = ?1 WHERE character_id = ?2", params![keep_id, merge_id])
+        .map_err(db_error)?;
+    result.illustrations_moved = tx
+        .execute("UPDATE illustration_generations SET character_id = ?1 WHERE character_id = ?2", params![keep_id, merge_id])
+        .map_err(db_error)?
+        + tx
+            .execute("UPDATE pollinations_generations SET character_id = ?1 WHERE character_id = ?2", params![keep_id, merge_id])
+            .map_err(db_error)?;
+    tx.execute(
+        "UPDATE analysis_queue SET target_id = ?1 WHERE analysis_type = 'character' AND target_id = ?2",
+        params![keep_id, merge_id],
+    )
+    .map_err(db_error)?;
+    tx.execute(
+        "UPDATE creative_suggestions SET target_id = ?1 WHERE target_type = 'character' AND target_id = ?2",
+        params![keep_id, merge_id],
+    )
+    .map_err(db_error)?;
+    tx.execute(
+        "DELETE FROM analysis_cache WHERE analysis_type = 'character' AND target_id LIKE ?1 || ':%'",
+        [merge_id],
+    )
+    .map_err(db_error)?;
+    
+    // 視覺特徵：保留角色沒有時才接手被合併角色的特徵
+    result.visual_traits_moved = tx
+        .execute("UPDATE OR IGNORE character_visual_traits SET character_id = ?1 WHERE character_id = ?2", params![keep_id, merge_id])
+        .map_err(db_error)?
+        > 0;
+    tx.execute("DELETE FROM character_visual_traits WHERE character_id = ?1", [merge_id]).map_err(db_error)?;
+    
+    // 出場記錄：同一章節的次數相加，首次出場取較早的位置
+    result.mentions_merged = tx
+        .execute(
+            "INSERT INTO character_chapter_mentions (character_id, chapter_id, mention_count, first_position, scanned_at)
+             SELECT ?1, chapter_id, mention_count, first_position, scanned_at FROM character_chapter_mentions WHERE character_id = ?2
+             ON CONFLICT(character_id, chapter_id) DO UPDATE SET
+                 mention_count = mention_count + excluded.mention_count,
+                 first_position = MIN(COALESCE(first_position, excluded.first_position), COALESCE(excluded.first_position, first_position))",
+            params![keep_id, merge_id],
+        )
+        .map_err(db_error)?;
+    tx.execute("DELETE FROM character_chapter_mentions WHERE character_id = ?1", [merge_id]).map_err(db_error)?;
+    
+    // 補上保留角色缺少的描述與屬性，並把被合併角色的名稱記為別名
+    let description = keep_description
+        .filter(|description| !description.trim().is_empty())
+        .or(merge_description);
+    let attributes = merge_character_attributes(keep_attributes.as_deref(), merge_attributes.as_deref(), &keep_name, &merge_name);
+    tx.execute(
+        "UPDATE characters SET description = ?2, attributes = ?3, updated_at = ?4 WHERE id = ?1",
+        params![keep_id, description, attributes, Utc::now()],
+    )
+    .map_err(db_error)?;
+    
+    tx.execute("DELETE FROM characters WHERE id = ?1", [merge_id]).map_err(db_error)?;
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+/// 合併屬性：保留角色已有的鍵不變，缺少的由被合併角色補上，並將被合併角色的名稱加入 aliases
+fn merge_character_attributes(keep: Option<&str>, merge: Option<&str>, keep_name: &str, merge_name: &str) -> Option<String> {
+    let parse = |attributes: Option<&str>| match attributes.map(serde_json::from_str::<serde_json::Value>) {
+        Some(Ok(serde_json::Value::Object(object))) => Some(object),
+        _ => None,
+    };
+    let keep_object = parse(keep);
+    if keep.is_some_and(|attributes| !attributes.trim().is_empty()) && keep_object.is_none() {
+        // 無法解析的屬性保持原樣
+        return keep.map(str::to_string);
+    }
+    
+    let mut object = keep_object.unwrap_or_default();
+    for (key, value) in parse(merge).unwrap_or_default() {
+        object.entry(key).or_insert(value);
+    }
+    
+    let merge_name = merge_name.trim();
+    if !merge_name.is_empty() && merge_name != keep_name.trim() {
+        let aliases = object.entry("aliases").or_insert_with(|| serde_json::Value::Array(Vec::new()));
+        match aliases {
+            serde_json::Value::Array(list) if !list.iter().any(|alias| alias.as_str() == Some(merge_name)) => {
+                list.push(serde_json::Value::String(merge_name.to_string()));
+            }
+            serde_json::Value::String(list) if !list.split([',', '，', '、', '/']).any(|alias| alias.trim() == merge_name) => {
+                list.push_str(&format!("、{}", merge_name));
+            }
+            _ => {}
+        }
+    }
+    
+    (!object.is_empty()).then(|| serde_json::Value::Object(object).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    #[test]
+    fn test_normalize_relationship_type() {
+        assert_eq!(normalize_relationship_type("友人"), "朋友");
+        assert_eq!(normalize_relationship_type(" Friend "), "朋友");
+        assert_eq!(normalize_relationship_type("朋友"), "朋友");
+        assert_eq!(normalize_relationship_type("契約者"), "契約者");
+    }
+
+    #[test]
+    fn test_validate_attributes_json() {
+        assert!(validate_attributes_json(r#"{"age": 17, "gender": "女", "tags": ["劍士", "貴族"], "alive": true}"#).is_ok());
+        assert!(validate_attributes_json("").is_ok());
+        assert!(validate_attributes_json("{age: 17").is_err());
+        assert!(validate_attributes_json(r#"["劍士"]"#).is_err());
+        assert!(validate_attributes_json(r#"{"stats": {"str": 10}}"#).unwrap_err().contains("stats"));
+    }
+
+    #[test]
+    fn test_count_mentions_prefers_longest_name() {
+        let characters = vec![
+            ("a".to_string(), "小明".to_string(), None),
+            ("b".to_string(), "小明月".to_string(), None),
+            ("c".to_string(), "艾莉絲·格雷".to_string(), Some(r#"{"aliases": ["小艾"]}"#.to_string())),
+            ("d".to_string(), "Al".to_string(), None),
+        ];
+        let patterns = build_mention_patterns(&characters);
+        let mentions = count_mentions("小明月看著小明。艾莉絲·格雷笑了，小艾和艾莉絲都點頭。Alice 向 Al 揮手", &patterns);
+
+        assert_eq!(mentions["a"], (1, 5));
+        assert_eq!(mentions["b"], (1, 0));
+        assert_eq!(mentions["c"].0, 3);
+        assert_eq!(mentions["d"].0, 1);
+
+        let mut conn = migrated_conn_with_project();
+        conn.execute_batch(
+            r#"INSERT INTO characters (id, project_id, name) VALUES ('a', 'p1', '小明');
+               INSERT INTO chapters (id, project_id, title, content, order_index)
+                   VALUES ('c1', 'p1', '第一章', '[{"type":"paragraph","children":[{"text":"小明與小明"}]}]', 0);"#,
+        ).unwrap();
+        let summaries = scan_character_mention_records(&mut conn, "p1").unwrap();
+        assert_eq!(summaries[0].total_mentions, 2);
+        let stored: i64 = conn
+            .query_row("SELECT mention_count FROM character_chapter_mentions WHERE character_id = 'a'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, 2);
+    }
+
+    #[test]
+    fn test_bidirectional_relationship_pair() {
+        let mut conn = migrated_conn_with_project();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name) VALUES ('a', 'p1', '甲');
+             INSERT INTO characters (id, project_id, name) VALUES ('b', 'p1', '乙');
+             INSERT INTO characters (id, project_id, name) VALUES ('c', 'p1', '丙');",
+        ).unwrap();
+
+        let pair = insert_relationship_records(&mut conn, "a", "b", "師父", None, true).unwrap();
+        let single = insert_relationship_records(&mut conn, "a", "c", "朋友", None, false).unwrap();
+
+        let reverse_type: String = conn
+            .query_row("SELECT relationship_type FROM character_relationships WHERE from_character_id = 'b'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(reverse_type, "弟子");
+
+        assert_eq!(delete_relationship_records(&conn, &pair).unwrap(), 2);
+        assert_eq!(delete_relationship_records(&conn, &single).unwrap(), 1);
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM character_relationships", [], |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[test]
+    fn test_relationship_rejects_self_and_duplicates() {
+        let mut conn = migrated_conn_with_project();
+        conn.execute_batch(
+            "INSERT INTO characters (id, project_id, name) VALUES ('a', 'p1', '甲');
+             INSERT INTO characters (id, project_id, name) VALUES ('b', 'p1', '乙');
+             INSERT INTO characters (id, project_id, name) VALUES ('c', 'p1', '丙');",
+        ).unwrap();
+
+        assert!(insert_relationship_records(&mut conn, "a", "a", "朋友", None, false).is_err());
+        insert_relationship_records(&mut conn, "a", "b", "朋友", None, false).unwrap();
+        let duplicate = insert_relationship_records(&mut conn, "a", "b", "朋友", None, false).unwrap_err();
+        assert!(duplicate.contains("已存在"));
+        insert_relationship_records(&mut conn, "b", "a", "朋友", None, false).unwrap();
+        insert_relationship_records(&mut conn, "c", "a", "敵人", None, false).unwrap();
+        insert_relationship_records(&mut conn, "b", "c", "同學", None, true).unwrap();
+        insert_relationship_records(&mut conn, "b", "c", "鄰居", None, true).unwrap();
+        // 雙向建立時反向重複也要整筆回滾
+        assert!(insert_relationship_records(&mut conn, "a", "c", "敵人", None, true).is_err());
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM character_relationships", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 7);
+
+        let graph = build_relationship_graph(&conn, "p1").unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.edges.len(), 7);
+        assert_eq!(graph.adjacency["a"], vec!["b".to_string()]);
+        let one_way = graph.edges.iter().find(|e| e.from == "c" && e.relationship_type == "敵人").unwrap();
+        assert!(!one_way.reciprocated);
+    }
+
+    #[test]
+    fn test_find_duplicate_pairs_uses_names_and_attributes() {
+        let characters = vec![
+            ("a".to_string(), "艾莉絲·格雷".to_string(), Some(r#"{"gender": "女", "age": 17}"#.to_string())),
+            ("b".to_string(), "艾莉絲".to_string(), Some(r#"{"gender": "女", "age": 17}"#.to_string())),
+            ("c".to_string(), "小艾".to_string(), Some(r#"{"aliases": ["艾莉絲·格雷"]}"#.to_string())),
+            ("d".to_string(), "艾倫".to_string(), Some(r#"{"gender": "男"}"#.to_string())),
+            ("e".to_string(), "布魯斯".to_string(), None),
+        ];
+        let pairs = find_duplicate_pairs(&characters);
+        let ids: Vec<(&str, &str)> = pairs.iter().map(|p| (p.first_id.as_str(), p.second_id.as_str())).collect();
+
+        assert_eq!(ids, vec![("a", "b"), ("a", "c")]);
+        assert_eq!(pairs[0].attribute_overlap, Some(1.0));
+        assert_eq!(pairs[1].name_similarity, 1.0);
+    }
+
+    #[test]
+    fn test_merge_characters_repoints_references() {
+        let mut conn = migrated_conn_with_project();
+        crate::database::connection::enable_foreign_keys(&conn).unwrap();
+        conn.execute_batch(
+            r#"INSERT INTO characters (id, project_id, name, attributes) VALUES ('keep', 'p1', '艾莉絲', '{"age": 17}');
+               INSERT INTO characters (id, project_id, name, description, attributes) VALUES ('dup', 'p1', '艾莉絲·格雷', '劍士', '{"age": 18, "gender": "女"}');
+               INSERT INTO characters (id, project_id, name) VALUES ('b', 'p1', '布魯斯');
+               INSERT INTO chapters (id, project_id, title, order_index) VALUES ('c1', 'p1', '第一章', 0);
+               INSERT INTO character_chapter_mentions (character_id, chapter_id, mention_count, first_position) VALUES ('keep', 'c1', 2, 10);
+               INSERT INTO character_chapter_mentions (character_id, chapter_id, mention_count, first_position) VALUES ('dup', 'c1', 3, 4);
+               INSERT INTO character_visual_traits (character_id, seed_value, art_style_params) VALUES ('dup', 42, '{}');"#,
+        ).unwrap();
+        insert_relationship_records(&mut conn, "keep", "b", "朋友", None, true).unwrap();
+        insert_relationship_records(&mut conn, "dup", "b", "朋友", None, false).unwrap();
+        insert_relationship_records(&mut conn, "dup", "keep", "家人", None, false).unwrap();
+
+        assert!(merge_character_records(&mut conn, "keep", "keep").is_err());
+        let result = merge_character_records(&mut conn, "keep", "dup").unwrap();
+        assert_eq!((result.relationships_moved, result.relationships_removed), (2, 2));
+        assert!(result.visual_traits_moved);
+
+        let relationships: i64 = conn.query_row("SELECT COUNT(*) FROM character_relationships", [], |row| row.get(0)).unwrap();
+        assert_eq!(relationships, 2);
+        let (count, first): (i64, i64) = conn
+            .query_row("SELECT mention_count, first_position FROM character_chapter_mentions WHERE character_id = 'keep'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!((count, first), (5, 4));
+        let (description, attributes): (String, String) = conn
+            .query_row("SELECT description, attributes FROM characters WHERE id = 'keep'", [], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        assert_eq!(description, "劍士");
+        let attributes: serde_json::Value = serde_json::from_str(&attributes).unwrap();
+        assert_eq!(attributes["age"], 17);
+        assert_eq!(attributes["gender"], "女");
+        assert_eq!(attributes["aliases"][0], "艾莉絲·格雷");
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM characters WHERE id = 'dup'", [], |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 0);
+    }
+}
@@ -1,42 +1,51 @@
-use crate::database::{get_db, models::*};
+use crate::database::{get_db_conn, models::*};
+use crate::commands::character::normalize_relationship_type;
+use crate::commands::settings::{read_project_setting, SYSTEM_PROMPT_OVERRIDE_KEY};
 use crate::utils::language_purity::LanguagePurityEnforcer;
+use crate::utils::slate::{count_text, slate_to_plain_text};
 use rusqlite::Result as SqliteResult;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use tauri::command;
 
+/// 將角色屬性值轉為顯示文字（字串、數字、布林與簡單陣列）
+fn attribute_value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(if *b { "是" } else { "否" }.to_string()),
+        Value::Array(items) => Some(
+            items.iter().filter_map(attribute_value_text).collect::<Vec<_>>().join("、"),
+        ),
+        _ => None,
+    }
+}
+
+/// 讀取 metadata 物件中的非空筆記
+fn notes_from_metadata(metadata: &Value) -> Option<String> {
+    metadata
+        .get("notes")
+        .and_then(Value::as_str)
+        .filter(|notes| !notes.trim().is_empty())
+        .map(str::to_string)
+}
+
 /// 從章節內容中提取筆記
 fn extract_chapter_notes(content_json: &str) -> Option<String> {
     // 嘗試解析章節內容的 JSON
     if let Ok(content) = serde_json::from_str::<Value>(content_json) {
         // 查找 metadata.notes
-        if let Some(obj) = content.as_object() {
-            if let Some(metadata) = obj.get("metadata") {
-                if let Some(notes) = metadata.get("notes") {
-                    if let Some(notes_str) = notes.as_str() {
-                        if !notes_str.trim().is_empty() {
-                            log::info!("✅ 找到章節筆記，長度: {} 字符", notes_str.len());
-                            return Some(notes_str.to_string());
-                        }
-                    }
-                }
-            }
+        if let Some(notes) = content.get("metadata").and_then(notes_from_metadata) {
+            log::info!("✅ 找到章節筆記，長度: {} 字符", notes.len());
+            return Some(notes);
         }
         
         // 如果是陣列格式，檢查每個元素
         if let Some(array) = content.as_array() {
             for item in array {
-                if let Some(obj) = item.as_object() {
-                    if let Some(metadata) = obj.get("metadata") {
-                        if let Some(notes) = metadata.get("notes") {
-                            if let Some(notes_str) = notes.as_str() {
-                                if !notes_str.trim().is_empty() {
-                                    log::info!("✅ 在陣列中找到章節筆記，長度: {} 字符", notes_str.len());
-                                    return Some(notes_str.to_string());
-                                }
-                            }
-                        }
-                    }
+                if let Some(notes) = item.get("metadata").and_then(notes_from_metadata) {
+                    log::info!("✅ 在陣列中找到章節筆記，長度: {} 字符", notes.len());
+                    return Some(notes);
                 }
             }
         }
@@ -46,6 +55,19 @@ fn extract_chapter_notes(content_json: &str) -> Option<String> {
     None
 }
 
+/// 取得章節筆記：優先讀取 chapters.metadata 欄位的 notes，舊資料再從章節內容中提取
+fn chapter_notes(chapter: &Chapter) -> Option<String> {
+    let from_column = chapter
+        .metadata
+        .as_deref()
+        .and_then(|metadata| serde_json::from_str::<Value>(metadata).ok())
+        .and_then(|metadata| notes_from_metadata(&metadata));
+    if from_column.is_some() {
+        return from_column;
+    }
+    chapter.content.as_deref().and_then(extract_chapter_notes)
+}
+
 /// 字符清理函數 - 保留合法的文字字符，包括中文
 fn clean_text(text: &str) -> String {
     text.chars()
@@ -61,29 +83,286 @@ fn clean_text(text: &str) -> String {
         .collect()
 }
 
+/// 讀取專案資訊
+fn load_project(conn: &rusqlite::Connection, project_id: &str) -> Result<Project, String> {
+    conn.query_row(
+        "SELECT id, name, description, type, novel_length, settings, created_at, updated_at, is_archived FROM projects WHERE id = ?",
+        [project_id],
+        |row| Ok(Project {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            r#type: row.get(3)?,
+            novel_length: row.get(4)?,
+            settings: row.get(5)?,
+            created_at: row.get(6)?,
+            updated_at: row.get(7)?,
+            is_archived: row.get(8)?,
+        })
+    )
+    .map_err(|e| format!("獲取專案失敗: {}", e))
+}
+
+/// 讀取章節
+fn load_chapter(conn: &rusqlite::Connection, chapter_id: &str) -> Result<Chapter, String> {
+    conn.query_row(
+        "SELECT id, project_id, title, content, order_index, chapter_number, metadata, created_at, updated_at FROM chapters WHERE id = ?",
+        [chapter_id],
+        |row| Ok(Chapter {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get(3)?,
+            order_index: row.get(4)?,
+            chapter_number: row.get(5)?,
+            metadata: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    )
+    .map_err(|e| format!("獲取章節失敗: {}", e))
+}
+
+/// 讀取專案的所有角色
+fn load_characters(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<Character>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, project_id, name, description, attributes, avatar_url, created_at, updated_at FROM characters WHERE project_id = ?")
+        .map_err(|e| e.to_string())?;
+    
+    let characters = stmt
+        .query_map([project_id], |row| {
+            Ok(Character {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                name: row.get(2)?,
+                description: row.get(3)?,
+                attributes: row.get(4)?,
+                avatar_url: row.get(5)?,
+                created_at: row.get(6)?,
+                updated_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(characters)
+}
+
+/// 角色關係（起點名稱、終點名稱、關係類型、描述）
+type RelationshipRow = (String, String, String, Option<String>);
+
+/// 讀取專案的角色關係
+fn load_relationships(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<RelationshipRow>, String> {
+    let mut stmt = conn
+        .prepare("
+            SELECT cr.*, c1.name as from_name, c2.name as to_name 
+            FROM character_relationships cr
+            JOIN characters c1 ON cr.from_character_id = c1.id
+            JOIN characters c2 ON cr.to_character_id = c2.id
+            WHERE c1.project_id = ?
+        ")
+        .map_err(|e| e.to_string())?;
+    
+    let relationships = stmt
+        .query_map([project_id], |row| {
+            Ok((
+                row.get::<_, String>("from_name")?,
+                row.get::<_, String>("to_name")?,
+                row.get::<_, String>("relationship_type")?,
+                row.get::<_, Option<String>>("description")?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(relationships)
+}
+
+/// 加入【故事背景】區塊（書名、簡介、類型）
+fn push_story_background(context: &mut String, project: &Project) {
+    context.push_str("【故事背景】\n");
+    context.push_str(&format!("書名：{}\n", clean_text(&project.name)));
+    if let Some(desc) = &project.description {
+        context.push_str(&format!("簡介：{}\n", clean_text(desc)));
+    }
+    if let Some(project_type) = &project.r#type {
+        context.push_str(&format!("類型：{}\n", clean_text(project_type)));
+    }
+    context.push('\n');
+}
+
+/// 加入【角色設定】與【角色關係】區塊
+fn push_character_section(
+    context: &mut String,
+    characters: &[Character],
+    relationships: &[RelationshipRow],
+) {
+    if characters.is_empty() {
+        return;
+    }
+    context.push_str("【角色設定】\n");
+    for character in characters {
+        context.push_str(&format!("◆ {}\n", character.name));
+        if let Some(desc) = &character.description {
+            context.push_str(&format!("  描述：{}\n", desc));
+        }
+        if let Some(attrs) = &character.attributes {
+            // 解析 JSON 屬性
+            if let Ok(attrs_json) = serde_json::from_str::<serde_json::Value>(attrs) {
+                if let Some(obj) = attrs_json.as_object() {
+                    for (key, value) in obj {
+                        if let Some(v) = attribute_value_text(value) {
+                            if !v.is_empty() {
+                                context.push_str(&format!("  {}：{}\n", key, v));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    
+    // 添加角色關係
+    if !relationships.is_empty() {
+        context.push_str("\n【角色關係】\n");
+        for (from, to, rel_type, desc) in relationships {
+            // 舊資料可能使用別名，顯示時統一為標準名稱
+            let rel_type = normalize_relationship_type(rel_type);
+            context.push_str(&format!("- {} 與 {} 的關係：{}", from, to, rel_type));
+            if let Some(d) = desc {
+                context.push_str(&format!("（{}）", d));
+            }
+            context.push('\n');
+        }
+    }
+    context.push('\n');
+}
+
+/// 句子結束符號（截取前後文時用來對齊句子邊界）
+fn is_sentence_boundary(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '\n' | '。' | '！' | '？')
+}
+
+/// 取文字結尾最多 max_chars 字，超過時從第一個句子邊界之後開始；回傳（片段, 是否截斷）
+fn text_tail(text: &str, max_chars: usize) -> (String, bool) {
+    let chars: Vec<char> = text.trim().chars().collect();
+    if chars.len() <= max_chars {
+        return (chars.into_iter().collect(), false);
+    }
+    let tail = &chars[chars.len() - max_chars..];
+    let start = tail
+        .iter()
+        .position(|c| is_sentence_boundary(*c))
+        .map(|i| i + 1)
+        .filter(|i| *i < tail.len())
+        .unwrap_or(0);
+    (tail[start..].iter().collect::<String>().trim_start().to_string(), true)
+}
+
+/// 讀取目標章節之前的章節（由近到遠），回傳（標題, 純文字內容）
+fn load_previous_chapters(conn: &rusqlite::Connection, chapter: &Chapter, limit: usize) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT title, content FROM chapters
+             WHERE project_id = ?1 AND order_index < ?2
+             ORDER BY order_index DESC LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+    let chapters = stmt
+        .query_map(
+            rusqlite::params![chapter.project_id, chapter.order_index, limit as i64],
+            |row| {
+                let content: Option<String> = row.get(1)?;
+                Ok((row.get::<_, String>(0)?, slate_to_plain_text(content.as_deref().unwrap_or(""))))
+            },
+        )
+        .map_err(|e| e.to_string())?
+        .collect::<SqliteResult<Vec<_>>>()
+        .map_err(|e| e.to_string())?;
+    Ok(chapters)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ContextStats {
     pub total_characters: usize,
     pub estimated_tokens: usize,
     pub chapter_count: usize,
     pub character_count: usize,
+    /// 是否為逐章解析 Slate JSON 的精確計算（否則為 SQL 估算）
+    pub accurate: bool,
+}
+
+/// 自訂系統提示的套用方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SystemPromptOverrideMode {
+    /// 取代內建續寫指令
+    #[default]
+    Replace,
+    /// 加在內建續寫指令之前
+    Prepend,
+}
+
+/// 自訂系統提示（專案設定 system_prompt_override 或呼叫端傳入）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemPromptOverride {
+    pub prompt: String,
+    #[serde(default)]
+    pub mode: SystemPromptOverrideMode,
+}
+
+impl SystemPromptOverride {
+    /// 解析設定值：JSON 物件 {"prompt", "mode"}，非 JSON 的純文字視為取代內建指令
+    pub fn from_setting(raw: &str) -> Option<Self> {
+        let parsed = serde_json::from_str::<Self>(raw).unwrap_or_else(|_| Self {
+            prompt: raw.to_string(),
+            mode: SystemPromptOverrideMode::Replace,
+        });
+        if parsed.prompt.trim().is_empty() {
+            None
+        } else {
+            Some(parsed)
+        }
+    }
 }
 
 /// 系統提示建構器 - 分離固定指令以節省 token（簡化版）
 #[derive(Debug, Clone)]
 pub struct SystemPromptBuilder {
     pub project_type: Option<String>,
+    pub prompt_override: Option<SystemPromptOverride>,
 }
 
 impl SystemPromptBuilder {
     pub fn new(project_type: Option<String>) -> Self {
-        Self { project_type }
+        Self { project_type, prompt_override: None }
     }
 
-    /// 建構系統提示，專注於繁體中文小說續寫
+    /// 套用自訂系統提示（空白內容視為未設定）
+    pub fn with_override(mut self, prompt_override: Option<SystemPromptOverride>) -> Self {
+        self.prompt_override = prompt_override.filter(|o| !o.prompt.trim().is_empty());
+        self
+    }
+
+    /// 建構系統提示，專注於繁體中文小說續寫；自訂提示同樣會經過語言純度強化
     pub fn build_system_prompt(&self) -> String {
         let enforcer = LanguagePurityEnforcer::new();
-        
+        let default_instructions = self.default_instructions();
+
+        let instructions = match &self.prompt_override {
+            None => default_instructions,
+            Some(o) => match o.mode {
+                SystemPromptOverrideMode::Replace => o.prompt.trim().to_string(),
+                SystemPromptOverrideMode::Prepend => format!("{}\n\n{}", o.prompt.trim(), default_instructions),
+            },
+        };
+
+        // 使用語言純度增強器生成強化的系統提示
+        enforcer.generate_enhanced_system_prompt(&instructions)
+    }
+
+    /// 內建續寫指令（未經語言純度強化），供介面作為自訂提示的編輯起點
+    pub fn default_instructions(&self) -> String {
         let base_instructions = "你是一個專業的中文小說續寫助手。你的任務是根據提供的上下文資訊，在指定位置插入合適的續寫內容。
 
 核心要求:
@@ -124,12 +403,16 @@ impl SystemPromptBuilder {
             ""
         };
 
-        // 使用語言純度增強器生成強化的系統提示
-        let enhanced_prompt = format!("{}{}", base_instructions, genre_specific);
-        enforcer.generate_enhanced_system_prompt(&enhanced_prompt)
+        format!("{}{}", base_instructions, genre_specific)
     }
 }
 
+/// 讀取專案的自訂系統提示設定（未設定或內容空白時回傳 None）
+fn project_system_prompt_override(conn: &rusqlite::Connection, project_id: &str) -> Result<Option<SystemPromptOverride>, String> {
+    Ok(read_project_setting(conn, project_id, SYSTEM_PROMPT_OVERRIDE_KEY)?
+        .and_then(|raw| SystemPromptOverride::from_setting(&raw)))
+}
+
 /// 用戶上下文建構器 - 精簡的內容上下文
 #[derive(Debug)]
 pub struct UserContextBuilder {
@@ -174,7 +457,7 @@ impl UserContextBuilder {
                         if let Some(obj) = attrs_json.as_object() {
                             let mut key_attrs = Vec::new();
                             for (key, value) in obj.iter().take(3) { // 最多顯示3個屬性
-                                if let Some(v) = value.as_str() {
+                                if let Some(v) = attribute_value_text(value) {
                                     if !v.is_empty() && v.len() < 30 {
                                         key_attrs.push(format!("{}:{}", key, v));
                                     }
@@ -193,6 +476,14 @@ impl UserContextBuilder {
         context.push_str(&format!("\nChapter: {}\n", clean_text(&self.chapter.title)));
         context.push_str("Content:\n");
         context.push_str(&self.extract_relevant_content());
+        
+        // 章節筆記 - 與 build_context 一致，避免切換到分離模式後遺失作者筆記
+        if let Some(notes) = chapter_notes(&self.chapter) {
+            context.push_str("\n【章節筆記】\n作者筆記：");
+            context.push_str(&clean_text(&notes));
+            context.push('\n');
+        }
+        
         context.push_str("\n[CONTINUE HERE]");
         
         context
@@ -278,6 +569,44 @@ impl UserContextBuilder {
     }
 }
 
+/// 前文不足時最多往前補幾章
+const CONTEXT_PREVIOUS_CHAPTERS: usize = 3;
+/// 前文缺口小於此字數時不補前章內容
+const MIN_PREVIOUS_FILL_CHARS: usize = 100;
+
+/// 以前幾章的結尾填補前文缺口（previous_chapters 由近到遠排列），並標示章節邊界
+fn previous_chapter_fill(previous_chapters: &[(String, String)], budget: usize) -> Option<String> {
+    let mut remaining = budget;
+    let mut tails = Vec::new();
+    for (index, (title, text)) in previous_chapters.iter().enumerate() {
+        if remaining < MIN_PREVIOUS_FILL_CHARS {
+            break;
+        }
+        let (tail, truncated) = text_tail(&clean_text(text), remaining);
+        if tail.is_empty() {
+            continue;
+        }
+        remaining = remaining.saturating_sub(tail.chars().count());
+        tails.push((index, title, tail, truncated));
+    }
+    if tails.is_empty() {
+        return None;
+    }
+    
+    let mut fill = String::new();
+    for (index, title, tail, truncated) in tails.into_iter().rev() {
+        let label = if index == 0 { "上一章結尾" } else { "更早章節結尾" };
+        fill.push_str(&format!("（{}：{}）\n", label, clean_text(title)));
+        if truncated {
+            fill.push_str("...");
+        }
+        fill.push_str(&tail);
+        fill.push_str("\n\n");
+    }
+    fill.push_str("（本章開始）\n");
+    Some(fill)
+}
+
 /// 構建 AI 續寫的上下文（簡化版 - 向後兼容）
 #[command]
 pub async fn build_context(
@@ -288,107 +617,16 @@ pub async fn build_context(
 ) -> Result<String, String> {
     log::info!("構建上下文 - 專案: {}, 章節: {}, 位置: {} (簡化版)", project_id, chapter_id, position);
     
-    let db = get_db().map_err(|e| e.to_string())?;
-    
-    // 更好地處理 poisoned lock 錯誤
-    let conn = match db.lock() {
-        Ok(conn) => conn,
-        Err(poisoned) => {
-            log::error!("資料庫鎖被 poisoned，嘗試恢復: {}", poisoned);
-            // 嘗試恢復 poisoned lock
-            poisoned.into_inner()
-        }
-    };
-    
-    // 1. 獲取專案資訊
-    let project: Project = conn
-        .query_row(
-            "SELECT id, name, description, type, novel_length, settings, created_at, updated_at FROM projects WHERE id = ?",
-            [&project_id],
-            |row| Ok(Project {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                r#type: row.get(3)?,
-                novel_length: row.get(4)?,
-                settings: row.get(5)?,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        )
-        .map_err(|e| format!("獲取專案失敗: {}", e))?;
-    
-    // 2. 獲取當前章節內容
-    let chapter: Chapter = conn
-        .query_row(
-            "SELECT id, project_id, title, content, order_index, chapter_number, metadata, created_at, updated_at FROM chapters WHERE id = ?",
-            [&chapter_id],
-            |row| Ok(Chapter {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                title: row.get(2)?,
-                content: row.get(3)?,
-                order_index: row.get(4)?,
-                chapter_number: row.get(5)?,
-                metadata: row.get(6)?,
-                created_at: row.get(7)?,
-                updated_at: row.get(8)?,
-            })
-        )
-        .map_err(|e| format!("獲取章節失敗: {}", e))?;
-    
-    // 3. 獲取專案的所有角色
-    let mut stmt = conn
-        .prepare("SELECT id, project_id, name, description, attributes, avatar_url, created_at, updated_at FROM characters WHERE project_id = ?")
-        .map_err(|e| e.to_string())?;
-    
-    let characters: Vec<Character> = stmt
-        .query_map([&project_id], |row| {
-            Ok(Character {
-                id: row.get(0)?,
-                project_id: row.get(1)?,
-                name: row.get(2)?,
-                description: row.get(3)?,
-                attributes: row.get(4)?,
-                avatar_url: row.get(5)?,
-                created_at: row.get(6)?,
-                updated_at: row.get(7)?,
-            })
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<SqliteResult<Vec<_>>>()
-        .map_err(|e| e.to_string())?;
-    
-    // 4. 獲取角色關係
-    let mut stmt = conn
-        .prepare("
-            SELECT cr.*, c1.name as from_name, c2.name as to_name 
-            FROM character_relationships cr
-            JOIN characters c1 ON cr.from_character_id = c1.id
-            JOIN characters c2 ON cr.to_character_id = c2.id
-            WHERE c1.project_id = ?
-        ")
-        .map_err(|e| e.to_string())?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
-    let relationships: Vec<(String, String, String, Option<String>)> = stmt
-        .query_map([&project_id], |row| {
-            Ok((
-                row.get::<_, String>("from_name")?,
-                row.get::<_, String>("to_name")?,
-                row.get::<_, String>("relationship_type")?,
-                row.get::<_, Option<String>>("description")?,
-            ))
-        })
-        .map_err(|e| e.to_string())?
-        .collect::<SqliteResult<Vec<_>>>()
-        .map_err(|e| e.to_string())?;
+    // 1-4. 獲取專案、章節、角色與角色關係
+    let project = load_project(&conn, &project_id)?;
+    let chapter = load_chapter(&conn, &chapter_id)?;
+    let characters = load_characters(&conn, &project_id)?;
+    let relationships = load_relationships(&conn, &project_id)?;
     
     // 5. 提取章節筆記
-    let chapter_notes = if let Some(content) = &chapter.content {
-        extract_chapter_notes(content)
-    } else {
-        None
-    };
+    let chapter_notes = chapter_notes(&chapter);
     
     // 6. 構建上下文
     let mut context = String::new();
@@ -408,15 +646,8 @@ pub async fn build_context(
             .collect()
     }
     
-    // 使用簡化的繁體中文標籤
+    // 使用簡化的繁體中文標籤（故事背景與角色段落的標籤在各自的 push_* 函數中）
     let labels = (
-        "【故事背景】",           // story_background
-        "書名：",                // book_name
-        "簡介：",                // description
-        "類型：",                // genre
-        "【角色設定】",          // character_settings
-        "  描述：",              // character_description
-        "【角色關係】",          // character_relationships
         "【當前章節】",          // current_chapter
         "章節標題：",            // chapter_title
         "內容：",                // content
@@ -428,63 +659,16 @@ pub async fn build_context(
     );
     
     // 添加專案背景
-    context.push_str(labels.0); // story_background
-    context.push_str("\n");
-    context.push_str(&format!("{}{}\n", labels.1, clean_text(&project.name))); // book_name
-    if let Some(desc) = &project.description {
-        context.push_str(&format!("{}{}\n", labels.2, clean_text(desc))); // description
-    }
-    if let Some(project_type) = &project.r#type {
-        context.push_str(&format!("{}{}\n", labels.3, clean_text(project_type))); // genre
-    }
-    context.push_str("\n");
+    push_story_background(&mut context, &project);
     
-    // 添加角色設定
-    if !characters.is_empty() {
-        context.push_str(labels.4); // character_settings
-        context.push_str("\n");
-        for character in &characters {
-            context.push_str(&format!("◆ {}\n", character.name));
-            if let Some(desc) = &character.description {
-                context.push_str(&format!("{}{}\n", labels.5, desc)); // character_description
-            }
-            if let Some(attrs) = &character.attributes {
-                // 解析 JSON 屬性
-                if let Ok(attrs_json) = serde_json::from_str::<serde_json::Value>(attrs) {
-                    if let Some(obj) = attrs_json.as_object() {
-                        for (key, value) in obj {
-                            if let Some(v) = value.as_str() {
-                                if !v.is_empty() {
-                                    context.push_str(&format!("  {}：{}\n", key, v));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        // 添加角色關係
-        if !relationships.is_empty() {
-            context.push_str("\n");
-            context.push_str(labels.6); // character_relationships
-            context.push_str("\n");
-            for (from, to, rel_type, desc) in &relationships {
-                context.push_str(&format!("- {} 與 {} 的關係：{}", from, to, rel_type));
-                if let Some(d) = desc {
-                    context.push_str(&format!("（{}）", d));
-                }
-                context.push_str("\n");
-            }
-        }
-        context.push_str("\n");
-    }
+    // 添加角色設定與角色關係
+    push_character_section(&mut context, &characters, &relationships);
     
     // 添加當前章節內容（包含游標前後的內容）
-    context.push_str(labels.7); // current_chapter
+    context.push_str(labels.0); // current_chapter
     context.push_str("\n");
-    context.push_str(&format!("{}{}\n", labels.8, clean_text(&chapter.title))); // chapter_title
-    context.push_str(labels.9); // content
+    context.push_str(&format!("{}{}\n", labels.1, clean_text(&chapter.title))); // chapter_title
+    context.push_str(labels.2); // content
     context.push_str("\n");
     
     if let Some(content) = &chapter.content {
@@ -515,19 +699,29 @@ pub async fn build_context(
                 .map(|i| i + 1)
                 .unwrap_or(0);
             
-            context.push_str(labels.10); // previous_content_omitted
+            context.push_str(labels.3); // previous_content_omitted
             context.push_str("\n\n");
             // 安全地截取調整後的內容
             let final_chars: Vec<char> = remaining_text.chars().collect();
             let final_text: String = final_chars[adjusted_start.min(final_chars.len())..].iter().collect();
             context.push_str(&final_text);
         } else {
+            // 游標接近章節開頭時，以前幾章的結尾補足前文
+            let plain_before = slate_to_plain_text(content).chars().take(char_position).count();
+            let fill_budget = MAX_CONTEXT_CHARS.saturating_sub(plain_before);
+            if fill_budget >= MIN_PREVIOUS_FILL_CHARS {
+                let previous_chapters = load_previous_chapters(&conn, &chapter, CONTEXT_PREVIOUS_CHAPTERS)?;
+                if let Some(previous) = previous_chapter_fill(&previous_chapters, fill_budget) {
+                    log::info!("本章前文不足，已補入前幾章結尾 {} 字符", previous.chars().count());
+                    context.push_str(&previous);
+                }
+            }
             context.push_str(&cleaned_before);
         }
         
         // 添加游標位置標記
         context.push_str("\n\n");
-        context.push_str(labels.11); // insert_continuation_here
+        context.push_str(labels.4); // insert_continuation_here
         context.push_str("\n\n");
         
         // 處理游標後的內容（如果有的話，顯示一小部分讓 AI 知道後續內容）
@@ -546,17 +740,17 @@ pub async fn build_context(
                     .rfind(|c| c == '.' || c == '!' || c == '?' || c == '\n')
                     .unwrap_or(truncated_after.len());
                 
-                context.push_str(labels.12); // existing_content_after
+                context.push_str(labels.5); // existing_content_after
                 context.push_str("\n");
                 // 安全地截取到 end_pos
                 let final_chars: Vec<char> = truncated_after.chars().collect();
                 let final_text: String = final_chars[..end_pos.min(final_chars.len())].iter().collect();
                 context.push_str(&final_text);
                 context.push_str("\n");
-                context.push_str(labels.13); // remaining_content_continues
+                context.push_str(labels.6); // remaining_content_continues
                 context.push_str("\n");
             } else {
-                context.push_str(labels.12); // existing_content_after
+                context.push_str(labels.5); // existing_content_after
                 context.push_str("\n");
                 context.push_str(&cleaned_after);
             }
@@ -574,7 +768,7 @@ pub async fn build_context(
     
     // 添加續寫指示
     context.push_str("\n\n");
-    context.push_str(labels.14); // writing_instructions
+    context.push_str(labels.7); // writing_instructions
     context.push_str("\n");
     
     // 添加繁體中文續寫要求（簡化版）
@@ -607,6 +801,96 @@ pub async fn build_context(
     Ok(context)
 }
 
+/// 續寫模式參考的前幾章數量
+const APPEND_PREVIOUS_CHAPTERS: usize = 2;
+/// 每個前章節取用的結尾字數
+const APPEND_PREVIOUS_TAIL_CHARS: usize = 300;
+/// 當前章節取用的結尾字數
+const APPEND_CURRENT_TAIL_CHARS: usize = 1200;
+
+/// 組合章節結尾續寫的上下文（previous_chapters 由近到遠排列）
+fn assemble_append_context(
+    project: &Project,
+    chapter: &Chapter,
+    characters: &[Character],
+    relationships: &[RelationshipRow],
+    previous_chapters: &[(String, String)],
+) -> String {
+    let mut context = String::new();
+    push_story_background(&mut context, project);
+    push_character_section(&mut context, characters, relationships);
+    
+    // 前幾章的結尾（依閱讀順序排列）
+    let previous: Vec<_> = previous_chapters
+        .iter()
+        .rev()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .collect();
+    if !previous.is_empty() {
+        context.push_str("【前情回顧】\n");
+        for (title, text) in previous {
+            let (tail, truncated) = text_tail(&clean_text(text), APPEND_PREVIOUS_TAIL_CHARS);
+            context.push_str(&format!("◇ {}（結尾）\n", clean_text(title)));
+            if truncated {
+                context.push_str("...");
+            }
+            context.push_str(&tail);
+            context.push_str("\n\n");
+        }
+    }
+    
+    // 當前章節結尾
+    context.push_str("【當前章節】\n");
+    context.push_str(&format!("章節標題：{}\n", clean_text(&chapter.title)));
+    context.push_str("內容：\n");
+    let current_text = slate_to_plain_text(chapter.content.as_deref().unwrap_or(""));
+    let (tail, truncated) = text_tail(&clean_text(&current_text), APPEND_CURRENT_TAIL_CHARS);
+    if truncated {
+        context.push_str("...（前文省略）...\n\n");
+    }
+    context.push_str(&tail);
+    context.push_str("\n\n【請接在章節結尾繼續寫作，CRITICAL: 嚴格使用繁體中文，絕對禁止任何英文單詞或簡體字】\n");
+    
+    if let Some(notes) = chapter_notes(chapter) {
+        context.push_str("\n【章節筆記】\n作者筆記：");
+        context.push_str(&clean_text(&notes));
+        context.push('\n');
+    }
+    
+    context.push_str("\n【續寫要求】\n");
+    context.push_str("你的回應應該只包含接在章節結尾之後的新文本，不要重複或改寫上述內容。\n");
+    context.push_str("要求：\n");
+    context.push_str("1. 保持角色一致性和對話風格\n");
+    context.push_str("2. 自然承接章節結尾，推進情節發展\n");
+    context.push_str("3. 保持相同的寫作風格和敘事視角\n");
+    context.push_str("4. 確保細節與前情一致（時間、地點、角色狀態）\n");
+    context.push_str("5. 只寫續寫文本，不要任何元評論或解釋\n");
+    context.push_str("6. CRITICAL: 嚴格使用繁體中文寫作，絕對不允許混雜任何英文單詞或簡體字\n");
+    context
+}
+
+/// 構建章節結尾續寫的上下文（附加模式）
+///
+/// 只使用前幾章的結尾與當前章節的結尾，不處理游標後內容，比 `build_context` 精簡，適合單純接續寫作。
+#[command]
+pub async fn build_append_context(project_id: String, chapter_id: String) -> Result<String, String> {
+    log::info!("構建續寫上下文（附加模式） - 專案: {}, 章節: {}", project_id, chapter_id);
+    
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    let project = load_project(&conn, &project_id)?;
+    let chapter = load_chapter(&conn, &chapter_id)?;
+    if chapter.project_id != project_id {
+        return Err("章節不屬於此專案".to_string());
+    }
+    let characters = load_characters(&conn, &project_id)?;
+    let relationships = load_relationships(&conn, &project_id)?;
+    let previous_chapters = load_previous_chapters(&conn, &chapter, APPEND_PREVIOUS_CHAPTERS)?;
+    
+    let context = assemble_append_context(&project, &chapter, &characters, &relationships, &previous_chapters);
+    log::info!("續寫上下文構建完成: {} 字符", context.chars().count());
+    Ok(context)
+}
+
 /// 壓縮上下文以適應 token 限制
 #[command]
 pub async fn compress_context(
@@ -659,18 +943,8 @@ pub async fn compress_context(
 
 /// 獲取上下文統計信息
 #[command]
-pub async fn get_context_stats(project_id: String) -> Result<ContextStats, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    
-    // 更好地處理 poisoned lock 錯誤
-    let conn = match db.lock() {
-        Ok(conn) => conn,
-        Err(poisoned) => {
-            log::error!("資料庫鎖被 poisoned，嘗試恢復: {}", poisoned);
-            // 嘗試恢復 poisoned lock
-            poisoned.into_inner()
-        }
-    };
+pub async fn get_context_stats(project_id: String, accurate: Option<bool>) -> Result<ContextStats, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     // 統計章節數量
     let chapter_count: usize = conn
@@ -690,13 +964,9 @@ pub async fn get_context_stats(project_id: String) -> Result<ContextStats, Strin
         )
         .map_err(|e| e.to_string())?;
     
-    // 統計總字數
-    let total_chars: usize = conn
-        .query_row(
-            "SELECT COALESCE(SUM(LENGTH(content)), 0) FROM chapters WHERE project_id = ?",
-            [&project_id],
-            |row| row.get(0)
-        )
+    // 統計總字數（以實際文字計算，不含 Slate JSON 結構）
+    let accurate = accurate.unwrap_or(false);
+    let total_chars = count_project_text_chars(&conn, &project_id, accurate)
         .map_err(|e| e.to_string())?;
     
     // 估算 token 數（中文約 1.5-2 字符 = 1 token）
@@ -707,33 +977,62 @@ pub async fn get_context_stats(project_id: String) -> Result<ContextStats, Strin
         estimated_tokens,
         chapter_count,
         character_count,
+        accurate,
     })
 }
 
+/// 計算專案章節的文字字符數
+///
+/// - 估算模式：由 SQLite 的 json_tree 加總文字葉節點長度（含空白），不需載入內容
+/// - 精確模式：逐章解析 Slate JSON，只計算可見字符
+///
+/// 非 JSON 格式的舊章節內容一律視為純文字。
+fn count_project_text_chars(conn: &rusqlite::Connection, project_id: &str, accurate: bool) -> SqliteResult<usize> {
+    if accurate {
+        let mut stmt = conn.prepare("SELECT content FROM chapters WHERE project_id = ?1 AND content IS NOT NULL")?;
+        let contents = stmt.query_map([project_id], |row| row.get::<_, String>(0))?;
+        let mut total = 0;
+        for content in contents {
+            total += count_text(&slate_to_plain_text(&content?)).characters;
+        }
+        return Ok(total);
+    }
+    
+    conn.query_row(
+        "SELECT
+            (SELECT COALESCE(SUM(LENGTH(j.value)), 0)
+             FROM chapters c, json_tree(CASE WHEN json_valid(c.content) THEN c.content ELSE '[]' END) j
+             WHERE c.project_id = ?1 AND j.key = 'text' AND j.type = 'text')
+          + (SELECT COALESCE(SUM(LENGTH(content)), 0)
+             FROM chapters
+             WHERE project_id = ?1 AND content IS NOT NULL AND NOT json_valid(content))",
+        [project_id],
+        |row| row.get(0),
+    )
+}
+
+/// 取得內建續寫系統提示範本，供介面作為自訂提示的編輯起點
+#[command]
+pub async fn get_default_system_prompt(project_type: Option<String>) -> Result<String, String> {
+    Ok(SystemPromptBuilder::new(project_type).default_instructions())
+}
+
 /// 構建分離的上下文（系統提示 + 用戶上下文）- 簡化版
 #[command]
 pub async fn build_separated_context(
     project_id: String,
     chapter_id: String,
     position: usize,
+    system_prompt_override: Option<SystemPromptOverride>,
 ) -> Result<(String, String), String> {
     log::info!("構建分離上下文 - 專案: {}, 章節: {}, 位置: {}", project_id, chapter_id, position);
     
-    let db = get_db().map_err(|e| e.to_string())?;
-    
-    // 處理 poisoned lock 錯誤
-    let conn = match db.lock() {
-        Ok(conn) => conn,
-        Err(poisoned) => {
-            log::error!("資料庫鎖被 poisoned，嘗試恢復: {}", poisoned);
-            poisoned.into_inner()
-        }
-    };
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     // 1. 獲取專案資訊
     let project: Project = conn
         .query_row(
-            "SELECT id, name, description, type, novel_length, settings, created_at, updated_at FROM projects WHERE id = ?",
+            "SELECT id, name, description, type, novel_length, settings, created_at, updated_at, is_archived FROM projects WHERE id = ?",
             [&project_id],
             |row| Ok(Project {
                 id: row.get(0)?,
@@ -744,6 +1043,7 @@ pub async fn build_separated_context(
                 settings: row.get(5)?,
                 created_at: row.get(6)?,
                 updated_at: row.get(7)?,
+                is_archived: row.get(8)?,
             })
         )
         .map_err(|e| format!("獲取專案失敗: {}", e))?;
@@ -789,8 +1089,12 @@ pub async fn build_separated_context(
         .collect::<SqliteResult<Vec<_>>>()
         .map_err(|e| e.to_string())?;
     
-    // 4. 構建系統提示
-    let system_prompt_builder = SystemPromptBuilder::new(project.r#type.clone());
+    // 4. 構建系統提示（呼叫端傳入的自訂提示優先於專案設定）
+    let system_prompt_override = match system_prompt_override {
+        Some(prompt_override) => Some(prompt_override),
+        None => project_system_prompt_override(&conn, &project_id)?,
+    };
+    let system_prompt_builder = SystemPromptBuilder::new(project.r#type.clone()).with_override(system_prompt_override);
     let system_prompt = system_prompt_builder.build_system_prompt();
     
     // 5. 構建用戶上下文
@@ -807,15 +1111,7 @@ pub async fn build_separated_context(
 /// 估算分離上下文的 token 使用情況
 #[command]
 pub async fn estimate_separated_context_tokens(project_id: String) -> Result<SeparatedContextStats, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    
-    let conn = match db.lock() {
-        Ok(conn) => conn,
-        Err(poisoned) => {
-            log::error!("資料庫鎖被 poisoned，嘗試恢復: {}", poisoned);
-            poisoned.into_inner()
-        }
-    };
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     // 獲取專案類型用於系統提示估算
     let project_type: Option<String> = conn
@@ -844,7 +1140,8 @@ pub async fn estimate_separated_context_tokens(project_id: String) -> Result<Sep
         .map_err(|e| e.to_string())?;
     
     // 估算系統提示 token（相對固定）
-    let system_prompt_builder = SystemPromptBuilder::new(project_type);
+    let system_prompt_builder = SystemPromptBuilder::new(project_type)
+        .with_override(project_system_prompt_override(&conn, &project_id)?);
     let system_prompt = system_prompt_builder.build_system_prompt();
     let system_prompt_tokens = system_prompt.chars().count() / 2; // 中文約 2 字符 = 1 token
     
@@ -929,4 +1226,149 @@ pub struct SeparatedContextStats {
     pub efficiency_percentage: f32,
     pub character_count: usize,
     pub estimated_savings_vs_legacy: f32,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    #[test]
+    fn test_system_prompt_override_keeps_purity_enforcement() {
+        let builder = SystemPromptBuilder::new(Some("輕小說".to_string()));
+        let default_instructions = builder.default_instructions();
+        assert!(default_instructions.contains("輕小說風格要求"));
+        assert!(!default_instructions.contains("語言純度強制要求"));
+
+        let replaced = builder.clone().with_override(SystemPromptOverride::from_setting("以冷硬派筆調續寫")).build_system_prompt();
+        assert!(replaced.starts_with("以冷硬派筆調續寫"));
+        assert!(!replaced.contains("輕小說風格要求"));
+        assert!(replaced.contains("語言純度強制要求"));
+
+        let prepended = builder
+            .clone()
+            .with_override(SystemPromptOverride::from_setting(r#"{"prompt":"多用短句","mode":"prepend"}"#))
+            .build_system_prompt();
+        assert!(prepended.starts_with("多用短句\n\n"));
+        assert!(prepended.contains(&default_instructions));
+
+        assert!(SystemPromptOverride::from_setting("  ").is_none());
+        assert_eq!(builder.clone().with_override(None).build_system_prompt(), builder.build_system_prompt());
+    }
+
+    #[test]
+    fn test_context_stats_count_text_not_json() {
+        let conn = migrated_conn_with_project();
+
+        conn.execute_batch(
+            r#"INSERT INTO chapters (id, project_id, title, content)
+                   VALUES ('c1', 'p1', '一', '[{"type":"paragraph","children":[{"text":"勇者 出發"},{"text":"了","bold":true}]}]');
+               INSERT INTO chapters (id, project_id, title, content) VALUES ('c2', 'p1', '二', '純文字');
+               INSERT INTO chapters (id, project_id, title, content) VALUES ('c3', 'p1', '三', NULL);"#,
+        ).unwrap();
+
+        assert_eq!(count_project_text_chars(&conn, "p1", false).unwrap(), 9);
+        assert_eq!(count_project_text_chars(&conn, "p1", true).unwrap(), 8);
+    }
+    
+    #[test]
+    fn test_separated_user_context_includes_chapter_notes() {
+        let now = chrono::Utc::now();
+        let project = Project {
+            id: "p1".to_string(),
+            name: "星之書".to_string(),
+            description: None,
+            r#type: None,
+            novel_length: None,
+            settings: None,
+            created_at: now,
+            updated_at: now,
+            is_archived: false,
+        };
+        let mut chapter = Chapter {
+            id: "c1".to_string(),
+            project_id: "p1".to_string(),
+            title: "第一章".to_string(),
+            content: Some("艾莉絲推開了門。".to_string()),
+            order_index: 0,
+            chapter_number: Some(1),
+            metadata: Some(r#"{"notes":"她其實認得來訪者"}"#.to_string()),
+            created_at: now,
+            updated_at: now,
+        };
+        
+        let context = UserContextBuilder::new(project.clone(), chapter.clone(), vec![], 4).build_user_context();
+        let notes = context.find("【章節筆記】").unwrap();
+        assert!(context[notes..].contains("她其實認得來訪者"));
+        assert!(notes < context.find("[CONTINUE HERE]").unwrap());
+        
+        // 空白筆記不輸出區段
+        chapter.metadata = Some(r#"{"notes":"  "}"#.to_string());
+        let context = UserContextBuilder::new(project, chapter, vec![], 4).build_user_context();
+        assert!(!context.contains("【章節筆記】"));
+    }
+    
+    #[test]
+    fn test_text_tail_starts_at_sentence_boundary() {
+        assert_eq!(text_tail("短句。", 10), ("短句。".to_string(), false));
+        let (tail, truncated) = text_tail("第一句很長很長。第二句。第三句。", 8);
+        assert!(truncated);
+        assert_eq!(tail, "第三句。");
+    }
+    
+    #[test]
+    fn test_append_context_uses_previous_chapter_endings() {
+        let now = chrono::Utc::now();
+        let project = Project {
+            id: "p1".to_string(),
+            name: "星之書".to_string(),
+            description: None,
+            r#type: Some("奇幻".to_string()),
+            novel_length: None,
+            settings: None,
+            created_at: now,
+            updated_at: now,
+            is_archived: false,
+        };
+        let chapter = Chapter {
+            id: "c3".to_string(),
+            project_id: "p1".to_string(),
+            title: "第三章".to_string(),
+            content: Some(r#"[{"type":"paragraph","children":[{"text":"艾莉絲推開了門。"}]}]"#.to_string()),
+            order_index: 2,
+            chapter_number: Some(3),
+            metadata: None,
+            created_at: now,
+            updated_at: now,
+        };
+        let previous = vec![
+            ("第二章".to_string(), "第二章的結尾。".to_string()),
+            ("第一章".to_string(), "第一章的結尾。".to_string()),
+        ];
+        let context = assemble_append_context(&project, &chapter, &[], &[], &previous);
+        
+        let first = context.find("第一章的結尾").unwrap();
+        let second = context.find("第二章的結尾").unwrap();
+        let current = context.find("艾莉絲推開了門").unwrap();
+        assert!(first < second && second < current);
+        assert!(!context.contains("游標後"));
+        assert!(!context.contains("\"children\""));
+    }
+    
+    #[test]
+    fn test_previous_chapter_fill_labels_boundaries() {
+        let previous = vec![
+            ("第二章".to_string(), "第二章的結尾。".to_string()),
+            ("第一章".to_string(), format!("{}第一章的結尾。", "很長的前文。".repeat(40))),
+        ];
+        let fill = previous_chapter_fill(&previous, 150).unwrap();
+        let first = fill.find("（更早章節結尾：第一章）").unwrap();
+        let second = fill.find("（上一章結尾：第二章）").unwrap();
+        assert!(first < second);
+        assert!(fill.ends_with("（本章開始）\n"));
+        assert!(fill.contains("...很長的前文。"));
+        
+        assert_eq!(previous_chapter_fill(&previous, 50), None);
+        assert_eq!(previous_chapter_fill(&[], 1000), None);
+    }
+}
@@ -1,6 +1,32 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
 use crate::database::connection::get_db_path;
+use crate::database::{get_db, get_db_conn, migrations, reset_db_pool};
+
+/// 自動備份設定在 settings 表中的鍵
+const AUTO_BACKUP_SETTING_KEY: &str = "auto_backup_config";
+/// 自動備份檔名前綴
+const AUTO_BACKUP_FILE_PREFIX: &str = "genesis-auto-backup-";
+
+/// 自動備份排程設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoBackupConfig {
+    pub interval_hours: u32,
+    pub keep_count: usize,
+    pub directory: String,
+    pub last_backup_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl AutoBackupConfig {
+    /// 判斷是否已到下一次備份時間
+    fn is_due(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        match self.last_backup_at {
+            Some(last) => now - last >= chrono::Duration::hours(self.interval_hours as i64),
+            None => true,
+        }
+    }
+}
 
 /// 計算資料庫碎片化程度
 /// 使用 SQLite 的 dbstat 虛擬表來計算碎片化百分比
@@ -82,8 +108,88 @@ pub async fn backup_database(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// 備份檔案預覽摘要
+#[derive(Debug, Serialize)]
+pub struct BackupSummary {
+    pub path: String,
+    pub file_size: u64,
+    pub schema_version: Option<i32>,
+    pub project_count: Option<i64>,
+    pub chapter_count: Option<i64>,
+    pub created_at: Option<String>,
+    /// 還原時傳回以確認還原的正是預覽過的檔案
+    pub confirmation_token: String,
+}
+
+/// 依備份路徑、大小與修改時間產生確認碼（檔案在預覽後變更時確認碼會不同）
+fn backup_confirmation_token(path: &Path) -> Result<String, String> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+    
+    let metadata = fs::metadata(path).map_err(|e| format!("無法讀取備份檔案資訊: {}", e))?;
+    let mut hasher = DefaultHasher::new();
+    path.to_string_lossy().hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    if let Ok(modified) = metadata.modified() {
+        modified.hash(&mut hasher);
+    }
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+/// 以唯讀方式預覽備份內容，不影響目前的資料庫
 #[tauri::command]
-pub async fn restore_database(path: String) -> Result<(), String> {
+pub async fn inspect_backup(path: String) -> Result<BackupSummary, String> {
+    use rusqlite::{Connection, OpenFlags};
+    
+    let backup_path = Path::new(&path);
+    if !backup_path.exists() {
+        return Err("備份檔案不存在".to_string());
+    }
+    
+    let conn = Connection::open_with_flags(backup_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("無法開啟備份檔案: {}", e))?;
+    
+    // 確認是有效的 SQLite 資料庫
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))
+        .map_err(|e| format!("備份檔案不是有效的資料庫: {}", e))?;
+    
+    let schema_version = conn
+        .query_row("SELECT MAX(version) FROM db_version", [], |row| row.get::<_, Option<i32>>(0))
+        .ok()
+        .flatten();
+    let project_count = conn
+        .query_row("SELECT COUNT(*) FROM projects", [], |row| row.get::<_, i64>(0))
+        .ok();
+    let chapter_count = conn
+        .query_row("SELECT COUNT(*) FROM chapters", [], |row| row.get::<_, i64>(0))
+        .ok();
+    
+    let metadata = fs::metadata(backup_path).map_err(|e| format!("無法讀取備份檔案資訊: {}", e))?;
+    let created_at = metadata
+        .created()
+        .or_else(|_| metadata.modified())
+        .ok()
+        .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339());
+    
+    Ok(BackupSummary {
+        path: path.clone(),
+        file_size: metadata.len(),
+        schema_version,
+        project_count,
+        chapter_count,
+        created_at,
+        confirmation_token: backup_confirmation_token(backup_path)?,
+    })
+}
+
+/// 從備份還原資料庫
+///
+/// 若提供 `confirmation_token`（來自 `inspect_backup`），會確認備份在預覽後未被變更。
+/// 還原前會將目前資料庫快照為 `.pre-restore` 檔案，以便還原錯誤時復原。
+/// 還原透過 SQLite 線上備份 API 寫入使用中的資料庫，不直接覆寫檔案，
+/// 因此其他連接同時寫入的 WAL 內容不會混進還原後的資料庫。
+#[tauri::command]
+pub async fn restore_database(path: String, confirmation_token: Option<String>) -> Result<(), String> {
     let source_path = Path::new(&path);
     let dest_path = get_db_path().map_err(|e| e.to_string())?;
     
@@ -92,6 +198,12 @@ pub async fn restore_database(path: String) -> Result<(), String> {
         return Err("備份檔案不存在".to_string());
     }
     
+    if let Some(token) = confirmation_token {
+        if token != backup_confirmation_token(source_path)? {
+            return Err("備份檔案在預覽後已變更，請重新預覽後再還原".to_string());
+        }
+    }
+    
     // 確保目標目錄存在
     if let Some(parent) = dest_path.parent() {
         if !parent.exists() {
@@ -100,14 +212,60 @@ pub async fn restore_database(path: String) -> Result<(), String> {
         }
     }
     
-    // 複製檔案
-    fs::copy(source_path, &dest_path)
-        .map_err(|e| format!("還原失敗: {}", e))?;
+    // 快照目前的資料庫（VACUUM INTO 在單一讀取交易中輸出，包含尚未寫回的 WAL）
+    if dest_path.exists() {
+        let snapshot_path = PathBuf::from(format!("{}.pre-restore", dest_path.to_string_lossy()));
+        if snapshot_path.exists() {
+            fs::remove_file(&snapshot_path)
+                .map_err(|e| format!("無法移除舊的還原前快照，已取消還原: {}", e))?;
+        }
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        conn.execute("VACUUM INTO ?1", [snapshot_path.to_string_lossy()])
+            .map_err(|e| format!("還原前快照失敗，已取消還原: {}", e))?;
+        log::info!("還原前已快照目前資料庫: {}", snapshot_path.display());
+    }
+    
+    let source = rusqlite::Connection::open_with_flags(source_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("無法開啟備份檔案: {}", e))?;
+    match get_db() {
+        // 服務共用的連接直接作為還原目標，還原期間其他服務無法透過它寫入
+        Ok(db) => {
+            let mut conn = db.lock().map_err(|e| format!("資料庫鎖定失敗: {}", e))?;
+            restore_from_backup(&source, &mut conn)?;
+        }
+        Err(_) => {
+            let mut conn = rusqlite::Connection::open(&dest_path)
+                .map_err(|e| format!("無法開啟資料庫: {}", e))?;
+            restore_from_backup(&source, &mut conn)?;
+        }
+    }
+    // 連接池中的連接可能快取了舊的結構描述與預備語句，關閉後重新建立
+    reset_db_pool();
     
     log::info!("資料庫已從備份還原: {}", path);
     Ok(())
 }
 
+/// 以 SQLite 線上備份 API 將 `source` 的內容整份寫入 `dest`
+///
+/// 一次複製全部頁面，寫入期間持有目標資料庫的寫入鎖，其他連接看到的是還原前或還原後的完整內容。
+fn restore_from_backup(source: &rusqlite::Connection, dest: &mut rusqlite::Connection) -> Result<(), String> {
+    use rusqlite::backup::{Backup, StepResult};
+    
+    let backup = Backup::new(source, dest).map_err(|e| format!("還原失敗: {}", e))?;
+    for _ in 0..RESTORE_BUSY_RETRIES {
+        match backup.step(-1).map_err(|e| format!("還原失敗: {}", e))? {
+            StepResult::Done => return Ok(()),
+            // 其他連接正在寫入：稍後重試
+            _ => std::thread::sleep(std::time::Duration::from_millis(100)),
+        }
+    }
+    Err("還原失敗：資料庫忙碌中，請稍後再試".to_string())
+}
+
+/// 還原時等待其他連接釋放寫入鎖的次數（每次 100 毫秒）
+const RESTORE_BUSY_RETRIES: u32 = 50;
+
 #[tauri::command]
 pub async fn run_database_maintenance() -> Result<String, String> {
     use rusqlite::Connection;
@@ -118,8 +276,12 @@ pub async fn run_database_maintenance() -> Result<String, String> {
     
     // 🔥 更強力的碎片化清理流程
     
-    // 1. 先執行 WAL 檢查點，確保所有變更都寫入主檔案
-    let _checkpoint = conn.execute("PRAGMA wal_checkpoint(TRUNCATE)", []);
+    // 1. 先執行 WAL 檢查點，確保所有變更都寫入主檔案並截斷 -wal 檔案
+    match run_wal_checkpoint(&conn, WalCheckpointMode::Truncate) {
+        Ok(result) if result.busy => log::warn!("WAL 檢查點未完成（資料庫忙碌中）: {:?}", result),
+        Ok(result) => log::info!("WAL 檢查點完成: {:?}", result),
+        Err(e) => log::warn!("WAL 檢查點失敗: {}", e),
+    }
     
     // 2. 設置較短的忙等待時間，避免鎖定衝突
     conn.pragma_update(None, "busy_timeout", 30000)
@@ -146,6 +308,58 @@ pub async fn run_database_maintenance() -> Result<String, String> {
     Ok("資料庫維護完成".to_string())
 }
 
+/// VACUUM INTO 壓縮結果
+#[derive(Debug, Serialize)]
+pub struct VacuumIntoResult {
+    pub target_path: String,
+    pub original_size: u64,
+    pub compacted_size: u64,
+    pub reclaimed_bytes: u64,
+}
+
+/// 以 VACUUM INTO 將資料庫壓縮輸出到新檔案，不需長時間鎖定原資料庫
+///
+/// 產生的檔案可透過 `restore_database` 取代原資料庫。
+#[tauri::command]
+pub async fn vacuum_into(target_path: String) -> Result<VacuumIntoResult, String> {
+    let target = Path::new(&target_path);
+    if target.exists() {
+        return Err("目標檔案已存在，請選擇新的檔案路徑".to_string());
+    }
+    if let Some(parent) = target.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("無法建立目標目錄: {}", e))?;
+        }
+    }
+    
+    let db_path = get_db_path().map_err(|e| e.to_string())?;
+    // 原始大小包含尚未寫回主檔案的 WAL
+    let wal_path = format!("{}-wal", db_path.to_string_lossy());
+    let original_size = fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0)
+        + fs::metadata(&wal_path).map(|m| m.len()).unwrap_or(0);
+    
+    {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        conn.execute("VACUUM INTO ?1", [&target_path])
+            .map_err(|e| format!("VACUUM INTO 失敗: {}", e))?;
+    }
+    
+    let compacted_size = fs::metadata(target)
+        .map_err(|e| format!("無法獲取壓縮檔案資訊: {}", e))?
+        .len();
+    let reclaimed_bytes = original_size.saturating_sub(compacted_size);
+    
+    log::info!("資料庫已壓縮至 {}：{} → {} bytes（回收 {} bytes）", target_path, original_size, compacted_size, reclaimed_bytes);
+    
+    Ok(VacuumIntoResult {
+        target_path,
+        original_size,
+        compacted_size,
+        reclaimed_bytes,
+    })
+}
+
 #[tauri::command]
 pub async fn reindex_database() -> Result<String, String> {
     use rusqlite::Connection;
@@ -192,6 +406,69 @@ pub async fn incremental_vacuum(pages: Option<i32>) -> Result<String, String> {
     Ok(message)
 }
 
+/// WAL 檢查點模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalCheckpointMode {
+    Passive,
+    Full,
+    Truncate,
+}
+
+impl WalCheckpointMode {
+    fn parse(mode: &str) -> Result<Self, String> {
+        match mode.to_uppercase().as_str() {
+            "PASSIVE" => Ok(Self::Passive),
+            "FULL" => Ok(Self::Full),
+            "TRUNCATE" => Ok(Self::Truncate),
+            _ => Err(format!("不支援的檢查點模式: {}（可用 PASSIVE、FULL、TRUNCATE）", mode)),
+        }
+    }
+    
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Passive => "PASSIVE",
+            Self::Full => "FULL",
+            Self::Truncate => "TRUNCATE",
+        }
+    }
+}
+
+/// WAL 檢查點結果（對應 PRAGMA wal_checkpoint 的三個回傳值）
+#[derive(Debug, Serialize)]
+pub struct WalCheckpointResult {
+    pub mode: String,
+    /// 是否因其他連接持有鎖而未能完成
+    pub busy: bool,
+    /// WAL 中的頁框數
+    pub log_frames: i64,
+    /// 已寫回主檔案的頁框數
+    pub checkpointed_frames: i64,
+}
+
+/// 執行 WAL 檢查點（非 WAL 模式時頁框數為 -1）
+fn run_wal_checkpoint(conn: &rusqlite::Connection, mode: WalCheckpointMode) -> Result<WalCheckpointResult, String> {
+    conn.query_row(&format!("PRAGMA wal_checkpoint({})", mode.as_str()), [], |row| {
+        Ok(WalCheckpointResult {
+            mode: mode.as_str().to_string(),
+            busy: row.get::<_, i64>(0)? != 0,
+            log_frames: row.get(1)?,
+            checkpointed_frames: row.get(2)?,
+        })
+    })
+    .map_err(|e| format!("WAL 檢查點失敗: {}", e))
+}
+
+/// 強制執行 WAL 檢查點（mode: PASSIVE / FULL / TRUNCATE）
+#[tauri::command]
+pub async fn checkpoint_wal(mode: String) -> Result<WalCheckpointResult, String> {
+    let mode = WalCheckpointMode::parse(&mode)?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let result = run_wal_checkpoint(&conn, mode)?;
+    log::info!("WAL 檢查點 ({}) 完成: {:?}", mode.as_str(), result);
+    Ok(result)
+}
+
 #[tauri::command]
 pub async fn get_wal_mode_status() -> Result<serde_json::Value, String> {
     use rusqlite::Connection;
@@ -343,6 +620,19 @@ pub async fn get_database_stats() -> Result<serde_json::Value, String> {
         relationship_count = count;
     }
     
+    // 各表的記錄數與佔用空間
+    let table_sizes = collect_table_sizes(&conn);
+    let table_details: serde_json::Map<String, serde_json::Value> = STATS_TABLES
+        .iter()
+        .filter_map(|table| {
+            let rows = conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get::<_, i64>(0))
+                .ok()?;
+            let bytes = table_sizes.as_ref().map(|sizes| sizes.get(*table).copied().unwrap_or(0));
+            Some((table.to_string(), json!({ "rows": rows, "bytes": bytes })))
+        })
+        .collect();
+    
     Ok(json!({
         "file_size": file_size,
         "file_path": db_path.to_string_lossy(),
@@ -351,10 +641,47 @@ pub async fn get_database_stats() -> Result<serde_json::Value, String> {
             "chapters": chapter_count,
             "characters": character_count,
             "character_relationships": relationship_count
-        }
+        },
+        "table_details": table_details,
+        "size_source": if table_sizes.is_some() { "dbstat" } else { "unavailable" }
     }))
 }
 
+/// 統計明細包含的資料表
+pub(crate) const STATS_TABLES: &[&str] = &[
+    "projects",
+    "chapters",
+    "chapter_versions",
+    "characters",
+    "character_relationships",
+    "character_chapter_mentions",
+    "ai_generation_history",
+    "pollinations_generations",
+    "illustration_generations",
+    "character_analysis",
+    "plot_analysis",
+    "creative_suggestions",
+    "analysis_cache",
+    "prompt_templates",
+    "project_settings",
+];
+
+/// 透過 dbstat 虛擬表計算各資料表（含其索引）的佔用位元組，dbstat 不可用時回傳 None
+fn collect_table_sizes(conn: &rusqlite::Connection) -> Option<std::collections::HashMap<String, i64>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT m.tbl_name, SUM(s.pgsize)
+             FROM dbstat s
+             JOIN sqlite_master m ON m.name = s.name
+             GROUP BY m.tbl_name",
+        )
+        .ok()?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .ok()?;
+    rows.collect::<Result<_, _>>().ok()
+}
+
 #[tauri::command]
 pub async fn health_check() -> Result<serde_json::Value, String> {
     use rusqlite::Connection;
@@ -523,4 +850,390 @@ pub async fn health_check() -> Result<serde_json::Value, String> {
         },
         "timestamp": chrono::Utc::now().to_rfc3339()
     }))
-}
\ No newline at end of file
+}
+
+/// 讀取自動備份設定
+fn load_auto_backup_config(conn: &rusqlite::Connection) -> Result<Option<AutoBackupConfig>, String> {
+    let value: Option<String> = match conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        [AUTO_BACKUP_SETTING_KEY],
+        |row| row.get(0),
+    ) {
+        Ok(value) => Some(value),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(format!("讀取自動備份設定失敗: {}", e)),
+    };
+    
+    value
+        .map(|v| serde_json::from_str(&v).map_err(|e| format!("自動備份設定格式錯誤: {}", e)))
+        .transpose()
+}
+
+/// 儲存自動備份設定
+fn save_auto_backup_config(conn: &rusqlite::Connection, config: &AutoBackupConfig) -> Result<(), String> {
+    let value = serde_json::to_string(config).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+        rusqlite::params![AUTO_BACKUP_SETTING_KEY, value],
+    )
+    .map_err(|e| format!("儲存自動備份設定失敗: {}", e))?;
+    Ok(())
+}
+
+/// 開啟備份檔並執行快速完整性檢查
+///
+/// 同時將備份切換為 DELETE 日誌模式，使備份成為不依賴 -wal/-shm 的單一檔案。
+fn verify_backup_file(path: &Path) -> Result<(), String> {
+    use rusqlite::Connection;
+    
+    let conn = Connection::open(path)
+        .map_err(|e| format!("無法開啟備份檔案: {}", e))?;
+    let _mode: String = conn
+        .query_row("PRAGMA journal_mode = DELETE", [], |row| row.get(0))
+        .map_err(|e| format!("無法設定備份日誌模式: {}", e))?;
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| format!("備份完整性檢查失敗: {}", e))?;
+    
+    if result != "ok" {
+        return Err(format!("備份完整性檢查未通過: {}", result));
+    }
+    Ok(())
+}
+
+/// 刪除超出保留數量的最舊自動備份，回傳被刪除的檔案
+fn rotate_auto_backups(directory: &Path, keep_count: usize) -> Result<Vec<PathBuf>, String> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(directory)
+        .map_err(|e| format!("讀取備份目錄失敗: {}", e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with(AUTO_BACKUP_FILE_PREFIX) && name.ends_with(".db"))
+                .unwrap_or(false)
+        })
+        .collect();
+    
+    // 檔名含時間戳記，依名稱排序即為時間順序（新到舊）
+    backups.sort();
+    backups.reverse();
+    
+    let mut removed = Vec::new();
+    for old_backup in backups.into_iter().skip(keep_count) {
+        match fs::remove_file(&old_backup) {
+            Ok(()) => removed.push(old_backup),
+            Err(e) => log::warn!("刪除舊備份失敗: {} ({})", old_backup.display(), e),
+        }
+    }
+    Ok(removed)
+}
+
+/// 設定自動備份排程（間隔小時數、保留份數、備份目錄）
+#[tauri::command]
+pub async fn configure_auto_backup(
+    interval_hours: u32,
+    keep_count: usize,
+    directory: String,
+) -> Result<AutoBackupConfig, String> {
+    if interval_hours == 0 {
+        return Err("備份間隔必須大於 0 小時".to_string());
+    }
+    if keep_count == 0 {
+        return Err("保留份數必須至少為 1".to_string());
+    }
+    
+    fs::create_dir_all(&directory)
+        .map_err(|e| format!("無法建立備份目錄: {}", e))?;
+    
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    // 保留上次備份時間，避免重新設定後立即觸發備份
+    let last_backup_at = load_auto_backup_config(&conn)?.and_then(|c| c.last_backup_at);
+    let config = AutoBackupConfig {
+        interval_hours,
+        keep_count,
+        directory,
+        last_backup_at,
+    };
+    save_auto_backup_config(&conn, &config)?;
+    
+    log::info!("自動備份已設定：每 {} 小時，保留 {} 份，目錄 {}", config.interval_hours, config.keep_count, config.directory);
+    Ok(config)
+}
+
+/// 執行到期的自動備份，回傳新建立的備份路徑（未設定或未到期時回傳 None）
+#[tauri::command]
+pub async fn run_due_backups() -> Result<Option<String>, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let Some(mut config) = load_auto_backup_config(&conn)? else {
+        return Ok(None);
+    };
+    
+    let now = chrono::Utc::now();
+    if !config.is_due(now) {
+        return Ok(None);
+    }
+    
+    let directory = PathBuf::from(&config.directory);
+    fs::create_dir_all(&directory)
+        .map_err(|e| format!("無法建立備份目錄: {}", e))?;
+    let backup_path = directory.join(format!("{}{}.db", AUTO_BACKUP_FILE_PREFIX, now.format("%Y%m%d-%H%M%S")));
+    
+    // VACUUM INTO 在單一讀取交易中輸出，不受其他連接同時寫入或自動檢查點影響
+    conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy()])
+        .map_err(|e| format!("自動備份失敗: {}", e))?;
+    
+    // 驗證通過才輪替舊備份，避免以損壞的備份取代好的備份
+    if let Err(e) = verify_backup_file(&backup_path) {
+        let _ = fs::remove_file(&backup_path);
+        return Err(e);
+    }
+    
+    let removed = rotate_auto_backups(&directory, config.keep_count)?;
+    
+    config.last_backup_at = Some(now);
+    save_auto_backup_config(&conn, &config)?;
+    
+    log::info!("💾 自動備份完成: {}（清除 {} 份舊備份）", backup_path.display(), removed.len());
+    Ok(Some(backup_path.to_string_lossy().to_string()))
+}
+
+/// 外鍵孤兒記錄
+#[derive(Debug, Serialize)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub rowid: Option<i64>,
+    pub parent: String,
+    pub fk_index: i64,
+}
+
+/// 資料庫完整性檢查報告
+#[derive(Debug, Serialize)]
+pub struct IntegrityReport {
+    pub integrity_ok: bool,
+    pub integrity_messages: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    pub repaired_rows: usize,
+}
+
+/// 執行 PRAGMA integrity_check 與 foreign_key_check，必要時刪除孤兒記錄
+fn check_database_integrity(conn: &mut rusqlite::Connection, auto_repair: bool) -> Result<IntegrityReport, String> {
+    let integrity_messages: Vec<String> = conn
+        .prepare("PRAGMA integrity_check")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get::<_, String>(0))?
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| format!("完整性檢查失敗: {}", e))?;
+    let integrity_ok = integrity_messages.len() == 1 && integrity_messages[0] == "ok";
+    
+    let foreign_key_violations: Vec<ForeignKeyViolation> = conn
+        .prepare("PRAGMA foreign_key_check")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| {
+                Ok(ForeignKeyViolation {
+                    table: row.get(0)?,
+                    rowid: row.get(1)?,
+                    parent: row.get(2)?,
+                    fk_index: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| format!("外鍵檢查失敗: {}", e))?;
+    
+    let mut repaired_rows = 0;
+    if auto_repair && !foreign_key_violations.is_empty() {
+        let tx = conn.transaction().map_err(|e| format!("開始修復交易失敗: {}", e))?;
+        for violation in &foreign_key_violations {
+            let Some(rowid) = violation.rowid else {
+                continue;
+            };
+            repaired_rows += tx
+                .execute(
+                    &format!("DELETE FROM \"{}\" WHERE rowid = ?1", violation.table.replace('"', "\"\"")),
+                    [rowid],
+                )
+                .map_err(|e| format!("刪除孤兒記錄失敗 ({}): {}", violation.table, e))?;
+        }
+        tx.commit().map_err(|e| format!("提交修復交易失敗: {}", e))?;
+        log::info!("已刪除 {} 筆孤兒記錄", repaired_rows);
+    }
+    
+    Ok(IntegrityReport {
+        integrity_ok,
+        integrity_messages,
+        foreign_key_violations,
+        repaired_rows,
+    })
+}
+
+/// 執行 SQLite 完整性與外鍵檢查，`auto_repair` 時在交易中刪除孤兒記錄
+#[tauri::command]
+pub async fn integrity_check(auto_repair: Option<bool>) -> Result<IntegrityReport, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let report = check_database_integrity(&mut conn, auto_repair.unwrap_or(false))?;
+    log::info!(
+        "完整性檢查完成：integrity={}，外鍵問題 {} 筆，已修復 {} 筆",
+        if report.integrity_ok { "ok" } else { "異常" },
+        report.foreign_key_violations.len(),
+        report.repaired_rows
+    );
+    Ok(report)
+}
+
+/// 查詢資料庫遷移狀態，可指定其他資料庫檔案（如備份），`dry_run` 時在臨時副本上試跑遷移
+#[tauri::command]
+pub async fn get_migration_status(
+    path: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<migrations::MigrationStatus, String> {
+    let inspect = |conn: &rusqlite::Connection| -> Result<migrations::MigrationStatus, String> {
+        let mut status = migrations::get_migration_status(conn).map_err(|e| format!("查詢遷移狀態失敗: {}", e))?;
+        if dry_run.unwrap_or(false) && !status.pending.is_empty() {
+            status.dry_run = Some(migrations::dry_run_migrations(conn).map_err(|e| format!("遷移試跑失敗: {}", e))?);
+        }
+        Ok(status)
+    };
+    
+    match path {
+        Some(path) => {
+            use rusqlite::{Connection, OpenFlags};
+            
+            if !Path::new(&path).exists() {
+                return Err("資料庫檔案不存在".to_string());
+            }
+            let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                .map_err(|e| format!("無法開啟資料庫檔案: {}", e))?;
+            inspect(&conn)
+        }
+        None => {
+            let conn = get_db_conn().map_err(|e| e.to_string())?;
+            inspect(&conn)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_backup_due() {
+        let now = chrono::Utc::now();
+        let mut config = AutoBackupConfig {
+            interval_hours: 6,
+            keep_count: 3,
+            directory: String::new(),
+            last_backup_at: None,
+        };
+        assert!(config.is_due(now));
+
+        config.last_backup_at = Some(now - chrono::Duration::hours(2));
+        assert!(!config.is_due(now));
+
+        config.last_backup_at = Some(now - chrono::Duration::hours(6));
+        assert!(config.is_due(now));
+    }
+
+    #[test]
+    fn test_rotate_auto_backups_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        for stamp in ["20240101-000000", "20240102-000000", "20240103-000000"] {
+            fs::write(dir.path().join(format!("{}{}.db", AUTO_BACKUP_FILE_PREFIX, stamp)), b"").unwrap();
+        }
+        fs::write(dir.path().join("manual.db"), b"").unwrap();
+
+        let removed = rotate_auto_backups(dir.path(), 2).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].to_string_lossy().contains("20240101"));
+        assert!(dir.path().join("manual.db").exists());
+    }
+
+    #[test]
+    fn test_integrity_check_repairs_orphans() {
+        let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "PRAGMA foreign_keys = OFF;
+             CREATE TABLE projects (id TEXT PRIMARY KEY);
+             CREATE TABLE chapters (
+                 id TEXT PRIMARY KEY,
+                 project_id TEXT NOT NULL,
+                 FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE CASCADE
+             );
+             INSERT INTO projects VALUES ('p1');
+             INSERT INTO chapters VALUES ('c1', 'p1');
+             INSERT INTO chapters VALUES ('c2', 'missing');",
+        ).unwrap();
+
+        let report = check_database_integrity(&mut conn, false).unwrap();
+        assert!(report.integrity_ok);
+        assert_eq!(report.foreign_key_violations.len(), 1);
+        assert_eq!(report.foreign_key_violations[0].table, "chapters");
+        assert_eq!(report.repaired_rows, 0);
+
+        let report = check_database_integrity(&mut conn, true).unwrap();
+        assert_eq!(report.repaired_rows, 1);
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM chapters", [], |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 1);
+    }
+
+    #[test]
+    fn test_collect_table_sizes() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE chapters (id TEXT PRIMARY KEY, content TEXT);
+             CREATE INDEX idx_chapters_content ON chapters (content);
+             INSERT INTO chapters VALUES ('c1', 'text');",
+        ).unwrap();
+
+        let sizes = collect_table_sizes(&conn).expect("bundled SQLite 應支援 dbstat");
+        assert!(sizes.get("chapters").copied().unwrap_or(0) > 0);
+    }
+
+    #[test]
+    fn test_restore_from_backup_replaces_live_wal_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let live_path = dir.path().join("live.db");
+        let mut live = rusqlite::Connection::open(&live_path).unwrap();
+        live.pragma_update(None, "journal_mode", "WAL").unwrap();
+        live.execute_batch("CREATE TABLE items (name TEXT); INSERT INTO items VALUES ('舊資料');").unwrap();
+        let other = rusqlite::Connection::open(&live_path).unwrap();
+        other.execute("INSERT INTO items VALUES ('未寫回的 WAL')", []).unwrap();
+        
+        let source = rusqlite::Connection::open(dir.path().join("backup.db")).unwrap();
+        source.execute_batch("CREATE TABLE items (name TEXT); INSERT INTO items VALUES ('備份資料');").unwrap();
+        restore_from_backup(&source, &mut live).unwrap();
+        
+        let names = |conn: &rusqlite::Connection| -> Vec<String> {
+            conn.prepare("SELECT name FROM items").unwrap()
+                .query_map([], |row| row.get(0)).unwrap()
+                .collect::<Result<_, _>>().unwrap()
+        };
+        assert_eq!(names(&live), vec!["備份資料"]);
+        assert_eq!(names(&other), vec!["備份資料"]);
+    }
+
+    #[test]
+    fn test_wal_checkpoint_truncates_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("wal.db");
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+        conn.pragma_update(None, "wal_autocheckpoint", 0).unwrap();
+        conn.execute_batch("CREATE TABLE items (id INTEGER PRIMARY KEY); INSERT INTO items (id) VALUES (1);").unwrap();
+        
+        let passive = run_wal_checkpoint(&conn, WalCheckpointMode::parse("passive").unwrap()).unwrap();
+        assert!(!passive.busy);
+        assert!(passive.log_frames > 0);
+        assert_eq!(passive.checkpointed_frames, passive.log_frames);
+        
+        let truncate = run_wal_checkpoint(&conn, WalCheckpointMode::Truncate).unwrap();
+        assert_eq!(truncate.log_frames, 0);
+        assert_eq!(std::fs::metadata(format!("{}-wal", db_path.to_string_lossy())).unwrap().len(), 0);
+        assert!(WalCheckpointMode::parse("RESTART").is_err());
+    }
+}
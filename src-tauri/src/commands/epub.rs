@@ -1,4 +1,6 @@
-use crate::database::{get_db, models::*};
+use crate::database::{get_db_conn, models::*};
+use crate::utils::slate::slate_to_html;
+use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
 use std::io::Write;
 use zip::{ZipWriter, CompressionMethod};
@@ -72,48 +74,22 @@ pub struct IllustrationFile {
     pub generation_time: Option<String>,
 }
 
-/// 掃描專案相關的 AI 插畫檔案
-fn scan_project_illustrations(_project_id: &str) -> Result<Vec<IllustrationFile>, String> {
-    // 取得插畫儲存目錄
-    let data_dir = dirs::data_local_dir()
-        .unwrap_or_else(|| std::path::PathBuf::from("."));
-    let illustrations_dir = data_dir.join("genesis-chronicle").join("generated-images");
-    
-    if !illustrations_dir.exists() {
-        println!("插畫目錄不存在: {:?}", illustrations_dir);
-        return Ok(Vec::new());
-    }
-    
-    let mut illustrations = Vec::new();
-    
-    // 遍歷插畫目錄
-    if let Ok(entries) = std::fs::read_dir(&illustrations_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            
-            // 只處理圖片檔案
-            if let Some(extension) = path.extension() {
-                let ext_str = extension.to_string_lossy().to_lowercase();
-                if matches!(ext_str.as_str(), "jpg" | "jpeg" | "png" | "webp") {
-                    if let Some(filename) = path.file_name() {
-                        let filename_str = filename.to_string_lossy().to_string();
-                        
-                        // 簡單的檔名解析（可以後續改進）
-                        // 假設檔名包含角色資訊或專案 ID
-                        let illustration = IllustrationFile {
-                            file_path: path.clone(),
-                            filename: filename_str,
-                            character_names: Vec::new(), // 暫時為空，後續可以從檔名或元資料解析
-                            generation_time: None,
-                        };
-                        
-                        illustrations.push(illustration);
-                    }
-                }
-            }
-        }
-    }
-    
+/// 掃描專案相關的 AI 插畫檔案（與 PDF、作品包導出使用相同的查詢）
+fn scan_project_illustrations(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<IllustrationFile>, String> {
+    let illustrations: Vec<IllustrationFile> = crate::commands::illustration::query_project_illustrations(conn, project_id)?
+        .into_iter()
+        .map(|illustration| IllustrationFile {
+            filename: illustration
+                .file_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            file_path: illustration.file_path,
+            character_names: illustration.character_name.into_iter().collect(),
+            generation_time: illustration.created_at,
+        })
+        .collect();
+
     println!("掃描到 {} 張插畫檔案", illustrations.len());
     Ok(illustrations)
 }
@@ -235,13 +211,12 @@ pub async fn generate_epub(
     
     // 1. 從資料庫獲取專案資料和章節
     let (project, chapters) = {
-        let db = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-        let conn = db.lock().unwrap();
+        let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
         
         // 獲取專案資料
         let project = {
             let mut stmt = conn
-                .prepare("SELECT id, name, description, type, settings, novel_length, created_at, updated_at FROM projects WHERE id = ?1")
+                .prepare("SELECT id, name, description, type, settings, novel_length, created_at, updated_at, is_archived FROM projects WHERE id = ?1")
                 .map_err(|e| format!("準備專案查詢失敗: {}", e))?;
             
             let project_result = stmt.query_row([&projectId], |row| {
@@ -254,6 +229,7 @@ pub async fn generate_epub(
                     novel_length: row.get::<_, Option<String>>(5)?,
                     created_at: row.get(6)?,
                     updated_at: row.get(7)?,
+                    is_archived: row.get(8)?,
                 })
             });
             
@@ -269,31 +245,7 @@ pub async fn generate_epub(
         };
         
         // 2. 獲取專案的所有章節
-        let chapters = {
-            let mut stmt = conn
-                .prepare("SELECT id, project_id, title, content, order_index, chapter_number, metadata, created_at, updated_at FROM chapters WHERE project_id = ?1 ORDER BY order_index")
-                .map_err(|e| format!("準備章節查詢失敗: {}", e))?;
-            
-            let chapter_iter = stmt.query_map([&projectId], |row| {
-                Ok(Chapter {
-                    id: row.get(0)?,
-                    project_id: row.get(1)?,
-                    title: row.get(2)?,
-                    content: row.get::<_, Option<String>>(3)?,
-                    order_index: row.get(4)?,
-                    chapter_number: row.get::<_, Option<i32>>(5)?,
-                    metadata: row.get::<_, Option<String>>(6)?,
-                    created_at: row.get(7)?,
-                    updated_at: row.get(8)?,
-                })
-            }).map_err(|e| format!("查詢章節失敗: {}", e))?;
-            
-            let mut chapters = Vec::new();
-            for chapter in chapter_iter {
-                chapters.push(chapter.map_err(|e| format!("處理章節資料失敗: {}", e))?);
-            }
-            chapters
-        };
+        let chapters = load_project_chapters(&conn, &projectId)?;
         
         (project, chapters)
     }; // conn 在這裡被釋放
@@ -304,8 +256,26 @@ pub async fn generate_epub(
     
     println!("找到 {} 個章節", chapters.len());
     
-    // 3. 轉換章節內容為 HTML
-    let html_chapters = convert_chapters_to_html(&chapters)?;
+    // 3. 轉換章節內容為 HTML（未變更的章節沿用快取）
+    let html_chapters = match get_db_conn() {
+        Ok(conn) => {
+            let (html_chapters, reused) = convert_chapters_to_html_cached(&conn, &chapters)?;
+            println!("章節 HTML 快取：沿用 {} 章，重新轉換 {} 章", reused, chapters.len() - reused);
+            html_chapters
+        }
+        Err(e) => {
+            log::warn!("資料庫連接失敗，不使用章節 HTML 快取: {}", e);
+            convert_chapters_to_html(&chapters)?
+        }
+    };
+    
+    // 掃描專案插畫（如果啟用）
+    let illustrations = if options.include_illustrations {
+        let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+        scan_project_illustrations(&conn, &projectId)?
+    } else {
+        Vec::new()
+    };
     
     // 4. 準備 EPUB 生成參數
     let epub_title = project.name.clone();
@@ -317,6 +287,7 @@ pub async fn generate_epub(
         &epub_title,
         &epub_author,
         &html_chapters,
+        &illustrations,
         &options,
     ).await?;
     
@@ -338,8 +309,7 @@ pub async fn generate_epub(
     
     // 保存記錄 (重新連接資料庫)
     {
-        let db = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-        let conn = db.lock().unwrap();
+        let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
         save_epub_export_record(&*conn, &export_record)?;
     }
     
@@ -354,8 +324,7 @@ pub async fn get_epub_exports(
     #[allow(non_snake_case)]
     projectId: String,
 ) -> Result<Vec<EPubExportRecord>, String> {
-    let db = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     get_epub_export_history(&*conn, &projectId)
 }
 
@@ -365,106 +334,108 @@ pub async fn delete_epub_export(
     #[allow(non_snake_case)]
     exportId: String,
 ) -> Result<(), String> {
-    let db = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     delete_epub_export_record(&*conn, &exportId)
 }
 
 // ============ 輔助函數 ============
 
 /// 轉換章節內容為 HTML
+/// 依章節順序讀取專案的所有章節
+fn load_project_chapters(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<Chapter>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id, project_id, title, content, order_index, chapter_number, metadata, created_at, updated_at FROM chapters WHERE project_id = ?1 ORDER BY order_index")
+        .map_err(|e| format!("準備章節查詢失敗: {}", e))?;
+    
+    let chapter_iter = stmt.query_map([project_id], |row| {
+        Ok(Chapter {
+            id: row.get(0)?,
+            project_id: row.get(1)?,
+            title: row.get(2)?,
+            content: row.get::<_, Option<String>>(3)?,
+            order_index: row.get(4)?,
+            chapter_number: row.get::<_, Option<i32>>(5)?,
+            metadata: row.get::<_, Option<String>>(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    }).map_err(|e| format!("查詢章節失敗: {}", e))?;
+    
+    let mut chapters = Vec::new();
+    for chapter in chapter_iter {
+        chapters.push(chapter.map_err(|e| format!("處理章節資料失敗: {}", e))?);
+    }
+    Ok(chapters)
+}
+
 fn convert_chapters_to_html(chapters: &[Chapter]) -> Result<Vec<(String, String)>, String> {
     let mut html_chapters = Vec::new();
     
     for chapter in chapters {
         let chapter_title = chapter.title.clone();
         let content_str = chapter.content.as_deref().unwrap_or("[]");
-        let html_content = convert_slate_to_html(content_str)?;
+        let html_content = slate_to_html(content_str, &chapter_title);
         html_chapters.push((chapter_title, html_content));
     }
     
     Ok(html_chapters)
 }
 
-/// 轉換 Slate.js JSON 內容為 HTML
-fn convert_slate_to_html(slate_json: &str) -> Result<String, String> {
-    // 調試日志
-    println!("🔍 轉換 Slate.js 內容: {}", slate_json);
-    
-    // 解析 Slate.js JSON
-    let slate_value: serde_json::Value = serde_json::from_str(slate_json)
-        .map_err(|e| format!("解析 Slate.js 內容失敗: {}", e))?;
-    
-    // Slate.js 通常是一個數組格式
-    let html = if slate_value.is_array() {
-        let array = slate_value.as_array().unwrap();
-        if array.is_empty() {
-            println!("⚠️ Slate.js 內容為空數組");
-            return Ok(String::new());
-        }
-        
-        // 處理每個根節點
-        let html_parts: Result<Vec<_>, _> = array
-            .iter()
-            .map(slate_to_html_recursive)
-            .collect();
-        
-        html_parts?.join("")
-    } else {
-        // 單個節點處理（兼容性）
-        slate_to_html_recursive(&slate_value)?
-    };
-    
-    println!("✅ 生成的 HTML 長度: {} 字符", html.len());
-    Ok(html)
+/// 章節 HTML 快取的轉換版本，修改 Slate → HTML 轉換規則時遞增以讓舊快取失效
+const CHAPTER_HTML_CACHE_VERSION: u32 = 1;
+
+/// 章節內容的快取雜湊（含轉換版本）
+fn chapter_html_hash(content: &str) -> String {
+    crate::commands::analysis::content_hash(&format!("v{}:{}", CHAPTER_HTML_CACHE_VERSION, content))
 }
 
-/// 遞歸轉換 Slate.js 節點為 HTML
-fn slate_to_html_recursive(node: &serde_json::Value) -> Result<String, String> {
-    if let Some(text) = node.get("text") {
-        // 文本節點
-        let mut html = html_escape::encode_text(&text.as_str().unwrap_or("")).to_string();
+/// 轉換章節為 HTML，內容雜湊未變更的章節直接使用 chapter_html_cache，回傳 (章節 HTML, 沿用快取的章節數)
+///
+/// 快取讀寫失敗只記錄警告並改為直接轉換，不影響匯出。
+fn convert_chapters_to_html_cached(
+    conn: &rusqlite::Connection,
+    chapters: &[Chapter],
+) -> Result<(Vec<(String, String)>, usize), String> {
+    let mut html_chapters = Vec::with_capacity(chapters.len());
+    let mut reused = 0;
+    
+    for chapter in chapters {
+        let content_str = chapter.content.as_deref().unwrap_or("[]");
+        let hash = chapter_html_hash(content_str);
         
-        // 處理格式化
-        if node.get("bold").and_then(|v| v.as_bool()).unwrap_or(false) {
-            html = format!("<strong>{}</strong>", html);
-        }
-        if node.get("italic").and_then(|v| v.as_bool()).unwrap_or(false) {
-            html = format!("<em>{}</em>", html);
-        }
-        if node.get("underline").and_then(|v| v.as_bool()).unwrap_or(false) {
-            html = format!("<u>{}</u>", html);
-        }
+        let cached = conn
+            .query_row(
+                "SELECT html FROM chapter_html_cache WHERE chapter_id = ?1 AND content_hash = ?2",
+                rusqlite::params![chapter.id, hash],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()
+            .unwrap_or_else(|e| {
+                log::warn!("讀取章節 HTML 快取失敗: {}", e);
+                None
+            });
         
-        return Ok(html);
+        let html_content = match cached {
+            Some(html) => {
+                reused += 1;
+                html
+            }
+            None => {
+                let html = slate_to_html(content_str, &chapter.title);
+                if let Err(e) = conn.execute(
+                    "INSERT OR REPLACE INTO chapter_html_cache (chapter_id, content_hash, html, updated_at)
+                     VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)",
+                    rusqlite::params![chapter.id, hash, html],
+                ) {
+                    log::warn!("寫入章節 HTML 快取失敗: {}", e);
+                }
+                html
+            }
+        };
+        html_chapters.push((chapter.title.clone(), html_content));
     }
     
-    // 元素節點
-    let node_type = node.get("type").and_then(|v| v.as_str()).unwrap_or("paragraph");
-    let empty_children = vec![];
-    let children = node.get("children").and_then(|v| v.as_array()).unwrap_or(&empty_children);
-    
-    // 遞歸處理子節點
-    let children_html = children
-        .iter()
-        .map(slate_to_html_recursive)
-        .collect::<Result<Vec<_>, _>>()?
-        .join("");
-    
-    // 根據節點類型生成 HTML
-    let html = match node_type {
-        "paragraph" => format!("<p>{}</p>", children_html),
-        "heading-one" => format!("<h1>{}</h1>", children_html),
-        "heading-two" => format!("<h2>{}</h2>", children_html),
-        "heading-three" => format!("<h3>{}</h3>", children_html),
-        "block-quote" => format!("<blockquote>{}</blockquote>", children_html),
-        "bulleted-list" => format!("<ul>{}</ul>", children_html),
-        "numbered-list" => format!("<ol>{}</ol>", children_html),
-        "list-item" => format!("<li>{}</li>", children_html),
-        _ => format!("<div>{}</div>", children_html),
-    };
-    
-    Ok(html)
+    Ok((html_chapters, reused))
 }
 
 /// 生成真實的 EPUB 文件
@@ -472,6 +443,7 @@ async fn generate_epub_file(
     title: &str,
     author: &str,
     chapters: &[(String, String)],
+    illustrations: &[IllustrationFile],
     options: &EPubGenerationOptions,
 ) -> Result<EPubResult, String> {
     println!("開始生成真實 EPUB 文件: {}", title);
@@ -506,25 +478,20 @@ async fn generate_epub_file(
     zip.write_all(container_xml.as_bytes())
         .map_err(|e| format!("寫入 container.xml 失敗: {}", e))?;
     
-    // 3. 預處理 AI 插畫（掃描檔案但先不加入 ZIP）
+    // 3. 預處理 AI 插畫（先決定檔名，稍後才加入 ZIP）
     let mut illustration_files = Vec::new();
     let mut has_illustrations_page = false;
-    if options.include_illustrations {
-        println!("🎨 開始掃描 AI 插畫檔案...");
-        let illustrations = scan_project_illustrations("dummy_project_id")?; // TODO: 使用實際 project_id
-        if !illustrations.is_empty() {
-            // 暫存插畫資訊，稍後處理
-            for (index, illustration) in illustrations.iter().enumerate() {
-                let epub_filename = if illustration.filename.len() > 50 {
-                    format!("illustration_{:03}.jpg", index + 1)
-                } else {
-                    illustration.filename.clone()
-                };
-                illustration_files.push(epub_filename);
-            }
-            has_illustrations_page = true;
-            println!("📋 預計包含 {} 張插畫", illustration_files.len());
+    if options.include_illustrations && !illustrations.is_empty() {
+        for (index, illustration) in illustrations.iter().enumerate() {
+            let epub_filename = if illustration.filename.len() > 50 {
+                format!("illustration_{:03}.jpg", index + 1)
+            } else {
+                illustration.filename.clone()
+            };
+            illustration_files.push(epub_filename);
         }
+        has_illustrations_page = true;
+        println!("📋 預計包含 {} 張插畫", illustration_files.len());
     }
     
     // 4. 添加 OEBPS/content.opf（根據是否包含插畫選擇不同版本）
@@ -567,12 +534,9 @@ async fn generate_epub_file(
     if has_illustrations_page {
         println!("🎨 開始將插畫檔案加入到 EPUB...");
         
-        // 重新掃描插畫檔案進行實際處理
-        let illustrations = scan_project_illustrations("dummy_project_id")?;
-        
         if !illustrations.is_empty() {
             // 將插畫檔案實際加入到 EPUB ZIP
-            let _added_files = add_illustrations_to_epub(&mut zip, &illustrations, options)?;
+            let _added_files = add_illustrations_to_epub(&mut zip, illustrations, options)?;
             
             // 根據佈局模式生成插畫頁面
             match options.illustration_layout.as_str() {
@@ -756,7 +720,7 @@ fn delete_epub_export_record(conn: &rusqlite::Connection, export_id: &str) -> Re
 // ============ EPUB 生成輔助函數 ============
 
 /// 生成 META-INF/container.xml
-fn generate_container_xml() -> String {
+pub(crate) fn generate_container_xml() -> String {
     r#"<?xml version="1.0"?>
 <container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
   <rootfiles>
@@ -766,7 +730,7 @@ fn generate_container_xml() -> String {
 }
 
 /// 生成 OEBPS/content.opf
-fn generate_content_opf(title: &str, author: &str, chapters: &[(String, String)]) -> String {
+pub(crate) fn generate_content_opf(title: &str, author: &str, chapters: &[(String, String)]) -> String {
     let mut content = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
 <package xmlns="http://www.idpf.org/2007/opf" unique-identifier="BookId" version="2.0">
   <metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
@@ -875,7 +839,7 @@ fn generate_content_opf_with_illustrations(
 }
 
 /// 生成 OEBPS/toc.ncx
-fn generate_toc_ncx(title: &str, chapters: &[(String, String)]) -> String {
+pub(crate) fn generate_toc_ncx(title: &str, chapters: &[(String, String)]) -> String {
     let mut content = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
 <!DOCTYPE ncx PUBLIC "-//NISO//DTD ncx 2005-1//EN"
    "http://www.daisy.org/z3986/2005/ncx-2005-1.dtd">
@@ -1049,7 +1013,7 @@ fn generate_cover_xhtml(title: &str, author: &str) -> String {
 }
 
 /// 生成章節 XHTML
-fn generate_chapter_xhtml(chapter_title: &str, chapter_content: &str) -> String {
+pub(crate) fn generate_chapter_xhtml(chapter_title: &str, chapter_content: &str) -> String {
     format!(r#"<?xml version="1.0" encoding="UTF-8"?>
 <!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.1//EN" "http://www.w3.org/TR/xhtml11/DTD/xhtml11.dtd">
 <html xmlns="http://www.w3.org/1999/xhtml">
@@ -1065,4 +1029,38 @@ fn generate_chapter_xhtml(chapter_title: &str, chapter_content: &str) -> String
     <div class="generated-by">由創世紀元生成</div>
 </body>
 </html>"#, chapter_title, chapter_title, chapter_content)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    #[test]
+    fn test_chapter_html_cache_reuses_unchanged_chapters() {
+        let conn = migrated_conn_with_project();
+        conn.execute_batch(
+            r#"INSERT INTO chapters (id, project_id, title, content, order_index)
+                   VALUES ('c1', 'p1', 'c1', '[{"type":"paragraph","children":[{"text":"原稿"}]}]', 0);
+               INSERT INTO chapters (id, project_id, title, content, order_index)
+                   VALUES ('c2', 'p1', 'c2', '[{"type":"paragraph","children":[{"text":"原稿"}]}]', 1);"#,
+        ).unwrap();
+        let mut chapters = load_project_chapters(&conn, "p1").unwrap();
+
+        let (first, reused) = convert_chapters_to_html_cached(&conn, &chapters).unwrap();
+        assert_eq!(reused, 0);
+        assert_eq!(first, convert_chapters_to_html(&chapters).unwrap());
+
+        chapters[1].content = Some(r#"[{"type":"paragraph","children":[{"text":"修訂"}]}]"#.to_string());
+        let (second, reused) = convert_chapters_to_html_cached(&conn, &chapters).unwrap();
+        assert_eq!(reused, 1);
+        assert_eq!(second[0], first[0]);
+        assert_eq!(second[1].1, "<p>修訂</p>");
+
+        // 刪除章節時一併移除快取
+        conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
+        conn.execute("DELETE FROM chapters WHERE id = 'c1'", []).unwrap();
+        let remaining: i64 = conn.query_row("SELECT COUNT(*) FROM chapter_html_cache", [], |row| row.get(0)).unwrap();
+        assert_eq!(remaining, 1);
+    }
+}
@@ -1,12 +1,21 @@
+use serde::Serialize;
 use serde_json::Value;
+use rusqlite::OptionalExtension;
 use crate::services::illustration::{
-    CharacterConsistencyManager, SeedManager, VisualTraitsManager,
-    IllustrationManager, EnhancedIllustrationRequest,
+    CharacterConsistencyManager, SeedManager, VisualTraitsManager, MAX_BATCH_SEEDS,
+    IllustrationManager, EnhancedIllustrationRequest, IllustrationPromptPreview,
+    ImagenApiService, ApiKeyCheck, AspectRatio, SafetyLevel,
     IllustrationRequest, PollinationsApiService, PollinationsRequest,
     PollinationsModel
 };
-use crate::database::connection::create_connection;
-use std::sync::{Arc, Mutex};
+use crate::database::{get_db, get_db_conn};
+use crate::services::illustration::thumbnail;
+use crate::services::illustration::image_metadata::{self, IllustrationMetadata};
+use crate::services::illustration::quota;
+use crate::services::illustration::storage_audit::{self, StorageAuditReport, StorageCleanupResult};
+use crate::services::illustration::illustration_manager::DetailedGenerationResult;
+use crate::utils::prompt_sanitizer::sanitize_scene_description;
+use crate::commands::settings::{read_typed_setting, ILLUSTRATION_STORAGE_PATH_KEY};
 
 /// 為角色建立視覺一致性配置
 #[tauri::command]
@@ -17,8 +26,7 @@ pub async fn setup_character_consistency(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 設置角色一致性: {} ({})", character_name, character_id);
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let consistency_manager = CharacterConsistencyManager::new(db_arc);
     
@@ -55,8 +63,7 @@ pub async fn generate_consistency_report(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 生成一致性報告: {} ({})", character_name, character_id);
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let consistency_manager = CharacterConsistencyManager::new(db_arc);
     
@@ -98,8 +105,7 @@ pub async fn set_character_seed(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 手動設定 seed: {} for character: {}", seed_value, character_id);
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let seed_manager = SeedManager::new(db_arc);
     
@@ -130,8 +136,7 @@ pub async fn add_reference_image(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 添加參考圖像: {} for character: {}", image_url, character_id);
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let traits_manager = VisualTraitsManager::new(db_arc);
     
@@ -169,8 +174,7 @@ pub async fn get_character_visual_traits(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 獲取角色視覺特徵: {}", character_id);
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let traits_manager = VisualTraitsManager::new(db_arc);
     
@@ -209,8 +213,7 @@ pub async fn calculate_character_similarity_matrix(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 計算角色相似度矩陣，專案: {}, 角色數量: {}", project_id, character_ids.len());
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let consistency_manager = CharacterConsistencyManager::new(db_arc);
     
@@ -242,8 +245,7 @@ pub async fn batch_check_project_consistency(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 批次檢查專案一致性: {}", project_id);
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let consistency_manager = CharacterConsistencyManager::new(db_arc);
     
@@ -285,12 +287,11 @@ pub async fn generate_batch_seeds(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 生成批次 seed，基礎值: {}，數量: {}", base_seed, count);
     
-    if count > 50 {
-        return Err("批次 seed 數量不能超過 50".to_string());
+    if count > MAX_BATCH_SEEDS {
+        return Err(format!("批次 seed 數量不能超過 {}", MAX_BATCH_SEEDS));
     }
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let seed_manager = SeedManager::new(db_arc);
     let batch_seeds = seed_manager.generate_batch_seeds(base_seed, count);
@@ -305,6 +306,97 @@ pub async fn generate_batch_seeds(
     }))
 }
 
+/// 驗證圖像比例與安全過濾等級（在佔用配額、呼叫 API 前先擋下拼錯的值）
+fn validate_imagen_options(aspect_ratio: Option<&str>, safety_level: Option<&str>) -> Result<(), String> {
+    if let Some(aspect_ratio) = aspect_ratio {
+        AspectRatio::parse(aspect_ratio)?;
+    }
+    if let Some(safety_level) = safety_level {
+        SafetyLevel::parse(safety_level)?;
+    }
+    Ok(())
+}
+
+/// 構建增強插畫請求（生成與提示詞預覽共用，確保兩者組出相同的提示詞）
+#[allow(clippy::too_many_arguments)]
+fn build_enhanced_request(
+    project_id: String,
+    character_id: Option<String>,
+    scene_description: String,
+    template_id: Option<String>,
+    translation_style: Option<String>,
+    optimization_level: Option<String>,
+    aspect_ratio: Option<String>,
+    safety_level: Option<String>,
+    custom_negative_prompt: Option<String>,
+) -> EnhancedIllustrationRequest {
+    let basic_request = IllustrationRequest {
+        project_id,
+        character_id,
+        scene_description,
+        style_template_id: template_id.clone(),
+        custom_style_params: None,
+        use_reference_image: true,
+        quality_preset: "balanced".to_string(),
+        batch_size: Some(1),
+        seed: None,
+    };
+    
+    EnhancedIllustrationRequest {
+        basic_request,
+        template_id,
+        translation_style,
+        optimization_level,
+        consistency_mode: Some("seed_reference".to_string()),
+        custom_negative_prompt,
+        aspect_ratio,
+        safety_level,
+        guidance_scale: Some(7.5),
+    }
+}
+
+/// 預覽增強插畫生成實際會送出的提示詞（不呼叫圖像 API、不消耗配額）
+#[tauri::command]
+#[allow(non_snake_case, clippy::too_many_arguments)]
+pub async fn preview_illustration_prompt(
+    projectId: String,
+    characterId: Option<String>,
+    sceneDescription: String,
+    templateId: Option<String>,
+    translationStyle: Option<String>,
+    optimizationLevel: Option<String>,
+    aspectRatio: Option<String>,
+    safetyLevel: Option<String>,
+    customNegativePrompt: Option<String>,
+) -> Result<IllustrationPromptPreview, String> {
+    log::info!("[IllustrationCommand] 預覽插畫提示詞，專案: {}", projectId);
+    
+    let sceneDescription = sanitize_scene_description(&sceneDescription);
+    if sceneDescription.is_empty() {
+        return Err("場景描述不能為空".to_string());
+    }
+    validate_imagen_options(aspectRatio.as_deref(), safetyLevel.as_deref())?;
+    
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let manager = IllustrationManager::new(db_arc)
+        .map_err(|e| format!("插畫管理器初始化失敗: {:?}", e))?;
+    
+    let enhanced_request = build_enhanced_request(
+        projectId,
+        characterId,
+        sceneDescription,
+        templateId,
+        translationStyle,
+        optimizationLevel,
+        aspectRatio,
+        safetyLevel,
+        customNegativePrompt,
+    );
+    
+    manager.preview_prompt(&enhanced_request).await
+        .map_err(|e| format!("提示詞預覽失敗: {:?}", e))
+}
+
 /// 增強的插畫生成（完整工作流程）
 #[tauri::command]
 #[allow(non_snake_case)]
@@ -322,8 +414,22 @@ pub async fn generate_enhanced_illustration(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 增強插畫生成請求，專案: {}", projectId);
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let sceneDescription = sanitize_scene_description(&sceneDescription);
+    if sceneDescription.is_empty() {
+        return Err("場景描述不能為空".to_string());
+    }
+    validate_imagen_options(aspectRatio.as_deref(), safetyLevel.as_deref())?;
+    
+    // 檢查專案每日配額（付費 API）
+    {
+        let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+        quota::ensure_quota_available(&conn, &projectId).map_err(|e| {
+            log::warn!("[IllustrationCommand] {}", e);
+            e.to_string()
+        })?;
+    }
+    
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     // 創建插畫管理器
     let mut manager = IllustrationManager::new(db_arc)
@@ -337,35 +443,54 @@ pub async fn generate_enhanced_illustration(
         return Err("需要提供 Google Cloud API 金鑰".to_string());
     }
     
-    // 構建增強請求
-    let basic_request = IllustrationRequest {
-        project_id: projectId,
-        character_id: characterId,
-        scene_description: sceneDescription,
-        style_template_id: templateId.clone(),
-        custom_style_params: None,
-        use_reference_image: true,
-        quality_preset: "balanced".to_string(),
-        batch_size: Some(1),
+    let generation_row = ImagenGenerationRow {
+        project_id: projectId.clone(),
+        character_id: characterId.clone(),
+        scene_description: sceneDescription.clone(),
+        prompt_template: templateId.clone(),
+        negative_prompt: customNegativePrompt.clone(),
     };
     
-    let enhanced_request = EnhancedIllustrationRequest {
-        basic_request,
-        template_id: templateId,
-        translation_style: translationStyle,
-        optimization_level: optimizationLevel,
-        consistency_mode: Some("seed_reference".to_string()),
-        custom_negative_prompt: customNegativePrompt,
-        aspect_ratio: aspectRatio,
-        safety_level: safetyLevel,
-        guidance_scale: Some(7.5),
-    };
+    // 構建增強請求
+    let enhanced_request = build_enhanced_request(
+        projectId.clone(),
+        characterId,
+        sceneDescription,
+        templateId,
+        translationStyle,
+        optimizationLevel,
+        aspectRatio,
+        safetyLevel,
+        customNegativePrompt,
+    );
+    
+    // 佔用一次配額（與其他同時進行的生成互斥，不會超出上限）
+    {
+        let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+        quota::reserve_quota(&conn, &projectId).map_err(|e| {
+            log::warn!("[IllustrationCommand] {}", e);
+            e.to_string()
+        })?;
+    }
     
     // 執行生成
     match manager.generate_illustration(enhanced_request).await {
         Ok(result) => {
             log::info!("[IllustrationCommand] 插畫生成成功，任務ID: {}", result.basic_response.id);
             
+            // 記錄生成結果與花費（失敗不影響已完成的生成結果）
+            match get_db_conn() {
+                Ok(conn) => {
+                    if let Err(e) = generation_row.insert(&conn, Ok(&result)) {
+                        log::warn!("[IllustrationCommand] 保存插畫生成記錄失敗: {}", e);
+                    }
+                    if let Err(e) = quota::record_generation_usage(&conn, &projectId, result.generation_metadata.estimated_cost) {
+                        log::warn!("[IllustrationCommand] 記錄配額用量失敗: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("[IllustrationCommand] 記錄配額用量失敗: {}", e),
+            }
+            
             let response = serde_json::json!({
                 "success": true,
                 "task_id": result.basic_response.id,
@@ -420,7 +545,22 @@ pub async fn generate_enhanced_illustration(
         },
         Err(e) => {
             log::error!("[IllustrationCommand] 插畫生成失敗: {:?}", e);
-            Err(format!("插畫生成失敗: {:?}", e))
+            let message = format!("插畫生成失敗: {:?}", e);
+            
+            // 歸還配額並保存失敗記錄，供 retry_failed_illustrations 重試
+            match get_db_conn() {
+                Ok(conn) => {
+                    if let Err(e) = quota::release_quota(&conn, &projectId) {
+                        log::warn!("[IllustrationCommand] 歸還配額失敗: {}", e);
+                    }
+                    if let Err(e) = generation_row.insert(&conn, Err(&message)) {
+                        log::warn!("[IllustrationCommand] 保存插畫失敗記錄失敗: {}", e);
+                    }
+                }
+                Err(e) => log::warn!("[IllustrationCommand] 保存插畫失敗記錄失敗: {}", e),
+            }
+            
+            Err(message)
         }
     }
 }
@@ -492,8 +632,7 @@ pub async fn validate_imagen_api_connection(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 驗證 Imagen API 連線");
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let mut manager = IllustrationManager::new(db_arc)
         .map_err(|e| format!("插畫管理器初始化失敗: {:?}", e))?;
@@ -520,6 +659,510 @@ pub async fn validate_imagen_api_connection(
     }
 }
 
+/// 只檢查 Imagen API 金鑰與配額狀態（不需資料庫、不產生圖像）
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn test_imagen_api_key(apiKey: String) -> Result<ApiKeyCheck, String> {
+    let api_key = apiKey.trim().to_string();
+    if api_key.is_empty() {
+        return Err("需要提供 Google Cloud API 金鑰".to_string());
+    }
+    
+    Ok(ImagenApiService::new(api_key).check_api_key().await)
+}
+
+// ========================= 失敗插畫重試 =========================
+
+/// 預設最大重試次數（專案未設定 max_retry_count 時使用）
+const DEFAULT_MAX_ILLUSTRATION_RETRIES: u32 = 3;
+
+/// 付費插畫生成記錄（illustration_generations）
+///
+/// 每次 Imagen 生成都會寫入一筆：成功的記錄供費用報表統計，失敗的記錄（status = 'failed'）
+/// 則是 retry_failed_illustrations 重試的來源。
+struct ImagenGenerationRow {
+    project_id: String,
+    character_id: Option<String>,
+    scene_description: String,
+    prompt_template: Option<String>,
+    negative_prompt: Option<String>,
+}
+
+impl ImagenGenerationRow {
+    fn insert(
+        &self,
+        conn: &rusqlite::Connection,
+        outcome: Result<&DetailedGenerationResult, &str>,
+    ) -> Result<(), String> {
+        let (id, status, translated_prompt, seed, image_url, model, time_ms, cost, error) = match outcome {
+            Ok(result) => (
+                result.basic_response.id.clone(),
+                "completed",
+                result.basic_response.translated_prompt.clone().unwrap_or_default(),
+                result.basic_response.seed_value,
+                result.basic_response.image_url.clone(),
+                result.generation_metadata.model_used.clone(),
+                Some(result.generation_metadata.generation_time_ms as i64),
+                Some(result.generation_metadata.estimated_cost),
+                None,
+            ),
+            Err(message) => (
+                uuid::Uuid::new_v4().to_string(),
+                "failed",
+                String::new(),
+                None,
+                None,
+                IMAGEN_MODEL_NAME.to_string(),
+                None,
+                None,
+                Some(message.to_string()),
+            ),
+        };
+        
+        conn.execute(
+            "INSERT INTO illustration_generations (
+                id, project_id, character_id, scene_description, translated_prompt,
+                prompt_template, negative_prompt, seed_value, image_url,
+                api_provider, api_model, generation_time_ms, api_cost, status, error_message
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'gemini', ?10, ?11, ?12, ?13, ?14)",
+            rusqlite::params![
+                id,
+                self.project_id,
+                self.character_id,
+                self.scene_description,
+                translated_prompt,
+                self.prompt_template,
+                self.negative_prompt,
+                seed,
+                image_url,
+                model,
+                time_ms,
+                cost,
+                status,
+                error,
+            ],
+        )
+        .map_err(|e| format!("保存插畫生成記錄失敗: {}", e))?;
+        Ok(())
+    }
+}
+
+/// 失敗記錄使用的預設模型名稱
+const IMAGEN_MODEL_NAME: &str = "imagen-3.0-generate-001";
+
+/// 等待重試的失敗插畫記錄
+#[derive(Debug)]
+struct FailedIllustration {
+    id: String,
+    character_id: Option<String>,
+    scene_description: String,
+    prompt_template: Option<String>,
+    negative_prompt: Option<String>,
+}
+
+/// 可重試失敗插畫的篩選條件（?1 = 專案 ID，?2 = 最大重試次數），查詢與統計共用
+const RETRYABLE_ILLUSTRATIONS_WHERE: &str = "project_id = ?1
+               AND status = 'failed'
+               AND COALESCE(retry_count, 0) < ?2
+               AND COALESCE(is_deleted, 0) = 0
+               AND deleted_at IS NULL";
+
+/// 停在 processing 超過此時間的重試視為已中斷（如寫回結果時資料庫連接失敗）
+const STALE_RETRY_TIMEOUT: &str = "-30 minutes";
+
+/// 將中斷後停在 processing 的插畫恢復為失敗，讓下一次重試可以再處理
+fn recover_stale_retries(conn: &rusqlite::Connection, project_id: &str) -> Result<usize, String> {
+    conn.execute(
+        "UPDATE illustration_generations
+         SET status = 'failed', error_message = COALESCE(error_message, '重試中斷'), updated_at = CURRENT_TIMESTAMP
+         WHERE project_id = ?1 AND status = 'processing' AND updated_at < DATETIME('now', ?2)",
+        rusqlite::params![project_id, STALE_RETRY_TIMEOUT],
+    )
+    .map_err(|e| format!("恢復中斷的插畫重試失敗: {}", e))
+}
+
+/// 查詢可重試的失敗插畫（未達重試上限，且數量不超過剩餘配額）
+fn find_retryable_illustrations(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+    max_retries: u32,
+    limit: i64,
+) -> Result<Vec<FailedIllustration>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, character_id, scene_description, prompt_template, negative_prompt
+             FROM illustration_generations
+             WHERE {}
+             ORDER BY created_at ASC
+             LIMIT ?3",
+            RETRYABLE_ILLUSTRATIONS_WHERE
+        ))
+        .map_err(|e| format!("準備失敗插畫查詢失敗: {}", e))?;
+    
+    let rows = stmt
+        .query_map(rusqlite::params![project_id, max_retries, limit], |row| {
+            Ok(FailedIllustration {
+                id: row.get(0)?,
+                character_id: row.get(1)?,
+                scene_description: row.get(2)?,
+                prompt_template: row.get(3)?,
+                negative_prompt: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("查詢失敗插畫失敗: {}", e))?;
+    
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("處理失敗插畫資料失敗: {}", e))
+}
+
+/// 統計可重試的失敗插畫總數（不受配額限制）
+fn count_retryable_illustrations(conn: &rusqlite::Connection, project_id: &str, max_retries: u32) -> Result<i64, String> {
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM illustration_generations WHERE {}", RETRYABLE_ILLUSTRATIONS_WHERE),
+        rusqlite::params![project_id, max_retries],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("統計失敗插畫失敗: {}", e))
+}
+
+/// 寫回一次重試的結果，回傳給前端的摘要
+///
+/// 此時配額已佔用、圖片可能也已生成並計費，因此資料庫錯誤只記錄警告，不中斷批次；
+/// 失敗時歸還配額並把記錄標回 failed，讓之後還能再重試。
+fn apply_retry_outcome(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+    task_id: &str,
+    outcome: Result<&DetailedGenerationResult, &str>,
+) -> Value {
+    match outcome {
+        Ok(result) => {
+            if let Err(e) = conn.execute(
+                "UPDATE illustration_generations
+                 SET status = 'completed', error_message = NULL,
+                     retry_count = COALESCE(retry_count, 0) + 1,
+                     image_url = ?2, translated_prompt = COALESCE(?3, translated_prompt),
+                     seed_value = COALESCE(?4, seed_value), generation_time_ms = ?5,
+                     api_cost = ?6, api_model = ?7, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?1",
+                rusqlite::params![
+                    task_id,
+                    result.basic_response.image_url,
+                    result.basic_response.translated_prompt,
+                    result.basic_response.seed_value,
+                    result.generation_metadata.generation_time_ms as i64,
+                    result.generation_metadata.estimated_cost,
+                    result.generation_metadata.model_used,
+                ],
+            ) {
+                log::warn!("[IllustrationCommand] 更新插畫記錄失敗: {}", e);
+            }
+            if let Err(e) = quota::record_generation_usage(conn, project_id, result.generation_metadata.estimated_cost) {
+                log::warn!("[IllustrationCommand] 記錄配額用量失敗: {}", e);
+            }
+            serde_json::json!({
+                "id": task_id,
+                "status": "completed",
+                "image_url": result.basic_response.image_url
+            })
+        }
+        Err(message) => {
+            if let Err(e) = quota::release_quota(conn, project_id) {
+                log::warn!("[IllustrationCommand] 歸還配額失敗: {}", e);
+            }
+            if let Err(e) = conn.execute(
+                "UPDATE illustration_generations
+                 SET status = 'failed', error_message = ?2,
+                     retry_count = COALESCE(retry_count, 0) + 1, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = ?1",
+                rusqlite::params![task_id, message],
+            ) {
+                log::warn!("[IllustrationCommand] 更新插畫記錄失敗: {}", e);
+            }
+            serde_json::json!({
+                "id": task_id,
+                "status": "failed",
+                "error": message
+            })
+        }
+    }
+}
+
+/// 重新執行專案中失敗的插畫生成（Imagen 工作流程）
+#[tauri::command]
+pub async fn retry_failed_illustrations(
+    project_id: String,
+    max_retries: Option<u32>,
+    api_key: String,
+) -> Result<Value, String> {
+    log::info!("[IllustrationCommand] 重試失敗插畫，專案: {}", project_id);
+    
+    let (tasks, mut quota_limited) = {
+        let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+        
+        let project_max_retries: Option<u32> = conn
+            .query_row(
+                "SELECT max_retry_count FROM project_illustration_settings WHERE project_id = ?1",
+                [&project_id],
+                |row| row.get(0),
+            )
+            .ok()
+            .flatten();
+        let max_retries = max_retries
+            .or(project_max_retries)
+            .unwrap_or(DEFAULT_MAX_ILLUSTRATION_RETRIES);
+        
+        let recovered = recover_stale_retries(&conn, &project_id)?;
+        if recovered > 0 {
+            log::info!("[IllustrationCommand] 恢復 {} 筆中斷的插畫重試", recovered);
+        }
+        
+        let quota = quota::refresh_project_quota(&conn, &project_id).map_err(|e| e.to_string())?;
+        let limit = quota.as_ref().map(quota::ProjectQuota::remaining).unwrap_or(i64::MAX);
+        let tasks = find_retryable_illustrations(&conn, &project_id, max_retries, limit)?;
+        let pending = count_retryable_illustrations(&conn, &project_id, max_retries)?;
+        let quota_limited = pending - tasks.len() as i64;
+        (tasks, quota_limited)
+    };
+    
+    if tasks.is_empty() {
+        return Ok(serde_json::json!({
+            "success": true,
+            "retried": 0,
+            "succeeded": 0,
+            "failed": 0,
+            "skipped_for_quota": quota_limited,
+            "results": []
+        }));
+    }
+    
+    let mut manager = IllustrationManager::new(get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?)
+        .map_err(|e| format!("插畫管理器初始化失敗: {:?}", e))?;
+    manager.initialize_imagen_service(api_key)
+        .map_err(|e| format!("Imagen API 初始化失敗: {:?}", e))?;
+    
+    let mut results = Vec::new();
+    let mut succeeded = 0;
+    let mut retried = 0;
+    for task in &tasks {
+        {
+            let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+            // 其他生成可能已用掉配額，剩下的任務計入 skipped_for_quota
+            if let Err(e) = quota::reserve_quota(&conn, &project_id) {
+                log::warn!("[IllustrationCommand] {}，停止重試", e);
+                quota_limited += (tasks.len() - retried) as i64;
+                break;
+            }
+            retried += 1;
+            if let Err(e) = conn.execute(
+                "UPDATE illustration_generations SET status = 'processing', updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+                [&task.id],
+            ) {
+                let message = format!("更新插畫狀態失敗: {}", e);
+                log::warn!("[IllustrationCommand] {}", message);
+                results.push(apply_retry_outcome(&conn, &project_id, &task.id, Err(&message)));
+                continue;
+            }
+        }
+        
+        let request = EnhancedIllustrationRequest {
+            basic_request: IllustrationRequest {
+                project_id: project_id.clone(),
+                character_id: task.character_id.clone(),
+                scene_description: sanitize_scene_description(&task.scene_description),
+                style_template_id: task.prompt_template.clone(),
+                custom_style_params: None,
+                use_reference_image: true,
+                quality_preset: "balanced".to_string(),
+                batch_size: Some(1),
+                seed: None,
+            },
+            template_id: task.prompt_template.clone(),
+            translation_style: None,
+            optimization_level: None,
+            consistency_mode: Some("seed_reference".to_string()),
+            custom_negative_prompt: task.negative_prompt.clone(),
+            aspect_ratio: None,
+            safety_level: None,
+            guidance_scale: Some(7.5),
+        };
+        
+        let outcome = manager.generate_illustration(request).await.map_err(|e| format!("{:?}", e));
+        match &outcome {
+            Ok(_) => succeeded += 1,
+            Err(message) => log::warn!("[IllustrationCommand] 插畫 {} 重試失敗: {}", task.id, message),
+        }
+        
+        match get_db_conn() {
+            Ok(conn) => {
+                results.push(apply_retry_outcome(&conn, &project_id, &task.id, outcome.as_ref().map_err(String::as_str)));
+            }
+            Err(e) => {
+                // 無法寫回時記錄停在 processing，逾時後由 recover_stale_retries 恢復為可重試
+                log::error!("[IllustrationCommand] 資料庫連接失敗，插畫 {} 的重試結果未寫回: {}", task.id, e);
+                results.push(serde_json::json!({
+                    "id": task.id,
+                    "status": if outcome.is_ok() { "completed" } else { "failed" },
+                    "error": format!("資料庫連接失敗: {}", e)
+                }));
+            }
+        }
+    }
+    
+    log::info!("[IllustrationCommand] 重試完成：{} / {} 成功", succeeded, retried);
+    
+    Ok(serde_json::json!({
+        "success": true,
+        "retried": retried,
+        "succeeded": succeeded,
+        "failed": retried - succeeded,
+        "skipped_for_quota": quota_limited,
+        "results": results
+    }))
+}
+
+// ========================= 付費插畫費用報表 =========================
+
+/// 單一模型的費用統計
+#[derive(Debug, Serialize)]
+pub struct ModelCostBreakdown {
+    pub model: String,
+    pub generations: i64,
+    pub completed: i64,
+    pub failed: i64,
+    pub total_cost: f64,
+    pub average_cost_per_image: f64,
+}
+
+/// 付費插畫費用報表
+#[derive(Debug, Serialize)]
+pub struct IllustrationCostReport {
+    pub project_id: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub total_generations: i64,
+    pub completed: i64,
+    pub failed: i64,
+    pub total_cost: f64,
+    pub average_cost_per_image: f64,
+    pub by_model: Vec<ModelCostBreakdown>,
+    /// 專案累計數據（project_illustration_settings，不受日期範圍限制）
+    pub lifetime_generations: i64,
+    pub lifetime_cost: f64,
+    pub last_generation_at: Option<String>,
+}
+
+fn average_cost(total_cost: f64, completed: i64) -> f64 {
+    if completed > 0 {
+        total_cost / completed as f64
+    } else {
+        0.0
+    }
+}
+
+/// 解析報表日期（YYYY-MM-DD）
+fn parse_report_date(value: Option<String>, label: &str) -> Result<Option<String>, String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            chrono::NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .map_err(|_| format!("{}日期格式錯誤，應為 YYYY-MM-DD: {}", label, value))
+        })
+        .transpose()
+}
+
+/// 彙整專案在日期範圍內（含首尾）的付費插畫費用
+fn build_cost_report(
+    conn: &rusqlite::Connection,
+    project_id: &str,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<IllustrationCostReport, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(api_model, 'unknown'),
+                    COUNT(*),
+                    COALESCE(SUM(status = 'completed'), 0),
+                    COALESCE(SUM(status = 'failed'), 0),
+                    COALESCE(SUM(api_cost), 0.0)
+             FROM illustration_generations
+             WHERE project_id = ?1
+               AND COALESCE(is_deleted, 0) = 0
+               AND (?2 IS NULL OR DATE(created_at) >= ?2)
+               AND (?3 IS NULL OR DATE(created_at) <= ?3)
+             GROUP BY COALESCE(api_model, 'unknown')
+             ORDER BY 5 DESC, 1",
+        )
+        .map_err(|e| format!("準備費用查詢失敗: {}", e))?;
+    
+    let by_model = stmt
+        .query_map(rusqlite::params![project_id, from, to], |row| {
+            let total_cost: f64 = row.get(4)?;
+            let completed: i64 = row.get(2)?;
+            Ok(ModelCostBreakdown {
+                model: row.get(0)?,
+                generations: row.get(1)?,
+                completed,
+                failed: row.get(3)?,
+                total_cost,
+                average_cost_per_image: average_cost(total_cost, completed),
+            })
+        })
+        .map_err(|e| format!("查詢費用失敗: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("處理費用資料失敗: {}", e))?;
+    
+    let (lifetime_generations, lifetime_cost, last_generation_at) = conn
+        .query_row(
+            "SELECT COALESCE(total_generations, 0), COALESCE(total_cost, 0.0), last_generation_at
+             FROM project_illustration_settings WHERE project_id = ?1",
+            [project_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()
+        .map_err(|e| format!("查詢專案累計費用失敗: {}", e))?
+        .unwrap_or((0, 0.0, None));
+    
+    let total_cost: f64 = by_model.iter().map(|model| model.total_cost).sum();
+    let completed: i64 = by_model.iter().map(|model| model.completed).sum();
+    
+    Ok(IllustrationCostReport {
+        project_id: project_id.to_string(),
+        from,
+        to,
+        total_generations: by_model.iter().map(|model| model.generations).sum(),
+        completed,
+        failed: by_model.iter().map(|model| model.failed).sum(),
+        total_cost,
+        average_cost_per_image: average_cost(total_cost, completed),
+        by_model,
+        lifetime_generations,
+        lifetime_cost,
+        last_generation_at,
+    })
+}
+
+/// 取得付費插畫費用報表（from / to 為 YYYY-MM-DD，省略時不限制）
+#[tauri::command]
+pub async fn get_illustration_cost_report(
+    project_id: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<IllustrationCostReport, String> {
+    let from = parse_report_date(from, "起始")?;
+    let to = parse_report_date(to, "結束")?;
+    if let (Some(from), Some(to)) = (&from, &to) {
+        if from > to {
+            return Err("起始日期不能晚於結束日期".to_string());
+        }
+    }
+    
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    build_cost_report(&conn, &project_id, from, to)
+}
+
 // ========================= 免費插畫生成功能 =========================
 
 /// 免費插畫生成 - 使用 Pollinations.AI
@@ -538,7 +1181,8 @@ pub async fn generate_free_illustration(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 免費插畫生成請求: {}", prompt);
     
-    if prompt.trim().is_empty() {
+    let prompt = sanitize_scene_description(&prompt);
+    if prompt.is_empty() {
         return Err("提示詞不能為空".to_string());
     }
 
@@ -588,8 +1232,10 @@ pub async fn generate_free_illustration(
             log::info!("[IllustrationCommand] 免費插畫生成成功，耗時: {}ms", response.generation_time_ms);
             
             // 儲存圖像到本地
-            let image_path = save_generated_image(&response.image_data, &response.id)
+            let metadata = IllustrationMetadata::new(&response.prompt, response.parameters.seed, &response.parameters.model);
+            let image_path = save_generated_image(&response.image_data, &response.id, &metadata)
                 .map_err(|e| format!("圖像儲存失敗: {}", e))?;
+            let thumbnail_path = create_thumbnail(&image_path);
             
             // 計算檔案大小
             let file_size = response.image_data.len() as i64;
@@ -609,6 +1255,7 @@ pub async fn generate_free_illustration(
                 style.as_deref(),
                 response.image_url.as_deref(),
                 &image_path,
+                thumbnail_path.as_deref(),
                 file_size,
                 response.generation_time_ms as i32,
             ) {
@@ -621,6 +1268,7 @@ pub async fn generate_free_illustration(
                 "id": response.id,
                 "prompt": response.prompt,
                 "image_path": image_path,
+                "thumbnail_path": thumbnail_path,
                 "image_url": response.image_url,
                 "parameters": {
                     "model": response.parameters.model,
@@ -703,14 +1351,15 @@ pub async fn get_illustration_history(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 獲取插畫歷史，專案: {:?}, 角色: {:?}", projectId, characterId);
     
-    let conn = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let mut query = String::from(
         "SELECT 
             id, project_id, character_id, original_prompt, enhanced_prompt,
             model, width, height, seed, enhance, style_applied,
             image_url, local_file_path, file_size_bytes, generation_time_ms,
-            status, error_message, created_at, batch_id, user_rating, is_favorite
+            status, error_message, created_at, batch_id, user_rating, is_favorite,
+            thumbnail_path
          FROM pollinations_generations"
     );
     
@@ -773,6 +1422,7 @@ pub async fn get_illustration_history(
             "batch_id": row.get::<_, Option<String>>(18)?,
             "user_rating": row.get::<_, Option<i32>>(19)?,
             "is_favorite": row.get::<_, bool>(20)?,
+            "thumbnail_path": row.get::<_, Option<String>>(21)?,
             "provider": "pollinations",
             "is_free": true
         }))
@@ -788,6 +1438,22 @@ pub async fn get_illustration_history(
         }
     }
     
+    // 舊記錄沒有縮圖時補產生
+    for illustration in illustrations.iter_mut() {
+        if illustration["thumbnail_path"].is_string() {
+            continue;
+        }
+        let (Some(id), Some(file_path)) = (
+            illustration["id"].as_str().map(str::to_string),
+            illustration["local_file_path"].as_str().map(str::to_string),
+        ) else {
+            continue;
+        };
+        if let Some(thumbnail_path) = backfill_thumbnail(&conn, &id, &file_path) {
+            illustration["thumbnail_path"] = Value::String(thumbnail_path);
+        }
+    }
+    
     log::info!("[IllustrationCommand] 獲取插畫歷史成功，共 {} 條記錄", illustrations.len());
     
     Ok(serde_json::json!({
@@ -799,6 +1465,13 @@ pub async fn get_illustration_history(
     }))
 }
 
+/// 讀取插畫檔案內嵌的生成參數（提示詞、seed、模型、應用版本）
+#[tauri::command]
+pub async fn read_illustration_metadata(path: String) -> Result<Option<IllustrationMetadata>, String> {
+    let data = std::fs::read(&path).map_err(|e| format!("無法讀取圖像檔案: {}", e))?;
+    image_metadata::read_metadata(&data).map_err(|e| format!("讀取圖像元資料失敗: {}", e))
+}
+
 /// 取得支援的免費模型列表
 #[tauri::command]
 pub async fn get_free_illustration_models() -> Result<Value, String> {
@@ -827,19 +1500,130 @@ pub async fn get_free_illustration_models() -> Result<Value, String> {
     }))
 }
 
+/// 稽核插畫儲存：列出沒有記錄引用的孤立檔案，以及指向不存在檔案的記錄
+#[tauri::command]
+pub async fn audit_illustration_storage(project_id: Option<String>) -> Result<StorageAuditReport, String> {
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let storage_dir = resolve_illustration_storage_dir(&conn)?;
+    storage_audit::audit_storage(&conn, &storage_dir, project_id.as_deref())
+        .map_err(|e| format!("插畫儲存稽核失敗: {}", e))
+}
+
+/// 清理孤立的插畫檔案；clear_dangling_paths 為 true 時一併清除失效記錄的檔案路徑
+#[tauri::command]
+pub async fn clean_orphaned_illustrations(
+    dry_run: bool,
+    clear_dangling_paths: Option<bool>,
+) -> Result<StorageCleanupResult, String> {
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let storage_dir = resolve_illustration_storage_dir(&conn)?;
+    storage_audit::clean_storage(&conn, &storage_dir, dry_run, clear_dangling_paths.unwrap_or(false))
+        .map_err(|e| format!("清理孤立插畫失敗: {}", e))
+}
+
 // ========================= 輔助函數 =========================
 
-/// 儲存生成的圖像到本地
-fn save_generated_image(image_data: &[u8], image_id: &str) -> Result<String, Box<dyn std::error::Error>> {
+/// 預設插畫儲存目錄（平台資料目錄下的 genesis-chronicle/generated-images）
+fn default_illustration_storage_dir() -> Result<std::path::PathBuf, String> {
+    dirs::data_dir()
+        .map(|dir| dir.join("genesis-chronicle").join("generated-images"))
+        .ok_or_else(|| "無法獲取應用資料目錄".to_string())
+}
+
+/// 依 illustration_storage_path 設定決定插畫儲存目錄，未設定時使用預設位置
+pub(crate) fn resolve_illustration_storage_dir(conn: &rusqlite::Connection) -> Result<std::path::PathBuf, String> {
+    let configured: String = read_typed_setting(conn, ILLUSTRATION_STORAGE_PATH_KEY)?;
+    let configured = configured.trim();
+    if configured.is_empty() {
+        default_illustration_storage_dir()
+    } else {
+        Ok(std::path::PathBuf::from(configured))
+    }
+}
+
+/// 取得目前的插畫儲存目錄（儲存與 EPUB 掃描共用）
+pub(crate) fn illustration_storage_dir() -> Result<std::path::PathBuf, String> {
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    resolve_illustration_storage_dir(&conn)
+}
+
+/// 專案的 AI 插畫檔案（Pollinations 與 Imagen 生成）
+#[derive(Debug, Clone)]
+pub(crate) struct ProjectIllustration {
+    pub file_path: std::path::PathBuf,
+    pub character_name: Option<String>,
+    pub created_at: Option<String>,
+}
+
+/// 查詢專案插畫（PDF、EPUB 與作品包共用）
+///
+/// 涵蓋兩個生成記錄表，排除垃圾桶中的項目（軟刪除只設定 deleted_at），
+/// 依生成時間排序，只保留本地檔案仍存在的圖片。
+pub(crate) fn query_project_illustrations(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<ProjectIllustration>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT file_path, character_name, created_at FROM (
+                 SELECT p.local_file_path AS file_path, c.name AS character_name, p.created_at
+                 FROM pollinations_generations p
+                 LEFT JOIN characters c ON c.id = p.character_id
+                 WHERE p.project_id = ?1
+                   AND p.deleted_at IS NULL
+                   AND COALESCE(p.is_deleted, 0) = 0
+                   AND p.local_file_path IS NOT NULL
+                 UNION ALL
+                 SELECT i.image_url, c.name, i.created_at
+                 FROM illustration_generations i
+                 LEFT JOIN characters c ON c.id = i.character_id
+                 WHERE i.project_id = ?1
+                   AND i.deleted_at IS NULL
+                   AND COALESCE(i.is_deleted, 0) = 0
+                   AND COALESCE(i.status, 'completed') = 'completed'
+                   AND i.image_url IS NOT NULL
+             ) ORDER BY created_at ASC",
+        )
+        .map_err(|e| format!("準備插畫查詢失敗: {}", e))?;
+
+    let rows = stmt
+        .query_map([project_id], |row| {
+            Ok(ProjectIllustration {
+                file_path: std::path::PathBuf::from(row.get::<_, String>(0)?),
+                character_name: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("查詢專案插畫失敗: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("處理插畫資料失敗: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .filter(|illustration| {
+            let is_image = illustration
+                .file_path
+                .extension()
+                .is_some_and(|ext| matches!(ext.to_string_lossy().to_lowercase().as_str(), "jpg" | "jpeg" | "png" | "webp"));
+            is_image && illustration.file_path.is_file()
+        })
+        .collect())
+}
+
+/// 將生成參數寫入圖像資料，格式不支援時保留原始資料
+fn embed_illustration_metadata<'a>(image_data: &'a [u8], metadata: &IllustrationMetadata) -> std::borrow::Cow<'a, [u8]> {
+    match image_metadata::embed_metadata(image_data, metadata) {
+        Ok(data) => std::borrow::Cow::Owned(data),
+        Err(e) => {
+            log::warn!("[IllustrationCommand] 寫入圖像元資料失敗，保留原始圖像: {}", e);
+            std::borrow::Cow::Borrowed(image_data)
+        }
+    }
+}
+
+/// 儲存生成的圖像到本地（附帶生成參數元資料）
+fn save_generated_image(image_data: &[u8], image_id: &str, metadata: &IllustrationMetadata) -> Result<String, Box<dyn std::error::Error>> {
     use std::fs;
     
-    // 確保圖像目錄存在（使用與EPUB/PDF相同的路徑）
-    let images_dir = dirs::home_dir()
-        .ok_or("無法獲取用戶目錄")?
-        .join("Library")
-        .join("Application Support")
-        .join("genesis-chronicle")
-        .join("generated-images");
+    // 確保圖像目錄存在（與 EPUB 掃描使用相同的設定）
+    let images_dir = illustration_storage_dir()?;
     
     fs::create_dir_all(&images_dir)?;
     
@@ -848,12 +1632,39 @@ fn save_generated_image(image_data: &[u8], image_id: &str) -> Result<String, Box
     let file_path = images_dir.join(&filename);
     
     // 寫入圖像數據
-    fs::write(&file_path, image_data)?;
+    fs::write(&file_path, embed_illustration_metadata(image_data, metadata))?;
     
     Ok(file_path.to_string_lossy().to_string())
 }
 
+/// 為已儲存的圖像產生縮圖（失敗時僅記錄警告，不影響圖像儲存）
+fn create_thumbnail(image_path: &str) -> Option<String> {
+    match thumbnail::generate_thumbnail(std::path::Path::new(image_path)) {
+        Ok(path) => Some(path.to_string_lossy().to_string()),
+        Err(e) => {
+            log::warn!("[IllustrationCommand] 縮圖產生失敗: {:?}", e);
+            None
+        }
+    }
+}
+
+/// 為缺少縮圖的舊記錄補產生縮圖並寫回資料庫
+fn backfill_thumbnail(conn: &rusqlite::Connection, id: &str, image_path: &str) -> Option<String> {
+    if !std::path::Path::new(image_path).exists() {
+        return None;
+    }
+    let thumbnail_path = create_thumbnail(image_path)?;
+    if let Err(e) = conn.execute(
+        "UPDATE pollinations_generations SET thumbnail_path = ?1 WHERE id = ?2",
+        rusqlite::params![thumbnail_path, id],
+    ) {
+        log::warn!("[IllustrationCommand] 更新縮圖路徑失敗: {}", e);
+    }
+    Some(thumbnail_path)
+}
+
 /// 保存成功的 Pollinations 生成歷史記錄
+#[allow(clippy::too_many_arguments)]
 fn save_pollinations_history(
     id: &str,
     project_id: Option<&str>,
@@ -868,21 +1679,22 @@ fn save_pollinations_history(
     style_applied: Option<&str>,
     image_url: Option<&str>,
     local_file_path: &str,
+    thumbnail_path: Option<&str>,
     file_size_bytes: i64,
     generation_time_ms: i32,
 ) -> Result<(), String> {
     use rusqlite::params;
     
-    let conn = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     conn.execute(
         "INSERT INTO pollinations_generations (
             id, project_id, character_id, original_prompt, enhanced_prompt,
             model, width, height, seed, enhance, style_applied,
-            image_url, local_file_path, file_size_bytes, generation_time_ms,
+            image_url, local_file_path, thumbnail_path, file_size_bytes, generation_time_ms,
             status, created_at
         ) VALUES (
-            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, 'completed', CURRENT_TIMESTAMP
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, 'completed', CURRENT_TIMESTAMP
         )",
         params![
             id,
@@ -898,13 +1710,14 @@ fn save_pollinations_history(
             style_applied,
             image_url,
             local_file_path,
+            thumbnail_path,
             file_size_bytes,
             generation_time_ms
         ],
     ).map_err(|e| format!("插入生成歷史失敗: {}", e))?;
     
-    // 更新使用統計
-    update_pollinations_stats(model, true, generation_time_ms as u64, file_size_bytes as u64)?;
+    // 更新使用統計（沿用同一連接，避免同一命令佔用多個連接）
+    update_pollinations_stats(&conn, model, true, generation_time_ms as u64, file_size_bytes as u64)?;
     
     Ok(())
 }
@@ -926,7 +1739,7 @@ fn save_pollinations_history_failed(
 ) -> Result<(), String> {
     use rusqlite::params;
     
-    let conn = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     conn.execute(
         "INSERT INTO pollinations_generations (
@@ -953,13 +1766,14 @@ fn save_pollinations_history_failed(
     ).map_err(|e| format!("插入失敗記錄失敗: {}", e))?;
     
     // 更新使用統計（失敗記錄）
-    update_pollinations_stats(model, false, 0, 0)?;
+    update_pollinations_stats(&conn, model, false, 0, 0)?;
     
     Ok(())
 }
 
 /// 更新 Pollinations 使用統計
 fn update_pollinations_stats(
+    conn: &rusqlite::Connection,
     model: &str,
     success: bool,
     generation_time_ms: u64,
@@ -967,8 +1781,6 @@ fn update_pollinations_stats(
 ) -> Result<(), String> {
     use rusqlite::params;
     
-    let conn = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    
     // 檢查是否需要重置每日計數
     conn.execute(
         "UPDATE pollinations_usage_stats 
@@ -1057,7 +1869,8 @@ pub async fn generate_free_illustration_to_temp(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 免費插畫生成到臨時目錄: {}", prompt);
     
-    if prompt.trim().is_empty() {
+    let prompt = sanitize_scene_description(&prompt);
+    if prompt.is_empty() {
         return Err("提示詞不能為空".to_string());
     }
 
@@ -1107,7 +1920,8 @@ pub async fn generate_free_illustration_to_temp(
             log::info!("[IllustrationCommand] 免費插畫生成成功，耗時: {}ms", response.generation_time_ms);
             
             // 儲存圖像到臨時目錄
-            let temp_path = save_temp_generated_image(&response.image_data, &response.id)
+            let metadata = IllustrationMetadata::new(&response.prompt, response.parameters.seed, &response.parameters.model);
+            let temp_path = save_temp_generated_image(&response.image_data, &response.id, &metadata)
                 .map_err(|e| format!("臨時圖像儲存失敗: {}", e))?;
             
             // 計算檔案大小
@@ -1211,6 +2025,7 @@ pub async fn confirm_temp_image_save(
     // 移動臨時圖像到正式目錄
     let final_path = move_temp_to_final_image(temp_path, temp_id)
         .map_err(|e| format!("移動圖像失敗: {}", e))?;
+    let thumbnail_path = create_thumbnail(&final_path);
     
     // 保存生成歷史到數據庫
     if let Err(e) = save_pollinations_history(
@@ -1227,6 +2042,7 @@ pub async fn confirm_temp_image_save(
         style,
         temp_image_data.get("image_url").and_then(|v| v.as_str()),
         &final_path,
+        thumbnail_path.as_deref(),
         file_size,
         generation_time,
     ) {
@@ -1238,6 +2054,7 @@ pub async fn confirm_temp_image_save(
         "success": true,
         "id": temp_id,
         "final_path": final_path,
+        "thumbnail_path": thumbnail_path,
         "message": "圖像已成功保存"
     }))
 }
@@ -1319,8 +2136,8 @@ fn get_temp_images_dir() -> Result<std::path::PathBuf, Box<dyn std::error::Error
     Ok(temp_dir)
 }
 
-/// 儲存生成的圖像到臨時目錄
-fn save_temp_generated_image(image_data: &[u8], image_id: &str) -> Result<String, Box<dyn std::error::Error>> {
+/// 儲存生成的圖像到臨時目錄（附帶生成參數元資料）
+fn save_temp_generated_image(image_data: &[u8], image_id: &str, metadata: &IllustrationMetadata) -> Result<String, Box<dyn std::error::Error>> {
     use std::fs;
     
     let temp_dir = get_temp_images_dir()?;
@@ -1330,7 +2147,7 @@ fn save_temp_generated_image(image_data: &[u8], image_id: &str) -> Result<String
     let file_path = temp_dir.join(&filename);
     
     // 寫入圖像數據
-    fs::write(&file_path, image_data)?;
+    fs::write(&file_path, embed_illustration_metadata(image_data, metadata))?;
     
     Ok(file_path.to_string_lossy().to_string())
 }
@@ -1340,12 +2157,7 @@ fn move_temp_to_final_image(temp_path: &str, image_id: &str) -> Result<String, B
     use std::fs;
     
     // 確保正式圖像目錄存在
-    let images_dir = dirs::home_dir()
-        .ok_or("無法獲取用戶目錄")?
-        .join("Library")
-        .join("Application Support")
-        .join("genesis-chronicle")
-        .join("generated-images");
+    let images_dir = illustration_storage_dir()?;
     
     fs::create_dir_all(&images_dir)?;
     
@@ -1381,7 +2193,7 @@ pub async fn delete_illustrations(
     let mut errors = Vec::new();
     
     // 建立資料庫連接
-    let conn = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     // 準備垃圾桶目錄（僅軟刪除需要）
     let deleted_images_dir = if deleteType == "soft" {
@@ -1447,7 +2259,7 @@ pub async fn restore_illustrations(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 恢復軟刪除插畫: {} 張", imageIds.len());
     
-    let conn = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     let mut restored_count = 0;
     let mut failed_count = 0;
     let mut errors = Vec::new();
@@ -1488,7 +2300,7 @@ pub async fn get_deleted_illustrations(
 ) -> Result<Value, String> {
     log::info!("[IllustrationCommand] 獲取專案 {} 的已刪除插畫", project_id);
     
-    let conn = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     // 查詢軟刪除的圖片
     let mut stmt = conn.prepare(
@@ -1637,6 +2449,12 @@ fn process_single_image_deletion(
                 fs::remove_file(file_path)
                     .map_err(|e| format!("刪除檔案失敗: {}", e))?;
             }
+            let thumbnail_path = thumbnail::thumbnail_path_for(std::path::Path::new(file_path));
+            if thumbnail_path.exists() {
+                if let Err(e) = fs::remove_file(&thumbnail_path) {
+                    log::warn!("[IllustrationCommand] 刪除縮圖失敗: {}", e);
+                }
+            }
         }
         
         // 從資料庫永久刪除記錄
@@ -1845,4 +2663,172 @@ struct DeletedImageInfo {
     original_file_path: Option<String>,
     deleted_file_path: Option<String>,
     table_name: String,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{connection::open_connection, migrations::run_migrations, pool::ConnectionPool};
+    use crate::database::test_support::{migrated_conn, migrated_conn_with_project};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_illustration_write_is_visible_to_shared_connection() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("shared.db");
+        
+        // 與正式環境相同：服務持有的共享連接 + 命令使用的連接池，指向同一檔案
+        let shared = Arc::new(Mutex::new(open_connection(&db_path).unwrap()));
+        run_migrations(&shared.lock().unwrap()).unwrap();
+        let pool_path = db_path.clone();
+        let pool = ConnectionPool::new(2, move || open_connection(&pool_path));
+        
+        {
+            let conn = pool.get().unwrap();
+            update_pollinations_stats(&conn, "flux", true, 1200, 2048).unwrap();
+        }
+        
+        let (total, flux): (i64, i64) = shared
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT total_generations, flux_usage FROM pollinations_usage_stats WHERE id = 'singleton'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((total, flux), (1, 1));
+    }
+
+    #[test]
+    fn test_retryable_illustrations_respect_retry_cap_and_quota() {
+        let conn = migrated_conn_with_project();
+        conn.execute(
+            "INSERT OR REPLACE INTO project_illustration_settings (project_id, api_quota_limit, api_quota_used, quota_reset_date)
+             VALUES ('p1', 5, 3, DATE('now', 'localtime'))",
+            [],
+        )
+        .unwrap();
+        for (id, status, retries) in [("a", "failed", 0), ("b", "failed", 1), ("c", "failed", 3), ("d", "completed", 0), ("e", "failed", 2)] {
+            conn.execute(
+                "INSERT INTO illustration_generations (id, project_id, scene_description, translated_prompt, api_model, status, retry_count)
+                 VALUES (?1, 'p1', '森林', 'forest', 'imagen', ?2, ?3)",
+                rusqlite::params![id, status, retries],
+            )
+            .unwrap();
+        }
+        
+        let quota = quota::refresh_project_quota(&conn, "p1").unwrap().unwrap();
+        assert_eq!(quota.remaining(), 2);
+        let tasks = find_retryable_illustrations(&conn, "p1", 3, quota.remaining()).unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert!(tasks.iter().all(|task| task.id != "c" && task.id != "d"));
+    }
+
+    #[test]
+    fn test_retry_failure_releases_quota_and_recovers_stale_rows() {
+        let conn = migrated_conn_with_project();
+        conn.execute(
+            "INSERT OR REPLACE INTO project_illustration_settings (project_id, api_quota_limit, api_quota_used, quota_reset_date)
+             VALUES ('p1', 5, 0, DATE('now', 'localtime'))",
+            [],
+        )
+        .unwrap();
+        for (id, status, deleted_at, updated_at) in [
+            ("a", "failed", None, "2025-01-01 00:00:00"),
+            ("b", "failed", Some("2025-01-02 00:00:00"), "2025-01-01 00:00:00"),
+            ("c", "processing", None, "2025-01-01 00:00:00"),
+        ] {
+            conn.execute(
+                "INSERT INTO illustration_generations (id, project_id, scene_description, translated_prompt, api_model, status, deleted_at, updated_at)
+                 VALUES (?1, 'p1', '森林', 'forest', 'imagen', ?2, ?3, ?4)",
+                rusqlite::params![id, status, deleted_at, updated_at],
+            )
+            .unwrap();
+        }
+        
+        assert_eq!(recover_stale_retries(&conn, "p1").unwrap(), 1);
+        assert_eq!(count_retryable_illustrations(&conn, "p1", 3).unwrap(), 2);
+        assert_eq!(find_retryable_illustrations(&conn, "p1", 3, 10).unwrap().len(), 2);
+        
+        quota::reserve_quota(&conn, "p1").unwrap();
+        let summary = apply_retry_outcome(&conn, "p1", "a", Err("timeout"));
+        assert_eq!(summary["status"], "failed");
+        let (used, status, retries): (i64, String, i64) = conn
+            .query_row(
+                "SELECT s.api_quota_used, g.status, g.retry_count
+                 FROM project_illustration_settings s, illustration_generations g
+                 WHERE s.project_id = 'p1' AND g.id = 'a'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((used, status.as_str(), retries), (0, "failed", 1));
+    }
+
+    #[test]
+    fn test_failed_generation_row_is_retryable() {
+        let conn = migrated_conn_with_project();
+        let row = ImagenGenerationRow {
+            project_id: "p1".to_string(),
+            character_id: None,
+            scene_description: "雨夜的港口".to_string(),
+            prompt_template: Some("anime".to_string()),
+            negative_prompt: None,
+        };
+        row.insert(&conn, Err("插畫生成失敗: timeout")).unwrap();
+        row.insert(&conn, Err("插畫生成失敗: quota")).unwrap();
+        conn.execute(
+            "UPDATE illustration_generations SET deleted_at = CURRENT_TIMESTAMP WHERE error_message LIKE '%quota'",
+            [],
+        )
+        .unwrap();
+        
+        let tasks = find_retryable_illustrations(&conn, "p1", 3, 10).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].scene_description, "雨夜的港口");
+        assert_eq!(tasks[0].prompt_template.as_deref(), Some("anime"));
+    }
+
+    #[test]
+    fn test_cost_report_groups_by_model_within_range() {
+        let conn = migrated_conn_with_project();
+        for (id, model, status, cost, created_at) in [
+            ("a", "imagen-3", "completed", Some(0.04), "2025-03-01 10:00:00"),
+            ("b", "imagen-3", "completed", Some(0.04), "2025-03-02 10:00:00"),
+            ("c", "imagen-3", "failed", None, "2025-03-02 11:00:00"),
+            ("d", "imagen-4", "completed", Some(0.06), "2025-03-03 09:00:00"),
+            ("e", "imagen-3", "completed", Some(0.04), "2025-04-01 09:00:00"),
+        ] {
+            conn.execute(
+                "INSERT INTO illustration_generations (id, project_id, scene_description, translated_prompt, api_model, status, api_cost, created_at)
+                 VALUES (?1, 'p1', '森林', 'forest', ?2, ?3, ?4, ?5)",
+                rusqlite::params![id, model, status, cost, created_at],
+            )
+            .unwrap();
+        }
+        
+        let report = build_cost_report(&conn, "p1", Some("2025-03-01".into()), Some("2025-03-31".into())).unwrap();
+        assert_eq!((report.total_generations, report.completed, report.failed), (4, 3, 1));
+        assert!((report.total_cost - 0.14).abs() < 1e-9);
+        assert!((report.average_cost_per_image - 0.14 / 3.0).abs() < 1e-9);
+        assert_eq!(report.by_model.len(), 2);
+        assert_eq!(report.by_model[0].model, "imagen-3");
+        assert_eq!(report.by_model[0].failed, 1);
+        
+        let all = build_cost_report(&conn, "p1", None, None).unwrap();
+        assert_eq!(all.total_generations, 5);
+        assert!(parse_report_date(Some("2025/03/01".into()), "起始").is_err());
+    }
+
+    #[test]
+    fn test_illustration_storage_dir_follows_setting() {
+        let conn = migrated_conn();
+        
+        let default_dir = resolve_illustration_storage_dir(&conn).unwrap();
+        assert!(default_dir.ends_with("genesis-chronicle/generated-images"));
+        
+        let dir = tempfile::tempdir().unwrap();
+        crate::commands::settings::write_typed_setting(&conn, ILLUSTRATION_STORAGE_PATH_KEY, &dir.path().to_string_lossy()).unwrap();
+        assert_eq!(resolve_illustration_storage_dir(&conn).unwrap(), dir.path());
+    }
+}
@@ -0,0 +1,589 @@
+use crate::database::get_db_conn;
+use crate::utils::html_import::{
+    docx_document_to_html, html_to_slate_nodes, markup_attr, slate_node_text, split_slate_into_chapters, tokenize_markup,
+    MarkupToken,
+};
+use crate::utils::slate::{paragraph_node, plain_text_to_html};
+use chrono::Utc;
+use rusqlite::params;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::Path;
+use uuid::Uuid;
+
+/// 匯入檔案大小上限（50MB）
+const MAX_IMPORT_FILE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// EPUB 中不屬於章節內容的頁面（封面、插畫集錦、目錄）
+const EPUB_NON_CHAPTER_PAGES: &[&str] = &["cover", "illustrations", "nav", "toc"];
+/// 匯出章節時附加的頁尾文字，匯入時移除
+const EPUB_GENERATED_FOOTER: &str = "由創世紀元生成";
+
+/// 匯入後建立的章節
+#[derive(Debug, Serialize)]
+pub struct ImportedChapter {
+    pub id: String,
+    pub title: String,
+    pub order_index: i32,
+    pub chapter_number: i32,
+}
+
+/// 從 HTML 建立章節：以最高層級的標題分章，內文轉為 Slate.js JSON，接在專案現有章節之後
+#[tauri::command]
+pub async fn import_chapters_from_html(project_id: String, html: String) -> Result<Vec<ImportedChapter>, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+
+    let chapters = insert_imported_chapters(&mut conn, &project_id, &html, "匯入的章節")?;
+    log::info!("從 HTML 匯入章節成功: 專案 ID {} ({} 章)", project_id, chapters.len());
+    Ok(chapters)
+}
+
+/// 從外部檔案建立章節，支援 .html / .htm / .xhtml、.docx 與 .txt
+#[tauri::command]
+pub async fn import_document(project_id: String, path: String) -> Result<Vec<ImportedChapter>, String> {
+    let path = Path::new(&path);
+    let html = read_document_as_html(path)?;
+    let fallback_title = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("匯入的章節")
+        .to_string();
+
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    let chapters = insert_imported_chapters(&mut conn, &project_id, &html, &fallback_title)?;
+    log::info!("匯入文件成功: {} → 專案 ID {} ({} 章)", path.display(), project_id, chapters.len());
+    Ok(chapters)
+}
+
+/// 讀取壓縮檔項目，解壓後大小超過 `limit` 時回傳錯誤（只檢查壓縮檔大小擋不住壓縮炸彈）
+fn read_limited(reader: impl Read, limit: u64, name: &str) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    reader
+        .take(limit + 1)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("讀取 {} 失敗: {}", name, e))?;
+    if data.len() as u64 > limit {
+        return Err(format!("{} 解壓後超過 {} MB 上限", name, limit / 1024 / 1024));
+    }
+    Ok(data)
+}
+
+/// 讀取文件並轉為 HTML
+fn read_document_as_html(path: &Path) -> Result<String, String> {
+    let size = std::fs::metadata(path).map_err(|e| format!("無法讀取檔案: {}", e))?.len();
+    if size > MAX_IMPORT_FILE_SIZE {
+        return Err(format!("檔案過大（{} MB），上限為 50 MB", size / 1024 / 1024));
+    }
+
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" | "xhtml" => std::fs::read_to_string(path).map_err(|e| format!("讀取 HTML 失敗: {}", e)),
+        "txt" => std::fs::read_to_string(path)
+            .map(|text| plain_text_to_html(&text))
+            .map_err(|e| format!("讀取文字檔失敗: {}", e)),
+        "docx" => {
+            let file = std::fs::File::open(path).map_err(|e| format!("無法開啟 DOCX: {}", e))?;
+            let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("DOCX 格式錯誤: {}", e))?;
+            let document = archive
+                .by_name("word/document.xml")
+                .map_err(|_| "DOCX 缺少 word/document.xml".to_string())?;
+            let xml = read_limited(document, MAX_IMPORT_FILE_SIZE, "word/document.xml")?;
+            let xml = String::from_utf8(xml).map_err(|e| format!("讀取 DOCX 內容失敗: {}", e))?;
+            Ok(docx_document_to_html(&xml))
+        }
+        other => Err(format!("不支援的檔案格式: .{}（支援 html、docx、txt）", other)),
+    }
+}
+
+/// 將 HTML 分章後在同一交易中插入，order_index 與 chapter_number 接續現有最大值
+fn insert_imported_chapters(
+    conn: &mut rusqlite::Connection,
+    project_id: &str,
+    html: &str,
+    fallback_title: &str,
+) -> Result<Vec<ImportedChapter>, String> {
+    let chapters = split_slate_into_chapters(html_to_slate_nodes(html), fallback_title);
+    if chapters.is_empty() {
+        return Err("文件中沒有可匯入的內容".to_string());
+    }
+
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let project_exists: bool = tx
+        .query_row("SELECT EXISTS(SELECT 1 FROM projects WHERE id = ?1)", [project_id], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if !project_exists {
+        return Err("專案不存在".to_string());
+    }
+    let (max_order, max_number): (i32, i32) = tx
+        .query_row(
+            "SELECT COALESCE(MAX(order_index), 0), COALESCE(MAX(chapter_number), 0) FROM chapters WHERE project_id = ?1",
+            [project_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+
+    let now = Utc::now();
+    let mut imported = Vec::with_capacity(chapters.len());
+    for (offset, (title, nodes)) in chapters.into_iter().enumerate() {
+        let chapter = ImportedChapter {
+            id: Uuid::new_v4().to_string(),
+            title,
+            order_index: max_order + 1 + offset as i32,
+            chapter_number: max_number + 1 + offset as i32,
+        };
+        tx.execute(
+            "INSERT INTO chapters (id, project_id, title, content, order_index, chapter_number, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                chapter.id,
+                project_id,
+                chapter.title,
+                Value::Array(nodes).to_string(),
+                chapter.order_index,
+                chapter.chapter_number,
+                now,
+                now
+            ],
+        )
+        .map_err(|e| format!("建立章節失敗: {}", e))?;
+        imported.push(chapter);
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(imported)
+}
+
+/// 從 EPUB 重建專案：依 spine 順序建立章節，並將書名與作者寫入新專案，回傳專案 ID
+///
+/// 章節名稱取自 toc.ncx（沒有時使用章節內第一個標題），
+/// `images/` 中的圖片會複製到插畫儲存目錄。
+#[tauri::command]
+pub async fn import_epub(path: String) -> Result<String, String> {
+    let book = read_epub_book(Path::new(&path))?;
+
+    let project_id = {
+        let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+        insert_epub_project(&mut conn, &book)?
+    };
+
+    if !book.images.is_empty() {
+        match crate::commands::illustration::illustration_storage_dir() {
+            Ok(dir) => {
+                let copied = copy_epub_images(&dir, &book.images, &project_id);
+                log::info!("EPUB 圖片已複製到插畫儲存目錄: {} / {} 張", copied, book.images.len());
+            }
+            Err(e) => log::warn!("無法取得插畫儲存目錄，略過 EPUB 圖片: {}", e),
+        }
+    }
+
+    log::info!("匯入 EPUB 成功: {} → 專案 ID {} ({} 章)", book.title, project_id, book.chapters.len());
+    Ok(project_id)
+}
+
+/// 解析後的 EPUB 內容
+#[derive(Debug)]
+struct EpubBook {
+    title: String,
+    author: Option<String>,
+    /// (章節名稱, Slate.js 節點)
+    chapters: Vec<(String, Vec<Value>)>,
+    /// (檔名, 圖片資料)
+    images: Vec<(String, Vec<u8>)>,
+}
+
+/// manifest 中的項目
+struct EpubManifestItem {
+    href: String,
+    media_type: String,
+    properties: String,
+}
+
+fn read_zip_text<R: Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>, name: &str) -> Result<String, String> {
+    let file = archive.by_name(name).map_err(|_| format!("EPUB 缺少檔案: {}", name))?;
+    let data = read_limited(file, MAX_IMPORT_FILE_SIZE, name)?;
+    String::from_utf8(data).map_err(|e| format!("讀取 {} 失敗: {}", name, e))
+}
+
+/// 將相對於 `base_dir` 的 href 轉為 ZIP 內的路徑（處理 ./ 與 ../，移除 #錨點）
+fn resolve_epub_path(base_dir: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or("");
+    let mut segments: Vec<&str> = base_dir.split('/').filter(|segment| !segment.is_empty()).collect();
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            other => segments.push(other),
+        }
+    }
+    segments.join("/")
+}
+
+/// 讀取元素的文字內容（`<dc:title>`、`<text>` 等）
+fn element_texts(tokens: &[MarkupToken], element: &str) -> Vec<String> {
+    let mut texts = Vec::new();
+    let mut current: Option<String> = None;
+    for token in tokens {
+        match token {
+            MarkupToken::Open { name, self_closing: false, .. } if name == element => current = Some(String::new()),
+            MarkupToken::Text(text) => {
+                if let Some(current) = &mut current {
+                    current.push_str(text);
+                }
+            }
+            MarkupToken::Close(name) if name == element => {
+                if let Some(text) = current.take() {
+                    texts.push(text.trim().to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    texts
+}
+
+/// 從 toc.ncx 建立「章節檔案路徑 → 目錄名稱」對照
+fn read_ncx_labels(ncx: &str, ncx_dir: &str) -> HashMap<String, String> {
+    let mut labels = HashMap::new();
+    let mut label: Option<String> = None;
+    let mut in_text = false;
+    for token in tokenize_markup(ncx) {
+        match token {
+            MarkupToken::Open { name, attrs, .. } => match name.as_str() {
+                "navlabel" => label = Some(String::new()),
+                "text" => in_text = label.is_some(),
+                "content" => {
+                    if let (Some(label), Some(src)) = (label.take(), markup_attr(&attrs, "src")) {
+                        labels.entry(resolve_epub_path(ncx_dir, &src)).or_insert_with(|| label.trim().to_string());
+                    }
+                }
+                _ => {}
+            },
+            MarkupToken::Close(name) if name == "text" => in_text = false,
+            MarkupToken::Text(text) if in_text => {
+                if let Some(label) = &mut label {
+                    label.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    labels
+}
+
+fn read_epub_book(path: &Path) -> Result<EpubBook, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("無法開啟 EPUB: {}", e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("EPUB 格式錯誤: {}", e))?;
+
+    // container.xml 指向 OPF 套件檔
+    let container = read_zip_text(&mut archive, "META-INF/container.xml")?;
+    let opf_path = tokenize_markup(&container)
+        .into_iter()
+        .find_map(|token| match token {
+            MarkupToken::Open { name, attrs, .. } if name == "rootfile" => markup_attr(&attrs, "full-path"),
+            _ => None,
+        })
+        .ok_or("container.xml 缺少 rootfile")?;
+    let opf_dir = opf_path.rsplit_once('/').map(|(dir, _)| dir.to_string()).unwrap_or_default();
+    let opf_tokens = tokenize_markup(&read_zip_text(&mut archive, &opf_path)?);
+
+    let title = element_texts(&opf_tokens, "dc:title")
+        .into_iter()
+        .find(|title| !title.is_empty())
+        .unwrap_or_else(|| path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("匯入的書籍").to_string());
+    let author = element_texts(&opf_tokens, "dc:creator").into_iter().find(|author| !author.is_empty());
+
+    let mut manifest: HashMap<String, EpubManifestItem> = HashMap::new();
+    let mut spine = Vec::new();
+    for token in &opf_tokens {
+        let MarkupToken::Open { name, attrs, .. } = token else {
+            continue;
+        };
+        match name.as_str() {
+            "item" => {
+                if let (Some(id), Some(href)) = (markup_attr(attrs, "id"), markup_attr(attrs, "href")) {
+                    manifest.insert(
+                        id,
+                        EpubManifestItem {
+                            href: resolve_epub_path(&opf_dir, &href),
+                            media_type: markup_attr(attrs, "media-type").unwrap_or_default(),
+                            properties: markup_attr(attrs, "properties").unwrap_or_default(),
+                        },
+                    );
+                }
+            }
+            "itemref" if markup_attr(attrs, "linear").as_deref() != Some("no") => {
+                if let Some(idref) = markup_attr(attrs, "idref") {
+                    spine.push(idref);
+                }
+            }
+            _ => {}
+        }
+    }
+    if spine.is_empty() {
+        return Err("EPUB 的 spine 沒有任何章節".to_string());
+    }
+
+    let labels = match manifest.values().find(|item| item.media_type == "application/x-dtbncx+xml") {
+        Some(ncx) => {
+            let ncx_dir = ncx.href.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("").to_string();
+            let ncx_path = ncx.href.clone();
+            read_zip_text(&mut archive, &ncx_path)
+                .map(|ncx| read_ncx_labels(&ncx, &ncx_dir))
+                .unwrap_or_default()
+        }
+        None => HashMap::new(),
+    };
+
+    let mut chapters = Vec::new();
+    for idref in &spine {
+        let Some(item) = manifest.get(idref) else {
+            log::warn!("EPUB spine 參照不存在的項目: {}", idref);
+            continue;
+        };
+        let stem = item.href.rsplit('/').next().unwrap_or("").split('.').next().unwrap_or("");
+        if item.properties.contains("nav") || EPUB_NON_CHAPTER_PAGES.contains(&idref.as_str()) || EPUB_NON_CHAPTER_PAGES.contains(&stem) {
+            continue;
+        }
+
+        let xhtml = read_zip_text(&mut archive, &item.href)?;
+        let mut nodes = html_to_slate_nodes(&xhtml);
+        let title = labels
+            .get(&item.href)
+            .filter(|label| !label.is_empty())
+            .cloned()
+            .or_else(|| {
+                nodes
+                    .iter()
+                    .find(|node| node["type"].as_str().is_some_and(|node_type| node_type.starts_with("heading")))
+                    .map(|node| slate_node_text(node).trim().to_string())
+            })
+            .unwrap_or_else(|| format!("第 {} 章", chapters.len() + 1));
+
+        // 移除匯出時加入的章節標題與頁尾
+        if nodes.first().is_some_and(|node| slate_node_text(node).trim() == title) {
+            nodes.remove(0);
+        }
+        if nodes.last().is_some_and(|node| slate_node_text(node).trim() == EPUB_GENERATED_FOOTER) {
+            nodes.pop();
+        }
+        if nodes.is_empty() {
+            nodes.push(paragraph_node(""));
+        }
+        chapters.push((title, nodes));
+    }
+    if chapters.is_empty() {
+        return Err("EPUB 中沒有可匯入的章節".to_string());
+    }
+
+    let image_prefix = resolve_epub_path(&opf_dir, "images/");
+    let mut images = Vec::new();
+    // 圖片合計解壓大小與單一匯入檔案共用同一上限
+    let mut image_budget = MAX_IMPORT_FILE_SIZE;
+    for index in 0..archive.len() {
+        let file = archive.by_index(index).map_err(|e| e.to_string())?;
+        let name = file.name().to_string();
+        let Some(filename) = name.strip_prefix(&format!("{}/", image_prefix)) else {
+            continue;
+        };
+        let is_image = filename
+            .rsplit_once('.')
+            .is_some_and(|(_, extension)| matches!(extension.to_lowercase().as_str(), "jpg" | "jpeg" | "png" | "webp" | "gif"));
+        if file.is_dir() || filename.contains('/') || !is_image {
+            continue;
+        }
+        let data = read_limited(file, image_budget, &name)?;
+        image_budget -= data.len() as u64;
+        images.push((filename.to_string(), data));
+    }
+
+    Ok(EpubBook { title, author, chapters, images })
+}
+
+/// 建立專案與章節（同一交易），回傳專案 ID
+fn insert_epub_project(conn: &mut rusqlite::Connection, book: &EpubBook) -> Result<String, String> {
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let project_id = Uuid::new_v4().to_string();
+    let now = Utc::now();
+    let description = book.author.as_ref().map(|author| format!("作者：{}", author));
+    tx.execute(
+        "INSERT INTO projects (id, name, description, novel_length, created_at, updated_at)
+         VALUES (?1, ?2, ?3, 'medium', ?4, ?4)",
+        params![project_id, book.title, description, now],
+    )
+    .map_err(|e| format!("建立專案失敗: {}", e))?;
+
+    for (index, (title, nodes)) in book.chapters.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO chapters (id, project_id, title, content, order_index, chapter_number, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?6)",
+            params![
+                Uuid::new_v4().to_string(),
+                project_id,
+                title,
+                Value::Array(nodes.clone()).to_string(),
+                index as i32 + 1,
+                now
+            ],
+        )
+        .map_err(|e| format!("建立章節失敗: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(project_id)
+}
+
+/// 複製 EPUB 圖片到插畫儲存目錄，回傳複製的張數
+///
+/// 同名但內容不同的檔案改用專案 ID 前綴（必要時再加序號）的名稱，不覆寫既有檔案。
+fn copy_epub_images(dir: &Path, images: &[(String, Vec<u8>)], project_id: &str) -> usize {
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        log::warn!("建立插畫儲存目錄失敗: {}", e);
+        return 0;
+    }
+    let prefix = &project_id[..8.min(project_id.len())];
+    let mut copied = 0;
+    'images: for (filename, data) in images {
+        let mut target = dir.join(filename);
+        let mut attempt = 0;
+        while target.exists() {
+            if std::fs::read(&target).is_ok_and(|existing| existing == *data) {
+                continue 'images;
+            }
+            attempt += 1;
+            target = if attempt == 1 {
+                dir.join(format!("{}-{}", prefix, filename))
+            } else {
+                dir.join(format!("{}-{}-{}", prefix, attempt, filename))
+            };
+        }
+        match std::fs::write(&target, data) {
+            Ok(()) => copied += 1,
+            Err(e) => log::warn!("複製 EPUB 圖片失敗 {}: {}", filename, e),
+        }
+    }
+    copied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::{migrated_conn, migrated_conn_with_project};
+
+    #[test]
+    fn test_read_limited_rejects_oversized_entries() {
+        assert_eq!(read_limited(&b"abc"[..], 3, "a.xml").unwrap(), b"abc");
+        // 無限資料流模擬壓縮炸彈：讀到上限即停止並回報錯誤
+        assert!(read_limited(std::io::repeat(0), 1024, "bomb.xml").unwrap_err().contains("bomb.xml"));
+    }
+
+    #[test]
+    fn test_imported_chapters_follow_existing_order() {
+        let mut conn = migrated_conn_with_project();
+        conn.execute(
+            "INSERT INTO chapters (id, project_id, title, order_index, chapter_number) VALUES ('c1', 'p1', '舊章', 3, 2)",
+            [],
+        )
+        .unwrap();
+
+        let html = "<h1>第一章</h1><p>開場<em>白</em></p><h1>第二章</h1><ul><li>條目</li></ul>";
+        let imported = insert_imported_chapters(&mut conn, "p1", html, "草稿").unwrap();
+        let summary: Vec<(&str, i32, i32)> = imported
+            .iter()
+            .map(|chapter| (chapter.title.as_str(), chapter.order_index, chapter.chapter_number))
+            .collect();
+        assert_eq!(summary, vec![("第一章", 4, 3), ("第二章", 5, 4)]);
+
+        let content: String = conn
+            .query_row("SELECT content FROM chapters WHERE id = ?1", [&imported[0].id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&content).unwrap(),
+            serde_json::json!([{ "type": "paragraph", "children": [{ "text": "開場" }, { "text": "白", "italic": true }] }])
+        );
+
+        assert!(insert_imported_chapters(&mut conn, "p1", "<p> </p>", "草稿").is_err());
+        assert!(insert_imported_chapters(&mut conn, "missing", html, "草稿").is_err());
+    }
+
+    #[test]
+    fn test_exported_epub_round_trip() {
+        use crate::commands::epub::{
+            generate_chapter_xhtml, generate_container_xml, generate_content_opf, generate_toc_ncx,
+        };
+        use crate::utils::slate::slate_to_html;
+        use std::io::Write;
+
+        let original = r#"[{"type":"paragraph","children":[{"text":"勇者"},{"text":"出發","bold":true}]},{"type":"bulleted-list","children":[{"type":"list-item","children":[{"text":"劍"}]}]}]"#;
+        let chapters = vec![
+            ("第一章 啟程".to_string(), slate_to_html(original, "第一章 啟程")),
+            ("第二章".to_string(), slate_to_html("[]", "第二章")),
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let epub_path = dir.path().join("book.epub");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&epub_path).unwrap());
+        let options = zip::write::FileOptions::default();
+        let mut add = |name: &str, data: &[u8]| {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(data).unwrap();
+        };
+        add("mimetype", b"application/epub+zip");
+        add("META-INF/container.xml", generate_container_xml().as_bytes());
+        add("OEBPS/content.opf", generate_content_opf("月光之書", "某作者", &chapters).as_bytes());
+        add("OEBPS/toc.ncx", generate_toc_ncx("月光之書", &chapters).as_bytes());
+        add("OEBPS/cover.xhtml", b"<html><body><h1>cover</h1></body></html>");
+        for (index, (title, content)) in chapters.iter().enumerate() {
+            add(&format!("OEBPS/chapter{}.xhtml", index + 1), generate_chapter_xhtml(title, content).as_bytes());
+        }
+        add("OEBPS/images/scene.png", b"png-bytes");
+        zip.finish().unwrap();
+
+        let book = read_epub_book(&epub_path).unwrap();
+        assert_eq!((book.title.as_str(), book.author.as_deref()), ("月光之書", Some("某作者")));
+        assert_eq!(book.chapters.len(), 2);
+        assert_eq!(book.chapters[0].0, "第一章 啟程");
+        assert_eq!(book.chapters[0].1, serde_json::from_str::<Vec<Value>>(original).unwrap());
+        assert_eq!(book.chapters[1].1, vec![paragraph_node("")]);
+        assert_eq!(book.images, vec![("scene.png".to_string(), b"png-bytes".to_vec())]);
+
+        let mut conn = migrated_conn();
+        let project_id = insert_epub_project(&mut conn, &book).unwrap();
+        let titles: Vec<String> = conn
+            .prepare("SELECT title FROM chapters WHERE project_id = ?1 ORDER BY order_index")
+            .unwrap()
+            .query_map([&project_id], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(titles, vec!["第一章 啟程", "第二章"]);
+
+        let store = dir.path().join("images");
+        assert_eq!(copy_epub_images(&store, &book.images, &project_id), 1);
+        assert_eq!(copy_epub_images(&store, &book.images, &project_id), 0);
+
+        // 同名不同內容：依序改名，不覆寫先前複製的檔案
+        let prefix = &project_id[..8];
+        for content in [&b"second"[..], b"third"] {
+            let images = vec![("scene.png".to_string(), content.to_vec())];
+            assert_eq!(copy_epub_images(&store, &images, &project_id), 1);
+        }
+        assert_eq!(std::fs::read(store.join("scene.png")).unwrap(), b"png-bytes");
+        assert_eq!(std::fs::read(store.join(format!("{}-scene.png", prefix))).unwrap(), b"second");
+        assert_eq!(std::fs::read(store.join(format!("{}-2-scene.png", prefix))).unwrap(), b"third");
+    }
+
+    #[test]
+    fn test_resolve_epub_path() {
+        assert_eq!(resolve_epub_path("OEBPS", "chapter1.xhtml#top"), "OEBPS/chapter1.xhtml");
+        assert_eq!(resolve_epub_path("OEBPS/text", "../images/a.png"), "OEBPS/images/a.png");
+        assert_eq!(resolve_epub_path("", "./content.opf"), "content.opf");
+    }
+}
@@ -9,9 +9,12 @@ pub mod settings;
 pub mod database;
 pub mod ai_history;
 pub mod epub;
+pub mod import;
 // 所有舊PDF模組已刪除 - 現在只使用Chrome Headless實現
 pub mod pdf_chrome; // Chrome Headless PDF模組 - 最新解決方案
 pub mod illustration;
 pub mod translation;
 pub mod prompt_templates;
-pub mod batch_illustration;
\ No newline at end of file
+pub mod batch_illustration;
+pub mod analysis;
+pub mod bundle;
\ No newline at end of file
@@ -1,33 +1,160 @@
-use tauri::command;
-use std::process::Command;
+use tauri::{command, AppHandle, Emitter};
+use tokio::process::Command;
 use std::path::PathBuf;
 use std::fs;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use crate::database::get_db;
+use crate::commands::illustration::query_project_illustrations;
+use crate::database::get_db_conn;
+use crate::utils::slate::slate_to_html;
 use html_escape;
 
 // PDF生成選項 (保持與現有V2選項兼容)
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct PdfOptionsChrome {
     pub page_size: Option<String>,
+    pub orientation: Option<String>,
     pub font_size: Option<f64>,
     pub margins: Option<String>,
+    pub margin_top: Option<f64>,
+    pub margin_bottom: Option<f64>,
+    pub margin_left: Option<f64>,
+    pub margin_right: Option<f64>,
     pub include_cover: Option<bool>,
+    pub include_header: Option<bool>,
+    pub include_footer: Option<bool>,
+    /// 頁首模板，可用 {title}、{page}、{pages} 佔位符
+    pub header_template: Option<String>,
+    /// 頁尾模板，可用 {title}、{page}、{pages} 佔位符
+    pub footer_template: Option<String>,
+    pub include_illustrations: Option<bool>,
+    pub illustration_layout: Option<String>, // "gallery", "inline", "chapter_start"
+    /// Chrome 執行逾時秒數
+    pub timeout_secs: Option<u64>,
 }
 
 impl Default for PdfOptionsChrome {
     fn default() -> Self {
         Self {
-            page_size: Some("A4".to_string()),
+            page_size: Some("A5".to_string()),
+            orientation: Some("portrait".to_string()),
             font_size: Some(12.0),
-            margins: Some("20mm".to_string()),
+            margins: None,
+            margin_top: Some(DEFAULT_MARGIN_VERTICAL_MM),
+            margin_bottom: Some(DEFAULT_MARGIN_VERTICAL_MM),
+            margin_left: Some(DEFAULT_MARGIN_HORIZONTAL_MM),
+            margin_right: Some(DEFAULT_MARGIN_HORIZONTAL_MM),
             include_cover: Some(true),
+            include_header: Some(true),
+            include_footer: Some(true),
+            header_template: None,
+            footer_template: None,
+            include_illustrations: Some(true),
+            illustration_layout: Some("chapter_start".to_string()),
+            timeout_secs: Some(DEFAULT_CHROME_TIMEOUT_SECS),
         }
     }
 }
 
+/// 正文與頁首頁尾共用的中文字體
+const BODY_FONT_FAMILY: &str = r#""Noto Sans TC", "Microsoft YaHei", "PingFang TC", "Heiti TC", sans-serif"#;
+const DEFAULT_HEADER_TEMPLATE: &str = "{title}";
+const DEFAULT_FOOTER_TEMPLATE: &str = "{page}";
+
+/// Chrome 生成 PDF 的預設逾時秒數
+const DEFAULT_CHROME_TIMEOUT_SECS: u64 = 120;
+
+/// 小說排版預設邊距 (mm)
+const DEFAULT_MARGIN_VERTICAL_MM: f64 = 18.0;
+const DEFAULT_MARGIN_HORIZONTAL_MM: f64 = 15.0;
+
+/// 解析後的頁面版面 (單位: mm)，對應Chrome列印的紙張尺寸、方向與邊距
+#[derive(Debug, Clone, PartialEq)]
+struct PageLayout {
+    pub width_mm: f64,
+    pub height_mm: f64,
+    pub landscape: bool,
+    pub margin_top: f64,
+    pub margin_bottom: f64,
+    pub margin_left: f64,
+    pub margin_right: f64,
+}
+
+impl PageLayout {
+    /// 從選項解析版面並驗證邊距不超過頁面
+    fn from_options(options: &PdfOptionsChrome) -> Result<Self, String> {
+        let page_size = options.page_size.as_deref().unwrap_or("A5");
+        let (portrait_width, portrait_height) = match page_size.to_uppercase().as_str() {
+            "A4" => (210.0, 297.0),
+            "A5" => (148.0, 210.0),
+            "LETTER" => (215.9, 279.4),
+            _ => return Err(format!("不支援的紙張尺寸: {}（可用: A4、A5、Letter）", page_size)),
+        };
+
+        let landscape = match options.orientation.as_deref().unwrap_or("portrait").to_lowercase().as_str() {
+            "portrait" => false,
+            "landscape" => true,
+            other => return Err(format!("不支援的頁面方向: {}（可用: portrait、landscape）", other)),
+        };
+        let (width_mm, height_mm) = if landscape {
+            (portrait_height, portrait_width)
+        } else {
+            (portrait_width, portrait_height)
+        };
+
+        // 舊版單一邊距字串（如 "20mm"）作為各邊的後備值
+        let legacy_margin = match options.margins.as_deref() {
+            Some(margins) => Some(
+                margins.trim().trim_end_matches("mm").trim().parse::<f64>()
+                    .map_err(|_| format!("無法解析邊距設定: {}", margins))?,
+            ),
+            None => None,
+        };
+
+        let layout = Self {
+            width_mm,
+            height_mm,
+            landscape,
+            margin_top: options.margin_top.or(legacy_margin).unwrap_or(DEFAULT_MARGIN_VERTICAL_MM),
+            margin_bottom: options.margin_bottom.or(legacy_margin).unwrap_or(DEFAULT_MARGIN_VERTICAL_MM),
+            margin_left: options.margin_left.or(legacy_margin).unwrap_or(DEFAULT_MARGIN_HORIZONTAL_MM),
+            margin_right: options.margin_right.or(legacy_margin).unwrap_or(DEFAULT_MARGIN_HORIZONTAL_MM),
+        };
+        layout.validate()?;
+        Ok(layout)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let margins = [self.margin_top, self.margin_bottom, self.margin_left, self.margin_right];
+        if margins.iter().any(|m| !m.is_finite() || *m < 0.0) {
+            return Err("邊距必須為非負數".to_string());
+        }
+        if self.margin_left + self.margin_right >= self.width_mm {
+            return Err(format!(
+                "左右邊距總和 ({}mm) 不可大於或等於頁面寬度 ({}mm)",
+                self.margin_left + self.margin_right, self.width_mm
+            ));
+        }
+        if self.margin_top + self.margin_bottom >= self.height_mm {
+            return Err(format!(
+                "上下邊距總和 ({}mm) 不可大於或等於頁面高度 ({}mm)",
+                self.margin_top + self.margin_bottom, self.height_mm
+            ));
+        }
+        Ok(())
+    }
+
+    /// 產生 CSS `@page` 規則；Chrome 的 --print-to-pdf 會以此決定紙張尺寸與邊距
+    /// （等同 Page.printToPDF 的 paperWidth/paperHeight/margin*/landscape 搭配 preferCSSPageSize）
+    fn css_page_rule(&self) -> String {
+        format!(
+            "@page {{\n            size: {}mm {}mm;\n            margin: {}mm {}mm {}mm {}mm;\n        }}",
+            self.width_mm, self.height_mm,
+            self.margin_top, self.margin_right, self.margin_bottom, self.margin_left
+        )
+    }
+}
+
 #[derive(Serialize)]
 pub struct PdfGenerationResult {
     pub success: bool,
@@ -39,86 +166,161 @@ pub struct PdfGenerationResult {
     pub error_message: Option<String>,
 }
 
-/// 檢測系統Chrome瀏覽器路徑
+/// 將頁首/頁尾模板轉為 CSS `content` 值（{page}、{pages} 對應頁碼計數器）
+fn template_to_css_content(template: &str, title: &str) -> String {
+    fn css_string(text: &str) -> String {
+        let escaped = text
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\A ");
+        format!("\"{}\"", escaped)
+    }
+
+    let template = template.replace("{title}", title);
+    let mut parts = Vec::new();
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        let (counter, token_len) = if rest[start..].starts_with("{pages}") {
+            ("counter(pages)", "{pages}".len())
+        } else if rest[start..].starts_with("{page}") {
+            ("counter(page)", "{page}".len())
+        } else {
+            parts.push(css_string(&rest[..=start]));
+            rest = &rest[start + 1..];
+            continue;
+        };
+        if start > 0 {
+            parts.push(css_string(&rest[..start]));
+        }
+        parts.push(counter.to_string());
+        rest = &rest[start + token_len..];
+    }
+    if !rest.is_empty() {
+        parts.push(css_string(rest));
+    }
+
+    if parts.is_empty() {
+        "\"\"".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+/// 產生頁首（置中標題）與頁尾（置中頁碼）的 `@page` 邊界區塊規則
+///
+/// Chrome 的 --print-to-pdf 無法傳入 headerTemplate，因此以 CSS 分頁邊界區塊呈現，
+/// 效果等同 Page.printToPDF 的 displayHeaderFooter，且字體與正文一致。
+fn header_footer_css(options: &PdfOptionsChrome, title: &str) -> String {
+    let mut boxes = String::new();
+    let box_style = format!("font-family: {}; font-size: 9pt; color: #666;", BODY_FONT_FAMILY);
+
+    if options.include_header.unwrap_or(true) {
+        let template = options.header_template.as_deref().unwrap_or(DEFAULT_HEADER_TEMPLATE);
+        boxes.push_str(&format!(
+            "@top-center {{ content: {}; {} }}\n",
+            template_to_css_content(template, title), box_style
+        ));
+    }
+    if options.include_footer.unwrap_or(true) {
+        let template = options.footer_template.as_deref().unwrap_or(DEFAULT_FOOTER_TEMPLATE);
+        boxes.push_str(&format!(
+            "@bottom-center {{ content: {}; {} }}\n",
+            template_to_css_content(template, title), box_style
+        ));
+    }
+
+    if boxes.is_empty() {
+        return String::new();
+    }
+    // 封面頁不顯示頁首頁尾
+    format!(
+        "@page {{\n{}}}\n        @page :first {{ @top-center {{ content: none; }} @bottom-center {{ content: none; }} }}",
+        boxes
+    )
+}
+
+/// PDF 導出記錄
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PdfExportRecord {
+    pub id: String,
+    pub project_id: String,
+    pub title: String,
+    pub file_path: String,
+    pub file_size: i64,
+    pub page_count: Option<i32>,
+    pub chapter_count: i32,
+    pub format_settings: String, // JSON string
+    pub export_status: String,
+    pub created_at: String,
+    pub downloaded_at: Option<String>,
+}
+
+/// PDF 生成進度事件（pdf-progress）
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfProgressEvent {
+    pub project_id: String,
+    pub stage: String,
+    pub progress: u8,
+    pub message: String,
+}
+
+/// 發送 PDF 生成進度事件（發送失敗不影響生成流程）
+fn emit_pdf_progress(app: &AppHandle, project_id: &str, stage: &str, progress: u8, message: &str) {
+    let event = PdfProgressEvent {
+        project_id: project_id.to_string(),
+        stage: stage.to_string(),
+        progress,
+        message: message.to_string(),
+    };
+    if let Err(e) = app.emit("pdf-progress", event) {
+        println!("⚠️ 發送PDF進度事件失敗: {}", e);
+    }
+}
+
+/// 檢測系統Chrome瀏覽器路徑（可用 CHROME_PATH 環境變數指定）
 fn detect_chrome_path() -> Result<PathBuf, String> {
-    let possible_paths = if cfg!(target_os = "macos") {
+    if let Ok(custom_path) = std::env::var("CHROME_PATH") {
+        let path = PathBuf::from(&custom_path);
+        if path.is_file() {
+            println!("🌐 使用CHROME_PATH指定的Chrome: {}", path.display());
+            return Ok(path);
+        }
+        println!("⚠️ CHROME_PATH 指定的路徑不存在: {}", custom_path);
+    }
+
+    let mut possible_paths: Vec<PathBuf> = if cfg!(target_os = "macos") {
         vec![
-            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome",
-            "/Applications/Chromium.app/Contents/MacOS/Chromium",
+            "/Applications/Google Chrome.app/Contents/MacOS/Google Chrome".into(),
+            "/Applications/Chromium.app/Contents/MacOS/Chromium".into(),
         ]
     } else if cfg!(target_os = "windows") {
         vec![
-            "C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe",
-            "C:\\Program Files (x86)\\Google\\Chrome\\Application\\chrome.exe",
-            "C:\\Users\\{}\\AppData\\Local\\Google\\Chrome\\Application\\chrome.exe",
+            "C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe".into(),
+            "C:\\Program Files (x86)\\Google\\Chrome\\Application\\chrome.exe".into(),
         ]
     } else {
         vec![
-            "/usr/bin/google-chrome",
-            "/usr/bin/chromium",
-            "/usr/bin/chromium-browser",
-            "/snap/bin/chromium",
+            "/usr/bin/google-chrome".into(),
+            "/usr/bin/google-chrome-stable".into(),
+            "/usr/bin/chromium".into(),
+            "/usr/bin/chromium-browser".into(),
+            "/snap/bin/chromium".into(),
         ]
     };
+    if cfg!(target_os = "windows") {
+        if let Some(local_app_data) = dirs::data_local_dir() {
+            possible_paths.push(local_app_data.join("Google").join("Chrome").join("Application").join("chrome.exe"));
+        }
+    }
 
-    for path_str in possible_paths {
-        let path = PathBuf::from(path_str);
+    for path in possible_paths {
         if path.exists() {
             println!("🌐 找到Chrome路徑: {}", path.display());
             return Ok(path);
         }
     }
 
-    Err("未找到Chrome或Chromium瀏覽器，請安裝Google Chrome以使用PDF功能".to_string())
-}
-
-/// 將Slate.js JSON內容提取為純文字
-#[allow(dead_code)]
-fn extract_text_from_slate(slate_content: &Value) -> Result<String, String> {
-    fn extract_text_recursive(node: &Value) -> String {
-        match node {
-            Value::Object(obj) => {
-                // 提取文字內容
-                if let Some(text) = obj.get("text") {
-                    if let Some(text_str) = text.as_str() {
-                        return text_str.to_string();
-                    }
-                }
-                
-                // 遞歸處理子節點
-                if let Some(children) = obj.get("children") {
-                    if let Some(children_array) = children.as_array() {
-                        return children_array
-                            .iter()
-                            .map(extract_text_recursive)
-                            .collect::<Vec<_>>()
-                            .join("");
-                    }
-                }
-            }
-            Value::Array(arr) => {
-                return arr
-                    .iter()
-                    .map(extract_text_recursive)
-                    .collect::<Vec<_>>()
-                    .join("\n");
-            }
-            _ => {}
-        }
-        String::new()
-    }
-
-    if let Some(content_array) = slate_content.as_array() {
-        let text = content_array
-            .iter()
-            .map(extract_text_recursive)
-            .collect::<Vec<_>>()
-            .join("\n\n");
-        
-        Ok(text)
-    } else {
-        Ok(extract_text_recursive(slate_content))
-    }
+    Err("未找到Chrome或Chromium瀏覽器。請從 https://www.google.com/chrome/ 安裝Google Chrome，或設定 CHROME_PATH 環境變數指向瀏覽器執行檔後再試".to_string())
 }
 
 // AI插畫結構
@@ -130,66 +332,120 @@ struct AIIllustration {
     pub character_names: Vec<String>,
 }
 
-/// 掃描專案AI插畫
-fn scan_project_illustrations(_project_id: &str) -> Result<Vec<AIIllustration>, String> {
-    let illustrations_dir = dirs::data_local_dir()
-        .ok_or("無法獲取本地數據目錄")?
-        .join("genesis-chronicle")
-        .join("generated-images");
-    
-    let mut illustrations = Vec::new();
-    
-    if illustrations_dir.exists() {
-        // 掃描目錄中的圖片檔案
-        let entries = std::fs::read_dir(&illustrations_dir)
-            .map_err(|e| format!("讀取插畫目錄失敗: {}", e))?;
-        
-        for entry in entries {
-            if let Ok(entry) = entry {
-                let path = entry.path();
-                if let Some(extension) = path.extension() {
-                    if ["jpg", "jpeg", "png", "webp"].contains(&extension.to_str().unwrap_or("").to_lowercase().as_str()) {
-                        if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                            illustrations.push(AIIllustration {
-                                file_name: file_name.to_string(),
-                                file_path: path.to_string_lossy().to_string(),
-                                character_names: vec![], // TODO: 從檔案名或metadata解析角色名
-                            });
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
+/// 掃描專案AI插畫（依生成時間排序，只保留本地檔案仍存在的插畫）
+fn scan_project_illustrations(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<AIIllustration>, String> {
+    let illustrations: Vec<AIIllustration> = query_project_illustrations(conn, project_id)?
+        .into_iter()
+        .map(|illustration| AIIllustration {
+            file_name: illustration
+                .file_path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            file_path: illustration.file_path.to_string_lossy().to_string(),
+            character_names: illustration.character_name.into_iter().collect(),
+        })
+        .collect();
+
     println!("🎨 掃描到 {} 個AI插畫檔案", illustrations.len());
     Ok(illustrations)
 }
 
+/// 依副檔名判斷圖片 MIME 類型
+fn image_mime_type(path: &std::path::Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    match extension.as_str() {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// 將插畫讀入並轉為 base64 data URI，讓 PDF 不依賴本地檔案路徑
+fn illustration_data_uri(illustration: &AIIllustration) -> Option<String> {
+    use base64::{Engine as _, engine::general_purpose};
+
+    let path = std::path::Path::new(&illustration.file_path);
+    let mime_type = image_mime_type(path)?;
+    match fs::read(path) {
+        Ok(bytes) => Some(format!("data:{};base64,{}", mime_type, general_purpose::STANDARD.encode(bytes))),
+        Err(e) => {
+            println!("⚠️ 讀取插畫失敗 {}: {}", illustration.file_path, e);
+            None
+        }
+    }
+}
+
+/// 產生插畫的 HTML 區塊
+fn illustration_html(illustration: &AIIllustration) -> String {
+    let Some(data_uri) = illustration_data_uri(illustration) else {
+        return String::new();
+    };
+    let caption = if illustration.character_names.is_empty() {
+        String::new()
+    } else {
+        format!(
+            r#"<p class="illustration-caption">{}</p>"#,
+            html_escape::encode_text(&illustration.character_names.join("、"))
+        )
+    };
+    format!(
+        r#"
+            <div class="chapter-illustration">
+                <img src="{}" alt="{}">
+                {}
+            </div>
+            "#,
+        data_uri,
+        html_escape::encode_double_quoted_attribute(&illustration.file_name),
+        caption
+    )
+}
+
+/// 在章節內容中段的段落之後插入插畫（內嵌模式）
+fn insert_inline_illustration(chapter_html: &str, illustration: &str) -> String {
+    let paragraph_ends: Vec<usize> = chapter_html.match_indices("</p>").map(|(i, _)| i + "</p>".len()).collect();
+    if paragraph_ends.is_empty() {
+        return format!("{}{}", chapter_html, illustration);
+    }
+    let split_at = paragraph_ends[(paragraph_ends.len() - 1) / 2];
+    format!("{}{}{}", &chapter_html[..split_at], illustration, &chapter_html[split_at..])
+}
+
 /// 創建HTML模板
-fn create_html_content(title: &str, chapters: &[Chapter], options: &PdfOptionsChrome, project_id: &str) -> Result<String, String> {
+fn create_html_content(
+    title: &str,
+    chapters: &[Chapter],
+    options: &PdfOptionsChrome,
+    layout: &PageLayout,
+    illustrations: &[AIIllustration],
+) -> Result<String, String> {
     let font_size = options.font_size.unwrap_or(12.0);
-    let margins = options.margins.as_deref().unwrap_or("20mm");
-    
-    // 掃描AI插畫
-    let illustrations = scan_project_illustrations(project_id).unwrap_or_default();
+    let illustration_layout = options.illustration_layout.as_deref().unwrap_or("chapter_start");
+    let illustrations: &[AIIllustration] = if options.include_illustrations.unwrap_or(true) {
+        illustrations
+    } else {
+        &[]
+    };
     
     let mut chapters_html = String::new();
     for (index, chapter) in chapters.iter().enumerate() {
         // 轉換Slate.js內容為HTML
         let content_str = chapter.content.as_deref().unwrap_or("[]");
-        let chapter_html = convert_slate_to_html(content_str)?;
+        let mut chapter_html = slate_to_html(content_str, &chapter.title);
         
-        // 為每章添加一張AI插畫 (如果有的話)
-        let chapter_illustration = if !illustrations.is_empty() && index < illustrations.len() {
-            format!(r#"
-            <div class="chapter-illustration">
-                <img src="file://{}" alt="章節插畫" style="max-width: 80%; height: auto; margin: 20px auto; display: block; border-radius: 8px; box-shadow: 0 4px 8px rgba(0,0,0,0.1);">
-            </div>
-            "#, illustrations[index].file_path)
-        } else {
-            String::new()
-        };
+        // 章節開頭/內嵌模式：依序為每章分配一張插畫
+        let mut chapter_illustration = String::new();
+        if let Some(illustration) = illustrations.get(index) {
+            match illustration_layout {
+                "inline" => {
+                    chapter_html = insert_inline_illustration(&chapter_html, &illustration_html(illustration));
+                }
+                "gallery" => {}
+                _ => chapter_illustration = illustration_html(illustration),
+            }
+        }
         
         chapters_html.push_str(&format!(
             r#"
@@ -207,6 +463,20 @@ fn create_html_content(title: &str, chapters: &[Chapter], options: &PdfOptionsCh
             chapter_html
         ));
     }
+    
+    // 集錦模式：所有插畫集中在書末
+    if illustration_layout == "gallery" && !illustrations.is_empty() {
+        let gallery_items: String = illustrations.iter().map(illustration_html).collect();
+        chapters_html.push_str(&format!(
+            r#"
+            <div class="chapter illustration-gallery" style="page-break-before: always;">
+                <h1 class="chapter-title">插畫集</h1>
+                {}
+            </div>
+            "#,
+            gallery_items
+        ));
+    }
 
     let cover_display = if options.include_cover.unwrap_or(true) { 
         format!(r#"
@@ -227,13 +497,12 @@ fn create_html_content(title: &str, chapters: &[Chapter], options: &PdfOptionsCh
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <title>{}</title>
     <style>
-        @page {{
-            size: A4;
-            margin: {};
-        }}
+        {}
+        
+        {}
         
         body {{
-            font-family: "Noto Sans TC", "Microsoft YaHei", "PingFang TC", "Heiti TC", sans-serif;
+            font-family: {};
             font-size: {}px;
             line-height: 1.6;
             color: #333;
@@ -299,6 +568,13 @@ fn create_html_content(title: &str, chapters: &[Chapter], options: &PdfOptionsCh
             box-shadow: 0 4px 12px rgba(0,0,0,0.15) !important;
         }}
         
+        .chapter-illustration .illustration-caption {{
+            text-indent: 0;
+            font-size: 0.85em;
+            color: #666;
+            margin-top: 0;
+        }}
+        
         @media print {{
             .chapter-illustration {{
                 -webkit-print-color-adjust: exact;
@@ -313,7 +589,9 @@ fn create_html_content(title: &str, chapters: &[Chapter], options: &PdfOptionsCh
 </body>
 </html>"#,
         html_escape::encode_text(title),
-        margins,
+        layout.css_page_rule(),
+        header_footer_css(options, title),
+        BODY_FONT_FAMILY,
         font_size,
         cover_display,
         chapters_html
@@ -351,104 +629,26 @@ struct Project {
     pub updated_at: String,
 }
 
-/// 轉換Slate.js JSON內容為HTML
-fn convert_slate_to_html(slate_json: &str) -> Result<String, String> {
-    println!("🔍 轉換Slate.js內容: {}", slate_json);
-    
-    // 解析Slate.js JSON
-    let slate_value: serde_json::Value = serde_json::from_str(slate_json)
-        .map_err(|e| format!("解析Slate.js內容失敗: {}", e))?;
-    
-    // Slate.js通常是一個數組格式
-    let html = if slate_value.is_array() {
-        let array = slate_value.as_array().unwrap();
-        if array.is_empty() {
-            println!("⚠️ Slate.js內容為空數組");
-            return Ok(String::new());
-        }
-        
-        // 處理每個根節點
-        let html_parts: Result<Vec<_>, _> = array
-            .iter()
-            .map(slate_to_html_recursive)
-            .collect();
-        
-        html_parts?.join("")
-    } else {
-        // 單個節點處理（兼容性）
-        slate_to_html_recursive(&slate_value)?
-    };
-    
-    println!("✅ 生成的HTML長度: {} 字符", html.len());
-    Ok(html)
-}
-
-/// 遞歸轉換Slate.js節點為HTML
-fn slate_to_html_recursive(node: &serde_json::Value) -> Result<String, String> {
-    if let Some(text) = node.get("text") {
-        // 文本節點
-        let mut html = html_escape::encode_text(&text.as_str().unwrap_or("")).to_string();
-        
-        // 處理格式化
-        if node.get("bold").and_then(|v| v.as_bool()).unwrap_or(false) {
-            html = format!("<strong>{}</strong>", html);
-        }
-        if node.get("italic").and_then(|v| v.as_bool()).unwrap_or(false) {
-            html = format!("<em>{}</em>", html);
-        }
-        if node.get("underline").and_then(|v| v.as_bool()).unwrap_or(false) {
-            html = format!("<u>{}</u>", html);
-        }
-        
-        return Ok(html);
-    }
-    
-    // 元素節點
-    let node_type = node.get("type").and_then(|v| v.as_str()).unwrap_or("paragraph");
-    let empty_children = vec![];
-    let children = node.get("children").and_then(|v| v.as_array()).unwrap_or(&empty_children);
-    
-    // 遞歸處理子節點
-    let children_html = children
-        .iter()
-        .map(slate_to_html_recursive)
-        .collect::<Result<Vec<_>, _>>()?
-        .join("");
-    
-    // 根據節點類型生成HTML
-    let html = match node_type {
-        "paragraph" => format!("<p>{}</p>", children_html),
-        "heading-one" => format!("<h1>{}</h1>", children_html),
-        "heading-two" => format!("<h2>{}</h2>", children_html),
-        "heading-three" => format!("<h3>{}</h3>", children_html),
-        "block-quote" => format!("<blockquote>{}</blockquote>", children_html),
-        "bulleted-list" => format!("<ul>{}</ul>", children_html),
-        "numbered-list" => format!("<ol>{}</ol>", children_html),
-        "list-item" => format!("<li>{}</li>", children_html),
-        _ => format!("<div>{}</div>", children_html),
-    };
-    
-    Ok(html)
-}
-
 #[command]
 pub async fn generate_pdf_chrome(
+    app: AppHandle,
     project_id: String,
     options: Option<PdfOptionsChrome>,
 ) -> Result<PdfGenerationResult, String> {
     let start_time = std::time::Instant::now();
     let options = options.unwrap_or_default();
+    let layout = PageLayout::from_options(&options)?;
     
     println!("🚀 開始Chrome Headless PDF生成，專案ID: {}", project_id);
+    println!("📐 頁面版面: {}mm x {}mm{}", layout.width_mm, layout.height_mm, if layout.landscape { " (橫向)" } else { "" });
     
     // 檢測Chrome路徑
     let chrome_path = detect_chrome_path()
         .map_err(|e| format!("Chrome檢測失敗: {}", e))?;
     
     // 從資料庫獲取專案和章節數據
-    let (project, chapters) = {
-        let db = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-        let conn = db.lock().unwrap();
+    let (project, chapters, illustrations) = {
+        let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
         
         // 1. 獲取專案資料
         let project = {
@@ -507,7 +707,13 @@ pub async fn generate_pdf_chrome(
             chapters
         };
         
-        (project, chapters)
+        // 3. 獲取專案的AI插畫
+        let illustrations = scan_project_illustrations(&conn, &project_id).unwrap_or_else(|e| {
+            println!("⚠️ 掃描專案插畫失敗: {}", e);
+            Vec::new()
+        });
+        
+        (project, chapters, illustrations)
     }; // conn在這裡被釋放
     
     if chapters.is_empty() {
@@ -517,7 +723,8 @@ pub async fn generate_pdf_chrome(
     println!("找到 {} 個章節", chapters.len());
     
     // 創建HTML內容
-    let html_content = create_html_content(&project.name, &chapters, &options, &project_id)?;
+    emit_pdf_progress(&app, &project_id, "rendering_html", 10, &format!("正在排版 {} 個章節", chapters.len()));
+    let html_content = create_html_content(&project.name, &chapters, &options, &layout, &illustrations)?;
     
     // 創建臨時HTML文件
     let temp_dir = std::env::temp_dir();
@@ -531,8 +738,10 @@ pub async fn generate_pdf_chrome(
     println!("📄 HTML模板已創建: {}", html_path.display());
     
     // 調用Chrome Headless生成PDF
-    let output = Command::new(&chrome_path)
-        .args(&[
+    emit_pdf_progress(&app, &project_id, "launching_chrome", 40, "正在啟動Chrome生成PDF");
+    let timeout_secs = options.timeout_secs.unwrap_or(DEFAULT_CHROME_TIMEOUT_SECS).max(1);
+    let child = Command::new(&chrome_path)
+        .args([
             "--headless",
             "--disable-gpu",
             "--disable-software-rasterizer",
@@ -548,13 +757,36 @@ pub async fn generate_pdf_chrome(
             &format!("--print-to-pdf={}", pdf_path.display()),
             &format!("file://{}", html_path.display()),
         ])
-        .output()
-        .map_err(|e| format!("Chrome命令執行失敗: {}", e))?;
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            let _ = fs::remove_file(&html_path);
+            format!("無法啟動Chrome ({}): {}，請確認瀏覽器已正確安裝", chrome_path.display(), e)
+        })?;
+    
+    // 逾時後放棄等待，kill_on_drop 會終止Chrome子進程
+    let output = match tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), child.wait_with_output()).await {
+        Ok(result) => result.map_err(|e| format!("Chrome命令執行失敗: {}", e))?,
+        Err(_) => {
+            let _ = fs::remove_file(&html_path);
+            let _ = fs::remove_file(&pdf_path);
+            println!("⏱️ Chrome PDF生成逾時 ({}秒)，已終止Chrome進程", timeout_secs);
+            return Err(format!(
+                "PDF生成逾時（超過 {} 秒），已終止Chrome。書籍較大時請在選項中提高 timeout_secs 後重試",
+                timeout_secs
+            ));
+        }
+    };
     
     println!("🌐 Chrome命令輸出: {}", String::from_utf8_lossy(&output.stdout));
     if !output.stderr.is_empty() {
         println!("⚠️  Chrome錯誤: {}", String::from_utf8_lossy(&output.stderr));
     }
+    if !output.status.success() {
+        println!("⚠️  Chrome結束狀態異常: {}", output.status);
+    }
     
     // 檢查PDF是否生成成功
     if !pdf_path.exists() {
@@ -565,7 +797,10 @@ pub async fn generate_pdf_chrome(
             file_size: None,
             generation_time: Some(start_time.elapsed().as_millis() as u64),
             page_count: None,
-            error_message: Some("PDF檔案生成失敗，請檢查Chrome是否正確安裝".to_string()),
+            error_message: Some(format!(
+                "PDF檔案生成失敗（Chrome結束狀態: {}），請檢查Chrome是否正確安裝或可正常啟動",
+                output.status
+            )),
         });
     }
     
@@ -575,6 +810,7 @@ pub async fn generate_pdf_chrome(
         .unwrap_or(0);
     
     // 移動PDF到下載目錄
+    emit_pdf_progress(&app, &project_id, "writing_file", 80, "正在寫入PDF檔案");
     let downloads_dir = dirs::download_dir()
         .unwrap_or_else(|| std::env::current_dir().unwrap());
     
@@ -594,6 +830,30 @@ pub async fn generate_pdf_chrome(
     
     let generation_time = start_time.elapsed().as_millis() as u64;
     
+    // 記錄導出歷史（失敗不影響已生成的檔案）
+    let record = PdfExportRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        project_id: project_id.clone(),
+        title: project.name.clone(),
+        file_path: final_path.to_string_lossy().to_string(),
+        file_size: file_size as i64,
+        page_count: None,
+        chapter_count: chapters.len() as i32,
+        format_settings: serde_json::to_string(&options).unwrap_or_else(|_| "{}".to_string()),
+        export_status: "completed".to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        downloaded_at: None,
+    };
+    match get_db_conn() {
+        Ok(conn) => {
+            if let Err(e) = save_pdf_export_record(&conn, &record) {
+                println!("⚠️ {}", e);
+            }
+        }
+        Err(e) => println!("⚠️ 資料庫連接失敗，未記錄PDF導出: {}", e),
+    }
+    
+    emit_pdf_progress(&app, &project_id, "completed", 100, "PDF生成完成");
     println!("✅ Chrome Headless PDF生成成功！");
     println!("📁 文件路徑: {}", final_path.display());
     println!("⏱️  生成時間: {}ms", generation_time);
@@ -607,4 +867,213 @@ pub async fn generate_pdf_chrome(
         page_count: Some(1), // TODO: 實現頁數計算
         error_message: None,
     })
-}
\ No newline at end of file
+}
+
+/// 獲取專案的 PDF 導出歷史
+#[command]
+pub async fn get_pdf_exports(
+    #[allow(non_snake_case)]
+    projectId: String,
+) -> Result<Vec<PdfExportRecord>, String> {
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    get_pdf_export_history(&conn, &projectId)
+}
+
+/// 刪除 PDF 導出記錄
+#[command]
+pub async fn delete_pdf_export(
+    #[allow(non_snake_case)]
+    exportId: String,
+) -> Result<(), String> {
+    let conn = get_db_conn().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    delete_pdf_export_record(&conn, &exportId)
+}
+
+/// 保存 PDF 導出記錄到資料庫
+fn save_pdf_export_record(conn: &rusqlite::Connection, record: &PdfExportRecord) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO pdf_exports (
+            id, project_id, title, file_path, file_size, page_count, chapter_count,
+            format_settings, export_status, created_at, downloaded_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        rusqlite::params![
+            record.id,
+            record.project_id,
+            record.title,
+            record.file_path,
+            record.file_size,
+            record.page_count,
+            record.chapter_count,
+            record.format_settings,
+            record.export_status,
+            record.created_at,
+            record.downloaded_at
+        ]
+    )
+    .map_err(|e| format!("保存 PDF 導出記錄失敗: {}", e))?;
+    
+    Ok(())
+}
+
+/// 獲取 PDF 導出歷史
+fn get_pdf_export_history(conn: &rusqlite::Connection, project_id: &str) -> Result<Vec<PdfExportRecord>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, project_id, title, file_path, file_size, page_count, chapter_count,
+                   format_settings, export_status, created_at, downloaded_at
+            FROM pdf_exports 
+            WHERE project_id = ?1 
+            ORDER BY created_at DESC"
+        )
+        .map_err(|e| format!("準備查詢語句失敗: {}", e))?;
+    
+    let export_iter = stmt
+        .query_map([project_id], |row| {
+            Ok(PdfExportRecord {
+                id: row.get(0)?,
+                project_id: row.get(1)?,
+                title: row.get(2)?,
+                file_path: row.get(3)?,
+                file_size: row.get(4)?,
+                page_count: row.get(5)?,
+                chapter_count: row.get(6)?,
+                format_settings: row.get(7)?,
+                export_status: row.get(8)?,
+                created_at: row.get(9)?,
+                downloaded_at: row.get(10)?,
+            })
+        })
+        .map_err(|e| format!("查詢 PDF 導出記錄失敗: {}", e))?;
+    
+    let mut exports = Vec::new();
+    for export in export_iter {
+        exports.push(export.map_err(|e| format!("處理導出記錄失敗: {}", e))?);
+    }
+    
+    Ok(exports)
+}
+
+/// 刪除 PDF 導出記錄（同時嘗試刪除實際檔案）
+fn delete_pdf_export_record(conn: &rusqlite::Connection, export_id: &str) -> Result<(), String> {
+    let file_path: Result<String, _> = conn.query_row(
+        "SELECT file_path FROM pdf_exports WHERE id = ?1",
+        [export_id],
+        |row| row.get(0)
+    );
+    
+    let rows_affected = conn
+        .execute("DELETE FROM pdf_exports WHERE id = ?1", [export_id])
+        .map_err(|e| format!("刪除 PDF 導出記錄失敗: {}", e))?;
+    
+    if rows_affected == 0 {
+        return Err("PDF 導出記錄不存在".to_string());
+    }
+    
+    if let Ok(path) = file_path {
+        if std::path::Path::new(&path).exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                log::warn!("刪除 PDF 文件失敗: {} ({})", path, e);
+            } else {
+                log::info!("已刪除 PDF 文件: {}", path);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    #[test]
+    fn test_default_layout_is_a5_portrait() {
+        let layout = PageLayout::from_options(&PdfOptionsChrome::default()).unwrap();
+        assert_eq!((layout.width_mm, layout.height_mm), (148.0, 210.0));
+        assert!(!layout.landscape);
+    }
+
+    #[test]
+    fn test_landscape_swaps_dimensions_and_legacy_margins() {
+        let options = PdfOptionsChrome {
+            page_size: Some("Letter".to_string()),
+            orientation: Some("landscape".to_string()),
+            margins: Some("20mm".to_string()),
+            margin_top: None,
+            margin_bottom: None,
+            margin_left: None,
+            margin_right: None,
+            ..PdfOptionsChrome::default()
+        };
+        let layout = PageLayout::from_options(&options).unwrap();
+        assert_eq!((layout.width_mm, layout.height_mm), (279.4, 215.9));
+        assert_eq!(layout.margin_left, 20.0);
+        assert!(layout.css_page_rule().contains("margin: 20mm 20mm 20mm 20mm"));
+    }
+
+    #[test]
+    fn test_rejects_margins_larger_than_page() {
+        let options = PdfOptionsChrome {
+            margin_left: Some(80.0),
+            margin_right: Some(80.0),
+            ..PdfOptionsChrome::default()
+        };
+        assert!(PageLayout::from_options(&options).is_err());
+    }
+
+    #[test]
+    fn test_template_to_css_content() {
+        assert_eq!(template_to_css_content("{page}", "書"), "counter(page)");
+        assert_eq!(
+            template_to_css_content("{title} - 第 {page} / {pages} 頁", "「雪」"),
+            r#""「雪」 - 第 " counter(page) " / " counter(pages) " 頁""#
+        );
+        assert_eq!(template_to_css_content("say \"{x}\"", ""), r#""say \"{" "x}\"""#);
+    }
+
+    #[test]
+    fn test_header_footer_can_be_disabled() {
+        let options = PdfOptionsChrome {
+            include_header: Some(false),
+            include_footer: Some(false),
+            ..PdfOptionsChrome::default()
+        };
+        assert!(header_footer_css(&options, "標題").is_empty());
+        assert!(header_footer_css(&PdfOptionsChrome::default(), "標題").contains("@bottom-center"));
+    }
+
+    #[test]
+    fn test_insert_inline_illustration_after_middle_paragraph() {
+        let html = "<p>一</p><p>二</p><p>三</p>";
+        assert_eq!(insert_inline_illustration(html, "<img>"), "<p>一</p><p>二</p><img><p>三</p>");
+        assert_eq!(insert_inline_illustration("", "<img>"), "<img>");
+    }
+
+    #[test]
+    fn test_scan_includes_imagen_and_skips_trashed_illustrations() {
+        let conn = migrated_conn_with_project();
+        let dir = tempfile::tempdir().unwrap();
+        let [free, imagen, trashed] = ["free.jpg", "imagen.png", "trashed.jpg"].map(|name| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"img").unwrap();
+            path.to_string_lossy().to_string()
+        });
+        conn.execute(
+            "INSERT INTO pollinations_generations (id, project_id, original_prompt, local_file_path, deleted_at, created_at)
+             VALUES ('g1', 'p1', 'a', ?1, NULL, '2024-01-01'), ('g2', 'p1', 'b', ?2, CURRENT_TIMESTAMP, '2024-01-02')",
+            rusqlite::params![free, trashed],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO illustration_generations (id, project_id, scene_description, translated_prompt, api_model, image_url, created_at)
+             VALUES ('i1', 'p1', '場景', 'scene', 'imagen-3.0-generate-001', ?1, '2024-01-03')",
+            [&imagen],
+        )
+        .unwrap();
+
+        let illustrations = scan_project_illustrations(&conn, "p1").unwrap();
+        let paths: Vec<_> = illustrations.iter().map(|i| i.file_path.as_str()).collect();
+        assert_eq!(paths, vec![free.as_str(), imagen.as_str()]);
+    }
+}
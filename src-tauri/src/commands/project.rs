@@ -1,20 +1,21 @@
-use crate::database::{get_db, models::*};
+use crate::database::{get_db_conn, models::*};
 use anyhow::Result;
+use crate::utils::slate::{count_text, slate_to_plain_text};
 use chrono::Utc;
 use rusqlite::params;
+use serde::Serialize;
 use uuid::Uuid;
 
 #[tauri::command]
-pub async fn get_all_projects() -> Result<Vec<Project>, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+pub async fn get_all_projects(include_archived: Option<bool>) -> Result<Vec<Project>, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn
-        .prepare("SELECT id, name, description, type, settings, novel_length, created_at, updated_at FROM projects ORDER BY updated_at DESC")
+        .prepare("SELECT id, name, description, type, settings, novel_length, created_at, updated_at, is_archived FROM projects WHERE (?1 OR is_archived = 0) ORDER BY updated_at DESC")
         .map_err(|e| e.to_string())?;
     
     let project_iter = stmt
-        .query_map([], |row| {
+        .query_map([include_archived.unwrap_or(false)], |row| {
             Ok(Project {
                 id: row.get(0)?,
                 name: row.get(1)?,
@@ -24,6 +25,7 @@ pub async fn get_all_projects() -> Result<Vec<Project>, String> {
                 novel_length: row.get(5)?,
                 created_at: row.get(6)?,
                 updated_at: row.get(7)?,
+                is_archived: row.get(8)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -38,11 +40,10 @@ pub async fn get_all_projects() -> Result<Vec<Project>, String> {
 
 #[tauri::command]
 pub async fn get_project_by_id(id: String) -> Result<Project, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn
-        .prepare("SELECT id, name, description, type, settings, novel_length, created_at, updated_at FROM projects WHERE id = ?1")
+        .prepare("SELECT id, name, description, type, settings, novel_length, created_at, updated_at, is_archived FROM projects WHERE id = ?1")
         .map_err(|e| e.to_string())?;
     
     let project = stmt
@@ -56,6 +57,7 @@ pub async fn get_project_by_id(id: String) -> Result<Project, String> {
                 novel_length: row.get(5)?,
                 created_at: row.get(6)?,
                 updated_at: row.get(7)?,
+                is_archived: row.get(8)?,
             })
         })
         .map_err(|e| format!("專案不存在: {}", e))?;
@@ -63,10 +65,15 @@ pub async fn get_project_by_id(id: String) -> Result<Project, String> {
     Ok(project)
 }
 
+/// 驗證請求中的小說篇幅，未提供時使用中篇
+fn validated_novel_length(novel_length: Option<&str>) -> Result<NovelLength, String> {
+    novel_length.map(NovelLength::parse).transpose().map(Option::unwrap_or_default)
+}
+
 #[tauri::command]
 pub async fn create_project(project: CreateProjectRequest) -> Result<String, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let novel_length = validated_novel_length(project.novel_length.as_deref())?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let project_id = Uuid::new_v4().to_string();
     let now = Utc::now();
@@ -80,7 +87,7 @@ pub async fn create_project(project: CreateProjectRequest) -> Result<String, Str
             project.description,
             project.r#type,
             project.settings,
-            project.novel_length.or(Some("medium".to_string())), // 預設為中篇
+            novel_length.as_str(),
             now,
             now
         ],
@@ -93,8 +100,8 @@ pub async fn create_project(project: CreateProjectRequest) -> Result<String, Str
 
 #[tauri::command]
 pub async fn update_project(project: UpdateProjectRequest) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let novel_length = validated_novel_length(project.novel_length.as_deref())?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let now = Utc::now();
     
@@ -106,7 +113,7 @@ pub async fn update_project(project: UpdateProjectRequest) -> Result<(), String>
                 project.description,
                 project.r#type,
                 project.settings,
-                project.novel_length,
+                novel_length.as_str(),
                 now,
                 project.id
             ],
@@ -121,10 +128,67 @@ pub async fn update_project(project: UpdateProjectRequest) -> Result<(), String>
     Ok(())
 }
 
+/// 小說篇幅的建議章節數與字數
+#[derive(Debug, Serialize)]
+pub struct NovelLengthPreset {
+    pub length: NovelLength,
+    pub label: &'static str,
+    pub min_chapters: u32,
+    pub max_chapters: Option<u32>,
+    pub min_words: u32,
+    pub max_words: Option<u32>,
+    pub words_per_chapter: u32,
+}
+
+/// 各篇幅的建議目標（與建立專案視窗的說明一致）
+pub const NOVEL_LENGTH_PRESETS: &[NovelLengthPreset] = &[
+    NovelLengthPreset {
+        length: NovelLength::Short,
+        label: "短篇",
+        min_chapters: 1,
+        max_chapters: Some(5),
+        min_words: 10_000,
+        max_words: Some(50_000),
+        words_per_chapter: 8_000,
+    },
+    NovelLengthPreset {
+        length: NovelLength::Medium,
+        label: "中篇",
+        min_chapters: 10,
+        max_chapters: Some(30),
+        min_words: 50_000,
+        max_words: Some(200_000),
+        words_per_chapter: 6_000,
+    },
+    NovelLengthPreset {
+        length: NovelLength::Long,
+        label: "長篇",
+        min_chapters: 50,
+        max_chapters: Some(150),
+        min_words: 200_000,
+        max_words: Some(1_000_000),
+        words_per_chapter: 5_000,
+    },
+    NovelLengthPreset {
+        length: NovelLength::Epic,
+        label: "史詩",
+        min_chapters: 150,
+        max_chapters: None,
+        min_words: 1_000_000,
+        max_words: None,
+        words_per_chapter: 4_000,
+    },
+];
+
+/// 獲取各小說篇幅的建議章節數與字數
+#[tauri::command]
+pub async fn get_novel_length_presets() -> Result<&'static [NovelLengthPreset], String> {
+    Ok(NOVEL_LENGTH_PRESETS)
+}
+
 #[tauri::command]
 pub async fn delete_project(id: String) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     // 因為有外鍵約束，刪除專案會自動刪除相關的章節和角色
     let rows_affected = conn
@@ -137,4 +201,500 @@ pub async fn delete_project(id: String) -> Result<(), String> {
     
     log::info!("刪除專案成功: ID {}", id);
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 封存專案（隱藏但保留所有資料）
+#[tauri::command]
+pub async fn archive_project(id: String) -> Result<(), String> {
+    set_project_archived(&id, true)
+}
+
+/// 取消封存專案
+#[tauri::command]
+pub async fn unarchive_project(id: String) -> Result<(), String> {
+    set_project_archived(&id, false)
+}
+
+fn set_project_archived(id: &str, archived: bool) -> Result<(), String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let rows_affected = conn
+        .execute(
+            "UPDATE projects SET is_archived = ?1, updated_at = ?2 WHERE id = ?3",
+            params![archived, Utc::now(), id],
+        )
+        .map_err(|e| format!("更新專案封存狀態失敗: {}", e))?;
+    
+    if rows_affected == 0 {
+        return Err("專案不存在".to_string());
+    }
+    
+    log::info!("{}專案成功: ID {}", if archived { "封存" } else { "取消封存" }, id);
+    Ok(())
+}
+
+/// 複製專案（章節、角色、角色關係與角色視覺特徵），回傳新專案 ID
+///
+/// 所有記錄都會產生新的 UUID 並改寫外鍵；生成歷史與導出記錄不會複製。
+#[tauri::command]
+pub async fn duplicate_project(project_id: String, new_name: String) -> Result<String, String> {
+    let new_name = new_name.trim().to_string();
+    if new_name.is_empty() {
+        return Err("新專案名稱不能為空".to_string());
+    }
+    
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let new_project_id = duplicate_project_records(&mut conn, &project_id, &new_name)
+        .map_err(|e| format!("複製專案失敗: {}", e))?;
+    
+    log::info!("複製專案成功: {} -> {} (ID: {})", project_id, new_name, new_project_id);
+    Ok(new_project_id)
+}
+
+/// 在單一交易中深度複製專案相關記錄
+fn duplicate_project_records(conn: &mut rusqlite::Connection, project_id: &str, new_name: &str) -> rusqlite::Result<String> {
+    let tx = conn.transaction()?;
+    let now = Utc::now();
+    let new_project_id = Uuid::new_v4().to_string();
+    
+    let copied = copy_row(&tx, "projects", "id", project_id, &[
+        ("id", &new_project_id),
+        ("name", &new_name),
+        ("created_at", &now),
+        ("updated_at", &now),
+    ])?;
+    if copied == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    
+    // 章節
+    let chapter_ids = query_ids(&tx, "SELECT id FROM chapters WHERE project_id = ?1", project_id)?;
+    for chapter_id in &chapter_ids {
+        let new_chapter_id = Uuid::new_v4().to_string();
+        copy_row(&tx, "chapters", "id", chapter_id, &[
+            ("id", &new_chapter_id),
+            ("project_id", &new_project_id),
+            ("created_at", &now),
+            ("updated_at", &now),
+        ])?;
+    }
+    
+    // 角色（記錄新舊 ID 對照，供關係與視覺特徵改寫外鍵）
+    let mut character_map = std::collections::HashMap::new();
+    for character_id in query_ids(&tx, "SELECT id FROM characters WHERE project_id = ?1", project_id)? {
+        let new_character_id = Uuid::new_v4().to_string();
+        copy_row(&tx, "characters", "id", &character_id, &[
+            ("id", &new_character_id),
+            ("project_id", &new_project_id),
+            ("created_at", &now),
+            ("updated_at", &now),
+        ])?;
+        character_map.insert(character_id, new_character_id);
+    }
+    
+    // 角色關係
+    let relationships: Vec<(String, String, String, Option<String>)> = {
+        let mut stmt = tx.prepare(
+            "SELECT r.id, r.from_character_id, r.to_character_id, r.pair_id
+             FROM character_relationships r
+             JOIN characters c ON c.id = r.from_character_id
+             WHERE c.project_id = ?1",
+        )?;
+        let rows = stmt.query_map([project_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    // 雙向關係的配對 ID 也要重新產生，避免副本與原專案共用配對
+    let mut pair_map = std::collections::HashMap::new();
+    for (relationship_id, from_id, to_id, pair_id) in relationships {
+        let (Some(new_from), Some(new_to)) = (character_map.get(&from_id), character_map.get(&to_id)) else {
+            continue;
+        };
+        let new_pair_id: Option<String> = pair_id.map(|pair_id| {
+            pair_map.entry(pair_id).or_insert_with(|| Uuid::new_v4().to_string()).clone()
+        });
+        let new_relationship_id = Uuid::new_v4().to_string();
+        copy_row(&tx, "character_relationships", "id", &relationship_id, &[
+            ("id", &new_relationship_id),
+            ("from_character_id", new_from),
+            ("to_character_id", new_to),
+            ("pair_id", &new_pair_id),
+            ("created_at", &now),
+            ("updated_at", &now),
+        ])?;
+    }
+    
+    // 角色視覺特徵
+    for (old_character_id, new_character_id) in &character_map {
+        copy_row(&tx, "character_visual_traits", "character_id", old_character_id, &[
+            ("character_id", new_character_id),
+        ])?;
+    }
+    
+    tx.commit()?;
+    Ok(new_project_id)
+}
+
+/// 查詢單欄 ID 列表
+fn query_ids(conn: &rusqlite::Connection, sql: &str, param: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map([param], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// 複製單筆記錄，`overrides` 中的欄位以新值取代，其餘欄位原樣複製
+///
+/// 欄位清單由 PRAGMA table_info 取得，因此之後遷移新增的欄位也會一併複製。
+fn copy_row(
+    conn: &rusqlite::Connection,
+    table: &str,
+    key_column: &str,
+    key: &str,
+    overrides: &[(&str, &dyn rusqlite::ToSql)],
+) -> rusqlite::Result<usize> {
+    let columns: Vec<String> = {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(1))?;
+        rows.collect::<rusqlite::Result<_>>()?
+    };
+    
+    let mut params: Vec<&dyn rusqlite::ToSql> = Vec::new();
+    let select_list: Vec<String> = columns
+        .iter()
+        .map(|column| match overrides.iter().find(|(name, _)| name == column) {
+            Some((_, value)) => {
+                params.push(*value);
+                format!("?{}", params.len())
+            }
+            None => column.clone(),
+        })
+        .collect();
+    params.push(&key);
+    
+    let sql = format!(
+        "INSERT INTO {table} ({columns}) SELECT {select} FROM {table} WHERE {key_column} = ?{key_index}",
+        table = table,
+        columns = columns.join(", "),
+        select = select_list.join(", "),
+        key_column = key_column,
+        key_index = params.len(),
+    );
+    conn.execute(&sql, params.as_slice())
+}
+
+/// 單一章節的字數統計
+#[derive(Debug, Serialize)]
+pub struct ChapterWritingStats {
+    pub chapter_id: String,
+    pub title: String,
+    pub chapter_number: Option<i32>,
+    pub words: usize,
+    pub characters: usize,
+    pub updated_at: String,
+}
+
+/// 依最後修改日期彙總的寫作活動
+#[derive(Debug, Serialize)]
+pub struct DailyWritingActivity {
+    /// 日期（YYYY-MM-DD，UTC）
+    pub date: String,
+    pub chapters_updated: usize,
+    pub words: usize,
+}
+
+/// 專案寫作統計
+#[derive(Debug, Serialize)]
+pub struct ProjectWritingStats {
+    pub project_id: String,
+    pub total_words: usize,
+    pub total_characters: usize,
+    pub chapter_count: usize,
+    pub chapters: Vec<ChapterWritingStats>,
+    /// 以章節 `updated_at` 推估的每日寫作量（章節最後修改當天計入其目前字數）
+    pub daily_activity: Vec<DailyWritingActivity>,
+}
+
+/// 取得專案寫作統計（從 Slate JSON 解析實際可見文字，而非原始 JSON 長度）
+#[tauri::command]
+pub async fn get_project_writing_stats(project_id: String) -> Result<ProjectWritingStats, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    compute_writing_stats(&conn, &project_id).map_err(|e| format!("計算寫作統計失敗: {}", e))
+}
+
+fn compute_writing_stats(conn: &rusqlite::Connection, project_id: &str) -> rusqlite::Result<ProjectWritingStats> {
+    let mut stmt = conn.prepare(
+        "SELECT id, title, chapter_number, content, updated_at
+         FROM chapters WHERE project_id = ?1 ORDER BY order_index",
+    )?;
+    let rows = stmt.query_map([project_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<i32>>(2)?,
+            row.get::<_, Option<String>>(3)?,
+            row.get::<_, String>(4)?,
+        ))
+    })?;
+    
+    let mut chapters = Vec::new();
+    let mut daily: std::collections::BTreeMap<String, DailyWritingActivity> = std::collections::BTreeMap::new();
+    for row in rows {
+        let (chapter_id, title, chapter_number, content, updated_at) = row?;
+        let counts = count_text(&slate_to_plain_text(content.as_deref().unwrap_or("")));
+        
+        let date: String = updated_at.chars().take(10).collect();
+        let activity = daily.entry(date.clone()).or_insert_with(|| DailyWritingActivity {
+            date,
+            chapters_updated: 0,
+            words: 0,
+        });
+        activity.chapters_updated += 1;
+        activity.words += counts.words;
+        
+        chapters.push(ChapterWritingStats {
+            chapter_id,
+            title,
+            chapter_number,
+            words: counts.words,
+            characters: counts.characters,
+            updated_at,
+        });
+    }
+    
+    Ok(ProjectWritingStats {
+        project_id: project_id.to_string(),
+        total_words: chapters.iter().map(|c| c.words).sum(),
+        total_characters: chapters.iter().map(|c| c.characters).sum(),
+        chapter_count: chapters.len(),
+        chapters,
+        daily_activity: daily.into_values().collect(),
+    })
+}
+
+/// 範例資料上限，避免誤輸入產生過大的資料庫
+#[cfg(debug_assertions)]
+const SAMPLE_MAX_CHAPTERS: usize = 200;
+#[cfg(debug_assertions)]
+const SAMPLE_MAX_CHARACTERS: usize = 50;
+
+/// 建立範例專案（僅開發版本），包含章節、角色、角色關係與 AI 生成歷史，回傳專案 ID
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub async fn seed_sample_project(chapters: usize, characters: usize) -> Result<String, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let project_id = seed_sample_project_records(&mut conn, chapters, characters)?;
+    log::info!("已建立範例專案: {} ({} 章, {} 角色)", project_id, chapters, characters);
+    Ok(project_id)
+}
+
+#[cfg(debug_assertions)]
+fn seed_sample_project_records(
+    conn: &mut rusqlite::Connection,
+    chapter_count: usize,
+    character_count: usize,
+) -> Result<String, String> {
+    use serde_json::json;
+    
+    const NAMES: &[&str] = &["林曉", "艾莉絲", "雷恩", "蘇婉", "卡爾", "夜瑠", "白雪", "墨言"];
+    const ARCHETYPES: &[&str] = &["英雄", "導師", "反派", "夥伴", "智者"];
+    const RELATIONSHIPS: &[&str] = &["朋友", "師父", "競爭對手", "家人", "同學"];
+    const HAIR_COLORS: &[&str] = &["黑色", "銀色", "紅色"];
+    const PERSONALITIES: &[&str] = &["冷靜", "熱情", "謹慎", "好奇"];
+    const SCENES: &[&str] = &[
+        "清晨的霧氣籠罩著學院的鐘樓",
+        "魔法陣在地下室中緩緩亮起",
+        "市集裡傳來商人的叫賣聲",
+        "暴風雨敲打著古堡的窗戶",
+    ];
+    
+    if chapter_count > SAMPLE_MAX_CHAPTERS || character_count > SAMPLE_MAX_CHARACTERS {
+        return Err(format!(
+            "範例資料過大：章節上限 {}，角色上限 {}",
+            SAMPLE_MAX_CHAPTERS, SAMPLE_MAX_CHARACTERS
+        ));
+    }
+    
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let now = Utc::now();
+    let project_id = Uuid::new_v4().to_string();
+    tx.execute(
+        "INSERT INTO projects (id, name, description, type, novel_length, created_at, updated_at)
+         VALUES (?1, ?2, ?3, 'isekai', 'medium', ?4, ?4)",
+        params![project_id, format!("範例專案 {}", now.format("%m%d-%H%M%S")), "由 seed_sample_project 產生的測試資料", now],
+    )
+    .map_err(|e| format!("建立範例專案失敗: {}", e))?;
+    
+    let mut names = Vec::with_capacity(character_count);
+    let mut character_ids = Vec::with_capacity(character_count);
+    for i in 0..character_count {
+        let name = match i / NAMES.len() {
+            0 => NAMES[i].to_string(),
+            round => format!("{}{}", NAMES[i % NAMES.len()], round + 1),
+        };
+        let archetype = ARCHETYPES[i % ARCHETYPES.len()];
+        let attributes = json!({
+            "archetype": archetype,
+            "age": 16 + (i * 7) % 40,
+            "gender": if i % 2 == 0 { "女" } else { "男" },
+            "appearance": format!("{}有一頭{}的頭髮", name, HAIR_COLORS[i % HAIR_COLORS.len()]),
+            "personality": PERSONALITIES[i % PERSONALITIES.len()],
+            "background": format!("{}來自邊境的小鎮", name),
+        });
+        let id = Uuid::new_v4().to_string();
+        tx.execute(
+            "INSERT INTO characters (id, project_id, name, description, attributes, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            params![id, project_id, name, format!("{}是故事中的{}", name, archetype), attributes.to_string(), now],
+        )
+        .map_err(|e| format!("建立範例角色失敗: {}", e))?;
+        names.push(name);
+        character_ids.push(id);
+    }
+    
+    for (i, pair) in character_ids.windows(2).enumerate() {
+        tx.execute(
+            "INSERT INTO character_relationships (id, from_character_id, to_character_id, relationship_type, description, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, NULL, ?5, ?5)",
+            params![Uuid::new_v4().to_string(), pair[0], pair[1], RELATIONSHIPS[i % RELATIONSHIPS.len()], now],
+        )
+        .map_err(|e| format!("建立範例角色關係失敗: {}", e))?;
+    }
+    
+    for i in 0..chapter_count {
+        let paragraphs: Vec<serde_json::Value> = (0..3)
+            .map(|p| {
+                let speaker = names.get((i + p) % names.len().max(1)).map(String::as_str).unwrap_or("旅人");
+                let text = format!("{}。{}停下腳步，思考著第{}章的謎題。", SCENES[(i + p) % SCENES.len()], speaker, i + 1);
+                json!({ "type": "paragraph", "children": [{ "text": text }] })
+            })
+            .collect();
+        let chapter_id = Uuid::new_v4().to_string();
+        tx.execute(
+            "INSERT INTO chapters (id, project_id, title, content, order_index, chapter_number, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6, ?6)",
+            params![chapter_id, project_id, format!("第{}章", i + 1), serde_json::Value::Array(paragraphs).to_string(), (i + 1) as i64, now],
+        )
+        .map_err(|e| format!("建立範例章節失敗: {}", e))?;
+        
+        // 前幾章附上 AI 生成歷史
+        if i < 3 {
+            tx.execute(
+                "INSERT INTO ai_generation_history (id, project_id, chapter_id, model, prompt, generated_text, token_count, generation_time_ms, selected, position, created_at)
+                 VALUES (?1, ?2, ?3, 'sample-model', ?4, ?5, 42, 800, ?6, 0, ?7)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    project_id,
+                    chapter_id,
+                    format!("續寫第{}章", i + 1),
+                    "夜色漸深，遠方傳來了鐘聲。",
+                    i == 0,
+                    now
+                ],
+            )
+            .map_err(|e| format!("建立範例生成歷史失敗: {}", e))?;
+        }
+    }
+    
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(project_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::connection::enable_foreign_keys;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    #[test]
+    fn test_novel_length_validation_and_presets() {
+        assert_eq!(validated_novel_length(None).unwrap(), NovelLength::Medium);
+        assert_eq!(validated_novel_length(Some(" Epic ")).unwrap(), NovelLength::Epic);
+        assert!(validated_novel_length(Some("novella")).is_err());
+        assert!(NovelLength::ALL
+            .iter()
+            .all(|length| NOVEL_LENGTH_PRESETS.iter().filter(|preset| preset.length == *length).count() == 1));
+    }
+
+    #[test]
+    fn test_duplicate_project_remaps_foreign_keys() {
+        let mut conn = migrated_conn_with_project();
+        enable_foreign_keys(&conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title, content, metadata) VALUES ('c1', 'p1', '第一章', '[]', '{}');
+             INSERT INTO characters (id, project_id, name) VALUES ('a', 'p1', '甲');
+             INSERT INTO characters (id, project_id, name) VALUES ('b', 'p1', '乙');
+             INSERT INTO character_relationships (id, from_character_id, to_character_id, relationship_type)
+                 VALUES ('r1', 'a', 'b', 'friend');
+             INSERT INTO character_visual_traits (character_id, seed_value, art_style_params) VALUES ('a', 42, '{}');
+             INSERT INTO ai_generation_history (id, project_id, chapter_id, model, prompt, generated_text)
+                 VALUES ('h1', 'p1', 'c1', 'm', 'p', 't');",
+        ).unwrap();
+
+        let new_id = duplicate_project_records(&mut conn, "p1", "副本").unwrap();
+        let count = |sql: &str| conn.query_row(sql, [&new_id], |row| row.get::<_, i64>(0)).unwrap();
+
+        assert_eq!(count("SELECT COUNT(*) FROM projects WHERE id = ?1 AND name = '副本'"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM chapters WHERE project_id = ?1 AND metadata = '{}'"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM characters WHERE project_id = ?1"), 2);
+        assert_eq!(count(
+            "SELECT COUNT(*) FROM character_relationships r
+             JOIN characters f ON f.id = r.from_character_id
+             JOIN characters t ON t.id = r.to_character_id
+             WHERE f.project_id = ?1 AND t.project_id = ?1"
+        ), 1);
+        assert_eq!(count(
+            "SELECT COUNT(*) FROM character_visual_traits v
+             JOIN characters c ON c.id = v.character_id WHERE c.project_id = ?1 AND v.seed_value = 42"
+        ), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM ai_generation_history WHERE project_id = ?1"), 0);
+    }
+
+    #[test]
+    fn test_writing_stats_count_visible_text() {
+        let conn = migrated_conn_with_project();
+
+        conn.execute_batch(
+            r#"INSERT INTO chapters (id, project_id, title, content, order_index, updated_at)
+                   VALUES ('c1', 'p1', '第一章', '[{"type":"paragraph","children":[{"text":"勇者出發 go"}]}]', 0, '2026-10-01 08:00:00');
+               INSERT INTO chapters (id, project_id, title, content, order_index, updated_at)
+                   VALUES ('c2', 'p1', '第二章', '魔王', 1, '2026-10-01T21:00:00+00:00');
+               INSERT INTO chapters (id, project_id, title, content, order_index, updated_at)
+                   VALUES ('c3', 'p1', '第三章', NULL, 2, '2026-10-03 09:00:00');"#,
+        ).unwrap();
+
+        let stats = compute_writing_stats(&conn, "p1").unwrap();
+        assert_eq!(stats.chapter_count, 3);
+        assert_eq!((stats.chapters[0].words, stats.chapters[0].characters), (5, 6));
+        assert_eq!(stats.total_words, 7);
+        assert_eq!(stats.total_characters, 8);
+        assert_eq!(stats.daily_activity.len(), 2);
+        assert_eq!(stats.daily_activity[0].date, "2026-10-01");
+        assert_eq!((stats.daily_activity[0].chapters_updated, stats.daily_activity[0].words), (2, 7));
+    }
+
+    // 範例專案只在開發版本提供
+    #[cfg(debug_assertions)]
+    #[test]
+    fn test_seed_sample_project_creates_valid_data() {
+        let mut conn = crate::database::test_support::migrated_conn();
+        enable_foreign_keys(&conn).unwrap();
+
+        let project_id = seed_sample_project_records(&mut conn, 5, 10).unwrap();
+        let count = |sql: &str| conn.query_row(sql, [&project_id], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM chapters WHERE project_id = ?1"), 5);
+        assert_eq!(count("SELECT COUNT(*) FROM characters WHERE project_id = ?1"), 10);
+        assert_eq!(count(
+            "SELECT COUNT(*) FROM character_relationships r JOIN characters c ON c.id = r.from_character_id WHERE c.project_id = ?1"
+        ), 9);
+        assert_eq!(count("SELECT COUNT(*) FROM ai_generation_history WHERE project_id = ?1"), 3);
+
+        let content: String = conn
+            .query_row("SELECT content FROM chapters WHERE project_id = ?1 AND chapter_number = 1", [&project_id], |row| row.get(0))
+            .unwrap();
+        assert!(slate_to_plain_text(&content).contains("第1章"));
+        assert!(seed_sample_project_records(&mut conn, SAMPLE_MAX_CHAPTERS + 1, 1).is_err());
+    }
+}
@@ -1,13 +1,29 @@
 use serde_json::Value;
 use std::sync::{Arc, Mutex};
 use crate::services::translation::{
-    PromptTemplateManager, TemplateApplicationRequest, TemplateSearchRequest, TemplateCategory
+    PromptTemplateManager, TemplateApplicationRequest, TemplateSearchRequest, TemplateCategory,
+    TemplateParameterSuggestion, VocabularyDatabase, PromptTemplate, PromptTemplateInput,
+    TemplateValidationReport, TemplateQualityPreview
 };
+use crate::database::{get_db, get_db_conn};
+use rusqlite::OptionalExtension;
 
 // 全局模板管理器
 lazy_static::lazy_static! {
     static ref TEMPLATE_MANAGER: Arc<Mutex<PromptTemplateManager>> = {
-        Arc::new(Mutex::new(PromptTemplateManager::new()))
+        let mut manager = PromptTemplateManager::new();
+        
+        // 首次使用時與資料庫同步，載入使用者模板
+        match get_db_conn() {
+            Ok(conn) => {
+                if let Err(e) = manager.sync_with_database(&conn) {
+                    log::error!("[Commands] 模板資料庫同步失敗: {:?}", e);
+                }
+            }
+            Err(e) => log::warn!("[Commands] 資料庫尚未初始化，僅使用內建模板: {}", e),
+        }
+        
+        Arc::new(Mutex::new(manager))
     };
 }
 
@@ -24,7 +40,7 @@ pub async fn apply_prompt_template(
 ) -> Result<Value, String> {
     log::info!("[Commands] 應用提示詞模板: {}", templateId);
     
-    let manager = TEMPLATE_MANAGER.lock()
+    let mut manager = TEMPLATE_MANAGER.lock()
         .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
     
     // 轉換參數格式
@@ -41,7 +57,7 @@ pub async fn apply_prompt_template(
     }
     
     let request = TemplateApplicationRequest {
-        template_id: templateId,
+        template_id: templateId.clone(),
         parameters: param_map,
         character_description: characterDescription,
         apply_quality_modifiers: applyQualityModifiers.unwrap_or(true),
@@ -51,6 +67,8 @@ pub async fn apply_prompt_template(
     
     match manager.apply_template(request) {
         Ok(result) => {
+            record_template_usage(&mut manager, &templateId);
+            
             let json_result = serde_json::to_value(result)
                 .map_err(|e| format!("結果序列化失敗: {}", e))?;
             
@@ -64,6 +82,196 @@ pub async fn apply_prompt_template(
     }
 }
 
+/// 建立自定義提示詞模板
+#[tauri::command]
+pub async fn create_prompt_template(template: PromptTemplateInput) -> Result<PromptTemplate, String> {
+    log::info!("[Commands] 建立提示詞模板: {}", template.name);
+    
+    let mut manager = TEMPLATE_MANAGER.lock()
+        .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    manager.create_template(&conn, template)
+        .map_err(|e| format!("建立模板失敗: {}", e))
+}
+
+/// 更新自定義提示詞模板
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn update_prompt_template(
+    templateId: String,
+    template: PromptTemplateInput,
+) -> Result<PromptTemplate, String> {
+    log::info!("[Commands] 更新提示詞模板: {}", templateId);
+    
+    let mut manager = TEMPLATE_MANAGER.lock()
+        .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    manager.update_template(&conn, &templateId, template)
+        .map_err(|e| format!("更新模板失敗: {}", e))
+}
+
+/// 刪除自定義提示詞模板
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn delete_prompt_template(templateId: String) -> Result<(), String> {
+    log::info!("[Commands] 刪除提示詞模板: {}", templateId);
+    
+    let mut manager = TEMPLATE_MANAGER.lock()
+        .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    manager.delete_template(&conn, &templateId)
+        .map_err(|e| format!("刪除模板失敗: {}", e))
+}
+
+/// 取得模板的指定語言版本（不存在時回傳 null）
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn get_template_in_language(
+    templateId: String,
+    targetLang: String,
+) -> Result<Option<PromptTemplate>, String> {
+    log::info!("[Commands] 取得模板語言版本: {} ({})", templateId, targetLang);
+    
+    let manager = TEMPLATE_MANAGER.lock()
+        .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
+    
+    manager.get_template_in_language(&templateId, &targetLang)
+        .map_err(|e| format!("取得模板語言版本失敗: {}", e))
+}
+
+/// 以相同參數預覽多個模板的預估品質，回傳依分數排序的結果
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn preview_template_quality(
+    templateIds: Vec<String>,
+    parameters: serde_json::Map<String, Value>,
+) -> Result<Vec<TemplateQualityPreview>, String> {
+    log::info!("[Commands] 預覽 {} 個模板的品質", templateIds.len());
+    
+    let manager = TEMPLATE_MANAGER.lock()
+        .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
+    
+    let param_map: std::collections::HashMap<String, String> = parameters
+        .into_iter()
+        .map(|(key, value)| match value {
+            Value::String(s) => (key, s),
+            other => (key, other.to_string()),
+        })
+        .collect();
+    
+    manager.preview_quality(&templateIds, &param_map)
+        .map_err(|e| format!("模板品質預覽失敗: {}", e))
+}
+
+/// 檢查所有模板的佔位符與參數宣告，回傳有問題的模板
+#[tauri::command]
+pub async fn validate_all_templates() -> Result<Vec<TemplateValidationReport>, String> {
+    log::info!("[Commands] 驗證所有提示詞模板");
+    
+    let manager = TEMPLATE_MANAGER.lock()
+        .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
+    
+    let reports = manager.validate_all_templates();
+    log::info!("[Commands] 模板驗證完成，{} 個模板有問題", reports.len());
+    Ok(reports)
+}
+
+/// 依角色資料建議模板參數
+///
+/// 從角色屬性、描述與視覺特徵的中文描述推測參數值，回傳預填參數與仍需填寫的必需參數。
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn suggest_template_parameters(
+    templateId: String,
+    characterId: String,
+) -> Result<TemplateParameterSuggestion, String> {
+    log::info!("[Commands] 建議模板參數: {} (角色 {})", templateId, characterId);
+    
+    let (attributes, descriptions) = {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        
+        let (description, attributes_json): (Option<String>, Option<String>) = conn
+            .query_row(
+                "SELECT description, attributes FROM characters WHERE id = ?1",
+                [&characterId],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("查詢角色失敗: {}", e))?
+            .ok_or_else(|| format!("角色不存在: {}", characterId))?;
+        
+        let visual_description: Option<String> = conn
+            .query_row(
+                "SELECT chinese_description FROM character_visual_traits WHERE character_id = ?1",
+                [&characterId],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("查詢視覺特徵失敗: {}", e))?
+            .flatten();
+        
+        let attributes: Vec<(String, String)> = attributes_json
+            .and_then(|json| serde_json::from_str::<serde_json::Map<String, Value>>(&json).ok())
+            .map(|map| {
+                map.into_iter()
+                    .filter_map(|(key, value)| attribute_text(&value).map(|text| (key, text)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        
+        let descriptions: Vec<String> = [description, visual_description].into_iter().flatten().collect();
+        (attributes, descriptions)
+    };
+    
+    let vocabulary_db = VocabularyDatabase::new(get_db().map_err(|e| e.to_string())?);
+    let manager = TEMPLATE_MANAGER.lock()
+        .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
+    
+    let suggestion = manager.suggest_parameters(&templateId, &attributes, &descriptions, &vocabulary_db)
+        .map_err(|e| format!("模板參數建議失敗: {}", e))?;
+    
+    log::info!("[Commands] 已建議 {} 個參數，尚缺 {} 個", 
+              suggestion.parameters.len(), suggestion.missing_parameters.len());
+    Ok(suggestion)
+}
+
+/// 將角色屬性值轉為文字（陣列以頓號連接）
+fn attribute_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Array(items) => Some(items.iter().filter_map(attribute_text).collect::<Vec<_>>().join("、")),
+        _ => None,
+    }
+}
+
+/// 記錄模板使用次數（寫入失敗只記錄警告，不影響模板套用結果）
+fn record_template_usage(manager: &mut PromptTemplateManager, template_id: &str) {
+    let result = get_db_conn()
+        .map_err(|e| e.to_string())
+        .and_then(|conn| manager.record_usage(&conn, template_id).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("[Commands] 記錄模板使用次數失敗: {}", e);
+    }
+}
+
+/// 為提示詞模板評分（1-5）
+#[tauri::command]
+#[allow(non_snake_case)]
+pub async fn rate_prompt_template(templateId: String, rating: f64) -> Result<PromptTemplate, String> {
+    log::info!("[Commands] 評分提示詞模板: {} ({})", templateId, rating);
+    
+    let mut manager = TEMPLATE_MANAGER.lock()
+        .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    manager.rate_template(&conn, &templateId, rating)
+        .map_err(|e| format!("模板評分失敗: {}", e))
+}
+
 /// 搜尋提示詞模板
 #[tauri::command]
 #[allow(non_snake_case)]
@@ -199,7 +407,7 @@ pub async fn batch_apply_templates(
 ) -> Result<Value, String> {
     log::info!("[Commands] 批次應用 {} 個模板", templateRequests.len());
     
-    let manager = TEMPLATE_MANAGER.lock()
+    let mut manager = TEMPLATE_MANAGER.lock()
         .map_err(|e| format!("模板管理器鎖定失敗: {}", e))?;
     
     let mut results = Vec::new();
@@ -209,8 +417,10 @@ pub async fn batch_apply_templates(
         let request: TemplateApplicationRequest = serde_json::from_value(request_value.clone())
             .map_err(|e| format!("請求 {} 解析失敗: {}", index, e))?;
         
+        let template_id = request.template_id.clone();
         match manager.apply_template(request) {
             Ok(result) => {
+                record_template_usage(&mut manager, &template_id);
                 results.push(serde_json::json!({
                     "success": true,
                     "result": result
@@ -1,8 +1,10 @@
-use crate::database::{get_db};
+use crate::database::get_db_conn;
 use anyhow::Result;
-use rusqlite::params;
+use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use tauri::command;
+use serde_json::Value;
+use tauri::{command, AppHandle, Emitter};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SettingEntry {
@@ -10,11 +12,192 @@ pub struct SettingEntry {
     pub value: String,
 }
 
+/// 已知設定鍵與預設值（預設值為 JSON，與前端 DEFAULT_SETTINGS 一致）
+#[derive(Debug, Serialize)]
+pub struct SettingDefinition {
+    pub key: &'static str,
+    pub default_json: &'static str,
+    pub description: &'static str,
+}
+
+/// 設定登錄表
+pub const SETTING_REGISTRY: &[SettingDefinition] = &[
+    SettingDefinition {
+        key: "language",
+        default_json: r#""zh-TW""#,
+        description: "介面語言",
+    },
+    SettingDefinition {
+        key: "ai",
+        default_json: r#"{"defaultModel":"","temperature":0.7,"topP":0.9,"maxTokens":600,"contextLength":4000,"enableAutoComplete":false,"autoCompleteDelay":1000,"ollamaBaseUrl":"http://127.0.0.1:11434","ollamaTimeout":120,"ollamaRetryAttempts":3,"ollamaRetryDelay":1000}"#,
+        description: "AI 設定",
+    },
+    SettingDefinition {
+        key: "editor",
+        default_json: r#"{"theme":"cosmic","fontFamily":"\"Noto Sans TC\", sans-serif","fontSize":16,"lineHeight":1.6,"showLineNumbers":false,"wordWrap":true,"spellCheck":false,"enableVimMode":false}"#,
+        description: "編輯器設定",
+    },
+    SettingDefinition {
+        key: "ui",
+        default_json: r#"{"sidebarWidth":280,"showStatusBar":true,"animationsEnabled":true,"soundEnabled":true,"notificationsEnabled":true}"#,
+        description: "介面設定",
+    },
+    SettingDefinition {
+        key: "backup",
+        default_json: r#"{"autoBackup":true,"backupInterval":24,"maxBackupFiles":10,"backupLocation":""}"#,
+        description: "備份設定",
+    },
+    SettingDefinition {
+        key: "privacy",
+        default_json: r#"{"enableTelemetry":false,"enableCrashReporting":true,"enableUsageAnalytics":false}"#,
+        description: "隱私設定",
+    },
+    SettingDefinition {
+        key: "shortcuts",
+        default_json: r#"{"save":"Ctrl+S","newProject":"Ctrl+N","openProject":"Ctrl+O","aiContinue":"Ctrl+Space","toggleSidebar":"Ctrl+B","toggleFullscreen":"F11","find":"Ctrl+F","replace":"Ctrl+H","undo":"Ctrl+Z","redo":"Ctrl+Y"}"#,
+        description: "快捷鍵設定",
+    },
+    SettingDefinition {
+        key: "update",
+        default_json: r#"{"autoCheck":true,"autoDownload":false,"checkInterval":24,"notifyOnUpdate":true,"allowPrerelease":false}"#,
+        description: "更新設定",
+    },
+    SettingDefinition {
+        key: "lastUpdateCheck",
+        default_json: "null",
+        description: "上次檢查更新的時間（毫秒時間戳）",
+    },
+    SettingDefinition {
+        key: ILLUSTRATION_STORAGE_PATH_KEY,
+        default_json: r#""""#,
+        description: "插畫儲存目錄（空字串表示使用預設位置）",
+    },
+];
+
+/// 插畫儲存目錄設定鍵
+pub const ILLUSTRATION_STORAGE_PATH_KEY: &str = "illustration_storage_path";
+
+/// 專案設定鍵：序章編為第 0 章（值為 JSON 布林）
+pub const COUNT_PROLOGUE_AS_ZERO_KEY: &str = "count_prologue_as_zero";
+
+/// 專案設定鍵：自訂續寫系統提示（JSON {"prompt", "mode": "replace" | "prepend"}，或純文字）
+pub const SYSTEM_PROMPT_OVERRIDE_KEY: &str = "system_prompt_override";
+
+/// 查詢已登錄的設定定義
+pub fn find_setting_definition(key: &str) -> Option<&'static SettingDefinition> {
+    SETTING_REGISTRY.iter().find(|definition| definition.key == key)
+}
+
+fn registered_default(key: &str) -> Result<Value, String> {
+    let definition = find_setting_definition(key)
+        .ok_or_else(|| format!("未知的設定鍵: {}", key))?;
+    serde_json::from_str(definition.default_json)
+        .map_err(|e| format!("設定 {} 的預設值格式錯誤: {}", key, e))
+}
+
+/// 解析儲存的設定值：前端會將字串原樣儲存，無法解析為 JSON 時視為字串
+fn parse_stored_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// 讀取已登錄的設定並反序列化，未設定時回傳登錄的預設值
+pub fn read_typed_setting<T: DeserializeOwned>(conn: &Connection, key: &str) -> Result<T, String> {
+    let default = registered_default(key)?;
+    let stored: Option<String> = match conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        [key],
+        |row| row.get(0),
+    ) {
+        Ok(value) => Some(value),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(e.to_string()),
+    };
+    
+    let value = stored.map(|raw| parse_stored_value(&raw)).unwrap_or(default);
+    serde_json::from_value(value).map_err(|e| format!("設定 {} 的型別不符: {}", key, e))
+}
+
+/// 以 JSON 序列化寫入已登錄的設定，值的型別須與預設值一致（預設值為 null 者不限）
+pub fn write_typed_setting<T: Serialize>(conn: &Connection, key: &str, value: &T) -> Result<Value, String> {
+    let default = registered_default(key)?;
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    if !default.is_null() && !value.is_null() && json_kind(&default) != json_kind(&value) {
+        return Err(format!(
+            "設定 {} 的型別不符：預期 {}，收到 {}",
+            key, json_kind(&default), json_kind(&value)
+        ));
+    }
+    validate_setting_value(key, &value)?;
+    
+    conn.execute(
+        "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+        params![key, value.to_string()],
+    )
+    .map_err(|e| e.to_string())?;
+    
+    Ok(value)
+}
+
+/// 設定變更事件（setting-changed）
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingChangedEvent {
+    pub key: String,
+    pub value: Value,
+}
+
+/// 發送設定變更事件，讓其他視窗即時更新（發送失敗不影響寫入結果）
+fn emit_setting_changed(app: &AppHandle, key: &str, value: Value) {
+    let event = SettingChangedEvent {
+        key: key.to_string(),
+        value,
+    };
+    if let Err(e) = app.emit("setting-changed", event) {
+        log::warn!("發送設定變更事件失敗: {}", e);
+    }
+}
+
+/// 針對個別設定的額外檢查（例如目錄必須可寫入）
+fn validate_setting_value(key: &str, value: &Value) -> Result<(), String> {
+    if key == ILLUSTRATION_STORAGE_PATH_KEY {
+        if let Some(path) = value.as_str().map(str::trim).filter(|path| !path.is_empty()) {
+            validate_writable_dir(std::path::Path::new(path))?;
+        }
+    }
+    Ok(())
+}
+
+/// 確認目錄存在（不存在時建立）且可寫入
+fn validate_writable_dir(dir: &std::path::Path) -> Result<(), String> {
+    if !dir.is_absolute() {
+        return Err(format!("儲存目錄必須是絕對路徑: {}", dir.display()));
+    }
+    std::fs::create_dir_all(dir)
+        .map_err(|e| format!("無法建立儲存目錄 {}: {}", dir.display(), e))?;
+    
+    let probe = dir.join(format!(".write-test-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&probe, b"ok")
+        .map_err(|e| format!("儲存目錄無法寫入 {}: {}", dir.display(), e))?;
+    if let Err(e) = std::fs::remove_file(&probe) {
+        log::warn!("刪除寫入測試檔失敗 {}: {}", probe.display(), e);
+    }
+    Ok(())
+}
+
+fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
 /// 獲取單個設定值
 #[command]
 pub async fn get_setting(key: String) -> Result<Option<String>, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn
         .prepare("SELECT value FROM settings WHERE key = ?1")
@@ -29,24 +212,52 @@ pub async fn get_setting(key: String) -> Result<Option<String>, String> {
 
 /// 設定單個設定值
 #[command]
-pub async fn set_setting(key: String, value: String) -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+pub async fn set_setting(app: AppHandle, key: String, value: String) -> Result<(), String> {
+    validate_setting_value(&key, &parse_stored_value(&value))?;
+    {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+            params![key, value],
+        )
+        .map_err(|e| e.to_string())?;
+    }
     
-    conn.execute(
-        "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
-        params![key, value],
-    )
-    .map_err(|e| e.to_string())?;
+    emit_setting_changed(&app, &key, parse_stored_value(&value));
+    Ok(())
+}
+
+/// 獲取已登錄設定的 JSON 值（未設定時回傳預設值）
+#[command]
+pub async fn get_setting_typed(key: String) -> Result<Value, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    read_typed_setting(&conn, &key)
+}
+
+/// 設定已登錄設定的 JSON 值（拒絕未知鍵與型別不符的值）
+#[command]
+pub async fn set_setting_typed(app: AppHandle, key: String, value: Value) -> Result<(), String> {
+    let written = {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
+        write_typed_setting(&conn, &key, &value)?
+    };
     
+    emit_setting_changed(&app, &key, written);
     Ok(())
 }
 
+/// 獲取設定登錄表
+#[command]
+pub async fn get_setting_registry() -> Result<&'static [SettingDefinition], String> {
+    Ok(SETTING_REGISTRY)
+}
+
 /// 獲取所有設定
 #[command]
 pub async fn get_all_settings() -> Result<Vec<SettingEntry>, String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
     let mut stmt = conn
         .prepare("SELECT key, value FROM settings ORDER BY key")
@@ -69,15 +280,265 @@ pub async fn get_all_settings() -> Result<Vec<SettingEntry>, String> {
     Ok(settings)
 }
 
-/// 重置所有設定
+/// 將已登錄的設定寫回預設值（未登錄的鍵，如自動備份設定，保持不變）
+fn reset_registered_settings(conn: &Connection) -> Result<(), String> {
+    for definition in SETTING_REGISTRY {
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+            params![definition.key, definition.default_json],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// 重置所有設定為預設值
 #[command]
 pub async fn reset_settings() -> Result<(), String> {
-    let db = get_db().map_err(|e| e.to_string())?;
-    let conn = db.lock().unwrap();
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
     
-    conn.execute("DELETE FROM settings", [])
-        .map_err(|e| e.to_string())?;
+    reset_registered_settings(&conn)?;
     
-    log::info!("所有設定已重置");
+    log::info!("所有設定已重置為預設值");
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 讀取專案層級設定，未設定時回退到全域設定
+pub(crate) fn read_project_setting(conn: &Connection, project_id: &str, key: &str) -> Result<Option<String>, String> {
+    match conn.query_row(
+        "SELECT value FROM (
+             SELECT value, 0 AS scope FROM project_settings WHERE project_id = ?1 AND key = ?2
+             UNION ALL
+             SELECT value, 1 AS scope FROM settings WHERE key = ?2
+         ) ORDER BY scope LIMIT 1",
+        params![project_id, key],
+        |row| row.get::<_, String>(0),
+    ) {
+        Ok(value) => Ok(Some(value)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 獲取專案層級設定值（專案未設定時使用全域設定）
+#[command]
+pub async fn get_project_setting(project_id: String, key: String) -> Result<Option<String>, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    read_project_setting(&conn, &project_id, &key)
+}
+
+/// 設定專案層級設定值
+#[command]
+pub async fn set_project_setting(project_id: String, key: String, value: String) -> Result<(), String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    conn.execute(
+        "INSERT OR REPLACE INTO project_settings (project_id, key, value, updated_at) VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)",
+        params![project_id, key, value],
+    )
+    .map_err(|e| e.to_string())?;
+    
+    Ok(())
+}
+
+/// 設定匯入結果
+#[derive(Debug, Serialize)]
+pub struct SettingsImportReport {
+    pub imported: usize,
+    pub skipped: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// 判斷設定鍵是否可能存放 API 金鑰等機密資料
+fn is_secret_setting_key(key: &str) -> bool {
+    const SECRET_MARKERS: &[&str] = &["apikey", "api_key", "secret", "token", "password"];
+    let key = key.to_lowercase();
+    SECRET_MARKERS.iter().any(|marker| key.contains(marker))
+}
+
+/// 將設定表序列化為 JSON 物件（機密設定預設排除）
+fn export_settings_json(conn: &Connection, include_secrets: bool) -> Result<String, String> {
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM settings ORDER BY key")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| e.to_string())?;
+    
+    let mut settings = serde_json::Map::new();
+    for row in rows {
+        let (key, raw) = row.map_err(|e| e.to_string())?;
+        if !include_secrets && is_secret_setting_key(&key) {
+            continue;
+        }
+        settings.insert(key, parse_stored_value(&raw));
+    }
+    
+    serde_json::to_string_pretty(&Value::Object(settings)).map_err(|e| e.to_string())
+}
+
+/// 匯入設定 JSON：僅接受已登錄的鍵；merge 為 false 時先將已登錄的設定清除
+fn import_settings_json(conn: &mut Connection, json: &str, merge: bool) -> Result<SettingsImportReport, String> {
+    let parsed: Value = serde_json::from_str(json).map_err(|e| format!("設定 JSON 格式錯誤: {}", e))?;
+    let Value::Object(entries) = parsed else {
+        return Err("設定 JSON 必須是物件".to_string());
+    };
+    
+    let mut report = SettingsImportReport {
+        imported: 0,
+        skipped: Vec::new(),
+        errors: Vec::new(),
+    };
+    
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    if !merge {
+        for definition in SETTING_REGISTRY {
+            tx.execute("DELETE FROM settings WHERE key = ?1", [definition.key])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    
+    for (key, value) in entries {
+        if find_setting_definition(&key).is_none() {
+            report.skipped.push(key);
+            continue;
+        }
+        match write_typed_setting(&tx, &key, &value) {
+            Ok(_) => report.imported += 1,
+            Err(e) => report.errors.push(e),
+        }
+    }
+    
+    if !report.errors.is_empty() {
+        // 有任何設定驗證失敗時整批放棄，避免留下半套設定
+        return Ok(report);
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    
+    Ok(report)
+}
+
+/// 匯出所有設定為 JSON（include_secrets 為 true 時才包含 API 金鑰等機密設定）
+#[command]
+pub async fn export_settings(include_secrets: Option<bool>) -> Result<String, String> {
+    let conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    export_settings_json(&conn, include_secrets.unwrap_or(false))
+}
+
+/// 從 JSON 匯入設定（merge 為 true 時合併，否則取代已登錄的設定）
+#[command]
+pub async fn import_settings(json: String, merge: bool) -> Result<SettingsImportReport, String> {
+    let mut conn = get_db_conn().map_err(|e| e.to_string())?;
+    
+    let report = import_settings_json(&mut conn, &json, merge)?;
+    if report.errors.is_empty() {
+        log::info!("已匯入 {} 項設定，略過 {} 項未知設定", report.imported, report.skipped.len());
+    } else {
+        log::warn!("設定匯入失敗，已還原: {:?}", report.errors);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE settings (key TEXT PRIMARY KEY, value TEXT NOT NULL, updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP)",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_registry_defaults_are_valid_json() {
+        for definition in SETTING_REGISTRY {
+            assert!(registered_default(definition.key).is_ok(), "{}", definition.key);
+        }
+    }
+
+    #[test]
+    fn test_typed_settings_validate_keys_and_types() {
+        let conn = settings_conn();
+
+        // 未設定時回傳預設值，前端原樣儲存的字串也能讀取
+        assert_eq!(read_typed_setting::<String>(&conn, "language").unwrap(), "zh-TW");
+        conn.execute("INSERT INTO settings (key, value) VALUES ('language', 'en')", []).unwrap();
+        assert_eq!(read_typed_setting::<String>(&conn, "language").unwrap(), "en");
+
+        write_typed_setting(&conn, "lastUpdateCheck", &1_700_000_000_000_i64).unwrap();
+        assert_eq!(read_typed_setting::<i64>(&conn, "lastUpdateCheck").unwrap(), 1_700_000_000_000);
+
+        assert!(write_typed_setting(&conn, "langauge", &"en").is_err());
+        assert!(write_typed_setting(&conn, "ui", &true).is_err());
+
+        conn.execute("INSERT INTO settings (key, value) VALUES ('auto_backup_config', '{}')", []).unwrap();
+        reset_registered_settings(&conn).unwrap();
+        assert_eq!(read_typed_setting::<String>(&conn, "language").unwrap(), "zh-TW");
+        let kept: i64 = conn
+            .query_row("SELECT COUNT(*) FROM settings WHERE key = 'auto_backup_config'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kept, 1);
+    }
+    #[test]
+    fn test_settings_export_and_import_round_trip() {
+        let mut conn = settings_conn();
+        write_typed_setting(&conn, "language", &"en").unwrap();
+        conn.execute("INSERT INTO settings (key, value) VALUES ('openai_api_key', 'sk-test')", []).unwrap();
+        
+        let exported = export_settings_json(&conn, false).unwrap();
+        assert!(!exported.contains("sk-test"));
+        assert!(export_settings_json(&conn, true).unwrap().contains("sk-test"));
+        
+        // 取代模式會清除匯出檔中沒有的已登錄設定
+        write_typed_setting(&conn, "lastUpdateCheck", &1_i64).unwrap();
+        let report = import_settings_json(&mut conn, &exported, false).unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(read_typed_setting::<String>(&conn, "language").unwrap(), "en");
+        assert_eq!(read_typed_setting::<Option<i64>>(&conn, "lastUpdateCheck").unwrap(), None);
+        
+        let report = import_settings_json(&mut conn, r#"{"language":"ja","ui":true,"unknown":1}"#, true).unwrap();
+        assert_eq!(report.skipped, vec!["unknown".to_string()]);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(read_typed_setting::<String>(&conn, "language").unwrap(), "en");
+    }
+    #[test]
+    fn test_project_setting_falls_back_to_global() {
+        let conn = settings_conn();
+        conn.execute(
+            "CREATE TABLE project_settings (project_id TEXT NOT NULL, key TEXT NOT NULL, value TEXT NOT NULL, updated_at TIMESTAMP, PRIMARY KEY (project_id, key))",
+            [],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO settings (key, value) VALUES ('illustration_style', 'anime')", []).unwrap();
+        conn.execute("INSERT INTO project_settings (project_id, key, value) VALUES ('p1', 'illustration_style', 'watercolor')", []).unwrap();
+        
+        assert_eq!(read_project_setting(&conn, "p1", "illustration_style").unwrap().as_deref(), Some("watercolor"));
+        assert_eq!(read_project_setting(&conn, "p2", "illustration_style").unwrap().as_deref(), Some("anime"));
+        assert_eq!(read_project_setting(&conn, "p1", "missing").unwrap(), None);
+    }
+    #[test]
+    fn test_illustration_storage_path_must_be_writable() {
+        let conn = settings_conn();
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("images");
+        
+        write_typed_setting(&conn, ILLUSTRATION_STORAGE_PATH_KEY, &target.to_string_lossy()).unwrap();
+        assert!(target.is_dir());
+        assert_eq!(std::fs::read_dir(&target).unwrap().count(), 0);
+        
+        // 空字串代表回到預設位置
+        write_typed_setting(&conn, ILLUSTRATION_STORAGE_PATH_KEY, &"").unwrap();
+        assert!(write_typed_setting(&conn, ILLUSTRATION_STORAGE_PATH_KEY, &"relative/images").is_err());
+        
+        let file = dir.path().join("not-a-dir");
+        std::fs::write(&file, b"x").unwrap();
+        assert!(write_typed_setting(&conn, ILLUSTRATION_STORAGE_PATH_KEY, &file.to_string_lossy()).is_err());
+        assert_eq!(read_typed_setting::<String>(&conn, ILLUSTRATION_STORAGE_PATH_KEY).unwrap(), "");
+    }
+}
@@ -1,4 +1,4 @@
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 use serde::{Serialize, Deserialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -9,9 +9,66 @@ pub struct UpdateCheckResult {
     pub current_version: String,
     #[serde(rename = "latestVersion")]
     pub latest_version: Option<String>,
+    #[serde(rename = "releaseNotes")]
+    pub release_notes: Option<String>,
+    #[serde(rename = "downloadSize")]
+    pub download_size: Option<u64>,
+    #[serde(rename = "downloadUrl")]
+    pub download_url: Option<String>,
+    /// 安裝檔的 SHA-256 校驗碼（發佈未提供時為 None）
+    pub checksum: Option<String>,
     pub error: Option<String>,
 }
 
+/// 最新發佈版本資訊（GitHub Releases）
+#[derive(Debug, Clone)]
+struct ReleaseInfo {
+    version: String,
+    notes: Option<String>,
+    asset: Option<ReleaseAsset>,
+}
+
+/// 適用於目前平台的安裝檔
+#[derive(Debug, Clone)]
+struct ReleaseAsset {
+    name: String,
+    url: String,
+    size: u64,
+    sha256: Option<String>,
+    checksum_url: Option<String>,
+}
+
+/// 已下載並通過校驗的更新檔（記錄於下載目錄，供 install_update 使用）
+#[derive(Debug, Serialize, Deserialize)]
+struct DownloadedUpdate {
+    version: String,
+    file_path: String,
+    sha256: String,
+}
+
+/// 安裝中的更新（記錄於資料目錄，用於啟動確認與回滾）
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingUpdate {
+    from_version: String,
+    to_version: String,
+    staged_path: String,
+    backup_path: Option<String>,
+    target_path: Option<String>,
+    #[serde(default)]
+    unconfirmed_launches: u32,
+}
+
+/// 新版本啟動後未經前端確認的次數上限，超過即自動回滾
+const MAX_UNCONFIRMED_LAUNCHES: u32 = 2;
+
+/// 更新下載進度事件（update-download-progress）
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateDownloadProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    pub progress: Option<u8>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct DialogFilter {
     pub name: String,
@@ -210,16 +267,17 @@ pub async fn open_external(app: AppHandle, url: String) -> Result<(), String> {
 pub async fn check_for_updates() -> Result<UpdateCheckResult, String> {
     let current_version = env!("CARGO_PKG_VERSION").to_string();
     
-    // 模擬檢查更新過程
-    tokio::time::sleep(tokio::time::Duration::from_millis(1500)).await;
-    
-    match fetch_latest_version().await {
-        Ok(latest_version) => {
-            let has_update = compare_versions(&current_version, &latest_version);
+    match fetch_latest_release().await {
+        Ok(release) => {
+            let has_update = compare_versions(&current_version, &release.version);
             Ok(UpdateCheckResult {
                 has_update,
                 current_version,
-                latest_version: Some(latest_version),
+                latest_version: Some(release.version),
+                release_notes: release.notes,
+                download_size: release.asset.as_ref().map(|asset| asset.size),
+                checksum: release.asset.as_ref().and_then(|asset| asset.sha256.clone()),
+                download_url: release.asset.map(|asset| asset.url),
                 error: None,
             })
         }
@@ -228,20 +286,313 @@ pub async fn check_for_updates() -> Result<UpdateCheckResult, String> {
                 has_update: false,
                 current_version,
                 latest_version: None,
+                release_notes: None,
+                download_size: None,
+                download_url: None,
+                checksum: None,
                 error: Some(error),
             })
         }
     }
 }
 
+/// 下載最新版本的安裝檔，下載過程以 update-download-progress 事件回報進度，回傳檔案路徑
 #[tauri::command]
-pub async fn download_update() -> Result<String, String> {
-    Err("Tauri 版本的更新下載功能尚未實現".to_string())
+pub async fn download_update(app: AppHandle) -> Result<String, String> {
+    let current_version = env!("CARGO_PKG_VERSION");
+    let release = fetch_latest_release().await?;
+    if !compare_versions(current_version, &release.version) {
+        return Err(format!("目前版本 {} 已是最新版本", current_version));
+    }
+    let asset = release
+        .asset
+        .ok_or_else(|| format!("版本 {} 沒有適用於此平台的安裝檔", release.version))?;
+    
+    let expected_sha256 = match (&asset.sha256, &asset.checksum_url) {
+        (Some(sha256), _) => sha256.clone(),
+        (None, Some(checksum_url)) => fetch_checksum(checksum_url).await?,
+        (None, None) => return Err(format!("版本 {} 的安裝檔未提供校驗碼，無法安全更新", release.version)),
+    };
+    
+    let download_dir = update_download_dir()?;
+    let file_path = download_dir.join(&asset.name);
+    download_asset(&app, &asset, &file_path).await?;
+    
+    if let Err(e) = verify_checksum(&file_path, &expected_sha256) {
+        let _ = std::fs::remove_file(&file_path);
+        return Err(e);
+    }
+    
+    let downloaded = DownloadedUpdate {
+        version: release.version,
+        file_path: file_path.to_string_lossy().to_string(),
+        sha256: expected_sha256,
+    };
+    write_json_file(&download_dir.join("downloaded.json"), &downloaded)?;
+    
+    log::info!("更新檔下載完成並通過校驗: {:?}", file_path);
+    Ok(downloaded.file_path)
+}
+
+/// 下載發佈附帶的 .sha256 校驗檔並取出校驗碼
+async fn fetch_checksum(url: &str) -> Result<String, String> {
+    let text = reqwest::Client::new()
+        .get(url)
+        .header("User-Agent", format!("genesis-chronicle/{}", env!("CARGO_PKG_VERSION")))
+        .send()
+        .await
+        .map_err(|e| format!("無法下載校驗檔: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("無法讀取校驗檔: {}", e))?;
+    
+    text.split_whitespace()
+        .next()
+        .filter(|hash| hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit()))
+        .map(|hash| hash.to_lowercase())
+        .ok_or_else(|| "校驗檔格式錯誤".to_string())
+}
+
+/// 計算檔案的 SHA-256
+fn sha256_file(path: &std::path::Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    
+    let mut file = std::fs::File::open(path).map_err(|e| format!("無法開啟更新檔: {}", e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("無法讀取更新檔: {}", e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// 驗證檔案校驗碼，不符時回傳明確的校驗錯誤
+fn verify_checksum(path: &std::path::Path, expected_sha256: &str) -> Result<(), String> {
+    let actual = sha256_file(path)?;
+    if !actual.eq_ignore_ascii_case(expected_sha256) {
+        return Err(format!(
+            "更新檔校驗碼不符（預期 {}，實際 {}），檔案可能已損毀或遭竄改",
+            expected_sha256, actual
+        ));
+    }
+    Ok(())
+}
+
+/// 更新狀態目錄（存放舊版本備份與安裝中的更新記錄）
+fn update_state_dir() -> Result<std::path::PathBuf, String> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| "無法獲取用戶資料目錄".to_string())?
+        .join("genesis-chronicle")
+        .join("updates");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("無法建立更新狀態目錄: {}", e))?;
+    Ok(dir)
+}
+
+fn read_json_file<T: serde::de::DeserializeOwned>(path: &std::path::Path) -> Result<Option<T>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| format!("無法讀取 {:?}: {}", path, e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("{:?} 格式錯誤: {}", path, e))
 }
 
+fn write_json_file<T: Serialize>(path: &std::path::Path, value: &T) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(path, content).map_err(|e| format!("無法寫入 {:?}: {}", path, e))
+}
+
+/// 更新檔下載目錄
+fn update_download_dir() -> Result<std::path::PathBuf, String> {
+    let dir = std::env::temp_dir().join("genesis-chronicle-updates");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("無法建立更新下載目錄: {}", e))?;
+    Ok(dir)
+}
+
+/// 串流下載安裝檔並發送進度事件（發送失敗不影響下載）
+async fn download_asset(app: &AppHandle, asset: &ReleaseAsset, file_path: &std::path::Path) -> Result<(), String> {
+    use tokio::io::AsyncWriteExt;
+    
+    let client = reqwest::Client::new();
+    let mut response = client
+        .get(&asset.url)
+        .header("User-Agent", format!("genesis-chronicle/{}", env!("CARGO_PKG_VERSION")))
+        .send()
+        .await
+        .map_err(|e| format!("無法下載更新檔: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("下載更新檔失敗: {}", response.status()));
+    }
+    
+    let total = response.content_length().or(Some(asset.size).filter(|size| *size > 0));
+    let mut file = tokio::fs::File::create(file_path)
+        .await
+        .map_err(|e| format!("無法建立更新檔: {}", e))?;
+    let mut downloaded: u64 = 0;
+    let mut last_progress: Option<u8> = None;
+    
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("下載更新檔中斷: {}", e))? {
+        file.write_all(&chunk).await.map_err(|e| format!("寫入更新檔失敗: {}", e))?;
+        downloaded += chunk.len() as u64;
+        
+        let progress = total.map(|total| ((downloaded * 100) / total.max(1)).min(100) as u8);
+        if progress.is_none() || progress != last_progress {
+            last_progress = progress;
+            let event = UpdateDownloadProgress { downloaded, total, progress };
+            if let Err(e) = app.emit("update-download-progress", event) {
+                log::warn!("發送更新下載進度事件失敗: {}", e);
+            }
+        }
+    }
+    file.flush().await.map_err(|e| format!("寫入更新檔失敗: {}", e))?;
+    
+    Ok(())
+}
+
+/// 安裝已下載的更新：重新校驗、複製到暫存區、備份目前版本後啟動安裝程式
 #[tauri::command]
-pub async fn install_update() -> Result<(), String> {
-    Err("Tauri 版本的更新安裝功能尚未實現".to_string())
+pub async fn install_update(app: AppHandle) -> Result<(), String> {
+    let download_dir = update_download_dir()?;
+    let downloaded: DownloadedUpdate = read_json_file(&download_dir.join("downloaded.json"))?
+        .ok_or_else(|| "尚未下載更新檔，請先執行下載".to_string())?;
+    
+    // 下載後到安裝前檔案仍可能被更動，安裝前再校驗一次
+    let downloaded_path = std::path::PathBuf::from(&downloaded.file_path);
+    verify_checksum(&downloaded_path, &downloaded.sha256)?;
+    
+    let staging_dir = download_dir.join("staged");
+    std::fs::create_dir_all(&staging_dir).map_err(|e| format!("無法建立暫存目錄: {}", e))?;
+    let file_name = downloaded_path
+        .file_name()
+        .ok_or_else(|| "更新檔路徑無效".to_string())?;
+    let staged_path = staging_dir.join(file_name);
+    std::fs::copy(&downloaded_path, &staged_path).map_err(|e| format!("無法暫存更新檔: {}", e))?;
+    verify_checksum(&staged_path, &downloaded.sha256)?;
+    
+    // 備份目前的執行檔，新版本無法正常啟動時可回滾
+    let state_dir = update_state_dir()?;
+    let current_exe = std::env::current_exe().ok();
+    let backup_path = match &current_exe {
+        Some(exe) => {
+            let backup_dir = state_dir.join("previous");
+            std::fs::create_dir_all(&backup_dir).map_err(|e| format!("無法建立備份目錄: {}", e))?;
+            let backup = backup_dir.join(exe.file_name().unwrap_or_default());
+            std::fs::copy(exe, &backup).map_err(|e| format!("無法備份目前版本: {}", e))?;
+            Some(backup.to_string_lossy().to_string())
+        }
+        None => {
+            log::warn!("無法取得目前執行檔路徑，本次更新將無法回滾");
+            None
+        }
+    };
+    
+    let pending = PendingUpdate {
+        from_version: env!("CARGO_PKG_VERSION").to_string(),
+        to_version: downloaded.version,
+        staged_path: staged_path.to_string_lossy().to_string(),
+        backup_path,
+        target_path: current_exe.map(|exe| exe.to_string_lossy().to_string()),
+        unconfirmed_launches: 0,
+    };
+    write_json_file(&state_dir.join("pending.json"), &pending)?;
+    
+    launch_installer(&staged_path)?;
+    log::info!("已啟動 {} 的安裝程式，應用程式即將關閉", pending.to_version);
+    app.exit(0);
+    Ok(())
+}
+
+/// 依平台啟動安裝程式
+fn launch_installer(path: &std::path::Path) -> Result<(), String> {
+    use std::process::Command;
+    
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let result = match (std::env::consts::OS, extension.as_str()) {
+        ("windows", "msi") => Command::new("msiexec").arg("/i").arg(path).spawn(),
+        ("macos", _) => Command::new("open").arg(path).spawn(),
+        ("linux", "deb") => Command::new("xdg-open").arg(path).spawn(),
+        _ => {
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+                    .map_err(|e| format!("無法設定安裝檔權限: {}", e))?;
+            }
+            Command::new(path).spawn()
+        }
+    };
+    
+    result
+        .map(|_| ())
+        .map_err(|e| format!("無法啟動安裝程式: {}", e))
+}
+
+/// 以備份的舊版本覆蓋目前執行檔
+fn restore_previous_version(pending: &PendingUpdate) -> Result<(), String> {
+    let (Some(backup_path), Some(target_path)) = (&pending.backup_path, &pending.target_path) else {
+        return Err("沒有可回滾的舊版本備份".to_string());
+    };
+    let target = std::path::Path::new(target_path);
+    
+    // 執行中的檔案無法直接覆蓋（Windows），先改名再複製備份回原位置
+    let replaced = target.with_extension("update-old");
+    if target.exists() {
+        std::fs::rename(target, &replaced).map_err(|e| format!("無法移開目前版本: {}", e))?;
+    }
+    if let Err(e) = std::fs::copy(backup_path, target) {
+        let _ = std::fs::rename(&replaced, target);
+        return Err(format!("無法還原舊版本: {}", e));
+    }
+    
+    log::info!("已回滾到版本 {}", pending.from_version);
+    Ok(())
+}
+
+/// 啟動時檢查安裝中的更新：未套用則清除記錄，新版本多次啟動都未確認則自動回滾
+pub fn finalize_pending_update() -> Result<(), String> {
+    let pending_path = update_state_dir()?.join("pending.json");
+    let Some(mut pending) = read_json_file::<PendingUpdate>(&pending_path)? else {
+        return Ok(());
+    };
+    
+    let current_version = env!("CARGO_PKG_VERSION");
+    if current_version != pending.to_version {
+        log::warn!("更新到 {} 未完成，目前版本仍為 {}", pending.to_version, current_version);
+        return std::fs::remove_file(&pending_path).map_err(|e| e.to_string());
+    }
+    
+    pending.unconfirmed_launches += 1;
+    if pending.unconfirmed_launches > MAX_UNCONFIRMED_LAUNCHES {
+        log::error!("版本 {} 連續 {} 次啟動未完成，回滾到 {}", pending.to_version, pending.unconfirmed_launches - 1, pending.from_version);
+        restore_previous_version(&pending)?;
+        return std::fs::remove_file(&pending_path).map_err(|e| e.to_string());
+    }
+    
+    write_json_file(&pending_path, &pending)
+}
+
+/// 前端完成啟動後確認更新成功，清除回滾記錄
+#[tauri::command]
+pub async fn confirm_update_launch() -> Result<(), String> {
+    let pending_path = update_state_dir()?.join("pending.json");
+    if let Some(pending) = read_json_file::<PendingUpdate>(&pending_path)? {
+        std::fs::remove_file(&pending_path).map_err(|e| e.to_string())?;
+        let _ = std::fs::remove_file(&pending.staged_path);
+        log::info!("已確認更新到版本 {}", pending.to_version);
+    }
+    Ok(())
+}
+
+/// 手動回滾到更新前的版本（需重新啟動應用程式）
+#[tauri::command]
+pub async fn rollback_update() -> Result<(), String> {
+    let pending_path = update_state_dir()?.join("pending.json");
+    let pending = read_json_file::<PendingUpdate>(&pending_path)?
+        .ok_or_else(|| "沒有進行中的更新可回滾".to_string())?;
+    
+    restore_previous_version(&pending)?;
+    std::fs::remove_file(&pending_path).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -249,8 +600,8 @@ pub async fn set_auto_update(_enabled: bool) -> Result<(), String> {
     Ok(()) // Tauri 版本暫時不實現自動更新設定
 }
 
-// 輔助函數：獲取最新版本
-async fn fetch_latest_version() -> Result<String, String> {
+// 輔助函數：獲取最新發佈版本
+async fn fetch_latest_release() -> Result<ReleaseInfo, String> {
     use reqwest;
     
     let client = reqwest::Client::new();
@@ -264,15 +615,7 @@ async fn fetch_latest_version() -> Result<String, String> {
         Ok(response) => {
             if response.status().is_success() {
                 match response.json::<serde_json::Value>().await {
-                    Ok(json) => {
-                        if let Some(tag_name) = json.get("tag_name").and_then(|v| v.as_str()) {
-                            // 移除 'v' 前綴（如果存在）
-                            let version = tag_name.strip_prefix('v').unwrap_or(tag_name);
-                            Ok(version.to_string())
-                        } else {
-                            Err("無法解析版本信息".to_string())
-                        }
-                    }
+                    Ok(json) => parse_release(&json),
                     Err(_) => Err("無法解析 GitHub API 響應".to_string()),
                 }
             } else {
@@ -290,28 +633,288 @@ async fn fetch_latest_version() -> Result<String, String> {
     }
 }
 
-// 輔助函數：比較版本號
+// 輔助函數：解析 GitHub Release JSON
+fn parse_release(json: &serde_json::Value) -> Result<ReleaseInfo, String> {
+    let tag_name = json
+        .get("tag_name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "無法解析版本信息".to_string())?;
+    // 移除 'v' 前綴（如果存在）
+    let version = tag_name.strip_prefix('v').unwrap_or(tag_name).to_string();
+    let notes = json
+        .get("body")
+        .and_then(|v| v.as_str())
+        .map(|body| body.trim().to_string())
+        .filter(|body| !body.is_empty());
+    
+    let mut assets: Vec<ReleaseAsset> = json
+        .get("assets")
+        .and_then(|v| v.as_array())
+        .map(|assets| {
+            assets
+                .iter()
+                .filter_map(|asset| {
+                    Some(ReleaseAsset {
+                        name: asset.get("name")?.as_str()?.to_string(),
+                        url: asset.get("browser_download_url")?.as_str()?.to_string(),
+                        size: asset.get("size").and_then(|v| v.as_u64()).unwrap_or(0),
+                        // GitHub 提供的 digest 格式為 "sha256:<hex>"
+                        sha256: asset
+                            .get("digest")
+                            .and_then(|v| v.as_str())
+                            .and_then(|digest| digest.strip_prefix("sha256:"))
+                            .map(|hash| hash.to_lowercase()),
+                        checksum_url: None,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    
+    // 沒有 digest 的安裝檔改用同名的 .sha256 校驗檔
+    let checksum_urls: Vec<(String, String)> = assets
+        .iter()
+        .filter_map(|asset| {
+            let target = asset.name.strip_suffix(".sha256")?;
+            Some((target.to_string(), asset.url.clone()))
+        })
+        .collect();
+    for asset in assets.iter_mut().filter(|asset| asset.sha256.is_none()) {
+        asset.checksum_url = checksum_urls
+            .iter()
+            .find(|(target, _)| *target == asset.name)
+            .map(|(_, url)| url.clone());
+    }
+    
+    Ok(ReleaseInfo {
+        version,
+        notes,
+        asset: select_platform_asset(assets, std::env::consts::OS),
+    })
+}
+
+// 輔助函數：依作業系統挑選安裝檔
+fn select_platform_asset(assets: Vec<ReleaseAsset>, os: &str) -> Option<ReleaseAsset> {
+    let extensions: &[&str] = match os {
+        "windows" => &[".msi", ".exe"],
+        "macos" => &[".dmg"],
+        "linux" => &[".appimage", ".deb"],
+        _ => &[],
+    };
+    
+    extensions.iter().find_map(|extension| {
+        assets
+            .iter()
+            .find(|asset| asset.name.to_lowercase().ends_with(extension))
+            .cloned()
+    })
+}
+
+// 輔助函數：比較版本號（依語意化版本規則，latest 較新時回傳 true）
 fn compare_versions(current: &str, latest: &str) -> bool {
-    fn parse_version(version: &str) -> Vec<u32> {
-        version
-            .split('.')
-            .filter_map(|s| s.parse().ok())
-            .collect()
+    fn parse_version(version: &str) -> Option<semver::Version> {
+        let version = version.trim().trim_start_matches('v');
+        semver::Version::parse(version).ok().or_else(|| {
+            // 補齊省略的版本段（如 "1.2" → "1.2.0"）
+            let parts: Vec<&str> = version.split('.').collect();
+            if parts.len() < 3 && parts.iter().all(|part| part.parse::<u64>().is_ok()) {
+                let mut padded = parts.join(".");
+                for _ in parts.len()..3 {
+                    padded.push_str(".0");
+                }
+                semver::Version::parse(&padded).ok()
+            } else {
+                None
+            }
+        })
     }
     
-    let current_parts = parse_version(current);
-    let latest_parts = parse_version(latest);
+    match (parse_version(current), parse_version(latest)) {
+        (Some(current), Some(latest)) => latest > current,
+        _ => {
+            log::warn!("無法比較版本號: {} / {}", current, latest);
+            false
+        }
+    }
+}
+
+/// 診斷資料中保留的日誌行數
+const DIAGNOSTICS_LOG_LINES: usize = 200;
+
+/// 收集診斷資料（版本、系統、AI 提供者類型、資料表筆數、日誌尾端），機密資訊已遮蔽，可直接貼到問題回報
+#[tauri::command]
+pub async fn collect_diagnostics(app: AppHandle) -> Result<String, String> {
+    use crate::database::{get_db_conn, migrations};
+    use serde_json::json;
     
-    for i in 0..std::cmp::max(current_parts.len(), latest_parts.len()) {
-        let current_part = current_parts.get(i).unwrap_or(&0);
-        let latest_part = latest_parts.get(i).unwrap_or(&0);
+    let (database, providers) = {
+        let conn = get_db_conn().map_err(|e| e.to_string())?;
         
-        if latest_part > current_part {
-            return true;
-        } else if latest_part < current_part {
-            return false;
-        }
+        let migration = migrations::get_migration_status(&conn).map_err(|e| format!("查詢遷移狀態失敗: {}", e))?;
+        let table_counts: serde_json::Map<String, serde_json::Value> = crate::commands::database::STATS_TABLES
+            .iter()
+            .filter_map(|table| {
+                let rows = conn
+                    .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get::<_, i64>(0))
+                    .ok()?;
+                Some((table.to_string(), json!(rows)))
+            })
+            .collect();
+        
+        // 僅收集提供者類型與啟用狀態，不包含金鑰與端點
+        let providers: Vec<serde_json::Value> = conn
+            .prepare("SELECT provider_type, is_enabled FROM ai_providers ORDER BY provider_type")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| {
+                    Ok(json!({
+                        "provider_type": row.get::<_, String>(0)?,
+                        "is_enabled": row.get::<_, bool>(1)?,
+                    }))
+                })?
+                .collect()
+            })
+            .unwrap_or_default();
+        
+        (
+            json!({
+                "version": migration.current_version,
+                "target_version": migration.target_version,
+                "table_counts": table_counts,
+            }),
+            providers,
+        )
+    };
+    
+    let log_tail = read_log_tail(&app, DIAGNOSTICS_LOG_LINES)
+        .map(|lines| lines.iter().map(|line| redact_diagnostic_text(line)).collect::<Vec<_>>())
+        .unwrap_or_else(|e| vec![format!("（無法讀取日誌: {}）", e)]);
+    
+    let diagnostics = json!({
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "database": database,
+        "ai_providers": providers,
+        "log_tail": log_tail,
+    });
+    
+    serde_json::to_string_pretty(&diagnostics).map_err(|e| e.to_string())
+}
+
+/// 讀取應用程式日誌檔的最後幾行
+fn read_log_tail(app: &AppHandle, max_lines: usize) -> Result<Vec<String>, String> {
+    let log_dir = app.path().app_log_dir().map_err(|e| e.to_string())?;
+    let log_file = std::fs::read_dir(&log_dir)
+        .map_err(|e| format!("無法開啟日誌目錄: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "log"))
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .ok_or_else(|| "找不到日誌檔".to_string())?;
+    
+    let content = std::fs::read(log_file.path()).map_err(|e| format!("無法讀取日誌檔: {}", e))?;
+    let content = String::from_utf8_lossy(&content);
+    let lines: Vec<&str> = content.lines().collect();
+    let start = lines.len().saturating_sub(max_lines);
+    Ok(lines[start..].iter().map(|line| line.to_string()).collect())
+}
+
+/// 遮蔽診斷文字中的 API 金鑰、授權標頭與使用者目錄
+fn redact_diagnostic_text(text: &str) -> String {
+    use regex::Regex;
+    
+    lazy_static::lazy_static! {
+        static ref SECRET_PATTERNS: Vec<Regex> = vec![
+            Regex::new(r"(?i)(bearer\s+)[A-Za-z0-9._~+/=-]+").unwrap(),
+            Regex::new(r"(?i)((?:api[_-]?key|token|secret|password|key)\s*[=:]\s*)[^\s&,;]+").unwrap(),
+            Regex::new(r"\bsk-[A-Za-z0-9_-]{10,}").unwrap(),
+            Regex::new(r"\bAIza[0-9A-Za-z_-]{20,}").unwrap(),
+        ];
+    }
+    
+    let mut redacted = text.to_string();
+    for pattern in SECRET_PATTERNS.iter() {
+        redacted = pattern
+            .replace_all(&redacted, |caps: &regex::Captures| {
+                format!("{}[REDACTED]", caps.get(1).map(|m| m.as_str()).unwrap_or(""))
+            })
+            .into_owned();
     }
     
-    false
-}
\ No newline at end of file
+    if let Some(home) = dirs::home_dir().map(|home| home.to_string_lossy().to_string()) {
+        if !home.is_empty() {
+            redacted = redacted.replace(&home, "~");
+        }
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_versions_uses_semver_ordering() {
+        assert!(compare_versions("0.9.0", "0.10.0"));
+        assert!(!compare_versions("0.10.0", "0.9.0"));
+        assert!(compare_versions("1.0.0-beta.2", "1.0.0"));
+        assert!(!compare_versions("1.0.0", "1.0.0-beta.2"));
+        assert!(compare_versions("1.2", "v1.2.1"));
+        assert!(!compare_versions("1.2.0", "1.2.0"));
+    }
+
+    #[test]
+    fn test_parse_release_selects_platform_asset() {
+        let json = serde_json::json!({
+            "tag_name": "v1.3.0",
+            "body": "  修正錯誤  ",
+            "assets": [
+                { "name": "app_1.3.0_x64.dmg", "browser_download_url": "https://example.com/a.dmg", "size": 10 },
+                { "name": "app_1.3.0_x64.dmg.sha256", "browser_download_url": "https://example.com/a.dmg.sha256", "size": 1 },
+                { "name": "app_1.3.0_x64_en-US.msi", "browser_download_url": "https://example.com/a.msi", "size": 20, "digest": "sha256:ABCDEF" },
+                { "name": "app_1.3.0_amd64.AppImage", "browser_download_url": "https://example.com/a.AppImage", "size": 30 }
+            ]
+        });
+        let release = parse_release(&json).unwrap();
+        assert_eq!(release.version, "1.3.0");
+        assert_eq!(release.notes.as_deref(), Some("修正錯誤"));
+        if let Some(asset) = release.asset {
+            match std::env::consts::OS {
+                "windows" => assert_eq!(asset.sha256.as_deref(), Some("abcdef")),
+                "macos" => assert_eq!(asset.checksum_url.as_deref(), Some("https://example.com/a.dmg.sha256")),
+                _ => assert!(asset.sha256.is_none() && asset.checksum_url.is_none()),
+            }
+        }
+        
+        let assets = vec![
+            ReleaseAsset { name: "a.dmg".to_string(), url: String::new(), size: 10, sha256: None, checksum_url: None },
+            ReleaseAsset { name: "a.msi".to_string(), url: String::new(), size: 20, sha256: None, checksum_url: None },
+        ];
+        assert_eq!(select_platform_asset(assets.clone(), "windows").unwrap().size, 20);
+        assert_eq!(select_platform_asset(assets.clone(), "macos").unwrap().size, 10);
+        assert!(select_platform_asset(assets, "linux").is_none());
+    }
+
+    #[test]
+    fn test_verify_checksum_reports_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("update.bin");
+        std::fs::write(&path, b"abc").unwrap();
+        
+        let expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert!(verify_checksum(&path, expected).is_ok());
+        assert!(verify_checksum(&path, &expected.to_uppercase()).is_ok());
+        let error = verify_checksum(&path, &"0".repeat(64)).unwrap_err();
+        assert!(error.contains("校驗碼不符"));
+    }
+    #[test]
+    fn test_redact_diagnostic_text_masks_secrets() {
+        let line = "request failed: Authorization: Bearer abc.def-123 url=https://x?key=AIzaSyA1234567890abcdefghijk&alt=json sk-proj-abcdefghijklmnop";
+        let redacted = redact_diagnostic_text(line);
+        assert!(!redacted.contains("abc.def-123"));
+        assert!(!redacted.contains("AIzaSyA1234567890"));
+        assert!(!redacted.contains("sk-proj-abcdefghijklmnop"));
+        assert!(redacted.contains("Bearer [REDACTED]"));
+        assert!(redacted.contains("&alt=json"));
+    }
+}
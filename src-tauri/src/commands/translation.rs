@@ -1,10 +1,14 @@
 use serde_json::Value;
+use std::collections::HashMap;
 use crate::services::translation::{
-    TranslationEngine, TranslationRequest, TranslationStyle, QualityLevel,
-    VocabularyDatabase, PromptOptimizer, OptimizationRequest, OptimizationLevel, PromptStyle, QualityFocus,
+    TranslationEngine, TranslationRequest, TranslationStyle, QualityLevel, CoverageReport,
+    VocabularyDatabase, VocabularyCategory, VocabularyEntryInput, VocabularySource, UpsertOutcome, PromptOptimizer, OptimizationRequest, IterativeOptimizationResult, OptimizationLevel, PromptStyle, QualityFocus,
 };
-use crate::database::connection::create_connection;
-use std::sync::{Arc, Mutex};
+use crate::database::get_db;
+use crate::services::translation::translation_engine::TranslationResult;
+use crate::utils::csv::{escape_csv_field, parse_csv};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
 
 /// 翻譯中文角色描述為英文提示詞
 #[tauri::command]
@@ -14,11 +18,11 @@ pub async fn translate_character_description(
     target_style: String, // "anime", "realistic", "concept_art", "comic"
     quality_level: String, // "fast", "standard", "high", "professional"
     preserve_original: Option<bool>,
+    locked_terms: Option<HashMap<String, String>>,
 ) -> Result<Value, String> {
     log::info!("[TranslationCommand] 翻譯角色描述: {}", chinese_description);
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     // 建立詞彙庫和翻譯引擎
     let vocabulary_db = VocabularyDatabase::new(db_arc);
@@ -50,6 +54,7 @@ pub async fn translate_character_description(
         quality_level: quality,
         context_hints: Vec::new(),
         preserve_original: preserve_original.unwrap_or(false),
+        locked_terms: locked_terms.unwrap_or_default(),
     };
 
     // 執行翻譯
@@ -105,17 +110,7 @@ pub async fn optimize_prompt(
         _ => PromptStyle::Detailed,
     };
 
-    let focus_items: Vec<QualityFocus> = quality_focus.iter().filter_map(|f| {
-        match f.as_str() {
-            "character_consistency" => Some(QualityFocus::CharacterConsistency),
-            "artistic_quality" => Some(QualityFocus::ArtisticQuality),
-            "detail_richness" => Some(QualityFocus::DetailRichness),
-            "composition" => Some(QualityFocus::Composition),
-            "lighting" => Some(QualityFocus::Lighting),
-            "color_harmony" => Some(QualityFocus::ColorHarmony),
-            _ => None,
-        }
-    }).collect();
+    let focus_items = parse_quality_focus(&quality_focus);
 
     let request = OptimizationRequest {
         base_prompt,
@@ -147,6 +142,49 @@ pub async fn optimize_prompt(
     }
 }
 
+/// 解析品質焦點（忽略未知項目）
+fn parse_quality_focus(quality_focus: &[String]) -> Vec<QualityFocus> {
+    quality_focus.iter().filter_map(|f| {
+        match f.as_str() {
+            "character_consistency" => Some(QualityFocus::CharacterConsistency),
+            "artistic_quality" => Some(QualityFocus::ArtisticQuality),
+            "detail_richness" => Some(QualityFocus::DetailRichness),
+            "composition" => Some(QualityFocus::Composition),
+            "lighting" => Some(QualityFocus::Lighting),
+            "color_harmony" => Some(QualityFocus::ColorHarmony),
+            _ => None,
+        }
+    }).collect()
+}
+
+/// 多輪優化提示詞，回傳每一輪的結果與改善分數
+#[tauri::command]
+pub async fn optimize_prompt_iterative(
+    prompt: String,
+    iterations: Option<usize>,
+    focus: Vec<String>, // ["character_consistency", "artistic_quality", etc.]
+    target_model: Option<String>, // 預設 "stable_diffusion"
+) -> Result<IterativeOptimizationResult, String> {
+    log::info!("[TranslationCommand] 多輪優化提示詞: {}", prompt);
+
+    let optimizer = PromptOptimizer::new();
+    let request = OptimizationRequest {
+        base_prompt: prompt,
+        target_model: target_model.unwrap_or_else(|| "stable_diffusion".to_string()),
+        optimization_level: OptimizationLevel::Standard,
+        prompt_style: PromptStyle::Detailed,
+        include_negative_prompt: false,
+        max_length: None,
+        quality_focus: parse_quality_focus(&focus),
+    };
+
+    let result = optimizer.optimize_iterative(request, iterations.unwrap_or(3))
+        .map_err(|e| format!("提示詞優化失敗: {}", e))?;
+
+    log::info!("[TranslationCommand] 多輪優化完成，共 {} 輪", result.passes.len());
+    Ok(result)
+}
+
 /// 搜尋詞彙庫中的翻譯
 #[tauri::command]
 pub async fn search_vocabulary(
@@ -157,8 +195,7 @@ pub async fn search_vocabulary(
 ) -> Result<Value, String> {
     log::info!("[TranslationCommand] 搜尋詞彙: {}", chinese_term);
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let vocabulary_db = VocabularyDatabase::new(db_arc);
 
@@ -166,26 +203,7 @@ pub async fn search_vocabulary(
         vocabulary_db.fuzzy_search(&chinese_term, limit)
             .map_err(|e| format!("模糊搜尋失敗: {:?}", e))?
     } else {
-        let category_enum = category.and_then(|cat| {
-            match cat.as_str() {
-                "facial_features" => Some(crate::services::translation::VocabularyCategory::FacialFeatures),
-                "hair" => Some(crate::services::translation::VocabularyCategory::Hair),
-                "eyes" => Some(crate::services::translation::VocabularyCategory::Eyes),
-                "body_type" => Some(crate::services::translation::VocabularyCategory::BodyType),
-                "clothing" => Some(crate::services::translation::VocabularyCategory::Clothing),
-                "accessories" => Some(crate::services::translation::VocabularyCategory::Accessories),
-                "expression" => Some(crate::services::translation::VocabularyCategory::Expression),
-                "pose" => Some(crate::services::translation::VocabularyCategory::Pose),
-                "background" => Some(crate::services::translation::VocabularyCategory::Background),
-                "art_style" => Some(crate::services::translation::VocabularyCategory::ArtStyle),
-                "effects" => Some(crate::services::translation::VocabularyCategory::Effects),
-                "colors" => Some(crate::services::translation::VocabularyCategory::Colors),
-                "texture" => Some(crate::services::translation::VocabularyCategory::Texture),
-                "lighting" => Some(crate::services::translation::VocabularyCategory::Lighting),
-                "personality" => Some(crate::services::translation::VocabularyCategory::Personality),
-                _ => None,
-            }
-        });
+        let category_enum = category.as_deref().and_then(VocabularyCategory::from_key);
 
         vocabulary_db.find_translation(&chinese_term, category_enum)
             .map_err(|e| format!("翻譯搜尋失敗: {:?}", e))?
@@ -203,13 +221,46 @@ pub async fn search_vocabulary(
     }
 }
 
+/// 記錄詞彙翻譯結果的回饋，用於計算詞彙成功率
+#[tauri::command]
+pub async fn record_vocabulary_outcome(term_id: i64, succeeded: bool) -> Result<Value, String> {
+    log::info!("[TranslationCommand] 記錄詞彙回饋: {} ({})", term_id, if succeeded { "成功" } else { "失敗" });
+
+    let db_connection = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let vocabulary_db = VocabularyDatabase::new(db_connection);
+
+    vocabulary_db.record_outcome(term_id, succeeded)
+        .map_err(|e| format!("記錄詞彙回饋失敗: {}", e))?;
+
+    Ok(serde_json::json!({ "success": true }))
+}
+
+/// 反向查詢詞彙：英文詞彙對應的中文詞彙
+#[tauri::command]
+pub async fn reverse_lookup_vocabulary(english_term: String) -> Result<Value, String> {
+    log::info!("[TranslationCommand] 反向查詢詞彙: {}", english_term);
+
+    let db_connection = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let vocabulary_db = VocabularyDatabase::new(db_connection);
+
+    let results = vocabulary_db.reverse_lookup(&english_term)
+        .map_err(|e| format!("反向查詢失敗: {}", e))?;
+
+    log::info!("[TranslationCommand] 反向查詢完成，找到 {} 個結果", results.len());
+
+    Ok(serde_json::json!({
+        "success": true,
+        "results": serde_json::to_value(&results).map_err(|e| format!("JSON 序列化失敗: {}", e))?,
+        "count": results.len()
+    }))
+}
+
 /// 獲取詞彙庫統計資訊
 #[tauri::command]
 pub async fn get_vocabulary_stats() -> Result<Value, String> {
     log::info!("[TranslationCommand] 獲取詞彙庫統計資訊");
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let vocabulary_db = VocabularyDatabase::new(db_arc);
 
@@ -232,18 +283,441 @@ pub async fn get_vocabulary_stats() -> Result<Value, String> {
     }
 }
 
+/// 解析使用者詞彙輸入
+#[allow(clippy::too_many_arguments)]
+fn build_vocabulary_input(
+    chinese_term: String,
+    english_term: String,
+    category: String,
+    subcategory: Option<String>,
+    priority: Option<i32>,
+    context_tags: Option<Vec<String>>,
+    synonyms: Option<Vec<String>>,
+    variations: Option<Vec<String>>,
+) -> Result<VocabularyEntryInput, String> {
+    let category = VocabularyCategory::from_key(&category)
+        .ok_or_else(|| format!("未知的詞彙分類: {}", category))?;
+
+    Ok(VocabularyEntryInput {
+        chinese_term,
+        english_term,
+        category,
+        subcategory,
+        priority,
+        context_tags: context_tags.unwrap_or_default(),
+        synonyms: synonyms.unwrap_or_default(),
+        variations: variations.unwrap_or_default(),
+    })
+}
+
+/// 新增使用者詞彙
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn add_vocabulary_entry(
+    chinese_term: String,
+    english_term: String,
+    category: String, // "hair", "eyes", "clothing" 等
+    subcategory: Option<String>,
+    priority: Option<i32>,
+    context_tags: Option<Vec<String>>,
+    synonyms: Option<Vec<String>>,
+    variations: Option<Vec<String>>,
+) -> Result<Value, String> {
+    log::info!("[TranslationCommand] 新增詞彙: {} -> {}", chinese_term, english_term);
+
+    let input = build_vocabulary_input(
+        chinese_term, english_term, category, subcategory, priority, context_tags, synonyms, variations,
+    )?;
+
+    let db_connection = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let vocabulary_db = VocabularyDatabase::new(db_connection);
+
+    let entry_id = vocabulary_db.add_user_entry(input)
+        .map_err(|e| format!("新增詞彙失敗: {}", e))?;
+
+    Ok(serde_json::json!({
+        "success": true,
+        "id": entry_id
+    }))
+}
+
+/// 更新使用者詞彙（內建詞彙不可修改）
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_vocabulary_entry(
+    id: i64,
+    chinese_term: String,
+    english_term: String,
+    category: String,
+    subcategory: Option<String>,
+    priority: Option<i32>,
+    context_tags: Option<Vec<String>>,
+    synonyms: Option<Vec<String>>,
+    variations: Option<Vec<String>>,
+) -> Result<Value, String> {
+    log::info!("[TranslationCommand] 更新詞彙: {}", id);
+
+    let input = build_vocabulary_input(
+        chinese_term, english_term, category, subcategory, priority, context_tags, synonyms, variations,
+    )?;
+
+    let db_connection = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let vocabulary_db = VocabularyDatabase::new(db_connection);
+
+    vocabulary_db.update_user_entry(id, input)
+        .map_err(|e| format!("更新詞彙失敗: {}", e))?;
+
+    Ok(serde_json::json!({
+        "success": true,
+        "id": id
+    }))
+}
+
+/// 刪除使用者詞彙（內建詞彙不可刪除）
+#[tauri::command]
+pub async fn delete_vocabulary_entry(id: i64) -> Result<Value, String> {
+    log::info!("[TranslationCommand] 刪除詞彙: {}", id);
+
+    let db_connection = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let vocabulary_db = VocabularyDatabase::new(db_connection);
+
+    vocabulary_db.delete_user_entry(id)
+        .map_err(|e| format!("刪除詞彙失敗: {}", e))?;
+
+    Ok(serde_json::json!({
+        "success": true,
+        "id": id
+    }))
+}
+
+/// 分析中文描述的詞彙覆蓋率，列出已匹配與未翻譯的詞彙
+#[tauri::command]
+pub async fn analyze_vocabulary_coverage(chinese_text: String) -> Result<CoverageReport, String> {
+    log::info!("[TranslationCommand] 分析詞彙覆蓋率: {}", chinese_text);
+
+    let db_connection = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let vocabulary_db = VocabularyDatabase::new(db_connection);
+    let translation_engine = TranslationEngine::new(vocabulary_db)
+        .map_err(|e| format!("翻譯引擎初始化失敗: {:?}", e))?;
+
+    translation_engine.analyze_coverage(&chinese_text)
+        .map_err(|e| format!("覆蓋率分析失敗: {}", e))
+}
+
+/// 詞彙匯出/匯入的檔案格式（CSV 的清單欄位以 `|` 分隔）
+#[derive(Debug, Serialize, Deserialize)]
+struct VocabularyFileRecord {
+    chinese_term: String,
+    english_term: String,
+    category: String,
+    #[serde(default)]
+    subcategory: Option<String>,
+    #[serde(default)]
+    priority: Option<i32>,
+    #[serde(default)]
+    context_tags: Vec<String>,
+    #[serde(default)]
+    synonyms: Vec<String>,
+    #[serde(default)]
+    variations: Vec<String>,
+}
+
+const VOCABULARY_CSV_HEADER: [&str; 8] = [
+    "chinese_term", "english_term", "category", "subcategory", "priority", "context_tags", "synonyms", "variations",
+];
+
+/// 匯入失敗的資料列
+#[derive(Debug, Serialize)]
+pub struct VocabularyImportError {
+    /// CSV 為行號、JSON 為陣列索引（皆從 1 開始）
+    pub row: usize,
+    pub message: String,
+}
+
+/// 詞彙匯入報告
+#[derive(Debug, Serialize)]
+pub struct VocabularyImportReport {
+    pub total_rows: usize,
+    pub inserted: usize,
+    pub updated: usize,
+    pub skipped_builtin: usize,
+    pub errors: Vec<VocabularyImportError>,
+}
+
+fn split_list_field(value: &str) -> Vec<String> {
+    value.split('|').map(str::trim).filter(|v| !v.is_empty()).map(str::to_string).collect()
+}
+
+/// 解析 CSV 詞彙檔，格式錯誤的列記錄在錯誤清單中
+fn parse_vocabulary_csv(text: &str, errors: &mut Vec<VocabularyImportError>) -> Vec<(usize, VocabularyFileRecord)> {
+    let mut rows = parse_csv(text).into_iter();
+    let Some((_, header)) = rows.next() else {
+        return Vec::new();
+    };
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let columns: Vec<Option<usize>> = VOCABULARY_CSV_HEADER.iter().map(|name| column(name)).collect();
+    let (Some(chinese_col), Some(english_col), Some(category_col)) = (columns[0], columns[1], columns[2]) else {
+        errors.push(VocabularyImportError {
+            row: 1,
+            message: "CSV 標題列必須包含 chinese_term、english_term、category".to_string(),
+        });
+        return Vec::new();
+    };
+
+    rows.filter_map(|(line, fields)| {
+        let get = |index: Option<usize>| index.and_then(|i| fields.get(i)).map(|v| v.trim().to_string()).unwrap_or_default();
+        let priority = get(columns[4]);
+        let priority = if priority.is_empty() {
+            None
+        } else {
+            match priority.parse() {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    errors.push(VocabularyImportError { row: line, message: format!("優先級不是整數: {}", priority) });
+                    return None;
+                }
+            }
+        };
+        let subcategory = get(columns[3]);
+        Some((line, VocabularyFileRecord {
+            chinese_term: get(Some(chinese_col)),
+            english_term: get(Some(english_col)),
+            category: get(Some(category_col)),
+            subcategory: (!subcategory.is_empty()).then_some(subcategory),
+            priority,
+            context_tags: split_list_field(&get(columns[5])),
+            synonyms: split_list_field(&get(columns[6])),
+            variations: split_list_field(&get(columns[7])),
+        }))
+    })
+    .collect()
+}
+
+/// 從 CSV 或 JSON 檔批次匯入詞彙
+///
+/// 以（中文詞彙, 分類）為鍵新增或更新，匯入的詞彙標記為社群貢獻（Community）；
+/// 格式錯誤的資料列會略過並記錄在報告中，內建詞彙不會被覆蓋。
+#[tauri::command]
+pub async fn import_vocabulary(path: String, format: String) -> Result<VocabularyImportReport, String> {
+    log::info!("[TranslationCommand] 匯入詞彙: {} ({})", path, format);
+
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("讀取詞彙檔失敗: {}", e))?;
+    let mut errors = Vec::new();
+    let records: Vec<(usize, VocabularyFileRecord)> = match format.as_str() {
+        "csv" => parse_vocabulary_csv(&text, &mut errors),
+        "json" => {
+            let values: Vec<Value> = serde_json::from_str(&text).map_err(|e| format!("JSON 格式錯誤: {}", e))?;
+            values
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, value)| match serde_json::from_value(value) {
+                    Ok(record) => Some((index + 1, record)),
+                    Err(e) => {
+                        errors.push(VocabularyImportError { row: index + 1, message: e.to_string() });
+                        None
+                    }
+                })
+                .collect()
+        }
+        other => return Err(format!("不支援的匯入格式: {}（支援 csv、json）", other)),
+    };
+
+    let db_connection = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let vocabulary_db = VocabularyDatabase::new(db_connection);
+
+    let mut report = VocabularyImportReport {
+        total_rows: records.len() + errors.len(),
+        inserted: 0,
+        updated: 0,
+        skipped_builtin: 0,
+        errors,
+    };
+
+    for (row, record) in records {
+        let input = build_vocabulary_input(
+            record.chinese_term,
+            record.english_term,
+            record.category,
+            record.subcategory,
+            record.priority,
+            Some(record.context_tags),
+            Some(record.synonyms),
+            Some(record.variations),
+        );
+        let outcome = input.and_then(|input| {
+            vocabulary_db.upsert_entry(input, VocabularySource::Community).map_err(|e| e.to_string())
+        });
+        match outcome {
+            Ok(UpsertOutcome::Inserted) => report.inserted += 1,
+            Ok(UpsertOutcome::Updated) => report.updated += 1,
+            Ok(UpsertOutcome::SkippedBuiltin) => report.skipped_builtin += 1,
+            Err(message) => report.errors.push(VocabularyImportError { row, message }),
+        }
+    }
+    report.errors.sort_by_key(|error| error.row);
+
+    log::info!(
+        "[TranslationCommand] 詞彙匯入完成，新增: {}, 更新: {}, 略過內建: {}, 錯誤: {}",
+        report.inserted, report.updated, report.skipped_builtin, report.errors.len()
+    );
+    Ok(report)
+}
+
+/// 匯出詞彙為 CSV 或 JSON（可依分類篩選），輸出檔可直接以 `import_vocabulary` 匯回
+#[tauri::command]
+pub async fn export_vocabulary(path: String, format: String, category_filter: Option<String>) -> Result<usize, String> {
+    log::info!("[TranslationCommand] 匯出詞彙: {} ({})", path, format);
+
+    let category = category_filter
+        .map(|key| VocabularyCategory::from_key(&key).ok_or_else(|| format!("未知的詞彙分類: {}", key)))
+        .transpose()?;
+
+    let db_connection = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
+    let vocabulary_db = VocabularyDatabase::new(db_connection);
+    let entries = vocabulary_db.list_entries(category.as_ref())
+        .map_err(|e| format!("讀取詞彙失敗: {}", e))?;
+
+    let records: Vec<VocabularyFileRecord> = entries
+        .into_iter()
+        .map(|entry| VocabularyFileRecord {
+            chinese_term: entry.chinese_term,
+            english_term: entry.english_term,
+            category: entry.category.key().to_string(),
+            subcategory: entry.subcategory,
+            priority: Some(entry.priority),
+            context_tags: entry.context_tags,
+            synonyms: entry.synonyms,
+            variations: entry.variations,
+        })
+        .collect();
+
+    let content = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&records).map_err(|e| format!("JSON 序列化失敗: {}", e))?,
+        "csv" => {
+            // 加上 BOM 讓 Excel 正確辨識 UTF-8
+            let mut csv = format!("\u{feff}{}\n", VOCABULARY_CSV_HEADER.join(","));
+            for record in &records {
+                let fields = [
+                    record.chinese_term.clone(),
+                    record.english_term.clone(),
+                    record.category.clone(),
+                    record.subcategory.clone().unwrap_or_default(),
+                    record.priority.map(|p| p.to_string()).unwrap_or_default(),
+                    record.context_tags.join("|"),
+                    record.synonyms.join("|"),
+                    record.variations.join("|"),
+                ];
+                csv.push_str(&fields.iter().map(|f| escape_csv_field(f)).collect::<Vec<_>>().join(","));
+                csv.push('\n');
+            }
+            csv
+        }
+        other => return Err(format!("不支援的匯出格式: {}（支援 csv、json）", other)),
+    };
+
+    std::fs::write(&path, content).map_err(|e| format!("寫入詞彙檔失敗: {}", e))?;
+
+    log::info!("[TranslationCommand] 已匯出 {} 筆詞彙到 {}", records.len(), path);
+    Ok(records.len())
+}
+
+/// 批次翻譯中成功的一筆
+#[derive(Debug, Serialize)]
+pub struct BatchTranslationSuccess<T> {
+    pub index: usize,
+    pub character_name: Option<String>,
+    pub result: T,
+}
+
+/// 批次翻譯中失敗的一筆
+#[derive(Debug, Serialize)]
+pub struct BatchTranslationFailure {
+    pub index: usize,
+    pub character_name: Option<String>,
+    pub error: String,
+}
+
+/// 批次翻譯結果（成功與失敗分開列出）
+#[derive(Debug, Serialize)]
+pub struct BatchTranslationResult<T> {
+    pub total_count: usize,
+    pub success_count: usize,
+    pub failed_count: usize,
+    pub successes: Vec<BatchTranslationSuccess<T>>,
+    pub failures: Vec<BatchTranslationFailure>,
+}
+
+/// translation-progress 事件內容
+#[derive(Debug, Clone, Serialize)]
+pub struct TranslationProgressEvent {
+    pub index: usize,
+    pub completed: usize,
+    pub total: usize,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// 逐筆翻譯，單筆失敗時記錄錯誤並繼續；每完成一筆呼叫 `on_progress`
+fn translate_in_batch<T, E: std::fmt::Display>(
+    descriptions: &[String],
+    character_names: Option<&[String]>,
+    mut translate: impl FnMut(&str, Option<String>) -> Result<T, E>,
+    mut on_progress: impl FnMut(TranslationProgressEvent),
+) -> BatchTranslationResult<T> {
+    let total = descriptions.len();
+    let mut successes = Vec::new();
+    let mut failures = Vec::new();
+
+    for (index, description) in descriptions.iter().enumerate() {
+        let character_name = character_names.and_then(|names| names.get(index)).cloned();
+
+        let error = match translate(description, character_name.clone()) {
+            Ok(result) => {
+                successes.push(BatchTranslationSuccess { index, character_name, result });
+                None
+            }
+            Err(e) => {
+                let error = e.to_string();
+                log::error!("[TranslationCommand] 描述 {} 翻譯失敗: {}", index, error);
+                failures.push(BatchTranslationFailure { index, character_name, error: error.clone() });
+                Some(error)
+            }
+        };
+
+        on_progress(TranslationProgressEvent {
+            index,
+            completed: index + 1,
+            total,
+            success: error.is_none(),
+            error,
+        });
+    }
+
+    BatchTranslationResult {
+        total_count: total,
+        success_count: successes.len(),
+        failed_count: failures.len(),
+        successes,
+        failures,
+    }
+}
+
 /// 批次翻譯多個角色描述
+///
+/// 每完成一筆發送 `translation-progress` 事件；單筆失敗不會中斷整批，
+/// 回傳結果中成功與失敗分開列出（以原始索引對應輸入）。
 #[tauri::command]
 pub async fn batch_translate_descriptions(
+    app: AppHandle,
     descriptions: Vec<String>,
     character_names: Option<Vec<String>>,
     target_style: String,
     quality_level: String,
-) -> Result<Value, String> {
+    locked_terms: Option<HashMap<String, String>>,
+) -> Result<BatchTranslationResult<TranslationResult>, String> {
     log::info!("[TranslationCommand] 批次翻譯 {} 個描述", descriptions.len());
     
-    let db_connection = create_connection().map_err(|e| format!("資料庫連接失敗: {}", e))?;
-    let db_arc = Arc::new(Mutex::new(db_connection));
+    let db_arc = get_db().map_err(|e| format!("資料庫連接失敗: {}", e))?;
     
     let vocabulary_db = VocabularyDatabase::new(db_arc);
     let translation_engine = TranslationEngine::new(vocabulary_db)
@@ -266,51 +740,79 @@ pub async fn batch_translate_descriptions(
         _ => QualityLevel::Standard,
     };
 
-    let mut results = Vec::new();
-    let mut failed_count = 0;
+    let locked_terms = locked_terms.unwrap_or_default();
+    let result = translate_in_batch(
+        &descriptions,
+        character_names.as_deref(),
+        |description, character_name| {
+            translation_engine.translate(TranslationRequest {
+                chinese_description: description.to_string(),
+                character_name,
+                target_style: translation_style.clone(),
+                quality_level: quality.clone(),
+                context_hints: Vec::new(),
+                preserve_original: false,
+                locked_terms: locked_terms.clone(),
+            })
+        },
+        |event| {
+            if let Err(e) = app.emit("translation-progress", event) {
+                log::warn!("[TranslationCommand] 發送翻譯進度事件失敗: {}", e);
+            }
+        },
+    );
 
-    for (index, description) in descriptions.iter().enumerate() {
-        let character_name = character_names.as_ref()
-            .and_then(|names| names.get(index))
-            .cloned();
-
-        let request = TranslationRequest {
-            chinese_description: description.clone(),
-            character_name,
-            target_style: translation_style.clone(),
-            quality_level: quality.clone(),
-            context_hints: Vec::new(),
-            preserve_original: false,
-        };
+    log::info!("[TranslationCommand] 批次翻譯完成，成功: {}, 失敗: {}", 
+               result.success_count, result.failed_count);
 
-        match translation_engine.translate(request) {
-            Ok(result) => {
-                results.push(serde_json::json!({
-                    "index": index,
-                    "success": true,
-                    "result": result
-                }));
-            },
-            Err(e) => {
-                failed_count += 1;
-                log::error!("[TranslationCommand] 描述 {} 翻譯失敗: {:?}", index, e);
-                results.push(serde_json::json!({
-                    "index": index,
-                    "success": false,
-                    "error": format!("{:?}", e)
-                }));
-            }
-        }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vocabulary_csv_reports_bad_rows() {
+        let text = "chinese_term,english_term,category,priority,synonyms\n\
+                    星空髮,galaxy hair,hair,7,starry hair|cosmic hair\n\
+                    壞資料,bad,hair,高,\n";
+        let mut errors = Vec::new();
+        let records = parse_vocabulary_csv(text, &mut errors);
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].1.priority, Some(7));
+        assert_eq!(records[0].1.synonyms, vec!["starry hair", "cosmic hair"]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].row, 3);
     }
 
-    log::info!("[TranslationCommand] 批次翻譯完成，成功: {}, 失敗: {}", 
-               descriptions.len() - failed_count, failed_count);
+    #[test]
+    fn test_batch_translation_continues_past_failures() {
+        let descriptions = vec!["銀髮少女".to_string(), String::new(), "黑衣劍士".to_string()];
+        let names = vec!["艾莉".to_string(), "無名".to_string()];
+        let mut events = Vec::new();
 
-    Ok(serde_json::json!({
-        "success": true,
-        "results": results,
-        "total_count": descriptions.len(),
-        "success_count": descriptions.len() - failed_count,
-        "failed_count": failed_count
-    }))
-}
\ No newline at end of file
+        let result = translate_in_batch(
+            &descriptions,
+            Some(&names),
+            |description, _| {
+                if description.is_empty() {
+                    Err("描述不能為空")
+                } else {
+                    Ok(description.chars().count())
+                }
+            },
+            |event| events.push(event),
+        );
+
+        assert_eq!((result.total_count, result.success_count, result.failed_count), (3, 2, 1));
+        assert_eq!(result.successes[0].character_name.as_deref(), Some("艾莉"));
+        assert_eq!(result.successes[1].index, 2);
+        assert_eq!(result.successes[1].character_name, None);
+        assert_eq!(result.failures[0].index, 1);
+        assert_eq!(result.failures[0].error, "描述不能為空");
+        assert_eq!(events.iter().map(|e| (e.completed, e.success)).collect::<Vec<_>>(), vec![(1, true), (2, false), (3, true)]);
+        assert!(events.iter().all(|e| e.total == 3));
+    }
+}
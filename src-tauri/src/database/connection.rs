@@ -62,21 +62,42 @@ pub fn get_db_path() -> Result<PathBuf> {
     }
 }
 
+/// 啟用外鍵約束並確認設定已生效
+///
+/// SQLite 的外鍵約束是逐連線設定的，若未生效，刪除專案會留下孤兒章節與插畫記錄。
+pub fn enable_foreign_keys(conn: &Connection) -> Result<()> {
+    conn.pragma_update(None, "foreign_keys", true)?;
+    
+    let enabled: bool = conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?;
+    if !enabled {
+        return Err(anyhow::anyhow!("無法啟用外鍵約束，請確認 SQLite 編譯時未停用外鍵支援"));
+    }
+    Ok(())
+}
+
 /// 創建資料庫連接
 pub fn create_connection() -> Result<Connection> {
     let db_path = get_db_path()?;
     
     log::info!("正在連接資料庫: {:?}", db_path);
     
+    open_connection(&db_path)
+}
+
+/// 以應用程式統一的 pragma 設定開啟指定路徑的資料庫
+pub fn open_connection(db_path: &std::path::Path) -> Result<Connection> {
     let conn = Connection::open_with_flags(
-        &db_path,
+        db_path,
         OpenFlags::SQLITE_OPEN_READ_WRITE 
             | OpenFlags::SQLITE_OPEN_CREATE
             | OpenFlags::SQLITE_OPEN_FULL_MUTEX,
     )?;
     
-    // 啟用外鍵約束
-    conn.pragma_update(None, "foreign_keys", &true)?;
+    // 啟用外鍵約束（ON DELETE CASCADE / SET NULL 依賴此設定）
+    enable_foreign_keys(&conn)?;
+    
+    // 多個連接同時寫入時等待鎖釋放，而非立即回傳 "database is locked"
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
     
     // === 性能優化設定 ===
     
@@ -104,4 +125,31 @@ pub fn create_connection() -> Result<Connection> {
     log::info!("資料庫連接成功，已啟用性能優化");
     
     Ok(conn)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    #[test]
+    fn test_deleting_project_cascades() {
+        let conn = migrated_conn_with_project();
+        enable_foreign_keys(&conn).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO chapters (id, project_id, title) VALUES ('c1', 'p1', '第一章');
+             INSERT INTO illustration_generations (id, project_id, scene_description, translated_prompt, api_model)
+                 VALUES ('i1', 'p1', '森林', 'forest', 'imagen');
+             INSERT INTO pollinations_generations (id, project_id, original_prompt)
+                 VALUES ('g1', 'p1', 'forest');",
+        ).unwrap();
+
+        conn.execute("DELETE FROM projects WHERE id = 'p1'", []).unwrap();
+
+        let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+        assert_eq!(count("SELECT COUNT(*) FROM chapters"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM illustration_generations"), 0);
+        assert_eq!(count("SELECT COUNT(*) FROM pollinations_generations WHERE project_id IS NULL"), 1);
+    }
+}
@@ -1,7 +1,67 @@
 use anyhow::Result;
 use rusqlite::{Connection, params};
+use serde::Serialize;
 
-const DB_VERSION: i32 = 17;
+const DB_VERSION: i32 = 30;
+
+/// 各版本遷移的說明（新增遷移時需同步更新）
+const MIGRATION_DESCRIPTIONS: &[(i32, &str)] = &[
+    (1, "建立基本表格"),
+    (2, "新增或修改欄位（保留）"),
+    (3, "重建 templates 表：template_data 欄位重命名為 settings"),
+    (4, "重建 chapters 表：order_num 欄位重命名為 order_index"),
+    (5, "重建 characters 和 character_relationships 表結構"),
+    (6, "確保 settings 表存在"),
+    (7, "新增 AI 生成歷史記錄表"),
+    (8, "新增小說篇幅類型和章節編號功能"),
+    (9, "新增多 AI 提供者支援"),
+    (10, "添加 EPUB 導出記錄表"),
+    (11, "Phase 2 智能創作分析功能"),
+    (12, "Phase 3 AI 插畫生成：角色視覺一致性系統"),
+    (13, "資料庫性能優化索引"),
+    (14, "添加章節 metadata 支援"),
+    (15, "添加 Pollinations.AI 插畫生成歷史記錄表"),
+    (16, "添加章節狀態管理功能"),
+    (17, "添加圖片刪除管理功能"),
+    (18, "AI 生成歷史全文搜尋（FTS5）"),
+    (19, "添加 PDF 導出記錄表"),
+    (20, "添加專案封存功能"),
+    (21, "添加章節版本歷史表"),
+    (22, "角色關係支援雙向配對（pair_id）"),
+    (23, "添加角色章節出場記錄表"),
+    (24, "詞彙庫英文詞彙索引（反向查詢）"),
+    (25, "添加提示詞模板表"),
+    (26, "添加專案層級設定表"),
+    (27, "插畫生成記錄新增縮圖路徑"),
+    (28, "添加作品包導出記錄表"),
+    (29, "正規化專案小說篇幅（novel_length）"),
+    (30, "添加章節 HTML 轉換快取表"),
+];
+
+/// 待執行的遷移
+#[derive(Debug, Serialize)]
+pub struct PendingMigration {
+    pub version: i32,
+    pub description: String,
+}
+
+/// 遷移試跑結果（在臨時副本上執行，不影響原資料庫）
+#[derive(Debug, Serialize)]
+pub struct MigrationDryRun {
+    pub success: bool,
+    pub resulting_version: Option<i32>,
+    pub new_tables: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// 資料庫遷移狀態
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+    pub current_version: i32,
+    pub target_version: i32,
+    pub pending: Vec<PendingMigration>,
+    pub dry_run: Option<MigrationDryRun>,
+}
 
 /// 執行資料庫遷移
 pub fn run_migrations(conn: &Connection) -> Result<()> {
@@ -124,6 +184,84 @@ pub fn run_migrations(conn: &Connection) -> Result<()> {
             log::info!("遷移到版本 17 完成");
         }
         
+        if current_version < 18 {
+            apply_migration_v18(conn)?;
+            update_version(conn, 18)?;
+            log::info!("遷移到版本 18 完成");
+        }
+        
+        if current_version < 19 {
+            apply_migration_v19(conn)?;
+            update_version(conn, 19)?;
+            log::info!("遷移到版本 19 完成");
+        }
+        
+        if current_version < 20 {
+            apply_migration_v20(conn)?;
+            update_version(conn, 20)?;
+            log::info!("遷移到版本 20 完成");
+        }
+        
+        if current_version < 21 {
+            apply_migration_v21(conn)?;
+            update_version(conn, 21)?;
+            log::info!("遷移到版本 21 完成");
+        }
+        
+        if current_version < 22 {
+            apply_migration_v22(conn)?;
+            update_version(conn, 22)?;
+            log::info!("遷移到版本 22 完成");
+        }
+        
+        if current_version < 23 {
+            apply_migration_v23(conn)?;
+            update_version(conn, 23)?;
+            log::info!("遷移到版本 23 完成");
+        }
+        
+        if current_version < 24 {
+            apply_migration_v24(conn)?;
+            update_version(conn, 24)?;
+            log::info!("遷移到版本 24 完成");
+        }
+        
+        if current_version < 25 {
+            apply_migration_v25(conn)?;
+            update_version(conn, 25)?;
+            log::info!("遷移到版本 25 完成");
+        }
+        
+        if current_version < 26 {
+            apply_migration_v26(conn)?;
+            update_version(conn, 26)?;
+            log::info!("遷移到版本 26 完成");
+        }
+        
+        if current_version < 27 {
+            apply_migration_v27(conn)?;
+            update_version(conn, 27)?;
+            log::info!("遷移到版本 27 完成");
+        }
+        
+        if current_version < 28 {
+            apply_migration_v28(conn)?;
+            update_version(conn, 28)?;
+            log::info!("遷移到版本 28 完成");
+        }
+        
+        if current_version < 29 {
+            apply_migration_v29(conn)?;
+            update_version(conn, 29)?;
+            log::info!("遷移到版本 29 完成");
+        }
+        
+        if current_version < 30 {
+            apply_migration_v30(conn)?;
+            update_version(conn, 30)?;
+            log::info!("遷移到版本 30 完成");
+        }
+        
         log::info!("資料庫遷移完成");
     } else {
         log::info!("資料庫已是最新版本");
@@ -132,6 +270,71 @@ pub fn run_migrations(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
+/// 獲取資料庫遷移狀態（目前版本、目標版本與待執行的遷移）
+pub fn get_migration_status(conn: &Connection) -> Result<MigrationStatus> {
+    let current_version = get_current_version(conn)?;
+    let pending = (current_version + 1..=DB_VERSION)
+        .map(|version| PendingMigration {
+            version,
+            description: MIGRATION_DESCRIPTIONS
+                .iter()
+                .find(|(v, _)| *v == version)
+                .map(|(_, description)| description.to_string())
+                .unwrap_or_default(),
+        })
+        .collect();
+    
+    Ok(MigrationStatus {
+        current_version,
+        target_version: DB_VERSION,
+        pending,
+        dry_run: None,
+    })
+}
+
+/// 在資料庫的臨時副本上試跑所有待執行的遷移，原資料庫不會被修改
+///
+/// 各遷移步驟的細節會照常寫入日誌，方便在正式升級前檢查重建表格等高風險步驟。
+pub fn dry_run_migrations(conn: &Connection) -> Result<MigrationDryRun> {
+    let temp_dir = tempfile::tempdir()?;
+    let copy_path = temp_dir.path().join("migration-dry-run.db");
+    
+    // VACUUM INTO 產生包含 WAL 內容的一致副本
+    conn.execute("VACUUM INTO ?1", [copy_path.to_string_lossy().to_string()])?;
+    
+    let copy = Connection::open(&copy_path)?;
+    let list_tables = |conn: &Connection| -> Result<Vec<String>> {
+        let mut stmt = conn.prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")?;
+        let tables = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(tables)
+    };
+    let tables_before = list_tables(&copy)?;
+    
+    log::info!("🧪 遷移試跑開始（臨時副本: {:?}）", copy_path);
+    let result = run_migrations(&copy);
+    
+    let dry_run = match result {
+        Ok(()) => MigrationDryRun {
+            success: true,
+            resulting_version: Some(get_current_version(&copy)?),
+            new_tables: list_tables(&copy)?
+                .into_iter()
+                .filter(|table| !tables_before.contains(table))
+                .collect(),
+            error: None,
+        },
+        Err(e) => MigrationDryRun {
+            success: false,
+            resulting_version: get_current_version(&copy).ok(),
+            new_tables: Vec::new(),
+            error: Some(e.to_string()),
+        },
+    };
+    log::info!("🧪 遷移試跑結束：{}", if dry_run.success { "成功" } else { "失敗" });
+    
+    Ok(dry_run)
+}
+
 /// 獲取當前資料庫版本
 fn get_current_version(conn: &Connection) -> Result<i32> {
     match conn.prepare("SELECT version FROM db_version ORDER BY version DESC LIMIT 1") {
@@ -2041,4 +2244,434 @@ pub fn apply_migration_v17(conn: &Connection) -> Result<()> {
     log::info!("版本 17 遷移完成：圖片刪除管理功能已準備就緒");
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 版本 18：AI 生成歷史全文搜尋（FTS5）
+pub fn apply_migration_v18(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 18 遷移：添加 AI 生成歷史全文搜尋");
+    
+    // 使用 trigram 分詞器，中文不需斷詞即可進行子字串搜尋
+    conn.execute(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS ai_generation_history_fts USING fts5(
+            prompt,
+            generated_text,
+            content='ai_generation_history',
+            content_rowid='rowid',
+            tokenize='trigram'
+        )",
+        [],
+    )?;
+    
+    // 同步觸發器：新增、刪除、更新時維護索引
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS ai_history_fts_insert AFTER INSERT ON ai_generation_history BEGIN
+            INSERT INTO ai_generation_history_fts (rowid, prompt, generated_text)
+            VALUES (new.rowid, new.prompt, new.generated_text);
+        END",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS ai_history_fts_delete AFTER DELETE ON ai_generation_history BEGIN
+            INSERT INTO ai_generation_history_fts (ai_generation_history_fts, rowid, prompt, generated_text)
+            VALUES ('delete', old.rowid, old.prompt, old.generated_text);
+        END",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE TRIGGER IF NOT EXISTS ai_history_fts_update AFTER UPDATE OF prompt, generated_text ON ai_generation_history BEGIN
+            INSERT INTO ai_generation_history_fts (ai_generation_history_fts, rowid, prompt, generated_text)
+            VALUES ('delete', old.rowid, old.prompt, old.generated_text);
+            INSERT INTO ai_generation_history_fts (rowid, prompt, generated_text)
+            VALUES (new.rowid, new.prompt, new.generated_text);
+        END",
+        [],
+    )?;
+    
+    // 為既有歷史記錄建立索引
+    conn.execute(
+        "INSERT INTO ai_generation_history_fts (ai_generation_history_fts) VALUES ('rebuild')",
+        [],
+    )?;
+    
+    log::info!("版本 18 遷移完成：AI 生成歷史全文搜尋已準備就緒");
+    
+    Ok(())
+}
+
+/// 版本 19: 添加 PDF 導出記錄表
+pub fn apply_migration_v19(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 19 遷移：添加 PDF 導出記錄表");
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS pdf_exports (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            file_path TEXT NOT NULL,
+            file_size INTEGER NOT NULL,
+            page_count INTEGER,
+            chapter_count INTEGER NOT NULL,
+            format_settings TEXT NOT NULL, -- JSON 字符串存儲格式選項
+            export_status TEXT NOT NULL DEFAULT 'completed', -- completed, failed, processing
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            downloaded_at TIMESTAMP,
+            FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_pdf_exports_project_id ON pdf_exports (project_id)",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_pdf_exports_created_at ON pdf_exports (created_at DESC)",
+        [],
+    )?;
+    
+    log::info!("版本 19 遷移完成：PDF 導出記錄表和索引創建完成");
+    
+    Ok(())
+}
+
+/// 版本 20: 添加專案封存功能
+pub fn apply_migration_v20(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 20 遷移：添加專案封存功能");
+    
+    let has_is_archived: bool = conn
+        .prepare("PRAGMA table_info(projects)")?
+        .query_map([], |row| {
+            let column_name: String = row.get(1)?;
+            Ok(column_name)
+        })?
+        .any(|result| match result {
+            Ok(name) => name == "is_archived",
+            Err(_) => false,
+        });
+    
+    if !has_is_archived {
+        conn.execute(
+            "ALTER TABLE projects ADD COLUMN is_archived INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+        log::info!("成功添加 is_archived 欄位到 projects 表");
+    }
+    
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_projects_is_archived ON projects (is_archived)",
+        [],
+    )?;
+    
+    log::info!("版本 20 遷移完成：專案封存功能已準備就緒");
+    
+    Ok(())
+}
+
+/// 版本 21: 添加章節版本歷史表
+pub fn apply_migration_v21(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 21 遷移：添加章節版本歷史表");
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chapter_versions (
+            id TEXT PRIMARY KEY,
+            chapter_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            content TEXT,
+            plain_text TEXT NOT NULL DEFAULT '', -- 純文字副本，方便比對差異
+            metadata TEXT,
+            source TEXT NOT NULL DEFAULT 'update', -- update, restore
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (chapter_id) REFERENCES chapters (id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_chapter_versions_chapter_id ON chapter_versions (chapter_id, created_at DESC)",
+        [],
+    )?;
+    
+    log::info!("版本 21 遷移完成：章節版本歷史表和索引創建完成");
+    
+    Ok(())
+}
+
+/// 版本 22: 角色關係支援雙向配對
+pub fn apply_migration_v22(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 22 遷移：角色關係支援雙向配對");
+    
+    let has_pair_id: bool = conn
+        .prepare("PRAGMA table_info(character_relationships)")?
+        .query_map([], |row| {
+            let column_name: String = row.get(1)?;
+            Ok(column_name)
+        })?
+        .any(|result| match result {
+            Ok(name) => name == "pair_id",
+            Err(_) => false,
+        });
+    
+    if !has_pair_id {
+        conn.execute(
+            "ALTER TABLE character_relationships ADD COLUMN pair_id TEXT",
+            [],
+        )?;
+        log::info!("成功添加 pair_id 欄位到 character_relationships 表");
+    }
+    
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_character_relationships_pair_id ON character_relationships (pair_id)",
+        [],
+    )?;
+    
+    log::info!("版本 22 遷移完成：雙向關係配對已準備就緒");
+    
+    Ok(())
+}
+
+/// 版本 23: 添加角色章節出場記錄表
+pub fn apply_migration_v23(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 23 遷移：添加角色章節出場記錄表");
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS character_chapter_mentions (
+            character_id TEXT NOT NULL,
+            chapter_id TEXT NOT NULL,
+            mention_count INTEGER NOT NULL DEFAULT 0,
+            first_position INTEGER, -- 第一次出現的字符位置
+            scanned_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (character_id, chapter_id),
+            FOREIGN KEY (character_id) REFERENCES characters (id) ON DELETE CASCADE,
+            FOREIGN KEY (chapter_id) REFERENCES chapters (id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_character_chapter_mentions_chapter_id ON character_chapter_mentions (chapter_id)",
+        [],
+    )?;
+    
+    log::info!("版本 23 遷移完成：角色章節出場記錄表和索引創建完成");
+    
+    Ok(())
+}
+
+/// 版本 24: 詞彙庫英文詞彙索引（反向查詢）
+///
+/// 詞彙表由翻譯服務在首次使用時建立，尚未建立時跳過（服務初始化時會一併建立索引）。
+pub fn apply_migration_v24(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 24 遷移：詞彙庫英文詞彙索引");
+    
+    let table_exists: bool = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'vocabulary_entries'",
+        [],
+        |row| row.get::<_, i64>(0),
+    )? > 0;
+    
+    if table_exists {
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_vocabulary_english ON vocabulary_entries (english_term COLLATE NOCASE)",
+            [],
+        )?;
+        log::info!("版本 24 遷移完成：英文詞彙索引創建完成");
+    } else {
+        log::info!("版本 24 遷移完成：詞彙表尚未建立，索引將由翻譯服務建立");
+    }
+    
+    Ok(())
+}
+
+/// 版本 25: 添加提示詞模板表
+pub fn apply_migration_v25(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 25 遷移：添加提示詞模板表");
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS prompt_templates (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            category TEXT NOT NULL,      -- JSON: TemplateCategory
+            template_data TEXT NOT NULL, -- JSON: 完整模板內容
+            is_builtin INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_prompt_templates_category ON prompt_templates (category)",
+        [],
+    )?;
+    
+    log::info!("版本 25 遷移完成：提示詞模板表和索引創建完成");
+    
+    Ok(())
+}
+
+/// 版本 26: 添加專案層級設定表（未設定的鍵回退到全域 settings）
+pub fn apply_migration_v26(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 26 遷移：添加專案層級設定表");
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS project_settings (
+            project_id TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (project_id, key),
+            FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    
+    log::info!("版本 26 遷移完成：專案層級設定表創建完成");
+    
+    Ok(())
+}
+
+/// 版本 27: 插畫生成記錄新增縮圖路徑（舊記錄於讀取時補產生）
+pub fn apply_migration_v27(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 27 遷移：插畫生成記錄新增縮圖路徑");
+    
+    let has_thumbnail_path: bool = conn
+        .prepare("PRAGMA table_info(pollinations_generations)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .any(|result| matches!(result.as_deref(), Ok("thumbnail_path")));
+    
+    if !has_thumbnail_path {
+        conn.execute(
+            "ALTER TABLE pollinations_generations ADD COLUMN thumbnail_path TEXT",
+            [],
+        )?;
+    }
+    
+    log::info!("版本 27 遷移完成：thumbnail_path 欄位已就緒");
+    
+    Ok(())
+}
+
+/// 版本 28: 添加作品包導出記錄表（EPUB/PDF/插畫打包成單一 ZIP）
+pub fn apply_migration_v28(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 28 遷移：添加作品包導出記錄表");
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS project_bundles (
+            id TEXT PRIMARY KEY,
+            project_id TEXT NOT NULL,
+            file_path TEXT NOT NULL,
+            file_size INTEGER NOT NULL,
+            formats TEXT NOT NULL, -- JSON 格式的導出格式陣列
+            illustration_count INTEGER NOT NULL DEFAULT 0,
+            manifest TEXT NOT NULL, -- 壓縮檔內 manifest.json 的內容
+            created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_project_bundles_project_id ON project_bundles (project_id, created_at DESC)",
+        [],
+    )?;
+    
+    log::info!("版本 28 遷移完成：作品包導出記錄表創建完成");
+    
+    Ok(())
+}
+
+/// 版本 29: 正規化專案小說篇幅（大小寫統一，未知值或 NULL 改為 medium）
+pub fn apply_migration_v29(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 29 遷移：正規化專案小說篇幅");
+    
+    conn.execute(
+        "UPDATE projects SET novel_length = LOWER(TRIM(novel_length))
+         WHERE novel_length IS NOT NULL AND novel_length != LOWER(TRIM(novel_length))",
+        [],
+    )?;
+    
+    let coerced = conn.execute(
+        "UPDATE projects SET novel_length = 'medium'
+         WHERE novel_length IS NULL OR novel_length NOT IN ('short', 'medium', 'long', 'epic')",
+        [],
+    )?;
+    
+    log::info!("版本 29 遷移完成：{} 個專案的小說篇幅改為 medium", coerced);
+    
+    Ok(())
+}
+
+/// 版本 30: 添加章節 HTML 轉換快取表（EPUB 匯出只重新轉換內容有變更的章節）
+pub fn apply_migration_v30(conn: &Connection) -> Result<()> {
+    log::info!("執行版本 30 遷移：添加章節 HTML 轉換快取表");
+    
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS chapter_html_cache (
+            chapter_id TEXT PRIMARY KEY,
+            content_hash TEXT NOT NULL,
+            html TEXT NOT NULL,
+            updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY (chapter_id) REFERENCES chapters (id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+    
+    log::info!("版本 30 遷移完成：章節 HTML 轉換快取表創建完成");
+    
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_migration_has_description() {
+        for version in 1..=DB_VERSION {
+            assert!(
+                MIGRATION_DESCRIPTIONS.iter().any(|(v, _)| *v == version),
+                "版本 {} 缺少遷移說明",
+                version
+            );
+        }
+    }
+
+    #[test]
+    fn test_dry_run_does_not_touch_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let conn = Connection::open(dir.path().join("original.db")).unwrap();
+
+        let status = get_migration_status(&conn).unwrap();
+        assert_eq!(status.current_version, 0);
+        assert_eq!(status.pending.len(), DB_VERSION as usize);
+
+        let dry_run = dry_run_migrations(&conn).unwrap();
+        assert!(dry_run.success, "{:?}", dry_run.error);
+        assert_eq!(dry_run.resulting_version, Some(DB_VERSION));
+        assert!(dry_run.new_tables.contains(&"projects".to_string()));
+
+        assert_eq!(get_migration_status(&conn).unwrap().current_version, 0);
+    }
+
+    #[test]
+    fn test_v29_coerces_unknown_novel_lengths() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, novel_length) VALUES ('a', '甲', ' Long ');
+             INSERT INTO projects (id, name, novel_length) VALUES ('b', '乙', 'novella');
+             INSERT INTO projects (id, name, novel_length) VALUES ('c', '丙', NULL);
+             INSERT INTO projects (id, name, novel_length) VALUES ('d', '丁', 'epic');",
+        ).unwrap();
+
+        apply_migration_v29(&conn).unwrap();
+        let lengths: Vec<String> = conn
+            .prepare("SELECT novel_length FROM projects ORDER BY id").unwrap()
+            .query_map([], |row| row.get(0)).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(lengths, vec!["long", "medium", "medium", "epic"]);
+    }
+}
@@ -1,14 +1,22 @@
 pub mod connection;
 pub mod migrations;
 pub mod models;
+pub mod pool;
+#[cfg(test)]
+pub mod test_support;
 
 use anyhow::Result;
 use rusqlite::Connection;
 use std::sync::{Arc, Mutex};
 
-// 全域資料庫連接
+pub use pool::PooledConnection;
+
+// 全域資料庫連接（供需要長期持有連接的服務使用）
 static DB_INSTANCE: std::sync::OnceLock<Arc<Mutex<Connection>>> = std::sync::OnceLock::new();
 
+// 全域資料庫連接池（供命令使用）
+static DB_POOL: std::sync::OnceLock<pool::ConnectionPool> = std::sync::OnceLock::new();
+
 /// 初始化資料庫連接
 pub fn init_database() -> Result<()> {
     let db = connection::create_connection()?;
@@ -16,6 +24,8 @@ pub fn init_database() -> Result<()> {
     
     DB_INSTANCE.set(Arc::new(Mutex::new(db)))
         .map_err(|_| anyhow::anyhow!("Database already initialized"))?;
+    DB_POOL.set(pool::ConnectionPool::new(pool::DEFAULT_POOL_SIZE, connection::create_connection))
+        .map_err(|_| anyhow::anyhow!("Database already initialized"))?;
     
     log::info!("資料庫初始化完成");
     Ok(())
@@ -26,4 +36,21 @@ pub fn get_db() -> Result<Arc<Mutex<Connection>>> {
     DB_INSTANCE.get()
         .ok_or_else(|| anyhow::anyhow!("Database not initialized"))
         .map(|db| db.clone())
-}
\ No newline at end of file
+}
+
+/// 從連接池借出資料庫連接，離開作用域時自動歸還
+///
+/// 各命令使用獨立連接，長時間的匯出或批次作業不會阻塞其他命令；
+/// 持有連接的命令 panic 時，未完成的交易會在歸還時回滾，不影響之後的命令。
+pub fn get_db_conn() -> Result<PooledConnection<'static>> {
+    DB_POOL.get()
+        .ok_or_else(|| anyhow::anyhow!("Database not initialized"))?
+        .get()
+}
+
+/// 資料庫檔案被取代後（如從備份還原），關閉連接池中的舊連接
+pub fn reset_db_pool() {
+    if let Some(pool) = DB_POOL.get() {
+        pool.reset();
+    }
+}
@@ -8,9 +8,11 @@ pub struct Project {
     pub description: Option<String>,
     pub r#type: Option<String>, // 使用 r#type 因為 type 是 Rust 關鍵字
     pub settings: Option<String>, // JSON 字串
-    pub novel_length: Option<String>, // 小說篇幅: short, medium, long
+    pub novel_length: Option<String>, // 小說篇幅: short, medium, long, epic
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub is_archived: bool, // 是否已封存（封存的專案預設不顯示）
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +47,8 @@ pub struct CharacterRelationship {
     pub to_character_id: String,
     pub relationship_type: String,
     pub description: Option<String>,
+    #[serde(default)]
+    pub pair_id: Option<String>, // 雙向關係的配對 ID（兩筆記錄共用）
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -57,6 +61,39 @@ pub struct Setting {
     pub updated_at: DateTime<Utc>,
 }
 
+/// 小說篇幅（projects.novel_length 允許的值）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NovelLength {
+    Short,
+    #[default]
+    Medium,
+    Long,
+    Epic,
+}
+
+impl NovelLength {
+    pub const ALL: [NovelLength; 4] = [Self::Short, Self::Medium, Self::Long, Self::Epic];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Short => "short",
+            Self::Medium => "medium",
+            Self::Long => "long",
+            Self::Epic => "epic",
+        }
+    }
+
+    /// 解析篇幅字串（忽略大小寫與前後空白），不在允許範圍內時回傳錯誤
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let normalized = value.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|length| length.as_str() == normalized)
+            .ok_or_else(|| format!("無效的小說篇幅: {}（可用: short、medium、long、epic）", value))
+    }
+}
+
 // 新增專案的請求結構
 #[derive(Debug, Deserialize)]
 pub struct CreateProjectRequest {
@@ -65,7 +102,7 @@ pub struct CreateProjectRequest {
     #[serde(rename = "type")]
     pub r#type: Option<String>,
     pub settings: Option<String>,
-    pub novel_length: Option<String>, // 小說篇幅: short, medium, long
+    pub novel_length: Option<String>, // 小說篇幅: short, medium, long, epic（未提供時為 medium）
 }
 
 // 更新專案的請求結構
@@ -77,7 +114,7 @@ pub struct UpdateProjectRequest {
     #[serde(rename = "type")]
     pub r#type: Option<String>,
     pub settings: Option<String>,
-    pub novel_length: Option<String>, // 小說篇幅: short, medium, long
+    pub novel_length: Option<String>, // 小說篇幅: short, medium, long, epic（未提供時為 medium）
 }
 
 // 新增章節的請求結構
@@ -99,6 +136,9 @@ pub struct UpdateChapterRequest {
     pub order_index: Option<i32>,
     pub chapter_number: Option<i32>, // 章節編號
     pub metadata: Option<String>, // JSON 格式的元數據（包含筆記等）
+    /// 呼叫端讀到的 updated_at；資料庫中的版本較新時拒絕寫入（樂觀並行控制）
+    #[serde(default)]
+    pub expected_updated_at: Option<DateTime<Utc>>,
 }
 
 // 新增角色的請求結構
@@ -161,11 +201,47 @@ pub struct CreateAIHistoryRequest {
 pub struct QueryAIHistoryRequest {
     pub project_id: Option<String>,
     pub chapter_id: Option<String>,
+    pub provider_id: Option<String>,
+    pub from_date: Option<String>, // RFC 3339 或 YYYY-MM-DD
+    pub to_date: Option<String>,   // RFC 3339 或 YYYY-MM-DD（含當日）
     pub selected_only: Option<bool>,
     pub limit: Option<i32>,
     pub offset: Option<i32>,
 }
 
+// AI 生成歷史全文搜尋結果
+#[derive(Debug, Clone, Serialize)]
+pub struct AIHistorySearchResult {
+    pub history: AIGenerationHistory,
+    pub prompt_snippet: String,         // 以 <mark> 標示命中位置
+    pub generated_text_snippet: String, // 以 <mark> 標示命中位置
+}
+
+// AI 生成歷史統計
+#[derive(Debug, Clone, Serialize)]
+pub struct AIHistoryStats {
+    pub total_generations: i64,
+    pub selected_count: i64,
+    pub acceptance_rate: f64, // selected / total (0-1)
+    pub avg_language_purity: Option<f64>,
+    pub avg_generation_time_ms: Option<f64>,
+    pub total_tokens: i64,
+    pub by_model: Vec<AIHistoryModelStats>,
+}
+
+// 單一模型的 AI 生成統計
+#[derive(Debug, Clone, Serialize)]
+pub struct AIHistoryModelStats {
+    pub provider_id: Option<String>,
+    pub model: String,
+    pub generations: i64,
+    pub selected_count: i64,
+    pub acceptance_rate: f64,
+    pub avg_language_purity: Option<f64>,
+    pub avg_generation_time_ms: Option<f64>,
+    pub total_tokens: i64,
+}
+
 // AI 提供者模型
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIProvider {
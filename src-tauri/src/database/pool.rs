@@ -0,0 +1,229 @@
+use anyhow::Result;
+use rusqlite::Connection;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// 預設連接數（WAL 模式下讀取可並行，寫入仍由 SQLite 依序處理）
+pub const DEFAULT_POOL_SIZE: usize = 4;
+
+/// 等待可用連接的預設逾時
+const DEFAULT_CHECKOUT_TIMEOUT: Duration = Duration::from_secs(30);
+
+type ConnectionFactory = Box<dyn Fn() -> Result<Connection> + Send + Sync>;
+
+struct PoolState {
+    idle: Vec<Connection>,
+    open: usize,
+    generation: u64,
+}
+
+/// SQLite 連接池
+///
+/// 連接在需要時才建立，最多 `size` 個；歸還時若仍有未完成的交易會先回滾，
+/// 持有連接的命令 panic 也不會影響之後的命令。
+pub struct ConnectionPool {
+    state: Mutex<PoolState>,
+    available: Condvar,
+    factory: ConnectionFactory,
+    size: usize,
+    checkout_timeout: Duration,
+}
+
+impl ConnectionPool {
+    pub fn new<F>(size: usize, factory: F) -> Self
+    where
+        F: Fn() -> Result<Connection> + Send + Sync + 'static,
+    {
+        Self {
+            state: Mutex::new(PoolState {
+                idle: Vec::new(),
+                open: 0,
+                generation: 0,
+            }),
+            available: Condvar::new(),
+            factory: Box::new(factory),
+            size: size.max(1),
+            checkout_timeout: DEFAULT_CHECKOUT_TIMEOUT,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn with_checkout_timeout(mut self, timeout: Duration) -> Self {
+        self.checkout_timeout = timeout;
+        self
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, PoolState> {
+        // 狀態只在短暫操作中持有，中毒時資料仍一致，直接沿用
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 取得一個連接，連接數已滿時等待其他命令歸還
+    pub fn get(&self) -> Result<PooledConnection<'_>> {
+        let deadline = Instant::now() + self.checkout_timeout;
+        let mut state = self.lock_state();
+
+        loop {
+            if let Some(conn) = state.idle.pop() {
+                return Ok(PooledConnection::new(self, conn, state.generation));
+            }
+
+            if state.open < self.size {
+                state.open += 1;
+                let generation = state.generation;
+                drop(state);
+
+                return match (self.factory)() {
+                    Ok(conn) => Ok(PooledConnection::new(self, conn, generation)),
+                    Err(e) => {
+                        self.lock_state().open -= 1;
+                        self.available.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(anyhow::anyhow!("等待資料庫連接逾時（{} 個連接皆在使用中）", self.size));
+            }
+            state = self
+                .available
+                .wait_timeout(state, deadline - now)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+    }
+
+    /// 關閉所有閒置連接，使用中的連接歸還時也會關閉（資料庫檔案被取代後使用）
+    pub fn reset(&self) {
+        let mut state = self.lock_state();
+        state.generation += 1;
+        let closed = state.idle.len();
+        state.idle.clear();
+        state.open -= closed;
+        drop(state);
+        self.available.notify_all();
+        log::info!("資料庫連接池已重置，關閉 {} 個閒置連接", closed);
+    }
+
+    fn release(&self, conn: Connection, generation: u64) {
+        if !conn.is_autocommit() {
+            log::warn!("歸還的資料庫連接仍有未完成的交易，已回滾");
+            if let Err(e) = conn.execute_batch("ROLLBACK") {
+                log::error!("回滾中斷的交易失敗，關閉此連接: {}", e);
+                self.lock_state().open -= 1;
+                self.available.notify_one();
+                return;
+            }
+        }
+
+        let mut state = self.lock_state();
+        if generation == state.generation {
+            state.idle.push(conn);
+        } else {
+            state.open -= 1;
+        }
+        drop(state);
+        self.available.notify_one();
+    }
+}
+
+/// 從連接池借出的連接，離開作用域時自動歸還
+pub struct PooledConnection<'a> {
+    pool: &'a ConnectionPool,
+    conn: Option<Connection>,
+    generation: u64,
+}
+
+impl<'a> PooledConnection<'a> {
+    fn new(pool: &'a ConnectionPool, conn: Connection, generation: u64) -> Self {
+        Self {
+            pool,
+            conn: Some(conn),
+            generation,
+        }
+    }
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("連接已歸還")
+    }
+}
+
+impl DerefMut for PooledConnection<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().expect("連接已歸還")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.release(conn, self.generation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn file_pool(path: PathBuf, size: usize) -> ConnectionPool {
+        ConnectionPool::new(size, move || {
+            let conn = Connection::open(&path)?;
+            conn.pragma_update(None, "journal_mode", "WAL")?;
+            Ok(conn)
+        })
+        .with_checkout_timeout(Duration::from_millis(100))
+    }
+
+    #[test]
+    fn test_pool_limits_and_reuses_connections() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = file_pool(dir.path().join("pool.db"), 2);
+
+        let first = pool.get().unwrap();
+        let second = pool.get().unwrap();
+        first.execute("CREATE TABLE items (id INTEGER PRIMARY KEY)", []).unwrap();
+        second.execute("INSERT INTO items (id) VALUES (1)", []).unwrap();
+        assert!(pool.get().is_err());
+
+        drop(first);
+        let reused = pool.get().unwrap();
+        let count: i64 = reused.query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+
+        drop(reused);
+        pool.reset();
+        drop(second);
+        assert_eq!(pool.lock_state().open, 0);
+    }
+
+    #[test]
+    fn test_panic_while_holding_connection_does_not_break_pool() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = std::sync::Arc::new(file_pool(dir.path().join("panic.db"), 1));
+        pool.get().unwrap().execute("CREATE TABLE items (id INTEGER PRIMARY KEY)", []).unwrap();
+
+        let panicking_pool = std::sync::Arc::clone(&pool);
+        let result = std::thread::spawn(move || {
+            let conn = panicking_pool.get().unwrap();
+            conn.execute_batch("BEGIN; INSERT INTO items (id) VALUES (1);").unwrap();
+            panic!("模擬命令執行中 panic");
+        })
+        .join();
+        assert!(result.is_err());
+
+        let conn = pool.get().unwrap();
+        assert!(conn.is_autocommit());
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM items", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 0);
+        conn.execute("INSERT INTO items (id) VALUES (2)", []).unwrap();
+    }
+}
@@ -0,0 +1,23 @@
+use rusqlite::Connection;
+
+use super::migrations::run_migrations;
+
+/// 已執行遷移的記憶體資料庫
+pub fn migrated_conn() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    run_migrations(&conn).unwrap();
+    conn
+}
+
+/// 已執行遷移、並建立測試專案 `p1` 的記憶體資料庫
+pub fn migrated_conn_with_project() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    migrate_with_project(&conn);
+    conn
+}
+
+/// 對既有連接執行遷移並建立測試專案 `p1`（供需要檔案資料庫的測試使用）
+pub fn migrate_with_project(conn: &Connection) {
+    run_migrations(conn).unwrap();
+    conn.execute("INSERT INTO projects (id, name) VALUES ('p1', '測試專案')", []).unwrap();
+}
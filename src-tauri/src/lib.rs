@@ -5,13 +5,18 @@ mod utils;
 
 use commands::system::{
     get_app_version, quit_app, reload_app, show_save_dialog, show_open_dialog, open_external,
-    check_for_updates, download_update, install_update, set_auto_update
+    check_for_updates, download_update, install_update, set_auto_update,
+    confirm_update_launch, rollback_update, collect_diagnostics
 };
-use commands::project::{get_all_projects, get_project_by_id, create_project, update_project, delete_project};
-use commands::chapter::{get_chapters_by_project_id, get_chapter_by_id, create_chapter, update_chapter, delete_chapter};
+use commands::project::{get_all_projects, get_project_by_id, create_project, update_project, delete_project, duplicate_project, archive_project, unarchive_project, get_project_writing_stats, get_novel_length_presets};
+#[cfg(debug_assertions)]
+use commands::project::seed_sample_project;
+use commands::chapter::{get_chapters_by_project_id, get_chapter_by_id, create_chapter, update_chapter, delete_chapter, reorder_chapters, normalize_chapter_numbers, list_chapter_versions, restore_chapter_version, find_and_replace_in_project};
 use commands::character::{
-    get_characters_by_project_id, get_character_by_id, create_character, update_character, delete_character,
+    get_characters_by_project_id, get_character_by_id, create_character, update_character, delete_character, validate_character_attributes,
     create_character_relationship, delete_character_relationship, get_character_relationships, clear_character_relationships,
+    get_relationship_types, get_character_relationship_graph, scan_character_mentions,
+    find_duplicate_characters, merge_characters,
 };
 use commands::ai::{
     check_ollama_service, get_service_status, list_models, get_models_info, check_model_availability,
@@ -19,23 +24,31 @@ use commands::ai::{
 };
 use commands::ai_providers::{
     get_ai_providers, create_ai_provider, update_ai_provider, delete_ai_provider,
-    test_ai_provider, generate_ai_text, get_supported_ai_provider_types, get_available_models
+    test_ai_provider, test_all_ai_providers, generate_ai_text, preview_provider_request, get_supported_ai_provider_types, get_available_models
 };
-use commands::context::{build_context, compress_context, get_context_stats, build_separated_context, estimate_separated_context_tokens, analyze_text_purity, enhance_generation_parameters};
-use commands::settings::{get_setting, set_setting, get_all_settings, reset_settings};
-use commands::database::{backup_database, restore_database, run_database_maintenance, get_database_stats, health_check, reindex_database, incremental_vacuum, get_wal_mode_status, set_wal_mode};
-use commands::ai_history::{create_ai_history, query_ai_history, mark_ai_history_selected, delete_ai_history, cleanup_ai_history};
+use commands::context::{build_context, build_append_context, compress_context, get_context_stats, build_separated_context, get_default_system_prompt, estimate_separated_context_tokens, analyze_text_purity, enhance_generation_parameters};
+use commands::settings::{
+    get_setting, set_setting, get_all_settings, reset_settings,
+    get_setting_typed, set_setting_typed, get_setting_registry, export_settings, import_settings,
+    get_project_setting, set_project_setting
+};
+use commands::database::{backup_database, restore_database, run_database_maintenance, get_database_stats, health_check, reindex_database, incremental_vacuum, get_wal_mode_status, set_wal_mode, configure_auto_backup, run_due_backups, inspect_backup, integrity_check, get_migration_status, vacuum_into, checkpoint_wal};
+use commands::ai_history::{create_ai_history, query_ai_history, mark_ai_history_selected, delete_ai_history, cleanup_ai_history, search_ai_history, apply_ai_history_to_chapter, get_ai_history_stats, export_ai_history};
 use commands::epub::{generate_epub, get_epub_exports, delete_epub_export};
+use commands::import::{import_chapters_from_html, import_document, import_epub};
 // 所有舊PDF命令已刪除 - 現在只使用Chrome Headless實現
-use commands::pdf_chrome::{generate_pdf_chrome}; // Chrome Headless PDF 命令 - 最新解決方案
+use commands::pdf_chrome::{generate_pdf_chrome, get_pdf_exports, delete_pdf_export}; // Chrome Headless PDF 命令 - 最新解決方案
+use commands::bundle::export_project_bundle;
 use commands::illustration::{
     setup_character_consistency, generate_consistency_report, set_character_seed,
     add_reference_image, get_character_visual_traits, calculate_character_similarity_matrix,
     batch_check_project_consistency, generate_batch_seeds, generate_illustration,
-    generate_enhanced_illustration, get_illustration_generation_status,
-    cancel_illustration_generation, validate_imagen_api_connection,
+    generate_enhanced_illustration, preview_illustration_prompt, get_illustration_generation_status,
+    cancel_illustration_generation, validate_imagen_api_connection, test_imagen_api_key, retry_failed_illustrations,
+    audit_illustration_storage, clean_orphaned_illustrations,
+    get_illustration_cost_report,
     generate_free_illustration, test_pollinations_connection, get_free_illustration_models,
-    get_illustration_history,
+    get_illustration_history, read_illustration_metadata,
     // 臨時圖像管理 API
     generate_free_illustration_to_temp, confirm_temp_image_save, delete_temp_image, cleanup_expired_temp_images,
     // 圖片刪除管理 API
@@ -43,12 +56,17 @@ use commands::illustration::{
 };
 use commands::translation::{
     translate_character_description, optimize_prompt, search_vocabulary, 
-    get_vocabulary_stats, batch_translate_descriptions
+    get_vocabulary_stats, batch_translate_descriptions,
+    add_vocabulary_entry, update_vocabulary_entry, delete_vocabulary_entry,
+    import_vocabulary, export_vocabulary, analyze_vocabulary_coverage,
+    record_vocabulary_outcome, reverse_lookup_vocabulary, optimize_prompt_iterative
 };
 use commands::prompt_templates::{
     apply_prompt_template, search_prompt_templates, get_template_categories,
     get_templates_by_category, get_template_stats, batch_apply_templates,
-    get_popular_templates, get_recommended_templates
+    get_popular_templates, get_recommended_templates, suggest_template_parameters,
+    create_prompt_template, update_prompt_template, delete_prompt_template, rate_prompt_template,
+    validate_all_templates, get_template_in_language, preview_template_quality
 };
 use commands::batch_illustration::{
     initialize_batch_manager, submit_batch_illustration_request, get_batch_status,
@@ -56,7 +74,12 @@ use commands::batch_illustration::{
     cleanup_completed_tasks, get_all_batches_summary, retry_failed_tasks,
     pause_batch, resume_batch
 };
+use commands::analysis::{
+    summarize_chapter, analyze_character, analyze_chapter_plot, clear_analysis_cache,
+    enqueue_analysis, get_analysis_queue, generate_creative_suggestions, accept_suggestion, reject_suggestion
+};
 use services::context::optimize_ultra_long_context_command;
+use tauri_plugin_log::{Target, TargetKind};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -65,13 +88,16 @@ pub fn run() {
     .plugin(tauri_plugin_shell::init())
     .plugin(tauri_plugin_opener::init())
     .setup(|app| {
-      if cfg!(debug_assertions) {
-        app.handle().plugin(
-          tauri_plugin_log::Builder::default()
-            .level(log::LevelFilter::Info)
-            .build(),
-        )?;
-      }
+      // 日誌寫入應用日誌目錄（release 版同樣需要，collect_diagnostics 會附上最後幾行）
+      app.handle().plugin(
+        tauri_plugin_log::Builder::default()
+          .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::LogDir { file_name: None }),
+          ])
+          .level(log::LevelFilter::Info)
+          .build(),
+      )?;
       
       // 初始化資料庫
       if let Err(e) = database::init_database() {
@@ -79,6 +105,14 @@ pub fn run() {
         return Err(e.into());
       }
       
+      // 檢查上次安裝的更新是否需要確認或回滾（失敗不影響啟動）
+      if let Err(e) = commands::system::finalize_pending_update() {
+        log::warn!("檢查更新安裝狀態失敗: {}", e);
+      }
+      
+      // 啟動背景分析佇列
+      commands::analysis::spawn_analysis_worker(app.handle().clone());
+      
       Ok(())
     })
     .invoke_handler(tauri::generate_handler![
@@ -92,6 +126,9 @@ pub fn run() {
       check_for_updates,
       download_update,
       install_update,
+      confirm_update_launch,
+      rollback_update,
+      collect_diagnostics,
       set_auto_update,
       // Project commands
       get_all_projects,
@@ -99,22 +136,40 @@ pub fn run() {
       create_project,
       update_project,
       delete_project,
+      duplicate_project,
+      archive_project,
+      unarchive_project,
+      get_project_writing_stats,
+      get_novel_length_presets,
+      #[cfg(debug_assertions)]
+      seed_sample_project,
       // Chapter commands
       get_chapters_by_project_id,
       get_chapter_by_id,
       create_chapter,
       update_chapter,
       delete_chapter,
+      reorder_chapters,
+      normalize_chapter_numbers,
+      list_chapter_versions,
+      restore_chapter_version,
+      find_and_replace_in_project,
       // Character commands
       get_characters_by_project_id,
       get_character_by_id,
       create_character,
       update_character,
       delete_character,
+      validate_character_attributes,
       create_character_relationship,
       delete_character_relationship,
       get_character_relationships,
       clear_character_relationships,
+      get_relationship_types,
+      get_character_relationship_graph,
+      scan_character_mentions,
+      find_duplicate_characters,
+      merge_characters,
       // AI commands (legacy Ollama)
       check_ollama_service,
       get_service_status,
@@ -131,14 +186,18 @@ pub fn run() {
       update_ai_provider,
       delete_ai_provider,
       test_ai_provider,
+      test_all_ai_providers,
       generate_ai_text,
+      preview_provider_request,
       get_supported_ai_provider_types,
       get_available_models,
       // Context commands
       build_context,
+      build_append_context,
       compress_context,
       get_context_stats,
       build_separated_context,
+      get_default_system_prompt,
       estimate_separated_context_tokens,
       analyze_text_purity,
       enhance_generation_parameters,
@@ -147,28 +206,53 @@ pub fn run() {
       set_setting,
       get_all_settings,
       reset_settings,
+      get_setting_typed,
+      set_setting_typed,
+      get_setting_registry,
+      export_settings,
+      import_settings,
+      get_project_setting,
+      set_project_setting,
       // Database commands
       backup_database,
       restore_database,
+      inspect_backup,
       run_database_maintenance,
       get_database_stats,
       health_check,
+      integrity_check,
+      get_migration_status,
       reindex_database,
       incremental_vacuum,
+      vacuum_into,
       get_wal_mode_status,
       set_wal_mode,
+      checkpoint_wal,
+      configure_auto_backup,
+      run_due_backups,
       // AI History commands
       create_ai_history,
       query_ai_history,
       mark_ai_history_selected,
       delete_ai_history,
       cleanup_ai_history,
+      search_ai_history,
+      apply_ai_history_to_chapter,
+      get_ai_history_stats,
+      export_ai_history,
       // EPUB commands
       generate_epub,
       get_epub_exports,
       delete_epub_export,
+      // Import commands
+      import_chapters_from_html,
+      import_document,
+      import_epub,
       // 所有舊PDF命令已刪除 - 僅保留Chrome Headless實現
       generate_pdf_chrome,
+      get_pdf_exports,
+      delete_pdf_export,
+      export_project_bundle,
       // Illustration commands
       setup_character_consistency,
       generate_consistency_report,
@@ -180,14 +264,21 @@ pub fn run() {
       generate_batch_seeds,
       generate_illustration,
       generate_enhanced_illustration,
+      preview_illustration_prompt,
       get_illustration_generation_status,
       cancel_illustration_generation,
       validate_imagen_api_connection,
+      test_imagen_api_key,
+      audit_illustration_storage,
+      clean_orphaned_illustrations,
+      retry_failed_illustrations,
+      get_illustration_cost_report,
       // Free Illustration commands (Pollinations.AI)
       generate_free_illustration,
       test_pollinations_connection,
       get_free_illustration_models,
       get_illustration_history,
+      read_illustration_metadata,
       // 臨時圖像預覽管理
       generate_free_illustration_to_temp,
       confirm_temp_image_save,
@@ -204,6 +295,15 @@ pub fn run() {
       search_vocabulary,
       get_vocabulary_stats,
       batch_translate_descriptions,
+      add_vocabulary_entry,
+      update_vocabulary_entry,
+      delete_vocabulary_entry,
+      import_vocabulary,
+      export_vocabulary,
+      analyze_vocabulary_coverage,
+      record_vocabulary_outcome,
+      reverse_lookup_vocabulary,
+      optimize_prompt_iterative,
       // Prompt Template commands
       apply_prompt_template,
       search_prompt_templates,
@@ -213,6 +313,14 @@ pub fn run() {
       batch_apply_templates,
       get_popular_templates,
       get_recommended_templates,
+      suggest_template_parameters,
+      create_prompt_template,
+      update_prompt_template,
+      delete_prompt_template,
+      rate_prompt_template,
+      validate_all_templates,
+      get_template_in_language,
+      preview_template_quality,
       // Batch Illustration commands
       initialize_batch_manager,
       submit_batch_illustration_request,
@@ -225,6 +333,16 @@ pub fn run() {
       retry_failed_tasks,
       pause_batch,
       resume_batch,
+      // Analysis commands
+      summarize_chapter,
+      analyze_character,
+      analyze_chapter_plot,
+      clear_analysis_cache,
+      enqueue_analysis,
+      get_analysis_queue,
+      generate_creative_suggestions,
+      accept_suggestion,
+      reject_suggestion,
       // Context Optimization commands
       optimize_ultra_long_context_command,
     ])
@@ -9,7 +9,7 @@ use super::r#trait::{
     AIProvider, ProviderConfig, AIGenerationRequest, AIGenerationResponse, 
     AIGenerationParams, AIUsageInfo, ModelInfo, detect_model_characteristics, ResponseFormat
 };
-use super::security::SecurityUtils;
+use super::security::{SecurityConstants, SecurityUtils};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ClaudeRequest {
@@ -96,6 +96,27 @@ impl ClaudeProvider {
         })
     }
 
+    /// 構建 Messages API 請求主體
+    fn build_messages_request(request: &AIGenerationRequest) -> ClaudeRequest {
+        // 構建消息列表
+        let messages = vec![
+            ClaudeMessage {
+                role: "user".to_string(),
+                content: request.prompt.clone(),
+            }
+        ];
+
+        ClaudeRequest {
+            model: request.model.clone(),
+            max_tokens: request.params.max_tokens,
+            messages,
+            system: request.system_prompt.clone(),
+            temperature: request.params.temperature,
+            top_p: request.params.top_p,
+            stop_sequences: request.params.stop.clone(),
+        }
+    }
+
     /// 發送 POST 請求到 Claude API
     async fn make_post_request<T>(&self, endpoint: &str, body: &impl Serialize) -> Result<T>
     where
@@ -227,23 +248,7 @@ impl AIProvider for ClaudeProvider {
         
         log::info!("[ClaudeProvider] 開始生成文本，模型: {}, API金鑰: {}", request.model, SecurityUtils::mask_api_key(&self.api_key));
 
-        // 構建消息列表
-        let messages = vec![
-            ClaudeMessage {
-                role: "user".to_string(),
-                content: request.prompt,
-            }
-        ];
-
-        let claude_request = ClaudeRequest {
-            model: request.model.clone(),
-            max_tokens: request.params.max_tokens,
-            messages,
-            system: request.system_prompt,
-            temperature: request.params.temperature,
-            top_p: request.params.top_p,
-            stop_sequences: request.params.stop,
-        };
+        let claude_request = Self::build_messages_request(&request);
 
         let response = self.make_post_request::<ClaudeResponse>("/messages", &claude_request).await?;
         
@@ -291,12 +296,25 @@ impl AIProvider for ClaudeProvider {
                 model: response.model,
                 usage: Some(usage),
                 finish_reason: response.stop_reason,
-            })
+            }.truncated_at_stop(request.params.stop.as_deref()))
         } else {
             Err(anyhow!("Claude API 回應中沒有有效內容"))
         }
     }
 
+    fn preview_request(&self, request: &AIGenerationRequest) -> Result<serde_json::Value> {
+        let body = Self::build_messages_request(request);
+        Ok(serde_json::json!({
+            "url": format!("{}/messages", self.endpoint),
+            "headers": {
+                "x-api-key": SecurityConstants::REDACTED_API_KEY,
+                "anthropic-version": "2023-06-01",
+                "Content-Type": "application/json",
+            },
+            "body": serde_json::to_value(&body)?,
+        }))
+    }
+
     async fn validate_api_key(&self, api_key: &str) -> Result<bool> {
         log::info!("[ClaudeProvider] 驗證 API 金鑰: {}...", SecurityUtils::mask_api_key(api_key));
         
@@ -388,4 +406,32 @@ impl AIProvider for ClaudeProvider {
     fn supports_custom_endpoint(&self) -> bool {
         false // Claude 通常使用標準端點
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ai_providers::r#trait::test_support::{sample_request, sample_request_with_stop};
+    
+    const MODEL: &str = "claude-3-5-sonnet-20241022";
+    
+    #[test]
+    fn test_stop_sequences_use_stop_sequences_field() {
+        let request = sample_request_with_stop(MODEL, "第二章");
+        let body = serde_json::to_value(ClaudeProvider::build_messages_request(&request)).unwrap();
+        
+        assert_eq!(body["stop_sequences"], serde_json::json!(["第二章"]));
+        assert_eq!(body["max_tokens"], 2000);
+    }
+    
+    #[test]
+    fn test_system_prompt_uses_top_level_system_field() {
+        let body = serde_json::to_value(ClaudeProvider::build_messages_request(&sample_request(MODEL))).unwrap();
+        
+        assert_eq!(body["system"], "你是一位小說家");
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["role"], "user");
+        assert_eq!(messages[0]["content"], "繼續寫下去");
+    }
+}
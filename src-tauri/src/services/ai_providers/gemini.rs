@@ -9,10 +9,12 @@ use super::r#trait::{
     AIProvider, ProviderConfig, AIGenerationRequest, AIGenerationResponse, 
     AIGenerationParams, AIUsageInfo, ModelInfo, detect_model_characteristics, ResponseFormat
 };
-use super::security::SecurityUtils;
+use super::security::{SecurityConstants, SecurityUtils};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct GeminiRequest {
+    #[serde(rename = "systemInstruction", skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<GeminiSystemInstruction>,
     contents: Vec<GeminiContent>,
     #[serde(rename = "generationConfig")]
     generation_config: GeminiGenerationConfig,
@@ -26,6 +28,11 @@ struct GeminiContent {
     parts: Option<Vec<GeminiPart>>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiSystemInstruction {
+    parts: Vec<GeminiPart>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct GeminiPart {
     text: String,
@@ -126,6 +133,46 @@ impl GeminiProvider {
         }
     }
 
+    /// 構建 generateContent 請求主體
+    fn build_generate_request(request: &AIGenerationRequest) -> GeminiRequest {
+        // 構建內容列表
+        let mut contents = Vec::new();
+        
+        // 系統提示放入 Gemini 原生的 systemInstruction 欄位
+        let system_instruction = request.system_prompt.as_ref().map(|system_prompt| {
+            GeminiSystemInstruction {
+                parts: vec![
+                    GeminiPart {
+                        text: system_prompt.clone(),
+                        other: std::collections::HashMap::new(),
+                    }
+                ],
+            }
+        });
+        
+        contents.push(GeminiContent {
+            role: "user".to_string(),
+            parts: Some(vec![
+                GeminiPart {
+                    text: request.prompt.clone(),
+                    other: std::collections::HashMap::new(),
+                }
+            ]),
+        });
+
+        GeminiRequest {
+            system_instruction,
+            contents,
+            generation_config: GeminiGenerationConfig {
+                temperature: request.params.temperature,
+                top_p: request.params.top_p.unwrap_or(0.95),
+                max_output_tokens: request.params.max_tokens,
+                stop_sequences: request.params.stop.clone(),
+            },
+            safety_settings: Self::get_default_safety_settings(),
+        }
+    }
+
     /// 發送 POST 請求到 Gemini API
     async fn make_post_request<T>(&self, endpoint: &str, body: &impl Serialize) -> Result<T>
     where
@@ -305,6 +352,7 @@ impl AIProvider for GeminiProvider {
         
         // 發送簡單的測試請求
         let test_request = GeminiRequest {
+            system_instruction: None,
             contents: vec![
                 GeminiContent {
                     role: "user".to_string(),
@@ -380,36 +428,7 @@ impl AIProvider for GeminiProvider {
             }
         }
 
-        // 構建內容列表
-        let mut contents = Vec::new();
-        
-        // Gemini 不支援系統角色，將系統提示合併到用戶消息中
-        let user_content = if let Some(system_prompt) = &request.system_prompt {
-            format!("{}\n\n{}", system_prompt, request.prompt)
-        } else {
-            request.prompt.clone()
-        };
-        
-        contents.push(GeminiContent {
-            role: "user".to_string(),
-            parts: Some(vec![
-                GeminiPart {
-                    text: user_content,
-                    other: std::collections::HashMap::new(),
-                }
-            ]),
-        });
-
-        let gemini_request = GeminiRequest {
-            contents,
-            generation_config: GeminiGenerationConfig {
-                temperature: request.params.temperature,
-                top_p: request.params.top_p.unwrap_or(0.95),
-                max_output_tokens: request.params.max_tokens,
-                stop_sequences: request.params.stop,
-            },
-            safety_settings: Self::get_default_safety_settings(),
-        };
+        let gemini_request = Self::build_generate_request(&request);
 
         let model_name = if request.model.starts_with("models/") {
             request.model.clone()
@@ -523,7 +542,7 @@ impl AIProvider for GeminiProvider {
                 usage,
                 finish_reason: response.candidates.first()
                     .and_then(|c| c.finish_reason.clone()),
-            })
+            }.truncated_at_stop(request.params.stop.as_deref()))
         } else {
             // 針對不同的失敗原因提供具體的錯誤消息
             let finish_reason = response.candidates.first()
@@ -562,6 +581,23 @@ impl AIProvider for GeminiProvider {
         }
     }
 
+    fn preview_request(&self, request: &AIGenerationRequest) -> Result<serde_json::Value> {
+        let body = Self::build_generate_request(request);
+        let model_name = if request.model.starts_with("models/") {
+            request.model.clone()
+        } else {
+            format!("models/{}", request.model)
+        };
+        Ok(serde_json::json!({
+            "url": format!("{}/{}:generateContent", self.endpoint, model_name),
+            "headers": {
+                "Content-Type": "application/json",
+                "x-goog-api-key": SecurityConstants::REDACTED_API_KEY,
+            },
+            "body": serde_json::to_value(&body)?,
+        }))
+    }
+
     async fn validate_api_key(&self, api_key: &str) -> Result<bool> {
         log::info!("[GeminiProvider] 驗證 API 金鑰...");
         
@@ -570,6 +606,7 @@ impl AIProvider for GeminiProvider {
         let url = format!("{}/models/gemini-2.5-flash:generateContent", self.endpoint);
         
         let test_request = GeminiRequest {
+            system_instruction: None,
             contents: vec![
                 GeminiContent {
                     role: "user".to_string(),
@@ -648,4 +685,43 @@ impl AIProvider for GeminiProvider {
     fn supports_custom_endpoint(&self) -> bool {
         false // Gemini 通常使用標準端點
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ai_providers::r#trait::test_support::{sample_request, sample_request_with_stop};
+    
+    const MODEL: &str = "gemini-1.5-flash";
+    
+    #[test]
+    fn test_stop_sequences_use_generation_config() {
+        let request = sample_request_with_stop(MODEL, "第二章");
+        let body = serde_json::to_value(GeminiProvider::build_generate_request(&request)).unwrap();
+        
+        assert_eq!(body["generationConfig"]["stopSequences"], serde_json::json!(["第二章"]));
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 2000);
+    }
+    
+    #[test]
+    fn test_system_prompt_uses_system_instruction() {
+        let body = serde_json::to_value(GeminiProvider::build_generate_request(&sample_request(MODEL))).unwrap();
+        
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "你是一位小說家");
+        let contents = body["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(contents[0]["role"], "user");
+        assert_eq!(contents[0]["parts"][0]["text"], "繼續寫下去");
+    }
+    
+    #[test]
+    fn test_system_instruction_omitted_without_system_prompt() {
+        let request = AIGenerationRequest {
+            system_prompt: None,
+            ..sample_request(MODEL)
+        };
+        let body = serde_json::to_value(GeminiProvider::build_generate_request(&request)).unwrap();
+        
+        assert!(body.get("systemInstruction").is_none());
+    }
+}
@@ -13,6 +13,7 @@ pub use r#trait::{
     ProviderConfig,
     AIGenerationRequest,
     AIGenerationParams,
+    AIUsageInfo,
 };
 
 // 重導出安全工具（僅導出實際使用的）
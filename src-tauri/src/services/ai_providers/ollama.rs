@@ -2,7 +2,7 @@ use anyhow::{Result, anyhow};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::collections::HashMap;
 
 use super::r#trait::{
@@ -29,11 +29,25 @@ struct OllamaVersionResponse {
     pub version: String,
 }
 
+/// /api/ps 回傳的已載入模型
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaRunningModel {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaRunningModelsResponse {
+    pub models: Vec<OllamaRunningModel>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct OllamaGenerateRequest {
     pub model: String,
     pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,  // Ollama 原生系統提示欄位
     pub stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub options: Option<OllamaOptions>,
 }
 
@@ -45,6 +59,8 @@ struct OllamaOptions {
     pub max_tokens: Option<u32>,  // Ollama 使用 num_predict 而不是 max_tokens
     pub presence_penalty: Option<f32>,
     pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -61,6 +77,52 @@ struct OllamaGenerateResponse {
     pub eval_duration: Option<u64>,
 }
 
+/// 等待模型冷啟動載入的最長時間
+const MODEL_LOAD_MAX_WAIT: Duration = Duration::from_secs(180);
+/// 模型載入中時第一次重試前的等待時間，之後每次加倍
+const MODEL_LOAD_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// 模型載入重試間隔上限
+const MODEL_LOAD_MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+/// Ollama 回報模型仍在載入（503 或 "loading model" 訊息）
+#[derive(Debug)]
+pub struct ModelLoadingError(pub String);
+
+impl std::fmt::Display for ModelLoadingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "模型載入中: {}", self.0)
+    }
+}
+
+impl std::error::Error for ModelLoadingError {}
+
+/// 模型載入狀態（作為 `model-loading` 事件內容）
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelLoadingStatus {
+    pub model: String,
+    pub loading: bool,
+    pub attempt: u32,
+    pub elapsed_ms: u64,
+    pub message: String,
+}
+
+/// 判斷錯誤回應是否代表模型仍在載入
+fn is_model_loading_response(status: reqwest::StatusCode, body: &str) -> bool {
+    let body = body.to_lowercase();
+    status == reqwest::StatusCode::SERVICE_UNAVAILABLE
+        || body.contains("loading model")
+        || body.contains("model is loading")
+}
+
+fn is_model_loading_error(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<ModelLoadingError>().is_some()
+}
+
+/// 指數退避的下一個等待時間
+fn next_model_load_backoff(current: Duration) -> Duration {
+    (current * 2).min(MODEL_LOAD_MAX_BACKOFF)
+}
+
 /// 過濾掉AI思考標籤和不當內容的函數
 fn filter_thinking_tags(text: &str) -> String {
     use regex::Regex;
@@ -205,6 +267,104 @@ impl OllamaProvider {
         })
     }
 
+    /// 構建 /api/generate 請求主體
+    fn build_generate_request(request: &AIGenerationRequest) -> OllamaGenerateRequest {
+        // 轉換參數格式
+        let options = OllamaOptions {
+            temperature: Some(request.params.temperature as f32),
+            top_p: request.params.top_p.map(|v| v as f32),
+            max_tokens: Some(request.params.max_tokens as u32),
+            presence_penalty: request.params.presence_penalty.map(|v| v as f32),
+            frequency_penalty: request.params.frequency_penalty.map(|v| v as f32),
+            stop: request.params.stop.clone(),
+        };
+
+        // 系統提示使用 Ollama 原生的 system 欄位，不再合併進提示詞
+        log::info!("[OllamaProvider] 最終提示詞長度: {} 字符", request.prompt.len());
+        log::info!("[OllamaProvider] 最終提示詞內容（前200字符）: {}", 
+                   request.prompt.chars().take(200).collect::<String>());
+
+        OllamaGenerateRequest {
+            model: request.model.clone(),
+            prompt: request.prompt.clone(),
+            system: request.system_prompt.clone(),
+            stream: false,
+            options: Some(options),
+        }
+    }
+
+    /// 確認模型已載入；未載入時以空提示詞預熱，並透過 `on_loading` 回報等待進度
+    ///
+    /// 已在記憶體中的模型不會觸發回報。預熱失敗（非載入中的錯誤）時直接回傳錯誤。
+    pub async fn warm_up_model(&self, model: &str, on_loading: impl Fn(&ModelLoadingStatus)) -> Result<()> {
+        // 舊版 Ollama 沒有 /api/ps，查詢失敗時一律預熱
+        if let Ok(running) = self.make_get_request::<OllamaRunningModelsResponse>("/api/ps").await {
+            if running.models.iter().any(|m| m.name == model || m.name == format!("{}:latest", model)) {
+                log::debug!("[OllamaProvider] 模型 {} 已載入，略過預熱", model);
+                return Ok(());
+            }
+        }
+
+        let started = Instant::now();
+        let status = |loading: bool, attempt: u32, message: &str| ModelLoadingStatus {
+            model: model.to_string(),
+            loading,
+            attempt,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            message: message.to_string(),
+        };
+        log::info!("[OllamaProvider] 模型 {} 尚未載入，開始預熱", model);
+        on_loading(&status(true, 0, "正在載入模型…"));
+
+        // 空提示詞只會載入模型而不產生內容
+        let body = OllamaGenerateRequest {
+            model: model.to_string(),
+            prompt: String::new(),
+            system: None,
+            stream: false,
+            options: None,
+        };
+        let mut backoff = MODEL_LOAD_INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.make_post_request::<serde_json::Value>("/api/generate", &body).await {
+                Ok(_) => {
+                    log::info!("[OllamaProvider] 模型 {} 預熱完成，耗時 {:?}", model, started.elapsed());
+                    on_loading(&status(false, attempt, "模型已就緒"));
+                    return Ok(());
+                }
+                Err(e) if is_model_loading_error(&e) && started.elapsed() + backoff < MODEL_LOAD_MAX_WAIT => {
+                    log::info!("[OllamaProvider] 模型 {} 仍在載入（第 {} 次），{:?} 後重試", model, attempt, backoff);
+                    on_loading(&status(true, attempt, "正在載入模型…"));
+                    tokio::time::sleep(backoff).await;
+                    backoff = next_model_load_backoff(backoff);
+                }
+                Err(e) => {
+                    log::warn!("[OllamaProvider] 模型 {} 預熱失敗: {}", model, e);
+                    on_loading(&status(false, attempt, &format!("模型載入失敗: {}", e)));
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// 呼叫 /api/generate；模型載入中時以指數退避等待，直到載入完成或超過 MODEL_LOAD_MAX_WAIT
+    async fn post_generate_with_backoff(&self, body: &OllamaGenerateRequest) -> Result<OllamaGenerateResponse> {
+        let started = Instant::now();
+        let mut backoff = MODEL_LOAD_INITIAL_BACKOFF;
+        loop {
+            match self.make_post_request::<OllamaGenerateResponse>("/api/generate", body).await {
+                Err(e) if is_model_loading_error(&e) && started.elapsed() + backoff < MODEL_LOAD_MAX_WAIT => {
+                    log::info!("[OllamaProvider] 模型 {} 載入中，{:?} 後重試", body.model, backoff);
+                    tokio::time::sleep(backoff).await;
+                    backoff = next_model_load_backoff(backoff);
+                }
+                result => return result,
+            }
+        }
+    }
+
     /// 發送 GET 請求
     async fn make_get_request<T>(&self, endpoint: &str) -> Result<T>
     where
@@ -238,11 +398,16 @@ impl OllamaProvider {
             .send()
             .await?;
 
-        if response.status().is_success() {
+        let status = response.status();
+        if status.is_success() {
             let data = response.json::<T>().await?;
             Ok(data)
         } else {
-            Err(anyhow!("HTTP {}: {}", response.status(), response.status().canonical_reason().unwrap_or("Unknown")))
+            let body = response.text().await.unwrap_or_default();
+            if is_model_loading_response(status, &body) {
+                return Err(ModelLoadingError(format!("HTTP {}", status)).into());
+            }
+            Err(anyhow!("HTTP {}: {}", status, status.canonical_reason().unwrap_or("Unknown")))
         }
     }
 }
@@ -311,39 +476,12 @@ impl AIProvider for OllamaProvider {
         // 先檢查服務可用性
         self.check_availability().await?;
 
-        // 轉換參數格式
-        let options = OllamaOptions {
-            temperature: Some(request.params.temperature as f32),
-            top_p: request.params.top_p.map(|v| v as f32),
-            max_tokens: Some(request.params.max_tokens as u32),
-            presence_penalty: request.params.presence_penalty.map(|v| v as f32),
-            frequency_penalty: request.params.frequency_penalty.map(|v| v as f32),
-        };
-
-        // 構建完整提示詞（包含系統提示）
-        let full_prompt = if let Some(system_prompt) = &request.system_prompt {
-            format!("{}
-
-{}", system_prompt, request.prompt)
-        } else {
-            request.prompt.clone()
-        };
-        
-        log::info!("[OllamaProvider] 最終提示詞長度: {} 字符", full_prompt.len());
-        log::info!("[OllamaProvider] 最終提示詞內容（前200字符）: {}", 
-                   full_prompt.chars().take(200).collect::<String>());
-
-        let request_body = OllamaGenerateRequest {
-            model: request.model.clone(),
-            prompt: full_prompt,
-            stream: false,
-            options: Some(options),
-        };
+        let request_body = Self::build_generate_request(&request);
 
         // 重試機制
         let mut last_error = String::new();
         for attempt in 1..=self.retry_attempts {
-            match self.make_post_request::<OllamaGenerateResponse>("/api/generate", &request_body).await {
+            match self.post_generate_with_backoff(&request_body).await {
                 Ok(response) => {
                     log::info!("[OllamaProvider] 文本生成成功");
                     log::info!("[OllamaProvider] 🔍 原始回應: response.response = '{}'", response.response);
@@ -399,7 +537,7 @@ impl AIProvider for OllamaProvider {
                         model: request.model,
                         usage: Some(usage),
                         finish_reason: if response.done { Some("stop".to_string()) } else { None },
-                    });
+                    }.truncated_at_stop(request.params.stop.as_deref()));
                 }
                 Err(e) => {
                     last_error = e.to_string();
@@ -415,6 +553,15 @@ impl AIProvider for OllamaProvider {
         Err(anyhow!("生成文本失敗 ({} 次嘗試): {}", self.retry_attempts, last_error))
     }
 
+    fn preview_request(&self, request: &AIGenerationRequest) -> Result<serde_json::Value> {
+        let body = Self::build_generate_request(request);
+        Ok(serde_json::json!({
+            "url": format!("{}/api/generate", self.endpoint),
+            "headers": {},
+            "body": serde_json::to_value(&body)?,
+        }))
+    }
+
     async fn validate_api_key(&self, _api_key: &str) -> Result<bool> {
         // Ollama 不需要 API 金鑰，直接檢查服務可用性
         self.check_availability().await
@@ -443,4 +590,48 @@ impl AIProvider for OllamaProvider {
     fn supports_custom_endpoint(&self) -> bool {
         true // Ollama 支援自訂端點
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ai_providers::r#trait::test_support::{sample_request, sample_request_with_stop};
+    
+    const MODEL: &str = "llama3.2";
+    
+    #[test]
+    fn test_detects_model_loading_and_backs_off() {
+        use reqwest::StatusCode;
+        
+        assert!(is_model_loading_response(StatusCode::SERVICE_UNAVAILABLE, ""));
+        assert!(is_model_loading_response(StatusCode::INTERNAL_SERVER_ERROR, r#"{"error":"Loading model, please retry"}"#));
+        assert!(!is_model_loading_response(StatusCode::NOT_FOUND, r#"{"error":"model not found"}"#));
+        
+        let error: anyhow::Error = ModelLoadingError("HTTP 503".to_string()).into();
+        assert!(is_model_loading_error(&error));
+        assert!(!is_model_loading_error(&anyhow!("HTTP 404")));
+        
+        let mut backoff = MODEL_LOAD_INITIAL_BACKOFF;
+        for _ in 0..10 {
+            backoff = next_model_load_backoff(backoff);
+        }
+        assert_eq!(backoff, MODEL_LOAD_MAX_BACKOFF);
+    }
+    
+    #[test]
+    fn test_stop_sequences_use_options_stop() {
+        let request = sample_request_with_stop(MODEL, "第二章");
+        let body = serde_json::to_value(OllamaProvider::build_generate_request(&request)).unwrap();
+        
+        assert_eq!(body["options"]["stop"], serde_json::json!(["第二章"]));
+        assert_eq!(body["options"]["num_predict"], 2000);
+    }
+    
+    #[test]
+    fn test_system_prompt_uses_system_field() {
+        let body = serde_json::to_value(OllamaProvider::build_generate_request(&sample_request(MODEL))).unwrap();
+        
+        assert_eq!(body["system"], "你是一位小說家");
+        assert_eq!(body["prompt"], "繼續寫下去");
+    }
+}
@@ -9,7 +9,7 @@ use super::r#trait::{
     AIProvider, ProviderConfig, AIGenerationRequest, AIGenerationResponse, 
     AIGenerationParams, AIUsageInfo, ModelInfo, detect_model_characteristics, ResponseFormat
 };
-use super::security::SecurityUtils;
+use super::security::{SecurityConstants, SecurityUtils};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct OpenAIRequest {
@@ -188,6 +188,68 @@ impl OpenAIProvider {
         String::new()
     }
 
+    /// 構建 Chat Completions 請求主體
+    fn build_chat_request(request: &AIGenerationRequest) -> OpenAIRequest {
+        // 構建訊息列表
+        let mut messages = Vec::new();
+        
+        // 添加系統提示（如果有）
+        if let Some(system_prompt) = &request.system_prompt {
+            messages.push(OpenAIMessage {
+                role: "system".to_string(),
+                content: Some(system_prompt.clone()),
+                reasoning: None,
+                reasoning_summary: None,
+            });
+        }
+        
+        // 添加用戶提示
+        messages.push(OpenAIMessage {
+            role: "user".to_string(),
+            content: Some(request.prompt.clone()),
+            reasoning: None,
+            reasoning_summary: None,
+        });
+
+        // 🔥 關鍵修復：根據模型類型選擇正確的參數
+        let is_new_model = is_new_api_model(&request.model);
+        
+        let (max_tokens, max_completion_tokens) = if is_new_model {
+            log::info!("[OpenAIProvider] 🆕 使用新API格式 (max_completion_tokens) 對模型: {}", request.model);
+            (None, Some(request.params.max_tokens))
+        } else {
+            log::info!("[OpenAIProvider] 📝 使用舊API格式 (max_tokens) 對模型: {}", request.model);
+            (Some(request.params.max_tokens), None)
+        };
+
+        // 🔥 新修復：GPT-5 系列模型只接受特定的參數值
+        let (temperature, top_p, presence_penalty, frequency_penalty, stop) = if is_new_model {
+            log::info!("[OpenAIProvider] 🎯 GPT-5 系列模型：使用固定參數 (temperature=1.0)");
+            (1.0, None, None, None, None) // GPT-5 系列只接受預設值，停止序列改由回應後截斷處理
+        } else {
+            log::info!("[OpenAIProvider] 🎛️ 傳統模型：使用用戶自定義參數");
+            (
+                request.params.temperature,
+                request.params.top_p,
+                request.params.presence_penalty,
+                request.params.frequency_penalty,
+                request.params.stop.clone(),
+            )
+        };
+
+        OpenAIRequest {
+            model: request.model.clone(),
+            messages,
+            temperature,
+            max_tokens,
+            max_completion_tokens,
+            top_p,
+            presence_penalty,
+            frequency_penalty,
+            stop,
+        }
+    }
+
     /// 發送 GET 請求到 OpenAI API
     async fn make_get_request<T>(&self, endpoint: &str) -> Result<T>
     where
@@ -366,64 +428,7 @@ impl AIProvider for OpenAIProvider {
         
         log::info!("[OpenAIProvider] ✅ 開始生成文本，模型: {}, API金鑰: {}", request.model, SecurityUtils::mask_api_key(&self.api_key));
 
-        // 構建訊息列表
-        let mut messages = Vec::new();
-        
-        // 添加系統提示（如果有）
-        if let Some(system_prompt) = &request.system_prompt {
-            messages.push(OpenAIMessage {
-                role: "system".to_string(),
-                content: Some(system_prompt.clone()),
-                reasoning: None,
-                reasoning_summary: None,
-            });
-        }
-        
-        // 添加用戶提示
-        messages.push(OpenAIMessage {
-            role: "user".to_string(),
-            content: Some(request.prompt),
-            reasoning: None,
-            reasoning_summary: None,
-        });
-
-        // 🔥 關鍵修復：根據模型類型選擇正確的參數
-        let is_new_model = is_new_api_model(&request.model);
-        
-        let (max_tokens, max_completion_tokens) = if is_new_model {
-            log::info!("[OpenAIProvider] 🆕 使用新API格式 (max_completion_tokens) 對模型: {}", request.model);
-            (None, Some(request.params.max_tokens))
-        } else {
-            log::info!("[OpenAIProvider] 📝 使用舊API格式 (max_tokens) 對模型: {}", request.model);
-            (Some(request.params.max_tokens), None)
-        };
-
-        // 🔥 新修復：GPT-5 系列模型只接受特定的參數值
-        let (temperature, top_p, presence_penalty, frequency_penalty, stop) = if is_new_model {
-            log::info!("[OpenAIProvider] 🎯 GPT-5 系列模型：使用固定參數 (temperature=1.0)");
-            (1.0, None, None, None, None) // GPT-5 系列只接受預設值
-        } else {
-            log::info!("[OpenAIProvider] 🎛️ 傳統模型：使用用戶自定義參數");
-            (
-                request.params.temperature,
-                request.params.top_p,
-                request.params.presence_penalty,
-                request.params.frequency_penalty,
-                request.params.stop.clone(),
-            )
-        };
-
-        let openai_request = OpenAIRequest {
-            model: request.model.clone(),
-            messages,
-            temperature,
-            max_tokens,
-            max_completion_tokens,
-            top_p,
-            presence_penalty,
-            frequency_penalty,
-            stop,
-        };
+        let openai_request = Self::build_chat_request(&request);
 
         let response = self.make_post_request::<OpenAIResponse>("/chat/completions", &openai_request).await?;
         
@@ -494,12 +499,24 @@ impl AIProvider for OpenAIProvider {
                 usage: Some(usage),
                 finish_reason: response.choices.first()
                     .and_then(|c| c.finish_reason.clone()),
-            })
+            }.truncated_at_stop(request.params.stop.as_deref()))
         } else {
             Err(anyhow!("OpenAI API 回應中沒有有效內容"))
         }
     }
 
+    fn preview_request(&self, request: &AIGenerationRequest) -> Result<serde_json::Value> {
+        let body = Self::build_chat_request(request);
+        Ok(serde_json::json!({
+            "url": format!("{}/chat/completions", self.endpoint),
+            "headers": {
+                "Authorization": format!("Bearer {}", SecurityConstants::REDACTED_API_KEY),
+                "Content-Type": "application/json",
+            },
+            "body": serde_json::to_value(&body)?,
+        }))
+    }
+
     async fn validate_api_key(&self, api_key: &str) -> Result<bool> {
         log::info!("[OpenAIProvider] 驗證 API 金鑰: {}...", SecurityUtils::mask_api_key(api_key));
         
@@ -566,4 +583,47 @@ impl AIProvider for OpenAIProvider {
     fn supports_custom_endpoint(&self) -> bool {
         true // 支援自訂端點，適用於 Azure OpenAI 等
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ai_providers::r#trait::test_support::{sample_request, sample_request_with_stop};
+    
+    const MODEL: &str = "gpt-4-turbo";
+    
+    #[test]
+    fn test_stop_sequences_map_to_stop_or_post_trim() {
+        let mut request = sample_request_with_stop(MODEL, "\n\n第");
+        let body = serde_json::to_value(OpenAIProvider::build_chat_request(&request)).unwrap();
+        assert_eq!(body["stop"], serde_json::json!(["\n\n第"]));
+        assert_eq!(body["max_tokens"], 2000);
+        
+        // GPT-5 系列不接受 stop，改以回應後截斷
+        request.model = "gpt-5".to_string();
+        let body = serde_json::to_value(OpenAIProvider::build_chat_request(&request)).unwrap();
+        assert!(body.get("stop").is_none());
+        assert_eq!(body["max_completion_tokens"], 2000);
+        
+        let response = AIGenerationResponse {
+            text: "她推開門。\n\n第二章 雨夜".to_string(),
+            model: "gpt-5".to_string(),
+            usage: None,
+            finish_reason: Some("length".to_string()),
+        }.truncated_at_stop(request.params.stop.as_deref());
+        assert_eq!(response.text, "她推開門。");
+        assert_eq!(response.finish_reason.as_deref(), Some("stop"));
+    }
+    
+    #[test]
+    fn test_system_prompt_uses_system_message() {
+        let body = serde_json::to_value(OpenAIProvider::build_chat_request(&sample_request(MODEL))).unwrap();
+        
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[0]["content"], "你是一位小說家");
+        assert_eq!(messages[1]["role"], "user");
+        assert_eq!(messages[1]["content"], "繼續寫下去");
+    }
+}
@@ -9,7 +9,7 @@ use super::r#trait::{
     AIProvider, ProviderConfig, AIGenerationRequest, AIGenerationResponse, 
     AIGenerationParams, AIUsageInfo, ModelInfo, detect_model_characteristics, ResponseFormat
 };
-use super::security::SecurityUtils;
+use super::security::{SecurityConstants, SecurityUtils};
 
 /// 檢測是否為 GPT-5 系列模型（需要特殊參數處理）
 fn is_gpt5_model(model: &str) -> bool {
@@ -187,6 +187,126 @@ impl OpenRouterProvider {
         }
     }
 
+    /// 構建 Chat Completions 請求主體（含 max_tokens 智能調整）
+    fn build_chat_request(request: &AIGenerationRequest) -> OpenRouterRequest {
+        // 🔥 關鍵修復：智能調整max_tokens防止空回應
+        // 更精確的token估算：中文字符約2個字符=1token，英文約4個字符=1token
+        let char_count = request.prompt.chars().count();
+        let estimated_prompt_tokens = if char_count > 0 {
+            // 混合估算：假設50%中文50%英文
+            (char_count as f64 * 0.75) as usize  // 保守估算
+        } else {
+            0
+        };
+        
+        // 🎯 基於模型特性的智能調整策略
+        let mut adjusted_max_tokens = request.params.max_tokens;
+        let model_lower = request.model.to_lowercase();
+        
+        // 🔥 新增：確保 max_tokens 至少為 200，避免過低導致空回應
+        if adjusted_max_tokens < 200 {
+            adjusted_max_tokens = 200;
+            log::warn!("[OpenRouterProvider] ⚠️ max_tokens 過低 ({}), 提升至 200 避免空回應", request.params.max_tokens);
+        }
+        
+        // 🔥 修復：重新分類模型類型，特別處理 GPT-5 nano
+        let is_gpt5_nano = model_lower.contains("gpt-5") && model_lower.contains("nano");
+        let is_traditional_nano = (model_lower.contains("nano") || model_lower.contains("mini")) && !is_gpt5_nano;
+        let is_large_model = model_lower.contains("4o") || model_lower.contains("claude") || model_lower.contains("gemini");
+        
+        // 根據模型類型和輸入長度調整策略
+        if estimated_prompt_tokens > 300 {  // 降低觸發閾值
+            let (multiplier, max_limit) = if is_gpt5_nano {
+                // 🔥 GPT-5 nano：特殊策略，比傳統 nano 模型更強大
+                let mult = if estimated_prompt_tokens > 2000 { 3.5 }
+                          else if estimated_prompt_tokens > 1000 { 2.5 }
+                          else { 2.0 };
+                (mult, 3000.0)  // 📈 提高上限到 3000
+            } else if is_traditional_nano {
+                // 傳統 Nano 模型：更保守的策略
+                let mult = if estimated_prompt_tokens > 1500 { 2.5 }
+                          else if estimated_prompt_tokens > 800 { 2.0 }
+                          else { 1.5 };
+                (mult, 1500.0)  // 更低的上限
+            } else if is_large_model {
+                // 大型模型：更激進的策略
+                let mult = if estimated_prompt_tokens > 2000 { 4.0 }
+                          else if estimated_prompt_tokens > 1000 { 3.0 }
+                          else { 2.0 };
+                (mult, 4000.0)
+            } else {
+                // 標準模型：平衡策略
+                let mult = if estimated_prompt_tokens > 1500 { 3.0 }
+                          else if estimated_prompt_tokens > 800 { 2.0 }
+                          else { 1.5 };
+                (mult, 2500.0)
+            };
+            
+            adjusted_max_tokens = (request.params.max_tokens as f64 * multiplier).min(max_limit) as i32;
+            log::warn!("[OpenRouterProvider] ⚠️ 模型: {}, 提示詞較長 (~{} tokens)，調整max_tokens: {} -> {} (策略: {})", 
+                request.model, estimated_prompt_tokens, request.params.max_tokens, adjusted_max_tokens,
+                if is_gpt5_nano { "GPT-5 nano增強" } 
+                else if is_traditional_nano { "傳統nano保守" } 
+                else if is_large_model { "大型激進" } 
+                else { "標準平衡" });
+        }
+        
+        log::info!("[OpenRouterProvider] 📊 Token分配: 字符數={}, 預估輸入~{} tokens, 調整後輸出限制={} tokens", 
+            char_count, estimated_prompt_tokens, adjusted_max_tokens);
+
+        // 構建訊息列表
+        let mut messages = Vec::new();
+        
+        // 添加系統提示（如果有）
+        if let Some(system_prompt) = &request.system_prompt {
+            messages.push(OpenRouterMessage {
+                role: "system".to_string(),
+                content: system_prompt.clone(),
+            });
+        }
+        
+        // 添加用戶提示
+        messages.push(OpenRouterMessage {
+            role: "user".to_string(),
+            content: request.prompt.clone(),
+        });
+
+        // 🔥 關鍵修復：確保模型ID格式正確
+        let formatted_model = Self::format_model_id(&request.model);
+        log::info!("[OpenRouterProvider] 🔧 模型ID轉換: {} -> {}", request.model, formatted_model);
+        
+        // 🔥 新增：GPT-5 模型特殊參數處理
+        let is_gpt5 = is_gpt5_model(&formatted_model);
+        let uses_completion_api = uses_completion_tokens_api(&formatted_model);
+        
+        let (final_temperature, final_top_p, final_presence_penalty, final_frequency_penalty, final_stop) = if is_gpt5 {
+            log::info!("[OpenRouterProvider] 🎯 GPT-5 模型：使用固定參數 (temperature=1.0)");
+            (1.0, None, None, None, None) // GPT-5 系列只接受預設值，停止序列改由回應後截斷處理
+        } else {
+            (request.params.temperature, request.params.top_p, request.params.presence_penalty, request.params.frequency_penalty, request.params.stop.clone())
+        };
+
+        let (max_tokens, max_completion_tokens) = if uses_completion_api {
+            log::info!("[OpenRouterProvider] 🆕 使用新API格式 (max_completion_tokens) 對模型: {}", formatted_model);
+            (None, Some(adjusted_max_tokens))
+        } else {
+            log::info!("[OpenRouterProvider] 📝 使用舊API格式 (max_tokens) 對模型: {}", formatted_model);
+            (Some(adjusted_max_tokens), None)
+        };
+        
+        OpenRouterRequest {
+            model: formatted_model.clone(),
+            messages,
+            temperature: final_temperature,
+            max_tokens,
+            max_completion_tokens,
+            top_p: final_top_p,
+            presence_penalty: final_presence_penalty,
+            frequency_penalty: final_frequency_penalty,
+            stop: final_stop,
+        }
+    }
+
     /// 獲取熱門模型的預定義列表（用於離線情況）
     fn get_popular_models() -> Vec<ModelInfo> {
         vec![
@@ -333,122 +453,11 @@ impl AIProvider for OpenRouterProvider {
         log::info!("[OpenRouterProvider] 🔍 請求參數: temperature={}, max_tokens={}, prompt長度={}", 
             request.params.temperature, request.params.max_tokens, request.prompt.len());
         
-        // 🔥 關鍵修復：智能調整max_tokens防止空回應
-        // 更精確的token估算：中文字符約2個字符=1token，英文約4個字符=1token
-        let char_count = request.prompt.chars().count();
-        let estimated_prompt_tokens = if char_count > 0 {
-            // 混合估算：假設50%中文50%英文
-            (char_count as f64 * 0.75) as usize  // 保守估算
-        } else {
-            0
-        };
-        
-        // 🎯 基於模型特性的智能調整策略
-        let mut adjusted_max_tokens = request.params.max_tokens;
-        let model_lower = request.model.to_lowercase();
-        
-        // 🔥 新增：確保 max_tokens 至少為 200，避免過低導致空回應
-        if adjusted_max_tokens < 200 {
-            adjusted_max_tokens = 200;
-            log::warn!("[OpenRouterProvider] ⚠️ max_tokens 過低 ({}), 提升至 200 避免空回應", request.params.max_tokens);
-        }
-        
-        // 🔥 修復：重新分類模型類型，特別處理 GPT-5 nano
-        let is_gpt5_nano = model_lower.contains("gpt-5") && model_lower.contains("nano");
-        let is_traditional_nano = (model_lower.contains("nano") || model_lower.contains("mini")) && !is_gpt5_nano;
-        let is_large_model = model_lower.contains("4o") || model_lower.contains("claude") || model_lower.contains("gemini");
-        
-        // 根據模型類型和輸入長度調整策略
-        if estimated_prompt_tokens > 300 {  // 降低觸發閾值
-            let (multiplier, max_limit) = if is_gpt5_nano {
-                // 🔥 GPT-5 nano：特殊策略，比傳統 nano 模型更強大
-                let mult = if estimated_prompt_tokens > 2000 { 3.5 }
-                          else if estimated_prompt_tokens > 1000 { 2.5 }
-                          else { 2.0 };
-                (mult, 3000.0)  // 📈 提高上限到 3000
-            } else if is_traditional_nano {
-                // 傳統 Nano 模型：更保守的策略
-                let mult = if estimated_prompt_tokens > 1500 { 2.5 }
-                          else if estimated_prompt_tokens > 800 { 2.0 }
-                          else { 1.5 };
-                (mult, 1500.0)  // 更低的上限
-            } else if is_large_model {
-                // 大型模型：更激進的策略
-                let mult = if estimated_prompt_tokens > 2000 { 4.0 }
-                          else if estimated_prompt_tokens > 1000 { 3.0 }
-                          else { 2.0 };
-                (mult, 4000.0)
-            } else {
-                // 標準模型：平衡策略
-                let mult = if estimated_prompt_tokens > 1500 { 3.0 }
-                          else if estimated_prompt_tokens > 800 { 2.0 }
-                          else { 1.5 };
-                (mult, 2500.0)
-            };
-            
-            adjusted_max_tokens = (request.params.max_tokens as f64 * multiplier).min(max_limit) as i32;
-            log::warn!("[OpenRouterProvider] ⚠️ 模型: {}, 提示詞較長 (~{} tokens)，調整max_tokens: {} -> {} (策略: {})", 
-                request.model, estimated_prompt_tokens, request.params.max_tokens, adjusted_max_tokens,
-                if is_gpt5_nano { "GPT-5 nano增強" } 
-                else if is_traditional_nano { "傳統nano保守" } 
-                else if is_large_model { "大型激進" } 
-                else { "標準平衡" });
-        }
-        
-        log::info!("[OpenRouterProvider] 📊 Token分配: 字符數={}, 預估輸入~{} tokens, 調整後輸出限制={} tokens", 
-            char_count, estimated_prompt_tokens, adjusted_max_tokens);
-
-        // 構建訊息列表
-        let mut messages = Vec::new();
-        
-        // 添加系統提示（如果有）
-        if let Some(system_prompt) = &request.system_prompt {
-            messages.push(OpenRouterMessage {
-                role: "system".to_string(),
-                content: system_prompt.clone(),
-            });
-        }
-        
-        // 添加用戶提示
-        messages.push(OpenRouterMessage {
-            role: "user".to_string(),
-            content: request.prompt,
-        });
-
-        // 🔥 關鍵修復：確保模型ID格式正確
-        let formatted_model = Self::format_model_id(&request.model);
-        log::info!("[OpenRouterProvider] 🔧 模型ID轉換: {} -> {}", request.model, formatted_model);
-        
-        // 🔥 新增：GPT-5 模型特殊參數處理
-        let is_gpt5 = is_gpt5_model(&formatted_model);
-        let uses_completion_api = uses_completion_tokens_api(&formatted_model);
-        
-        let (final_temperature, final_top_p, final_presence_penalty, final_frequency_penalty, final_stop) = if is_gpt5 {
-            log::info!("[OpenRouterProvider] 🎯 GPT-5 模型：使用固定參數 (temperature=1.0)");
-            (1.0, None, None, None, None) // GPT-5 系列只接受預設值
-        } else {
-            (request.params.temperature, request.params.top_p, request.params.presence_penalty, request.params.frequency_penalty, request.params.stop)
-        };
-
-        let (max_tokens, max_completion_tokens) = if uses_completion_api {
-            log::info!("[OpenRouterProvider] 🆕 使用新API格式 (max_completion_tokens) 對模型: {}", formatted_model);
-            (None, Some(adjusted_max_tokens))
-        } else {
-            log::info!("[OpenRouterProvider] 📝 使用舊API格式 (max_tokens) 對模型: {}", formatted_model);
-            (Some(adjusted_max_tokens), None)
-        };
-        
-        let openrouter_request = OpenRouterRequest {
-            model: formatted_model.clone(),
-            messages,
-            temperature: final_temperature,
-            max_tokens,
-            max_completion_tokens,
-            top_p: final_top_p,
-            presence_penalty: final_presence_penalty,
-            frequency_penalty: final_frequency_penalty,
-            stop: final_stop,
-        };
+        let openrouter_request = Self::build_chat_request(&request);
+        let formatted_model = openrouter_request.model.clone();
+        let adjusted_max_tokens = openrouter_request.max_tokens
+            .or(openrouter_request.max_completion_tokens)
+            .unwrap_or(request.params.max_tokens);
 
         // 🔥 新增：記錄完整請求以便調試
         log::debug!("[OpenRouterProvider] 📤 發送請求: {}", serde_json::to_string(&openrouter_request).unwrap_or_default());
@@ -522,7 +531,7 @@ impl AIProvider for OpenRouterProvider {
                 usage: Some(usage),
                 finish_reason: response.choices.first()
                     .and_then(|c| c.finish_reason.clone()),
-            })
+            }.truncated_at_stop(request.params.stop.as_deref()))
         } else {
             // 🔥 新增：更詳細的錯誤信息，包含可能的解決方案
             let error_details = if let Some(choice) = response.choices.first() {
@@ -537,6 +546,20 @@ impl AIProvider for OpenRouterProvider {
         }
     }
 
+    fn preview_request(&self, request: &AIGenerationRequest) -> Result<serde_json::Value> {
+        let body = Self::build_chat_request(request);
+        Ok(serde_json::json!({
+            "url": format!("{}/chat/completions", self.endpoint),
+            "headers": {
+                "Authorization": format!("Bearer {}", SecurityConstants::REDACTED_API_KEY),
+                "Content-Type": "application/json",
+                "HTTP-Referer": "https://genesis-chronicle.app",
+                "X-Title": "Genesis Chronicle",
+            },
+            "body": serde_json::to_value(&body)?,
+        }))
+    }
+
     async fn validate_api_key(&self, api_key: &str) -> Result<bool> {
         log::info!("[OpenRouterProvider] 驗證 API 金鑰...");
         
@@ -602,4 +625,32 @@ impl AIProvider for OpenRouterProvider {
     fn supports_custom_endpoint(&self) -> bool {
         false // OpenRouter 使用標準端點
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::ai_providers::r#trait::test_support::{sample_request, sample_request_with_stop};
+    
+    const MODEL: &str = "anthropic/claude-3.5-sonnet";
+    
+    #[test]
+    fn test_stop_sequences_use_stop_field() {
+        let request = sample_request_with_stop(MODEL, "第二章");
+        let body = serde_json::to_value(OpenRouterProvider::build_chat_request(&request)).unwrap();
+        
+        assert_eq!(body["stop"], serde_json::json!(["第二章"]));
+    }
+    
+    #[test]
+    fn test_system_prompt_uses_system_message() {
+        let body = serde_json::to_value(OpenRouterProvider::build_chat_request(&sample_request(MODEL))).unwrap();
+        
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["role"], "system");
+        assert_eq!(messages[0]["content"], "你是一位小說家");
+        assert_eq!(messages[1]["role"], "user");
+        assert_eq!(messages[1]["content"], "繼續寫下去");
+    }
+}
@@ -14,6 +14,7 @@ impl SecurityConstants {
     pub const MAX_TOP_P: f64 = 1.0;
     pub const MIN_PENALTY: f64 = -2.0;
     pub const MAX_PENALTY: f64 = 2.0;
+    pub const MAX_STOP_SEQUENCES: usize = 4; // OpenAI 上限，各提供者中最嚴格
     
     // HTTP 安全限制
     pub const MAX_RESPONSE_SIZE_BYTES: usize = 50 * 1024 * 1024; // 50MB
@@ -24,6 +25,7 @@ impl SecurityConstants {
     pub const API_KEY_VISIBLE_PREFIX: usize = 4;
     pub const API_KEY_VISIBLE_SUFFIX: usize = 4;
     pub const API_KEY_MIN_LENGTH_TO_MASK: usize = 10;
+    pub const REDACTED_API_KEY: &'static str = "[REDACTED]"; // 請求預覽時取代金鑰
 }
 
 /// 安全工具集合
@@ -117,6 +119,20 @@ impl SecurityUtils {
             }
         }
         
+        // 驗證停止序列（如果提供）
+        if let Some(stop) = &params.stop {
+            if stop.len() > SecurityConstants::MAX_STOP_SEQUENCES {
+                return Err(anyhow!(
+                    "停止序列最多 {} 個，當前 {} 個",
+                    SecurityConstants::MAX_STOP_SEQUENCES,
+                    stop.len()
+                ));
+            }
+            if stop.iter().any(|sequence| sequence.is_empty()) {
+                return Err(anyhow!("停止序列不能為空字串"));
+            }
+        }
+        
         Ok(())
     }
     
@@ -310,5 +326,17 @@ mod tests {
             ..valid_params.clone()
         };
         assert!(SecurityUtils::validate_generation_params(&invalid_temp).is_err());
+        
+        // 停止序列數量與內容
+        let too_many_stops = AIGenerationParams {
+            stop: Some(vec!["a".to_string(); SecurityConstants::MAX_STOP_SEQUENCES + 1]),
+            ..valid_params.clone()
+        };
+        assert!(SecurityUtils::validate_generation_params(&too_many_stops).is_err());
+        let empty_stop = AIGenerationParams {
+            stop: Some(vec![String::new()]),
+            ..valid_params.clone()
+        };
+        assert!(SecurityUtils::validate_generation_params(&empty_stop).is_err());
     }
 }
\ No newline at end of file
@@ -45,6 +45,26 @@ pub struct AIGenerationResponse {
     pub finish_reason: Option<String>,
 }
 
+impl AIGenerationResponse {
+    /// 在第一個出現的停止序列處截斷輸出
+    ///
+    /// 部分模型（如 GPT-5 系列）不接受 stop 參數，截斷後各提供者的輸出行為一致；
+    /// 原生支援停止序列的提供者輸出中不會出現停止序列，此處不會有任何變更。
+    pub fn truncated_at_stop(mut self, stop: Option<&[String]>) -> Self {
+        let cut = stop
+            .unwrap_or_default()
+            .iter()
+            .filter(|sequence| !sequence.is_empty())
+            .filter_map(|sequence| self.text.find(sequence.as_str()))
+            .min();
+        if let Some(index) = cut {
+            self.text.truncate(index);
+            self.finish_reason = Some("stop".to_string());
+        }
+        self
+    }
+}
+
 /// AI 使用統計
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIUsageInfo {
@@ -159,6 +179,9 @@ pub trait AIProvider: Send + Sync {
     /// 生成文本
     async fn generate_text(&self, request: AIGenerationRequest) -> Result<AIGenerationResponse>;
     
+    /// 預覽實際送出的請求（URL、標頭與請求主體），不呼叫 API，金鑰已遮蔽
+    fn preview_request(&self, request: &AIGenerationRequest) -> Result<serde_json::Value>;
+    
     /// 驗證 API 金鑰（如果需要）
     async fn validate_api_key(&self, api_key: &str) -> Result<bool>;
     
@@ -216,4 +239,26 @@ impl AIProviderFactory {
     pub fn supported_providers() -> Vec<&'static str> {
         vec!["ollama", "openai", "gemini", "claude", "openrouter"]
     }
-}
\ No newline at end of file
+}
+/// 各提供者測試共用的請求樣本
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::{AIGenerationParams, AIGenerationRequest};
+
+    /// 帶系統提示詞與預設參數的生成請求
+    pub fn sample_request(model: &str) -> AIGenerationRequest {
+        AIGenerationRequest {
+            model: model.to_string(),
+            prompt: "繼續寫下去".to_string(),
+            system_prompt: Some("你是一位小說家".to_string()),
+            params: AIGenerationParams::default(),
+        }
+    }
+
+    /// 設定單一停止序列的生成請求
+    pub fn sample_request_with_stop(model: &str, stop: &str) -> AIGenerationRequest {
+        let mut request = sample_request(model);
+        request.params.stop = Some(vec![stop.to_string()]);
+        request
+    }
+}
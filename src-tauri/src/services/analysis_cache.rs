@@ -0,0 +1,115 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use uuid::Uuid;
+
+/// 分析快取的有效時間（小時）
+pub const ANALYSIS_CACHE_TTL_HOURS: i64 = 24 * 7;
+
+/// 組合快取鍵：project_id + analysis_type + target_id + 內容雜湊
+pub fn cache_key(project_id: &str, analysis_type: &str, target_id: &str, data_hash: &str) -> String {
+    format!("{}:{}:{}:{}", project_id, analysis_type, target_id, data_hash)
+}
+
+/// 讀取未過期的快取資料，命中時累加 hit_count 並更新 last_accessed
+pub fn lookup(conn: &Connection, key: &str) -> rusqlite::Result<Option<String>> {
+    let cached: Option<(String, String)> = conn
+        .query_row(
+            "SELECT id, cached_data FROM analysis_cache
+             WHERE cache_key = ?1 AND is_valid = 1
+               AND (expires_at IS NULL OR expires_at > datetime('now'))",
+            [key],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    let Some((id, data)) = cached else {
+        return Ok(None);
+    };
+    conn.execute(
+        "UPDATE analysis_cache SET hit_count = COALESCE(hit_count, 0) + 1, last_accessed = CURRENT_TIMESTAMP WHERE id = ?1",
+        [&id],
+    )?;
+    log::debug!("[AnalysisCache] 命中快取: {}", key);
+    Ok(Some(data))
+}
+
+/// 寫入分析結果，並移除同一目標中內容雜湊已變更（或已過期）的舊快取
+pub fn store(
+    conn: &Connection,
+    project_id: &str,
+    analysis_type: &str,
+    target_id: &str,
+    data_hash: &str,
+    data: &str,
+) -> rusqlite::Result<()> {
+    let removed = conn.execute(
+        "DELETE FROM analysis_cache
+         WHERE project_id = ?1 AND analysis_type = ?2 AND target_id = ?3
+           AND (data_hash != ?4 OR (expires_at IS NOT NULL AND expires_at <= datetime('now')))",
+        params![project_id, analysis_type, target_id, data_hash],
+    )?;
+    if removed > 0 {
+        log::info!("[AnalysisCache] 內容已變更，移除 {} 筆舊快取", removed);
+    }
+
+    conn.execute(
+        "INSERT INTO analysis_cache (
+            id, cache_key, analysis_type, project_id, target_id,
+            cached_data, data_hash, hit_count, last_accessed, expires_at, is_valid, cache_size
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, CURRENT_TIMESTAMP, datetime('now', ?8), 1, ?9)
+        ON CONFLICT(cache_key) DO UPDATE SET
+            cached_data = excluded.cached_data,
+            expires_at = excluded.expires_at,
+            is_valid = 1,
+            cache_size = excluded.cache_size",
+        params![
+            Uuid::new_v4().to_string(),
+            cache_key(project_id, analysis_type, target_id, data_hash),
+            analysis_type,
+            project_id,
+            target_id,
+            data,
+            data_hash,
+            format!("+{} hours", ANALYSIS_CACHE_TTL_HOURS),
+            data.len() as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// 清除專案的所有分析快取，回傳刪除筆數
+pub fn clear_project(conn: &Connection, project_id: &str) -> rusqlite::Result<usize> {
+    conn.execute("DELETE FROM analysis_cache WHERE project_id = ?1", [project_id])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    #[test]
+    fn test_cache_hit_invalidation_and_expiry() {
+        let conn = migrated_conn_with_project();
+
+        let old_key = cache_key("p1", "plot", "c1", "hash-a");
+        assert_eq!(lookup(&conn, &old_key).unwrap(), None);
+        store(&conn, "p1", "plot", "c1", "hash-a", r#"{"v":1}"#).unwrap();
+        assert_eq!(lookup(&conn, &old_key).unwrap().as_deref(), Some(r#"{"v":1}"#));
+        assert_eq!(lookup(&conn, &old_key).unwrap().as_deref(), Some(r#"{"v":1}"#));
+        let hits: i64 = conn
+            .query_row("SELECT hit_count FROM analysis_cache WHERE cache_key = ?1", [&old_key], |row| row.get(0))
+            .unwrap();
+        assert_eq!(hits, 2);
+
+        // 章節內容變更後，舊雜湊的快取被移除
+        store(&conn, "p1", "plot", "c1", "hash-b", r#"{"v":2}"#).unwrap();
+        assert_eq!(lookup(&conn, &old_key).unwrap(), None);
+        let new_key = cache_key("p1", "plot", "c1", "hash-b");
+        assert_eq!(lookup(&conn, &new_key).unwrap().as_deref(), Some(r#"{"v":2}"#));
+
+        // 過期的快取不會命中
+        conn.execute("UPDATE analysis_cache SET expires_at = datetime('now', '-1 hours')", []).unwrap();
+        assert_eq!(lookup(&conn, &new_key).unwrap(), None);
+
+        assert_eq!(clear_project(&conn, "p1").unwrap(), 1);
+    }
+}
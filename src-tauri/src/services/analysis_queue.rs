@@ -0,0 +1,203 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// 佇列支援的分析類型
+pub const SUPPORTED_ANALYSIS_TYPES: &[&str] = &["character", "plot"];
+
+/// 分析任務（analysis_queue 的一筆資料）
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisTask {
+    pub id: String,
+    pub project_id: String,
+    pub analysis_type: String,
+    pub target_id: Option<String>,
+    pub priority: i64,
+    pub status: String,
+    pub progress: f64,
+    pub current_step: Option<String>,
+    pub params: Option<Value>,
+    pub retry_count: i64,
+    pub max_retries: i64,
+    pub result_id: Option<String>,
+    pub error_message: Option<String>,
+    pub queued_at: String,
+    pub started_at: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+const TASK_COLUMNS: &str = "id, project_id, analysis_type, target_id, priority, status, progress, current_step,
+    params, retry_count, max_retries, result_id, error_message, queued_at, started_at, completed_at";
+
+fn task_from_row(row: &rusqlite::Row) -> rusqlite::Result<AnalysisTask> {
+    let params: Option<String> = row.get(8)?;
+    Ok(AnalysisTask {
+        id: row.get(0)?,
+        project_id: row.get(1)?,
+        analysis_type: row.get(2)?,
+        target_id: row.get(3)?,
+        priority: row.get(4)?,
+        status: row.get(5)?,
+        progress: row.get(6)?,
+        current_step: row.get(7)?,
+        params: params.and_then(|params| serde_json::from_str(&params).ok()),
+        retry_count: row.get(9)?,
+        max_retries: row.get(10)?,
+        result_id: row.get(11)?,
+        error_message: row.get(12)?,
+        queued_at: row.get(13)?,
+        started_at: row.get(14)?,
+        completed_at: row.get(15)?,
+    })
+}
+
+fn get_task(conn: &Connection, id: &str) -> rusqlite::Result<AnalysisTask> {
+    conn.query_row(
+        &format!("SELECT {} FROM analysis_queue WHERE id = ?1", TASK_COLUMNS),
+        [id],
+        task_from_row,
+    )
+}
+
+/// 加入分析任務，優先級限制在 1-10（10 最高）
+pub fn enqueue(
+    conn: &Connection,
+    project_id: &str,
+    analysis_type: &str,
+    target_id: Option<&str>,
+    priority: i64,
+    params: Option<&Value>,
+) -> rusqlite::Result<AnalysisTask> {
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO analysis_queue (id, project_id, analysis_type, target_id, priority, status, params)
+         VALUES (?1, ?2, ?3, ?4, ?5, 'pending', ?6)",
+        params![
+            id,
+            project_id,
+            analysis_type,
+            target_id,
+            priority.clamp(1, 10),
+            params.map(Value::to_string),
+        ],
+    )?;
+    get_task(conn, &id)
+}
+
+/// 取出優先級最高（同級時最早加入）的待處理任務並標記為處理中
+pub fn claim_next(conn: &Connection) -> rusqlite::Result<Option<AnalysisTask>> {
+    loop {
+        let id: Option<String> = conn
+            .query_row(
+                "SELECT id FROM analysis_queue WHERE status = 'pending'
+                 ORDER BY priority DESC, queued_at ASC, rowid ASC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        // 狀態仍為 pending 時才取得任務，避免同一任務被重複處理
+        let claimed = conn.execute(
+            "UPDATE analysis_queue
+             SET status = 'processing', progress = 0.0, current_step = '開始分析',
+                 started_at = CURRENT_TIMESTAMP, error_message = NULL
+             WHERE id = ?1 AND status = 'pending'",
+            [&id],
+        )?;
+        if claimed > 0 {
+            return get_task(conn, &id).map(Some);
+        }
+    }
+}
+
+/// 更新處理中任務的進度
+pub fn update_progress(conn: &Connection, id: &str, progress: f64, step: &str) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE analysis_queue SET progress = ?2, current_step = ?3 WHERE id = ?1",
+        params![id, progress.clamp(0.0, 1.0), step],
+    )?;
+    Ok(())
+}
+
+/// 標記任務完成並記錄結果 ID
+pub fn complete(conn: &Connection, id: &str, result_id: &str) -> rusqlite::Result<AnalysisTask> {
+    conn.execute(
+        "UPDATE analysis_queue
+         SET status = 'completed', progress = 1.0, current_step = '完成', result_id = ?2,
+             error_message = NULL, completed_at = CURRENT_TIMESTAMP
+         WHERE id = ?1",
+        params![id, result_id],
+    )?;
+    get_task(conn, id)
+}
+
+/// 記錄任務失敗：未超過重試上限時放回佇列，否則標記為 failed
+pub fn fail(conn: &Connection, id: &str, error: &str) -> rusqlite::Result<AnalysisTask> {
+    conn.execute(
+        "UPDATE analysis_queue
+         SET retry_count = COALESCE(retry_count, 0) + 1,
+             status = CASE WHEN COALESCE(retry_count, 0) + 1 < COALESCE(max_retries, 3) THEN 'pending' ELSE 'failed' END,
+             completed_at = CASE WHEN COALESCE(retry_count, 0) + 1 < COALESCE(max_retries, 3) THEN NULL ELSE CURRENT_TIMESTAMP END,
+             progress = 0.0, current_step = NULL, error_message = ?2
+         WHERE id = ?1",
+        params![id, error],
+    )?;
+    get_task(conn, id)
+}
+
+/// 將上次關閉時中斷的任務放回佇列，回傳筆數
+pub fn requeue_interrupted(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE analysis_queue SET status = 'pending', progress = 0.0, current_step = NULL WHERE status = 'processing'",
+        [],
+    )
+}
+
+/// 列出專案的分析任務（未完成的在前）
+pub fn list_project_tasks(conn: &Connection, project_id: &str) -> rusqlite::Result<Vec<AnalysisTask>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM analysis_queue WHERE project_id = ?1
+         ORDER BY CASE status WHEN 'processing' THEN 0 WHEN 'pending' THEN 1 ELSE 2 END,
+                  priority DESC, queued_at DESC",
+        TASK_COLUMNS
+    ))?;
+    let tasks = stmt.query_map([project_id], task_from_row)?;
+    tasks.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    #[test]
+    fn test_claims_by_priority_and_retries_until_limit() {
+        let conn = migrated_conn_with_project();
+
+        let low = enqueue(&conn, "p1", "plot", Some("c1"), 2, None).unwrap();
+        let high = enqueue(&conn, "p1", "plot", Some("c2"), 42, None).unwrap();
+        assert_eq!(high.priority, 10);
+
+        let task = claim_next(&conn).unwrap().unwrap();
+        assert_eq!(task.id, high.id);
+        assert_eq!(task.status, "processing");
+        update_progress(&conn, &task.id, 0.5, "分析中").unwrap();
+        let done = complete(&conn, &task.id, "result-1").unwrap();
+        assert_eq!((done.status.as_str(), done.result_id.as_deref()), ("completed", Some("result-1")));
+
+        // 失敗的任務會放回佇列，直到達到 max_retries
+        for attempt in 1..=3 {
+            let task = claim_next(&conn).unwrap().unwrap();
+            assert_eq!(task.id, low.id);
+            let failed = fail(&conn, &task.id, "AI 逾時").unwrap();
+            assert_eq!(failed.retry_count, attempt);
+            assert_eq!(failed.status, if attempt < 3 { "pending" } else { "failed" });
+        }
+        assert!(claim_next(&conn).unwrap().is_none());
+        assert_eq!(list_project_tasks(&conn, "p1").unwrap().len(), 2);
+    }
+}
@@ -1,6 +1,6 @@
 // 超長上下文處理優化器
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, BinaryHeap, HashSet};
+use std::collections::{HashMap, HashSet};
 use tauri::command;
 use std::cmp::Ordering;
 use anyhow::Result;
@@ -8,12 +8,32 @@ use anyhow::Result;
 /// 超長上下文優化器
 pub struct UltraLongContextOptimizer {
     max_tokens: usize,
+    strategy: ContextOptimizationStrategy,
     compression_levels: Vec<CompressionLevel>,
     content_analyzer: ContentAnalyzer,
     context_cache: ContextCache,
     attention_mechanism: AttentionMechanism,
 }
 
+/// 超出 token 預算時的處理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContextOptimizationStrategy {
+    /// 依重要性保留完整段落，放不下的段落直接捨棄
+    Truncate,
+    /// 從最不重要的段落開始改為摘要，全部摘要仍超出預算時才捨棄
+    Summarize,
+    /// 以七成預算保留最重要的完整段落，其餘段落改為摘要（預設）
+    #[default]
+    Hybrid,
+}
+
+/// Hybrid 策略中保留完整段落可使用的預算比例
+const HYBRID_WHOLE_BLOCK_SHARE: f32 = 0.7;
+/// 段落摘要的最大字數
+const BLOCK_SUMMARY_MAX_CHARS: usize = 60;
+/// 報告中段落預覽的字數
+const BLOCK_PREVIEW_CHARS: usize = 30;
+
 /// 壓縮等級配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressionLevel {
@@ -174,6 +194,7 @@ impl UltraLongContextOptimizer {
     pub fn new(max_tokens: usize) -> Self {
         Self {
             max_tokens,
+            strategy: ContextOptimizationStrategy::default(),
             compression_levels: Self::default_compression_levels(),
             content_analyzer: ContentAnalyzer::new(),
             context_cache: ContextCache::new(),
@@ -181,6 +202,12 @@ impl UltraLongContextOptimizer {
         }
     }
 
+    /// 設定超出預算時的處理策略
+    pub fn with_strategy(mut self, strategy: ContextOptimizationStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
     /// 獲取快取統計資訊
     #[allow(dead_code)]
     pub fn get_cache_stats(&self) -> (usize, usize) {
@@ -421,38 +448,97 @@ impl UltraLongContextOptimizer {
         Ok(self.compression_levels.last().unwrap().clone())
     }
 
-    /// 執行智能壓縮
+    /// 執行智能壓縮：依策略保留、摘要或捨棄段落，使結果符合 token 預算
     async fn execute_intelligent_compression(
         &self,
         blocks: Vec<ContentBlock>,
         compression_level: CompressionLevel,
     ) -> Result<OptimizedContext, String> {
-        // 使用二進制堆按重要性排序
-        let mut priority_queue: BinaryHeap<ContentBlock> = blocks.into_iter().collect();
+        let original_tokens = self.estimate_total_tokens(&blocks);
+        
+        // 依重要性由高到低排列
+        let mut by_importance = blocks;
+        by_importance.sort_by(|a, b| b.cmp(a));
+        
         let mut selected_blocks = Vec::new();
-        let mut current_tokens = 0;
+        let mut summarized = Vec::new();
+        let mut dropped = Vec::new();
         
-        // 選擇最重要的內容塊，直到達到token限制
-        while let Some(block) = priority_queue.pop() {
-            let block_tokens = self.estimate_block_tokens(&block);
-            
-            if current_tokens + block_tokens <= self.max_tokens {
-                current_tokens += block_tokens;
-                selected_blocks.push(block);
-            } else {
-                // 嘗試壓縮這個塊
-                if let Ok(compressed_block) = self.compress_block(&block, &compression_level).await {
-                    let compressed_tokens = self.estimate_block_tokens(&compressed_block);
-                    if current_tokens + compressed_tokens <= self.max_tokens {
-                        current_tokens += compressed_tokens;
-                        selected_blocks.push(compressed_block);
+        match self.strategy {
+            ContextOptimizationStrategy::Truncate | ContextOptimizationStrategy::Hybrid => {
+                let whole_budget = if self.strategy == ContextOptimizationStrategy::Hybrid && original_tokens > self.max_tokens {
+                    (self.max_tokens as f32 * HYBRID_WHOLE_BLOCK_SHARE) as usize
+                } else {
+                    self.max_tokens
+                };
+                
+                // 先保留最重要的完整段落
+                let mut current_tokens = 0;
+                let mut leftovers = Vec::new();
+                for block in by_importance {
+                    let block_tokens = self.estimate_block_tokens(&block);
+                    if current_tokens + block_tokens <= whole_budget {
+                        current_tokens += block_tokens;
+                        selected_blocks.push(block);
+                    } else {
+                        leftovers.push(block);
+                    }
+                }
+                
+                // 剩餘預算：Hybrid 以摘要填入，Truncate 直接捨棄
+                for block in leftovers {
+                    let block_tokens = self.estimate_block_tokens(&block);
+                    if self.strategy == ContextOptimizationStrategy::Hybrid {
+                        let summary = self.summarize_block(&block);
+                        let summary_tokens = self.estimate_block_tokens(&summary);
+                        if summary_tokens < block_tokens && current_tokens + summary_tokens <= self.max_tokens {
+                            current_tokens += summary_tokens;
+                            summarized.push(BlockChange::new(&block, summary_tokens));
+                            selected_blocks.push(summary);
+                            continue;
+                        }
+                    }
+                    if current_tokens + block_tokens <= self.max_tokens {
+                        current_tokens += block_tokens;
+                        selected_blocks.push(block);
+                    } else {
+                        dropped.push(BlockChange::new(&block, 0));
                     }
                 }
             }
+            ContextOptimizationStrategy::Summarize => {
+                // 從最不重要的段落開始改為摘要，直到符合預算
+                let mut total_tokens = original_tokens;
+                let mut plan: Vec<ContentBlock> = by_importance;
+                for block in plan.iter_mut().rev() {
+                    if total_tokens <= self.max_tokens {
+                        break;
+                    }
+                    let block_tokens = self.estimate_block_tokens(block);
+                    let summary = self.summarize_block(block);
+                    let summary_tokens = self.estimate_block_tokens(&summary);
+                    if summary_tokens < block_tokens {
+                        total_tokens -= block_tokens - summary_tokens;
+                        summarized.push(BlockChange::new(block, summary_tokens));
+                        *block = summary;
+                    }
+                }
+                
+                // 全部摘要後仍超出預算，從最不重要的段落開始捨棄
+                while total_tokens > self.max_tokens {
+                    let Some(block) = plan.pop() else { break };
+                    total_tokens -= self.estimate_block_tokens(&block);
+                    summarized.retain(|change| change.block_id != block.id);
+                    dropped.push(BlockChange::new(&block, 0));
+                }
+                selected_blocks = plan;
+            }
         }
         
         // 按時間順序重新排列
         selected_blocks.sort_by_key(|block| block.temporal_position);
+        summarized.sort_by_key(|change| change.temporal_position);
+        dropped.sort_by_key(|change| change.temporal_position);
         
         // 組合最終內容
         let final_content = selected_blocks
@@ -460,12 +546,13 @@ impl UltraLongContextOptimizer {
             .map(|block| block.content.clone())
             .collect::<Vec<String>>()
             .join("\n\n");
+        let final_tokens = self.estimate_total_tokens(&selected_blocks);
         
         Ok(OptimizedContext {
             content: final_content,
-            original_token_count: priority_queue.len() * 50, // 估算
-            final_token_count: current_tokens,
-            compression_ratio: current_tokens as f32 / (priority_queue.len() * 50) as f32,
+            original_token_count: original_tokens,
+            final_token_count: final_tokens,
+            compression_ratio: if original_tokens == 0 { 1.0 } else { final_tokens as f32 / original_tokens as f32 },
             compression_level: compression_level.level,
             quality_score: self.calculate_context_quality(&selected_blocks),
             preserved_elements: self.identify_preserved_elements(&selected_blocks),
@@ -476,6 +563,14 @@ impl UltraLongContextOptimizer {
                 attention_applied: true,
                 compression_strategies_used: compression_level.strategies.clone(),
             },
+            report: OptimizationReport {
+                strategy: self.strategy,
+                target_tokens: self.max_tokens,
+                estimated_tokens: final_tokens,
+                kept_blocks: selected_blocks.len() - summarized.len(),
+                summarized_blocks: summarized,
+                dropped_blocks: dropped,
+            },
         })
     }
 
@@ -578,9 +673,22 @@ impl UltraLongContextOptimizer {
         block.content.chars().count() / 2
     }
 
-    async fn compress_block(&self, block: &ContentBlock, _level: &CompressionLevel) -> Result<ContentBlock, String> {
-        // TODO: 實作塊壓縮
-        Ok(block.clone())
+    /// 擷取式摘要：保留段落的第一句（最多 BLOCK_SUMMARY_MAX_CHARS 字）
+    fn summarize_block(&self, block: &ContentBlock) -> ContentBlock {
+        let text = block.content.trim();
+        let first_sentence_end = text
+            .char_indices()
+            .find(|(_, c)| matches!(c, '。' | '！' | '？' | '」' | '.' | '!' | '?' | '\n'))
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(text.len());
+        let mut summary: String = text[..first_sentence_end].chars().take(BLOCK_SUMMARY_MAX_CHARS).collect();
+        if summary.len() < text.len() {
+            summary.push_str("……");
+        }
+        ContentBlock {
+            content: summary,
+            ..block.clone()
+        }
     }
 
     fn calculate_context_quality(&self, _blocks: &[ContentBlock]) -> f32 {
@@ -828,6 +936,44 @@ pub struct OptimizedContext {
     pub preserved_elements: Vec<String>,
     pub lost_elements: Vec<String>,
     pub optimization_stats: OptimizationStats,
+    pub report: OptimizationReport,
+}
+
+/// 優化報告：使用的策略、預算與被摘要 / 捨棄的段落
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationReport {
+    pub strategy: ContextOptimizationStrategy,
+    pub target_tokens: usize,
+    /// 優化後的估計 token 數（中文約 2 字符 = 1 token）
+    pub estimated_tokens: usize,
+    /// 原文保留的段落數
+    pub kept_blocks: usize,
+    pub summarized_blocks: Vec<BlockChange>,
+    pub dropped_blocks: Vec<BlockChange>,
+}
+
+/// 被摘要或捨棄的段落
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockChange {
+    pub block_id: String,
+    /// 段落在原文中的順序
+    pub temporal_position: usize,
+    pub original_tokens: usize,
+    /// 摘要後的 token 數（捨棄時為 0）
+    pub final_tokens: usize,
+    pub preview: String,
+}
+
+impl BlockChange {
+    fn new(block: &ContentBlock, final_tokens: usize) -> Self {
+        Self {
+            block_id: block.id.clone(),
+            temporal_position: block.temporal_position,
+            original_tokens: block.content.chars().count() / 2,
+            final_tokens,
+            preview: block.content.trim().chars().take(BLOCK_PREVIEW_CHARS).collect(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -839,18 +985,81 @@ pub struct OptimizationStats {
 }
 
 /// Tauri 命令：優化超長上下文
+///
+/// `target_tokens` 為目標模型可用的 token 預算（未提供時使用 `max_tokens`），
+/// `strategy` 決定超出預算時的處理方式（預設 Hybrid）。
+///
+/// 限制：
+/// - token 數以「中文約 2 字符 = 1 token」估算，與實際模型的計算可能有落差
+/// - 以空行分段，段落重要性為關鍵詞啟發式評分
+/// - 摘要為擷取段落首句，不會呼叫 AI
+/// - 原文未超出預算時不會做任何刪減
 #[command]
 pub async fn optimize_ultra_long_context_command(
     original_context: String,
     max_tokens: usize,
     focus_characters: Vec<String>,
     current_position: usize,
+    target_tokens: Option<usize>,
+    strategy: Option<ContextOptimizationStrategy>,
 ) -> Result<OptimizedContext, String> {
-    let mut optimizer = UltraLongContextOptimizer::new(max_tokens);
+    let budget = target_tokens.unwrap_or(max_tokens);
+    if budget == 0 {
+        return Err("token 預算必須大於 0".to_string());
+    }
+    let mut optimizer = UltraLongContextOptimizer::new(budget).with_strategy(strategy.unwrap_or_default());
     
     optimizer.optimize_ultra_long_context(
         &original_context,
         &focus_characters,
         current_position,
     ).await
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn long_context() -> String {
+        (0..10)
+            .map(|i| format!("第{}段開頭。{}", i, "這是一段很長的描述文字，".repeat(10)))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    async fn optimize(strategy: ContextOptimizationStrategy, budget: usize) -> OptimizedContext {
+        UltraLongContextOptimizer::new(budget)
+            .with_strategy(strategy)
+            .optimize_ultra_long_context(&long_context(), &[], 0)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_strategies_respect_token_budget() {
+        let truncated = optimize(ContextOptimizationStrategy::Truncate, 200).await;
+        assert!(truncated.final_token_count <= 200);
+        assert!(truncated.report.summarized_blocks.is_empty());
+        assert!(!truncated.report.dropped_blocks.is_empty());
+        assert!(truncated.original_token_count > 200);
+
+        let summarized = optimize(ContextOptimizationStrategy::Summarize, 200).await;
+        assert!(summarized.final_token_count <= 200);
+        assert!(summarized.report.dropped_blocks.is_empty());
+        assert!(!summarized.report.summarized_blocks.is_empty());
+        assert!(summarized.content.contains("段開頭。……"));
+
+        let hybrid = optimize(ContextOptimizationStrategy::Hybrid, 200).await;
+        assert_eq!(hybrid.report.estimated_tokens, hybrid.final_token_count);
+        assert!(hybrid.final_token_count <= 200);
+        assert!(hybrid.report.kept_blocks > 0 && !hybrid.report.summarized_blocks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_large_budget_keeps_everything() {
+        let result = optimize(ContextOptimizationStrategy::Hybrid, 200_000).await;
+        assert_eq!(result.content, long_context());
+        assert_eq!(result.report.kept_blocks, 10);
+        assert!(result.report.summarized_blocks.is_empty() && result.report.dropped_blocks.is_empty());
+    }
+}
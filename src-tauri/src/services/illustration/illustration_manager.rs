@@ -166,6 +166,23 @@ pub struct ConsistencyAnalysis {
     pub reference_image_similarity: Option<f64>,
 }
 
+/// 提示詞預覽中的一個處理步驟
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTransformation {
+    pub stage: String, // "translation", "template", "style", "optimization", "consistency", "negative_prompt"
+    pub description: String,
+    pub output: Option<String>,
+}
+
+/// 插畫提示詞預覽（實際會送出的內容，不呼叫圖像 API）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IllustrationPromptPreview {
+    pub positive_prompt: String,
+    pub negative_prompt: Option<String>,
+    pub seed: Option<u32>,
+    pub transformations: Vec<PromptTransformation>,
+}
+
 /// 生成元數據
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GenerationMetadata {
@@ -308,6 +325,91 @@ impl IllustrationManager {
         Ok(result)
     }
     
+    /// 預覽實際會送往圖像 API 的提示詞、負面提示詞與 seed
+    ///
+    /// 與 [`generate_illustration`](Self::generate_illustration) 使用相同的翻譯、模板與優化步驟；
+    /// 角色 seed 只查詢不建立，因此預覽不會改變角色的一致性設定。
+    pub async fn preview_prompt(&self, request: &EnhancedIllustrationRequest) -> Result<IllustrationPromptPreview> {
+        let mut transformations = Vec::new();
+        
+        let translation = self.translate_and_apply_template(request).await?;
+        transformations.push(match &translation.applied_template {
+            Some(template_id) => PromptTransformation {
+                stage: "template".to_string(),
+                description: format!("套用模板 {}（含品質修飾詞與模板負面提示詞）", template_id),
+                output: Some(translation.translated_prompt.clone()),
+            },
+            None => PromptTransformation {
+                stage: "translation".to_string(),
+                description: "中文描述經詞彙庫翻譯為英文提示詞".to_string(),
+                output: Some(translation.translated_prompt.clone()),
+            },
+        });
+        transformations.push(PromptTransformation {
+            stage: "style".to_string(),
+            description: format!(
+                "翻譯風格: {:?}",
+                StyleResolver::resolve_translation_style(request.translation_style.as_deref())
+            ),
+            output: None,
+        });
+        
+        let optimization = self.optimize_prompt(&translation.translated_prompt, request).await?;
+        transformations.push(PromptTransformation {
+            stage: "optimization".to_string(),
+            description: if optimization.applied_optimizations.is_empty() {
+                "提示詞優化（無變更）".to_string()
+            } else {
+                format!("提示詞優化: {}", optimization.applied_optimizations.join("、"))
+            },
+            output: Some(optimization.optimized_prompt.clone()),
+        });
+        
+        let seed = if let Some(seed) = request.basic_request.seed {
+            transformations.push(PromptTransformation {
+                stage: "consistency".to_string(),
+                description: "使用請求指定的 seed".to_string(),
+                output: Some(seed.to_string()),
+            });
+            Some(seed)
+        } else if let Some(character_id) = &request.basic_request.character_id {
+            let (seed, exists) = self.seed_manager.peek_seed(character_id, "Character")?;
+            transformations.push(PromptTransformation {
+                stage: "consistency".to_string(),
+                description: if exists {
+                    "使用角色已儲存的一致性 seed".to_string()
+                } else {
+                    "角色尚無 seed，首次生成時會建立此 seed".to_string()
+                },
+                output: Some(seed.to_string()),
+            });
+            Some(seed)
+        } else {
+            None
+        };
+        
+        let negative_prompt = Self::negative_prompt_for(request);
+        if let Some(negative_prompt) = &negative_prompt {
+            transformations.push(PromptTransformation {
+                stage: "negative_prompt".to_string(),
+                description: "使用自訂負面提示詞".to_string(),
+                output: Some(negative_prompt.clone()),
+            });
+        }
+        
+        Ok(IllustrationPromptPreview {
+            positive_prompt: optimization.optimized_prompt,
+            negative_prompt,
+            seed,
+            transformations,
+        })
+    }
+    
+    /// 實際送往圖像 API 的負面提示詞
+    fn negative_prompt_for(request: &EnhancedIllustrationRequest) -> Option<String> {
+        request.custom_negative_prompt.clone()
+    }
+    
     /// 翻譯和應用模板
     async fn translate_and_apply_template(&self, request: &EnhancedIllustrationRequest) -> Result<TranslationInfo> {
         log::info!("[IllustrationManager] 翻譯描述: {}", request.basic_request.scene_description);
@@ -336,6 +438,7 @@ impl IllustrationManager {
                 quality_level: crate::services::translation::QualityLevel::High,
                 context_hints: Vec::new(),
                 preserve_original: false,
+                locked_terms: std::collections::HashMap::new(),
             };
             
             let translation_result = self.translation_engine.translate(translation_request)?;
@@ -387,11 +490,16 @@ impl IllustrationManager {
         let mut consistency_score = 1.0;
         let mut visual_traits_match = HashMap::new();
         
+        // 批次變體指定的 seed 優先於角色 seed
+        if let Some(seed) = request.basic_request.seed {
+            character_seed = Some(seed);
+        }
+        
         // 如果有角色ID，獲取一致性種子
         if let Some(character_id) = &request.basic_request.character_id {
             // 使用實際存在的方法，需要角色名稱（簡化處理）
             if let Ok(seed) = self.seed_manager.get_or_create_seed(character_id, "Character") {
-                character_seed = Some(seed);
+                character_seed = character_seed.or(Some(seed));
                 consistency_score = 0.95; // 有種子的一致性分數較高
             }
             
@@ -420,18 +528,17 @@ impl IllustrationManager {
             .ok_or_else(|| IllustrationError::Config("Imagen API 服務未初始化".to_string()))?;
         
         // 構建生成配置
-        let aspect_ratio = match request.aspect_ratio.as_deref() {
-            Some("portrait") => AspectRatio::Portrait,
-            Some("landscape") => AspectRatio::Landscape,
-            Some("square") => AspectRatio::Square,
-            _ => AspectRatio::Square,
-        };
-        
-        let safety_level = match request.safety_level.as_deref() {
-            Some("block_few") => SafetyLevel::BlockFew,
-            Some("block_some") => SafetyLevel::BlockSome,
-            _ => SafetyLevel::BlockMost,
-        };
+        let aspect_ratio = request.aspect_ratio.as_deref()
+            .map(AspectRatio::parse)
+            .transpose()
+            .map_err(IllustrationError::Config)?
+            .unwrap_or(AspectRatio::Square);
+        
+        let safety_level = request.safety_level.as_deref()
+            .map(SafetyLevel::parse)
+            .transpose()
+            .map_err(IllustrationError::Config)?
+            .unwrap_or(SafetyLevel::BlockMost);
         
         let config = ImageGenerationConfig {
             model: "imagen-3.0-generate-001".to_string(),
@@ -445,7 +552,7 @@ impl IllustrationManager {
         
         let generation_request = ImageGenerationRequest {
             prompt: prompt.to_string(),
-            negative_prompt: request.custom_negative_prompt.clone(),
+            negative_prompt: Self::negative_prompt_for(request),
             config,
             character_seed: consistency.character_seed,
             style_reference: None,
@@ -489,15 +596,17 @@ impl IllustrationManager {
         Ok(processed_images)
     }
     
-    /// 保存圖像到本地
+    /// 保存圖像到插畫儲存目錄（依 illustration_storage_path 設定），回傳絕對路徑
     async fn save_image_to_local(&self, image_data: &str, image_id: &str) -> Result<String> {
-        use std::path::Path;
-        
-        // 創建保存目錄
-        let save_dir = Path::new("generated_images");
-        if !save_dir.exists() {
-            std::fs::create_dir_all(save_dir)?;
-        }
+        let save_dir = crate::commands::illustration::illustration_storage_dir()
+            .map_err(IllustrationError::Unknown)?;
+        std::fs::create_dir_all(&save_dir)?;
+        // 設定為相對路徑時也記錄絕對路徑，之後讀取不受工作目錄影響
+        let save_dir = if save_dir.is_relative() {
+            std::env::current_dir()?.join(save_dir)
+        } else {
+            save_dir
+        };
         
         // 解碼 Base64 圖像
         let image_bytes = base64::engine::general_purpose::STANDARD.decode(image_data)
@@ -0,0 +1,239 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+/// 嵌入圖像檔的生成參數（JPEG 註解區段 / PNG iTXt 區塊，內容為 JSON）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IllustrationMetadata {
+    pub prompt: String,
+    pub seed: Option<u32>,
+    pub model: String,
+    pub app_version: String,
+}
+
+impl IllustrationMetadata {
+    pub fn new(prompt: &str, seed: Option<u32>, model: &str) -> Self {
+        Self {
+            prompt: prompt.to_string(),
+            seed,
+            model: model.to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// JPEG 註解前綴與 PNG iTXt 關鍵字
+const METADATA_KEYWORD: &str = "genesis-chronicle";
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_SOI: &[u8] = &[0xFF, 0xD8];
+const JPEG_COM: u8 = 0xFE;
+const JPEG_SOS: u8 = 0xDA;
+
+/// 將生成參數寫入圖像資料（取代先前寫入的同名元資料），僅支援 JPEG 與 PNG
+pub fn embed_metadata(data: &[u8], metadata: &IllustrationMetadata) -> Result<Vec<u8>> {
+    let json = serde_json::to_string(metadata)?;
+    if data.starts_with(PNG_SIGNATURE) {
+        embed_png(data, &json)
+    } else if data.starts_with(JPEG_SOI) {
+        embed_jpeg(data, &json)
+    } else {
+        Err(anyhow!("不支援的圖像格式，無法寫入元資料"))
+    }
+}
+
+/// 讀取圖像資料中的生成參數，沒有寫入過時回傳 None
+pub fn read_metadata(data: &[u8]) -> Result<Option<IllustrationMetadata>> {
+    let json = if data.starts_with(PNG_SIGNATURE) {
+        png_chunks(data)?
+            .into_iter()
+            .find_map(|chunk| parse_itxt(chunk.kind, chunk.data))
+    } else if data.starts_with(JPEG_SOI) {
+        jpeg_segments(data)?
+            .into_iter()
+            .find_map(|segment| parse_jpeg_comment(segment.marker, segment.payload))
+    } else {
+        return Err(anyhow!("不支援的圖像格式"));
+    };
+
+    json.map(|json| serde_json::from_str(&json).map_err(|e| anyhow!("元資料格式錯誤: {}", e)))
+        .transpose()
+}
+
+struct PngChunk<'a> {
+    kind: &'a [u8],
+    data: &'a [u8],
+    /// 含長度、類型與 CRC 的完整區塊
+    raw: &'a [u8],
+}
+
+fn png_chunks(data: &[u8]) -> Result<Vec<PngChunk<'_>>> {
+    let mut chunks = Vec::new();
+    let mut pos = PNG_SIGNATURE.len();
+    while pos < data.len() {
+        let header = data.get(pos..pos + 8).ok_or_else(|| anyhow!("PNG 區塊標頭不完整"))?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let end = pos + 12 + length;
+        let raw = data.get(pos..end).ok_or_else(|| anyhow!("PNG 區塊長度超出檔案範圍"))?;
+        chunks.push(PngChunk {
+            kind: &raw[4..8],
+            data: &raw[8..8 + length],
+            raw,
+        });
+        pos = end;
+    }
+    Ok(chunks)
+}
+
+fn parse_itxt(kind: &[u8], data: &[u8]) -> Option<String> {
+    if kind != b"iTXt" {
+        return None;
+    }
+    // keyword\0 compression_flag compression_method language\0 translated_keyword\0 text
+    let rest = data.strip_prefix(METADATA_KEYWORD.as_bytes())?.strip_prefix(&[0, 0, 0])?;
+    let language_end = rest.iter().position(|&b| b == 0)?;
+    let rest = &rest[language_end + 1..];
+    let translated_end = rest.iter().position(|&b| b == 0)?;
+    String::from_utf8(rest[translated_end + 1..].to_vec()).ok()
+}
+
+fn embed_png(data: &[u8], json: &str) -> Result<Vec<u8>> {
+    let chunks = png_chunks(data)?;
+    if chunks.first().map(|chunk| chunk.kind) != Some(b"IHDR".as_slice()) {
+        return Err(anyhow!("PNG 缺少 IHDR 區塊"));
+    }
+
+    let mut chunk_data = Vec::with_capacity(METADATA_KEYWORD.len() + json.len() + 5);
+    chunk_data.extend_from_slice(METADATA_KEYWORD.as_bytes());
+    chunk_data.extend_from_slice(&[0, 0, 0, 0, 0]);
+    chunk_data.extend_from_slice(json.as_bytes());
+
+    let mut output = Vec::with_capacity(data.len() + chunk_data.len() + 12);
+    output.extend_from_slice(PNG_SIGNATURE);
+    for (index, chunk) in chunks.iter().enumerate() {
+        if parse_itxt(chunk.kind, chunk.data).is_some() {
+            continue;
+        }
+        output.extend_from_slice(chunk.raw);
+        if index == 0 {
+            output.extend_from_slice(&(chunk_data.len() as u32).to_be_bytes());
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(b"iTXt");
+            hasher.update(&chunk_data);
+            output.extend_from_slice(b"iTXt");
+            output.extend_from_slice(&chunk_data);
+            output.extend_from_slice(&hasher.finalize().to_be_bytes());
+        }
+    }
+    Ok(output)
+}
+
+struct JpegSegment<'a> {
+    marker: u8,
+    payload: &'a [u8],
+    /// 區段在原始資料中的起訖位置（含標記）
+    start: usize,
+    end: usize,
+}
+
+/// 解析 SOS 之前的標頭區段
+fn jpeg_segments(data: &[u8]) -> Result<Vec<JpegSegment<'_>>> {
+    let mut segments = Vec::new();
+    let mut pos = JPEG_SOI.len();
+    loop {
+        let header = data.get(pos..pos + 4).ok_or_else(|| anyhow!("JPEG 區段標頭不完整"))?;
+        if header[0] != 0xFF {
+            return Err(anyhow!("JPEG 區段標記錯誤"));
+        }
+        let marker = header[1];
+        if marker == JPEG_SOS {
+            return Ok(segments);
+        }
+        let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let end = pos + 2 + length;
+        let payload = data.get(pos + 4..end).ok_or_else(|| anyhow!("JPEG 區段長度超出檔案範圍"))?;
+        segments.push(JpegSegment { marker, payload, start: pos, end });
+        pos = end;
+    }
+}
+
+fn parse_jpeg_comment(marker: u8, payload: &[u8]) -> Option<String> {
+    if marker != JPEG_COM {
+        return None;
+    }
+    let json = payload.strip_prefix(METADATA_KEYWORD.as_bytes())?.strip_prefix(b":")?;
+    String::from_utf8(json.to_vec()).ok()
+}
+
+fn embed_jpeg(data: &[u8], json: &str) -> Result<Vec<u8>> {
+    let comment = format!("{}:{}", METADATA_KEYWORD, json);
+    // 區段長度欄位為 16 位元（含自身 2 bytes）
+    if comment.len() > u16::MAX as usize - 2 {
+        return Err(anyhow!("元資料過長，無法寫入 JPEG 註解"));
+    }
+
+    let segments = jpeg_segments(data)?;
+    // JFIF/EXIF 等 APPn 區段須緊接在 SOI 之後，註解放在它們後面
+    let insert_at = segments
+        .iter()
+        .take_while(|segment| (0xE0..=0xEF).contains(&segment.marker))
+        .last()
+        .map(|segment| segment.end)
+        .unwrap_or(JPEG_SOI.len());
+
+    let mut output = Vec::with_capacity(data.len() + comment.len() + 4);
+    output.extend_from_slice(&data[..insert_at]);
+    output.extend_from_slice(&[0xFF, JPEG_COM]);
+    output.extend_from_slice(&((comment.len() + 2) as u16).to_be_bytes());
+    output.extend_from_slice(comment.as_bytes());
+
+    // 略過先前寫入的元資料註解（APPn 之後才可能出現註解區段）
+    let mut pos = insert_at;
+    for segment in segments
+        .iter()
+        .filter(|segment| parse_jpeg_comment(segment.marker, segment.payload).is_some())
+    {
+        output.extend_from_slice(&data[pos..segment.start]);
+        pos = segment.end;
+    }
+    output.extend_from_slice(&data[pos..]);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(format: image::ImageFormat) -> Vec<u8> {
+        let mut data = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(8, 8, image::Rgb([10, 20, 30]))
+            .write_to(&mut data, format)
+            .unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn test_metadata_round_trip_keeps_image_decodable() {
+        let metadata = IllustrationMetadata::new("月光下的精靈少女", Some(42), "flux");
+        for format in [image::ImageFormat::Jpeg, image::ImageFormat::Png] {
+            let original = encode(format);
+            assert_eq!(read_metadata(&original).unwrap(), None);
+
+            let embedded = embed_metadata(&original, &metadata).unwrap();
+            assert_eq!(read_metadata(&embedded).unwrap(), Some(metadata.clone()));
+            assert!(image::load_from_memory(&embedded).is_ok(), "{:?}", format);
+
+            // 重複寫入時取代舊的元資料
+            let updated = IllustrationMetadata::new("new prompt", None, "sdxl");
+            let rewritten = embed_metadata(&embedded, &updated).unwrap();
+            assert_eq!(read_metadata(&rewritten).unwrap(), Some(updated.clone()));
+            assert_eq!(rewritten, embed_metadata(&original, &updated).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_unsupported_format_is_rejected() {
+        let metadata = IllustrationMetadata::new("prompt", None, "flux");
+        assert!(embed_metadata(b"RIFF....WEBP", &metadata).is_err());
+        assert!(read_metadata(b"not an image").is_err());
+    }
+}
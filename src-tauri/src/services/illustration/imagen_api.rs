@@ -17,6 +17,70 @@ pub struct ImagenApiService {
     default_config: ImageGenerationConfig,
 }
 
+/// API 金鑰的配額／計費狀態
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiQuotaInfo {
+    pub status: String, // "ok", "quota_exceeded", "billing_disabled"
+    pub message: Option<String>,
+}
+
+/// API 金鑰檢查結果（不產生圖像、不使用資料庫）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiKeyCheck {
+    pub valid: bool,
+    pub error: Option<String>,
+    pub quota_info: Option<ApiQuotaInfo>,
+}
+
+impl ApiKeyCheck {
+    fn invalid(error: impl Into<String>) -> Self {
+        Self { valid: false, error: Some(error.into()), quota_info: None }
+    }
+
+    /// 依模型查詢端點的 HTTP 狀態與 Google API 錯誤內容判斷金鑰狀態
+    ///
+    /// 配額用盡（429 / RESOURCE_EXHAUSTED）與未啟用計費（BILLING_DISABLED）代表金鑰本身有效但沒有可用額度。
+    pub fn from_response(status: u16, body: &str) -> Self {
+        if (200..300).contains(&status) {
+            return Self {
+                valid: true,
+                error: None,
+                quota_info: Some(ApiQuotaInfo { status: "ok".to_string(), message: None }),
+            };
+        }
+
+        let error: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+        let error = &error["error"];
+        let message = error["message"].as_str().map(str::to_string);
+        let api_status = error["status"].as_str().unwrap_or_default();
+        let reasons: Vec<&str> = error["details"]
+            .as_array()
+            .map(|details| details.iter().filter_map(|detail| detail["reason"].as_str()).collect())
+            .unwrap_or_default();
+
+        let quota_status = if status == 429 || api_status == "RESOURCE_EXHAUSTED" {
+            Some("quota_exceeded")
+        } else if reasons.contains(&"BILLING_DISABLED") {
+            Some("billing_disabled")
+        } else {
+            None
+        };
+
+        match quota_status {
+            Some(quota_status) => Self {
+                valid: true,
+                error: message.clone(),
+                quota_info: Some(ApiQuotaInfo { status: quota_status.to_string(), message }),
+            },
+            None => Self::invalid(format!(
+                "API 錯誤 {}: {}",
+                status,
+                message.unwrap_or_else(|| body.chars().take(200).collect())
+            )),
+        }
+    }
+}
+
 /// 圖像生成配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImageGenerationConfig {
@@ -55,6 +119,46 @@ pub enum SafetyLevel {
     BlockFew,
 }
 
+impl AspectRatio {
+    /// 前端可傳入的比例名稱（亦接受對應的 "1:1" 等比例寫法）
+    pub const VALID_NAMES: &'static [&'static str] = &["square", "portrait", "landscape", "standard", "tall"];
+
+    /// 解析比例名稱或比例寫法，未知值回傳列出可用值的錯誤
+    pub fn parse(value: &str) -> std::result::Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "square" | "1:1" => Ok(Self::Square),
+            "portrait" | "9:16" => Ok(Self::Portrait),
+            "landscape" | "16:9" => Ok(Self::Landscape),
+            "standard" | "4:3" => Ok(Self::Standard),
+            "tall" | "3:4" => Ok(Self::Tall),
+            _ => Err(format!(
+                "無效的圖像比例: {}（可用: {}、1:1、9:16、16:9、4:3、3:4）",
+                value,
+                Self::VALID_NAMES.join("、")
+            )),
+        }
+    }
+}
+
+impl SafetyLevel {
+    /// 可用的安全過濾等級
+    pub const VALID_NAMES: &'static [&'static str] = &["block_most", "block_some", "block_few"];
+
+    /// 解析安全過濾等級，未知值回傳列出可用值的錯誤
+    pub fn parse(value: &str) -> std::result::Result<Self, String> {
+        match value.trim().to_lowercase().as_str() {
+            "block_most" => Ok(Self::BlockMost),
+            "block_some" => Ok(Self::BlockSome),
+            "block_few" => Ok(Self::BlockFew),
+            _ => Err(format!(
+                "無效的安全過濾等級: {}（可用: {}）",
+                value,
+                Self::VALID_NAMES.join("、")
+            )),
+        }
+    }
+}
+
 /// 人物生成設定
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PersonGeneration {
@@ -569,6 +673,35 @@ impl ImagenApiService {
         }
     }
     
+    /// 以查詢模型資訊的請求檢查 API 金鑰，不產生圖像也不計費
+    pub async fn check_api_key(&self) -> ApiKeyCheck {
+        let project_id = match self.get_project_id() {
+            Ok(project_id) => project_id,
+            Err(e) => return ApiKeyCheck::invalid(e.to_string()),
+        };
+        let url = format!(
+            "{}/projects/{}/locations/us-central1/publishers/google/models/{}",
+            self.base_url, project_id, self.default_config.model
+        );
+        
+        let response = match self.client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .timeout(std::time::Duration::from_secs(15))
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => return ApiKeyCheck::invalid(format!("API 請求失敗: {}", e)),
+        };
+        
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        let check = ApiKeyCheck::from_response(status, &body);
+        log::info!("[ImagenApi] API 金鑰檢查: HTTP {}，有效: {}", status, check.valid);
+        check
+    }
+    
     /// 獲取支援的模型列表
     #[allow(dead_code)]
     pub fn get_supported_models(&self) -> Vec<String> {
@@ -622,4 +755,51 @@ struct ApiSafetyRating {
     category: String,
     probability: String,
     blocked: Option<bool>,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aspect_ratio_and_safety_level_parsing() {
+        assert!(matches!(AspectRatio::parse("Portrait"), Ok(AspectRatio::Portrait)));
+        assert!(matches!(AspectRatio::parse("16:9"), Ok(AspectRatio::Landscape)));
+        let err = AspectRatio::parse("landscpae").unwrap_err();
+        assert!(err.contains("landscpae") && err.contains("square"));
+
+        assert!(matches!(SafetyLevel::parse("block_few"), Ok(SafetyLevel::BlockFew)));
+        let err = SafetyLevel::parse("block_all").unwrap_err();
+        assert!(err.contains("block_most") && err.contains("block_some"));
+    }
+
+    #[test]
+    fn test_api_key_check_classifies_google_errors() {
+        let ok = ApiKeyCheck::from_response(200, "{}");
+        assert!(ok.valid);
+        assert_eq!(ok.quota_info.unwrap().status, "ok");
+
+        let exhausted = ApiKeyCheck::from_response(
+            429,
+            r#"{"error":{"code":429,"message":"Quota exceeded","status":"RESOURCE_EXHAUSTED"}}"#,
+        );
+        assert!(exhausted.valid);
+        assert_eq!(exhausted.quota_info.unwrap().status, "quota_exceeded");
+
+        let billing = ApiKeyCheck::from_response(
+            403,
+            r#"{"error":{"code":403,"message":"Billing required","status":"PERMISSION_DENIED",
+                "details":[{"@type":"type.googleapis.com/google.rpc.ErrorInfo","reason":"BILLING_DISABLED"}]}}"#,
+        );
+        assert!(billing.valid);
+        assert_eq!(billing.quota_info.unwrap().message.as_deref(), Some("Billing required"));
+
+        let invalid = ApiKeyCheck::from_response(
+            401,
+            r#"{"error":{"code":401,"message":"Request had invalid authentication credentials.","status":"UNAUTHENTICATED"}}"#,
+        );
+        assert!(!invalid.valid);
+        assert!(invalid.quota_info.is_none());
+        assert!(invalid.error.unwrap().contains("invalid authentication"));
+    }
+}
@@ -16,19 +16,23 @@ pub mod pollinations_api;
 pub mod illustration_manager;
 pub mod batch_manager;
 pub mod style_resolver;
+pub mod thumbnail;
+pub mod image_metadata;
+pub mod quota;
+pub mod storage_audit;
 
 pub use character_consistency::CharacterConsistencyManager;
-pub use seed_manager::SeedManager;
+pub use seed_manager::{SeedManager, MAX_BATCH_SEEDS, DEFAULT_DIVERSE_SEED_SPACING};
 pub use visual_traits::{VisualTraits, VisualTraitsManager};
 pub use imagen_api::{
     ImagenApiService, ImageGenerationRequest, ImageGenerationResponse, 
-    ImageGenerationConfig, AspectRatio, SafetyLevel, PersonGeneration
+    ImageGenerationConfig, AspectRatio, SafetyLevel, PersonGeneration, ApiKeyCheck
 };
 pub use pollinations_api::{
     PollinationsApiService, PollinationsRequest, PollinationsModel
 };
 pub use illustration_manager::{
-    IllustrationManager, EnhancedIllustrationRequest
+    IllustrationManager, EnhancedIllustrationRequest, IllustrationPromptPreview
 };
 pub use batch_manager::{
     BatchManager, BatchRequest, TaskPriority
@@ -46,6 +50,9 @@ pub struct IllustrationRequest {
     pub use_reference_image: bool,
     pub quality_preset: String, // "speed", "balanced", "quality"
     pub batch_size: Option<u32>,
+    /// 指定 seed（批次變體用），優先於角色的一致性 seed
+    #[serde(default)]
+    pub seed: Option<u32>,
 }
 
 /// 插畫生成響應結構
@@ -107,6 +114,9 @@ pub enum IllustrationError {
     #[error("配置錯誤: {0}")]
     Config(String),
     
+    #[error("QuotaExceeded: 專案每日插畫配額已用完（已使用 {used} / 上限 {limit}）")]
+    QuotaExceeded { used: i64, limit: i64 },
+    
     #[error("未知錯誤: {0}")]
     Unknown(String),
 }
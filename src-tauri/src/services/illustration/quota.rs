@@ -0,0 +1,198 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::{IllustrationError, Result};
+
+/// 專案每日插畫配額（project_illustration_settings）
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectQuota {
+    pub limit: i64,
+    pub used: i64,
+}
+
+impl ProjectQuota {
+    pub fn remaining(&self) -> i64 {
+        (self.limit - self.used).max(0)
+    }
+}
+
+/// 配額重置日期早於今天（或尚未設定）時視為新的一天，用量從 0 起算
+const QUOTA_IS_STALE: &str = "(quota_reset_date IS NULL OR quota_reset_date < DATE('now', 'localtime'))";
+
+/// 讀取專案配額；重置日期已過（或尚未設定）時以 0 計算用量，不需寫入資料庫
+///
+/// 專案沒有插畫設定時回傳 None，代表不限制。
+pub fn refresh_project_quota(conn: &Connection, project_id: &str) -> Result<Option<ProjectQuota>> {
+    let quota = conn
+        .query_row(
+            &format!(
+                "SELECT COALESCE(api_quota_limit, 100),
+                        CASE WHEN {} THEN 0 ELSE COALESCE(api_quota_used, 0) END
+                 FROM project_illustration_settings WHERE project_id = ?1",
+                QUOTA_IS_STALE
+            ),
+            [project_id],
+            |row| Ok(ProjectQuota { limit: row.get(0)?, used: row.get(1)? }),
+        )
+        .optional()?;
+    Ok(quota)
+}
+
+/// 確認專案仍有剩餘配額，用完時回傳 QuotaExceeded（只檢查，不佔用配額）
+pub fn ensure_quota_available(conn: &Connection, project_id: &str) -> Result<Option<ProjectQuota>> {
+    let quota = refresh_project_quota(conn, project_id)?;
+    if let Some(quota) = &quota {
+        if quota.remaining() == 0 {
+            return Err(IllustrationError::QuotaExceeded {
+                used: quota.used,
+                limit: quota.limit,
+            });
+        }
+    }
+    Ok(quota)
+}
+
+/// 在呼叫付費 API 前佔用一次配額，回傳佔用後的配額
+///
+/// 跨日重置、上限檢查與累加在同一個 UPDATE 中完成，同時進行的生成不會超出上限。
+/// 生成失敗時應呼叫 [`release_quota`] 歸還。專案沒有插畫設定時回傳 None（不限制）。
+pub fn reserve_quota(conn: &Connection, project_id: &str) -> Result<Option<ProjectQuota>> {
+    let reserved = conn.execute(
+        &format!(
+            "UPDATE project_illustration_settings
+             SET api_quota_used = CASE WHEN {stale} THEN 1 ELSE COALESCE(api_quota_used, 0) + 1 END,
+                 quota_reset_date = DATE('now', 'localtime'),
+                 updated_at = CURRENT_TIMESTAMP
+             WHERE project_id = ?1
+               AND (CASE WHEN {stale} THEN 0 ELSE COALESCE(api_quota_used, 0) END) < COALESCE(api_quota_limit, 100)",
+            stale = QUOTA_IS_STALE
+        ),
+        [project_id],
+    )?;
+
+    let quota = refresh_project_quota(conn, project_id)?;
+    match quota {
+        Some(quota) if reserved == 0 => Err(IllustrationError::QuotaExceeded {
+            used: quota.used,
+            limit: quota.limit,
+        }),
+        quota => Ok(quota),
+    }
+}
+
+/// 歸還生成失敗的配額（只歸還今天的用量，跨日後已重置則不處理）
+pub fn release_quota(conn: &Connection, project_id: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE project_illustration_settings
+         SET api_quota_used = MAX(COALESCE(api_quota_used, 0) - 1, 0), updated_at = CURRENT_TIMESTAMP
+         WHERE project_id = ?1 AND quota_reset_date = DATE('now', 'localtime')",
+        [project_id],
+    )?;
+    Ok(())
+}
+
+/// 記錄一次成功的付費生成：累加總生成次數與總花費（每日配額已在 [`reserve_quota`] 佔用）
+pub fn record_generation_usage(conn: &Connection, project_id: &str, cost: f64) -> Result<()> {
+    conn.execute(
+        "UPDATE project_illustration_settings
+         SET total_generations = COALESCE(total_generations, 0) + 1,
+             total_cost = COALESCE(total_cost, 0) + ?2,
+             last_generation_at = CURRENT_TIMESTAMP,
+             updated_at = CURRENT_TIMESTAMP
+         WHERE project_id = ?1",
+        params![project_id, cost],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::{migrate_with_project, migrated_conn_with_project};
+
+    fn quota_conn(limit: i64, used: i64, reset_date: &str) -> Connection {
+        let conn = migrated_conn_with_project();
+        conn.execute(
+            "INSERT OR REPLACE INTO project_illustration_settings (project_id, api_quota_limit, api_quota_used, quota_reset_date)
+             VALUES ('p1', ?1, ?2, DATE('now', 'localtime', ?3))",
+            params![limit, used, reset_date],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_quota_exhausted_until_next_day() {
+        let conn = quota_conn(2, 1, "+0 days");
+        assert_eq!(ensure_quota_available(&conn, "p1").unwrap().unwrap().remaining(), 1);
+
+        assert_eq!(reserve_quota(&conn, "p1").unwrap().unwrap().used, 2);
+        record_generation_usage(&conn, "p1", 0.04).unwrap();
+        match ensure_quota_available(&conn, "p1") {
+            Err(IllustrationError::QuotaExceeded { used, limit }) => assert_eq!((used, limit), (2, 2)),
+            other => panic!("預期配額用完，實際: {:?}", other),
+        }
+        let (total, cost): (i64, f64) = conn
+            .query_row(
+                "SELECT total_generations, total_cost FROM project_illustration_settings WHERE project_id = 'p1'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(total, 1);
+        assert!((cost - 0.04).abs() < f64::EPSILON);
+
+        assert!(matches!(reserve_quota(&conn, "p1"), Err(IllustrationError::QuotaExceeded { .. })));
+
+        // 沒有插畫設定的專案不受限制
+        assert_eq!(ensure_quota_available(&conn, "missing").unwrap(), None);
+        assert_eq!(reserve_quota(&conn, "missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_quota_resets_after_reset_date() {
+        let conn = quota_conn(2, 2, "-1 days");
+        let quota = ensure_quota_available(&conn, "p1").unwrap().unwrap();
+        assert_eq!(quota, ProjectQuota { limit: 2, used: 0 });
+
+        // 跨日後第一次佔用從 1 開始計算，並更新重置日期
+        assert_eq!(reserve_quota(&conn, "p1").unwrap().unwrap().used, 1);
+        release_quota(&conn, "p1").unwrap();
+        assert_eq!(refresh_project_quota(&conn, "p1").unwrap().unwrap().used, 0);
+    }
+
+    #[test]
+    fn test_concurrent_reservations_never_exceed_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quota.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            migrate_with_project(&conn);
+            conn.execute(
+                "INSERT OR REPLACE INTO project_illustration_settings (project_id, api_quota_limit, api_quota_used, quota_reset_date)
+                 VALUES ('p1', 3, 1, DATE('now', 'localtime'))",
+                [],
+            )
+            .unwrap();
+        }
+
+        // 兩個「生成」各自使用獨立連線，同時搶剩下的 2 次配額
+        let barrier = std::sync::Arc::new(std::sync::Barrier::new(2));
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let path = path.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    let conn = Connection::open(&path).unwrap();
+                    conn.busy_timeout(std::time::Duration::from_secs(5)).unwrap();
+                    barrier.wait();
+                    (0..5).filter(|_| reserve_quota(&conn, "p1").is_ok()).count()
+                })
+            })
+            .collect();
+        let reserved: usize = handles.into_iter().map(|handle| handle.join().unwrap()).sum();
+
+        let conn = Connection::open(&path).unwrap();
+        assert_eq!(reserved, 2);
+        assert_eq!(refresh_project_quota(&conn, "p1").unwrap().unwrap(), ProjectQuota { limit: 3, used: 3 });
+    }
+}
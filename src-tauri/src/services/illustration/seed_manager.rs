@@ -4,6 +4,25 @@ use rusqlite::{Connection, params};
 use serde::{Deserialize, Serialize};
 use super::{Result, IllustrationError};
 
+/// 單次批次生成的 seed 數量上限
+pub const MAX_BATCH_SEEDS: u32 = 50;
+
+/// 分散 seed 之間預設的最小數值間距
+pub const DEFAULT_DIVERSE_SEED_SPACING: u32 = 1_000_000;
+
+/// SplitMix64 混合函數：相鄰的輸入會得到均勻分散、互不相關的輸出
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// 以 base_seed 與計數器產生第 `counter` 個候選 seed（計數器式 PRNG，不依賴任何狀態）
+fn batch_seed_candidate(base_seed: u32, counter: u32) -> u32 {
+    (splitmix64(((base_seed as u64) << 32) | counter as u64) >> 32) as u32
+}
+
 /// Seed 管理器 - 負責角色一致性的核心機制
 /// 
 /// 功能：
@@ -79,6 +98,16 @@ impl SeedManager {
         Ok(seed_value)
     }
 
+    /// 查詢角色將使用的 seed 而不寫入資料庫，回傳 (seed, 是否已存在)
+    ///
+    /// 供提示詞預覽使用；與 [`get_or_create_seed`](Self::get_or_create_seed) 得到相同的 seed。
+    pub fn peek_seed(&self, character_id: &str, character_name: &str) -> Result<(u32, bool)> {
+        match self.get_seed_info(character_id)? {
+            Some(seed_info) => Ok((seed_info.seed_value, true)),
+            None => Ok((self.generate_seed_from_name(character_name), false)),
+        }
+    }
+
     /// 基於角色名稱生成確定性的 seed 值
     /// 
     /// 使用 Rust 的 DefaultHasher 確保：
@@ -249,25 +278,67 @@ impl SeedManager {
     }
 
     /// 驗證 seed 值的有效性
-    #[allow(dead_code)]
     pub fn validate_seed(&self, seed_value: u32) -> bool {
         // 基本驗證：確保 seed 在合理範圍內
         seed_value > 0 && seed_value < u32::MAX
     }
 
     /// 生成批次 seed 值（用於批次生成相似但略有不同的圖像）
+    ///
+    /// 演算法：依序以計數器 0, 1, 2… 計算 `splitmix64((base_seed << 32) | counter)` 的高 32 位元，
+    /// 略過與 base_seed 相同、已出現過或無法通過 [`validate_seed`](Self::validate_seed) 的值，
+    /// 直到取得 `count` 個（上限 [`MAX_BATCH_SEEDS`]）。結果只取決於 base_seed 與 count，
+    /// 相同輸入在任何執行環境都得到相同序列，且較小 count 的結果是較大 count 的前綴。
     pub fn generate_batch_seeds(&self, base_seed: u32, count: u32) -> Vec<u32> {
-        let mut seeds = Vec::new();
-        
-        for i in 0..count {
-            // 基於基礎 seed 和索引生成變體
-            let variant_seed = base_seed.wrapping_add(i * 1000); // 增加足夠的間隔避免相似性過高
-            seeds.push(variant_seed);
+        let count = count.min(MAX_BATCH_SEEDS) as usize;
+        let mut seeds = Vec::with_capacity(count);
+        let mut counter = 0u32;
+
+        while seeds.len() < count {
+            let candidate = batch_seed_candidate(base_seed, counter);
+            counter += 1;
+            if candidate != base_seed && self.validate_seed(candidate) && !seeds.contains(&candidate) {
+                seeds.push(candidate);
+            }
         }
 
         seeds
     }
 
+    /// 生成分散的批次 seed（用於需要明顯不同構圖的變體）
+    ///
+    /// 將 u32 空間平均切成 `count` 段，起點由 base_seed 決定；每段內以計數器式 PRNG 取一個偏移，
+    /// 偏移範圍預留 `min_spacing`，因此任兩個 seed 的數值差距至少為 `min_spacing`
+    /// （超過每段寬度時以段寬為準）。結果只取決於三個參數，可重現。
+    pub fn generate_diverse_seeds(&self, base_seed: u32, count: u32, min_spacing: u32) -> Vec<u32> {
+        const SPACE: u64 = 1 << 32;
+        let count = count.min(MAX_BATCH_SEEDS) as u64;
+        if count == 0 {
+            return Vec::new();
+        }
+
+        let stride = SPACE / count;
+        let jitter_range = stride - (min_spacing as u64).min(stride) + 1;
+        // 起點與 base_seed 錯開，避免第一個變體就是基礎 seed
+        let start = batch_seed_candidate(base_seed, u32::MAX) as u64;
+
+        (0..count)
+            .map(|index| {
+                let segment_start = start + index * stride;
+                // 候選值無效（0、u32::MAX 或等於 base_seed）時換下一個偏移，間距保證不變
+                let mut candidate = 0;
+                for round in 0..8u64 {
+                    let jitter = batch_seed_candidate(base_seed, (index + round * count) as u32) as u64 % jitter_range;
+                    candidate = ((segment_start + jitter) % SPACE) as u32;
+                    if candidate != base_seed && self.validate_seed(candidate) {
+                        break;
+                    }
+                }
+                candidate
+            })
+            .collect()
+    }
+
     /// 清理未使用的 seed 記錄（維護功能）
     #[allow(dead_code)]
     pub fn cleanup_unused_seeds(&self, days_threshold: i64) -> Result<usize> {
@@ -333,6 +404,20 @@ mod tests {
         assert_ne!(seed1, seed3);
     }
 
+    #[test]
+    fn test_peek_seed_matches_created_seed_without_saving() {
+        let db = create_test_db();
+        let seed_manager = SeedManager::new(db);
+
+        let (peeked, exists) = seed_manager.peek_seed("char-1", "Character").unwrap();
+        assert!(!exists);
+        assert!(seed_manager.get_seed_info("char-1").unwrap().is_none());
+
+        // 預覽的 seed 與首次生成時建立的 seed 相同
+        assert_eq!(seed_manager.get_or_create_seed("char-1", "Character").unwrap(), peeked);
+        assert_eq!(seed_manager.peek_seed("char-1", "Character").unwrap(), (peeked, true));
+    }
+
     #[test]
     fn test_seed_validation() {
         let db = create_test_db();
@@ -361,4 +446,52 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_batch_seeds_are_deterministic_and_avoid_base() {
+        let db = create_test_db();
+        let seed_manager = SeedManager::new(db);
+
+        // 固定的期望值：演算法變動會讓既有作品的批次變體無法重現
+        assert_eq!(seed_manager.generate_batch_seeds(12345, 3), vec![2400783100, 1745152419, 2633931112]);
+
+        for base_seed in [0, 1, 12345, u32::MAX - 1, u32::MAX] {
+            let seeds = seed_manager.generate_batch_seeds(base_seed, MAX_BATCH_SEEDS);
+            assert_eq!(seeds.len(), MAX_BATCH_SEEDS as usize);
+            assert_eq!(seeds, seed_manager.generate_batch_seeds(base_seed, MAX_BATCH_SEEDS));
+            assert!(!seeds.contains(&base_seed));
+            assert!(seeds.iter().all(|&seed| seed_manager.validate_seed(seed)));
+            let unique: std::collections::HashSet<_> = seeds.iter().collect();
+            assert_eq!(unique.len(), seeds.len());
+            // 較小的批次是較大批次的前綴
+            assert_eq!(seed_manager.generate_batch_seeds(base_seed, 5), seeds[..5]);
+        }
+
+        assert_eq!(seed_manager.generate_batch_seeds(12345, MAX_BATCH_SEEDS + 10).len(), MAX_BATCH_SEEDS as usize);
+    }
+
+    #[test]
+    fn test_diverse_seeds_keep_minimum_spacing() {
+        let db = create_test_db();
+        let seed_manager = SeedManager::new(db);
+
+        for (base_seed, count) in [(12345, 8), (u32::MAX - 1, MAX_BATCH_SEEDS), (7, 2)] {
+            let seeds = seed_manager.generate_diverse_seeds(base_seed, count, DEFAULT_DIVERSE_SEED_SPACING);
+            assert_eq!(seeds.len(), count as usize);
+            assert_eq!(seeds, seed_manager.generate_diverse_seeds(base_seed, count, DEFAULT_DIVERSE_SEED_SPACING));
+            assert!(!seeds.contains(&base_seed));
+            for i in 0..seeds.len() {
+                for j in i + 1..seeds.len() {
+                    assert!(seeds[i].abs_diff(seeds[j]) >= DEFAULT_DIVERSE_SEED_SPACING);
+                }
+            }
+        }
+
+        // 間距大於段寬時退化為平均分布
+        let even = seed_manager.generate_diverse_seeds(1, 4, u32::MAX);
+        let mut sorted = even.clone();
+        sorted.sort();
+        assert!(sorted.windows(2).all(|pair| pair[1] - pair[0] == 1 << 30));
+        assert!(seed_manager.generate_diverse_seeds(1, 0, 10).is_empty());
+    }
 }
\ No newline at end of file
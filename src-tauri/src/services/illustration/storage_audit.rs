@@ -0,0 +1,377 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use super::thumbnail::{thumbnail_path_for, THUMBNAIL_DIR_NAME};
+use super::Result;
+
+/// 縮圖檔名後綴（見 thumbnail::thumbnail_path_for）
+const THUMBNAIL_SUFFIX: &str = "_thumb.jpg";
+
+/// 沒有任何資料庫記錄引用的檔案
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedFile {
+    pub path: String,
+    pub size: u64,
+}
+
+/// 指向不存在檔案的資料庫記錄
+#[derive(Debug, Clone, Serialize)]
+pub struct DanglingRecord {
+    pub id: String,
+    pub table_name: String,
+    pub project_id: Option<String>,
+    pub file_path: String,
+}
+
+/// 插畫儲存稽核報告
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageAuditReport {
+    pub storage_dir: String,
+    pub orphaned_files: Vec<OrphanedFile>,
+    pub orphaned_bytes: u64,
+    pub dangling_records: Vec<DanglingRecord>,
+}
+
+/// 孤立檔案清理結果
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageCleanupResult {
+    pub dry_run: bool,
+    pub removed_files: Vec<String>,
+    pub reclaimed_bytes: u64,
+    pub cleared_records: usize,
+    pub failed: Vec<String>,
+}
+
+fn file_name_of(path: &str) -> Option<String> {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// 生成原圖的副檔名：Pollinations 為 `.jpg`，Imagen 為 `.png`
+const GENERATED_EXTENSIONS: [&str; 2] = [".jpg", ".png"];
+
+/// 是否為本應用生成的檔名：原圖為 `<生成 ID>.jpg` / `<生成 ID>.png`，縮圖為 `<生成 ID>_thumb.jpg`
+///
+/// 儲存目錄可由使用者指定，只清理符合此格式的檔案，避免誤刪目錄中使用者自己的圖片。
+fn is_generated_file_name(name: &str, thumbnail: bool) -> bool {
+    let is_generation_id = |id: &str| uuid::Uuid::parse_str(id).is_ok();
+    if thumbnail {
+        return name.strip_suffix(THUMBNAIL_SUFFIX).is_some_and(is_generation_id);
+    }
+    GENERATED_EXTENSIONS
+        .iter()
+        .any(|extension| name.strip_suffix(extension).is_some_and(is_generation_id))
+}
+
+fn is_local_path(path: &str) -> bool {
+    !path.is_empty() && !path.starts_with("http://") && !path.starts_with("https://") && !path.starts_with("data:")
+}
+
+/// 收集資料庫引用的所有檔名（含軟刪除記錄與縮圖）
+///
+/// 以檔名比對而非完整路徑：檔名即生成 ID，更換儲存目錄後搬移的檔案仍能對應到記錄。
+fn referenced_file_names(conn: &Connection) -> Result<HashSet<String>> {
+    let mut names = HashSet::new();
+    let mut stmt = conn.prepare(
+        "SELECT local_file_path, thumbnail_path, deleted_file_path FROM pollinations_generations",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok([
+            row.get::<_, Option<String>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, Option<String>>(2)?,
+        ])
+    })?;
+    for paths in rows {
+        let [local, thumbnail, deleted] = paths?;
+        if let Some(local) = &local {
+            let derived = thumbnail_path_for(Path::new(local));
+            names.extend(derived.file_name().map(|name| name.to_string_lossy().to_string()));
+        }
+        names.extend([local, thumbnail, deleted].iter().flatten().filter_map(|path| file_name_of(path)));
+    }
+
+    let mut stmt = conn.prepare("SELECT image_url, deleted_file_path FROM illustration_generations")?;
+    let rows = stmt.query_map([], |row| {
+        Ok([row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?])
+    })?;
+    for paths in rows {
+        let paths = paths?;
+        names.extend(
+            paths
+                .iter()
+                .flatten()
+                .filter(|path| is_local_path(path))
+                .filter_map(|path| file_name_of(path)),
+        );
+    }
+    Ok(names)
+}
+
+/// 列出儲存目錄（及縮圖子目錄）中沒有記錄引用的生成圖像檔案
+fn find_orphaned_files(storage_dir: &Path, referenced: &HashSet<String>) -> Vec<OrphanedFile> {
+    let mut orphans = Vec::new();
+    for (dir, thumbnail) in [(storage_dir.to_path_buf(), false), (storage_dir.join(THUMBNAIL_DIR_NAME), true)] {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !is_generated_file_name(&name, thumbnail) || !path.is_file() {
+                continue;
+            }
+            if !referenced.contains(&name) {
+                orphans.push(OrphanedFile {
+                    path: path.to_string_lossy().to_string(),
+                    size: entry.metadata().map(|meta| meta.len()).unwrap_or(0),
+                });
+            }
+        }
+    }
+    orphans.sort_by(|a, b| a.path.cmp(&b.path));
+    orphans
+}
+
+/// 記錄的本地檔案是否確定已不存在
+///
+/// 舊版 Imagen 以相對於工作目錄的路徑儲存，是否存在會隨啟動目錄改變，
+/// 無法判斷，因此不視為失效（避免清除仍有效的路徑）。
+fn is_missing_local_file(file_path: &str) -> bool {
+    let path = Path::new(file_path);
+    is_local_path(file_path) && path.is_absolute() && !path.exists()
+}
+
+/// 列出本地檔案已不存在的未刪除記錄（可依專案篩選）
+///
+/// 軟刪除只設定 deleted_at 並把檔案移到垃圾桶，原路徑保留給還原使用，因此不列入。
+fn find_dangling_records(conn: &Connection, project_id: Option<&str>) -> Result<Vec<DanglingRecord>> {
+    let queries = [
+        (
+            "pollinations_generations",
+            "SELECT id, project_id, local_file_path FROM pollinations_generations
+             WHERE local_file_path IS NOT NULL AND deleted_at IS NULL AND COALESCE(is_permanently_deleted, 0) = 0
+               AND (?1 IS NULL OR project_id = ?1)",
+        ),
+        (
+            "illustration_generations",
+            "SELECT id, project_id, image_url FROM illustration_generations
+             WHERE image_url IS NOT NULL AND deleted_at IS NULL AND COALESCE(is_permanently_deleted, 0) = 0
+               AND (?1 IS NULL OR project_id = ?1)",
+        ),
+    ];
+
+    let mut dangling = Vec::new();
+    for (table_name, sql) in queries {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params![project_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, String>(2)?))
+        })?;
+        for row in rows {
+            let (id, project_id, file_path) = row?;
+            if is_missing_local_file(&file_path) {
+                dangling.push(DanglingRecord {
+                    id,
+                    table_name: table_name.to_string(),
+                    project_id,
+                    file_path,
+                });
+            }
+        }
+    }
+    Ok(dangling)
+}
+
+/// 稽核插畫儲存：孤立檔案以整個儲存目錄為範圍（檔案無法歸屬專案），失效記錄可依專案篩選
+pub fn audit_storage(conn: &Connection, storage_dir: &Path, project_id: Option<&str>) -> Result<StorageAuditReport> {
+    let referenced = referenced_file_names(conn)?;
+    let orphaned_files = find_orphaned_files(storage_dir, &referenced);
+    let orphaned_bytes = orphaned_files.iter().map(|file| file.size).sum();
+
+    Ok(StorageAuditReport {
+        storage_dir: storage_dir.to_string_lossy().to_string(),
+        orphaned_files,
+        orphaned_bytes,
+        dangling_records: find_dangling_records(conn, project_id)?,
+    })
+}
+
+/// 刪除孤立檔案，並可選擇將失效記錄的檔案路徑清為 NULL；dry_run 時只回報不變更
+pub fn clean_storage(
+    conn: &Connection,
+    storage_dir: &Path,
+    dry_run: bool,
+    clear_dangling_paths: bool,
+) -> Result<StorageCleanupResult> {
+    let report = audit_storage(conn, storage_dir, None)?;
+    let mut result = StorageCleanupResult {
+        dry_run,
+        removed_files: Vec::new(),
+        reclaimed_bytes: 0,
+        cleared_records: 0,
+        failed: Vec::new(),
+    };
+
+    for file in report.orphaned_files {
+        if !dry_run {
+            if let Err(e) = std::fs::remove_file(PathBuf::from(&file.path)) {
+                log::warn!("[StorageAudit] 刪除孤立檔案失敗 {}: {}", file.path, e);
+                result.failed.push(file.path);
+                continue;
+            }
+        }
+        result.reclaimed_bytes += file.size;
+        result.removed_files.push(file.path);
+    }
+
+    if clear_dangling_paths {
+        for record in &report.dangling_records {
+            if !dry_run {
+                let sql = match record.table_name.as_str() {
+                    "pollinations_generations" => {
+                        "UPDATE pollinations_generations SET local_file_path = NULL, thumbnail_path = NULL WHERE id = ?1"
+                    }
+                    _ => "UPDATE illustration_generations SET image_url = NULL WHERE id = ?1",
+                };
+                conn.execute(sql, [&record.id])?;
+            }
+            result.cleared_records += 1;
+        }
+    }
+
+    log::info!(
+        "[StorageAudit] 清理{}：孤立檔案 {} 個（{} bytes），清除失效路徑 {} 筆",
+        if dry_run { "預覽" } else { "完成" },
+        result.removed_files.len(),
+        result.reclaimed_bytes,
+        result.cleared_records
+    );
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::migrated_conn_with_project;
+
+    fn generated_name() -> String {
+        format!("{}.jpg", uuid::Uuid::new_v4())
+    }
+
+    fn setup() -> (Connection, tempfile::TempDir) {
+        let conn = migrated_conn_with_project();
+        conn.execute("INSERT INTO projects (id, name) VALUES ('p2', '乙')", []).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join(THUMBNAIL_DIR_NAME)).unwrap();
+        (conn, dir)
+    }
+
+    #[test]
+    fn test_audit_and_clean_orphans_and_dangling_records() {
+        let (conn, dir) = setup();
+
+        let kept = dir.path().join(generated_name());
+        std::fs::write(&kept, b"kept").unwrap();
+        std::fs::write(thumbnail_path_for(&kept), b"t").unwrap();
+        let orphan = dir.path().join(generated_name());
+        std::fs::write(&orphan, b"orphan").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), b"not an image").unwrap();
+        // 使用者自己的圖片（檔名不符合生成格式）不得視為孤立檔案
+        std::fs::write(dir.path().join("holiday.jpg"), b"mine").unwrap();
+        std::fs::write(dir.path().join(THUMBNAIL_DIR_NAME).join("holiday_thumb.jpg"), b"mine").unwrap();
+        let missing = dir.path().join(generated_name());
+
+        conn.execute(
+            "INSERT INTO pollinations_generations (id, project_id, original_prompt, local_file_path)
+             VALUES ('g1', 'p1', 'a', ?1), ('g2', 'p2', 'b', ?2)",
+            params![kept.to_string_lossy(), missing.to_string_lossy()],
+        )
+        .unwrap();
+
+        let report = audit_storage(&conn, dir.path(), None).unwrap();
+        assert_eq!(report.orphaned_files.len(), 1);
+        assert_eq!(report.orphaned_files[0].path, orphan.to_string_lossy());
+        assert_eq!(report.orphaned_bytes, 6);
+        assert_eq!(report.dangling_records.len(), 1);
+        assert_eq!(report.dangling_records[0].id, "g2");
+        assert!(audit_storage(&conn, dir.path(), Some("p1")).unwrap().dangling_records.is_empty());
+
+        let preview = clean_storage(&conn, dir.path(), true, true).unwrap();
+        assert_eq!((preview.removed_files.len(), preview.cleared_records), (1, 1));
+        assert!(orphan.exists());
+
+        let cleaned = clean_storage(&conn, dir.path(), false, true).unwrap();
+        assert_eq!(cleaned.reclaimed_bytes, 6);
+        assert!(!orphan.exists());
+        assert!(kept.exists() && thumbnail_path_for(&kept).exists());
+        assert!(dir.path().join("holiday.jpg").exists());
+        assert!(dir.path().join(THUMBNAIL_DIR_NAME).join("holiday_thumb.jpg").exists());
+        let path: Option<String> = conn
+            .query_row("SELECT local_file_path FROM pollinations_generations WHERE id = 'g2'", [], |row| row.get(0))
+            .unwrap();
+        assert!(path.is_none());
+        assert!(audit_storage(&conn, dir.path(), None).unwrap().dangling_records.is_empty());
+    }
+
+    #[test]
+    fn test_clean_keeps_trashed_records_restorable() {
+        let (conn, dir) = setup();
+        let trash = tempfile::tempdir().unwrap();
+
+        // 軟刪除後原檔已移入垃圾桶，local_file_path 仍保留原路徑供還原
+        let name = generated_name();
+        let original = dir.path().join(&name);
+        let trashed = trash.path().join(&name);
+        std::fs::write(&trashed, b"trashed").unwrap();
+        conn.execute(
+            "INSERT INTO pollinations_generations (id, project_id, original_prompt, local_file_path, deleted_at, deleted_file_path)
+             VALUES ('g1', 'p1', 'a', ?1, CURRENT_TIMESTAMP, ?2)",
+            params![original.to_string_lossy(), trashed.to_string_lossy()],
+        )
+        .unwrap();
+
+        assert!(audit_storage(&conn, dir.path(), None).unwrap().dangling_records.is_empty());
+        let cleaned = clean_storage(&conn, dir.path(), false, true).unwrap();
+        assert_eq!(cleaned.cleared_records, 0);
+        let path: Option<String> = conn
+            .query_row("SELECT local_file_path FROM pollinations_generations WHERE id = 'g1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(path.as_deref(), Some(original.to_string_lossy().as_ref()));
+        assert!(trashed.exists());
+    }
+
+    #[test]
+    fn test_audit_covers_imagen_png_and_skips_relative_paths() {
+        let (conn, dir) = setup();
+
+        let kept = dir.path().join(format!("{}.png", uuid::Uuid::new_v4()));
+        std::fs::write(&kept, b"kept").unwrap();
+        let orphan = dir.path().join(format!("{}.png", uuid::Uuid::new_v4()));
+        std::fs::write(&orphan, b"orphan").unwrap();
+        std::fs::write(dir.path().join("cover.png"), b"mine").unwrap();
+        // 舊版相對路徑：是否存在取決於工作目錄，不得列為失效記錄
+        let relative = format!("generated_images/{}.png", uuid::Uuid::new_v4());
+        conn.execute(
+            "INSERT INTO illustration_generations (id, project_id, scene_description, translated_prompt, api_model, image_url)
+             VALUES ('i1', 'p1', '森林', 'forest', 'imagen', ?1), ('i2', 'p1', '海邊', 'beach', 'imagen', ?2)",
+            params![kept.to_string_lossy(), relative],
+        )
+        .unwrap();
+
+        let report = audit_storage(&conn, dir.path(), None).unwrap();
+        assert_eq!(report.orphaned_files.len(), 1);
+        assert_eq!(report.orphaned_files[0].path, orphan.to_string_lossy());
+        assert!(report.dangling_records.is_empty());
+
+        clean_storage(&conn, dir.path(), false, true).unwrap();
+        assert!(kept.exists() && !orphan.exists() && dir.path().join("cover.png").exists());
+        let path: Option<String> = conn
+            .query_row("SELECT image_url FROM illustration_generations WHERE id = 'i2'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(path, Some(relative));
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// 縮圖最長邊（像素）
+pub const THUMBNAIL_MAX_EDGE: u32 = 256;
+
+/// 縮圖存放的子目錄名稱（位於原圖所在目錄下）
+pub(crate) const THUMBNAIL_DIR_NAME: &str = "thumbnails";
+
+/// 原圖對應的縮圖路徑：`<原圖目錄>/thumbnails/<檔名>_thumb.jpg`
+pub fn thumbnail_path_for(image_path: &Path) -> PathBuf {
+    let stem = image_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "image".to_string());
+    image_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(THUMBNAIL_DIR_NAME)
+        .join(format!("{}_thumb.jpg", stem))
+}
+
+/// 為原圖產生縮圖（維持長寬比，最長邊不超過 THUMBNAIL_MAX_EDGE），回傳縮圖路徑
+pub fn generate_thumbnail(image_path: &Path) -> Result<PathBuf> {
+    let image = image::open(image_path)
+        .with_context(|| format!("無法讀取圖像: {}", image_path.display()))?;
+    let thumbnail = image.thumbnail(THUMBNAIL_MAX_EDGE, THUMBNAIL_MAX_EDGE).to_rgb8();
+    
+    let thumbnail_path = thumbnail_path_for(image_path);
+    if let Some(dir) = thumbnail_path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("無法建立縮圖目錄: {}", dir.display()))?;
+    }
+    thumbnail
+        .save_with_format(&thumbnail_path, image::ImageFormat::Jpeg)
+        .with_context(|| format!("無法寫入縮圖: {}", thumbnail_path.display()))?;
+    
+    Ok(thumbnail_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_thumbnail_keeps_aspect_ratio() {
+        let dir = tempfile::tempdir().unwrap();
+        let image_path = dir.path().join("wide.png");
+        image::RgbImage::from_pixel(1024, 512, image::Rgb([200, 120, 40]))
+            .save(&image_path)
+            .unwrap();
+        
+        let thumbnail_path = generate_thumbnail(&image_path).unwrap();
+        assert_eq!(thumbnail_path, dir.path().join("thumbnails").join("wide_thumb.jpg"));
+        
+        let thumbnail = image::open(&thumbnail_path).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (256, 128));
+    }
+}
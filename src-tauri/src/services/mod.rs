@@ -2,4 +2,6 @@ pub mod ollama;
 pub mod ai_providers;
 pub mod illustration;
 pub mod translation;
-pub mod context;
\ No newline at end of file
+pub mod context;
+pub mod analysis_cache;
+pub mod analysis_queue;
\ No newline at end of file
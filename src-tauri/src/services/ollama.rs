@@ -25,6 +25,8 @@ pub struct OllamaVersionResponse {
 pub struct OllamaGenerateRequest {
     pub model: String,
     pub prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,  // Ollama 原生系統提示欄位
     pub stream: bool,
     pub options: Option<OllamaOptions>,
 }
@@ -118,6 +120,11 @@ impl OllamaService {
         }
     }
 
+    /// 目前使用的服務端點
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
     /// 檢查 Ollama 服務是否可用
     pub async fn check_service_availability(&self) -> ServiceStatus {
         log::info!("[OllamaService] 開始檢查服務可用性...");
@@ -198,6 +205,17 @@ impl OllamaService {
         model: &str,
         prompt: &str,
         options: Option<OllamaOptions>,
+    ) -> GenerateResult {
+        self.generate_text_with_system(model, prompt, None, options).await
+    }
+
+    /// 生成文本（系統提示透過 Ollama 的 `system` 欄位傳送）
+    pub async fn generate_text_with_system(
+        &self,
+        model: &str,
+        prompt: &str,
+        system: Option<&str>,
+        options: Option<OllamaOptions>,
     ) -> GenerateResult {
         log::info!("[OllamaService] 開始生成文本，模型: {}", model);
 
@@ -215,6 +233,7 @@ impl OllamaService {
         let request_body = OllamaGenerateRequest {
             model: model.to_string(),
             prompt: prompt.to_string(),
+            system: system.map(|s| s.to_string()),
             stream: false,
             options,
         };
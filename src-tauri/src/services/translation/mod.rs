@@ -10,16 +10,18 @@ pub mod prompt_templates;
 
 pub use translation_engine::{
     TranslationEngine, TranslationRequest, 
-    TranslationStyle, QualityLevel
+    TranslationStyle, QualityLevel, CoverageReport
 };
-pub use vocabulary_database::{VocabularyDatabase, VocabularyCategory};
+pub use vocabulary_database::{VocabularyDatabase, VocabularyCategory, VocabularyEntryInput, VocabularySource, UpsertOutcome};
 pub use prompt_optimizer::{
     PromptOptimizer, OptimizationLevel, PromptStyle, 
-    OptimizationRequest, QualityFocus
+    OptimizationRequest, QualityFocus, IterativeOptimizationResult
 };
 pub use prompt_templates::{
     PromptTemplateManager, TemplateCategory,
-    TemplateApplicationRequest, TemplateSearchRequest
+    TemplateApplicationRequest, TemplateSearchRequest, TemplateParameterSuggestion,
+    PromptTemplate, PromptTemplateInput, TemplateValidationReport,
+    TemplateQualityPreview
 };
 
 use thiserror::Error;
@@ -47,6 +49,9 @@ pub enum TranslationError {
     #[error("JSON 序列化錯誤: {0}")]
     JsonError(#[from] serde_json::Error),
     
+    #[error("詞彙條目無效: {0}")]
+    InvalidVocabularyEntry(String),
+    
     #[error("未知錯誤: {0}")]
     Unknown(String),
 }
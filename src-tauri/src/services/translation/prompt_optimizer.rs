@@ -92,6 +92,31 @@ pub struct OptimizationResult {
     pub token_count_estimate: usize,
 }
 
+/// 多輪優化中單一輪的結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationPass {
+    pub iteration: usize,
+    pub prompt: String,
+    pub improvement_score: f64,
+    pub applied_optimizations: Vec<String>,
+}
+
+/// 多輪優化結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IterativeOptimizationResult {
+    pub original_prompt: String,
+    pub final_prompt: String,
+    pub passes: Vec<OptimizationPass>,
+    /// 是否因分數趨於平穩或提示詞不再變化而提前停止
+    pub stopped_early: bool,
+}
+
+/// 多輪優化的最大輪數
+pub const MAX_OPTIMIZATION_ITERATIONS: usize = 10;
+
+/// 改善分數低於此值視為趨於平穩
+const IMPROVEMENT_PLATEAU_THRESHOLD: f64 = 0.01;
+
 /// 提示詞分析
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptAnalysis {
@@ -361,6 +386,42 @@ impl PromptOptimizer {
         Ok(result)
     }
 
+    /// 多輪優化：以上一輪的結果作為下一輪輸入，改善分數趨於平穩或提示詞不再變化時提前停止
+    pub fn optimize_iterative(&self, request: OptimizationRequest, iterations: usize) -> Result<IterativeOptimizationResult> {
+        let iterations = iterations.clamp(1, MAX_OPTIMIZATION_ITERATIONS);
+        let original_prompt = request.base_prompt.clone();
+        let mut current = request;
+        let mut passes = Vec::new();
+        let mut stopped_early = false;
+
+        for iteration in 1..=iterations {
+            let result = self.optimize(current.clone())?;
+            let unchanged = result.optimized_prompt == current.base_prompt;
+            let plateaued = iteration > 1 && result.improvement_score < IMPROVEMENT_PLATEAU_THRESHOLD;
+
+            passes.push(OptimizationPass {
+                iteration,
+                prompt: result.optimized_prompt.clone(),
+                improvement_score: result.improvement_score,
+                applied_optimizations: result.applied_optimizations,
+            });
+            current.base_prompt = result.optimized_prompt;
+
+            if (unchanged || plateaued) && iteration < iterations {
+                log::info!("[PromptOptimizer] 第 {} 輪後改善分數趨於平穩，提前停止", iteration);
+                stopped_early = true;
+                break;
+            }
+        }
+
+        Ok(IterativeOptimizationResult {
+            original_prompt,
+            final_prompt: current.base_prompt,
+            passes,
+            stopped_early,
+        })
+    }
+
     /// 分析提示詞
     fn analyze_prompt(&self, prompt: &str) -> Result<PromptAnalysis> {
         let words: Vec<&str> = prompt.split_whitespace().collect();
@@ -668,4 +729,29 @@ impl PromptOptimizer {
         let word_count = prompt.split_whitespace().count();
         (word_count as f64 * 1.3) as usize
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_optimize_iterative_stops_when_prompt_settles() {
+        let optimizer = PromptOptimizer::new();
+        let request = OptimizationRequest {
+            base_prompt: "girl, long hair, long hair, blue eyes, school uniform".to_string(),
+            target_model: "stable_diffusion".to_string(),
+            optimization_level: OptimizationLevel::Standard,
+            prompt_style: PromptStyle::Detailed,
+            include_negative_prompt: false,
+            max_length: None,
+            quality_focus: Vec::new(),
+        };
+
+        let result = optimizer.optimize_iterative(request, 50).unwrap();
+
+        assert!(!result.passes.is_empty());
+        assert!(result.passes.len() <= MAX_OPTIMIZATION_ITERATIONS);
+        assert_eq!(result.final_prompt, result.passes.last().unwrap().prompt);
+        assert!(result.stopped_early);
+    }
+}
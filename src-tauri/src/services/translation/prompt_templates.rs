@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use super::{Result, TranslationError};
+use std::collections::{HashMap, HashSet};
+use rusqlite::{params, Connection};
+use super::{Result, TranslationError, VocabularyCategory, VocabularyDatabase};
 
 /// 提示詞模板管理器
 /// 
@@ -12,6 +13,7 @@ use super::{Result, TranslationError};
 pub struct PromptTemplateManager {
     templates: HashMap<String, PromptTemplate>,
     template_categories: HashMap<TemplateCategory, Vec<String>>,
+    builtin_ids: HashSet<String>,
 }
 
 /// 提示詞模板
@@ -37,12 +39,16 @@ pub struct PromptTemplate {
     // 使用統計和評分
     pub usage_count: u32,
     pub average_rating: f64,
+    #[serde(default)]
+    pub rating_count: u32,                  // 使用者評分次數
     pub success_rate: f64,
     
     // 元數據
     pub author: String,
     pub version: String,
     pub language: String,                   // "zh", "en", "ja"
+    #[serde(default)]
+    pub translation_group_id: Option<String>, // 同一模板的不同語言版本共用
     pub tags: Vec<String>,
     pub created_at: String,
     pub updated_at: String,
@@ -105,6 +111,125 @@ pub struct TemplateInfo {
     pub version: String,
 }
 
+/// 建立或更新使用者模板的輸入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplateInput {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub category: Option<TemplateCategory>, // 預設為 Custom
+    pub subcategory: Option<String>,
+    pub template: String,
+    #[serde(default)]
+    pub required_parameters: Vec<String>,
+    #[serde(default)]
+    pub optional_parameters: Vec<String>,
+    #[serde(default)]
+    pub default_values: HashMap<String, String>,
+    #[serde(default)]
+    pub quality_modifiers: Vec<String>,
+    #[serde(default)]
+    pub negative_prompts: Vec<String>,
+    #[serde(default)]
+    pub style_tags: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub language: Option<String>,
+    pub translation_group_id: Option<String>,
+}
+
+/// 模板品質預覽
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateQualityPreview {
+    pub template_id: String,
+    pub template_name: String,
+    pub estimated_quality_score: f64,
+    pub missing_parameter_count: usize,
+    pub missing_parameters: Vec<String>,
+}
+
+/// 模板驗證報告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateValidationReport {
+    pub template_id: String,
+    pub template_name: String,
+    pub is_valid: bool,
+    /// 模板內容中出現但未宣告的佔位符
+    pub undeclared_placeholders: Vec<String>,
+    /// 已宣告但未出現在模板內容中的參數
+    pub unused_parameters: Vec<String>,
+}
+
+/// 模板參數建議結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateParameterSuggestion {
+    pub template_id: String,
+    /// 建議的參數值（英文）
+    pub parameters: HashMap<String, String>,
+    /// 各參數的來源（屬性鍵名或「角色描述」）
+    pub sources: HashMap<String, String>,
+    /// 仍需手動填寫的必需參數（已排除有預設值者）
+    pub missing_parameters: Vec<String>,
+}
+
+/// 角色屬性鍵名對應的模板參數
+const ATTRIBUTE_PARAMETER_ALIASES: &[(&str, &[&str])] = &[
+    ("age", &["年齡", "歲數"]),
+    ("gender", &["性別"]),
+    ("hair_description", &["髮型", "髮色", "頭髮", "hair"]),
+    ("eye_description", &["眼睛", "瞳色", "眼色", "eyes"]),
+    ("expression", &["表情"]),
+    ("accessories", &["配件", "飾品"]),
+    ("pose", &["姿勢", "動作"]),
+];
+
+/// 模板引用語法的開頭，完整形式為 `{{template:模板ID}}`
+const TEMPLATE_REFERENCE_PREFIX: &str = "{{template:";
+
+/// 模板引用的最大巢狀層數
+const MAX_TEMPLATE_REFERENCE_DEPTH: usize = 4;
+
+/// 服裝類詞彙可填入的參數（依序取模板中第一個存在的參數）
+const CLOTHING_PARAMETERS: &[&str] = &["uniform_type", "robe_type", "shirt_type", "armor_type", "clothing"];
+
+/// 解析模板內容中的 `{參數}` 佔位符（略過 `{{template:id}}` 引用）
+fn extract_placeholders(body: &str) -> Vec<String> {
+    let mut placeholders = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start..];
+        if after.starts_with(TEMPLATE_REFERENCE_PREFIX) {
+            rest = after.find("}}").map(|end| &after[end + 2..]).unwrap_or("");
+            continue;
+        }
+        match after[1..].find(['{', '}']) {
+            Some(end) if after.as_bytes()[end + 1] == b'}' => {
+                let name = after[1..end + 1].trim();
+                if !name.is_empty() {
+                    placeholders.push(name.to_string());
+                }
+                rest = &after[end + 2..];
+            }
+            Some(end) => rest = &after[end + 1..],
+            None => break,
+        }
+    }
+    placeholders
+}
+
+/// 詞彙分類對應的模板參數
+fn category_parameter(category: &VocabularyCategory) -> Option<&'static str> {
+    match category {
+        VocabularyCategory::Hair => Some("hair_description"),
+        VocabularyCategory::Eyes => Some("eye_description"),
+        VocabularyCategory::Expression => Some("expression"),
+        VocabularyCategory::Pose => Some("pose"),
+        VocabularyCategory::Accessories => Some("accessories"),
+        VocabularyCategory::FacialFeatures => Some("facial_details"),
+        _ => None,
+    }
+}
+
 /// 模板搜尋請求
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TemplateSearchRequest {
@@ -122,12 +247,14 @@ impl PromptTemplateManager {
         let mut manager = Self {
             templates: HashMap::new(),
             template_categories: HashMap::new(),
+            builtin_ids: HashSet::new(),
         };
         
         // 初始化內建模板
         if let Err(e) = manager.load_builtin_templates() {
             log::error!("[PromptTemplateManager] 內建模板載入失敗: {:?}", e);
         }
+        manager.builtin_ids = manager.templates.keys().cloned().collect();
         
         manager
     }
@@ -208,11 +335,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["anime".to_string(), "school".to_string(), "youth".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.5,
             success_rate: 0.9,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["角色".to_string(), "學生".to_string(), "校園".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -259,11 +388,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["fantasy".to_string(), "magic".to_string(), "mystical".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.3,
             success_rate: 0.85,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["角色".to_string(), "魔法師".to_string(), "奇幻".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -310,11 +441,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["Japanese".to_string(), "samurai".to_string(), "traditional".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.4,
             success_rate: 0.88,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["角色".to_string(), "武士".to_string(), "日式".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -364,11 +497,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["school".to_string(), "educational".to_string(), "youth".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.2,
             success_rate: 0.9,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["背景".to_string(), "校園".to_string(), "日常".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -413,11 +548,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["fantasy".to_string(), "magical".to_string(), "nature".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.6,
             success_rate: 0.87,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["背景".to_string(), "魔法".to_string(), "森林".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -468,11 +605,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["uniform".to_string(), "school".to_string(), "formal".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.3,
             success_rate: 0.92,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["服裝".to_string(), "校服".to_string(), "學生".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -521,11 +660,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["emotion".to_string(), "positive".to_string(), "natural".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.5,
             success_rate: 0.95,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["表情".to_string(), "快樂".to_string(), "情緒".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -574,11 +715,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["pose".to_string(), "standing".to_string(), "basic".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.2,
             success_rate: 0.89,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["姿勢".to_string(), "站立".to_string(), "基礎".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -628,11 +771,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["anime".to_string(), "Japanese".to_string(), "illustration".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.7,
             success_rate: 0.93,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["風格".to_string(), "動漫".to_string(), "日式".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -682,11 +827,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["lighting".to_string(), "soft".to_string(), "warm".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.4,
             success_rate: 0.88,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["光線".to_string(), "柔和".to_string(), "氛圍".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -735,11 +882,13 @@ impl PromptTemplateManager {
             ],
             style_tags: vec!["composite".to_string(), "complete".to_string(), "scene".to_string()],
             usage_count: 0,
+            rating_count: 0,
             average_rating: 4.6,
             success_rate: 0.85,
             author: "Genesis Chronicle".to_string(),
             version: "1.0".to_string(),
             language: "zh".to_string(),
+            translation_group_id: None,
             tags: vec!["組合".to_string(), "完整".to_string(), "場景".to_string()],
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
@@ -788,12 +937,14 @@ impl PromptTemplateManager {
             }
         }
         
-        // 替換模板中的參數
+        // 替換模板中的參數，並展開 {{template:id}} 引用
         let mut final_prompt = template.template.clone();
         for (key, value) in &final_parameters {
             let placeholder = format!("{{{}}}", key);
             final_prompt = final_prompt.replace(&placeholder, value);
         }
+        let mut stack = vec![template.id.clone()];
+        final_prompt = self.expand_template_references(&final_prompt, &request.parameters, &mut stack, &mut missing_parameters)?;
         
         // 添加角色基礎描述
         if let Some(char_desc) = &request.character_description {
@@ -840,6 +991,507 @@ impl PromptTemplateManager {
         Ok(result)
     }
     
+    /// 與資料庫同步：以 `INSERT OR IGNORE` 補齊內建模板，並載入使用者模板
+    ///
+    /// 內建模板以程式碼中的定義為準，僅從資料庫還原使用次數與評分。
+    pub fn sync_with_database(&mut self, conn: &Connection) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        for id in &self.builtin_ids {
+            let template = &self.templates[id];
+            conn.execute(
+                "INSERT OR IGNORE INTO prompt_templates (id, name, category, template_data, is_builtin, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, 1, ?5, ?5)",
+                params![id, template.name, serde_json::to_string(&template.category)?, serde_json::to_string(template)?, now],
+            )?;
+        }
+        
+        let mut stmt = conn.prepare("SELECT template_data, is_builtin FROM prompt_templates")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)))?;
+        let mut loaded = 0;
+        for row in rows {
+            let (data, is_builtin) = row?;
+            match serde_json::from_str::<PromptTemplate>(&data) {
+                Ok(stored) if is_builtin => {
+                    // 內建模板只沿用資料庫中的使用統計
+                    if let Some(template) = self.templates.get_mut(&stored.id) {
+                        template.usage_count = stored.usage_count;
+                        template.average_rating = stored.average_rating;
+                        template.rating_count = stored.rating_count;
+                    }
+                }
+                Ok(template) => {
+                    self.add_template(template)?;
+                    loaded += 1;
+                }
+                Err(e) => log::warn!("[PromptTemplateManager] 略過無法解析的模板: {}", e),
+            }
+        }
+        
+        log::info!("[PromptTemplateManager] 資料庫同步完成，載入 {} 個使用者模板", loaded);
+        Ok(())
+    }
+    
+    /// 建立使用者模板並寫入資料庫
+    pub fn create_template(&mut self, conn: &Connection, input: PromptTemplateInput) -> Result<PromptTemplate> {
+        Self::validate_template_input(&input)?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let template = PromptTemplate {
+            id: format!("user_{}", uuid::Uuid::new_v4()),
+            name: input.name.trim().to_string(),
+            description: input.description,
+            category: input.category.unwrap_or(TemplateCategory::Custom),
+            subcategory: input.subcategory,
+            template: input.template,
+            required_parameters: input.required_parameters,
+            optional_parameters: input.optional_parameters,
+            default_values: input.default_values,
+            quality_modifiers: input.quality_modifiers,
+            negative_prompts: input.negative_prompts,
+            style_tags: input.style_tags,
+            usage_count: 0,
+            rating_count: 0,
+            average_rating: 0.0,
+            success_rate: 1.0,
+            author: "使用者".to_string(),
+            version: "1.0".to_string(),
+            language: input.language.unwrap_or_else(|| "zh".to_string()),
+            translation_group_id: input.translation_group_id,
+            tags: input.tags,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        Self::ensure_valid_template(&template)?;
+        
+        conn.execute(
+            "INSERT INTO prompt_templates (id, name, category, template_data, is_builtin, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6)",
+            params![
+                template.id,
+                template.name,
+                serde_json::to_string(&template.category)?,
+                serde_json::to_string(&template)?,
+                template.created_at,
+                template.updated_at,
+            ],
+        )?;
+        self.add_template(template.clone())?;
+        
+        log::info!("[PromptTemplateManager] 已建立模板: {}", template.name);
+        Ok(template)
+    }
+    
+    /// 更新使用者模板（內建模板不可修改），保留使用統計與建立時間
+    pub fn update_template(&mut self, conn: &Connection, template_id: &str, input: PromptTemplateInput) -> Result<PromptTemplate> {
+        self.ensure_user_template(template_id)?;
+        Self::validate_template_input(&input)?;
+        
+        let existing = self.templates[template_id].clone();
+        let template = PromptTemplate {
+            name: input.name.trim().to_string(),
+            description: input.description,
+            category: input.category.unwrap_or(existing.category.clone()),
+            subcategory: input.subcategory,
+            template: input.template,
+            required_parameters: input.required_parameters,
+            optional_parameters: input.optional_parameters,
+            default_values: input.default_values,
+            quality_modifiers: input.quality_modifiers,
+            negative_prompts: input.negative_prompts,
+            style_tags: input.style_tags,
+            language: input.language.unwrap_or(existing.language.clone()),
+            translation_group_id: input.translation_group_id,
+            tags: input.tags,
+            updated_at: chrono::Utc::now().to_rfc3339(),
+            ..existing.clone()
+        };
+        Self::ensure_valid_template(&template)?;
+        
+        conn.execute(
+            "UPDATE prompt_templates SET name = ?1, category = ?2, template_data = ?3, updated_at = ?4 WHERE id = ?5",
+            params![
+                template.name,
+                serde_json::to_string(&template.category)?,
+                serde_json::to_string(&template)?,
+                template.updated_at,
+                template_id,
+            ],
+        )?;
+        
+        self.remove_from_index(template_id, &existing.category);
+        self.add_template(template.clone())?;
+        Ok(template)
+    }
+    
+    /// 刪除使用者模板（內建模板不可刪除）
+    pub fn delete_template(&mut self, conn: &Connection, template_id: &str) -> Result<()> {
+        self.ensure_user_template(template_id)?;
+        
+        conn.execute("DELETE FROM prompt_templates WHERE id = ?1", params![template_id])?;
+        if let Some(template) = self.templates.remove(template_id) {
+            self.remove_from_index(template_id, &template.category);
+        }
+        Ok(())
+    }
+    
+    /// 記錄模板被套用一次
+    pub fn record_usage(&mut self, conn: &Connection, template_id: &str) -> Result<()> {
+        let template = self.templates.get_mut(template_id)
+            .ok_or_else(|| TranslationError::OptimizationError(format!("模板不存在: {}", template_id)))?;
+        template.usage_count += 1;
+        
+        let template = template.clone();
+        Self::persist_template(conn, &template)
+    }
+    
+    /// 為模板評分（1-5），以累計平均更新 `average_rating`
+    ///
+    /// 尚無使用者評分時，第一筆評分會取代模板的預設評分。
+    pub fn rate_template(&mut self, conn: &Connection, template_id: &str, rating: f64) -> Result<PromptTemplate> {
+        if !(1.0..=5.0).contains(&rating) {
+            return Err(TranslationError::OptimizationError(format!("評分必須介於 1 到 5 之間: {}", rating)));
+        }
+        let template = self.templates.get_mut(template_id)
+            .ok_or_else(|| TranslationError::OptimizationError(format!("模板不存在: {}", template_id)))?;
+        
+        let count = template.rating_count as f64;
+        template.average_rating = (template.average_rating * count + rating) / (count + 1.0);
+        template.rating_count += 1;
+        
+        let template = template.clone();
+        Self::persist_template(conn, &template)?;
+        Ok(template)
+    }
+    
+    /// 將模板目前狀態寫回資料庫（內建模板的列由 `sync_with_database` 建立）
+    fn persist_template(conn: &Connection, template: &PromptTemplate) -> Result<()> {
+        conn.execute(
+            "UPDATE prompt_templates SET template_data = ?1 WHERE id = ?2",
+            params![serde_json::to_string(template)?, template.id],
+        )?;
+        Ok(())
+    }
+    
+    fn ensure_user_template(&self, template_id: &str) -> Result<()> {
+        if self.builtin_ids.contains(template_id) {
+            return Err(TranslationError::OptimizationError(format!("內建模板不可修改或刪除: {}", template_id)));
+        }
+        if !self.templates.contains_key(template_id) {
+            return Err(TranslationError::OptimizationError(format!("模板不存在: {}", template_id)));
+        }
+        Ok(())
+    }
+    
+    fn remove_from_index(&mut self, template_id: &str, category: &TemplateCategory) {
+        if let Some(ids) = self.template_categories.get_mut(category) {
+            ids.retain(|id| id != template_id);
+            if ids.is_empty() {
+                self.template_categories.remove(category);
+            }
+        }
+    }
+    
+    /// 驗證模板輸入：名稱與內容不可為空
+    fn validate_template_input(input: &PromptTemplateInput) -> Result<()> {
+        if input.name.trim().is_empty() {
+            return Err(TranslationError::OptimizationError("模板名稱不可為空".to_string()));
+        }
+        if input.template.trim().is_empty() {
+            return Err(TranslationError::OptimizationError("模板內容不可為空".to_string()));
+        }
+        Ok(())
+    }
+    
+    /// 檢查模板內容的佔位符與宣告的參數是否一致
+    ///
+    /// `{{template:id}}` 引用不視為佔位符。
+    pub fn validate_template(template: &PromptTemplate) -> TemplateValidationReport {
+        let placeholders = extract_placeholders(&template.template);
+        let declared: Vec<&String> = template.required_parameters.iter()
+            .chain(template.optional_parameters.iter())
+            .collect();
+        
+        let mut undeclared_placeholders = Vec::new();
+        for placeholder in &placeholders {
+            if !declared.contains(&placeholder) && !undeclared_placeholders.contains(placeholder) {
+                undeclared_placeholders.push(placeholder.clone());
+            }
+        }
+        let mut unused_parameters = Vec::new();
+        for param in declared {
+            if !placeholders.contains(param) && !unused_parameters.contains(param) {
+                unused_parameters.push(param.clone());
+            }
+        }
+        
+        TemplateValidationReport {
+            template_id: template.id.clone(),
+            template_name: template.name.clone(),
+            is_valid: undeclared_placeholders.is_empty() && unused_parameters.is_empty(),
+            undeclared_placeholders,
+            unused_parameters,
+        }
+    }
+    
+    /// 檢查所有模板，回傳有問題的模板報告（依 ID 排序）
+    pub fn validate_all_templates(&self) -> Vec<TemplateValidationReport> {
+        let mut reports: Vec<TemplateValidationReport> = self.templates.values()
+            .map(Self::validate_template)
+            .filter(|report| !report.is_valid)
+            .collect();
+        reports.sort_by(|a, b| a.template_id.cmp(&b.template_id));
+        reports
+    }
+    
+    fn ensure_valid_template(template: &PromptTemplate) -> Result<()> {
+        let report = Self::validate_template(template);
+        let mut problems = Vec::new();
+        if !report.undeclared_placeholders.is_empty() {
+            problems.push(format!("未宣告的佔位符: {}", report.undeclared_placeholders.join(", ")));
+        }
+        if !report.unused_parameters.is_empty() {
+            problems.push(format!("參數未出現在模板中: {}", report.unused_parameters.join(", ")));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(TranslationError::OptimizationError(problems.join("；")))
+        }
+    }
+    
+    /// 依角色屬性與外貌描述建議模板參數
+    ///
+    /// 屬性鍵名與參數名稱（或常見中文鍵名）相同時優先採用，
+    /// 其餘參數以詞彙庫掃描描述文字，依詞彙分類填入對應參數。
+    pub fn suggest_parameters(
+        &self,
+        template_id: &str,
+        attributes: &[(String, String)],
+        descriptions: &[String],
+        vocabulary_db: &VocabularyDatabase,
+    ) -> Result<TemplateParameterSuggestion> {
+        let template = self.templates.get(template_id)
+            .ok_or_else(|| TranslationError::OptimizationError(format!("模板不存在: {}", template_id)))?;
+        
+        let accepts = |param: &str| {
+            template.required_parameters.iter().chain(template.optional_parameters.iter()).any(|p| p == param)
+        };
+        let clothing_param = CLOTHING_PARAMETERS.iter().copied().find(|p| accepts(p));
+        
+        let mut parameters = HashMap::new();
+        let mut sources = HashMap::new();
+        
+        // 1. 角色屬性
+        for (key, value) in attributes {
+            let key_trimmed = key.trim();
+            let param = if accepts(key_trimmed) {
+                Some(key_trimmed)
+            } else {
+                ATTRIBUTE_PARAMETER_ALIASES.iter()
+                    .find(|(_, aliases)| aliases.iter().any(|a| a.eq_ignore_ascii_case(key_trimmed)))
+                    .map(|(param, _)| *param)
+                    .filter(|param| accepts(param))
+            };
+            let Some(param) = param else { continue };
+            if parameters.contains_key(param) {
+                continue;
+            }
+            if let Some(english) = Self::attribute_to_english(param, value, vocabulary_db)? {
+                parameters.insert(param.to_string(), english);
+                sources.insert(param.to_string(), key.clone());
+            }
+        }
+        
+        // 2. 描述文字中的詞彙
+        let mut described: Vec<(String, Vec<String>)> = Vec::new();
+        for description in descriptions {
+            for entry in vocabulary_db.scan_terms(description)? {
+                let param = match entry.category {
+                    VocabularyCategory::Clothing => clothing_param,
+                    ref category => category_parameter(category).filter(|p| accepts(p)),
+                };
+                let Some(param) = param else { continue };
+                if parameters.contains_key(param) {
+                    continue;
+                }
+                match described.iter_mut().find(|(p, _)| p == param) {
+                    Some((_, terms)) if !terms.contains(&entry.english_term) => terms.push(entry.english_term),
+                    Some(_) => {}
+                    None => described.push((param.to_string(), vec![entry.english_term])),
+                }
+            }
+        }
+        for (param, terms) in described {
+            parameters.insert(param.clone(), terms.join(", "));
+            sources.insert(param, "角色描述".to_string());
+        }
+        
+        let missing_parameters = template.required_parameters.iter()
+            .filter(|p| !parameters.contains_key(*p) && !template.default_values.contains_key(*p))
+            .cloned()
+            .collect();
+        
+        Ok(TemplateParameterSuggestion {
+            template_id: template_id.to_string(),
+            parameters,
+            sources,
+            missing_parameters,
+        })
+    }
+    
+    /// 將屬性值轉為英文參數值：數字與英文直接採用，中文以詞彙庫翻譯
+    fn attribute_to_english(param: &str, value: &str, vocabulary_db: &VocabularyDatabase) -> Result<Option<String>> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(None);
+        }
+        if param == "gender" {
+            return Ok(match value {
+                "女" | "女性" | "女生" => Some("girl".to_string()),
+                "男" | "男性" | "男生" => Some("boy".to_string()),
+                other if other.is_ascii() => Some(other.to_string()),
+                _ => None,
+            });
+        }
+        if param == "age" {
+            let digits: String = value.chars().filter(|c| c.is_ascii_digit()).collect();
+            return Ok((!digits.is_empty()).then_some(digits));
+        }
+        if value.is_ascii() {
+            return Ok(Some(value.to_string()));
+        }
+        
+        let terms: Vec<String> = vocabulary_db.scan_terms(value)?
+            .into_iter()
+            .map(|entry| entry.english_term)
+            .collect();
+        Ok((!terms.is_empty()).then(|| terms.join(", ")))
+    }
+    
+    /// 以同一組參數評估多個模板的預估品質，依分數由高到低排序（分數相同時缺少參數較少者優先）
+    pub fn preview_quality(&self, template_ids: &[String], parameters: &HashMap<String, String>) -> Result<Vec<TemplateQualityPreview>> {
+        let mut previews = Vec::new();
+        for template_id in template_ids {
+            let result = self.apply_template(TemplateApplicationRequest {
+                template_id: template_id.clone(),
+                parameters: parameters.clone(),
+                character_description: None,
+                apply_quality_modifiers: false,
+                include_negative_prompts: false,
+                target_style: None,
+            })?;
+            previews.push(TemplateQualityPreview {
+                template_id: template_id.clone(),
+                template_name: result.template_info.template_name,
+                estimated_quality_score: result.estimated_quality_score,
+                missing_parameter_count: result.missing_parameters.len(),
+                missing_parameters: result.missing_parameters,
+            });
+        }
+        
+        previews.sort_by(|a, b| {
+            b.estimated_quality_score.total_cmp(&a.estimated_quality_score)
+                .then(a.missing_parameter_count.cmp(&b.missing_parameter_count))
+        });
+        Ok(previews)
+    }
+    
+    /// 展開文字中的 `{{template:id}}` 引用
+    ///
+    /// 被引用的模板使用請求中的共用參數，`id.參數名` 形式的參數只提供給該模板；
+    /// 被引用模板缺少的必需參數以 `id.參數名` 回報。
+    fn expand_template_references(
+        &self,
+        text: &str,
+        parameters: &HashMap<String, String>,
+        stack: &mut Vec<String>,
+        missing_parameters: &mut Vec<String>,
+    ) -> Result<String> {
+        let mut output = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find(TEMPLATE_REFERENCE_PREFIX) {
+            let after = &rest[start + TEMPLATE_REFERENCE_PREFIX.len()..];
+            let Some(end) = after.find("}}") else { break };
+            let reference_id = after[..end].trim();
+            output.push_str(&rest[..start]);
+            output.push_str(&self.render_referenced_template(reference_id, parameters, stack, missing_parameters)?);
+            rest = &after[end + 2..];
+        }
+        output.push_str(rest);
+        Ok(output)
+    }
+    
+    fn render_referenced_template(
+        &self,
+        template_id: &str,
+        parameters: &HashMap<String, String>,
+        stack: &mut Vec<String>,
+        missing_parameters: &mut Vec<String>,
+    ) -> Result<String> {
+        if stack.iter().any(|id| id == template_id) {
+            return Err(TranslationError::OptimizationError(
+                format!("模板循環引用: {} -> {}", stack.join(" -> "), template_id)
+            ));
+        }
+        if stack.len() > MAX_TEMPLATE_REFERENCE_DEPTH {
+            return Err(TranslationError::OptimizationError(
+                format!("模板引用層數超過上限 ({}): {}", MAX_TEMPLATE_REFERENCE_DEPTH, template_id)
+            ));
+        }
+        let template = self.templates.get(template_id)
+            .ok_or_else(|| TranslationError::OptimizationError(format!("引用的模板不存在: {}", template_id)))?;
+        
+        // 共用參數（不含其他模板的專屬參數），再以本模板的專屬參數覆蓋
+        let prefix = format!("{}.", template_id);
+        let mut child_parameters = template.default_values.clone();
+        for (key, value) in parameters {
+            if !key.contains('.') {
+                child_parameters.insert(key.clone(), value.clone());
+            }
+        }
+        for (key, value) in parameters {
+            if let Some(name) = key.strip_prefix(&prefix) {
+                child_parameters.insert(name.to_string(), value.clone());
+            }
+        }
+        
+        for required_param in &template.required_parameters {
+            if !child_parameters.contains_key(required_param) {
+                missing_parameters.push(format!("{}{}", prefix, required_param));
+            }
+        }
+        
+        let mut body = template.template.clone();
+        for (key, value) in &child_parameters {
+            body = body.replace(&format!("{{{}}}", key), value);
+        }
+        
+        stack.push(template_id.to_string());
+        let expanded = self.expand_template_references(&body, parameters, stack, missing_parameters);
+        stack.pop();
+        expanded
+    }
+    
+    /// 取得模板的指定語言版本（依 `translation_group_id` 尋找同組模板）
+    ///
+    /// 模板本身已是該語言時直接回傳；找不到對應語言版本時回傳 `None`。
+    pub fn get_template_in_language(&self, template_id: &str, target_lang: &str) -> Result<Option<PromptTemplate>> {
+        let template = self.templates.get(template_id)
+            .ok_or_else(|| TranslationError::OptimizationError(format!("模板不存在: {}", template_id)))?;
+        
+        if template.language == target_lang {
+            return Ok(Some(template.clone()));
+        }
+        let Some(group_id) = template.translation_group_id.as_deref() else {
+            return Ok(None);
+        };
+        
+        let mut siblings: Vec<&PromptTemplate> = self.templates.values()
+            .filter(|t| t.translation_group_id.as_deref() == Some(group_id) && t.language == target_lang)
+            .collect();
+        siblings.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(siblings.first().map(|t| (*t).clone()))
+    }
+    
     /// 搜尋模板
     pub fn search_templates(&self, request: TemplateSearchRequest) -> Result<Vec<PromptTemplate>> {
         let mut results: Vec<&PromptTemplate> = self.templates.values().collect();
@@ -959,4 +1611,192 @@ pub struct TemplateStats {
     pub average_rating: f64,
     pub total_usage: u32,
     pub last_updated: String,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_suggest_parameters_from_attributes_and_description() {
+        let conn = Connection::open_in_memory().unwrap();
+        let vocabulary_db = VocabularyDatabase::new(std::sync::Arc::new(std::sync::Mutex::new(conn)));
+        let manager = PromptTemplateManager::new();
+
+        let attributes = vec![
+            ("性別".to_string(), "女".to_string()),
+            ("年齡".to_string(), "17歲".to_string()),
+            ("瞳色".to_string(), "未知的顏色".to_string()),
+        ];
+        let descriptions = vec!["黑髮雙馬尾，總是帶著酒窩".to_string()];
+
+        let suggestion = manager
+            .suggest_parameters("student_character", &attributes, &descriptions, &vocabulary_db)
+            .unwrap();
+
+        assert_eq!(suggestion.parameters["gender"], "girl");
+        assert_eq!(suggestion.parameters["age"], "17");
+        assert_eq!(suggestion.parameters["hair_description"], "black hair, twintails");
+        assert_eq!(suggestion.sources["hair_description"], "角色描述");
+        // 瞳色無法翻譯，仍列為缺少；uniform_type 有預設值不列入
+        assert_eq!(suggestion.missing_parameters, vec!["eye_description"]);
+    }
+
+    fn template_input(name: &str) -> PromptTemplateInput {
+        PromptTemplateInput {
+            name: name.to_string(),
+            description: String::new(),
+            category: None,
+            subcategory: None,
+            template: "{gender} knight, {armor_color} armor".to_string(),
+            required_parameters: vec!["gender".to_string(), "armor_color".to_string()],
+            optional_parameters: Vec::new(),
+            default_values: HashMap::new(),
+            quality_modifiers: Vec::new(),
+            negative_prompts: Vec::new(),
+            style_tags: Vec::new(),
+            tags: Vec::new(),
+            language: None,
+            translation_group_id: None,
+        }
+    }
+
+    #[test]
+    fn test_user_templates_persist_across_managers() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::apply_migration_v25(&conn).unwrap();
+
+        let mut manager = PromptTemplateManager::new();
+        manager.sync_with_database(&conn).unwrap();
+        // 重複同步不會重複寫入內建模板
+        manager.sync_with_database(&conn).unwrap();
+        let builtin_rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM prompt_templates WHERE is_builtin = 1", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(builtin_rows as usize, manager.builtin_ids.len());
+
+        let created = manager.create_template(&conn, template_input("騎士")).unwrap();
+        assert!(manager.create_template(&conn, PromptTemplateInput { template: "{gender} knight".to_string(), ..template_input("缺參數") }).is_err());
+        manager.update_template(&conn, &created.id, template_input("聖騎士")).unwrap();
+        assert!(manager.delete_template(&conn, "student_character").is_err());
+
+        let mut reloaded = PromptTemplateManager::new();
+        reloaded.sync_with_database(&conn).unwrap();
+        let custom = reloaded.get_templates_by_category(&TemplateCategory::Custom);
+        assert_eq!(custom.len(), 1);
+        assert_eq!(custom[0].name, "聖騎士");
+
+        reloaded.record_usage(&conn, "student_character").unwrap();
+        reloaded.rate_template(&conn, "student_character", 4.0).unwrap();
+        let rated = reloaded.rate_template(&conn, "student_character", 5.0).unwrap();
+        assert_eq!(rated.average_rating, 4.5);
+        assert!(reloaded.rate_template(&conn, "student_character", 6.0).is_err());
+
+        // 內建模板的使用統計在重新載入後保留
+        let mut again = PromptTemplateManager::new();
+        again.sync_with_database(&conn).unwrap();
+        let student = again.get_templates_by_category(&TemplateCategory::CharacterTypes)
+            .into_iter()
+            .find(|t| t.id == "student_character")
+            .unwrap();
+        assert_eq!(student.usage_count, 1);
+        assert_eq!(student.rating_count, 2);
+
+        reloaded.delete_template(&conn, &created.id).unwrap();
+        assert!(reloaded.get_templates_by_category(&TemplateCategory::Custom).is_empty());
+    }
+
+    fn apply_request(template_id: &str, parameters: &[(&str, &str)]) -> TemplateApplicationRequest {
+        TemplateApplicationRequest {
+            template_id: template_id.to_string(),
+            parameters: parameters.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            character_description: None,
+            apply_quality_modifiers: false,
+            include_negative_prompts: false,
+            target_style: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_template_expands_references() {
+        let mut manager = PromptTemplateManager::new();
+        let result = manager.apply_template(apply_request("complete_character_scene", &[
+            ("character_template", "{{template:student_character}}"),
+            ("background_template", "{{template:school_background}}"),
+            ("gender", "girl"),
+            ("student_character.hair_description", "black hair"),
+        ])).unwrap();
+
+        assert!(result.final_prompt.starts_with("16 year old girl, black hair"));
+        assert!(!result.final_prompt.contains("{{template:"));
+        assert!(result.missing_parameters.contains(&"student_character.eye_description".to_string()));
+
+        // 循環引用會被拒絕
+        for (id, body) in [("loop_a", "{{template:loop_b}}"), ("loop_b", "{{template:loop_a}}")] {
+            manager.add_template(PromptTemplate { id: id.to_string(), template: body.to_string(), ..manager.templates["student_character"].clone() }).unwrap();
+        }
+        assert!(manager.apply_template(apply_request("loop_a", &[])).unwrap_err().to_string().contains("循環引用"));
+    }
+
+    #[test]
+    fn test_validate_template_reports_placeholder_mismatches() {
+        let manager = PromptTemplateManager::new();
+        let template = PromptTemplate {
+            template: "{gender} knight, {armor_color} armor, {{template:soft_lighting}}".to_string(),
+            required_parameters: vec!["gender".to_string()],
+            optional_parameters: vec!["weapon".to_string()],
+            ..manager.templates["student_character"].clone()
+        };
+
+        let report = PromptTemplateManager::validate_template(&template);
+        assert!(!report.is_valid);
+        assert_eq!(report.undeclared_placeholders, vec!["armor_color"]);
+        assert_eq!(report.unused_parameters, vec!["weapon"]);
+        // 內建的學生模板宣告了未使用的 accessories
+        let reports = manager.validate_all_templates();
+        let student = reports.iter().find(|r| r.template_id == "student_character").unwrap();
+        assert_eq!(student.unused_parameters, vec!["accessories"]);
+    }
+
+    #[test]
+    fn test_get_template_in_language_follows_translation_group() {
+        let conn = Connection::open_in_memory().unwrap();
+        crate::database::migrations::apply_migration_v25(&conn).unwrap();
+        let mut manager = PromptTemplateManager::new();
+
+        let zh = manager.create_template(&conn, PromptTemplateInput {
+            translation_group_id: Some("knight".to_string()),
+            ..template_input("騎士")
+        }).unwrap();
+        let en = manager.create_template(&conn, PromptTemplateInput {
+            language: Some("en".to_string()),
+            translation_group_id: Some("knight".to_string()),
+            ..template_input("Knight")
+        }).unwrap();
+
+        assert_eq!(manager.get_template_in_language(&zh.id, "en").unwrap().unwrap().id, en.id);
+        assert_eq!(manager.get_template_in_language(&en.id, "en").unwrap().unwrap().id, en.id);
+        assert!(manager.get_template_in_language(&zh.id, "ja").unwrap().is_none());
+        assert!(manager.get_template_in_language("student_character", "en").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_preview_quality_ranks_best_fit_first() {
+        let manager = PromptTemplateManager::new();
+        let parameters: HashMap<String, String> = [
+            ("gender", "girl"),
+            ("hair_description", "black hair"),
+            ("eye_description", "blue eyes"),
+        ].into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+
+        let previews = manager
+            .preview_quality(&["complete_character_scene".to_string(), "student_character".to_string()], &parameters)
+            .unwrap();
+
+        assert_eq!(previews[0].template_id, "student_character");
+        assert_eq!(previews[0].missing_parameter_count, 0);
+        assert!(previews[1].missing_parameter_count > 0);
+        assert!(manager.preview_quality(&["no_such_template".to_string()], &parameters).is_err());
+    }
+}
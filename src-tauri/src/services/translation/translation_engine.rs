@@ -30,6 +30,9 @@ pub struct TranslationRequest {
     pub quality_level: QualityLevel,
     pub context_hints: Vec<String>,
     pub preserve_original: bool, // 是否保留原文
+    /// 鎖定詞彙（中文 → 英文）：描述中出現時原樣使用，優先於詞彙庫匹配
+    #[serde(default)]
+    pub locked_terms: HashMap<String, String>,
 }
 
 /// 翻譯結果
@@ -69,6 +72,32 @@ pub struct ExtractedFeature {
     pub category: VocabularyCategory,
 }
 
+/// 詞彙覆蓋率分析報告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub matched_terms: Vec<CoverageMatch>,
+    /// 未被詞彙庫涵蓋的片段（依出現順序，不重複）
+    pub untranslated_terms: Vec<String>,
+    /// 被詞彙涵蓋的字符比例 0-100
+    pub coverage_percentage: f64,
+    pub covered_characters: usize,
+    pub total_characters: usize,
+}
+
+/// 覆蓋率分析中匹配到的詞彙
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageMatch {
+    pub chinese_term: String,
+    pub english_term: String,
+    pub category: VocabularyCategory,
+    pub vocabulary_entry_id: i64,
+    /// 在描述中出現的次數
+    pub occurrences: usize,
+}
+
+/// 連接用的虛詞，不計入覆蓋率分母
+const COVERAGE_FILLER_CHARS: &[char] = &['的', '和', '與', '及', '且', '又', '很', '有', '是', '著'];
+
 /// 翻譯風格
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TranslationStyle {
@@ -192,8 +221,12 @@ impl TranslationEngine {
         
         log::info!("[TranslationEngine] 開始翻譯: {}", request.chinese_description);
 
+        // 0. 先取出鎖定詞彙，避免詞彙庫再次匹配到同一段文字
+        let (locked_renderings, unlocked_description) =
+            Self::extract_locked_terms(&request.chinese_description, &request.locked_terms);
+
         // 1. 預處理中文描述
-        let cleaned_description = self.preprocess_chinese_text(&request.chinese_description);
+        let cleaned_description = self.preprocess_chinese_text(&unlocked_description);
         
         // 2. 特徵提取
         let extracted_features = self.extract_features(&cleaned_description)?;
@@ -220,8 +253,8 @@ impl TranslationEngine {
                     });
                     vocabulary_coverage_count += 1;
                     
-                    // 更新使用統計
-                    let _ = self.vocabulary_db.update_usage_stats(translation.id, true);
+                    // 更新使用統計（成功率由使用者回饋另行記錄）
+                    let _ = self.vocabulary_db.record_usage(translation.id);
                 } else {
                     unmatched_terms.push(feature.chinese_term.clone());
                     
@@ -235,6 +268,7 @@ impl TranslationEngine {
                             priority: fuzzy_match.priority - 1,
                         });
                         vocabulary_coverage_count += 1;
+                        let _ = self.vocabulary_db.record_usage(fuzzy_match.id);
                     }
                 }
             }
@@ -244,7 +278,12 @@ impl TranslationEngine {
         let organized_prompt = self.organize_prompt(translation_parts, &request)?;
         
         // 5. 後處理優化
-        let final_prompt = self.post_process_prompt(organized_prompt, &request)?;
+        let final_prompt = self.post_process_prompt(organized_prompt, &locked_renderings, &request)?;
+
+        // 確認每個鎖定詞彙的英文都原樣出現在輸出中
+        if let Some(missing) = locked_renderings.iter().find(|english| !final_prompt.contains(english.as_str())) {
+            return Err(TranslationError::EngineError(format!("鎖定詞彙未出現在輸出中: {}", missing)));
+        }
 
         // 6. 生成替代翻譯
         let alternatives = self.generate_alternatives(&final_prompt, &extracted_features)?;
@@ -259,6 +298,11 @@ impl TranslationEngine {
 
         let processing_time = start_time.elapsed().as_millis() as u64;
 
+        let mut applied_transformations = vec!["grammar_optimization".to_string(), "weight_sorting".to_string()];
+        if !locked_renderings.is_empty() {
+            applied_transformations.push("locked_terms".to_string());
+        }
+
         let result = TranslationResult {
             english_prompt: final_prompt,
             original_chinese: if request.preserve_original {
@@ -270,7 +314,7 @@ impl TranslationEngine {
             translation_breakdown: TranslationBreakdown {
                 extracted_features,
                 unmatched_terms,
-                applied_transformations: vec!["grammar_optimization".to_string(), "weight_sorting".to_string()],
+                applied_transformations,
                 grammar_adjustments: vec!["article_insertion".to_string(), "adjective_ordering".to_string()],
             },
             suggestions: self.generate_suggestions(&vocabulary_coverage),
@@ -286,6 +330,124 @@ impl TranslationEngine {
         Ok(result)
     }
 
+    /// 分析中文描述的詞彙覆蓋率（不執行翻譯、不更新使用統計）
+    pub fn analyze_coverage(&self, chinese_text: &str) -> Result<CoverageReport> {
+        let cleaned = self.preprocess_chinese_text(chinese_text);
+        let features = self.extract_features(&cleaned)?;
+
+        // 只採用描述中實際出現的詞彙，同一詞彙保留第一筆（較長的詞優先標記）
+        let mut matched: Vec<CoverageMatch> = Vec::new();
+        for feature in features.into_values().flatten() {
+            let Some(entry_id) = feature.vocabulary_entry_id else { continue };
+            let Some(entry) = self.vocabulary_db.get_entry(entry_id)? else { continue };
+            if !cleaned.contains(&entry.chinese_term) || matched.iter().any(|m| m.chinese_term == entry.chinese_term) {
+                continue;
+            }
+            matched.push(CoverageMatch {
+                chinese_term: entry.chinese_term,
+                english_term: entry.english_term,
+                category: entry.category,
+                vocabulary_entry_id: entry_id,
+                occurrences: 0,
+            });
+        }
+        matched.sort_by_key(|m| std::cmp::Reverse(m.chinese_term.chars().count()));
+
+        let chars: Vec<char> = cleaned.chars().collect();
+        let mut covered = vec![false; chars.len()];
+        for term in &mut matched {
+            let term_chars: Vec<char> = term.chinese_term.chars().collect();
+            let mut index = 0;
+            while index + term_chars.len() <= chars.len() {
+                let window = &chars[index..index + term_chars.len()];
+                if window == term_chars.as_slice() && !covered[index..index + term_chars.len()].iter().any(|c| *c) {
+                    covered[index..index + term_chars.len()].iter_mut().for_each(|c| *c = true);
+                    term.occurrences += 1;
+                    index += term_chars.len();
+                } else {
+                    index += 1;
+                }
+            }
+        }
+        // 被較長詞彙完全覆蓋的短詞不列入匹配結果
+        matched.retain(|term| term.occurrences > 0);
+
+        // 收集未覆蓋的連續片段，去除頭尾虛詞
+        let mut untranslated_terms: Vec<String> = Vec::new();
+        let mut total_characters = 0;
+        let mut covered_characters = 0;
+        let mut segment = String::new();
+        for (ch, is_covered) in chars.iter().zip(covered.iter()) {
+            if !ch.is_whitespace() && !COVERAGE_FILLER_CHARS.contains(ch) {
+                total_characters += 1;
+                if *is_covered {
+                    covered_characters += 1;
+                }
+            }
+            if *is_covered || ch.is_whitespace() {
+                Self::push_untranslated_segment(&mut untranslated_terms, &segment);
+                segment.clear();
+            } else {
+                segment.push(*ch);
+            }
+        }
+        Self::push_untranslated_segment(&mut untranslated_terms, &segment);
+
+        let coverage_percentage = if total_characters > 0 {
+            covered_characters as f64 / total_characters as f64 * 100.0
+        } else {
+            0.0
+        };
+
+        log::info!("[TranslationEngine] 覆蓋率分析完成: {:.1}%（匹配 {} 個詞彙，未翻譯 {} 個片段）",
+                   coverage_percentage, matched.len(), untranslated_terms.len());
+
+        Ok(CoverageReport {
+            matched_terms: matched,
+            untranslated_terms,
+            coverage_percentage,
+            covered_characters,
+            total_characters,
+        })
+    }
+
+    fn push_untranslated_segment(segments: &mut Vec<String>, segment: &str) {
+        let trimmed = segment.trim_matches(|c| COVERAGE_FILLER_CHARS.contains(&c));
+        if !trimmed.is_empty() && !segments.iter().any(|s| s == trimmed) {
+            segments.push(trimmed.to_string());
+        }
+    }
+
+    /// 找出描述中的鎖定詞彙，回傳（依出現順序的英文、移除鎖定詞彙後的描述）
+    ///
+    /// 較長的詞彙優先，避免「魔法少女制服」被其中的「魔法少女」拆開。
+    fn extract_locked_terms(description: &str, locked_terms: &HashMap<String, String>) -> (Vec<String>, String) {
+        let mut terms: Vec<(&String, &String)> = locked_terms
+            .iter()
+            .filter(|(chinese, english)| !chinese.trim().is_empty() && !english.trim().is_empty())
+            .collect();
+        terms.sort_by(|a, b| b.0.chars().count().cmp(&a.0.chars().count()).then(a.0.cmp(b.0)));
+
+        let mut remaining = description.to_string();
+        let mut found: Vec<(usize, String)> = Vec::new();
+        for (chinese, english) in terms {
+            if let Some(position) = remaining.find(chinese.as_str()) {
+                found.push((position, english.trim().to_string()));
+                // 以等長空白取代，保留其餘詞彙的位置以便排序
+                remaining = remaining.replace(chinese.as_str(), &" ".repeat(chinese.len()));
+            }
+        }
+        found.sort_by_key(|(position, _)| *position);
+
+        let mut renderings: Vec<String> = Vec::new();
+        for (_, english) in found {
+            if !renderings.contains(&english) {
+                renderings.push(english);
+            }
+        }
+        (renderings, remaining)
+    }
+
     /// 預處理中文文本
     fn preprocess_chinese_text(&self, text: &str) -> String {
         // 移除標點符號並標準化空格
@@ -434,7 +596,7 @@ impl TranslationEngine {
     }
 
     /// 後處理優化
-    fn post_process_prompt(&self, prompt: String, request: &TranslationRequest) -> Result<String> {
+    fn post_process_prompt(&self, prompt: String, locked_renderings: &[String], request: &TranslationRequest) -> Result<String> {
         let mut optimized = prompt;
 
         // 移除重複詞彙
@@ -445,6 +607,16 @@ impl TranslationEngine {
             optimized = self.adjust_grammar(optimized)?;
         }
 
+        // 鎖定詞彙在語法調整之後加入，確保原樣輸出
+        if !locked_renderings.is_empty() {
+            let locked = locked_renderings.join(", ");
+            optimized = if optimized.is_empty() {
+                locked
+            } else {
+                format!("{}, {}", locked, optimized)
+            };
+        }
+
         // 品質修飾詞
         match request.quality_level {
             QualityLevel::Professional => {
@@ -585,4 +757,57 @@ struct TranslationPart {
     weight: f64,
     category: VocabularyCategory,
     priority: i32,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    #[test]
+    fn test_analyze_coverage_lists_matched_and_untranslated_terms() {
+        let conn = Connection::open_in_memory().unwrap();
+        let db = VocabularyDatabase::new(std::sync::Arc::new(std::sync::Mutex::new(conn)));
+        let engine = TranslationEngine::new(db).unwrap();
+
+        let report = engine.analyze_coverage("雙馬尾的少女，有著酒窩和星空披風").unwrap();
+
+        let terms: Vec<&str> = report.matched_terms.iter().map(|m| m.chinese_term.as_str()).collect();
+        assert!(terms.contains(&"雙馬尾"));
+        assert!(terms.contains(&"酒窩"));
+        // 「馬尾」被「雙馬尾」完全覆蓋，不重複列出
+        assert!(!terms.contains(&"馬尾"));
+        assert!(report.untranslated_terms.iter().any(|t| t.contains("星空披風")));
+        assert!(report.coverage_percentage > 0.0 && report.coverage_percentage < 100.0);
+    }
+
+    #[test]
+    fn test_locked_terms_override_vocabulary_matches() {
+        let conn = Connection::open_in_memory().unwrap();
+        let db = VocabularyDatabase::new(std::sync::Arc::new(std::sync::Mutex::new(conn)));
+        let engine = TranslationEngine::new(db).unwrap();
+
+        let locked_terms = HashMap::from([
+            ("雙馬尾".to_string(), "signature twin drills".to_string()),
+            ("魔法少女".to_string(), "magical girl".to_string()),
+            ("魔法少女制服".to_string(), "Starlight Academy magical girl uniform".to_string()),
+        ]);
+        let result = engine.translate(TranslationRequest {
+            chinese_description: "雙馬尾的少女，穿著魔法少女制服，有著酒窩".to_string(),
+            character_name: None,
+            target_style: TranslationStyle::Anime,
+            quality_level: QualityLevel::Fast,
+            context_hints: Vec::new(),
+            preserve_original: false,
+            locked_terms,
+        }).unwrap();
+
+        let prompt = &result.english_prompt;
+        assert!(prompt.starts_with("signature twin drills, Starlight Academy magical girl uniform"));
+        // 語法調整不會改動鎖定詞彙（girl 不會變成 1girl）
+        assert!(!prompt.contains("magical 1girl"));
+        // 被鎖定的「雙馬尾」不再經由詞彙庫翻譯
+        assert!(!prompt.contains("twintails"));
+        assert!(result.translation_breakdown.applied_transformations.contains(&"locked_terms".to_string()));
+    }
+}
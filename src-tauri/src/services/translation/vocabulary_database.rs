@@ -88,6 +88,75 @@ pub enum VocabularyCategory {
     Other,
 }
 
+impl VocabularyCategory {
+    /// 由前端使用的分類鍵（例如 "hair"、"art_style"）取得分類
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "facial_features" => Some(Self::FacialFeatures),
+            "hair" => Some(Self::Hair),
+            "eyes" => Some(Self::Eyes),
+            "body_type" => Some(Self::BodyType),
+            "clothing" => Some(Self::Clothing),
+            "accessories" => Some(Self::Accessories),
+            "expression" => Some(Self::Expression),
+            "pose" => Some(Self::Pose),
+            "background" => Some(Self::Background),
+            "art_style" => Some(Self::ArtStyle),
+            "effects" => Some(Self::Effects),
+            "colors" => Some(Self::Colors),
+            "texture" => Some(Self::Texture),
+            "lighting" => Some(Self::Lighting),
+            "personality" => Some(Self::Personality),
+            "other" => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    /// 分類鍵（`from_key` 的反向）
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::FacialFeatures => "facial_features",
+            Self::Hair => "hair",
+            Self::Eyes => "eyes",
+            Self::BodyType => "body_type",
+            Self::Clothing => "clothing",
+            Self::Accessories => "accessories",
+            Self::Expression => "expression",
+            Self::Pose => "pose",
+            Self::Background => "background",
+            Self::ArtStyle => "art_style",
+            Self::Effects => "effects",
+            Self::Colors => "colors",
+            Self::Texture => "texture",
+            Self::Lighting => "lighting",
+            Self::Personality => "personality",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// 匯入時單筆詞彙的處理結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpsertOutcome {
+    Inserted,
+    Updated,
+    /// 與內建詞彙衝突，未覆蓋
+    SkippedBuiltin,
+}
+
+/// 使用者新增或編輯詞彙時提供的內容
+#[derive(Debug, Clone, Deserialize)]
+pub struct VocabularyEntryInput {
+    pub chinese_term: String,
+    pub english_term: String,
+    pub category: VocabularyCategory,
+    pub subcategory: Option<String>,
+    pub priority: Option<i32>,
+    pub context_tags: Vec<String>,
+    pub synonyms: Vec<String>,
+    pub variations: Vec<String>,
+}
+
 /// 性別限定
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Gender {
@@ -178,12 +247,29 @@ impl VocabularyDatabase {
             [],
         )?;
 
+        // 舊版資料表補上回饋次數欄位（成功率以回饋次數計算，與使用次數分開）
+        let has_feedback_count = conn
+            .prepare("SELECT COUNT(*) FROM pragma_table_info('vocabulary_entries') WHERE name = 'feedback_count'")?
+            .query_row([], |row| row.get::<_, i64>(0))?
+            > 0;
+        if !has_feedback_count {
+            conn.execute(
+                "ALTER TABLE vocabulary_entries ADD COLUMN feedback_count INTEGER DEFAULT 0",
+                [],
+            )?;
+        }
+
         // 建立索引以提高查詢效能
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_vocabulary_chinese ON vocabulary_entries(chinese_term)",
             [],
         )?;
         
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_vocabulary_english ON vocabulary_entries(english_term COLLATE NOCASE)",
+            [],
+        )?;
+        
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_vocabulary_category ON vocabulary_entries(category)",
             [],
@@ -580,6 +666,189 @@ impl VocabularyDatabase {
         Ok(conn.last_insert_rowid())
     }
 
+    /// 依 ID 取得詞彙條目
+    pub fn get_entry(&self, entry_id: i64) -> Result<Option<VocabularyEntry>> {
+        let conn = self.db_connection.lock()
+            .map_err(|e| TranslationError::Unknown(format!("資料庫鎖定失敗: {}", e)))?;
+
+        let mut stmt = conn.prepare("SELECT * FROM vocabulary_entries WHERE id = ?1")?;
+        let mut rows = stmt.query_map(params![entry_id], |row| self.row_to_vocabulary_entry(row))?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// 新增使用者詞彙（來源標記為 UserAdded），回傳新條目 ID
+    pub fn add_user_entry(&self, input: VocabularyEntryInput) -> Result<i64> {
+        let input = Self::validate_input(input)?;
+        if self.term_exists(&input.chinese_term, &input.category)? {
+            return Err(TranslationError::InvalidVocabularyEntry(format!(
+                "「{}」在此分類中已存在", input.chinese_term
+            )));
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let entry = VocabularyEntry {
+            id: 0, // 由資料庫自動分配
+            chinese_term: input.chinese_term,
+            english_term: input.english_term,
+            category: input.category,
+            subcategory: input.subcategory,
+            priority: input.priority.unwrap_or(5),
+            usage_weight: 1.0,
+            context_tags: input.context_tags,
+            style_tags: Vec::new(),
+            gender_specific: None,
+            age_appropriate: None,
+            synonyms: input.synonyms,
+            variations: input.variations,
+            usage_count: 0,
+            success_rate: 1.0,
+            last_used_at: None,
+            source: VocabularySource::UserAdded,
+            verified: false,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+
+        self.insert_vocabulary_entry(&entry)
+    }
+
+    /// 更新使用者詞彙（內建詞彙不可修改）
+    pub fn update_user_entry(&self, entry_id: i64, input: VocabularyEntryInput) -> Result<()> {
+        let input = Self::validate_input(input)?;
+        self.ensure_editable(entry_id)?;
+
+        let conn = self.db_connection.lock()
+            .map_err(|e| TranslationError::Unknown(format!("資料庫鎖定失敗: {}", e)))?;
+
+        let category_json = serde_json::to_string(&input.category)?;
+        let conflict: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM vocabulary_entries WHERE chinese_term = ?1 AND category = ?2 AND id != ?3)",
+            params![input.chinese_term, category_json, entry_id],
+            |row| row.get(0),
+        )?;
+        if conflict {
+            return Err(TranslationError::InvalidVocabularyEntry(format!(
+                "「{}」在此分類中已存在", input.chinese_term
+            )));
+        }
+
+        conn.execute(
+            "UPDATE vocabulary_entries
+             SET chinese_term = ?1, english_term = ?2, category = ?3, subcategory = ?4, priority = ?5,
+                 context_tags = ?6, synonyms = ?7, variations = ?8, updated_at = ?9
+             WHERE id = ?10",
+            params![
+                input.chinese_term,
+                input.english_term,
+                category_json,
+                input.subcategory,
+                input.priority.unwrap_or(5),
+                serde_json::to_string(&input.context_tags)?,
+                serde_json::to_string(&input.synonyms)?,
+                serde_json::to_string(&input.variations)?,
+                chrono::Utc::now().to_rfc3339(),
+                entry_id
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// 刪除使用者詞彙（內建詞彙不可刪除）
+    pub fn delete_user_entry(&self, entry_id: i64) -> Result<()> {
+        self.ensure_editable(entry_id)?;
+
+        let conn = self.db_connection.lock()
+            .map_err(|e| TranslationError::Unknown(format!("資料庫鎖定失敗: {}", e)))?;
+        conn.execute("DELETE FROM vocabulary_entries WHERE id = ?1", params![entry_id])?;
+        Ok(())
+    }
+
+    /// 以（中文詞彙, 分類）為鍵新增或更新詞彙，供批次匯入使用
+    ///
+    /// 已存在的內建詞彙不會被覆蓋（回傳 `SkippedBuiltin`）。
+    pub fn upsert_entry(&self, input: VocabularyEntryInput, source: VocabularySource) -> Result<UpsertOutcome> {
+        let input = Self::validate_input(input)?;
+        let category_json = serde_json::to_string(&input.category)?;
+
+        let existing: Option<(i64, String)> = {
+            let conn = self.db_connection.lock()
+                .map_err(|e| TranslationError::Unknown(format!("資料庫鎖定失敗: {}", e)))?;
+            let mut stmt = conn.prepare(
+                "SELECT id, source FROM vocabulary_entries WHERE chinese_term = ?1 AND category = ?2",
+            )?;
+            let mut rows = stmt.query_map(params![input.chinese_term, category_json], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            rows.next().transpose()?
+        };
+
+        let (entry_id, outcome) = match existing {
+            Some((_, existing_source)) if serde_json::from_str::<VocabularySource>(&existing_source)
+                .is_ok_and(|s| matches!(s, VocabularySource::Builtin)) =>
+            {
+                return Ok(UpsertOutcome::SkippedBuiltin);
+            }
+            Some((entry_id, _)) => {
+                self.update_user_entry(entry_id, input)?;
+                (entry_id, UpsertOutcome::Updated)
+            }
+            None => (self.add_user_entry(input)?, UpsertOutcome::Inserted),
+        };
+
+        let conn = self.db_connection.lock()
+            .map_err(|e| TranslationError::Unknown(format!("資料庫鎖定失敗: {}", e)))?;
+        conn.execute(
+            "UPDATE vocabulary_entries SET source = ?1 WHERE id = ?2",
+            params![serde_json::to_string(&source)?, entry_id],
+        )?;
+        Ok(outcome)
+    }
+
+    /// 列出詞彙（可依分類篩選），依分類、優先級排序
+    pub fn list_entries(&self, category: Option<&VocabularyCategory>) -> Result<Vec<VocabularyEntry>> {
+        let conn = self.db_connection.lock()
+            .map_err(|e| TranslationError::Unknown(format!("資料庫鎖定失敗: {}", e)))?;
+
+        let category_json = category.map(serde_json::to_string).transpose()?;
+        let mut stmt = conn.prepare(
+            "SELECT * FROM vocabulary_entries
+             WHERE ?1 IS NULL OR category = ?1
+             ORDER BY category, priority DESC, chinese_term"
+        )?;
+        let rows = stmt.query_map(params![category_json], |row| self.row_to_vocabulary_entry(row))?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+        Ok(entries)
+    }
+
+    /// 確認條目存在且不是內建詞彙
+    fn ensure_editable(&self, entry_id: i64) -> Result<()> {
+        match self.get_entry(entry_id)? {
+            None => Err(TranslationError::InvalidVocabularyEntry(format!("找不到詞彙條目 {}", entry_id))),
+            Some(entry) if matches!(entry.source, VocabularySource::Builtin) => Err(
+                TranslationError::InvalidVocabularyEntry("內建詞彙不可修改或刪除".to_string()),
+            ),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// 檢查並整理使用者輸入
+    fn validate_input(mut input: VocabularyEntryInput) -> Result<VocabularyEntryInput> {
+        input.chinese_term = input.chinese_term.trim().to_string();
+        input.english_term = input.english_term.trim().to_string();
+        if input.chinese_term.is_empty() || input.english_term.is_empty() {
+            return Err(TranslationError::InvalidVocabularyEntry("中文詞彙與英文翻譯都不能為空".to_string()));
+        }
+        if let Some(priority) = input.priority {
+            if !(1..=10).contains(&priority) {
+                return Err(TranslationError::InvalidVocabularyEntry("優先級必須介於 1 到 10".to_string()));
+            }
+        }
+        Ok(input)
+    }
+
     /// 根據中文詞彙搜尋英文翻譯
     pub fn find_translation(&self, chinese_term: &str, category: Option<VocabularyCategory>) -> Result<Vec<VocabularyEntry>> {
         let conn = self.db_connection.lock()
@@ -592,7 +861,7 @@ impl VocabularyDatabase {
             let mut stmt = conn.prepare(
                 "SELECT * FROM vocabulary_entries 
                  WHERE chinese_term = ?1 AND category = ?2 
-                 ORDER BY priority DESC, usage_count DESC"
+                 ORDER BY priority DESC, success_rate DESC, usage_count DESC"
             )?;
             
             let rows = stmt.query_map(params![chinese_term, category_json], |row| {
@@ -606,7 +875,7 @@ impl VocabularyDatabase {
             let mut stmt = conn.prepare(
                 "SELECT * FROM vocabulary_entries 
                  WHERE chinese_term = ?1 
-                 ORDER BY priority DESC, usage_count DESC"
+                 ORDER BY priority DESC, success_rate DESC, usage_count DESC"
             )?;
             
             let rows = stmt.query_map(params![chinese_term], |row| {
@@ -618,6 +887,138 @@ impl VocabularyDatabase {
             }
         }
 
+        if entries.is_empty() {
+            drop(conn);
+            entries = self.match_registered_terms(chinese_term, category.as_ref())?;
+        }
+
+        Ok(entries)
+    }
+
+    /// 精確比對失敗時的後備比對
+    ///
+    /// 1. 詞彙的 `variations` 中有完全相同的寫法
+    /// 2. 查詢字串中包含的最長已登錄詞彙（含變體，至少兩個字），如「雙馬尾髮型」→「雙馬尾」
+    ///
+    /// 同樣長度的匹配依優先級、成功率、使用次數排序。
+    fn match_registered_terms(&self, chinese_term: &str, category: Option<&VocabularyCategory>) -> Result<Vec<VocabularyEntry>> {
+        let term = chinese_term.trim();
+        if term.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let candidates = self.list_entries(category)?;
+
+        let mut variation_matches: Vec<VocabularyEntry> = candidates
+            .iter()
+            .filter(|entry| entry.variations.iter().any(|v| v.trim() == term))
+            .cloned()
+            .collect();
+        if !variation_matches.is_empty() {
+            Self::sort_by_preference(&mut variation_matches);
+            return Ok(variation_matches);
+        }
+
+        let mut best_len = 0;
+        let mut substring_matches: Vec<VocabularyEntry> = Vec::new();
+        for entry in &candidates {
+            let matched_len = std::iter::once(entry.chinese_term.as_str())
+                .chain(entry.variations.iter().map(|v| v.trim()))
+                .filter(|written| written.chars().count() >= 2 && term.contains(written))
+                .map(|written| written.chars().count())
+                .max();
+            match matched_len {
+                Some(len) if len > best_len => {
+                    best_len = len;
+                    substring_matches = vec![entry.clone()];
+                }
+                Some(len) if len == best_len => substring_matches.push(entry.clone()),
+                _ => {}
+            }
+        }
+        Self::sort_by_preference(&mut substring_matches);
+        Ok(substring_matches)
+    }
+
+    fn sort_by_preference(entries: &mut [VocabularyEntry]) {
+        entries.sort_by(|a, b| {
+            b.priority.cmp(&a.priority)
+                .then(b.success_rate.total_cmp(&a.success_rate))
+                .then(b.usage_count.cmp(&a.usage_count))
+        });
+    }
+
+    /// 掃描中文文本中出現的已登錄詞彙（含變體），以最長匹配優先且不重疊
+    ///
+    /// 回傳依出現順序排列，同一詞彙只回傳一次。
+    pub fn scan_terms(&self, text: &str) -> Result<Vec<VocabularyEntry>> {
+        let mut candidates: Vec<(Vec<char>, VocabularyEntry)> = Vec::new();
+        for entry in self.list_entries(None)? {
+            let written_forms: Vec<String> = std::iter::once(entry.chinese_term.clone())
+                .chain(entry.variations.iter().map(|v| v.trim().to_string()))
+                .filter(|w| w.chars().count() >= 2)
+                .collect();
+            for written in written_forms {
+                candidates.push((written.chars().collect(), entry.clone()));
+            }
+        }
+        candidates.sort_by(|(a_chars, a), (b_chars, b)| {
+            b_chars.len().cmp(&a_chars.len())
+                .then(b.priority.cmp(&a.priority))
+                .then(b.success_rate.total_cmp(&a.success_rate))
+        });
+
+        let chars: Vec<char> = text.chars().collect();
+        let mut found: Vec<(usize, VocabularyEntry)> = Vec::new();
+        let mut index = 0;
+        while index < chars.len() {
+            let matched = candidates.iter().find(|(written, _)| chars[index..].starts_with(written));
+            match matched {
+                Some((written, entry)) => {
+                    if !found.iter().any(|(_, e)| e.id == entry.id) {
+                        found.push((index, entry.clone()));
+                    }
+                    index += written.len();
+                }
+                None => index += 1,
+            }
+        }
+
+        Ok(found.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// 反向查詢：列出對應指定英文詞彙的所有中文詞彙（不分大小寫，含英文同義詞）
+    ///
+    /// 多個中文寫法可能共用同一英文翻譯，結果依優先級排序。
+    pub fn reverse_lookup(&self, english_term: &str) -> Result<Vec<VocabularyEntry>> {
+        let term = english_term.trim();
+        if term.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.db_connection.lock()
+            .map_err(|e| TranslationError::Unknown(format!("資料庫鎖定失敗: {}", e)))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT * FROM vocabulary_entries
+             WHERE english_term = ?1 COLLATE NOCASE OR synonyms LIKE ?2
+             ORDER BY priority DESC, success_rate DESC, usage_count DESC"
+        )?;
+        let rows = stmt.query_map(params![term, format!("%{}%", term)], |row| {
+            self.row_to_vocabulary_entry(row)
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let entry = row?;
+            // LIKE 只是預篩，同義詞需完全相符
+            if entry.english_term.eq_ignore_ascii_case(term)
+                || entry.synonyms.iter().any(|s| s.trim().eq_ignore_ascii_case(term))
+            {
+                entries.push(entry);
+            }
+        }
+
         Ok(entries)
     }
 
@@ -631,7 +1032,7 @@ impl VocabularyDatabase {
         let mut stmt = conn.prepare(
             "SELECT * FROM vocabulary_entries 
              WHERE chinese_term LIKE ?1 
-             ORDER BY priority DESC, usage_count DESC
+             ORDER BY priority DESC, success_rate DESC, usage_count DESC
              LIMIT ?2"
         )?;
 
@@ -657,7 +1058,7 @@ impl VocabularyDatabase {
         let age_json: String = row.get(10)?;
         let synonyms_json: String = row.get(11)?;
         let variations_json: String = row.get(12)?;
-        let source_json: String = row.get(17)?;
+        let source_json: String = row.get(16)?;
 
         Ok(VocabularyEntry {
             id: row.get(0)?,
@@ -677,39 +1078,42 @@ impl VocabularyDatabase {
             success_rate: row.get(14).unwrap_or(1.0),
             last_used_at: row.get(15).ok(),
             source: serde_json::from_str(&source_json).unwrap_or(VocabularySource::Builtin),
-            verified: row.get(18).unwrap_or(false),
-            created_at: row.get(19)?,
-            updated_at: row.get(20)?,
+            verified: row.get(17).unwrap_or(false),
+            created_at: row.get(18)?,
+            updated_at: row.get(19)?,
         })
     }
 
-    /// 更新詞彙使用統計
-    pub fn update_usage_stats(&self, entry_id: i64, success: bool) -> Result<()> {
+    /// 記錄詞彙被翻譯引擎採用（使用次數 +1、更新最後使用時間）
+    pub fn record_usage(&self, entry_id: i64) -> Result<()> {
         let conn = self.db_connection.lock()
             .map_err(|e| TranslationError::Unknown(format!("資料庫鎖定失敗: {}", e)))?;
 
-        let now = chrono::Utc::now().to_rfc3339();
-        
-        if success {
-            conn.execute(
-                "UPDATE vocabulary_entries 
-                 SET usage_count = usage_count + 1, 
-                     success_rate = (success_rate * usage_count + 1.0) / (usage_count + 1),
-                     last_used_at = ?1,
-                     updated_at = ?1
-                 WHERE id = ?2",
-                params![now, entry_id],
-            )?;
-        } else {
-            conn.execute(
-                "UPDATE vocabulary_entries 
-                 SET usage_count = usage_count + 1,
-                     success_rate = (success_rate * usage_count) / (usage_count + 1),
-                     last_used_at = ?1,
-                     updated_at = ?1
-                 WHERE id = ?2",
-                params![now, entry_id],
-            )?;
+        conn.execute(
+            "UPDATE vocabulary_entries
+             SET usage_count = usage_count + 1,
+                 last_used_at = ?1
+             WHERE id = ?2",
+            params![chrono::Utc::now().to_rfc3339(), entry_id],
+        )?;
+
+        Ok(())
+    }
+
+    /// 記錄使用者對詞彙翻譯結果的回饋，以回饋次數計算成功率
+    pub fn record_outcome(&self, entry_id: i64, succeeded: bool) -> Result<()> {
+        let conn = self.db_connection.lock()
+            .map_err(|e| TranslationError::Unknown(format!("資料庫鎖定失敗: {}", e)))?;
+
+        let updated = conn.execute(
+            "UPDATE vocabulary_entries
+             SET success_rate = (success_rate * feedback_count + ?1) / (feedback_count + 1),
+                 feedback_count = feedback_count + 1
+             WHERE id = ?2",
+            params![if succeeded { 1.0 } else { 0.0 }, entry_id],
+        )?;
+        if updated == 0 {
+            return Err(TranslationError::InvalidVocabularyEntry(format!("找不到詞彙: {}", entry_id)));
         }
 
         Ok(())
@@ -749,10 +1153,29 @@ impl VocabularyDatabase {
             }
         }
 
+        let mut stmt = conn.prepare(
+            "SELECT id, chinese_term, english_term, usage_count, success_rate FROM vocabulary_entries
+             WHERE usage_count > 0
+             ORDER BY usage_count DESC, success_rate DESC
+             LIMIT 10"
+        )?;
+        let most_used_terms = stmt
+            .query_map([], |row| {
+                Ok(VocabularyUsage {
+                    id: row.get(0)?,
+                    chinese_term: row.get(1)?,
+                    english_term: row.get(2)?,
+                    usage_count: row.get(3)?,
+                    success_rate: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
         Ok(VocabularyStats {
             total_entries: total_entries as u32,
             verified_entries: verified_entries as u32,
             category_counts,
+            most_used_terms,
             last_updated: chrono::Utc::now().to_rfc3339(),
         })
     }
@@ -764,5 +1187,129 @@ pub struct VocabularyStats {
     pub total_entries: u32,
     pub verified_entries: u32,
     pub category_counts: HashMap<VocabularyCategory, u32>,
+    /// 使用次數最多的詞彙（前 10 名）
+    pub most_used_terms: Vec<VocabularyUsage>,
     pub last_updated: String,
-}
\ No newline at end of file
+}
+
+/// 詞彙使用統計摘要
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VocabularyUsage {
+    pub id: i64,
+    pub chinese_term: String,
+    pub english_term: String,
+    pub usage_count: u32,
+    pub success_rate: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(chinese_term: &str, english_term: &str) -> VocabularyEntryInput {
+        VocabularyEntryInput {
+            chinese_term: chinese_term.to_string(),
+            english_term: english_term.to_string(),
+            category: VocabularyCategory::Hair,
+            subcategory: None,
+            priority: Some(6),
+            context_tags: Vec::new(),
+            synonyms: Vec::new(),
+            variations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_user_entry_crud_protects_builtin() {
+        let conn = Connection::open_in_memory().unwrap();
+        let db = VocabularyDatabase::new(std::sync::Arc::new(std::sync::Mutex::new(conn)));
+
+        let id = db.add_user_entry(input("星空漸層髮", "galaxy gradient hair")).unwrap();
+        assert!(matches!(db.get_entry(id).unwrap().unwrap().source, VocabularySource::UserAdded));
+        assert!(db.add_user_entry(input("星空漸層髮", "starry hair")).is_err());
+
+        db.update_user_entry(id, input("星空漸層髮", "starry gradient hair")).unwrap();
+        assert_eq!(db.get_entry(id).unwrap().unwrap().english_term, "starry gradient hair");
+
+        let builtin_id = db.find_translation("雙馬尾", None).unwrap()[0].id;
+        assert!(db.update_user_entry(builtin_id, input("雙馬尾", "pigtails")).is_err());
+        assert!(db.delete_user_entry(builtin_id).is_err());
+
+        db.delete_user_entry(id).unwrap();
+        assert!(db.get_entry(id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_upsert_entry_tags_source_and_skips_builtin() {
+        let conn = Connection::open_in_memory().unwrap();
+        let db = VocabularyDatabase::new(std::sync::Arc::new(std::sync::Mutex::new(conn)));
+
+        assert_eq!(db.upsert_entry(input("星空漸層髮", "galaxy hair"), VocabularySource::Community).unwrap(), UpsertOutcome::Inserted);
+        assert_eq!(db.upsert_entry(input("星空漸層髮", "starry hair"), VocabularySource::Community).unwrap(), UpsertOutcome::Updated);
+        assert_eq!(db.upsert_entry(input("雙馬尾", "pigtails"), VocabularySource::Community).unwrap(), UpsertOutcome::SkippedBuiltin);
+
+        let entries = db.list_entries(Some(&VocabularyCategory::Hair)).unwrap();
+        let imported = entries.iter().find(|e| e.chinese_term == "星空漸層髮").unwrap();
+        assert_eq!(imported.english_term, "starry hair");
+        assert!(matches!(imported.source, VocabularySource::Community));
+    }
+
+    #[test]
+    fn test_find_translation_matches_variations_and_longest_substring() {
+        let conn = Connection::open_in_memory().unwrap();
+        let db = VocabularyDatabase::new(std::sync::Arc::new(std::sync::Mutex::new(conn)));
+
+        let mut galaxy = input("星空漸層髮", "galaxy gradient hair");
+        galaxy.variations = vec!["銀河髮".to_string()];
+        db.add_user_entry(galaxy).unwrap();
+
+        // 只能透過變體匹配
+        let found = db.find_translation("銀河髮", Some(VocabularyCategory::Hair)).unwrap();
+        assert_eq!(found[0].english_term, "galaxy gradient hair");
+
+        // 最長子字串優先：「雙馬尾」勝過「馬尾」
+        let found = db.find_translation("雙馬尾髮型", None).unwrap();
+        assert_eq!(found[0].english_term, "twintails");
+
+        assert!(db.find_translation("完全無關", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_usage_and_outcome_are_tracked_separately() {
+        let conn = Connection::open_in_memory().unwrap();
+        let db = VocabularyDatabase::new(std::sync::Arc::new(std::sync::Mutex::new(conn)));
+        let id = db.add_user_entry(input("星空漸層髮", "galaxy gradient hair")).unwrap();
+
+        db.record_usage(id).unwrap();
+        db.record_usage(id).unwrap();
+        let entry = db.get_entry(id).unwrap().unwrap();
+        assert_eq!(entry.usage_count, 2);
+        assert!(entry.last_used_at.is_some());
+        assert_eq!(entry.success_rate, 1.0);
+
+        db.record_outcome(id, true).unwrap();
+        db.record_outcome(id, false).unwrap();
+        assert_eq!(db.get_entry(id).unwrap().unwrap().success_rate, 0.5);
+        assert!(db.record_outcome(-1, true).is_err());
+
+        let stats = db.get_vocabulary_stats().unwrap();
+        assert_eq!(stats.most_used_terms[0].id, id);
+    }
+
+    #[test]
+    fn test_reverse_lookup_returns_all_chinese_variants() {
+        let conn = Connection::open_in_memory().unwrap();
+        let db = VocabularyDatabase::new(std::sync::Arc::new(std::sync::Mutex::new(conn)));
+
+        let mut low = input("雙辮", "Twintails");
+        low.priority = Some(3);
+        db.add_user_entry(low).unwrap();
+        let mut synonym = input("雙尾髮", "double tails");
+        synonym.synonyms = vec!["twintails".to_string()];
+        db.add_user_entry(synonym).unwrap();
+
+        let terms: Vec<String> = db.reverse_lookup("twintails").unwrap().into_iter().map(|e| e.chinese_term).collect();
+        assert_eq!(terms, vec!["雙馬尾", "雙尾髮", "雙辮"]);
+        assert!(db.reverse_lookup("twin").unwrap().is_empty());
+    }
+}
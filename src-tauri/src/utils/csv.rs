@@ -0,0 +1,91 @@
+/// CSV 欄位跳脫：含逗號、引號或換行時以雙引號包住，並將引號加倍
+pub fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 解析 CSV 文字為列與欄位（支援引號包住的逗號、換行與加倍的引號）
+///
+/// 回傳每列的起始行號（從 1 開始）與欄位，方便回報錯誤位置；開頭的 UTF-8 BOM 與空白列會被略過。
+pub fn parse_csv(text: &str) -> Vec<(usize, Vec<String>)> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_start = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => fields.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                if fields.iter().any(|f| !f.is_empty()) {
+                    rows.push((row_start, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                row_start = line;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        if fields.iter().any(|f| !f.is_empty()) {
+            rows.push((row_start, fields));
+        }
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_csv_field() {
+        assert_eq!(escape_csv_field("plain"), "plain");
+        assert_eq!(escape_csv_field("a,b"), "\"a,b\"");
+        assert_eq!(escape_csv_field("say \"hi\"\nnext"), "\"say \"\"hi\"\"\nnext\"");
+    }
+
+    #[test]
+    fn test_parse_csv_round_trip() {
+        let original = ["雙馬尾", "a,b", "say \"hi\"\nnext", ""];
+        let line = original.iter().map(|f| escape_csv_field(f)).collect::<Vec<_>>().join(",");
+        let text = format!("\u{feff}header,x,y,z\r\n{}\n\n最後,一列,,", line);
+
+        let rows = parse_csv(&text);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1], (2, original.iter().map(|f| f.to_string()).collect()));
+        assert_eq!(rows[2].0, 5);
+        assert_eq!(rows[2].1, vec!["最後", "一列", "", ""]);
+    }
+}
@@ -0,0 +1,571 @@
+use serde_json::{json, Map, Value};
+
+/// HTML / XML 標記
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkupToken {
+    /// 開始標籤（名稱為小寫，`attrs` 為原始屬性字串）
+    Open { name: String, attrs: String, self_closing: bool },
+    Close(String),
+    /// 已解碼實體的文字
+    Text(String),
+}
+
+/// 內容會被略過的標籤
+const SKIPPED_ELEMENTS: &[&str] = &["head", "script", "style", "title", "nav"];
+
+/// 將 HTML / XHTML / XML 切分為標記，略過註解、DOCTYPE 與處理指令
+///
+/// 只做容錯的詞法切分，不檢查標籤是否正確配對。
+pub fn tokenize_markup(input: &str) -> Vec<MarkupToken> {
+    let mut tokens = Vec::new();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let Some(start) = rest.find('<') else {
+            push_text(&mut tokens, rest);
+            break;
+        };
+        push_text(&mut tokens, &rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map(|end| &comment[end + 3..]).unwrap_or("");
+            continue;
+        }
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            if !cdata[..end].is_empty() {
+                tokens.push(MarkupToken::Text(cdata[..end].to_string()));
+            }
+            rest = cdata.get(end + 3..).unwrap_or("");
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            // 未閉合的「<」視為一般文字
+            push_text(&mut tokens, rest);
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(MarkupToken::Close(name.trim().to_lowercase()));
+            continue;
+        }
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let name = tag[..name_end].to_lowercase();
+        if name.is_empty() {
+            push_text(&mut tokens, "<");
+            continue;
+        }
+
+        // 略過 head、script 等元素的整段內容
+        if !self_closing && SKIPPED_ELEMENTS.contains(&name.as_str()) {
+            let closing = format!("</{}", name);
+            let lower = rest.to_ascii_lowercase();
+            rest = match lower.find(&closing) {
+                Some(position) => rest[position..].find('>').map(|end| &rest[position + end + 1..]).unwrap_or(""),
+                None => "",
+            };
+            continue;
+        }
+
+        tokens.push(MarkupToken::Open {
+            name,
+            attrs: tag[name_end..].trim().to_string(),
+            self_closing,
+        });
+    }
+    tokens
+}
+
+fn push_text(tokens: &mut Vec<MarkupToken>, text: &str) {
+    if !text.is_empty() {
+        tokens.push(MarkupToken::Text(html_escape::decode_html_entities(text).into_owned()));
+    }
+}
+
+/// 讀取標籤屬性值（名稱不分大小寫）
+pub fn markup_attr(attrs: &str, name: &str) -> Option<String> {
+    let lower = attrs.to_ascii_lowercase();
+    let pattern = format!("{}=", name.to_ascii_lowercase());
+    let mut search_from = 0;
+    while let Some(found) = lower[search_from..].find(&pattern) {
+        let position = search_from + found;
+        // 確認是完整的屬性名稱（例如找 href 時不匹配 data-href）
+        let boundary = !matches!(lower[..position].chars().last(), Some(c) if !c.is_whitespace());
+        let value = &attrs[position + pattern.len()..];
+        if boundary {
+            let value = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or(""),
+                _ => value.split(char::is_whitespace).next().unwrap_or(""),
+            };
+            return Some(html_escape::decode_html_entities(value).into_owned());
+        }
+        search_from = position + pattern.len();
+    }
+    None
+}
+
+/// 文字格式
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Marks {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+/// 標籤對應的文字格式（0 粗體、1 斜體、2 底線）
+fn mark_for_tag(name: &str) -> Option<usize> {
+    match name {
+        "b" | "strong" => Some(0),
+        "i" | "em" | "cite" => Some(1),
+        "u" | "ins" => Some(2),
+        _ => None,
+    }
+}
+
+/// 區塊標籤對應的 Slate 節點類型
+fn block_type_for_tag(name: &str) -> Option<&'static str> {
+    match name {
+        "p" | "div" | "section" | "article" | "pre" => Some("paragraph"),
+        "h1" => Some("heading-one"),
+        "h2" => Some("heading-two"),
+        "h3" | "h4" | "h5" | "h6" => Some("heading-three"),
+        "blockquote" => Some("block-quote"),
+        "li" => Some("list-item"),
+        _ => None,
+    }
+}
+
+struct SlateBuilder {
+    blocks: Vec<Value>,
+    /// 進行中的區塊：(類型, 文字葉節點)
+    current: Option<(&'static str, Vec<(String, Marks)>)>,
+    /// 巢狀清單：(清單類型, 清單項目)；內層清單的項目會併入最外層清單
+    lists: Vec<(&'static str, Vec<Value>)>,
+    quote_depth: usize,
+    mark_depth: [usize; 3],
+}
+
+impl SlateBuilder {
+    fn marks(&self) -> Marks {
+        Marks {
+            bold: self.mark_depth[0] > 0,
+            italic: self.mark_depth[1] > 0,
+            underline: self.mark_depth[2] > 0,
+        }
+    }
+
+    fn start_block(&mut self, block_type: &'static str) {
+        self.flush();
+        let block_type = match block_type {
+            "paragraph" if self.quote_depth > 0 => "block-quote",
+            "paragraph" if !self.lists.is_empty() => "list-item",
+            other => other,
+        };
+        self.current = Some((block_type, Vec::new()));
+    }
+
+    fn push_text(&mut self, text: &str) {
+        // HTML 的連續空白視為一個空格
+        let mut collapsed = String::with_capacity(text.len());
+        let mut last_space = false;
+        for c in text.chars() {
+            if c.is_whitespace() {
+                if !last_space {
+                    collapsed.push(' ');
+                }
+                last_space = true;
+            } else {
+                collapsed.push(c);
+                last_space = false;
+            }
+        }
+        if collapsed.trim().is_empty() && self.current.is_none() {
+            return;
+        }
+        if self.current.is_none() {
+            self.start_block("paragraph");
+        }
+        let marks = self.marks();
+        if let Some((_, leaves)) = &mut self.current {
+            match leaves.last_mut() {
+                Some((last, last_marks)) if *last_marks == marks => last.push_str(&collapsed),
+                _ => leaves.push((collapsed, marks)),
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        let Some((block_type, mut leaves)) = self.current.take() else {
+            return;
+        };
+        if let Some((first, _)) = leaves.first_mut() {
+            *first = first.trim_start().to_string();
+        }
+        if let Some((last, _)) = leaves.last_mut() {
+            *last = last.trim_end().to_string();
+        }
+        leaves.retain(|(text, _)| !text.is_empty());
+        if leaves.is_empty() {
+            return;
+        }
+
+        let children: Vec<Value> = leaves
+            .into_iter()
+            .map(|(text, marks)| {
+                let mut leaf = Map::new();
+                leaf.insert("text".to_string(), Value::String(text));
+                for (enabled, key) in [(marks.bold, "bold"), (marks.italic, "italic"), (marks.underline, "underline")] {
+                    if enabled {
+                        leaf.insert(key.to_string(), Value::Bool(true));
+                    }
+                }
+                Value::Object(leaf)
+            })
+            .collect();
+
+        match self.lists.first_mut() {
+            Some((_, items)) if block_type == "list-item" => {
+                items.push(json!({ "type": "list-item", "children": children }));
+            }
+            _ => {
+                let block_type = if block_type == "list-item" { "paragraph" } else { block_type };
+                self.blocks.push(json!({ "type": block_type, "children": children }));
+            }
+        }
+    }
+
+    fn close_list(&mut self) {
+        self.flush();
+        if self.lists.len() > 1 {
+            self.lists.pop();
+            return;
+        }
+        if let Some((list_type, items)) = self.lists.pop() {
+            if !items.is_empty() {
+                self.blocks.push(json!({ "type": list_type, "children": items }));
+            }
+        }
+    }
+}
+
+/// 將 HTML 轉為編輯器使用的 Slate.js 節點
+///
+/// 支援段落、標題（h4-h6 視為 heading-three）、引言、清單與粗體/斜體/底線；
+/// `<br>` 視為段落分隔，其他無法對應的標籤只保留文字。
+pub fn html_to_slate_nodes(html: &str) -> Vec<Value> {
+    let mut builder = SlateBuilder {
+        blocks: Vec::new(),
+        current: None,
+        lists: Vec::new(),
+        quote_depth: 0,
+        mark_depth: [0; 3],
+    };
+
+    for token in tokenize_markup(html) {
+        match token {
+            MarkupToken::Text(text) => builder.push_text(&text),
+            MarkupToken::Open { name, self_closing, .. } => {
+                if let Some(index) = mark_for_tag(&name) {
+                    if !self_closing {
+                        builder.mark_depth[index] += 1;
+                    }
+                    continue;
+                }
+                match name.as_str() {
+                    "br" | "hr" => builder.flush(),
+                    "ul" | "ol" => {
+                        builder.flush();
+                        builder.lists.push((if name == "ul" { "bulleted-list" } else { "numbered-list" }, Vec::new()));
+                    }
+                    "blockquote" => {
+                        builder.flush();
+                        builder.quote_depth += 1;
+                        builder.start_block("block-quote");
+                    }
+                    _ => {
+                        if let Some(block_type) = block_type_for_tag(&name) {
+                            builder.start_block(block_type);
+                            if self_closing {
+                                builder.flush();
+                            }
+                        }
+                    }
+                }
+            }
+            MarkupToken::Close(name) => {
+                if let Some(index) = mark_for_tag(&name) {
+                    builder.mark_depth[index] = builder.mark_depth[index].saturating_sub(1);
+                    continue;
+                }
+                match name.as_str() {
+                    "ul" | "ol" => builder.close_list(),
+                    "blockquote" => {
+                        builder.flush();
+                        builder.quote_depth = builder.quote_depth.saturating_sub(1);
+                    }
+                    _ if block_type_for_tag(&name).is_some() => builder.flush(),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    builder.flush();
+    while !builder.lists.is_empty() {
+        builder.close_list();
+    }
+    builder.blocks
+}
+
+/// 取得節點的純文字
+pub fn slate_node_text(node: &Value) -> String {
+    if let Some(text) = node.get("text").and_then(Value::as_str) {
+        return text.to_string();
+    }
+    node.get("children")
+        .and_then(Value::as_array)
+        .map(|children| children.iter().map(slate_node_text).collect())
+        .unwrap_or_default()
+}
+
+/// 依標題切分章節：以文件中最高層級的標題作為章節分隔，標題文字即章節名稱
+///
+/// 第一個標題前的內容成為「前言」章節；沒有任何標題時整份文件為一章，使用 `fallback_title`。
+/// 回傳 (章節名稱, 章節內容節點)，空章節會補上一個空段落。
+pub fn split_slate_into_chapters(nodes: Vec<Value>, fallback_title: &str) -> Vec<(String, Vec<Value>)> {
+    let chapter_heading = ["heading-one", "heading-two", "heading-three"]
+        .into_iter()
+        .find(|heading| nodes.iter().any(|node| node["type"] == *heading));
+    let Some(chapter_heading) = chapter_heading else {
+        return if nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![(fallback_title.to_string(), nodes)]
+        };
+    };
+
+    let mut chapters: Vec<(String, Vec<Value>)> = Vec::new();
+    let mut preface = Vec::new();
+    for node in nodes {
+        if node["type"] == chapter_heading {
+            chapters.push((slate_node_text(&node).trim().to_string(), Vec::new()));
+        } else if let Some((_, content)) = chapters.last_mut() {
+            content.push(node);
+        } else {
+            preface.push(node);
+        }
+    }
+    if !preface.is_empty() {
+        chapters.insert(0, ("前言".to_string(), preface));
+    }
+
+    for (title, content) in &mut chapters {
+        if title.is_empty() {
+            *title = fallback_title.to_string();
+        }
+        if content.is_empty() {
+            content.push(crate::utils::slate::paragraph_node(""));
+        }
+    }
+    chapters
+}
+
+/// DOCX 段落
+#[derive(Default)]
+struct DocxParagraph {
+    style: Option<String>,
+    /// 是否為清單項目（有 w:numPr）
+    list: bool,
+    runs: Vec<(String, Marks)>,
+}
+
+/// 將 DOCX 的 word/document.xml 轉為 HTML（段落、標題、清單與粗體/斜體/底線）
+pub fn docx_document_to_html(document_xml: &str) -> String {
+    let mut html = String::new();
+    let mut in_list = false;
+
+    let mut paragraph: Option<DocxParagraph> = None;
+    let mut run_marks = Marks::default();
+    let mut in_paragraph_props = false;
+    let mut in_text = false;
+
+    for token in tokenize_markup(document_xml) {
+        match token {
+            MarkupToken::Open { name, attrs, self_closing } => match name.as_str() {
+                "w:p" => paragraph = (!self_closing).then(DocxParagraph::default),
+                "w:ppr" => in_paragraph_props = !self_closing,
+                "w:pstyle" => {
+                    if let Some(paragraph) = &mut paragraph {
+                        paragraph.style = markup_attr(&attrs, "w:val");
+                    }
+                }
+                "w:numpr" => {
+                    if let Some(paragraph) = &mut paragraph {
+                        paragraph.list = true;
+                    }
+                }
+                "w:r" => run_marks = Marks::default(),
+                "w:b" | "w:i" | "w:u" if !in_paragraph_props => {
+                    let enabled = !matches!(
+                        markup_attr(&attrs, "w:val").as_deref(),
+                        Some("0" | "false" | "none")
+                    );
+                    match name.as_str() {
+                        "w:b" => run_marks.bold = enabled,
+                        "w:i" => run_marks.italic = enabled,
+                        _ => run_marks.underline = enabled,
+                    }
+                }
+                "w:t" => in_text = !self_closing,
+                "w:tab" | "w:br" if !in_paragraph_props => {
+                    if let Some(paragraph) = &mut paragraph {
+                        paragraph.runs.push((" ".to_string(), run_marks));
+                    }
+                }
+                _ => {}
+            },
+            MarkupToken::Close(name) => match name.as_str() {
+                "w:ppr" => in_paragraph_props = false,
+                "w:t" => in_text = false,
+                "w:p" => {
+                    if let Some(DocxParagraph { style, list, runs }) = paragraph.take() {
+                        let text: String = runs
+                            .iter()
+                            .map(|(text, marks)| {
+                                let mut segment = html_escape::encode_text(text).into_owned();
+                                if marks.bold {
+                                    segment = format!("<strong>{}</strong>", segment);
+                                }
+                                if marks.italic {
+                                    segment = format!("<em>{}</em>", segment);
+                                }
+                                if marks.underline {
+                                    segment = format!("<u>{}</u>", segment);
+                                }
+                                segment
+                            })
+                            .collect();
+                        let tag = docx_heading_tag(style.as_deref());
+                        if list && tag.is_none() {
+                            if !in_list {
+                                html.push_str("<ul>");
+                                in_list = true;
+                            }
+                            html.push_str(&format!("<li>{}</li>", text));
+                            continue;
+                        }
+                        if in_list {
+                            html.push_str("</ul>");
+                            in_list = false;
+                        }
+                        let tag = tag.unwrap_or("p");
+                        html.push_str(&format!("<{tag}>{text}</{tag}>"));
+                    }
+                }
+                _ => {}
+            },
+            MarkupToken::Text(text) => {
+                if in_text {
+                    if let Some(paragraph) = &mut paragraph {
+                        paragraph.runs.push((text, run_marks));
+                    }
+                }
+            }
+        }
+    }
+    if in_list {
+        html.push_str("</ul>");
+    }
+    html
+}
+
+/// DOCX 段落樣式對應的標題標籤（Title / Heading1 / 標題 1 等）
+fn docx_heading_tag(style: Option<&str>) -> Option<&'static str> {
+    let style = style?.to_lowercase();
+    if style == "title" {
+        return Some("h1");
+    }
+    let level = style
+        .strip_prefix("heading")
+        .or_else(|| style.strip_prefix("標題"))?
+        .trim();
+    match level {
+        "1" => Some("h1"),
+        "2" => Some("h2"),
+        "" => None,
+        _ => Some("h3"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_folding_keeps_byte_offsets_for_unicode() {
+        // U+212A（克耳文符號）小寫後位元組長度會改變，不能用來切原字串
+        let tokens = tokenize_markup("<style>\u{212A}中</style><p>正文</p>");
+        assert!(tokens.iter().any(|token| matches!(token, MarkupToken::Text(text) if text == "正文")));
+        assert!(tokenize_markup("<STYLE>\u{212A}中</STYLE>").iter().all(|token| !matches!(token, MarkupToken::Text(_))));
+
+        assert_eq!(markup_attr("title=\"\u{212A}\u{212A}\" HREF=\"a.html\"", "href").as_deref(), Some("a.html"));
+    }
+
+    #[test]
+    fn test_html_to_slate_nodes() {
+        let html = r#"<!DOCTYPE html><html><head><title>書名</title><style>p{}</style></head><body>
+            <h1>第一章 啟程</h1>
+            <p>勇者<strong>終於</strong>出發了，<em>天色&amp;微亮</em>。<br/>新的一段</p>
+            <blockquote><p>引言</p></blockquote>
+            <ul><li>清單一</li><li>清單<b>二</b><ol><li>內層</li></ol></li></ul>
+            <h2>小節</h2>
+            裸露的文字
+        </body></html>"#;
+        let nodes = html_to_slate_nodes(html);
+        let types: Vec<&str> = nodes.iter().map(|node| node["type"].as_str().unwrap()).collect();
+        assert_eq!(types, vec!["heading-one", "paragraph", "paragraph", "block-quote", "bulleted-list", "heading-two", "paragraph"]);
+
+        assert_eq!(nodes[1]["children"][1], json!({ "text": "終於", "bold": true }));
+        assert_eq!(nodes[1]["children"][3], json!({ "text": "天色&微亮", "italic": true }));
+        assert_eq!(slate_node_text(&nodes[2]), "新的一段");
+        let items = nodes[4]["children"].as_array().unwrap();
+        assert_eq!(items.iter().map(slate_node_text).collect::<Vec<_>>(), vec!["清單一", "清單二", "內層"]);
+        assert_eq!(slate_node_text(&nodes[6]), "裸露的文字");
+    }
+
+    #[test]
+    fn test_split_slate_into_chapters() {
+        let nodes = html_to_slate_nodes("<p>序</p><h2>第一章</h2><p>一</p><h3>小節</h3><p>二</p><h2>第二章</h2>");
+        let chapters = split_slate_into_chapters(nodes, "匯入");
+        let titles: Vec<&str> = chapters.iter().map(|(title, _)| title.as_str()).collect();
+        assert_eq!(titles, vec!["前言", "第一章", "第二章"]);
+        assert_eq!(chapters[1].1.len(), 3);
+        assert_eq!(chapters[2].1, vec![crate::utils::slate::paragraph_node("")]);
+
+        let single = split_slate_into_chapters(html_to_slate_nodes("<p>沒有標題</p>"), "草稿");
+        assert_eq!(single[0].0, "草稿");
+    }
+
+    #[test]
+    fn test_docx_document_to_html() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <w:document><w:body>
+              <w:p><w:pPr><w:pStyle w:val="Heading1"/><w:rPr><w:b/></w:rPr></w:pPr><w:r><w:t>第一章</w:t></w:r></w:p>
+              <w:p><w:r><w:t xml:space="preserve">他說 </w:t></w:r><w:r><w:rPr><w:b/><w:i w:val="0"/></w:rPr><w:t>快走&amp;</w:t></w:r></w:p>
+              <w:p><w:pPr><w:numPr><w:ilvl w:val="0"/></w:numPr></w:pPr><w:r><w:t>項目</w:t></w:r></w:p>
+              <w:p/>
+            </w:body></w:document>"#;
+        assert_eq!(
+            docx_document_to_html(xml),
+            "<h1>第一章</h1><p>他說 <strong>快走&amp;</strong></p><ul><li>項目</li></ul>"
+        );
+        assert_eq!(markup_attr(r#"data-href="x" href='y'"#, "href").as_deref(), Some("y"));
+    }
+}
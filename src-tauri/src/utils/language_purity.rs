@@ -132,6 +132,32 @@ impl LanguagePurityEnforcer {
         (1.0 - (penalty / total_chars)).max(0.0)
     }
     
+    /// 將檢測到的問題轉為修正指示，附加在重新生成的提示詞之後
+    pub fn build_corrective_instructions(&self, analysis: &PurityAnalysis) -> String {
+        let collect = |wanted: fn(&IssueType) -> bool| {
+            let mut seen = HashSet::new();
+            analysis.issues.iter()
+                .filter(|issue| wanted(&issue.issue_type))
+                .map(|issue| issue.content.as_str())
+                .filter(|content| seen.insert(*content))
+                .take(20)
+                .collect::<Vec<_>>()
+                .join("、")
+        };
+        let english = collect(|t| matches!(t, IssueType::EnglishWords | IssueType::ForbiddenPattern));
+        let simplified = collect(|t| matches!(t, IssueType::SimplifiedChinese));
+        
+        let mut instructions = String::from("\n\n【上次輸出的語言純度問題，請重新撰寫】\n");
+        if !english.is_empty() {
+            instructions.push_str(&format!("- 出現英文：{}，請改用繁體中文表達\n", english));
+        }
+        if !simplified.is_empty() {
+            instructions.push_str(&format!("- 出現簡體字：{}，請改用對應的繁體字\n", simplified));
+        }
+        instructions.push_str("- 全文只能使用繁體中文，不得包含任何英文字母或簡體字");
+        instructions
+    }
+    
     /// 生成增強的系統提示
     pub fn generate_enhanced_system_prompt(&self, base_prompt: &str) -> String {
         let purity_enforcement = "
@@ -1,4 +1,8 @@
+pub mod csv;
+pub mod html_import;
 pub mod language_purity;
+pub mod prompt_sanitizer;
+pub mod slate;
 
 #[allow(unused_imports)]
 pub use language_purity::*;
\ No newline at end of file
@@ -0,0 +1,323 @@
+use serde_json::{json, Value};
+
+/// 檢查內容是否為 Slate.js 文件並回傳節點陣列
+///
+/// 內容須為節點陣列（或單一節點），且每個節點都有 `text` 或 `children`；
+/// 空白內容視為空文件，其他內容（純文字、格式錯誤的 JSON）回傳 None。
+pub fn parse_slate_document(content: &str) -> Option<Vec<Value>> {
+    if content.trim().is_empty() {
+        return Some(Vec::new());
+    }
+    let is_node = |node: &Value| node.get("text").is_some() || node.get("children").is_some_and(Value::is_array);
+    match serde_json::from_str::<Value>(content).ok()? {
+        Value::Array(nodes) if nodes.iter().all(is_node) => Some(nodes),
+        node if is_node(&node) => Some(vec![node]),
+        _ => None,
+    }
+}
+
+/// 將純文字轉為 HTML 段落（以換行分段，略過空行）
+pub fn plain_text_to_html(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("<p>{}</p>", html_escape::encode_text(line)))
+        .collect()
+}
+
+/// 將章節內容轉為 HTML（PDF 與 EPUB 導出共用）
+///
+/// 不是有效的 Slate.js JSON 時改以純文字處理，避免單一章節中斷整本書的匯出。
+pub fn slate_to_html(content: &str, chapter_title: &str) -> String {
+    let Some(nodes) = parse_slate_document(content) else {
+        log::warn!("章節「{}」的內容不是有效的 Slate.js JSON，改以純文字匯出", chapter_title);
+        return plain_text_to_html(content);
+    };
+    nodes.iter().map(slate_node_to_html).collect()
+}
+
+/// 將 Slate.js 節點轉為 HTML（文字節點套用粗體、斜體、底線格式）
+fn slate_node_to_html(node: &Value) -> String {
+    if let Some(text) = node.get("text") {
+        let mut html = html_escape::encode_text(text.as_str().unwrap_or("")).to_string();
+        let mark = |name: &str| node.get(name).and_then(Value::as_bool).unwrap_or(false);
+        if mark("bold") {
+            html = format!("<strong>{}</strong>", html);
+        }
+        if mark("italic") {
+            html = format!("<em>{}</em>", html);
+        }
+        if mark("underline") {
+            html = format!("<u>{}</u>", html);
+        }
+        return html;
+    }
+
+    let children_html: String = node
+        .get("children")
+        .and_then(Value::as_array)
+        .map(|children| children.iter().map(slate_node_to_html).collect())
+        .unwrap_or_default();
+    match node.get("type").and_then(Value::as_str).unwrap_or("paragraph") {
+        "paragraph" => format!("<p>{}</p>", children_html),
+        "heading-one" => format!("<h1>{}</h1>", children_html),
+        "heading-two" => format!("<h2>{}</h2>", children_html),
+        "heading-three" => format!("<h3>{}</h3>", children_html),
+        "block-quote" => format!("<blockquote>{}</blockquote>", children_html),
+        "bulleted-list" => format!("<ul>{}</ul>", children_html),
+        "numbered-list" => format!("<ol>{}</ol>", children_html),
+        "list-item" => format!("<li>{}</li>", children_html),
+        _ => format!("<div>{}</div>", children_html),
+    }
+}
+
+/// 建立段落節點
+pub fn paragraph_node(text: &str) -> Value {
+    json!({
+        "type": "paragraph",
+        "children": [{ "text": text }]
+    })
+}
+
+/// 計算節點的純文字長度（字符數，與 Slate 的 Node.string 一致，不含段落分隔）
+fn node_char_len(node: &Value) -> usize {
+    if let Some(text) = node.get("text").and_then(|t| t.as_str()) {
+        return text.chars().count();
+    }
+    node.get("children")
+        .and_then(|c| c.as_array())
+        .map(|children| children.iter().map(node_char_len).sum())
+        .unwrap_or(0)
+}
+
+/// 收集節點內所有文字葉節點的內容
+fn collect_node_text(node: &Value, output: &mut String) {
+    if let Some(text) = node.get("text").and_then(|t| t.as_str()) {
+        output.push_str(text);
+        return;
+    }
+    if let Some(children) = node.get("children").and_then(|c| c.as_array()) {
+        for child in children {
+            collect_node_text(child, output);
+        }
+    }
+}
+
+/// 將章節內容轉為純文字（頂層區塊以換行分隔）
+///
+/// 非 Slate JSON 的舊資料視為純文字直接回傳。
+pub fn slate_to_plain_text(content: &str) -> String {
+    match serde_json::from_str::<Value>(content) {
+        Ok(Value::Array(nodes)) => nodes
+            .iter()
+            .map(|node| {
+                let mut text = String::new();
+                collect_node_text(node, &mut text);
+                text
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Ok(_) => String::new(),
+        Err(_) => content.to_string(),
+    }
+}
+
+/// 文字統計結果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextCounts {
+    /// 字數（中文字 + 英文單詞，與前端編輯器的計算方式一致）
+    pub words: usize,
+    /// 可見字符數（不含空白）
+    pub characters: usize,
+}
+
+/// 計算純文字的字數與可見字符數
+pub fn count_text(text: &str) -> TextCounts {
+    let mut counts = TextCounts::default();
+    let mut in_latin_word = false;
+    for ch in text.chars() {
+        if !ch.is_whitespace() {
+            counts.characters += 1;
+        }
+        if ch.is_ascii_alphabetic() {
+            if !in_latin_word {
+                counts.words += 1;
+                in_latin_word = true;
+            }
+            continue;
+        }
+        in_latin_word = false;
+        if ('\u{4e00}'..='\u{9fff}').contains(&ch) {
+            counts.words += 1;
+        }
+    }
+    counts
+}
+
+/// 在指定的純文字位置插入文本，保留 Slate.js 的節點結構
+///
+/// 位置以字符計算（與編輯器的 `Editor.string` 一致）。
+/// 多段落文本會拆分成新的段落節點，游標後的原有內容會接在最後一段之後。
+/// 舊版純文字章節會先逐行轉為段落節點，不會遺失原有內容。
+pub fn insert_text_at_offset(content: &str, offset: Option<usize>, text: &str) -> Result<String, String> {
+    let mut nodes = parse_slate_document(content)
+        .unwrap_or_else(|| content.lines().map(paragraph_node).collect());
+    let paragraphs: Vec<&str> = text
+        .split('\n')
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+
+    if paragraphs.is_empty() {
+        return serde_json::to_string(&nodes).map_err(|e| e.to_string());
+    }
+
+    // 找出位置所在的頂層區塊與區塊內的相對位置
+    let mut target = None;
+    if let Some(offset) = offset {
+        let mut consumed = 0;
+        for (index, node) in nodes.iter().enumerate() {
+            let len = node_char_len(node);
+            if offset <= consumed + len {
+                target = Some((index, offset - consumed));
+                break;
+            }
+            consumed += len;
+        }
+    }
+
+    let Some((block_index, local_offset)) = target else {
+        // 沒有位置或超出內容範圍：附加在章節結尾
+        nodes.extend(paragraphs.iter().map(|p| paragraph_node(p)));
+        return serde_json::to_string(&nodes).map_err(|e| e.to_string());
+    };
+
+    let block = &mut nodes[block_index];
+    let Some(children) = block.get_mut("children").and_then(|c| c.as_array_mut()) else {
+        nodes.splice(block_index + 1..block_index + 1, paragraphs.iter().map(|p| paragraph_node(p)));
+        return serde_json::to_string(&nodes).map_err(|e| e.to_string());
+    };
+
+    // 只處理區塊的直接文字子節點；巢狀結構（如列表）改為在區塊後插入段落
+    let mut consumed = 0;
+    let mut leaf = None;
+    for (index, child) in children.iter().enumerate() {
+        let len = node_char_len(child);
+        if local_offset <= consumed + len {
+            if child.get("text").and_then(|t| t.as_str()).is_some() {
+                leaf = Some((index, local_offset - consumed));
+            }
+            break;
+        }
+        consumed += len;
+    }
+
+    let Some((leaf_index, leaf_offset)) = leaf else {
+        nodes.splice(block_index + 1..block_index + 1, paragraphs.iter().map(|p| paragraph_node(p)));
+        return serde_json::to_string(&nodes).map_err(|e| e.to_string());
+    };
+
+    let leaf_text = children[leaf_index]["text"].as_str().unwrap_or("").to_string();
+    let before: String = leaf_text.chars().take(leaf_offset).collect();
+    let after: String = leaf_text.chars().skip(leaf_offset).collect();
+
+    if paragraphs.len() == 1 {
+        children[leaf_index]["text"] = Value::String(format!("{}{}{}", before, paragraphs[0], after));
+        return serde_json::to_string(&nodes).map_err(|e| e.to_string());
+    }
+
+    // 多段落：拆分區塊，游標後的文字與後續子節點移到最後一段
+    children[leaf_index]["text"] = Value::String(format!("{}{}", before, paragraphs[0]));
+    let trailing_children: Vec<Value> = children.drain(leaf_index + 1..).collect();
+
+    let last = paragraphs.len() - 1;
+    let mut new_blocks: Vec<Value> = paragraphs[1..last].iter().map(|p| paragraph_node(p)).collect();
+
+    let mut last_leaf = children[leaf_index].clone();
+    last_leaf["text"] = Value::String(format!("{}{}", paragraphs[last], after));
+    let mut last_block = block.clone();
+    let mut last_children = vec![last_leaf];
+    last_children.extend(trailing_children);
+    last_block["children"] = Value::Array(last_children);
+    new_blocks.push(last_block);
+
+    nodes.splice(block_index + 1..block_index + 1, new_blocks);
+    serde_json::to_string(&nodes).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slate_to_html_falls_back_to_plain_text() {
+        let slate = r#"[{"type":"paragraph","children":[{"text":"月光","bold":true}]}]"#;
+        assert_eq!(slate_to_html(slate, "第一章"), "<p><strong>月光</strong></p>");
+        assert_eq!(slate_to_html("", "第二章"), "");
+        assert_eq!(
+            slate_to_html("舊版純文字\n第二段 & 結尾", "第三章"),
+            "<p>舊版純文字</p><p>第二段 &amp; 結尾</p>"
+        );
+        assert_eq!(slate_to_html(r#"[{"type":"paragraph""#, "第四章"), r#"<p>[{"type":"paragraph"</p>"#);
+    }
+
+    fn texts(content: &str) -> Vec<String> {
+        parse_slate_document(content)
+            .unwrap()
+            .iter()
+            .map(|node| {
+                node["children"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|c| c["text"].as_str().unwrap_or("").to_string())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_inline_text() {
+        let content = r#"[{"type":"paragraph","children":[{"text":"他走進森林。"}]}]"#;
+        let result = insert_text_at_offset(content, Some(3), "緩緩地").unwrap();
+        assert_eq!(texts(&result), vec!["他走進緩緩地森林。"]);
+    }
+
+    #[test]
+    fn test_insert_multiple_paragraphs_splits_block() {
+        let content = r#"[{"type":"paragraph","children":[{"text":"第一段"}]},{"type":"paragraph","children":[{"text":"前半後半"}]}]"#;
+        let result = insert_text_at_offset(content, Some(5), "甲\n\n乙\n丙").unwrap();
+        assert_eq!(texts(&result), vec!["第一段", "前半甲", "乙", "丙後半"]);
+    }
+
+    #[test]
+    fn test_plain_text_and_counts_ignore_json_structure() {
+        let content = r#"[{"type":"heading","children":[{"text":"序章"}]},{"type":"paragraph","children":[{"text":"他說 "},{"text":"hello world","bold":true},{"text":"。"}]}]"#;
+        let text = slate_to_plain_text(content);
+        assert_eq!(text, "序章\n他說 hello world。");
+        assert_eq!(count_text(&text), TextCounts { words: 6, characters: 15 });
+        assert_eq!(slate_to_plain_text("舊的純文字"), "舊的純文字");
+    }
+
+    #[test]
+    fn test_insert_without_position_appends() {
+        let result = insert_text_at_offset("", None, "新段落").unwrap();
+        assert_eq!(texts(&result), vec!["新段落"]);
+
+        let result = insert_text_at_offset("舊的第一段\n舊的第二段", Some(7), "插入").unwrap();
+        assert_eq!(texts(&result), vec!["舊的第一段", "舊的插入第二段"]);
+    }
+
+    #[test]
+    fn test_parse_slate_document_detects_plain_text() {
+        assert_eq!(parse_slate_document("  ").unwrap().len(), 0);
+        assert_eq!(parse_slate_document(r#"[{"type":"paragraph","children":[{"text":"a"}]}]"#).unwrap().len(), 1);
+        assert_eq!(parse_slate_document(r#"{"type":"paragraph","children":[]}"#).unwrap().len(), 1);
+        assert!(parse_slate_document("第一行\n第二行").is_none());
+        assert!(parse_slate_document(r#"[{"type":"paragraph","children":[{"text":"#).is_none());
+        assert!(parse_slate_document(r#"["a", "b"]"#).is_none());
+        assert!(parse_slate_document("42").is_none());
+
+        assert_eq!(plain_text_to_html("第一行\n\n  <第二行>  \n"), "<p>第一行</p><p>&lt;第二行&gt;</p>");
+    }
+}
@@ -2,14 +2,29 @@
 import { Descendant } from 'slate';
 
 // 專案相關
+// 小說篇幅
+export type NovelLength = 'short' | 'medium' | 'long' | 'epic';
+
+// 各篇幅的建議章節數與字數（max 為空表示不設上限）
+export interface NovelLengthPreset {
+  length: NovelLength;
+  label: string;
+  min_chapters: number;
+  max_chapters?: number;
+  min_words: number;
+  max_words?: number;
+  words_per_chapter: number;
+}
+
 export interface Project {
   id: string;
   name: string;
   type: 'isekai' | 'school' | 'scifi' | 'fantasy';
   description: string;
-  novelLength: 'short' | 'medium' | 'long';
+  novelLength: NovelLength;
   createdAt: string;
   updatedAt: string;
+  isArchived?: boolean;
   settings: {
     aiModel?: string;
     aiParams?: {
@@ -35,6 +50,171 @@ export interface Chapter {
   status?: 'draft' | 'writing' | 'reviewing' | 'completed'; // 章節狀態
 }
 
+// 章節歷史版本（後端回傳 snake_case 欄位）
+export interface ChapterVersion {
+  id: string;
+  chapter_id: string;
+  title: string;
+  content?: string; // Slate JSON 字串
+  plain_text: string;
+  metadata?: string;
+  source: 'update' | 'restore' | 'find_replace' | 'ai_history';
+  created_at: string;
+}
+
+// 章節編號重新計算的變更（normalize_chapter_numbers）
+export interface ChapterNumberChange {
+  chapter_id: string;
+  title: string;
+  old_number?: number;
+  new_number: number; // 專案設定 count_prologue_as_zero 時序章為 0
+}
+
+// 全專案尋找與取代選項
+export interface FindReplaceOptions {
+  case_sensitive?: boolean;
+  whole_word?: boolean; // 英數字搜尋詞不匹配較長單字的一部分
+  dry_run?: boolean; // 只預覽，不修改章節
+}
+
+// 全專案尋找與取代結果
+export interface FindReplaceResult {
+  dry_run: boolean;
+  total_replacements: number;
+  chapters: {
+    chapter_id: string;
+    chapter_title: string;
+    replacements: number;
+    previews: string[]; // 前後文預覽，比對文字以【】標示
+  }[];
+}
+
+// 匯入後建立的章節
+export interface ImportedChapter {
+  id: string;
+  title: string;
+  order_index: number;
+  chapter_number: number;
+}
+
+// 章節摘要結果
+export interface ChapterSummaryResult {
+  chapter_id: string;
+  summary?: string;
+  cached: boolean; // 內容未變更，沿用先前的摘要
+  skipped: boolean; // 章節字數不足，未產生摘要
+  purity_score?: number;
+}
+
+// 章節劇情節奏分析結果（plot_analysis）
+export interface PlotAnalysisResult {
+  id: string;
+  project_id: string;
+  chapter_id: string;
+  stats: {
+    dialogue_ratio: number;
+    action_ratio: number;
+    description_ratio: number;
+    word_count: number;
+    paragraph_count: number;
+    sentence_count: number;
+    avg_sentence_length: number;
+  };
+  tension?: {
+    tension_level: number;
+    pacing_score: number;
+    climax_detected: boolean;
+    conflict_points: { position: number; intensity: number; description: string }[];
+    summary?: string;
+    confidence: number;
+  };
+  ai_provider?: string;
+  ai_model?: string;
+  cached: boolean; // 章節內容未變更，沿用快取的分析結果
+}
+
+// 背景分析任務（analysis_queue）
+export type AnalysisTaskStatus = 'pending' | 'processing' | 'completed' | 'failed' | 'cancelled';
+
+export interface AnalysisTask {
+  id: string;
+  project_id: string;
+  analysis_type: 'character' | 'plot';
+  target_id?: string;
+  priority: number; // 1-10，10 最高
+  status: AnalysisTaskStatus;
+  progress: number; // 0-1
+  current_step?: string;
+  params?: { chapter_id?: string; provider_id?: string };
+  retry_count: number;
+  max_retries: number;
+  result_id?: string;
+  error_message?: string;
+  queued_at: string;
+  started_at?: string;
+  completed_at?: string;
+}
+
+// analysis-queue-progress 事件
+export interface AnalysisQueueEvent {
+  task_id: string;
+  project_id: string;
+  analysis_type: string;
+  target_id?: string;
+  status: AnalysisTaskStatus;
+  progress: number;
+  current_step?: string;
+  result_id?: string;
+  error_message?: string;
+}
+
+// model-loading 事件（Ollama 模型冷啟動載入中）
+export interface ModelLoadingEvent {
+  model: string;
+  loading: boolean;
+  attempt: number;
+  elapsed_ms: number;
+  message: string;
+}
+
+// translation-progress 事件（批次翻譯每完成一筆發送一次）
+export interface TranslationProgressEvent {
+  index: number;
+  completed: number;
+  total: number;
+  success: boolean;
+  error?: string;
+}
+
+// 創意建議（creative_suggestions）
+export type SuggestionTargetType = 'plot' | 'character' | 'dialogue' | 'scene' | 'general';
+export type SuggestionType = 'continuation' | 'alternative' | 'enhancement' | 'conflict' | 'resolution';
+
+export interface CreativeSuggestion {
+  id: string;
+  project_id: string;
+  chapter_id: string;
+  target_type: SuggestionTargetType;
+  target_id?: string;
+  suggestion_type: SuggestionType;
+  title: string;
+  content: string;
+  themes: string[];
+  relevance_score: number;
+  creativity_score: number;
+  quality_score: number;
+  ai_provider: string;
+  ai_model: string;
+  votes: Record<string, number>; // 與其他提供者建議的主題相似度
+  consensus_score: number;
+  status: 'pending' | 'accepted' | 'rejected' | 'modified';
+}
+
+export interface CreativeSuggestionsResult {
+  suggestions: CreativeSuggestion[];
+  failures: Record<string, string>; // 提供者 ID → 錯誤訊息
+}
+
 // 角色相關
 export interface Relationship {
   id?: string;
@@ -49,6 +229,90 @@ export interface CreateRelationshipRequest {
   toCharacterId: string;
   relationshipType: string;
   description: string;
+  bidirectional?: boolean; // 同時建立反向關係（刪除時一併移除）
+}
+
+// 建議的關係類型（符合別名時後端會正規化為 value）
+export interface RelationshipTypeInfo {
+  value: string;
+  aliases: string[];
+}
+
+// 專案角色關係圖（後端回傳 snake_case 欄位）
+export interface RelationshipGraph {
+  nodes: { id: string; name: string }[];
+  edges: {
+    id: string;
+    from: string;
+    to: string;
+    relationship_type: string;
+    description?: string;
+    pair_id?: string;
+    reciprocated: boolean; // 是否存在反方向的關係
+  }[];
+  adjacency: Record<string, string[]>;
+}
+
+// 角色出場掃描結果（後端回傳 snake_case 欄位）
+export interface CharacterMentionSummary {
+  character_id: string;
+  name: string;
+  total_mentions: number;
+  chapters: {
+    chapter_id: string;
+    chapter_title: string;
+    mention_count: number;
+    first_position: number; // 第一次出現的字符位置
+  }[];
+}
+
+// 疑似重複的角色組合
+export interface DuplicateCharacterPair {
+  first_id: string;
+  first_name: string;
+  second_id: string;
+  second_name: string;
+  name_similarity: number;
+  attribute_overlap: number | null; // 任一方沒有屬性時為 null
+  score: number;
+}
+
+// 合併角色結果
+export interface MergeCharactersResult {
+  keep_id: string;
+  merged_id: string;
+  relationships_moved: number;
+  relationships_removed: number;
+  analyses_moved: number;
+  illustrations_moved: number;
+  visual_traits_moved: boolean;
+  mentions_merged: number;
+}
+
+// 角色章節分析結果（character_analysis）
+export interface CharacterAnalysisResult {
+  id: string;
+  character_id: string;
+  chapter_id: string;
+  project_id: string;
+  stats: {
+    dialogue_count: number;
+    avg_dialogue_length: number;
+    vocabulary_richness: number; // 相異字數 / 總字數
+    sentence_complexity: number; // 平均每句的子句數
+  };
+  dialogue_samples: string[];
+  personality?: {
+    openness: number;
+    conscientiousness: number;
+    extraversion: number;
+    agreeableness: number;
+    neuroticism: number;
+    emotional_tone: 'positive' | 'negative' | 'neutral' | 'mixed';
+    emotional_intensity: number;
+    confidence: number;
+  };
+  cached: boolean; // 章節內容未變更，沿用快取的分析結果
 }
 
 export interface Character {
@@ -93,6 +357,19 @@ export interface AIGenerationParams {
   seed?: number;
 }
 
+// 語言純度不足時自動重新生成的設定
+export interface PurityRetryOptions {
+  min_score?: number;
+  max_retries?: number;
+}
+
+// 分離上下文生成結果（分數最高的一次與實際生成次數）
+export interface SeparatedGenerationResult {
+  text: string;
+  purity_score: number;
+  attempts: number;
+}
+
 export interface AIServiceStatus {
   isRunning: boolean;
   version?: string;
@@ -131,6 +408,31 @@ export interface ContextStats {
   wordCount: number;
 }
 
+// 寫作統計（後端回傳 snake_case 欄位）
+export interface ChapterWritingStats {
+  chapter_id: string;
+  title: string;
+  chapter_number?: number;
+  words: number;
+  characters: number;
+  updated_at: string;
+}
+
+export interface DailyWritingActivity {
+  date: string; // YYYY-MM-DD
+  chapters_updated: number;
+  words: number;
+}
+
+export interface ProjectWritingStats {
+  project_id: string;
+  total_words: number;
+  total_characters: number;
+  chapter_count: number;
+  chapters: ChapterWritingStats[];
+  daily_activity: DailyWritingActivity[];
+}
+
 // 設定相關
 export interface Settings {
   theme?: 'light' | 'dark' | 'system';
@@ -210,6 +512,7 @@ export interface UpdateInfo {
   releaseDate: string;
   releaseNotes: string;
   downloadUrl?: string;
+  downloadSize?: number;
   isAvailable: boolean;
   error?: string;
 }
@@ -274,12 +577,22 @@ export interface AIProviderTestResult {
   error?: string;
 }
 
+export interface AIProviderHealth {
+  id: string;
+  name: string;
+  provider_type: string;
+  reachable: boolean;
+  latency_ms?: number;
+  error?: string;
+}
+
 export interface AIGenerationResult {
   success: boolean;
   generated_text?: string;
   model?: string;
   usage?: unknown;
   provider_id?: string;
+  history_id?: string;
   error?: string;
 }
 
@@ -397,6 +710,30 @@ export interface PDFResult {
   error_message?: string;
 }
 
+// 作品包（EPUB + PDF + 插畫打包的 ZIP）
+export type BundleFormat = 'epub' | 'pdf';
+
+export interface BundleManifest {
+  project_id: string;
+  project_name: string;
+  created_at: string;
+  app_version: string;
+  formats: BundleFormat[];
+  files: {
+    kind: BundleFormat | 'illustration';
+    path: string; // 壓縮檔內路徑
+    size: number;
+  }[];
+}
+
+export interface ProjectBundleResult {
+  id: string;
+  file_path: string;
+  file_size: number;
+  illustration_count: number;
+  manifest: BundleManifest;
+}
+
 export interface PDFExportRecord {
   id: string;
   project_id: string;
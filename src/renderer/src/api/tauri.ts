@@ -4,7 +4,8 @@ import type {
   Character,
   Relationship,
   CharacterAttributes,
-  CreateRelationshipRequest
+  CreateRelationshipRequest,
+  NovelLength
 } from './models';
 import type { BatchRequest } from '../types/illustration';
 import type { Descendant } from 'slate';
@@ -20,6 +21,7 @@ interface TauriProject {
   created_at: string;
   updated_at: string;
   settings?: string;
+  is_archived?: boolean;
 }
 
 interface TauriChapter {
@@ -261,8 +263,8 @@ const enhancedSafeInvoke = async <T = unknown>(command: string, args?: Record<st
 // Tauri API 實現
 export const tauriAPI: API = {
   projects: {
-    getAll: async () => {
-      const projects = await enhancedSafeInvoke<TauriProject[]>('get_all_projects');
+    getAll: async (includeArchived) => {
+      const projects = await enhancedSafeInvoke<TauriProject[]>('get_all_projects', { includeArchived });
       
       // 檢查返回值是否為有效陣列
       if (!Array.isArray(projects)) {
@@ -276,9 +278,10 @@ export const tauriAPI: API = {
         name: project.name,
         description: project.description || '',
         type: (project.type as 'isekai' | 'school' | 'scifi' | 'fantasy') || 'isekai',
-        novelLength: (project.novel_length as 'short' | 'medium' | 'long') || 'medium',
+        novelLength: (project.novel_length as NovelLength) || 'medium',
         createdAt: project.created_at,
         updatedAt: project.updated_at,
+        isArchived: project.is_archived ?? false,
         settings: project.settings ? JSON.parse(project.settings) : {}
       }));
     },
@@ -306,6 +309,8 @@ export const tauriAPI: API = {
       return safeInvoke('update_project', { project: updateRequest });
     },
     delete: (id) => safeInvoke('delete_project', { id }),
+    archive: (id) => safeInvoke('archive_project', { id }),
+    unarchive: (id) => safeInvoke('unarchive_project', { id }),
     getById: async (id) => {
       const project = await safeInvoke<TauriProject>('get_project_by_id', { id });
       // 轉換 Tauri 後端格式到前端格式
@@ -314,12 +319,19 @@ export const tauriAPI: API = {
         name: project.name,
         description: project.description || '',
         type: (project.type as 'isekai' | 'school' | 'scifi' | 'fantasy') || 'isekai',
-        novelLength: (project.novel_length as 'short' | 'medium' | 'long') || 'medium',
+        novelLength: (project.novel_length as NovelLength) || 'medium',
         createdAt: project.created_at,
         updatedAt: project.updated_at,
+        isArchived: project.is_archived ?? false,
         settings: project.settings ? JSON.parse(project.settings) : {}
       };
     },
+    getWritingStats: (projectId) => safeInvoke('get_project_writing_stats', { projectId }),
+    getNovelLengthPresets: () => safeInvoke('get_novel_length_presets'),
+    clearAnalysisCache: (projectId) => safeInvoke('clear_analysis_cache', { projectId }),
+    enqueueAnalysis: (projectId, analysisType, targetId, priority, params) =>
+      safeInvoke('enqueue_analysis', { projectId, analysisType, targetId, priority, params }),
+    getAnalysisQueue: (projectId) => safeInvoke('get_analysis_queue', { projectId }),
   },
   
   chapters: {
@@ -423,14 +435,15 @@ export const tauriAPI: API = {
         chapter_number: chapter.chapterNumber
       }
     }),
-    update: (chapter) => safeInvoke('update_chapter', {
+    update: (chapter, expectedUpdatedAt) => safeInvoke<string>('update_chapter', {
       chapter: {
         id: chapter.id,
         title: chapter.title,
         content: JSON.stringify(chapter.content),
         order_index: chapter.order,
         chapter_number: chapter.chapterNumber,
-        metadata: chapter.metadata  // 🔧 添加遺失的 metadata 欄位！
+        metadata: chapter.metadata,  // 🔧 添加遺失的 metadata 欄位！
+        expected_updated_at: expectedUpdatedAt  // 提供時，章節已被其他操作更新會回傳 Conflict 錯誤
       }
     }),
     delete: (id) => safeInvoke('delete_chapter', { id }),
@@ -473,6 +486,19 @@ export const tauriAPI: API = {
         updatedAt: chapter.updated_at
       };
     },
+    reorder: (projectId, orderedIds) => safeInvoke('reorder_chapters', { projectId, orderedIds }),
+    normalizeNumbers: (projectId) => safeInvoke('normalize_chapter_numbers', { projectId }),
+    listVersions: (chapterId) => safeInvoke('list_chapter_versions', { chapterId }),
+    restoreVersion: (versionId) => safeInvoke('restore_chapter_version', { versionId }),
+    findAndReplace: (projectId, find, replace, options) => safeInvoke('find_and_replace_in_project', { projectId, find, replace, options }),
+    summarize: (chapterId, providerId) => safeInvoke('summarize_chapter', { chapterId, providerId }),
+    analyzePlot: (chapterId, providerId) => safeInvoke('analyze_chapter_plot', { chapterId, providerId }),
+    generateSuggestions: (chapterId, targetType, suggestionType, providerIds, targetId) =>
+      safeInvoke('generate_creative_suggestions', { chapterId, targetType, suggestionType, providerIds, targetId }),
+    acceptSuggestion: (id, userRating) => safeInvoke('accept_suggestion', { id, userRating }),
+    rejectSuggestion: (id, feedback, userRating) => safeInvoke('reject_suggestion', { id, feedback, userRating }),
+    importFromHtml: (projectId, html) => safeInvoke('import_chapters_from_html', { projectId, html }),
+    importDocument: (projectId, path) => safeInvoke('import_document', { projectId, path }),
   },
 
   characters: {
@@ -595,6 +621,7 @@ export const tauriAPI: API = {
       });
     },
     delete: (id) => safeInvoke('delete_character', { id }),
+    validateAttributes: (attributes) => safeInvoke('validate_character_attributes', { attributes }),
     getById: async (id) => {
       interface TauriCharacter {
         id: string;
@@ -657,9 +684,16 @@ export const tauriAPI: API = {
       toCharacterId: request.toCharacterId,
       relationshipType: request.relationshipType,
       description: request.description || null,
+      bidirectional: request.bidirectional ?? false,
     }),
     deleteRelationship: (id) => safeInvoke('delete_character_relationship', { id }),
     clearRelationships: (characterId) => safeInvoke('clear_character_relationships', { characterId }),
+    getRelationshipTypes: () => safeInvoke('get_relationship_types'),
+    getRelationshipGraph: (projectId) => safeInvoke('get_character_relationship_graph', { projectId }),
+    scanMentions: (projectId) => safeInvoke('scan_character_mentions', { projectId }),
+    findDuplicates: (projectId) => safeInvoke('find_duplicate_characters', { projectId }),
+    merge: (keepId, mergeId) => safeInvoke('merge_characters', { keepId, mergeId }),
+    analyze: (characterId, chapterId, providerId) => safeInvoke('analyze_character', { characterId, chapterId, providerId }),
   },
 
   ai: {
@@ -680,6 +714,8 @@ export const tauriAPI: API = {
         language: language || 'zh-TW'
       });
     },
+    generateWithSeparatedContext: (projectId, chapterId, position, model, params, purityRetry) =>
+      safeInvoke('generate_with_separated_context', { projectId, chapterId, position, model, params, purityRetry }),
     updateOllamaConfig: (config) => safeInvoke('update_ollama_config', { config }),
   },
   // AI 提供者管理 (新多提供者系統)
@@ -699,6 +735,9 @@ export const tauriAPI: API = {
     test: async (id) => {
       return await safeInvoke('test_ai_provider', { id });
     },
+    testAll: async (timeoutSecs) => {
+      return await safeInvoke('test_all_ai_providers', { timeoutSecs });
+    },
     generateText: async (request) => {
       return await safeInvoke('generate_ai_text', { request });
     },
@@ -713,15 +752,20 @@ export const tauriAPI: API = {
   context: {
     buildContext: (projectId, chapterId, position) => 
       safeInvoke('build_context', { projectId, chapterId, position }),
+    buildAppendContext: (projectId, chapterId) =>
+      safeInvoke('build_append_context', { projectId, chapterId }),
     compressContext: (context, maxTokens) => 
       safeInvoke('compress_context', { context, maxTokens }),
-    getContextStats: (projectId) => safeInvoke('get_context_stats', { projectId }),
+    getContextStats: (projectId, accurate) => safeInvoke('get_context_stats', { projectId, accurate }),
+    getDefaultSystemPrompt: (projectType) => safeInvoke('get_default_system_prompt', { projectType }),
     optimizeUltraLongContext: (params) => 
       safeInvoke('optimize_ultra_long_context_command', {
         originalContext: params.originalContext,
         maxTokens: params.maxTokens,
         focusCharacters: params.focusCharacters,
-        currentPosition: params.currentPosition
+        currentPosition: params.currentPosition,
+        targetTokens: params.targetTokens,
+        strategy: params.strategy
       }),
   },
 
@@ -765,7 +809,7 @@ export const tauriAPI: API = {
 
   database: {
     backup: (path) => safeInvoke('backup_database', { path }),
-    restore: (path) => safeInvoke('restore_database', { path }),
+    restore: (path, confirmationToken) => safeInvoke('restore_database', { path, confirmationToken }),
     runMaintenance: () => safeInvoke('run_database_maintenance'),
     getStats: () => safeInvoke('get_database_stats'),
     healthCheck: () => safeInvoke('health_check'),
@@ -773,6 +817,7 @@ export const tauriAPI: API = {
     incrementalVacuum: (pages?: number) => safeInvoke('incremental_vacuum', { pages }),
     getWalModeStatus: () => safeInvoke('get_wal_mode_status'),
     setWalMode: (enable: boolean) => safeInvoke('set_wal_mode', { enable }),
+    checkpointWal: (mode: 'PASSIVE' | 'FULL' | 'TRUNCATE') => safeInvoke('checkpoint_wal', { mode }),
   },
 
   system: {
@@ -795,12 +840,15 @@ export const tauriAPI: API = {
     },
     quitApp: () => safeInvoke('quit_app'),
     reloadApp: () => safeInvoke('reload_app'),
+    collectDiagnostics: () => safeInvoke('collect_diagnostics'),
   },
 
   updates: {
     checkForUpdates: () => safeInvoke('check_for_updates'),
     downloadUpdate: () => safeInvoke('download_update'),
     installUpdate: () => safeInvoke('install_update'),
+    confirmUpdateLaunch: () => safeInvoke('confirm_update_launch'),
+    rollbackUpdate: () => safeInvoke('rollback_update'),
     setAutoUpdate: (enabled) => safeInvoke('set_auto_update', { enabled }),
   },
 
@@ -894,8 +942,8 @@ export const tauriAPI: API = {
       safeInvoke('mark_ai_history_selected', { historyId, projectId }),
     delete: (historyId) => 
       safeInvoke('delete_ai_history', { historyId }),
-    cleanup: (projectId, keepCount) => 
-      safeInvoke('cleanup_ai_history', { projectId, keepCount }),
+    cleanup: (projectId, keepCount, keepPerChapter) => 
+      safeInvoke('cleanup_ai_history', { projectId, keepCount, keepPerChapter }),
   },
 
   // 小說分析功能
@@ -998,7 +1046,9 @@ export const tauriAPI: API = {
       return safeInvoke('delete_epub_export', {
         exportId: exportId
       });
-    }
+    },
+
+    import: (path) => safeInvoke('import_epub', { path })
   },
 
   // PDF 文檔生成 - Chrome Headless 最新方案
@@ -1016,9 +1066,13 @@ export const tauriAPI: API = {
         return await safeInvoke('generate_pdf_chrome', {
           projectId: projectId.trim(),
           options: options || {
-            page_size: 'A4',
+            page_size: 'A5',
+            orientation: 'portrait',
             font_size: 12.0,
-            margins: '20mm',
+            margin_top: 18.0,
+            margin_bottom: 18.0,
+            margin_left: 15.0,
+            margin_right: 15.0,
             include_cover: true
           }
         });
@@ -1054,14 +1108,23 @@ export const tauriAPI: API = {
     },
 
 
-    getExports: async (_projectId) => {
-      // 注意：PDF V2 系統未來會支援匯出管理功能
-      throw new Error('PDF V2 匯出管理功能尚未實現，敬請期待');
+    getExports: async (projectId) => {
+      return safeInvoke('get_pdf_exports', {
+        projectId: projectId
+      });
     },
+    
+    deleteExport: async (exportId) => {
+      return safeInvoke('delete_pdf_export', {
+        exportId: exportId
+      });
+    }
+  },
 
-    deleteExport: async (_exportId) => {
-      // 注意：PDF V2 系統未來會支援匯出管理功能  
-      throw new Error('PDF V2 匯出刪除功能尚未實現，敬請期待');
+  // 作品包導出
+  bundle: {
+    exportProject: async (projectId, formats, outputPath) => {
+      return safeInvoke('export_project_bundle', { projectId, formats, outputPath });
     }
   },
 
@@ -1149,6 +1212,30 @@ export const tauriAPI: API = {
       });
     },
 
+    previewIllustrationPrompt: async (
+      projectId: string,
+      characterId: string | null,
+      sceneDescription: string,
+      templateId?: string,
+      translationStyle?: string,
+      optimizationLevel?: string,
+      aspectRatio?: string,
+      safetyLevel?: string,
+      customNegativePrompt?: string
+    ) => {
+      return safeInvoke('preview_illustration_prompt', {
+        projectId: projectId,
+        characterId: characterId,
+        sceneDescription: sceneDescription,
+        templateId: templateId,
+        translationStyle: translationStyle || 'anime',
+        optimizationLevel: optimizationLevel || 'standard',
+        aspectRatio: aspectRatio || 'square',
+        safetyLevel: safetyLevel || 'block_most',
+        customNegativePrompt: customNegativePrompt
+      });
+    },
+
     cancelGeneration: async (taskId: string) => {
       return safeInvoke('cancel_illustration_generation', {
         taskId: taskId
@@ -1161,6 +1248,32 @@ export const tauriAPI: API = {
       });
     },
 
+    testImagenApiKey: async (apiKey: string) => {
+      return safeInvoke('test_imagen_api_key', {
+        apiKey: apiKey
+      });
+    },
+
+    auditStorage: async (projectId?: string) => {
+      return safeInvoke('audit_illustration_storage', { projectId });
+    },
+
+    cleanOrphanedIllustrations: async (dryRun: boolean, clearDanglingPaths?: boolean) => {
+      return safeInvoke('clean_orphaned_illustrations', { dryRun, clearDanglingPaths });
+    },
+
+    getCostReport: async (projectId: string, from?: string, to?: string) => {
+      return safeInvoke('get_illustration_cost_report', { projectId, from, to });
+    },
+
+    retryFailedIllustrations: async (projectId: string, apiKey: string, maxRetries?: number) => {
+      return safeInvoke('retry_failed_illustrations', {
+        projectId,
+        maxRetries,
+        apiKey
+      });
+    },
+
     // 批次生成管理
     initializeBatchManager: async () => {
       return safeInvoke('initialize_batch_manager', {});
@@ -1172,7 +1285,10 @@ export const tauriAPI: API = {
       requests: BatchRequest[],
       priority?: string,
       maxParallel?: number,
-      apiKey?: string
+      apiKey?: string,
+      seedMode?: 'diverse' | 'clustered',
+      baseSeed?: number,
+      minSeedSpacing?: number
     ) => {
       return safeInvoke('submit_batch_illustration_request', {
         batchName: batchName,
@@ -1180,7 +1296,10 @@ export const tauriAPI: API = {
         requests: requests,
         priority: priority,
         maxParallel: maxParallel,
-        apiKey: apiKey
+        apiKey: apiKey,
+        seedMode: seedMode,
+        baseSeed: baseSeed,
+        minSeedSpacing: minSeedSpacing
       });
     },
 
@@ -1206,6 +1325,10 @@ export const tauriAPI: API = {
       return response.success ? response.illustrations : [];
     },
 
+    readIllustrationMetadata: async (path: string) => {
+      return safeInvoke('read_illustration_metadata', { path });
+    },
+
     getAllBatchesSummary: async () => {
       return safeInvoke('get_all_batches_summary', {});
     },
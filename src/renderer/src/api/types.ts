@@ -1,16 +1,37 @@
 // 統一的 API 接口定義
 import type {
   Project,
+  NovelLengthPreset,
   Chapter,
+  ChapterVersion,
+  ChapterNumberChange,
+  FindReplaceOptions,
+  FindReplaceResult,
+  ChapterSummaryResult,
+  ImportedChapter,
+  PlotAnalysisResult,
+  AnalysisTask,
+  CreativeSuggestionsResult,
+  SuggestionTargetType,
+  SuggestionType,
   Character,
   CreateRelationshipRequest,
+  RelationshipTypeInfo,
+  RelationshipGraph,
+  CharacterMentionSummary,
+  DuplicateCharacterPair,
+  MergeCharactersResult,
+  CharacterAnalysisResult,
   AIGenerationHistory,
   AIServiceStatus,
   AIModelInfo,
   AIModelAvailability,
   AIGenerationParams,
+  PurityRetryOptions,
+  SeparatedGenerationResult,
   OllamaConfig,
   ContextStats,
+  ProjectWritingStats,
   Settings,
   DatabaseStats,
   DatabaseHealth,
@@ -23,6 +44,7 @@ import type {
   UpdateAIProviderRequest,
   AIProviderResponse,
   AIProviderTestResult,
+  AIProviderHealth,
   AIGenerationResult,
   AIGenerationRequestData,
   EPubGenerationOptions,
@@ -30,7 +52,9 @@ import type {
   EPubExportRecord,
   PDFGenerationOptions,
   PDFResult,
-  PDFExportRecord
+  PDFExportRecord,
+  BundleFormat,
+  ProjectBundleResult
 } from './models';
 
 // 小說分析相關類型
@@ -44,6 +68,12 @@ import type { NovelTemplate } from '../types/template';
 import type { 
   BatchRequest,
   IllustrationHistoryItem,
+  IllustrationFileMetadata,
+  IllustrationCostReport,
+  IllustrationPromptPreview,
+  ImagenApiKeyCheck,
+  IllustrationStorageAudit,
+  IllustrationStorageCleanup,
   BatchListResponse,
   BatchStatusResponse,
   VisualTraitsApiResponse,
@@ -55,20 +85,51 @@ import type {
 export interface API {
   // 專案管理
   projects: {
-    getAll: () => Promise<Project[]>;
+    getAll: (includeArchived?: boolean) => Promise<Project[]>;
     create: (project: Omit<Project, 'id' | 'createdAt' | 'updatedAt'>) => Promise<string>;
     update: (project: Project) => Promise<void>;
     delete: (id: string) => Promise<void>;
+    archive: (id: string) => Promise<void>;
+    unarchive: (id: string) => Promise<void>;
     getById: (id: string) => Promise<Project>;
+    getWritingStats: (projectId: string) => Promise<ProjectWritingStats>;
+    getNovelLengthPresets: () => Promise<NovelLengthPreset[]>;
+    clearAnalysisCache: (projectId: string) => Promise<number>;
+    enqueueAnalysis: (
+      projectId: string,
+      analysisType: AnalysisTask['analysis_type'],
+      targetId: string,
+      priority?: number,
+      params?: AnalysisTask['params']
+    ) => Promise<AnalysisTask>;
+    getAnalysisQueue: (projectId: string) => Promise<AnalysisTask[]>;
   };
   
   // 章節管理
   chapters: {
     getByProjectId: (projectId: string) => Promise<Chapter[]>;
     create: (chapter: Omit<Chapter, 'id' | 'createdAt' | 'updatedAt'>) => Promise<string>;
-    update: (chapter: Chapter) => Promise<void>;
+    update: (chapter: Chapter, expectedUpdatedAt?: string) => Promise<string>;
     delete: (id: string) => Promise<void>;
     getById: (id: string) => Promise<Chapter>;
+    reorder: (projectId: string, orderedIds: string[]) => Promise<void>;
+    normalizeNumbers: (projectId: string) => Promise<ChapterNumberChange[]>;
+    listVersions: (chapterId: string) => Promise<ChapterVersion[]>;
+    restoreVersion: (versionId: string) => Promise<void>;
+    findAndReplace: (projectId: string, find: string, replace: string, options?: FindReplaceOptions) => Promise<FindReplaceResult>;
+    summarize: (chapterId: string, providerId: string) => Promise<ChapterSummaryResult>;
+    analyzePlot: (chapterId: string, providerId?: string) => Promise<PlotAnalysisResult>;
+    generateSuggestions: (
+      chapterId: string,
+      targetType: SuggestionTargetType,
+      suggestionType: SuggestionType,
+      providerIds: string[],
+      targetId?: string
+    ) => Promise<CreativeSuggestionsResult>;
+    acceptSuggestion: (id: string, userRating?: number) => Promise<void>;
+    rejectSuggestion: (id: string, feedback?: string, userRating?: number) => Promise<void>;
+    importFromHtml: (projectId: string, html: string) => Promise<ImportedChapter[]>;
+    importDocument: (projectId: string, path: string) => Promise<ImportedChapter[]>;
   };
 
   // 角色管理
@@ -78,9 +139,16 @@ export interface API {
     update: (character: Character) => Promise<void>;
     delete: (id: string) => Promise<void>;
     getById: (id: string) => Promise<Character>;
+    validateAttributes: (attributes: string) => Promise<void>;
     createRelationship: (request: CreateRelationshipRequest) => Promise<string>;
     deleteRelationship: (id: string) => Promise<void>;
     clearRelationships: (characterId: string) => Promise<void>;
+    getRelationshipTypes: () => Promise<RelationshipTypeInfo[]>;
+    getRelationshipGraph: (projectId: string) => Promise<RelationshipGraph>;
+    scanMentions: (projectId: string) => Promise<CharacterMentionSummary[]>;
+    findDuplicates: (projectId: string) => Promise<DuplicateCharacterPair[]>;
+    merge: (keepId: string, mergeId: string) => Promise<MergeCharactersResult>;
+    analyze: (characterId: string, chapterId: string, providerId?: string) => Promise<CharacterAnalysisResult>;
   };
 
   // AI 功能 (傳統 Ollama)
@@ -92,6 +160,7 @@ export interface API {
     checkModelAvailability: (modelName: string) => Promise<AIModelAvailability>;
     generateText: (prompt: string, model: string, params: AIGenerationParams) => Promise<string>;
     generateWithContext: (projectId: string, chapterId: string, position: number, model: string, params: AIGenerationParams, language?: string) => Promise<string>;
+    generateWithSeparatedContext: (projectId: string, chapterId: string, position: number, model: string, params: AIGenerationParams, purityRetry?: PurityRetryOptions) => Promise<SeparatedGenerationResult>;
     updateOllamaConfig: (config: OllamaConfig) => Promise<OllamaConfig>;
   };
 
@@ -102,6 +171,7 @@ export interface API {
     update: (request: UpdateAIProviderRequest) => Promise<AIProviderResponse>;
     delete: (id: string) => Promise<AIProviderResponse>;
     test: (id: string) => Promise<AIProviderTestResult>;
+    testAll: (timeoutSecs?: number) => Promise<AIProviderHealth[]>;
     generateText: (request: AIGenerationRequestData) => Promise<AIGenerationResult>;
     getSupportedTypes: () => Promise<string[]>;
     getAvailableModels: (providerId: string) => Promise<AIProviderTestResult>;
@@ -110,8 +180,10 @@ export interface API {
   // 上下文管理
   context: {
     buildContext: (projectId: string, chapterId: string, position: number) => Promise<string>;
+    buildAppendContext: (projectId: string, chapterId: string) => Promise<string>;
     compressContext: (context: string, maxTokens: number) => Promise<string>;
-    getContextStats: (projectId: string) => Promise<ContextStats>;
+    getContextStats: (projectId: string, accurate?: boolean) => Promise<ContextStats>;
+    getDefaultSystemPrompt: (projectType?: string) => Promise<string>;
     optimizeUltraLongContext: (params: UltraLongContextOptimizationParams) => Promise<OptimizedContextResult>;
   };
 
@@ -126,7 +198,7 @@ export interface API {
   // 資料庫維護
   database: {
     backup: (path: string) => Promise<void>;
-    restore: (path: string) => Promise<void>;
+    restore: (path: string, confirmationToken?: string) => Promise<void>;
     runMaintenance: () => Promise<string>;
     getStats: () => Promise<DatabaseStats>;
     healthCheck: () => Promise<DatabaseHealth>;
@@ -134,6 +206,7 @@ export interface API {
     incrementalVacuum: (pages?: number) => Promise<string>;
     getWalModeStatus: () => Promise<{ journal_mode: string; is_wal_mode: boolean; synchronous: number; wal_autocheckpoint: number; wal_info: Record<string, unknown>; benefits: Record<string, string[]>; recommendations: string }>;
     setWalMode: (enable: boolean) => Promise<string>;
+    checkpointWal: (mode: 'PASSIVE' | 'FULL' | 'TRUNCATE') => Promise<{ mode: string; busy: boolean; log_frames: number; checkpointed_frames: number }>;
   };
 
   // 系統功能
@@ -145,6 +218,7 @@ export interface API {
     selectDirectory: (title?: string) => Promise<string>;
     quitApp: () => Promise<void>;
     reloadApp: () => Promise<void>;
+    collectDiagnostics: () => Promise<string>;
   };
 
   // 更新管理
@@ -152,6 +226,8 @@ export interface API {
     checkForUpdates: () => Promise<UpdateInfo>;
     downloadUpdate: () => Promise<void>;
     installUpdate: () => Promise<void>;
+    confirmUpdateLaunch: () => Promise<void>;
+    rollbackUpdate: () => Promise<void>;
     setAutoUpdate: (enabled: boolean) => Promise<void>;
   };
 
@@ -161,7 +237,7 @@ export interface API {
     query: (params: AIHistoryQueryParams) => Promise<AIGenerationHistory[]>;
     markSelected: (historyId: string, projectId: string) => Promise<void>;
     delete: (historyId: string) => Promise<void>;
-    cleanup: (projectId: string, keepCount: number) => Promise<number>;
+    cleanup: (projectId: string, keepCount: number, keepPerChapter?: number) => Promise<number>;
   };
 
   // 小說分析功能
@@ -177,6 +253,7 @@ export interface API {
     generate: (projectId: string, options?: EPubGenerationOptions) => Promise<EPubResult>;
     getExports: (projectId: string) => Promise<EPubExportRecord[]>;
     deleteExport: (exportId: string) => Promise<void>;
+    import: (path: string) => Promise<string>;
   };
 
   // PDF 文檔生成
@@ -186,6 +263,11 @@ export interface API {
     deleteExport: (exportId: string) => Promise<void>;
   };
 
+  // 作品包導出（EPUB + PDF + 插畫）
+  bundle: {
+    exportProject: (projectId: string, formats: BundleFormat[], outputPath: string) => Promise<ProjectBundleResult>;
+  };
+
   // AI 插畫生成
   illustration: {
     // 角色一致性管理
@@ -202,13 +284,31 @@ export interface API {
       aspectRatio: string, safetyLevel: string, customNegativePrompt?: string,
       apiKey?: string
     ) => Promise<IllustrationGenerationResponse>;
+    previewIllustrationPrompt: (
+      projectId: string, characterId: string | null, sceneDescription: string,
+      templateId?: string, translationStyle?: string, optimizationLevel?: string,
+      aspectRatio?: string, safetyLevel?: string, customNegativePrompt?: string
+    ) => Promise<IllustrationPromptPreview>;
     getIllustrationHistory: (projectId: string, characterId?: string, limit?: number, offset?: number) => Promise<IllustrationHistoryItem[]>;
+    readIllustrationMetadata: (path: string) => Promise<IllustrationFileMetadata | null>;
     cancelGeneration: (taskId: string) => Promise<void>;
     validateImagenConnection: (apiKey: string) => Promise<TranslationValidationResponse>;
+    testImagenApiKey: (apiKey: string) => Promise<ImagenApiKeyCheck>;
+    auditStorage: (projectId?: string) => Promise<IllustrationStorageAudit>;
+    cleanOrphanedIllustrations: (dryRun: boolean, clearDanglingPaths?: boolean) => Promise<IllustrationStorageCleanup>;
+    getCostReport: (projectId: string, from?: string, to?: string) => Promise<IllustrationCostReport>;
+    retryFailedIllustrations: (projectId: string, apiKey: string, maxRetries?: number) => Promise<{
+      success: boolean;
+      retried: number;
+      succeeded: number;
+      failed: number;
+      skipped_for_quota: number;
+      results: Array<{ id: string; status: 'completed' | 'failed'; image_url?: string; error?: string }>;
+    }>;
 
     // 批次管理
     initializeBatchManager: () => Promise<{ success: boolean; message?: string }>;
-    submitBatchRequest: (name: string, projectId: string, requests: BatchRequest[], priority: string, maxParallel: number, apiKey: string) => Promise<{ batchId: string }>;
+    submitBatchRequest: (name: string, projectId: string, requests: BatchRequest[], priority: string, maxParallel: number, apiKey: string, seedMode?: 'diverse' | 'clustered', baseSeed?: number, minSeedSpacing?: number) => Promise<{ batchId: string }>;
     getBatchStatus: (batchId: string) => Promise<BatchStatusResponse>;
     cancelBatch: (batchId: string) => Promise<{ success: boolean; message?: string }>;
     getAllBatchesSummary: () => Promise<BatchListResponse>;
@@ -366,8 +466,14 @@ export interface UltraLongContextOptimizationParams {
   maxTokens: number;
   focusCharacters: string[];
   currentPosition: number;
+  /** 目標 token 預算，未提供時使用 maxTokens */
+  targetTokens?: number;
+  /** 超出預算時的處理策略，預設 Hybrid */
+  strategy?: ContextOptimizationStrategy;
 }
 
+export type ContextOptimizationStrategy = 'Truncate' | 'Summarize' | 'Hybrid';
+
 export interface OptimizedContextResult {
   content: string;
   originalTokenCount: number;
@@ -378,6 +484,24 @@ export interface OptimizedContextResult {
   preservedElements: string[];
   lostElements: string[];
   optimizationStats: OptimizationStats;
+  report: OptimizationReport;
+}
+
+export interface OptimizationReport {
+  strategy: ContextOptimizationStrategy;
+  targetTokens: number;
+  estimatedTokens: number;
+  keptBlocks: number;
+  summarizedBlocks: BlockChange[];
+  droppedBlocks: BlockChange[];
+}
+
+export interface BlockChange {
+  blockId: string;
+  temporalPosition: number;
+  originalTokens: number;
+  finalTokens: number;
+  preview: string;
 }
 
 export interface OptimizationStats {
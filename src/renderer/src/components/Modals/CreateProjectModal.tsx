@@ -45,7 +45,7 @@ const projectTypes: ProjectType[] = [
 ];
 
 interface NovelLength {
-  id: 'short' | 'medium' | 'long';
+  id: 'short' | 'medium' | 'long' | 'epic';
   name: string;
   icon: string;
   description: string;
@@ -75,8 +75,16 @@ const novelLengths: NovelLength[] = [
     name: '長篇',
     icon: '📚',
     description: '宏大史詩，構建完整的世界觀',
-    estimatedChapters: '50+ 章',
-    wordCount: '20+ 萬字',
+    estimatedChapters: '50-150 章',
+    wordCount: '20-100 萬字',
+  },
+  {
+    id: 'epic',
+    name: '史詩',
+    icon: '🏛️',
+    description: '多卷連載規模，適合長期經營的大型作品',
+    estimatedChapters: '150+ 章',
+    wordCount: '100+ 萬字',
   },
 ];
 
@@ -415,7 +423,7 @@ const CreateProjectModal: React.FC = () => {
 
               <div className="mb-6">
                 <label className="block text-gray-300 mb-2">選擇小說篇幅</label>
-                <div className="grid grid-cols-1 md:grid-cols-3 gap-4">
+                <div className="grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-4">
                   {novelLengths.map((length) => (
                     <div
                       key={length.id}
@@ -92,6 +92,7 @@ const SelectProjectForPlotAnalysisModal: React.FC = () => {
                         {project?.novelLength === 'short' && '短篇'}
                         {project?.novelLength === 'medium' && '中篇'}
                         {project?.novelLength === 'long' && '長篇'}
+                        {project?.novelLength === 'epic' && '史詩'}
                         {!project?.novelLength && '中篇'}
                       </span>
                     </div>
@@ -1,6 +1,6 @@
 import React, { useCallback } from 'react';
 import { useAppSelector, useAppDispatch } from '../../hooks/redux';
-import { updateChapter, Chapter } from '../../store/slices/chaptersSlice';
+import { reorderChapters, Chapter } from '../../store/slices/chaptersSlice';
 import CreateProjectModal from '../Modals/CreateProjectModal';
 import ProjectManageModal from '../Modals/ProjectManageModal';
 import ImportProjectModal from '../Modals/ImportProjectModal';
@@ -28,13 +28,13 @@ const ModalContainer: React.FC = () => {
   // 章節重新排序處理函數（使用 useCallback 避免無限重新渲染）
   const handleReorderChapters = useCallback(async (reorderedChapters: Chapter[]) => {
     try {
-      // 更新所有章節的順序
-      for (const chapter of reorderedChapters) {
-        await dispatch(updateChapter(chapter)).unwrap();
-      }
-      
-      // 移除重新獲取章節列表的調用，避免無限循環
-      // Redux state 已經通過 updateChapter 更新了
+      if (reorderedChapters.length === 0) return;
+
+      // 一次性更新所有章節的順序（後端以單一交易重新編號，避免部分成功）
+      await dispatch(reorderChapters({
+        projectId: reorderedChapters[0].projectId,
+        orderedIds: reorderedChapters.map(chapter => chapter.id)
+      })).unwrap();
       
     } catch (error) {
       console.error('重新排序章節失敗:', error);
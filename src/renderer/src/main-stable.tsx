@@ -6,6 +6,7 @@ import { store } from './store/store';
 import { useAppDispatch } from './hooks/redux';
 import { checkOllamaService, fetchModelsInfo, fetchAIProviders, setActiveProvider } from './store/slices/aiSlice';
 import { fetchProjects } from './store/slices/projectsSlice';
+import { api } from './api';
 import Layout from './components/Layout/Layout';
 import Dashboard from './pages/Dashboard/Dashboard';
 import CharacterManager from './pages/CharacterManager/CharacterManager';
@@ -119,6 +120,11 @@ const SimpleApp: React.FC = () => {
         
         setIsLoading(false);
         
+        // 介面已成功載入，確認更新後的版本可正常啟動（避免被自動回滾）
+        api.updates.confirmUpdateLaunch().catch((error) => {
+          console.warn('⚠️  更新啟動確認失敗:', error);
+        });
+        
         // 背景載入資料（不阻塞 UI）
         setTimeout(async () => {
           try {
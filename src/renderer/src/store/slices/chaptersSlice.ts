@@ -93,6 +93,15 @@ export const updateChapter = createAsyncThunk(
   }
 );
 
+export const reorderChapters = createAsyncThunk(
+  'chapters/reorder',
+  async ({ projectId, orderedIds }: { projectId: string; orderedIds: string[] }) => {
+    // 後端以單一交易重新編號 order 與 chapterNumber
+    await api.chapters.reorder(projectId, orderedIds);
+    return orderedIds;
+  }
+);
+
 export const deleteChapter = createAsyncThunk(
   'chapters/delete',
   async (chapterId: string) => {
@@ -244,6 +253,27 @@ const chaptersSlice = createSlice({
         state.error = action.error.message || '儲存章節失敗';
       })
       
+      // reorderChapters
+      .addCase(reorderChapters.fulfilled, (state, action) => {
+        const positions = new Map(action.payload.map((id, index) => [id, index]));
+        state.chapters.forEach(chapter => {
+          const index = positions.get(chapter.id);
+          if (index !== undefined) {
+            chapter.order = index;
+            chapter.chapterNumber = index + 1;
+          }
+        });
+        state.chapters.sort((a, b) => a.order - b.order);
+        if (state.currentChapter && positions.has(state.currentChapter.id)) {
+          const index = positions.get(state.currentChapter.id)!;
+          state.currentChapter.order = index;
+          state.currentChapter.chapterNumber = index + 1;
+        }
+      })
+      .addCase(reorderChapters.rejected, (state, action) => {
+        state.error = action.error.message || '章節排序失敗';
+      })
+      
       // deleteChapter
       .addCase(deleteChapter.fulfilled, (state, action) => {
         state.chapters = state.chapters.filter(c => c.id !== action.payload);
@@ -329,6 +329,87 @@ export interface IllustrationFilter {
   sort_order: 'asc' | 'desc';
 }
 
+/** 單一模型的費用統計 */
+export interface ModelCostBreakdown {
+  model: string;
+  generations: number;
+  completed: number;
+  failed: number;
+  total_cost: number;
+  average_cost_per_image: number;
+}
+
+/** 付費插畫費用報表 */
+export interface IllustrationCostReport {
+  project_id: string;
+  from?: string;
+  to?: string;
+  total_generations: number;
+  completed: number;
+  failed: number;
+  total_cost: number;
+  average_cost_per_image: number;
+  by_model: ModelCostBreakdown[];
+  lifetime_generations: number;
+  lifetime_cost: number;
+  last_generation_at?: string;
+}
+
+/** Imagen API 金鑰檢查結果（不產生圖像） */
+export interface ImagenApiKeyCheck {
+  valid: boolean;
+  error?: string;
+  quota_info?: {
+    status: 'ok' | 'quota_exceeded' | 'billing_disabled';
+    message?: string;
+  };
+}
+
+/** 插畫儲存稽核報告 */
+export interface IllustrationStorageAudit {
+  storage_dir: string;
+  orphaned_files: Array<{ path: string; size: number }>;
+  orphaned_bytes: number;
+  dangling_records: Array<{
+    id: string;
+    table_name: 'pollinations_generations' | 'illustration_generations';
+    project_id?: string;
+    file_path: string;
+  }>;
+}
+
+/** 孤立插畫清理結果 */
+export interface IllustrationStorageCleanup {
+  dry_run: boolean;
+  removed_files: string[];
+  reclaimed_bytes: number;
+  cleared_records: number;
+  failed: string[];
+}
+
+/** 提示詞預覽中的處理步驟 */
+export interface PromptTransformation {
+  stage: 'translation' | 'template' | 'style' | 'optimization' | 'consistency' | 'negative_prompt';
+  description: string;
+  output?: string;
+}
+
+/** 增強插畫生成實際會送出的提示詞預覽 */
+export interface IllustrationPromptPreview {
+  positive_prompt: string;
+  negative_prompt?: string;
+  seed?: number;
+  transformations: PromptTransformation[];
+}
+
+/** 插畫檔案內嵌的生成參數 */
+export interface IllustrationFileMetadata {
+  prompt: string;
+  seed?: number;
+  model: string;
+  app_version: string;
+}
+
 /** 插畫歷史項目 */
 export interface IllustrationHistoryItem {
   id: string;
@@ -344,6 +425,7 @@ export interface IllustrationHistoryItem {
   style_applied?: string;
   image_url?: string;
   local_file_path?: string;
+  thumbnail_path?: string;
   file_size_bytes?: number;
   generation_time_ms?: number;
   status: 'completed' | 'failed' | 'pending' | 'processing';